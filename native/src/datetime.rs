@@ -0,0 +1,125 @@
+//! Date and time parsing shared by profiling, bucketing and date formulas
+//!
+//! Accepts the layouts `DateTime.TryParse` understands with the invariant
+//! culture (ISO 8601, US month/day/year, month names) and converts to and
+//! from spreadsheet serial numbers (days since 1899-12-30).
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m/%d/%Y %I:%M %p",
+];
+
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%m/%d/%Y",
+    "%m-%d-%Y",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%B %d %Y",
+    "%b %d %Y",
+];
+
+/// Parse a date or date-time; dates without a time are at midnight
+pub fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    parse_with_format(text).map(|(dt, _)| dt)
+}
+
+/// Parse like [`parse_datetime`], also returning the `chrono` format that
+/// matched so generated values can be written back the same way
+pub fn parse_with_format(text: &str) -> Option<(NaiveDateTime, &'static str)> {
+    let text = text.trim();
+    if text.is_empty() || !text.bytes().any(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some((dt.naive_local(), "%Y-%m-%dT%H:%M:%S"));
+    }
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|f| Some((NaiveDateTime::parse_from_str(text, f).ok()?, *f)))
+        .or_else(|| {
+            DATE_FORMATS.iter().find_map(|f| {
+                let date = NaiveDate::parse_from_str(text, f).ok()?;
+                Some((date.and_time(NaiveTime::MIN), *f))
+            })
+        })
+}
+
+fn epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap_or_default()
+        .and_time(NaiveTime::MIN)
+}
+
+/// Spreadsheet serial number: whole days since 1899-12-30 plus the day fraction
+pub fn to_serial(dt: NaiveDateTime) -> f64 {
+    let elapsed = dt - epoch();
+    elapsed.num_milliseconds() as f64 / 86_400_000.0
+}
+
+/// Inverse of [`to_serial`], rounded to the millisecond
+pub fn from_serial(serial: f64) -> Option<NaiveDateTime> {
+    if !serial.is_finite() || !(-693_593.0..2_958_466.0).contains(&serial) {
+        return None;
+    }
+    epoch().checked_add_signed(Duration::milliseconds(
+        (serial * 86_400_000.0).round() as i64
+    ))
+}
+
+/// ISO text: `2024-03-01`, or `2024-03-01 14:30:00` when there is a time part
+pub fn format_datetime(dt: NaiveDateTime) -> String {
+    if dt.time() == NaiveTime::MIN {
+        dt.format("%Y-%m-%d").to_string()
+    } else if dt.nanosecond() == 0 {
+        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+    } else {
+        dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_datetime() {
+        let date = |y, m, d| {
+            NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_time(NaiveTime::MIN)
+        };
+
+        assert_eq!(parse_datetime("2024-03-01"), Some(date(2024, 3, 1)));
+        assert_eq!(parse_datetime("03/01/2024"), Some(date(2024, 3, 1)));
+        assert_eq!(parse_datetime("1 March 2024"), Some(date(2024, 3, 1)));
+        assert_eq!(
+            format_datetime(parse_datetime("2024-03-01T14:30:00Z").unwrap()),
+            "2024-03-01 14:30:00"
+        );
+        assert_eq!(parse_datetime("2024"), None);
+        assert_eq!(parse_datetime("hello"), None);
+    }
+
+    #[test]
+    fn test_serial_round_trip() {
+        let dt = parse_datetime("2024-01-01 12:00").unwrap();
+        assert_eq!(to_serial(dt), 45292.5);
+        assert_eq!(from_serial(45292.5), Some(dt));
+        assert_eq!(from_serial(f64::NAN), None);
+    }
+}
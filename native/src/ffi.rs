@@ -0,0 +1,108 @@
+//! Helpers shared by the `extern "C"` entry points for moving strings
+//! and arrays across the FFI boundary
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+/// Borrow a C string argument as UTF-8
+///
+/// Returns `None` for null pointers or invalid encoding.
+///
+/// # Safety
+/// `ptr` must be null or a valid NUL-terminated C string that outlives `'a`
+pub(crate) unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+/// Copy an array of C strings into owned Rust strings
+///
+/// Null entries and invalid encodings become empty strings, matching how the
+/// aggregate functions skip them.
+///
+/// # Safety
+/// `ptr` must be null or point to `count` valid C string pointers
+pub(crate) unsafe fn str_array_arg(ptr: *const *const c_char, count: usize) -> Option<Vec<String>> {
+    if ptr.is_null() {
+        return if count == 0 { Some(Vec::new()) } else { None };
+    }
+
+    let values = std::slice::from_raw_parts(ptr, count);
+    Some(
+        values
+            .iter()
+            .map(|&value| str_arg(value).unwrap_or_default().to_string())
+            .collect(),
+    )
+}
+
+/// Hand a string to the caller (free with `tessera_free_string`)
+///
+/// Interior NUL bytes cannot be represented in a C string and are dropped.
+pub(crate) fn to_c_string(value: &str) -> *mut c_char {
+    match CString::new(value) {
+        Ok(c_str) => c_str.into_raw(),
+        Err(_) => CString::new(value.replace('\0', ""))
+            .unwrap_or_default()
+            .into_raw(),
+    }
+}
+
+/// Errors common enough to be handed out without allocating
+///
+/// `error_string` returns pointers into this table for these messages and
+/// `tessera_free_string` recognizes and ignores them, so callers free every
+/// error the same way.
+static STATIC_ERRORS: [&CStr; 15] = [
+    c"Null pointer provided",
+    c"Invalid file path",
+    c"Invalid column name encoding",
+    c"Invalid sheet name encoding",
+    c"Invalid cell value encoding",
+    c"Invalid formula encoding",
+    c"Invalid text encoding",
+    c"Invalid pattern encoding",
+    c"Invalid name encoding",
+    c"Invalid JSON encoding",
+    c"No numeric values found in column",
+    c"Null formula string",
+    c"Formula must start with '='",
+    c"Formula missing closing parenthesis",
+    c"Invalid formula syntax: expected function(arg)",
+];
+
+/// True for pointers returned from the static error table
+pub(crate) fn is_static_error(ptr: *const c_char) -> bool {
+    STATIC_ERRORS.iter().any(|msg| msg.as_ptr() == ptr)
+}
+
+/// Error message for the caller (free with `tessera_free_string`)
+///
+/// Common messages come from a static table; others are allocated, with
+/// interior NUL bytes dropped rather than panicking.
+pub(crate) fn error_string(msg: &str) -> *mut c_char {
+    match STATIC_ERRORS
+        .iter()
+        .find(|error| error.to_bytes() == msg.as_bytes())
+    {
+        // Never written through: tessera_free_string skips these
+        Some(error) => error.as_ptr().cast_mut(),
+        None => to_c_string(msg),
+    }
+}
+
+/// Borrow an array argument; a zero count accepts a null pointer
+///
+/// # Safety
+/// `ptr` must be null or point to `count` valid elements that outlive `'a`
+pub(crate) unsafe fn slice_arg<'a, T>(ptr: *const T, count: usize) -> Option<&'a [T]> {
+    if count == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(ptr, count))
+    }
+}
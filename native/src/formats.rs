@@ -0,0 +1,233 @@
+//! Recognising common text formats: email addresses, URLs, IP addresses and
+//! JSON documents
+//!
+//! The checks are structural rather than exhaustive: an address that passes
+//! is well-formed, not necessarily deliverable or reachable. Shared by the
+//! `ISEMAIL`-style formula functions and the `format` validation rule.
+
+use std::net::{IpAddr, Ipv6Addr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    /// `name@example.com`: a dot-separated local part and a domain with a TLD
+    Email,
+    /// `scheme://host[:port][/path][?query][#fragment]`
+    Url,
+    /// IPv4 dotted quad or IPv6 address
+    IpAddress,
+    /// JSON object or array
+    Json,
+}
+
+impl TextFormat {
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(TextFormat::Email),
+            1 => Some(TextFormat::Url),
+            2 => Some(TextFormat::IpAddress),
+            3 => Some(TextFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Name used when saving rules
+    pub fn name(self) -> &'static str {
+        match self {
+            TextFormat::Email => "email",
+            TextFormat::Url => "url",
+            TextFormat::IpAddress => "ip",
+            TextFormat::Json => "json",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            TextFormat::Email,
+            TextFormat::Url,
+            TextFormat::IpAddress,
+            TextFormat::Json,
+        ]
+        .into_iter()
+        .find(|format| format.name() == name)
+    }
+
+    /// Description for messages, e.g. "an email address"
+    pub fn description(self) -> &'static str {
+        match self {
+            TextFormat::Email => "an email address",
+            TextFormat::Url => "a URL",
+            TextFormat::IpAddress => "an IP address",
+            TextFormat::Json => "a JSON object or array",
+        }
+    }
+
+    /// Whether `text`, surrounding whitespace ignored, is in this format
+    pub fn matches(self, text: &str) -> bool {
+        let text = text.trim();
+        match self {
+            TextFormat::Email => is_email(text),
+            TextFormat::Url => is_url(text),
+            TextFormat::IpAddress => text.parse::<IpAddr>().is_ok(),
+            TextFormat::Json => matches!(
+                serde_json::from_str(text),
+                Ok(serde_json::Value::Object(_) | serde_json::Value::Array(_))
+            ),
+        }
+    }
+}
+
+/// Host name such as `example.com` or `localhost`: labels of letters, digits
+/// and inner hyphens, at most 63 characters each and 253 in all
+pub(crate) fn is_hostname(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+fn is_email(text: &str) -> bool {
+    let Some((local, domain)) = text.rsplit_once('@') else {
+        return false;
+    };
+    let local_ok = (1..=64).contains(&local.len())
+        && local.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c))
+        });
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    local_ok
+        && is_hostname(domain)
+        && domain.contains('.')
+        && tld.chars().count() >= 2
+        && tld.chars().all(char::is_alphabetic)
+}
+
+/// A URL split into its parts, which are not checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UrlParts<'a> {
+    /// `https` in `https://...`; `None` for text such as `example.com/page`
+    pub scheme: Option<&'a str>,
+    /// Host name or IP address, without the brackets around IPv6 addresses
+    pub host: &'a str,
+    pub port: Option<&'a str>,
+    /// Path from its leading `/`, empty when there is none
+    pub path: &'a str,
+    /// Text after `?`, up to any fragment
+    pub query: Option<&'a str>,
+    /// Text after `#`
+    pub fragment: Option<&'a str>,
+}
+
+/// Split `[scheme://][user@]host[:port][/path][?query][#fragment]`, `None`
+/// when the scheme or a bracketed host is malformed
+pub(crate) fn split_url(text: &str) -> Option<UrlParts<'_>> {
+    // A scheme comes before any path, query or fragment, which may hold URLs
+    // of their own (`example.com/?next=http://x`)
+    let authority_end = text.find(['/', '?', '#']).unwrap_or(text.len());
+    let scheme_split = text
+        .find("://")
+        .filter(|&i| i <= authority_end)
+        .map(|i| (&text[..i], &text[i + 3..]));
+    let (scheme, rest) = match scheme_split {
+        Some((scheme, rest)) => {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if !valid {
+                return None;
+            }
+            (Some(scheme), rest)
+        }
+        None => (None, text),
+    };
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, rest)| rest);
+    let (host, port) = match host_port.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed.split_once(']')?;
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':')?)),
+            }
+        }
+        None => match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        },
+    };
+    Some(UrlParts {
+        scheme,
+        host,
+        port,
+        path,
+        query,
+        fragment,
+    })
+}
+
+/// Absolute URL with a valid host, port and no whitespace
+fn is_url(text: &str) -> bool {
+    let Some(url) = split_url(text) else {
+        return false;
+    };
+    url.scheme.is_some()
+        && (is_hostname(url.host) || url.host.parse::<Ipv6Addr>().is_ok())
+        && url.port.is_none_or(|port| {
+            !port.is_empty()
+                && port.chars().all(|c| c.is_ascii_digit())
+                && port.parse::<u16>().is_ok()
+        })
+        && !text.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_formats() {
+        let email = |s| TextFormat::Email.matches(s);
+        assert!(email("ann.lee+news@mail.example.com"));
+        assert!(!email("ann..lee@example.com"));
+        assert!(!email("ann@localhost"));
+        assert!(!email("ann@example.c0m"));
+        assert!(!email("@example.com"));
+
+        let url = |s| TextFormat::Url.matches(s);
+        assert!(url("https://example.com/a/b?q=1#top"));
+        assert!(url("http://user:pw@127.0.0.1:8080"));
+        assert!(url("ftp://[::1]:21/files"));
+        assert!(!url("example.com/path"));
+        assert!(!url("http://exa mple.com"));
+        assert!(!url("http://example.com:99999"));
+        assert!(!url("http://-bad-.com"));
+
+        let ip = |s| TextFormat::IpAddress.matches(s);
+        assert!(ip("192.168.0.1") && ip("2001:db8::1"));
+        assert!(!ip("256.1.1.1") && !ip("10.0.0"));
+
+        let json = |s| TextFormat::Json.matches(s);
+        assert!(json(r#"{"a": [1, 2]}"#) && json("[]"));
+        assert!(!json("42") && !json("{a: 1}"));
+
+        assert_eq!(TextFormat::from_name("ip"), Some(TextFormat::IpAddress));
+    }
+}
@@ -0,0 +1,179 @@
+//! Parenthesis matching for the formula editor
+//!
+//! Pairs every `(` with its `)`, lists the ones left unbalanced and works out
+//! the text that would close what is still open at the end of the formula
+//! (a string, a bracketed column name, a quoted sheet name, then the open
+//! parentheses innermost first). Built on the lenient tokenizer, so
+//! parentheses inside strings and column names are not counted.
+
+use std::ops::Range;
+use std::os::raw::c_char;
+
+use super::lexer::{tokenize_lenient, TokenKind};
+use crate::ffi::str_arg;
+use crate::StringResult;
+
+/// Byte offsets of a matched `(` and `)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pair {
+    pub open: usize,
+    pub close: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Brackets {
+    /// Matched pairs, by position of the `(`
+    pub pairs: Vec<Pair>,
+    /// Byte offsets of `(` with no `)`
+    pub unclosed: Vec<usize>,
+    /// Byte offsets of `)` with no `(`
+    pub unopened: Vec<usize>,
+    /// Text that, appended to the formula, closes everything left open
+    pub auto_close: String,
+}
+
+impl Brackets {
+    /// Offset of the parenthesis matching the one at the cursor: the character
+    /// right after the cursor, else the one right before it
+    pub fn partner(&self, src: &str, cursor: usize) -> Option<usize> {
+        let find = |at: usize| {
+            self.pairs.iter().find_map(|pair| {
+                if at == pair.open {
+                    Some(pair.close)
+                } else {
+                    (at == pair.close).then_some(pair.open)
+                }
+            })
+        };
+        let before = src[..cursor].char_indices().next_back().map(|(i, _)| i);
+        find(cursor).or_else(|| before.and_then(find))
+    }
+}
+
+/// Closing text for an unterminated string, column or sheet token
+fn unterminated(text: &str) -> Option<&'static str> {
+    let (first, rest) = text.split_at(text.chars().next()?.len_utf8());
+    match first {
+        "\"" => Some("\""),
+        "[" => Some("]"),
+        // An odd number of quotes after the first means the name was closed
+        // and only the `!` is missing
+        "'" if rest.matches('\'').count() % 2 == 0 => Some("'!"),
+        _ => None,
+    }
+}
+
+/// Parenthesis structure of `src`
+pub fn match_brackets(src: &str) -> Brackets {
+    let mut brackets = Brackets::default();
+    let mut open: Vec<usize> = Vec::new();
+    let mut tail = "";
+    for token in tokenize_lenient(src) {
+        let Range { start, end } = token.span;
+        match token.kind {
+            TokenKind::LParen => open.push(start),
+            TokenKind::RParen => match open.pop() {
+                Some(open) => brackets.pairs.push(Pair { open, close: start }),
+                None => brackets.unopened.push(start),
+            },
+            TokenKind::Invalid if end == src.len() => {
+                tail = unterminated(&src[start..end]).unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+    brackets.pairs.sort_by_key(|pair| pair.open);
+    brackets.auto_close = tail.to_string() + &")".repeat(open.len());
+    brackets.unclosed = open;
+    brackets
+}
+
+/// Matching parentheses of a formula being edited
+///
+/// # Arguments
+/// * `formula` - Formula text being edited
+/// * `cursor` - Cursor position as a byte offset into `formula`
+///
+/// # Returns
+/// StringResult with JSON `{pairs: [{open, close, char_open, char_close}],
+/// unclosed: [..], unopened: [..], match: {at, char_at} | null, auto_close}`;
+/// offsets are bytes, `char_*` characters. `match` is the partner of the
+/// parenthesis at the cursor (or just before it); `auto_close` is the text
+/// that completes the formula.
+///
+/// # Safety
+/// `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_match_brackets(
+    formula: *const c_char,
+    cursor: usize,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+    if !formula.is_char_boundary(cursor) {
+        return StringResult::error(&format!("Cursor {} is out of range", cursor));
+    }
+
+    let chars = |byte: usize| formula[..byte].chars().count();
+    let brackets = match_brackets(formula);
+    let pairs: Vec<serde_json::Value> = brackets
+        .pairs
+        .iter()
+        .map(|pair| {
+            serde_json::json!({
+                "open": pair.open,
+                "close": pair.close,
+                "char_open": chars(pair.open),
+                "char_close": chars(pair.close),
+            })
+        })
+        .collect();
+    let partner = brackets
+        .partner(formula, cursor)
+        .map(|at| serde_json::json!({ "at": at, "char_at": chars(at) }));
+    StringResult::success(
+        &serde_json::json!({
+            "pairs": pairs,
+            "unclosed": brackets.unclosed,
+            "unopened": brackets.unopened,
+            "match": partner,
+            "auto_close": brackets.auto_close,
+        })
+        .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_brackets() {
+        let src = "=IF((A1 > 1), LEN(\")\"), 0)";
+        let brackets = match_brackets(src);
+        assert_eq!(
+            brackets.pairs,
+            [
+                Pair { open: 3, close: 25 },
+                Pair { open: 4, close: 11 },
+                Pair {
+                    open: 17,
+                    close: 21
+                },
+            ]
+        );
+        assert!(brackets.unclosed.is_empty() && brackets.unopened.is_empty());
+        assert_eq!(brackets.auto_close, "");
+        assert_eq!(brackets.partner(src, 3), Some(25));
+        assert_eq!(brackets.partner(src, 26), Some(3));
+        assert_eq!(brackets.partner(src, 8), None);
+
+        let open = match_brackets("=1) + SUM(LEFT(\"a(b");
+        assert_eq!(open.unopened, [2]);
+        assert_eq!(open.unclosed, [9, 14]);
+        assert_eq!(open.auto_close, "\"))");
+        assert_eq!(match_brackets("=([Net").auto_close, "])");
+        assert_eq!(match_brackets("='Q1 Sales").auto_close, "'!");
+    }
+}
@@ -0,0 +1,159 @@
+//! Case conversion and transliteration: PROPER, SNAKECASE, CAMELCASE,
+//! UNACCENT and UNICODENORMALIZE
+
+use icu_normalizer::{ComposingNormalizer, DecomposingNormalizer};
+use icu_properties::{maps, GeneralCategory};
+
+use super::value::{ErrorValue, Value};
+
+/// PROPER(text): upper-case every letter that follows a non-letter and
+/// lower-case the rest, as Excel does (`o'neil 2-way` → `O'Neil 2-Way`)
+pub(super) fn proper(text: &str) -> String {
+    let mut after_letter = false;
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match after_letter {
+            true => out.extend(c.to_lowercase()),
+            false => out.extend(c.to_uppercase()),
+        }
+        after_letter = c.is_alphabetic();
+    }
+    out
+}
+
+/// Words of an identifier or phrase: runs of letters and digits, also split
+/// where a capital follows a lower-case letter or digit (`orderId`) and
+/// before the last capital of an acronym (`HTTPServer` → `HTTP`, `Server`)
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for run in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = run.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, c) = (chars[i - 1], chars[i]);
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            let boundary =
+                prev.is_lowercase() || prev.is_numeric() || prev.is_uppercase() && next_lower;
+            if c.is_uppercase() && boundary {
+                words.push(chars[start..i].iter().collect());
+                start = i;
+            }
+        }
+        if start < chars.len() {
+            words.push(chars[start..].iter().collect());
+        }
+    }
+    words
+}
+
+/// SNAKECASE(text): `Order ID` or `orderId` → `order_id`
+pub(super) fn snake_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// CAMELCASE(text): `order_id` or `Order ID` → `orderId`
+pub(super) fn camel_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let lower = word.to_lowercase();
+            let mut chars = lower.chars();
+            match (i, chars.next()) {
+                (0, _) | (_, None) => lower,
+                (_, Some(first)) => first.to_uppercase().chain(chars).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Letters that carry no combining mark to strip but have a plain Latin
+/// spelling
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'đ' => "d",
+        'Đ' => "D",
+        'ø' => "o",
+        'Ø' => "O",
+        'ł' => "l",
+        'Ł' => "L",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ı' => "i",
+        _ => return None,
+    })
+}
+
+/// UNACCENT(text): drop accents and other combining marks after canonical
+/// decomposition (`Crème Brûlée` → `Creme Brulee`), and spell letters such
+/// as `đ`, `ø` and `ß` in plain Latin
+pub(super) fn unaccent(text: &str) -> String {
+    let categories = maps::general_category();
+    let mut out = String::with_capacity(text.len());
+    for c in DecomposingNormalizer::new_nfd().normalize(text).chars() {
+        match transliterate(c) {
+            Some(latin) => out.push_str(latin),
+            None if categories.get(c) == GeneralCategory::NonspacingMark => {}
+            None => out.push(c),
+        }
+    }
+    ComposingNormalizer::new_nfc().normalize(&out)
+}
+
+/// UNICODENORMALIZE(text, [form]): `form` is NFC (default), NFD, NFKC or NFKD
+pub(super) fn normalize(value: &Value, form: Option<&Value>) -> Value {
+    let (text, form) = match (
+        value.as_text(),
+        form.map_or(Ok("NFC".to_string()), Value::as_text),
+    ) {
+        (Ok(text), Ok(form)) => (text, form.trim().to_uppercase()),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    Value::Text(match form.as_str() {
+        "NFC" => ComposingNormalizer::new_nfc().normalize(&text),
+        "NFD" => DecomposingNormalizer::new_nfd().normalize(&text),
+        "NFKC" => ComposingNormalizer::new_nfkc().normalize(&text),
+        "NFKD" => DecomposingNormalizer::new_nfkd().normalize(&text),
+        _ => return Value::Error(ErrorValue::Value),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_and_transliteration() {
+        assert_eq!(proper("o'NEIL 2-way élan"), "O'Neil 2-Way Élan");
+        assert_eq!(snake_case("Order ID"), "order_id");
+        assert_eq!(
+            snake_case("parseHTTPServer2Config"),
+            "parse_http_server2_config"
+        );
+        assert_eq!(camel_case("customer-first name"), "customerFirstName");
+        assert_eq!(camel_case("ORDER_ID 2nd"), "orderId2nd");
+        assert_eq!(unaccent("Crème Brûlée"), "Creme Brulee");
+        assert_eq!(
+            unaccent("Đặng Thị Ánh, Søren, Straße"),
+            "Dang Thi Anh, Soren, Strasse"
+        );
+
+        let text = |s: &str| Value::Text(s.into());
+        assert_eq!(normalize(&text("e\u{301}"), None), text("é"));
+        assert_eq!(normalize(&text("é"), Some(&text("nfd"))), text("e\u{301}"));
+        assert_eq!(normalize(&text("ﬁ①"), Some(&text("NFKC"))), text("fi1"));
+        assert_eq!(
+            normalize(&text("x"), Some(&text("NFX"))),
+            Value::Error(ErrorValue::Value)
+        );
+    }
+}
@@ -0,0 +1,914 @@
+//! Catalog of the built-in functions, for autocomplete and help panels
+//!
+//! Every function the evaluator knows (`functions.rs`) has an entry
+//! here; a test keeps the two in step.
+
+use crate::StringResult;
+use Category::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Logical,
+    Math,
+    Text,
+    Information,
+    DateTime,
+    Array,
+    Lookup,
+    Engineering,
+}
+
+impl Category {
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Logical => "logical",
+            Category::Math => "math",
+            Category::Text => "text",
+            Category::Information => "information",
+            Category::DateTime => "datetime",
+            Category::Array => "array",
+            Category::Lookup => "lookup",
+            Category::Engineering => "engineering",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argument {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionInfo {
+    pub name: &'static str,
+    pub category: Category,
+    pub description: &'static str,
+    pub args: &'static [Argument],
+    /// The last argument may be repeated
+    pub variadic: bool,
+}
+
+impl FunctionInfo {
+    pub fn min_args(&self) -> usize {
+        self.args.iter().filter(|arg| !arg.optional).count()
+    }
+
+    /// `None` when the argument count is unbounded
+    pub fn max_args(&self) -> Option<usize> {
+        (!self.variadic).then_some(self.args.len())
+    }
+
+    /// Call shape for help text, e.g. `LEFT(text, [count])`
+    pub fn signature(&self) -> String {
+        let mut args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| match arg.optional {
+                true => format!("[{}]", arg.name),
+                false => arg.name.to_string(),
+            })
+            .collect();
+        if self.variadic {
+            args.push("...".to_string());
+        }
+        format!("{}({})", self.name, args.join(", "))
+    }
+}
+
+const fn arg(name: &'static str, description: &'static str) -> Argument {
+    Argument {
+        name,
+        description,
+        optional: false,
+    }
+}
+
+const fn opt(name: &'static str, description: &'static str) -> Argument {
+    Argument {
+        name,
+        description,
+        optional: true,
+    }
+}
+
+const fn function(
+    name: &'static str,
+    category: Category,
+    description: &'static str,
+    args: &'static [Argument],
+) -> FunctionInfo {
+    FunctionInfo {
+        name,
+        category,
+        description,
+        args,
+        variadic: false,
+    }
+}
+
+const fn variadic(
+    name: &'static str,
+    category: Category,
+    description: &'static str,
+    args: &'static [Argument],
+) -> FunctionInfo {
+    FunctionInfo {
+        variadic: true,
+        ..function(name, category, description, args)
+    }
+}
+
+/// Every built-in function, by category then name
+pub static FUNCTIONS: &[FunctionInfo] = &[
+    variadic(
+        "AND",
+        Logical,
+        "TRUE when every argument is true",
+        &[arg("logical", "Condition to test")],
+    ),
+    function(
+        "IF",
+        Logical,
+        "One value when a condition is true, another when it is false",
+        &[
+            arg("condition", "Condition to test"),
+            arg("if_true", "Result when the condition is true"),
+            opt(
+                "if_false",
+                "Result when the condition is false (FALSE if omitted)",
+            ),
+        ],
+    ),
+    function(
+        "IFERROR",
+        Logical,
+        "A fallback when a value is an error",
+        &[
+            arg("value", "Value to check"),
+            arg("if_error", "Result when the value is an error"),
+        ],
+    ),
+    function(
+        "NOT",
+        Logical,
+        "Reverses a logical value",
+        &[arg("logical", "Condition to reverse")],
+    ),
+    variadic(
+        "OR",
+        Logical,
+        "TRUE when any argument is true",
+        &[arg("logical", "Condition to test")],
+    ),
+    function(
+        "ABS",
+        Math,
+        "Absolute value of a number",
+        &[arg("number", "Number")],
+    ),
+    function(
+        "ARABIC",
+        Math,
+        "Value of a Roman numeral",
+        &[arg("text", "Roman numeral in any case, optionally with a leading minus")],
+    ),
+    function(
+        "COUNTIF",
+        Math,
+        "Number of values meeting a criterion such as \">10\" or \"inv-*\"",
+        &[
+            arg("range", "Values to test"),
+            arg(
+                "criteria",
+                "Value, or text with an optional leading comparison; * and ? are wildcards, ~ escapes them",
+            ),
+            opt("use_regex", "TRUE to read text criteria as a regular expression"),
+        ],
+    ),
+    function(
+        "CURRENCY",
+        Math,
+        "Converts an amount between currencies using the workbook's exchange rates",
+        &[
+            arg("value", "Amount in from_currency"),
+            arg("from_currency", "Currency code such as \"USD\""),
+            arg("to_currency", "Currency code to convert to"),
+            opt("date", "Use the rates that applied on this date (latest if omitted)"),
+        ],
+    ),
+    function(
+        "RAND",
+        Math,
+        "Random number between 0 and 1 (volatile)",
+        &[],
+    ),
+    function(
+        "RANDBETWEEN",
+        Math,
+        "Random whole number between two bounds (volatile)",
+        &[
+            arg("bottom", "Smallest value returned"),
+            arg("top", "Largest value returned"),
+        ],
+    ),
+    function(
+        "ROMAN",
+        Math,
+        "Roman numeral of a whole number from 0 to 3999",
+        &[arg("number", "Number to write as a Roman numeral")],
+    ),
+    function(
+        "ROUND",
+        Math,
+        "Rounds a number to a number of digits",
+        &[
+            arg("number", "Number to round"),
+            arg(
+                "digits",
+                "Digits after the decimal point; negative rounds to tens, hundreds, ...",
+            ),
+        ],
+    ),
+    variadic(
+        "SUM",
+        Math,
+        "Adds numbers; text and blanks inside ranges are ignored",
+        &[arg(
+            "number",
+            "Number, range or 3-D reference such as Sheet1:Sheet3!B2",
+        )],
+    ),
+    function(
+        "CAMELCASE",
+        Text,
+        "Joins the words of text in camelCase, e.g. order_id to orderId",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "CRC32",
+        Text,
+        "CRC-32 checksum of a text as 8 hex digits",
+        &[arg("text", "Text; numbers are hashed as displayed")],
+    ),
+    function(
+        "DATASIZE",
+        Text,
+        "Byte count as a readable size such as 1.5 MiB",
+        &[
+            arg("bytes", "Number of bytes"),
+            opt("decimal", "TRUE for 1000-based kB, MB, ...; FALSE (default) for KiB, MiB, ..."),
+            opt("digits", "Decimal places (1 if omitted)"),
+        ],
+    ),
+    function(
+        "FIND",
+        Text,
+        "Position of one text in another, case-sensitive",
+        &[
+            arg("find_text", "Text to look for"),
+            arg("within_text", "Text to search"),
+        ],
+    ),
+    function(
+        "IPNORMALIZE",
+        Text,
+        "Canonical form of an IP address: compressed lower-case IPv6, dotted IPv4",
+        &[arg("ip", "IPv4 or IPv6 address")],
+    ),
+    function(
+        "JSONPATH",
+        Text,
+        "Value at a JSONPath such as $.items[0].price in JSON text; wildcards return every match",
+        &[
+            arg("json", "JSON text"),
+            arg("path", "Path from $ using .name, ['name'], [index] and *"),
+        ],
+    ),
+    function(
+        "LEFT",
+        Text,
+        "First characters of a text",
+        &[
+            arg("text", "Text"),
+            opt("count", "Number of characters (1 if omitted)"),
+        ],
+    ),
+    function(
+        "LEN",
+        Text,
+        "Number of characters in a text",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "LOWER",
+        Text,
+        "Converts text to lower case",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "MD5",
+        Text,
+        "MD5 digest of a text as 32 hex digits",
+        &[arg("text", "Text; numbers are hashed as displayed")],
+    ),
+    function(
+        "NUMBERVALUE",
+        Text,
+        "Converts text to a number using the given decimal and group separators",
+        &[
+            arg("text", "Number as text, e.g. 1.234,5"),
+            opt("decimal_separator", "Decimal separator (\".\" if omitted)"),
+            opt("group_separator", "Thousands separator (\",\" if omitted)"),
+        ],
+    ),
+    function(
+        "PHONENORMALIZE",
+        Text,
+        "Phone number in E.164 form such as +14155550123, or its bare digits without a country",
+        &[
+            arg("number", "Phone number as written"),
+            opt("country", "ISO country code such as \"US\" for national numbers"),
+        ],
+    ),
+    function(
+        "POSTCODENORMALIZE",
+        Text,
+        "Postal code in a country's usual layout, e.g. SW1A 1AA or 94105-1234",
+        &[
+            arg("code", "Postal code as written"),
+            opt("country", "ISO country code such as \"GB\""),
+        ],
+    ),
+    function(
+        "PROPER",
+        Text,
+        "Capitalizes the first letter of each word and lower-cases the rest",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "RIGHT",
+        Text,
+        "Last characters of a text",
+        &[
+            arg("text", "Text"),
+            opt("count", "Number of characters (1 if omitted)"),
+        ],
+    ),
+    function(
+        "SEARCH",
+        Text,
+        "Position of one text in another, ignoring case",
+        &[
+            arg("find_text", "Text to look for"),
+            arg("within_text", "Text to search"),
+        ],
+    ),
+    function(
+        "SHA1",
+        Text,
+        "SHA-1 digest of a text as 40 hex digits",
+        &[arg("text", "Text; numbers are hashed as displayed")],
+    ),
+    function(
+        "SHA256",
+        Text,
+        "SHA-256 digest of a text as 64 hex digits",
+        &[arg("text", "Text; numbers are hashed as displayed")],
+    ),
+    function(
+        "SNAKECASE",
+        Text,
+        "Joins the words of text in snake_case, e.g. orderId to order_id",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "TRIM",
+        Text,
+        "Removes extra spaces between and around words",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "UNACCENT",
+        Text,
+        "Removes accents and spells letters such as đ and ß in plain Latin",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "UNICODENORMALIZE",
+        Text,
+        "Unicode normalization form of text",
+        &[
+            arg("text", "Text"),
+            opt("form", "\"NFC\" (default), \"NFD\", \"NFKC\" or \"NFKD\""),
+        ],
+    ),
+    function(
+        "UPPER",
+        Text,
+        "Converts text to upper case",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "URLDECODE",
+        Text,
+        "Decodes %XX escapes in text",
+        &[arg("text", "Percent-encoded text")],
+    ),
+    function(
+        "URLENCODE",
+        Text,
+        "Percent-encodes text for use in a URL",
+        &[arg("text", "Text")],
+    ),
+    function(
+        "URLHOST",
+        Text,
+        "Host name of a URL, in lower case",
+        &[arg("url", "URL such as https://example.com/page?id=3; the scheme is optional")],
+    ),
+    function(
+        "URLPARAM",
+        Text,
+        "Decoded value of a query-string parameter of a URL",
+        &[
+            arg("url", "URL such as https://example.com/page?id=3; the scheme is optional"),
+            arg("name", "Parameter name"),
+        ],
+    ),
+    function(
+        "URLPATH",
+        Text,
+        "Path of a URL from its leading /",
+        &[arg("url", "URL such as https://example.com/page?id=3; the scheme is optional")],
+    ),
+    function(
+        "IPINCIDR",
+        Information,
+        "TRUE when an IP address is in a network such as 10.0.0.0/8, or in any network of a range",
+        &[
+            arg("ip", "IPv4 or IPv6 address"),
+            arg("networks", "CIDR block, single address or range of them"),
+        ],
+    ),
+    function(
+        "IPVERSION",
+        Information,
+        "IP version of an address: 4 or 6",
+        &[arg("ip", "IPv4 or IPv6 address")],
+    ),
+    function(
+        "ISBLANK",
+        Information,
+        "TRUE when a value is empty",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISEMAIL",
+        Information,
+        "TRUE when a value is text shaped like an email address",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISERROR",
+        Information,
+        "TRUE when a value is an error",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISIPADDRESS",
+        Information,
+        "TRUE when a value is an IPv4 or IPv6 address",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISJSON",
+        Information,
+        "TRUE when a value is a JSON object or array",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISNUMBER",
+        Information,
+        "TRUE when a value is a number",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISTEXT",
+        Information,
+        "TRUE when a value is text",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "ISURL",
+        Information,
+        "TRUE when a value is an absolute URL such as https://example.com/page",
+        &[arg("value", "Value to check")],
+    ),
+    function(
+        "DATEVALUE",
+        DateTime,
+        "Serial number of a date written as text, optionally in a given format",
+        &[
+            arg("date_text", "Date as text"),
+            opt("format", "Date format code such as \"dd/mm/yyyy\" or \"d mmm yyyy\""),
+        ],
+    ),
+    function(
+        "NOW",
+        DateTime,
+        "Current date and time as a serial number (volatile)",
+        &[],
+    ),
+    function(
+        "TODAY",
+        DateTime,
+        "Current date as a serial number (volatile)",
+        &[],
+    ),
+    function(
+        "CUMMAX",
+        Array,
+        "Running maximum down each column",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "CUMMIN",
+        Array,
+        "Running minimum down each column",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "CUMPROD",
+        Array,
+        "Running product down each column",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "CUMSUM",
+        Array,
+        "Running total down each column",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "DENSERANKS",
+        Array,
+        "Rank of each number in its column; equal numbers share a rank and none is skipped",
+        &[
+            arg("array", "Column of numbers; blanks are skipped"),
+            opt("order", "0 (default) to rank the largest first, anything else the smallest"),
+        ],
+    ),
+    function(
+        "DIFF",
+        Array,
+        "Per-row change from the value a number of rows above",
+        &[
+            arg("array", "Column of numbers"),
+            opt("rows", "How many rows back to compare with (1 if omitted)"),
+        ],
+    ),
+    function(
+        "EWMA",
+        Array,
+        "Exponentially weighted moving average down each column",
+        &[
+            arg("array", "Column of numbers; blanks are skipped"),
+            arg("alpha", "Weight of each new value, between 0 (exclusive) and 1"),
+        ],
+    ),
+    function(
+        "EWMSTD",
+        Array,
+        "Exponentially weighted moving standard deviation down each column",
+        &[
+            arg("array", "Column of numbers; blanks are skipped"),
+            arg("alpha", "Weight of each new value, between 0 (exclusive) and 1"),
+        ],
+    ),
+    function(
+        "EWMVAR",
+        Array,
+        "Exponentially weighted moving variance down each column",
+        &[
+            arg("array", "Column of numbers; blanks are skipped"),
+            arg("alpha", "Weight of each new value, between 0 (exclusive) and 1"),
+        ],
+    ),
+    function(
+        "FILTER",
+        Array,
+        "Rows or columns of an array that match a condition",
+        &[
+            arg("array", "Array to filter"),
+            arg("include", "TRUE/FALSE per row or column"),
+            opt(
+                "if_empty",
+                "Result when nothing matches (#CALC! if omitted)",
+            ),
+        ],
+    ),
+    function(
+        "LAG",
+        Array,
+        "Per-row value from a number of rows above",
+        &[
+            arg("array", "Column of values"),
+            opt("rows", "How many rows back (1 if omitted)"),
+            opt("default", "Value before the first row (blank if omitted)"),
+        ],
+    ),
+    function(
+        "LEAD",
+        Array,
+        "Per-row value from a number of rows below",
+        &[
+            arg("array", "Column of values"),
+            opt("rows", "How many rows ahead (1 if omitted)"),
+            opt("default", "Value past the last row (blank if omitted)"),
+        ],
+    ),
+    function(
+        "MINMAXSCALE",
+        Array,
+        "Numbers rescaled to 0 (column minimum) to 1 (column maximum)",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "PCTCHANGE",
+        Array,
+        "Per-row change from the value a number of rows above, as a fraction of it",
+        &[
+            arg("array", "Column of numbers"),
+            opt("rows", "How many rows back to compare with (1 if omitted)"),
+        ],
+    ),
+    function(
+        "PCTRANKS",
+        Array,
+        "Share of the column's other numbers below each number, from 0 to 1",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "RANKS",
+        Array,
+        "Rank of each number in its column",
+        &[
+            arg("array", "Column of numbers; blanks are skipped"),
+            opt("order", "0 (default) to rank the largest first, anything else the smallest"),
+            opt(
+                "ties",
+                "Rank of equal numbers: 0 (default) lowest, 1 average, 2 highest, 3 row order",
+            ),
+        ],
+    ),
+    function(
+        "ROBUSTSCALE",
+        Array,
+        "Distance of each number from the column median, in scaled median absolute deviations",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "SHUFFLE",
+        Array,
+        "Rows of an array in a random order fixed by a seed",
+        &[
+            arg("array", "Array to shuffle, or a count n to shuffle 1 to n"),
+            arg("seed", "Whole number; the same seed gives the same order"),
+        ],
+    ),
+    function(
+        "SORT",
+        Array,
+        "Sorts the rows or columns of an array",
+        &[
+            arg("array", "Array to sort"),
+            opt("sort_index", "Row or column to sort by (1 if omitted)"),
+            opt("sort_order", "1 for ascending, -1 for descending"),
+            opt("by_col", "TRUE to sort columns instead of rows"),
+        ],
+    ),
+    function(
+        "SPLIT",
+        Array,
+        "Splits text at a delimiter into a row of parts; a column of texts gives one row each",
+        &[
+            arg("text", "Text or column of texts"),
+            arg("delimiter", "Text separating the parts"),
+            opt("max_parts", "Most parts per text, the last keeping the rest (0 for no limit)"),
+            opt("trim", "TRUE to trim spaces around each part"),
+        ],
+    ),
+    function(
+        "UNIQUE",
+        Array,
+        "Distinct rows or columns of an array",
+        &[
+            arg("array", "Array to deduplicate"),
+            opt("by_col", "TRUE to compare columns instead of rows"),
+            opt("exactly_once", "TRUE to keep only values occurring once"),
+        ],
+    ),
+    function(
+        "ZSCORE",
+        Array,
+        "Distance of each number from the column mean, in standard deviations",
+        &[arg("array", "Column of numbers; blanks are skipped")],
+    ),
+    function(
+        "MATCH",
+        Lookup,
+        "Position of a value in a row or column",
+        &[
+            arg("lookup_value", "Value to find; * and ? are wildcards, ~ escapes them"),
+            arg("lookup_array", "Row or column to search"),
+            opt(
+                "match_type",
+                "0 for an exact match; 1 (default) or -1 for the closest in an ascending or descending list",
+            ),
+            opt("use_regex", "TRUE to read a text lookup value as a regular expression"),
+        ],
+    ),
+    function(
+        "VLOOKUP",
+        Lookup,
+        "Value from the row whose first column matches a lookup value",
+        &[
+            arg("lookup_value", "Value to find in the first column"),
+            arg("table", "Table to search"),
+            arg("col_index", "Column of the table to return, from 1"),
+            opt(
+                "approximate",
+                "FALSE for an exact match with wildcards; TRUE (default) for the closest in a sorted column",
+            ),
+            opt("use_regex", "TRUE to read a text lookup value as a regular expression"),
+        ],
+    ),
+    function(
+        "BITAND",
+        Engineering,
+        "Bitwise AND of two whole numbers, as 64-bit unsigned integers",
+        &[
+            arg("number1", "Whole number from 0 to 2^64 - 1"),
+            arg("number2", "Whole number from 0 to 2^64 - 1"),
+        ],
+    ),
+    function(
+        "BITLSHIFT",
+        Engineering,
+        "Bits of a whole number shifted left; bits past 64 are dropped",
+        &[
+            arg("number", "Whole number from 0 to 2^64 - 1"),
+            arg("shift", "Whole number of bits; negative shifts right"),
+        ],
+    ),
+    function(
+        "BITOR",
+        Engineering,
+        "Bitwise OR of two whole numbers, as 64-bit unsigned integers",
+        &[
+            arg("number1", "Whole number from 0 to 2^64 - 1"),
+            arg("number2", "Whole number from 0 to 2^64 - 1"),
+        ],
+    ),
+    function(
+        "BITRSHIFT",
+        Engineering,
+        "Bits of a whole number shifted right",
+        &[
+            arg("number", "Whole number from 0 to 2^64 - 1"),
+            arg("shift", "Whole number of bits; negative shifts left"),
+        ],
+    ),
+    function(
+        "BITXOR",
+        Engineering,
+        "Bitwise exclusive OR of two whole numbers, as 64-bit unsigned integers",
+        &[
+            arg("number1", "Whole number from 0 to 2^64 - 1"),
+            arg("number2", "Whole number from 0 to 2^64 - 1"),
+        ],
+    ),
+    function(
+        "COMPLEX",
+        Engineering,
+        "Complex number from its real and imaginary parts, as text such as 3+4i",
+        &[
+            arg("real", "Real part"),
+            arg("imaginary", "Imaginary part"),
+            opt("suffix", "\"i\" (default) or \"j\""),
+        ],
+    ),
+    function(
+        "CONVERT",
+        Engineering,
+        "Converts a number between units of length, mass, temperature or data size",
+        &[
+            arg("number", "Value in from_unit"),
+            arg("from_unit", "Unit such as \"km\", \"lbm\", \"C\" or \"Mibyte\" (case-sensitive)"),
+            arg("to_unit", "Unit of the same kind to convert to"),
+        ],
+    ),
+    function(
+        "IMABS",
+        Engineering,
+        "Absolute value (modulus) of a complex number",
+        &[arg("inumber", "Complex number such as \"3+4i\", or a real number")],
+    ),
+    function(
+        "IMAGINARY",
+        Engineering,
+        "Imaginary part of a complex number",
+        &[arg("inumber", "Complex number such as \"3+4i\", or a real number")],
+    ),
+    variadic(
+        "IMPRODUCT",
+        Engineering,
+        "Product of complex numbers",
+        &[arg("inumber", "Complex number, real number or range of them")],
+    ),
+    function(
+        "IMREAL",
+        Engineering,
+        "Real part of a complex number",
+        &[arg("inumber", "Complex number such as \"3+4i\", or a real number")],
+    ),
+    variadic(
+        "IMSUM",
+        Engineering,
+        "Sum of complex numbers",
+        &[arg("inumber", "Complex number, real number or range of them")],
+    ),
+];
+
+/// Catalog entry for a function name, in any case
+pub fn lookup(name: &str) -> Option<&'static FunctionInfo> {
+    FUNCTIONS.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+}
+
+/// Every supported function with its arity, arguments and category
+///
+/// # Returns
+/// StringResult with a JSON array of `{name, category, description,
+/// signature, min_args, max_args, args: [{name, description, optional}]}`;
+/// `max_args` is null for functions taking any number of arguments
+#[no_mangle]
+pub extern "C" fn tessera_list_functions() -> StringResult {
+    let functions: Vec<serde_json::Value> = FUNCTIONS
+        .iter()
+        .map(|f| {
+            let args: Vec<serde_json::Value> = f
+                .args
+                .iter()
+                .map(|arg| {
+                    serde_json::json!({
+                        "name": arg.name,
+                        "description": arg.description,
+                        "optional": arg.optional,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "name": f.name,
+                "category": f.category.name(),
+                "description": f.description,
+                "signature": f.signature(),
+                "min_args": f.min_args(),
+                "max_args": f.max_args(),
+                "args": args,
+            })
+        })
+        .collect();
+    StringResult::success(&serde_json::Value::from(functions).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::{evaluate, parse, ErrorValue, EvalContext, Value};
+
+    struct Empty;
+    impl EvalContext for Empty {}
+
+    #[test]
+    fn test_catalog_matches_evaluator() {
+        for f in FUNCTIONS {
+            let call = |count: usize| {
+                let args = vec!["1"; count].join(", ");
+                evaluate(&parse(&format!("={}({})", f.name, args)).unwrap(), &Empty)
+            };
+            assert_ne!(
+                call(f.min_args()),
+                Value::Error(ErrorValue::Name),
+                "{}",
+                f.name
+            );
+            if let Some(max) = f.max_args() {
+                assert_eq!(call(max + 1), Value::Error(ErrorValue::Value), "{}", f.name);
+            }
+        }
+        assert_eq!(lookup("left").unwrap().signature(), "LEFT(text, [count])");
+        assert_eq!(lookup("and").unwrap().max_args(), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Completion candidates for a formula being typed
+//!
+//! The text before the cursor decides what is offered: after `[` column
+//! names, after an opening `'` sheet names, after `Sheet2!` the columns of
+//! that sheet and otherwise functions, defined names, columns and sheets
+//! matching the identifier under the cursor. Nothing is offered inside a
+//! string literal. Positions are byte offsets, like token spans; each
+//! candidate carries the span of text it replaces.
+
+use std::ops::Range;
+use std::os::raw::c_char;
+
+use super::catalog::FUNCTIONS;
+use super::parser::{parse, quote_sheet, Expr};
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TesseraTable};
+use crate::workbook::Workbook;
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CandidateKind {
+    Column,
+    Name,
+    Function,
+    Sheet,
+}
+
+impl CandidateKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            CandidateKind::Column => "column",
+            CandidateKind::Name => "name",
+            CandidateKind::Function => "function",
+            CandidateKind::Sheet => "sheet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub kind: CandidateKind,
+    /// What the popup shows
+    pub label: String,
+    /// Text replacing `span`
+    pub insert: String,
+    pub span: Range<usize>,
+    /// Signature of a function, empty otherwise
+    pub detail: String,
+}
+
+/// What can be referenced from the formula's sheet
+pub struct Scope {
+    pub columns: Vec<String>,
+    pub names: Vec<String>,
+    /// Every sheet with its columns
+    pub sheets: Vec<(String, Vec<String>)>,
+}
+
+impl Scope {
+    pub fn for_table(table: &TesseraTable) -> Self {
+        Scope {
+            columns: table.headers().to_vec(),
+            names: Vec::new(),
+            sheets: Vec::new(),
+        }
+    }
+
+    /// Scope of the sheet at `sheet` (which must exist)
+    pub fn for_workbook(workbook: &Workbook, sheet: usize) -> Self {
+        let table = workbook.sheet_at(sheet).expect("sheet index in range");
+        Scope {
+            columns: table.headers().to_vec(),
+            names: workbook
+                .defined_names()
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            sheets: workbook
+                .sheet_names()
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let headers = workbook.sheet_at(i).map(|t| t.headers().to_vec());
+                    (name.to_string(), headers.unwrap_or_default())
+                })
+                .collect(),
+        }
+    }
+
+    fn sheet_columns(&self, sheet: &str) -> &[String] {
+        self.sheets
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(sheet))
+            .map_or(&[], |(_, columns)| columns)
+    }
+}
+
+/// Bare when the header parses back as itself, else bracketed
+fn column_text(name: &str) -> String {
+    match parse(name) {
+        Ok(Expr::Name(parsed)) if parsed == name => name.to_string(),
+        _ => format!("[{}]", name),
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.')
+}
+
+/// 0 for a prefix match, 1 for a match elsewhere, `None` for no match
+fn quality(label: &str, typed: &str) -> Option<u8> {
+    let (label, typed) = (label.to_lowercase(), typed.to_lowercase());
+    if label.starts_with(&typed) {
+        Some(0)
+    } else if label.contains(&typed) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Where the cursor sits, found by scanning the text before it
+enum Site<'a> {
+    String,
+    /// Inside `[...`, optionally after a sheet prefix
+    Bracket {
+        sheet: Option<String>,
+        typed: &'a str,
+    },
+    /// Inside an unterminated `'...`
+    QuotedSheet {
+        typed: &'a str,
+    },
+    /// After `Sheet2!` or `'Q1 Sales'!`
+    Qualified {
+        sheet: String,
+        typed: &'a str,
+    },
+    Ident {
+        typed: &'a str,
+    },
+}
+
+/// Sheet name written right before byte `end` (which is a `!` or `[`)
+fn sheet_before(before: &str, end: usize) -> Option<String> {
+    let head = &before[..end];
+    let head = head.strip_suffix('!')?;
+    if let Some(quoted) = head.strip_suffix('\'') {
+        let open = quoted.rfind('\'')?;
+        return Some(quoted[open + 1..].replace("''", "'"));
+    }
+    let start = head
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_ident_char(c))
+        .last()
+        .map(|(i, _)| i)?;
+    Some(head[start..].to_string())
+}
+
+fn site(before: &str) -> (Site<'_>, usize) {
+    // Walk the text once, tracking open strings, quotes and brackets
+    let (mut in_string, mut in_quote, mut bracket) = (false, None, None);
+    for (i, c) in before.char_indices() {
+        match c {
+            '"' if in_quote.is_none() && bracket.is_none() => in_string = !in_string,
+            '\'' if !in_string && bracket.is_none() => {
+                in_quote = if in_quote.is_some() { None } else { Some(i) }
+            }
+            '[' if !in_string && in_quote.is_none() => bracket = Some(i),
+            ']' if !in_string && in_quote.is_none() => bracket = None,
+            _ => {}
+        }
+    }
+    if in_string {
+        return (Site::String, before.len());
+    }
+    if let Some(open) = bracket {
+        let typed = &before[open + 1..];
+        return (
+            Site::Bracket {
+                sheet: sheet_before(before, open),
+                typed,
+            },
+            open,
+        );
+    }
+    if let Some(open) = in_quote {
+        return (
+            Site::QuotedSheet {
+                typed: &before[open + 1..],
+            },
+            open,
+        );
+    }
+
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_ident_char(c))
+        .last()
+        .map_or(before.len(), |(i, _)| i);
+    let typed = &before[start..];
+    match before[..start]
+        .ends_with('!')
+        .then(|| sheet_before(before, start))
+    {
+        Some(Some(sheet)) => (Site::Qualified { sheet, typed }, start),
+        _ => (Site::Ident { typed }, start),
+    }
+}
+
+/// Ranked candidates for the formula text with the cursor at byte `cursor`
+pub fn complete(formula: &str, cursor: usize, scope: &Scope) -> Result<Vec<Candidate>, String> {
+    if !formula.is_char_boundary(cursor) {
+        return Err(format!("Cursor {} is out of range", cursor));
+    }
+    let before = &formula[..cursor];
+    let (site, start) = site(before);
+
+    let mut found: Vec<(u8, Candidate)> = Vec::new();
+    let mut offer = |kind, label: &str, insert: String, detail: String, typed: &str, end| {
+        if let Some(q) = quality(label, typed) {
+            found.push((
+                q,
+                Candidate {
+                    kind,
+                    label: label.to_string(),
+                    insert,
+                    span: start..end,
+                    detail,
+                },
+            ));
+        }
+    };
+
+    match site {
+        Site::String => {}
+        Site::Bracket { sheet, typed } => {
+            // Swallow the closing bracket the editor may have inserted already
+            let end = cursor + usize::from(formula[cursor..].starts_with(']'));
+            let columns = match &sheet {
+                Some(sheet) => scope.sheet_columns(sheet),
+                None => &scope.columns,
+            };
+            for column in columns {
+                let insert = format!("[{}]", column);
+                offer(
+                    CandidateKind::Column,
+                    column,
+                    insert,
+                    String::new(),
+                    typed,
+                    end,
+                );
+            }
+        }
+        Site::QuotedSheet { typed } => {
+            for (sheet, _) in &scope.sheets {
+                let insert = format!("{}!", quote_sheet(sheet));
+                offer(
+                    CandidateKind::Sheet,
+                    sheet,
+                    insert,
+                    String::new(),
+                    typed,
+                    cursor,
+                );
+            }
+        }
+        Site::Qualified { sheet, typed } => {
+            for column in scope.sheet_columns(&sheet) {
+                let insert = column_text(column);
+                offer(
+                    CandidateKind::Column,
+                    column,
+                    insert,
+                    String::new(),
+                    typed,
+                    cursor,
+                );
+            }
+        }
+        Site::Ident { typed } if typed.starts_with(|c: char| c.is_ascii_digit()) => {}
+        Site::Ident { typed } => {
+            for column in &scope.columns {
+                let insert = column_text(column);
+                offer(
+                    CandidateKind::Column,
+                    column,
+                    insert,
+                    String::new(),
+                    typed,
+                    cursor,
+                );
+            }
+            for name in &scope.names {
+                offer(
+                    CandidateKind::Name,
+                    name,
+                    name.clone(),
+                    String::new(),
+                    typed,
+                    cursor,
+                );
+            }
+            for function in FUNCTIONS {
+                let insert = format!("{}(", function.name);
+                let detail = function.signature();
+                offer(
+                    CandidateKind::Function,
+                    function.name,
+                    insert,
+                    detail,
+                    typed,
+                    cursor,
+                );
+            }
+            for (sheet, _) in &scope.sheets {
+                let insert = format!("{}!", quote_sheet(sheet));
+                offer(
+                    CandidateKind::Sheet,
+                    sheet,
+                    insert,
+                    String::new(),
+                    typed,
+                    cursor,
+                );
+            }
+        }
+    }
+
+    found.sort_by(|(qa, a), (qb, b)| {
+        (qa, a.kind, a.label.to_lowercase()).cmp(&(qb, b.kind, b.label.to_lowercase()))
+    });
+    Ok(found.into_iter().map(|(_, candidate)| candidate).collect())
+}
+
+/// Candidates as JSON: `[{kind, label, insert, start, end, detail}]`
+pub fn candidates_to_json(candidates: &[Candidate]) -> String {
+    let list: Vec<serde_json::Value> = candidates
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "kind": c.kind.name(),
+                "label": c.label,
+                "insert": c.insert,
+                "start": c.span.start,
+                "end": c.span.end,
+                "detail": c.detail,
+            })
+        })
+        .collect();
+    serde_json::Value::from(list).to_string()
+}
+
+/// Completion candidates for a formula typed against a table
+///
+/// # Arguments
+/// * `formula` - Formula text being edited
+/// * `cursor` - Cursor position as a byte offset into `formula`
+///
+/// # Returns
+/// StringResult with a JSON array of `{kind, label, insert, start, end,
+/// detail}`, best first; `insert` replaces the bytes `[start, end)`
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_complete_formula(
+    table: *const TesseraTable,
+    formula: *const c_char,
+    cursor: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    complete(formula, cursor, &Scope::for_table(table))
+        .map(|candidates| candidates_to_json(&candidates))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope() -> Scope {
+        Scope {
+            columns: vec!["Amount".into(), "Sale Region".into()],
+            names: vec!["Rate".into()],
+            sheets: vec![
+                ("Data".into(), vec!["Qty".into()]),
+                ("Q1 Sales".into(), vec!["Region".into()]),
+            ],
+        }
+    }
+
+    fn labels(formula: &str, cursor: usize) -> Vec<String> {
+        complete(formula, cursor, &scope())
+            .unwrap()
+            .into_iter()
+            .map(|c| c.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_completion_sites() {
+        let candidates = complete("=ROUND(am", 9, &scope()).unwrap();
+        assert_eq!(candidates[0].label, "Amount");
+        assert_eq!(
+            (candidates[0].insert.as_str(), candidates[0].span.clone()),
+            ("Amount", 7..9)
+        );
+
+        // Prefix matches rank above matches further in
+        assert_eq!(
+            labels("=ra", 3),
+            [
+                "Rate",
+                "RAND",
+                "RANDBETWEEN",
+                "RANKS",
+                "ARABIC",
+                "DENSERANKS",
+                "PCTRANKS",
+                "URLPARAM"
+            ]
+        );
+        assert_eq!(labels("=[sa]", 4), ["Sale Region"]);
+        assert_eq!(complete("=[sa]", 4, &scope()).unwrap()[0].span, 1..5);
+        assert_eq!(labels("='q", 3), ["Q1 Sales"]);
+        assert_eq!(
+            complete("='q", 3, &scope()).unwrap()[0].insert,
+            "'Q1 Sales'!"
+        );
+        assert_eq!(labels("='Q1 Sales'!re", 14), ["Region"]);
+        assert_eq!(labels("=Data!", 6), ["Qty"]);
+        assert!(labels("=\"ra", 4).is_empty());
+        assert!(labels("=A1+12", 6).is_empty());
+        assert!(complete("=é", 2, &scope()).is_err());
+    }
+}
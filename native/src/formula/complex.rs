@@ -0,0 +1,197 @@
+//! Complex numbers written as text, like Excel's IM functions
+//!
+//! A complex number is text such as `"3+4i"`, `"-2.5j"`, `"i"` or `"7"`, or
+//! a plain number for a real one. Results are text in the same form, using
+//! the `j` suffix when the inputs did; mixing `i` and `j` is `#VALUE!` and
+//! anything unreadable `#NUM!`.
+
+use super::value::{format_number, ErrorValue, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+/// Coefficient text such as `2.5`, `-1e3`, `+` or `-`; only plain decimals
+/// are accepted, not `inf` or `NaN`
+fn coefficient(text: &str) -> Option<f64> {
+    match text {
+        "" | "+" => Some(1.0),
+        "-" => Some(-1.0),
+        _ if text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')) =>
+        {
+            text.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Parse complex literal text, returning its imaginary suffix if it has one
+fn parse(text: &str) -> Option<(Complex, Option<char>)> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some((Complex { re: 0.0, im: 0.0 }, None));
+    }
+    let Some(body) = text.strip_suffix(['i', 'j']) else {
+        let re = coefficient(text).filter(|_| !matches!(text, "+" | "-"))?;
+        return Some((Complex { re, im: 0.0 }, None));
+    };
+    let suffix = text.chars().last();
+    // The sign starting the imaginary part, skipping exponent signs (1e-3)
+    let split = body
+        .char_indices()
+        .skip(1)
+        .filter(|&(i, c)| matches!(c, '+' | '-') && !body[..i].ends_with(['e', 'E']))
+        .map(|(i, _)| i)
+        .last();
+    let (re, im) = match split {
+        Some(i) => (
+            coefficient(&body[..i]).filter(|_| !matches!(&body[..i], "+" | "-"))?,
+            coefficient(&body[i..])?,
+        ),
+        None => (0.0, coefficient(body)?),
+    };
+    Some((Complex { re, im }, suffix))
+}
+
+fn format(value: Complex, suffix: char) -> String {
+    let Complex { re, im } = value;
+    let mut out = String::new();
+    if re != 0.0 || im == 0.0 {
+        out.push_str(&format_number(re));
+    }
+    if im != 0.0 {
+        if im > 0.0 && !out.is_empty() {
+            out.push('+');
+        }
+        match im {
+            1.0 => {}
+            -1.0 => out.push('-'),
+            _ => out.push_str(&format_number(im)),
+        }
+        out.push(suffix);
+    }
+    out
+}
+
+/// Complex argument: a number, blank (0) or complex literal text
+fn operand(value: &Value) -> Result<(Complex, Option<char>), ErrorValue> {
+    match value {
+        Value::Number(n) => Ok((Complex { re: *n, im: 0.0 }, None)),
+        Value::Blank => Ok((Complex { re: 0.0, im: 0.0 }, None)),
+        Value::Text(text) => parse(text).ok_or(ErrorValue::Num),
+        Value::Error(e) => Err(*e),
+        _ => Err(ErrorValue::Value),
+    }
+}
+
+/// COMPLEX(real, imaginary, [suffix])
+pub(super) fn complex(re: &Value, im: &Value, suffix: Option<&Value>) -> Value {
+    let suffix = match suffix.map(Value::as_text).transpose() {
+        Ok(None) => 'i',
+        Ok(Some(s)) if s == "i" || s.is_empty() => 'i',
+        Ok(Some(s)) if s == "j" => 'j',
+        Ok(Some(_)) => return Value::Error(ErrorValue::Value),
+        Err(e) => return Value::Error(e),
+    };
+    match (re.as_number(), im.as_number()) {
+        (Ok(re), Ok(im)) => Value::Text(format(Complex { re, im }, suffix)),
+        (Err(e), _) | (_, Err(e)) => Value::Error(e),
+    }
+}
+
+/// IMSUM(inumber, ...) or IMPRODUCT: sum or product of every argument,
+/// array elements included
+pub(super) fn fold(args: &[Value], product: bool) -> Value {
+    let mut acc = match product {
+        true => Complex { re: 1.0, im: 0.0 },
+        false => Complex { re: 0.0, im: 0.0 },
+    };
+    let mut suffix: Option<char> = None;
+    for value in args.iter().flat_map(|arg| match arg {
+        Value::Array(array) => array.values().to_vec(),
+        scalar => vec![scalar.clone()],
+    }) {
+        let (z, own) = match operand(&value) {
+            Ok(operand) => operand,
+            Err(e) => return Value::Error(e),
+        };
+        match (suffix, own) {
+            (Some(a), Some(b)) if a != b => return Value::Error(ErrorValue::Value),
+            (None, Some(_)) => suffix = own,
+            _ => {}
+        }
+        acc = match product {
+            true => Complex {
+                re: acc.re * z.re - acc.im * z.im,
+                im: acc.re * z.im + acc.im * z.re,
+            },
+            false => Complex {
+                re: acc.re + z.re,
+                im: acc.im + z.im,
+            },
+        };
+    }
+    Value::Text(format(acc, suffix.unwrap_or('i')))
+}
+
+/// Part of a complex number returned by IMREAL, IMAGINARY and IMABS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Part {
+    Real,
+    Imaginary,
+    Modulus,
+}
+
+pub(super) fn part(value: &Value, part: Part) -> Value {
+    match operand(value) {
+        Ok((z, _)) => Value::number(match part {
+            Part::Real => z.re,
+            Part::Imaginary => z.im,
+            Part::Modulus => z.re.hypot(z.im),
+        }),
+        Err(e) => Value::Error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complex_numbers() {
+        let text = |s: &str| Value::Text(s.into());
+        let parsed = |s: &str| parse(s).map(|(z, _)| (z.re, z.im));
+        assert_eq!(parsed("3+4i"), Some((3.0, 4.0)));
+        assert_eq!(parsed("-2.5j"), Some((0.0, -2.5)));
+        assert_eq!(parsed("1e-3-i"), Some((0.001, -1.0)));
+        assert_eq!(parsed(" 7 "), Some((7.0, 0.0)));
+        assert_eq!(parsed("i"), Some((0.0, 1.0)));
+        assert_eq!(parsed("3+4"), None);
+        assert_eq!(parsed("infi"), None);
+
+        assert_eq!(
+            complex(&Value::Number(3.0), &Value::Number(-1.0), None),
+            text("3-i")
+        );
+        assert_eq!(
+            complex(&Value::Number(0.0), &Value::Number(2.0), Some(&text("j"))),
+            text("2j")
+        );
+        assert_eq!(
+            fold(&[text("3+4i"), text("1-i"), Value::Number(2.0)], false),
+            text("6+3i")
+        );
+        assert_eq!(fold(&[text("1+2j"), text("3-j")], true), text("5+5j"));
+        assert_eq!(
+            fold(&[text("1+2i"), text("3-j")], false),
+            Value::Error(ErrorValue::Value)
+        );
+        assert_eq!(part(&text("3+4i"), Part::Modulus), Value::Number(5.0));
+        assert_eq!(part(&text("3+4i"), Part::Imaginary), Value::Number(4.0));
+        assert_eq!(part(&text("x"), Part::Real), Value::Error(ErrorValue::Num));
+    }
+}
@@ -0,0 +1,255 @@
+//! Contact data cleaning: PHONENORMALIZE and POSTCODENORMALIZE
+//!
+//! Both are best-effort and work from a country's ISO 3166 alpha-2 code
+//! (`"US"`, `"GB"`, `"VN"`, ...). Phone numbers become E.164 (`+14155550123`);
+//! postal codes take the country's usual spacing and separators. Input that
+//! cannot fit the country's format is `#VALUE!`.
+
+use super::value::{format_number, ErrorValue, Value};
+
+/// Country code, calling code and the trunk prefix dialled before national
+/// numbers, if any
+const COUNTRIES: &[(&str, &str, Option<&str>)] = &[
+    ("AR", "54", Some("0")),
+    ("AT", "43", Some("0")),
+    ("AU", "61", Some("0")),
+    ("BE", "32", Some("0")),
+    ("BR", "55", Some("0")),
+    ("CA", "1", Some("1")),
+    ("CH", "41", Some("0")),
+    ("CN", "86", Some("0")),
+    ("DE", "49", Some("0")),
+    ("DK", "45", None),
+    ("ES", "34", None),
+    ("FI", "358", Some("0")),
+    ("FR", "33", Some("0")),
+    ("GB", "44", Some("0")),
+    ("HK", "852", None),
+    ("ID", "62", Some("0")),
+    ("IE", "353", Some("0")),
+    ("IN", "91", Some("0")),
+    ("IT", "39", None),
+    ("JP", "81", Some("0")),
+    ("KR", "82", Some("0")),
+    ("MX", "52", None),
+    ("MY", "60", Some("0")),
+    ("NL", "31", Some("0")),
+    ("NO", "47", None),
+    ("NZ", "64", Some("0")),
+    ("PH", "63", Some("0")),
+    ("PL", "48", None),
+    ("PT", "351", None),
+    ("RU", "7", Some("8")),
+    ("SE", "46", Some("0")),
+    ("SG", "65", None),
+    ("TH", "66", Some("0")),
+    ("TR", "90", Some("0")),
+    ("US", "1", Some("1")),
+    ("VN", "84", Some("0")),
+    ("ZA", "27", Some("0")),
+];
+
+/// Postal code layouts: `9` is a digit, `A` a letter, anything else is
+/// written as is
+const POSTCODES: &[(&str, &[&str])] = &[
+    ("AT", &["9999"]),
+    ("AU", &["9999"]),
+    ("BE", &["9999"]),
+    ("BR", &["99999-999"]),
+    ("CA", &["A9A 9A9"]),
+    ("CH", &["9999"]),
+    ("CN", &["999999"]),
+    ("DE", &["99999"]),
+    ("DK", &["9999"]),
+    ("ES", &["99999"]),
+    ("FI", &["99999"]),
+    ("FR", &["99999"]),
+    ("IN", &["999999"]),
+    ("IT", &["99999"]),
+    ("JP", &["999-9999"]),
+    ("KR", &["99999"]),
+    ("MX", &["99999"]),
+    ("NL", &["9999 AA"]),
+    ("NO", &["9999"]),
+    ("NZ", &["9999"]),
+    ("PL", &["99-999"]),
+    ("PT", &["9999-999"]),
+    ("RU", &["999999"]),
+    ("SE", &["999 99"]),
+    ("SG", &["999999"]),
+    ("US", &["99999", "99999-9999"]),
+    ("VN", &["99999"]),
+    ("ZA", &["9999"]),
+];
+
+/// Optional country argument, upper-cased; empty when omitted or blank
+fn country(value: Option<&Value>) -> Result<String, ErrorValue> {
+    match value {
+        None | Some(Value::Blank) => Ok(String::new()),
+        Some(value) => Ok(value.as_text()?.trim().to_uppercase()),
+    }
+}
+
+/// Number text without punctuation: a leading `+` and the digits, stopping
+/// at the first letter so extensions (`ext. 12`, `x12`) are dropped. A
+/// trunk prefix written as `(0)` after the calling code is dropped too.
+fn dial_digits(text: &str) -> String {
+    let text = text.replace("(0)", "");
+    let start = text.find(|c: char| c.is_ascii_digit() || c == '+');
+    let Some(text) = start.map(|start| &text[start..]) else {
+        return String::new();
+    };
+    let end = text.find(char::is_alphabetic).unwrap_or(text.len());
+    let plus = text.starts_with('+').then_some('+');
+    plus.into_iter()
+        .chain(text[..end].chars().filter(char::is_ascii_digit))
+        .collect()
+}
+
+/// PHONENORMALIZE(number, [country]): E.164 form of a phone number
+///
+/// Numbers starting with `+` or `00` (`011` in North America) are already
+/// international. National numbers lose their trunk prefix and gain the
+/// country's calling code; without a country they come back as bare digits.
+pub(super) fn phone(value: &Value, default_country: Option<&Value>) -> Value {
+    let (number, country) = match (value.as_text(), country(default_country)) {
+        (Ok(number), Ok(country)) => (dial_digits(&number), country),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    let entry = COUNTRIES.iter().find(|(code, ..)| *code == country);
+    if !country.is_empty() && entry.is_none() {
+        return Value::Error(ErrorValue::Value);
+    }
+
+    let exit = match entry {
+        Some((_, "1", _)) => "011",
+        _ => "00",
+    };
+    let international = number
+        .strip_prefix('+')
+        .or_else(|| number.strip_prefix(exit))
+        .map(str::to_string);
+    let digits = match (international, entry) {
+        (Some(digits), _) => digits,
+        (None, None) if !number.is_empty() => return Value::Text(number),
+        (None, Some((_, calling, trunk))) => {
+            let national = trunk
+                .and_then(|trunk| number.strip_prefix(trunk))
+                .filter(|rest| *calling != "1" || rest.len() == 10)
+                .unwrap_or(&number);
+            if *calling == "1" && national.len() != 10 {
+                return Value::Error(ErrorValue::Value);
+            }
+            format!("{}{}", calling, national)
+        }
+        (None, None) => return Value::Error(ErrorValue::Value),
+    };
+    match digits.len() {
+        8..=15 if !digits.starts_with('0') => Value::Text(format!("+{}", digits)),
+        _ => Value::Error(ErrorValue::Value),
+    }
+}
+
+/// Fill `layout` with the letters and digits of `code`, if they fit
+fn fill(layout: &str, code: &[char]) -> Option<String> {
+    let mut chars = code.iter();
+    let out = layout
+        .chars()
+        .map(|slot| match slot {
+            '9' => chars.next().filter(|c| c.is_ascii_digit()).copied(),
+            'A' => chars.next().filter(|c| c.is_ascii_alphabetic()).copied(),
+            literal => Some(literal),
+        })
+        .collect::<Option<String>>()?;
+    chars.next().is_none().then_some(out)
+}
+
+/// POSTCODENORMALIZE(code, [country]): postal code in the country's layout
+///
+/// Numbers are zero-padded, so a German `1067` read as a number becomes
+/// `01067`. British codes are split before the inward code (`SW1A 1AA`).
+/// Without a country, or for one without a known layout, the code is only
+/// upper-cased with its spacing tidied.
+pub(super) fn postcode(value: &Value, default_country: Option<&Value>) -> Value {
+    let country = match country(default_country) {
+        Ok(country) => country,
+        Err(e) => return Value::Error(e),
+    };
+    let text = match value {
+        Value::Number(n) => format_number(*n),
+        value => match value.as_text() {
+            Ok(text) => text,
+            Err(e) => return Value::Error(e),
+        },
+    };
+    let code: Vec<char> = text
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if country == "GB" {
+        let inward = code.len().saturating_sub(3);
+        return match (code.len(), fill("9AA", &code[inward..])) {
+            (5..=7, Some(inward)) => Value::Text(format!(
+                "{} {}",
+                code[..code.len() - 3].iter().collect::<String>(),
+                inward
+            )),
+            _ => Value::Error(ErrorValue::Value),
+        };
+    }
+    let Some((_, layouts)) = POSTCODES.iter().find(|(code, _)| *code == country) else {
+        return Value::Text(
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_uppercase(),
+        );
+    };
+    let padded = |layout: &str| {
+        let width = layout.chars().filter(|&c| c == '9').count();
+        match value {
+            Value::Number(_) if layout.chars().all(|c| c == '9') && code.len() < width => {
+                let mut padded = vec!['0'; width - code.len()];
+                padded.extend(&code);
+                padded
+            }
+            _ => code.clone(),
+        }
+    };
+    layouts
+        .iter()
+        .find_map(|layout| fill(layout, &padded(layout)))
+        .map_or(Value::Error(ErrorValue::Value), Value::Text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phone_and_postcode() {
+        let text = |s: &str| Value::Text(s.into());
+        let e164 = |number: &str, country: &str| phone(&text(number), Some(&text(country)));
+
+        assert_eq!(e164("(415) 555-0123", "US"), text("+14155550123"));
+        assert_eq!(e164("1-415-555-0123 ext. 9", "US"), text("+14155550123"));
+        assert_eq!(e164("020 7946 0958", "GB"), text("+442079460958"));
+        assert_eq!(e164("0049 30 901820", "GB"), text("+4930901820"));
+        assert_eq!(e164("+84 (0)90 123 4567", "US"), text("+84901234567"));
+        assert_eq!(e164("06 12 34 56 78", "fr"), text("+33612345678"));
+        assert_eq!(e164("555-0123", "US"), Value::Error(ErrorValue::Value));
+        assert_eq!(e164("0612345678", "XX"), Value::Error(ErrorValue::Value));
+        assert_eq!(phone(&text("06.12.34"), None), text("061234"));
+
+        let code = |value: Value, country: &str| postcode(&value, Some(&text(country)));
+        assert_eq!(code(text("sw1a1aa"), "GB"), text("SW1A 1AA"));
+        assert_eq!(code(text("k1a0b1"), "CA"), text("K1A 0B1"));
+        assert_eq!(code(text("1234ab"), "NL"), text("1234 AB"));
+        assert_eq!(code(text("941051234"), "US"), text("94105-1234"));
+        assert_eq!(code(Value::Number(1067.0), "DE"), text("01067"));
+        assert_eq!(code(text("1067"), "DE"), Value::Error(ErrorValue::Value));
+        assert_eq!(postcode(&text(" ab1  2cd "), None), text("AB1 2CD"));
+    }
+}
@@ -0,0 +1,478 @@
+//! Unit, currency, number-system and text conversions
+//!
+//! `CONVERT` follows Excel's unit names, which are case-sensitive: `"m"`,
+//! `"mi"`, `"lbm"`, `"C"`, `"byte"`, ... Metric units take the decimal
+//! prefixes (`"km"`, `"mg"`), and `bit` and `byte` also take the binary ones
+//! (`"kibyte"`, `"Mibyte"`). Only units of the same kind convert; anything
+//! else is `#N/A`.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use super::value::{format_number, Array, ErrorValue, Value};
+use crate::datetime::{parse_datetime, to_serial};
+use crate::render::format::NumberFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Length,
+    Mass,
+    Temperature,
+    Information,
+}
+
+struct Unit {
+    name: &'static str,
+    kind: Kind,
+    /// Size in the base unit of its kind: metre, gram, kelvin degree, byte
+    factor: f64,
+    prefixes: bool,
+}
+
+const fn unit(name: &'static str, kind: Kind, factor: f64, prefixes: bool) -> Unit {
+    Unit {
+        name,
+        kind,
+        factor,
+        prefixes,
+    }
+}
+
+const UNITS: &[Unit] = &[
+    unit("m", Kind::Length, 1.0, true),
+    unit("mi", Kind::Length, 1609.344, false),
+    unit("Nmi", Kind::Length, 1852.0, false),
+    unit("in", Kind::Length, 0.0254, false),
+    unit("ft", Kind::Length, 0.3048, false),
+    unit("yd", Kind::Length, 0.9144, false),
+    unit("ang", Kind::Length, 1e-10, true),
+    unit("ly", Kind::Length, 9_460_730_472_580_800.0, false),
+    unit("g", Kind::Mass, 1.0, true),
+    unit("u", Kind::Mass, 1.660_539_066_60e-24, true),
+    unit("lbm", Kind::Mass, 453.592_37, false),
+    unit("ozm", Kind::Mass, 28.349_523_125, false),
+    unit("stone", Kind::Mass, 6_350.293_18, false),
+    unit("ton", Kind::Mass, 907_184.74, false),
+    unit("C", Kind::Temperature, 1.0, false),
+    unit("cel", Kind::Temperature, 1.0, false),
+    unit("F", Kind::Temperature, 5.0 / 9.0, false),
+    unit("fah", Kind::Temperature, 5.0 / 9.0, false),
+    unit("K", Kind::Temperature, 1.0, true),
+    unit("kel", Kind::Temperature, 1.0, true),
+    unit("bit", Kind::Information, 0.125, true),
+    unit("byte", Kind::Information, 1.0, true),
+];
+
+/// Longest first, so `da` wins over `d`
+const DECIMAL_PREFIXES: &[(&str, f64)] = &[
+    ("da", 1e1),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+];
+
+const BINARY_PREFIXES: &[(&str, f64)] = &[
+    ("ki", 1024.0),
+    ("Mi", 1_048_576.0),
+    ("Gi", 1_073_741_824.0),
+    ("Ti", 1_099_511_627_776.0),
+    ("Pi", 1_125_899_906_842_624.0),
+    ("Ei", 1_152_921_504_606_846_976.0),
+];
+
+/// Kind and size in the base unit of a unit name, prefix included
+fn resolve(name: &str) -> Option<(&'static Unit, f64)> {
+    if let Some(unit) = UNITS.iter().find(|unit| unit.name == name) {
+        return Some((unit, unit.factor));
+    }
+    BINARY_PREFIXES
+        .iter()
+        .chain(DECIMAL_PREFIXES)
+        .find_map(|&(prefix, scale)| {
+            let unit = UNITS
+                .iter()
+                .find(|unit| unit.prefixes && name.strip_prefix(prefix) == Some(unit.name))?;
+            let binary = BINARY_PREFIXES.iter().any(|&(p, _)| p == prefix);
+            (!binary || unit.kind == Kind::Information).then_some((unit, unit.factor * scale))
+        })
+}
+
+/// Offset of a temperature scale's zero from absolute zero, in its own degrees
+fn temperature_offset(name: &str) -> f64 {
+    match name {
+        "C" | "cel" => 273.15,
+        "F" | "fah" => 459.67,
+        _ => 0.0,
+    }
+}
+
+/// CONVERT(number, from_unit, to_unit)
+pub(super) fn convert(number: &Value, from: &Value, to: &Value) -> Value {
+    let (number, from, to) = match (number.as_number(), from.as_text(), to.as_text()) {
+        (Ok(number), Ok(from), Ok(to)) => (number, from, to),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return Value::Error(e),
+    };
+    let (Some((from, from_factor)), Some((to, to_factor))) = (resolve(&from), resolve(&to)) else {
+        return Value::Error(ErrorValue::NA);
+    };
+    if from.kind != to.kind {
+        return Value::Error(ErrorValue::NA);
+    }
+    let converted = match from.kind {
+        Kind::Temperature => {
+            let kelvin = (number + temperature_offset(from.name)) * from_factor;
+            kelvin / to_factor - temperature_offset(to.name)
+        }
+        _ => number * from_factor / to_factor,
+    };
+    Value::number(converted)
+}
+
+/// Rate of `code` in a rates table: the row dated latest on or before
+/// `date` (latest overall without a date), or the last undated row
+fn rate(rates: &Array, code: &str, date: Option<f64>) -> Option<f64> {
+    let mut best: Option<(f64, f64)> = None;
+    for row in rates.iter_rows() {
+        let (Value::Text(name), Value::Number(rate)) = (&row[0], &row[1]) else {
+            continue;
+        };
+        if !name.trim().eq_ignore_ascii_case(code) || *rate <= 0.0 {
+            continue;
+        }
+        let from = match row.get(2) {
+            Some(Value::Number(from)) => *from,
+            _ => f64::NEG_INFINITY,
+        };
+        if date.is_some_and(|date| from > date) {
+            continue;
+        }
+        if best.is_none_or(|(latest, _)| from >= latest) {
+            best = Some((from, *rate));
+        }
+    }
+    best.map(|(_, rate)| rate)
+}
+
+/// CURRENCY(value, from, to, [date]) against the workbook's rates table
+/// (see `workbook::currency`); `#N/A` without rates for both currencies
+pub(super) fn currency(
+    value: &Value,
+    from: &Value,
+    to: &Value,
+    date: Option<&Value>,
+    rates: Option<Value>,
+) -> Value {
+    let parsed = (
+        value.as_number(),
+        from.as_text(),
+        to.as_text(),
+        date.map(Value::as_number).transpose(),
+    );
+    let (value, from, to, date) = match parsed {
+        (Ok(value), Ok(from), Ok(to), Ok(date)) => (value, from, to, date),
+        (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => {
+            return Value::Error(e)
+        }
+    };
+    let (from, to) = (from.trim(), to.trim());
+    if from.eq_ignore_ascii_case(to) {
+        return Value::number(value);
+    }
+    let rates = match rates {
+        Some(Value::Array(rates)) if rates.cols() >= 2 => rates,
+        Some(Value::Error(e)) => return Value::Error(e),
+        _ => return Value::Error(ErrorValue::NA),
+    };
+    match (rate(&rates, from, date), rate(&rates, to, date)) {
+        (Some(from), Some(to)) => Value::number(value / from * to),
+        _ => Value::Error(ErrorValue::NA),
+    }
+}
+
+const NUMERALS: [(u32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// ROMAN(number): classic Roman numeral of a whole number from 0 to 3999;
+/// 0 gives empty text
+pub(super) fn roman(number: &Value) -> Value {
+    let mut n = match number.as_number() {
+        Ok(n) if (0.0..4000.0).contains(&n) => n.trunc() as u32,
+        Ok(_) => return Value::Error(ErrorValue::Value),
+        Err(e) => return Value::Error(e),
+    };
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    Value::Text(out)
+}
+
+/// ARABIC(text): value of a Roman numeral in any case, optionally negative;
+/// empty text gives 0
+pub(super) fn arabic(text: &Value) -> Value {
+    let text = match text.as_text() {
+        Ok(text) => text.trim().to_ascii_uppercase(),
+        Err(e) => return Value::Error(e),
+    };
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.as_str()),
+    };
+    let values: Option<Vec<f64>> = digits
+        .chars()
+        .map(|c| match c {
+            'I' => Some(1.0),
+            'V' => Some(5.0),
+            'X' => Some(10.0),
+            'L' => Some(50.0),
+            'C' => Some(100.0),
+            'D' => Some(500.0),
+            'M' => Some(1000.0),
+            _ => None,
+        })
+        .collect();
+    let Some(values) = values else {
+        return Value::Error(ErrorValue::Value);
+    };
+    // A numeral smaller than the one after it is subtracted (IV, XC)
+    let total: f64 = values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| match values.get(i + 1) {
+            Some(&next) if next > v => -v,
+            _ => v,
+        })
+        .sum();
+    Value::number(sign * total)
+}
+
+/// DATASIZE(bytes, [decimal], [digits]): a byte count in the largest unit
+/// that keeps it at 1 or more, such as `1.5 MiB`, or `1.6 MB` with decimal
+/// (1000-based) units
+pub(super) fn data_size(bytes: &Value, decimal: Option<&Value>, digits: Option<&Value>) -> Value {
+    let parsed = (
+        bytes.as_number(),
+        decimal.map_or(Ok(false), Value::as_bool),
+        digits.map_or(Ok(1.0), Value::as_number),
+    );
+    let (bytes, decimal, digits) = match parsed {
+        (Ok(bytes), Ok(decimal), Ok(digits)) if digits >= 0.0 => (bytes, decimal, digits),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return Value::Error(e),
+        _ => return Value::Error(ErrorValue::Num),
+    };
+    let (base, units) = match decimal {
+        true => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+        false => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+    };
+
+    let mut size = bytes;
+    let mut unit = 0;
+    while size.abs() >= base && unit + 1 < units.len() {
+        size /= base;
+        unit += 1;
+    }
+    let factor = 10f64.powi(digits.trunc().min(15.0) as i32);
+    let rounded = match unit {
+        0 => size,
+        _ => (size * factor).round() / factor,
+    };
+    Value::Text(format!("{} {}", format_number(rounded), units[unit]))
+}
+
+/// First character of an optional separator argument, `default` when omitted
+fn separator(value: Option<&Value>, default: char) -> Result<char, ErrorValue> {
+    match value {
+        None => Ok(default),
+        Some(value) => value.as_text()?.chars().next().ok_or(ErrorValue::Value),
+    }
+}
+
+/// NUMBERVALUE(text, [decimal_separator], [group_separator]): read a number
+/// written with other separators, e.g. `"1.234,5"` with `","` and `"."`
+///
+/// Separators default to `.` and `,`. Whitespace is ignored, group
+/// separators may only come before the decimal one, and each trailing `%`
+/// divides by 100.
+pub(super) fn number_value(text: &Value, decimal: Option<&Value>, group: Option<&Value>) -> Value {
+    if let Value::Number(n) = text {
+        return Value::Number(*n);
+    }
+    let (text, decimal, group) = match (
+        text.as_text(),
+        separator(decimal, '.'),
+        separator(group, ','),
+    ) {
+        (Ok(text), Ok(decimal), Ok(group)) if decimal != group => (text, decimal, group),
+        (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Value::Error(e),
+        _ => return Value::Error(ErrorValue::Value),
+    };
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let digits = compact.trim_end_matches('%');
+    let percents = (compact.len() - digits.len()) as i32;
+    let point = digits.find(decimal);
+    let misplaced_group = point.is_some_and(|at| digits[at..].contains(group));
+    if digits.matches(decimal).count() > 1 || misplaced_group {
+        return Value::Error(ErrorValue::Value);
+    }
+
+    let number: String = digits
+        .chars()
+        .filter(|&c| c != group)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+    let valid = number
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '+' | '-' | 'e' | 'E'));
+    match number.parse::<f64>() {
+        _ if number.is_empty() => Value::Number(0.0),
+        Ok(n) if valid => Value::number(n / 100f64.powi(percents)),
+        _ => Value::Error(ErrorValue::Value),
+    }
+}
+
+/// DATEVALUE(text, [format]): serial number of the date in text
+///
+/// `format` is a date format code such as `"dd/mm/yyyy"` or `"d mmm yyyy"`;
+/// without one the usual layouts are tried (ISO, US month/day/year, month
+/// names). Any time of day is dropped.
+pub(super) fn date_value(text: &Value, format: Option<&Value>) -> Value {
+    let text = match text {
+        Value::Number(n) => return Value::Number(n.floor()),
+        text => match text.as_text() {
+            Ok(text) => text,
+            Err(e) => return Value::Error(e),
+        },
+    };
+    let parsed = match format {
+        None => parse_datetime(&text),
+        Some(format) => {
+            let pattern = match format.as_text() {
+                Ok(code) => NumberFormat::parse(&code)
+                    .ok()
+                    .and_then(|f| f.date_pattern()),
+                Err(e) => return Value::Error(e),
+            };
+            pattern.and_then(|pattern| {
+                let text = text.trim();
+                NaiveDateTime::parse_from_str(text, &pattern)
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(text, &pattern)
+                            .map(|d| d.and_time(NaiveTime::MIN))
+                    })
+                    .ok()
+            })
+        }
+    };
+    match parsed {
+        Some(dt) => Value::Number(to_serial(dt).floor()),
+        None => Value::Error(ErrorValue::Value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let text = |s: &str| Value::Text(s.into());
+        let convert =
+            |n: f64, from: &str, to: &str| match convert(&Value::Number(n), &text(from), &text(to))
+            {
+                Value::Number(n) => Value::Number((n * 1e6).round() / 1e6),
+                other => other,
+            };
+        assert_eq!(convert(1.0, "mi", "km"), Value::Number(1.609344));
+        assert_eq!(convert(12.0, "in", "ft"), Value::Number(1.0));
+        assert_eq!(convert(1.0, "lbm", "g"), Value::Number(453.59237));
+        assert_eq!(convert(100.0, "C", "F"), Value::Number(212.0));
+        assert_eq!(convert(0.0, "K", "cel"), Value::Number(-273.15));
+        assert_eq!(convert(1.0, "Mibyte", "kibyte"), Value::Number(1024.0));
+        assert_eq!(convert(8.0, "kbit", "byte"), Value::Number(1000.0));
+        assert_eq!(convert(1.0, "m", "g"), Value::Error(ErrorValue::NA));
+        assert_eq!(convert(1.0, "kim", "m"), Value::Error(ErrorValue::NA));
+        assert_eq!(convert(1.0, "M", "m"), Value::Error(ErrorValue::NA));
+
+        assert_eq!(roman(&Value::Number(1994.0)), text("MCMXCIV"));
+        assert_eq!(
+            roman(&Value::Number(4000.0)),
+            Value::Error(ErrorValue::Value)
+        );
+        assert_eq!(arabic(&text("mcmxciv")), Value::Number(1994.0));
+        assert_eq!(arabic(&text("-XL")), Value::Number(-40.0));
+        assert_eq!(arabic(&text("XZ")), Value::Error(ErrorValue::Value));
+
+        let size = |bytes: f64, decimal: bool| {
+            data_size(&Value::Number(bytes), Some(&Value::Bool(decimal)), None)
+        };
+        assert_eq!(size(512.0, false), text("512 B"));
+        assert_eq!(size(1_572_864.0, false), text("1.5 MiB"));
+        assert_eq!(size(1_572_864.0, true), text("1.6 MB"));
+        assert_eq!(size(2048.0, false), text("2 KiB"));
+    }
+
+    #[test]
+    fn test_number_and_date_values() {
+        let text = |s: &str| Value::Text(s.into());
+        let number = |s: &str, decimal: &str, group: &str| {
+            number_value(&text(s), Some(&text(decimal)), Some(&text(group)))
+        };
+        assert_eq!(number("1.234,5", ",", "."), Value::Number(1234.5));
+        assert_eq!(number(" 1 234 567 ", ",", " "), Value::Number(1234567.0));
+        assert_eq!(number("2,5%", ",", "."), Value::Number(0.025));
+        assert_eq!(number("", ",", "."), Value::Number(0.0));
+        assert_eq!(number("1,2,3", ",", "."), Value::Error(ErrorValue::Value));
+        assert_eq!(number("1,5.000", ",", "."), Value::Error(ErrorValue::Value));
+        assert_eq!(number("1.5", ".", "."), Value::Error(ErrorValue::Value));
+        assert_eq!(
+            number_value(&text("1,000.25"), None, None),
+            Value::Number(1000.25)
+        );
+
+        let date = |s: &str, format: &str| date_value(&text(s), Some(&text(format)));
+        assert_eq!(date("05/03/2024", "dd/mm/yyyy"), Value::Number(45356.0));
+        assert_eq!(date("5 mar 2024", "d mmm yyyy"), Value::Number(45356.0));
+        assert_eq!(
+            date("05.03.24 18:30", "dd.mm.yy hh:mm"),
+            Value::Number(45356.0)
+        );
+        assert_eq!(date("2024-03-05", "0.00"), Value::Error(ErrorValue::Value));
+        assert_eq!(
+            date("13/13/2024", "dd/mm/yyyy"),
+            Value::Error(ErrorValue::Value)
+        );
+        assert_eq!(
+            date_value(&text("2024-03-05"), None),
+            Value::Number(45356.0)
+        );
+    }
+}
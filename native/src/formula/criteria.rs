@@ -0,0 +1,175 @@
+//! Criteria for the lookup and counting functions
+//!
+//! `COUNTIF` criteria may start with a comparison (`">=10"`, `"<>done"`);
+//! `MATCH` and `VLOOKUP` look up their value as is. Text is compared
+//! case-insensitively against the whole cell, and when testing for equality
+//! `*` stands for any run of characters and `?` for one character; `~*`,
+//! `~?` and `~~` stand for the literal characters. With the regex option the
+//! text is a regular expression (Rust `regex` syntax) that must match the
+//! whole cell instead.
+
+use std::cmp::Ordering;
+
+use regex::{Regex, RegexBuilder};
+
+use super::value::{ErrorValue, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Criterion {
+    comparison: Comparison,
+    operand: Value,
+    /// Text operand of an equality test, compiled
+    pattern: Option<Regex>,
+}
+
+/// Regex matching the whole of a wildcard pattern such as `INV-??*`
+fn wildcard_regex(pattern: &str) -> String {
+    let mut out = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            '~' => match chars.next() {
+                Some(escaped) => out.push_str(&regex::escape(&escaped.to_string())),
+                None => out.push('~'),
+            },
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+fn compile(text: &str, regex: bool) -> Result<Regex, ErrorValue> {
+    let pattern = match regex {
+        true => format!("^(?:{})$", text),
+        false => wildcard_regex(text),
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .dot_matches_new_line(true)
+        .build()
+        .map_err(|_| ErrorValue::Value)
+}
+
+fn same_kind(a: &Value, b: &Value) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+impl Criterion {
+    /// Equality with a lookup value, as `MATCH` and `VLOOKUP` use
+    pub(super) fn equal_to(value: &Value, regex: bool) -> Result<Criterion, ErrorValue> {
+        let pattern = match value {
+            Value::Text(text) => Some(compile(text, regex)?),
+            Value::Error(e) => return Err(*e),
+            Value::Array(_) => return Err(ErrorValue::Value),
+            _ => None,
+        };
+        Ok(Criterion {
+            comparison: Comparison::Equal,
+            operand: value.clone(),
+            pattern,
+        })
+    }
+
+    /// `COUNTIF` criterion: a value, or text with an optional leading comparison
+    pub(super) fn parse(value: &Value, regex: bool) -> Result<Criterion, ErrorValue> {
+        let Value::Text(text) = value else {
+            return Criterion::equal_to(value, regex);
+        };
+        let (comparison, rest) = [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            ("<>", Comparison::NotEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(prefix, comparison)| Some((comparison, text.strip_prefix(prefix)?)))
+        .unwrap_or((Comparison::Equal, text));
+
+        let operand = match Value::from_cell(rest) {
+            Value::Text(_) => Value::Text(rest.to_string()),
+            operand => operand,
+        };
+        let equality = matches!(comparison, Comparison::Equal | Comparison::NotEqual);
+        let pattern = match &operand {
+            Value::Text(text) if equality => Some(compile(text, regex)?),
+            _ => None,
+        };
+        Ok(Criterion {
+            comparison,
+            operand,
+            pattern,
+        })
+    }
+
+    fn equals(&self, value: &Value) -> bool {
+        match (&self.pattern, value) {
+            (Some(pattern), Value::Text(text)) => pattern.is_match(text),
+            (Some(_), _) => false,
+            (None, Value::Text(text)) if self.operand.is_blank() => text.is_empty(),
+            (None, value) => *value == self.operand,
+        }
+    }
+
+    pub(super) fn matches(&self, value: &Value) -> bool {
+        let ordering = || {
+            same_kind(&self.operand, value)
+                .then(|| value.compare(&self.operand))
+                .filter(|_| !value.is_blank())
+        };
+        match self.comparison {
+            Comparison::Equal => self.equals(value),
+            Comparison::NotEqual => !self.equals(value),
+            Comparison::Less => ordering().is_some_and(Ordering::is_lt),
+            Comparison::LessOrEqual => ordering().is_some_and(Ordering::is_le),
+            Comparison::Greater => ordering().is_some_and(Ordering::is_gt),
+            Comparison::GreaterOrEqual => ordering().is_some_and(Ordering::is_ge),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_criteria() {
+        let text = |s: &str| Value::Text(s.into());
+        let matches = |criterion: &str, value: Value| {
+            Criterion::parse(&text(criterion), false)
+                .unwrap()
+                .matches(&value)
+        };
+        assert!(matches("inv-??*", text("INV-0042")));
+        assert!(!matches("inv-??*", text("INV-4")));
+        assert!(matches("100~%~*", text("100%*")));
+        assert!(!matches("100~%~*", text("100%x")));
+        assert!(matches(">=10", Value::Number(10.0)));
+        assert!(!matches(">=10", text("abc")));
+        assert!(matches("<>done", text("open")));
+        assert!(matches("<>", Value::Number(0.0)) && !matches("<>", Value::Blank));
+        assert!(matches("", Value::Blank) && matches("=", text("")));
+        assert!(matches("<b", text("Apple")));
+
+        let regex = Criterion::parse(&text("(inv|po)-\\d+"), true).unwrap();
+        assert!(regex.matches(&text("PO-17")) && !regex.matches(&text("PO-17x")));
+        assert_eq!(
+            Criterion::parse(&text("("), true).unwrap_err(),
+            ErrorValue::Value
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Cells a formula reads, for dependency tracking
+//!
+//! References are reported as written: names (column headers or defined
+//! names) are left for the caller to resolve since only it knows the table.
+
+use std::ops::RangeInclusive;
+
+use super::parser::Expr;
+
+/// What a formula reads; `sheet` is `None` for the formula's own sheet
+#[derive(Debug, Clone, PartialEq)]
+pub enum Precedent {
+    /// Block of cells; whole columns run to `usize::MAX`
+    Area {
+        sheet: Option<String>,
+        rows: RangeInclusive<usize>,
+        cols: RangeInclusive<usize>,
+    },
+    /// Bare or bracketed name
+    Name { sheet: Option<String>, name: String },
+    /// `reference` on every sheet from `first` to `last`
+    Sheets {
+        first: String,
+        last: String,
+        reference: Box<Precedent>,
+    },
+}
+
+fn reference(expr: &Expr, sheet: Option<&str>) -> Option<Precedent> {
+    let sheet = sheet.map(str::to_string);
+    match expr {
+        Expr::Cell(cell) | Expr::Spill(cell) => Some(Precedent::Area {
+            sheet,
+            rows: cell.row..=cell.row,
+            cols: cell.col..=cell.col,
+        }),
+        Expr::Range(start, end) => Some(Precedent::Area {
+            sheet,
+            rows: start.row.min(end.row)..=start.row.max(end.row),
+            cols: start.col.min(end.col)..=start.col.max(end.col),
+        }),
+        Expr::ColumnRange(start, end) => Some(Precedent::Area {
+            sheet,
+            rows: 0..=usize::MAX,
+            cols: start.col.min(end.col)..=start.col.max(end.col),
+        }),
+        Expr::Name(name) | Expr::Column(name) => Some(Precedent::Name {
+            sheet,
+            name: name.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Every reference in `expr`, in the order they appear
+pub fn precedents(expr: &Expr) -> Vec<Precedent> {
+    let mut found = Vec::new();
+    expr.visit(&mut |node| {
+        let precedent = match node {
+            Expr::Sheet(sheet, inner) => reference(inner, Some(sheet)),
+            Expr::Sheets(first, last, inner) => {
+                reference(inner, None).map(|inner| Precedent::Sheets {
+                    first: first.clone(),
+                    last: last.clone(),
+                    reference: Box::new(inner),
+                })
+            }
+            node => reference(node, None),
+        };
+        found.extend(precedent);
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::parse;
+
+    #[test]
+    fn test_precedents() {
+        let expr = parse("=IF(A1 > 0, SORT(B2:C3), Data!D:D) + Amount").unwrap();
+        assert_eq!(
+            precedents(&expr),
+            [
+                Precedent::Area {
+                    sheet: None,
+                    rows: 0..=0,
+                    cols: 0..=0
+                },
+                Precedent::Area {
+                    sheet: None,
+                    rows: 1..=2,
+                    cols: 1..=2
+                },
+                Precedent::Area {
+                    sheet: Some("Data".into()),
+                    rows: 0..=usize::MAX,
+                    cols: 3..=3
+                },
+                Precedent::Name {
+                    sheet: None,
+                    name: "Amount".into()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Positioned problems in a formula, for inline squiggles in the formula bar
+//!
+//! A syntax error is reported with its code and range. The tokens are then
+//! checked whether or not the formula parses: calls to unknown functions or
+//! with the wrong number of arguments are errors, and, given a [`Scope`],
+//! names, columns and sheets that do not exist are warnings. Unknown words
+//! come with the closest known spelling as a suggestion.
+
+use std::ops::Range;
+use std::os::raw::c_char;
+
+use super::catalog::{lookup, FUNCTIONS};
+use super::complete::Scope;
+use super::highlight::{classify, HighlightKind};
+use super::lexer::{tokenize_lenient, Token, TokenKind};
+use super::parser::parse_detailed;
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Kebab-case identifier such as `unknown-function`
+    pub code: &'static str,
+    pub message: String,
+    /// Byte range in the formula text
+    pub span: Range<usize>,
+    /// Replacement for the text in `span`, e.g. the closest function name
+    pub suggestion: Option<String>,
+}
+
+/// Levenshtein distance, ignoring case
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Closest candidate within a third of the word's length (at least one edit)
+fn suggest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let limit = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(word, candidate), candidate))
+        .filter(|&(d, _)| d <= limit)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Arguments of the call whose `(` is at `open`, `None` when it is not closed
+fn argument_count(tokens: &[Token], open: usize) -> Option<usize> {
+    let (mut depth, mut commas, mut empty) = (0usize, 0, true);
+    for token in &tokens[open + 1..] {
+        match token.kind {
+            TokenKind::RParen if depth == 0 => {
+                return Some(if empty { 0 } else { commas + 1 });
+            }
+            TokenKind::RParen => depth -= 1,
+            TokenKind::LParen => depth += 1,
+            TokenKind::Comma if depth == 0 => commas += 1,
+            _ => {}
+        }
+        empty = false;
+    }
+    None
+}
+
+fn check_call(tokens: &[Token], i: usize, name: &str, out: &mut Vec<Diagnostic>) {
+    let span = tokens[i].span.clone();
+    let Some(function) = lookup(name) else {
+        let suggestion = suggest(name, FUNCTIONS.iter().map(|f| f.name));
+        let message = match &suggestion {
+            Some(known) => format!("Unknown function '{}'; did you mean {}?", name, known),
+            None => format!("Unknown function '{}'", name),
+        };
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            code: "unknown-function",
+            message,
+            span,
+            suggestion,
+        });
+        return;
+    };
+
+    let Some(count) = argument_count(tokens, i + 1) else {
+        return;
+    };
+    let (min, max) = (function.min_args(), function.max_args());
+    if count < min || max.is_some_and(|max| count > max) {
+        let expected = match max {
+            Some(max) if max == min => format!("{}", min),
+            Some(max) => format!("{} to {}", min, max),
+            None => format!("at least {}", min),
+        };
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            code: "argument-count",
+            message: format!(
+                "{} takes {} argument(s), got {}",
+                function.name, expected, count
+            ),
+            span,
+            suggestion: None,
+        });
+    }
+}
+
+fn unknown(
+    code: &'static str,
+    what: &str,
+    word: &str,
+    span: Range<usize>,
+    known: &[String],
+) -> Diagnostic {
+    let suggestion = suggest(word, known.iter().map(String::as_str));
+    let message = match &suggestion {
+        Some(close) => format!("Unknown {} '{}'; did you mean {}?", what, word, close),
+        None => format!("Unknown {} '{}'", what, word),
+    };
+    Diagnostic {
+        severity: Severity::Warning,
+        code,
+        message,
+        span,
+        suggestion,
+    }
+}
+
+/// Every problem found in `src`, in text order
+pub fn diagnose(src: &str, scope: Option<&Scope>) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    if let Err(error) = parse_detailed(src) {
+        let suggestion = match error.code {
+            "unterminated-string" => Some(format!("{}\"", &src[error.span.clone()])),
+            "unterminated-column" => Some(format!("{}]", &src[error.span.clone()])),
+            _ => None,
+        };
+        found.push(Diagnostic {
+            severity: Severity::Error,
+            code: error.code,
+            message: error.message,
+            span: error.span,
+            suggestion,
+        });
+    }
+
+    let tokens = tokenize_lenient(src);
+    let names: Vec<String> = scope
+        .map(|scope| scope.columns.iter().chain(&scope.names).cloned().collect())
+        .unwrap_or_default();
+    for (i, token) in tokens.iter().enumerate() {
+        let span = token.span.clone();
+        let qualified = i > 0 && matches!(tokens[i - 1].kind, TokenKind::Sheet(_));
+        match (&token.kind, classify(&tokens, i)) {
+            (TokenKind::Ident(name), HighlightKind::Function) => {
+                check_call(&tokens, i, name, &mut found)
+            }
+            (TokenKind::Ident(name), HighlightKind::Name)
+                if !qualified
+                    && scope.is_some()
+                    && !names.iter().any(|n| n.eq_ignore_ascii_case(name)) =>
+            {
+                found.push(unknown("unknown-name", "name", name, span, &names));
+            }
+            (TokenKind::Column(name), _) if !qualified => {
+                if let Some(scope) = scope {
+                    if !scope.columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+                        found.push(unknown(
+                            "unknown-column",
+                            "column",
+                            name,
+                            span,
+                            &scope.columns,
+                        ));
+                    }
+                }
+            }
+            (TokenKind::Sheet(sheet) | TokenKind::Ident(sheet), HighlightKind::Sheet) => {
+                if let Some(scope) = scope.filter(|scope| !scope.sheets.is_empty()) {
+                    let sheets: Vec<String> = scope.sheets.iter().map(|(s, _)| s.clone()).collect();
+                    // A quoted 3-D span names two sheets
+                    for sheet in sheet.split(':') {
+                        if !sheets.iter().any(|s| s.eq_ignore_ascii_case(sheet)) {
+                            let span = span.clone();
+                            found.push(unknown("unknown-sheet", "sheet", sheet, span, &sheets));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    found.sort_by_key(|d| (d.span.start, d.span.end));
+    found
+}
+
+/// Diagnostics as JSON: `[{severity, code, message, start, end, char_start,
+/// char_end, suggestion}]`
+pub fn diagnostics_to_json(src: &str, diagnostics: &[Diagnostic]) -> String {
+    let chars = |byte: usize| src[..byte].chars().count();
+    let list: Vec<serde_json::Value> = diagnostics
+        .iter()
+        .map(|d| {
+            serde_json::json!({
+                "severity": match d.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                "code": d.code,
+                "message": d.message,
+                "start": d.span.start,
+                "end": d.span.end,
+                "char_start": chars(d.span.start),
+                "char_end": chars(d.span.end),
+                "suggestion": d.suggestion,
+            })
+        })
+        .collect();
+    serde_json::Value::from(list).to_string()
+}
+
+/// Problems in a formula written against a table
+///
+/// # Arguments
+/// * `table` - Table whose columns the formula may use, or null to skip
+///   name checks
+/// * `formula` - Formula text
+///
+/// # Returns
+/// StringResult with a JSON array of `{severity, code, message, start, end,
+/// char_start, char_end, suggestion}` (empty when the formula is fine);
+/// `start`/`end` are byte offsets, `char_*` character offsets
+///
+/// # Safety
+/// `table` must be null or a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_diagnose_formula(
+    table: *const TesseraTable,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    let scope = table_arg(table).map(Scope::for_table);
+    let diagnostics = diagnose(formula, scope.as_ref());
+    StringResult::success(&diagnostics_to_json(formula, &diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let codes = |src: &str,
+                     scope: Option<&Scope>|
+         -> Vec<(&'static str, Range<usize>, Option<String>)> {
+            diagnose(src, scope)
+                .into_iter()
+                .map(|d| (d.code, d.span, d.suggestion))
+                .collect()
+        };
+        assert!(codes("=IF(A1 > 1, \"a\", LEN(B2))", None).is_empty());
+        assert_eq!(
+            codes("=UPPR(A1) + IF(1)", None),
+            [
+                ("unknown-function", 1..5, Some("UPPER".into())),
+                ("argument-count", 12..14, None),
+            ]
+        );
+        assert_eq!(
+            codes("=LEN(\"abc", None),
+            [("unterminated-string", 5..9, Some("\"abc\"".into()))]
+        );
+        assert_eq!(codes("=(1 + 2", None)[0].0, "expected-token");
+
+        let scope = Scope {
+            columns: vec!["Amount".into(), "Region".into()],
+            names: vec!["Rate".into()],
+            sheets: vec![("Data".into(), vec![])],
+        };
+        assert_eq!(
+            codes("=Amont * rate + [Regin] + Dta!A1", Some(&scope)),
+            [
+                ("unknown-name", 1..6, Some("Amount".into())),
+                ("unknown-column", 16..23, Some("Region".into())),
+                ("unknown-sheet", 26..30, Some("Data".into())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Expression evaluation
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::functions;
+use super::parser::{BinaryOp, CellRef, Expr, UnaryOp};
+use super::value::{Array, ErrorValue, Value};
+use super::volatile;
+
+/// Supplies the values that names and references in a formula point at
+pub trait EvalContext {
+    /// Value of a bare or bracketed name (column header, defined name)
+    ///
+    /// `None` means the name is unknown and evaluates to `#NAME?`.
+    fn name(&self, _name: &str) -> Option<Value> {
+        None
+    }
+
+    /// Value of a single cell; contexts without a grid return `#REF!`
+    fn cell(&self, _cell: &CellRef) -> Value {
+        Value::Error(ErrorValue::Ref)
+    }
+
+    /// Used rows and columns; ranges are clamped to this extent so
+    /// references like `A:A` or `A1:Z100000` stay proportional to the data
+    fn extent(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    /// Every value spilled by the array formula at `cell` (`A1#`); `#REF!`
+    /// when the cell holds no array formula
+    fn spill(&self, _cell: &CellRef) -> Value {
+        Value::Error(ErrorValue::Ref)
+    }
+
+    /// Context for another sheet of the same workbook, `None` when there is
+    /// no such sheet (references to it evaluate to `#REF!`)
+    fn sheet(&self, _name: &str) -> Option<Box<dyn EvalContext + '_>> {
+        None
+    }
+
+    /// Names of the sheets from `first` to `last` in workbook order, `None`
+    /// when either does not exist
+    fn sheet_span(&self, _first: &str, _last: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Exchange rates table read by `CURRENCY()`, `None` when the host has
+    /// not registered one
+    fn currency_rates(&self) -> Option<Value> {
+        None
+    }
+
+    /// Called with every evaluated node and its value, children first;
+    /// only tracing contexts care
+    fn record(&self, _expr: &Expr, _value: &Value) {}
+
+    /// Serial date-time returned by `NOW()`
+    fn now(&self) -> f64 {
+        volatile::now()
+    }
+
+    /// Next number in `[0, 1)` for `RAND()` and `RANDBETWEEN()`
+    fn random(&self) -> f64 {
+        volatile::random()
+    }
+
+    /// Values of the block between two corners (inclusive, any order)
+    fn range(&self, start: &CellRef, end: &CellRef) -> Value {
+        let (rows, cols) = self.extent();
+        let first_row = start.row.min(end.row);
+        let first_col = start.col.min(end.col);
+        let last_row = start
+            .row
+            .max(end.row)
+            .min(rows.saturating_sub(1).max(first_row));
+        let last_col = start
+            .col
+            .max(end.col)
+            .min(cols.saturating_sub(1).max(first_col));
+
+        let mut values =
+            Vec::with_capacity((last_row - first_row + 1) * (last_col - first_col + 1));
+        for row in first_row..=last_row {
+            for col in first_col..=last_col {
+                values.push(self.cell(&CellRef {
+                    row,
+                    col,
+                    row_absolute: false,
+                    col_absolute: false,
+                }));
+            }
+        }
+        Value::Array(Array::new(
+            last_row - first_row + 1,
+            last_col - first_col + 1,
+            values,
+        ))
+    }
+}
+
+/// Evaluate an expression to a single value
+pub fn evaluate(expr: &Expr, ctx: &dyn EvalContext) -> Value {
+    let value = evaluate_node(expr, ctx);
+    ctx.record(expr, &value);
+    value
+}
+
+fn evaluate_node(expr: &Expr, ctx: &dyn EvalContext) -> Value {
+    match expr {
+        Expr::Number(n) => Value::Number(*n),
+        Expr::Text(text) => Value::Text(text.clone()),
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Error(e) => Value::Error(*e),
+        Expr::Name(name) | Expr::Column(name) => {
+            ctx.name(name).unwrap_or(Value::Error(ErrorValue::Name))
+        }
+        Expr::Cell(cell) => ctx.cell(cell),
+        Expr::Spill(cell) => ctx.spill(cell),
+        Expr::Range(start, end) => ctx.range(start, end),
+        Expr::ColumnRange(start, end) => {
+            let last_row = ctx.extent().0.saturating_sub(1);
+            let corner = |col, row| CellRef {
+                row,
+                col,
+                row_absolute: false,
+                col_absolute: false,
+            };
+            ctx.range(&corner(start.col, 0), &corner(end.col, last_row))
+        }
+        Expr::Sheet(name, reference) => match ctx.sheet(name) {
+            Some(sheet) => evaluate(reference, sheet.as_ref()),
+            None => Value::Error(ErrorValue::Ref),
+        },
+        Expr::Sheets(first, last, reference) => {
+            // One column holding every sheet's values in turn, since ranges
+            // clamped to each sheet's extent need not have the same shape
+            let mut values = Vec::new();
+            for name in ctx.sheet_span(first, last).unwrap_or_default() {
+                let Some(sheet) = ctx.sheet(&name) else {
+                    return Value::Error(ErrorValue::Ref);
+                };
+                match evaluate(reference, sheet.as_ref()) {
+                    Value::Array(array) => values.extend_from_slice(array.values()),
+                    value => values.push(value),
+                }
+            }
+            if values.is_empty() {
+                return Value::Error(ErrorValue::Ref);
+            }
+            Value::Array(Array::new(values.len(), 1, values))
+        }
+        Expr::Unary(op, operand) => lift_unary(evaluate(operand, ctx), |v| unary(*op, v)),
+        Expr::Binary(op, left, right) => {
+            lift_binary(evaluate(left, ctx), evaluate(right, ctx), |a, b| {
+                binary(*op, a, b)
+            })
+        }
+        Expr::Call(name, args) => functions::call(name, args, ctx),
+    }
+}
+
+/// One evaluated subexpression of a traced formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// 0 for the whole formula, 1 for its operands or arguments, ...
+    pub depth: usize,
+    /// The subexpression as formula text
+    pub expression: String,
+    pub value: Value,
+}
+
+/// Records the nodes of one tree while delegating to the real context
+struct Tracer<'a> {
+    inner: &'a dyn EvalContext,
+    depths: HashMap<*const Expr, usize>,
+    steps: RefCell<Vec<TraceStep>>,
+}
+
+impl EvalContext for Tracer<'_> {
+    fn name(&self, name: &str) -> Option<Value> {
+        self.inner.name(name)
+    }
+
+    fn cell(&self, cell: &CellRef) -> Value {
+        self.inner.cell(cell)
+    }
+
+    fn spill(&self, cell: &CellRef) -> Value {
+        self.inner.spill(cell)
+    }
+
+    fn extent(&self) -> (usize, usize) {
+        self.inner.extent()
+    }
+
+    fn sheet(&self, name: &str) -> Option<Box<dyn EvalContext + '_>> {
+        self.inner.sheet(name)
+    }
+
+    fn sheet_span(&self, first: &str, last: &str) -> Option<Vec<String>> {
+        self.inner.sheet_span(first, last)
+    }
+
+    fn range(&self, start: &CellRef, end: &CellRef) -> Value {
+        self.inner.range(start, end)
+    }
+
+    fn currency_rates(&self) -> Option<Value> {
+        self.inner.currency_rates()
+    }
+
+    fn now(&self) -> f64 {
+        self.inner.now()
+    }
+
+    fn random(&self) -> f64 {
+        self.inner.random()
+    }
+
+    fn record(&self, expr: &Expr, value: &Value) {
+        // Defined names evaluate trees of their own; only this one is traced
+        if let Some(&depth) = self.depths.get(&(expr as *const Expr)) {
+            self.steps.borrow_mut().push(TraceStep {
+                depth,
+                expression: expr.to_string(),
+                value: value.clone(),
+            });
+        }
+    }
+}
+
+/// Evaluate `expr` and list every subexpression that was evaluated, in
+/// evaluation order (operands before the operation using them)
+///
+/// Branches skipped by `IF` and friends do not appear, and a node evaluated
+/// more than once appears each time.
+pub fn trace(expr: &Expr, ctx: &dyn EvalContext) -> (Value, Vec<TraceStep>) {
+    fn depths(expr: &Expr, depth: usize, out: &mut HashMap<*const Expr, usize>) {
+        out.insert(expr as *const Expr, depth);
+        match expr {
+            Expr::Unary(_, operand) => depths(operand, depth + 1, out),
+            Expr::Binary(_, left, right) => {
+                depths(left, depth + 1, out);
+                depths(right, depth + 1, out);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| depths(arg, depth + 1, out)),
+            _ => {}
+        }
+    }
+
+    let mut tracer = Tracer {
+        inner: ctx,
+        depths: HashMap::new(),
+        steps: RefCell::new(Vec::new()),
+    };
+    depths(expr, 0, &mut tracer.depths);
+    let value = evaluate(expr, &tracer);
+    (value, tracer.steps.into_inner())
+}
+
+/// Apply a scalar operation to every element of an array operand
+fn lift_unary(value: Value, f: impl Fn(Value) -> Value) -> Value {
+    match value {
+        Value::Array(array) => Value::Array(Array::new(
+            array.rows(),
+            array.cols(),
+            array.values().iter().cloned().map(f).collect(),
+        )),
+        value => f(value),
+    }
+}
+
+/// Apply a scalar operation elementwise, broadcasting scalars and single
+/// rows/columns like spreadsheet array formulas; positions missing from a
+/// smaller operand evaluate to `#N/A`
+fn lift_binary(left: Value, right: Value, f: impl Fn(Value, Value) -> Value) -> Value {
+    fn dims(value: &Value) -> (usize, usize) {
+        match value {
+            Value::Array(a) => (a.rows(), a.cols()),
+            _ => (1, 1),
+        }
+    }
+    fn element(value: &Value, row: usize, col: usize) -> Value {
+        match value {
+            Value::Array(a) => {
+                let r = if a.rows() == 1 { 0 } else { row };
+                let c = if a.cols() == 1 { 0 } else { col };
+                a.get(r, c).cloned().unwrap_or(Value::Error(ErrorValue::NA))
+            }
+            scalar => scalar.clone(),
+        }
+    }
+
+    if !matches!(left, Value::Array(_)) && !matches!(right, Value::Array(_)) {
+        return f(left, right);
+    }
+
+    let (left_rows, left_cols) = dims(&left);
+    let (right_rows, right_cols) = dims(&right);
+    let (rows, cols) = (left_rows.max(right_rows), left_cols.max(right_cols));
+    let mut values = Vec::with_capacity(rows * cols);
+    for row in 0..rows {
+        for col in 0..cols {
+            values.push(f(element(&left, row, col), element(&right, row, col)));
+        }
+    }
+    Value::Array(Array::new(rows, cols, values))
+}
+
+fn unary(op: UnaryOp, value: Value) -> Value {
+    match op {
+        UnaryOp::Plus => value,
+        UnaryOp::Neg => numeric(value.as_number().map(|n| -n)),
+        UnaryOp::Percent => numeric(value.as_number().map(|n| n / 100.0)),
+        UnaryOp::Not => match value.as_bool() {
+            Ok(b) => Value::Bool(!b),
+            Err(e) => Value::Error(e),
+        },
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Value {
+    match op {
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow => {
+            let (a, b) = match (left.as_number(), right.as_number()) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+            };
+            match op {
+                BinaryOp::Add => Value::number(a + b),
+                BinaryOp::Sub => Value::number(a - b),
+                BinaryOp::Mul => Value::number(a * b),
+                BinaryOp::Div if b == 0.0 => Value::Error(ErrorValue::Div0),
+                BinaryOp::Div => Value::number(a / b),
+                _ if a == 0.0 && b < 0.0 => Value::Error(ErrorValue::Div0),
+                _ => Value::number(a.powf(b)),
+            }
+        }
+        BinaryOp::Concat => match (left.as_text(), right.as_text()) {
+            (Ok(a), Ok(b)) => Value::Text(a + &b),
+            (Err(e), _) | (_, Err(e)) => Value::Error(e),
+        },
+        BinaryOp::And | BinaryOp::Or => match (left.as_bool(), right.as_bool()) {
+            (Ok(a), Ok(b)) if op == BinaryOp::And => Value::Bool(a && b),
+            (Ok(a), Ok(b)) => Value::Bool(a || b),
+            (Err(e), _) | (_, Err(e)) => Value::Error(e),
+        },
+        _ => {
+            if let Value::Error(e) = left {
+                return Value::Error(e);
+            }
+            if let Value::Error(e) = right {
+                return Value::Error(e);
+            }
+            let ordering = left.compare(&right);
+            Value::Bool(match op {
+                BinaryOp::Eq => ordering.is_eq(),
+                BinaryOp::Ne => ordering.is_ne(),
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+    }
+}
+
+pub(super) fn numeric(result: Result<f64, ErrorValue>) -> Value {
+    match result {
+        Ok(n) => Value::number(n),
+        Err(e) => Value::Error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+    use super::*;
+
+    struct Names;
+
+    impl EvalContext for Names {
+        fn name(&self, name: &str) -> Option<Value> {
+            match name {
+                "Amount" => Some(Value::Number(150.0)),
+                "Region" => Some(Value::Text("eu".into())),
+                _ => None,
+            }
+        }
+    }
+
+    fn eval(src: &str) -> Value {
+        evaluate(&parse(src).unwrap(), &Names)
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(eval("=-2^2 + 10%"), Value::Number(4.1));
+        assert_eq!(eval("=\"n=\" & 1.50"), Value::Text("n=1.5".into()));
+        assert_eq!(eval("Amount > 100 AND Region = \"EU\""), Value::Bool(true));
+        assert_eq!(eval("NOT Amount > 100 OR FALSE"), Value::Bool(false));
+        assert_eq!(eval("1/0"), Value::Error(ErrorValue::Div0));
+        assert_eq!(eval("\"x\" + 1"), Value::Error(ErrorValue::Value));
+        assert_eq!(eval("Missing = 1"), Value::Error(ErrorValue::Name));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            eval("IF(Amount > 200, 1/0, \"ok\")"),
+            Value::Text("ok".into())
+        );
+        assert_eq!(eval("IFERROR(1/0, -1)"), Value::Number(-1.0));
+        assert_eq!(eval("ROUND(2.345, 2)"), Value::Number(2.35));
+        assert_eq!(eval("ISNUMBER(SEARCH(\"U\", Region))"), Value::Bool(true));
+        assert_eq!(eval("AND(TRUE, 1, Amount)"), Value::Bool(true));
+        assert_eq!(eval("NOPE(1)"), Value::Error(ErrorValue::Name));
+    }
+}
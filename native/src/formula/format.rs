@@ -0,0 +1,187 @@
+//! Pretty-printing formulas for the "format formula" command
+//!
+//! The formula is parsed and written back from the tree, so spacing becomes
+//! uniform (one space around binary operators and after commas), function
+//! names and references are upper-cased and redundant parentheses go away.
+//! With a width limit, calls that do not fit on their line are broken with
+//! one indented argument per line.
+
+use std::os::raw::c_char;
+
+use super::parser::{parse, quote_sheet, Expr, UnaryOp};
+use crate::ffi::str_arg;
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Longest line before calls are broken; 0 keeps the formula on one line
+    pub max_width: usize,
+    /// Spaces per nesting level of a broken call
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            max_width: 0,
+            indent: 4,
+        }
+    }
+}
+
+struct Printer {
+    options: FormatOptions,
+}
+
+impl Printer {
+    fn operand(&self, expr: &Expr, min: u8, depth: usize, column: usize) -> String {
+        if expr.precedence() < min {
+            format!("({})", self.expr(expr, depth, column + 1))
+        } else {
+            self.expr(expr, depth, column)
+        }
+    }
+
+    /// `expr` starting at `column` of a line indented `depth` levels
+    fn expr(&self, expr: &Expr, depth: usize, column: usize) -> String {
+        match expr {
+            Expr::Sheet(name, reference) => {
+                format!("{}!{}", quote_sheet(name), reference)
+            }
+            Expr::Unary(UnaryOp::Percent, operand) => {
+                format!("{}%", self.operand(operand, 10, depth, column))
+            }
+            Expr::Unary(op, operand) => {
+                let (prefix, min) = match op {
+                    UnaryOp::Not => ("NOT ", 3),
+                    UnaryOp::Plus => ("+", 9),
+                    _ => ("-", 9),
+                };
+                let operand = self.operand(operand, min, depth, column + prefix.len());
+                format!("{}{}", prefix, operand)
+            }
+            Expr::Binary(op, left, right) => {
+                let symbol = format!(" {} ", op.symbol().trim());
+                let left = self.operand(left, op.precedence(), depth, column);
+                let column = last_line_width(&left, column) + symbol.len();
+                let right = self.operand(right, op.precedence() + 1, depth, column);
+                format!("{}{}{}", left, symbol, right)
+            }
+            Expr::Call(name, args) => {
+                let flat = self.flat(expr);
+                let width = self.options.max_width;
+                if width == 0 || args.is_empty() || column + flat.chars().count() <= width {
+                    return flat;
+                }
+                let inner = " ".repeat((depth + 1) * self.options.indent);
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| format!("{}{}", inner, self.expr(arg, depth + 1, inner.len())))
+                    .collect();
+                let outer = " ".repeat(depth * self.options.indent);
+                format!("{}(\n{}\n{})", name, args.join(",\n"), outer)
+            }
+            _ => expr.to_string(),
+        }
+    }
+
+    /// `expr` on a single line
+    fn flat(&self, expr: &Expr) -> String {
+        let printer = Printer {
+            options: FormatOptions {
+                max_width: 0,
+                ..self.options
+            },
+        };
+        match expr {
+            Expr::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|arg| printer.expr(arg, 0, 0)).collect();
+                format!("{}({})", name, args.join(", "))
+            }
+            _ => printer.expr(expr, 0, 0),
+        }
+    }
+}
+
+/// Column reached after writing `text` starting at `column`
+fn last_line_width(text: &str, column: usize) -> usize {
+    match text.rsplit_once('\n') {
+        Some((_, last)) => last.chars().count(),
+        None => column + text.chars().count(),
+    }
+}
+
+/// Canonical text of a formula; the leading `=` is kept when present
+pub fn format_formula(src: &str, options: &FormatOptions) -> Result<String, String> {
+    let expr = parse(src)?;
+    let prefix = if src.trim_start().starts_with('=') {
+        "="
+    } else {
+        ""
+    };
+    let printer = Printer { options: *options };
+    Ok(format!(
+        "{}{}",
+        prefix,
+        printer.expr(&expr, 0, prefix.len())
+    ))
+}
+
+/// Reformat a formula with canonical spacing and case
+///
+/// # Arguments
+/// * `formula` - Formula text
+/// * `max_width` - Break calls longer than this many characters across
+///   indented lines; 0 keeps the formula on one line
+/// * `indent` - Spaces per nesting level of a broken call
+///
+/// # Returns
+/// StringResult with the formatted formula, or the parse error
+///
+/// # Safety
+/// `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_format_formula(
+    formula: *const c_char,
+    max_width: usize,
+    indent: usize,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    format_formula(formula, &FormatOptions { max_width, indent }).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_formula() {
+        let one_line = |src: &str| format_formula(src, &FormatOptions::default()).unwrap();
+        assert_eq!(
+            one_line("=if(a1>1,sum( b1:b3 ),((2)) *-c1%)"),
+            "=IF(A1 > 1, SUM(B1:B3), 2 * -C1%)"
+        );
+        assert_eq!(
+            one_line("(1+2)*3&\"x\" and not [Net Total]<>0"),
+            "(1 + 2) * 3 & \"x\" AND NOT [Net Total] <> 0"
+        );
+
+        let broken = format_formula(
+            "=IF(AND(Amount>100,Region=\"EU\"),ROUND(Amount*Rate,2),0)+1",
+            &FormatOptions {
+                max_width: 40,
+                indent: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            broken,
+            "=IF(\n  AND(Amount > 100, Region = \"EU\"),\n  ROUND(Amount * Rate, 2),\n  0\n) + 1"
+        );
+        assert_eq!(parse(&broken).unwrap(), parse(&one_line(&broken)).unwrap());
+        assert!(format_formula("=1 +", &FormatOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,665 @@
+//! Built-in worksheet functions
+
+use super::case;
+use super::complex::{self, Part};
+use super::contact;
+use super::convert;
+use super::criteria::Criterion;
+use super::eval::{evaluate, numeric, EvalContext};
+use super::ip;
+use super::jsonpath;
+use super::parser::Expr;
+use super::series::{self, Cumulative, Normalization, Ranking, Weighted};
+use super::url;
+use super::value::{Array, ErrorValue, Value};
+use super::volatile::SeededRandom;
+use crate::formats::TextFormat;
+use crate::hash::HashAlgorithm;
+use crate::transform::split::{split_cell, SplitMode, SplitSettings};
+
+const VALUE: Value = Value::Error(ErrorValue::Value);
+
+/// Call a built-in function by its upper-case name
+///
+/// Unknown functions evaluate to `#NAME?` and wrong argument counts to
+/// `#VALUE!`. `IF` and `IFERROR` only evaluate the branch they return.
+/// `NOW`, `TODAY` and the random functions read the clock and generator of `ctx`,
+/// and `CURRENCY` its exchange rates.
+pub(super) fn call(name: &str, args: &[Expr], ctx: &dyn EvalContext) -> Value {
+    match name {
+        "IF" => {
+            if args.len() < 2 || args.len() > 3 {
+                return VALUE;
+            }
+            match evaluate(&args[0], ctx).as_bool() {
+                Ok(true) => evaluate(&args[1], ctx),
+                Ok(false) => args
+                    .get(2)
+                    .map_or(Value::Bool(false), |arg| evaluate(arg, ctx)),
+                Err(e) => Value::Error(e),
+            }
+        }
+        "IFERROR" => {
+            if args.len() != 2 {
+                return VALUE;
+            }
+            match evaluate(&args[0], ctx) {
+                Value::Error(_) => evaluate(&args[1], ctx),
+                value => value,
+            }
+        }
+        "NOW" | "TODAY" | "RAND" if !args.is_empty() => VALUE,
+        "NOW" => Value::Number(ctx.now()),
+        "TODAY" => Value::Number(ctx.now().floor()),
+        "RAND" => Value::Number(ctx.random()),
+        "RANDBETWEEN" => {
+            let values: Vec<Value> = args.iter().map(|arg| evaluate(arg, ctx)).collect();
+            match values.as_slice() {
+                [low, high] => random_between(low, high, ctx.random()),
+                _ => VALUE,
+            }
+        }
+        "CURRENCY" => {
+            let values: Vec<Value> = args.iter().map(|arg| evaluate(arg, ctx)).collect();
+            match values.as_slice() {
+                [value, from, to, rest @ ..] if rest.len() <= 1 => {
+                    convert::currency(value, from, to, rest.first(), ctx.currency_rates())
+                }
+                _ => VALUE,
+            }
+        }
+        _ => {
+            let values: Vec<Value> = args.iter().map(|arg| evaluate(arg, ctx)).collect();
+            call_eager(name, &values)
+        }
+    }
+}
+
+fn call_eager(name: &str, args: &[Value]) -> Value {
+    match (name, args) {
+        ("AND", [_, ..]) => logical(args, true),
+        ("OR", [_, ..]) => logical(args, false),
+        ("NOT", [value]) => match value.as_bool() {
+            Ok(b) => Value::Bool(!b),
+            Err(e) => Value::Error(e),
+        },
+        ("ABS", [value]) => numeric(value.as_number().map(f64::abs)),
+        ("SUM", [_, ..]) => sum(args),
+        ("ROUND", [value, digits]) => numeric(value.as_number().and_then(|n| {
+            let factor = 10f64.powi(digits.as_number()?.trunc() as i32);
+            Ok((n * factor).round() / factor)
+        })),
+        ("ROMAN", [value]) => convert::roman(value),
+        ("NUMBERVALUE", [text, rest @ ..]) if rest.len() <= 2 => {
+            convert::number_value(text, rest.first(), rest.get(1))
+        }
+        ("DATEVALUE", [text, rest @ ..]) if rest.len() <= 1 => {
+            convert::date_value(text, rest.first())
+        }
+        ("ARABIC", [value]) => convert::arabic(value),
+        ("CONVERT", [number, from, to]) => convert::convert(number, from, to),
+        ("COMPLEX", [re, im, rest @ ..]) if rest.len() <= 1 => {
+            complex::complex(re, im, rest.first())
+        }
+        ("IMSUM", [_, ..]) => complex::fold(args, false),
+        ("IMPRODUCT", [_, ..]) => complex::fold(args, true),
+        ("IMREAL", [value]) => complex::part(value, Part::Real),
+        ("IMAGINARY", [value]) => complex::part(value, Part::Imaginary),
+        ("IMABS", [value]) => complex::part(value, Part::Modulus),
+        ("BITAND", [a, b]) => bitwise(a, b, |a, b| a & b),
+        ("BITOR", [a, b]) => bitwise(a, b, |a, b| a | b),
+        ("BITXOR", [a, b]) => bitwise(a, b, |a, b| a ^ b),
+        ("BITLSHIFT", [number, shift]) => shift_bits(number, shift, true),
+        ("BITRSHIFT", [number, shift]) => shift_bits(number, shift, false),
+        ("DATASIZE", [bytes, rest @ ..]) if rest.len() <= 2 => {
+            convert::data_size(bytes, rest.first(), rest.get(1))
+        }
+        ("LEN", [value]) => text(value, |s| Value::Number(s.chars().count() as f64)),
+        ("LOWER", [value]) => text(value, |s| Value::Text(s.to_lowercase())),
+        ("UPPER", [value]) => text(value, |s| Value::Text(s.to_uppercase())),
+        ("PROPER", [value]) => text(value, |s| Value::Text(case::proper(s))),
+        ("SNAKECASE", [value]) => text(value, |s| Value::Text(case::snake_case(s))),
+        ("CAMELCASE", [value]) => text(value, |s| Value::Text(case::camel_case(s))),
+        ("UNACCENT", [value]) => text(value, |s| Value::Text(case::unaccent(s))),
+        ("UNICODENORMALIZE", [value, rest @ ..]) if rest.len() <= 1 => {
+            case::normalize(value, rest.first())
+        }
+        ("TRIM", [value]) => text(value, |s| {
+            Value::Text(s.split_whitespace().collect::<Vec<_>>().join(" "))
+        }),
+        ("URLHOST", [value]) => url::host(value),
+        ("URLPATH", [value]) => url::path(value),
+        ("URLPARAM", [value, name]) => url::param(value, name),
+        ("URLENCODE", [value]) => url::encode(value),
+        ("URLDECODE", [value]) => url::decode(value),
+        ("PHONENORMALIZE", [value, rest @ ..]) if rest.len() <= 1 => {
+            contact::phone(value, rest.first())
+        }
+        ("POSTCODENORMALIZE", [value, rest @ ..]) if rest.len() <= 1 => {
+            contact::postcode(value, rest.first())
+        }
+        ("JSONPATH", [json, path]) => jsonpath::json_path(json, path),
+        ("MD5", [value]) => digest(value, HashAlgorithm::Md5),
+        ("SHA1", [value]) => digest(value, HashAlgorithm::Sha1),
+        ("SHA256", [value]) => digest(value, HashAlgorithm::Sha256),
+        ("CRC32", [value]) => digest(value, HashAlgorithm::Crc32),
+        ("LEFT", [value, rest @ ..]) if rest.len() <= 1 => substring(value, rest.first(), true),
+        ("RIGHT", [value, rest @ ..]) if rest.len() <= 1 => substring(value, rest.first(), false),
+        ("ISBLANK", [value]) => Value::Bool(value.is_blank()),
+        ("ISNUMBER", [value]) => Value::Bool(matches!(value, Value::Number(_))),
+        ("ISTEXT", [value]) => Value::Bool(matches!(value, Value::Text(_))),
+        ("ISERROR", [value]) => Value::Bool(matches!(value, Value::Error(_))),
+        ("ISEMAIL", [value]) => is_format(value, TextFormat::Email),
+        ("ISURL", [value]) => is_format(value, TextFormat::Url),
+        ("ISIPADDRESS", [value]) => is_format(value, TextFormat::IpAddress),
+        ("ISJSON", [value]) => is_format(value, TextFormat::Json),
+        ("IPNORMALIZE", [value]) => ip::normalize(value),
+        ("IPVERSION", [value]) => ip::version(value),
+        ("IPINCIDR", [value, networks]) => ip::in_cidr(value, networks),
+        ("UNIQUE", [array, rest @ ..]) if rest.len() <= 2 => unique(array, rest),
+        ("SPLIT", [value, delimiter, rest @ ..]) if rest.len() <= 2 => {
+            split(value, delimiter, rest)
+        }
+        ("SORT", [array, rest @ ..]) if rest.len() <= 3 => sort(array, rest),
+        ("SHUFFLE", [array, seed]) => shuffle(array, seed),
+        ("FILTER", [array, include, rest @ ..]) if rest.len() <= 1 => {
+            filter(array, include, rest.first())
+        }
+        ("MATCH", [lookup, array, rest @ ..]) if rest.len() <= 2 => {
+            match_position(lookup, array, rest)
+        }
+        ("VLOOKUP", [lookup, table, col, rest @ ..]) if rest.len() <= 2 => {
+            vlookup(lookup, table, col, rest)
+        }
+        ("COUNTIF", [range, criteria, rest @ ..]) if rest.len() <= 1 => {
+            count_if(range, criteria, rest.first())
+        }
+        ("LAG", [array, rest @ ..]) if rest.len() <= 2 => {
+            series::shift(&as_array(array), rest.first(), rest.get(1), false)
+        }
+        ("LEAD", [array, rest @ ..]) if rest.len() <= 2 => {
+            series::shift(&as_array(array), rest.first(), rest.get(1), true)
+        }
+        ("DIFF", [array, rest @ ..]) if rest.len() <= 1 => {
+            series::change(&as_array(array), rest.first(), false)
+        }
+        ("PCTCHANGE", [array, rest @ ..]) if rest.len() <= 1 => {
+            series::change(&as_array(array), rest.first(), true)
+        }
+        ("CUMSUM", [array]) => series::cumulative(&as_array(array), Cumulative::Sum),
+        ("CUMPROD", [array]) => series::cumulative(&as_array(array), Cumulative::Product),
+        ("CUMMIN", [array]) => series::cumulative(&as_array(array), Cumulative::Min),
+        ("CUMMAX", [array]) => series::cumulative(&as_array(array), Cumulative::Max),
+        ("EWMA", [array, alpha]) => series::weighted(&as_array(array), alpha, Weighted::Mean),
+        ("EWMVAR", [array, alpha]) => series::weighted(&as_array(array), alpha, Weighted::Variance),
+        ("EWMSTD", [array, alpha]) => series::weighted(&as_array(array), alpha, Weighted::StdDev),
+        ("ZSCORE", [array]) => series::normalize(&as_array(array), Normalization::ZScore),
+        ("MINMAXSCALE", [array]) => series::normalize(&as_array(array), Normalization::MinMax),
+        ("ROBUSTSCALE", [array]) => series::normalize(&as_array(array), Normalization::Robust),
+        ("RANKS", [array, rest @ ..]) if rest.len() <= 2 => {
+            series::ranks(&as_array(array), rest.first(), rest.get(1), None)
+        }
+        ("DENSERANKS", [array, rest @ ..]) if rest.len() <= 1 => {
+            series::ranks(&as_array(array), rest.first(), None, Some(Ranking::Dense))
+        }
+        ("PCTRANKS", [array]) => {
+            series::ranks(&as_array(array), None, None, Some(Ranking::Percent))
+        }
+        ("FIND", [needle, haystack]) => position(needle, haystack, false),
+        ("SEARCH", [needle, haystack]) => position(needle, haystack, true),
+        (
+            "AND" | "OR" | "NOT" | "ABS" | "SUM" | "ROUND" | "LEN" | "LOWER" | "UPPER" | "TRIM",
+            _,
+        )
+        | ("LEFT" | "RIGHT" | "ISBLANK" | "ISNUMBER" | "ISTEXT" | "ISERROR", _)
+        | ("ISEMAIL" | "ISURL" | "ISIPADDRESS" | "ISJSON", _)
+        | ("IPNORMALIZE" | "IPVERSION" | "IPINCIDR", _)
+        | ("MD5" | "SHA1" | "SHA256" | "CRC32" | "JSONPATH", _)
+        | ("URLHOST" | "URLPATH" | "URLPARAM" | "URLENCODE" | "URLDECODE", _)
+        | ("PHONENORMALIZE" | "POSTCODENORMALIZE", _)
+        | ("PROPER" | "SNAKECASE" | "CAMELCASE" | "UNACCENT" | "UNICODENORMALIZE", _)
+        | ("ROMAN" | "ARABIC" | "CONVERT" | "DATASIZE" | "NUMBERVALUE" | "DATEVALUE", _)
+        | ("BITAND" | "BITOR" | "BITXOR" | "BITLSHIFT" | "BITRSHIFT", _)
+        | ("COMPLEX" | "IMSUM" | "IMPRODUCT" | "IMREAL" | "IMAGINARY" | "IMABS", _)
+        | ("FIND" | "SEARCH" | "UNIQUE" | "SORT" | "SHUFFLE" | "FILTER" | "SPLIT", _)
+        | ("MATCH" | "VLOOKUP" | "COUNTIF", _)
+        | ("LAG" | "LEAD" | "DIFF" | "PCTCHANGE", _)
+        | ("CUMSUM" | "CUMPROD" | "CUMMIN" | "CUMMAX", _)
+        | ("EWMA" | "EWMVAR" | "EWMSTD", _)
+        | ("ZSCORE" | "MINMAXSCALE" | "ROBUSTSCALE", _)
+        | ("RANKS" | "DENSERANKS" | "PCTRANKS", _) => VALUE,
+        _ => Value::Error(ErrorValue::Name),
+    }
+}
+
+/// Unsigned 64-bit integer for the BIT functions: `#NUM!` for negative,
+/// fractional or too large numbers
+fn bits(value: &Value) -> Result<u64, ErrorValue> {
+    match value.as_number()? {
+        n if n >= 0.0 && n.fract() == 0.0 && n < u64::MAX as f64 => Ok(n as u64),
+        _ => Err(ErrorValue::Num),
+    }
+}
+
+/// BITAND(a, b), BITOR and BITXOR on unsigned 64-bit integers
+///
+/// Results beyond 2^53 are rounded to the nearest representable number.
+fn bitwise(a: &Value, b: &Value, op: impl Fn(u64, u64) -> u64) -> Value {
+    numeric(bits(a).and_then(|a| Ok(op(a, bits(b)?) as f64)))
+}
+
+/// BITLSHIFT(number, shift) and BITRSHIFT: bits moved within a 64-bit word,
+/// dropping those shifted out; a negative shift moves the other way
+fn shift_bits(number: &Value, shift: &Value, left: bool) -> Value {
+    numeric(bits(number).and_then(|n| {
+        let shift = match shift.as_number()? {
+            s if s.fract() != 0.0 => return Err(ErrorValue::Num),
+            s => s,
+        };
+        let left = left == (shift >= 0.0);
+        let amount = shift.abs().min(64.0) as u32;
+        let shifted = match left {
+            true => n.checked_shl(amount),
+            false => n.checked_shr(amount),
+        };
+        Ok(shifted.unwrap_or(0) as f64)
+    }))
+}
+
+/// Whole number in `[ceil(low), floor(high)]` picked by `random` in `[0, 1)`
+fn random_between(low: &Value, high: &Value, random: f64) -> Value {
+    numeric(low.as_number().and_then(|low| {
+        let (low, high) = (low.ceil(), high.as_number()?.floor());
+        if low > high {
+            return Err(ErrorValue::Num);
+        }
+        Ok((low + (random * (high - low + 1.0)).floor()).min(high))
+    }))
+}
+
+/// Numbers in arrays are added and other array values skipped; direct
+/// arguments are coerced, so `SUM("2", TRUE)` is 3
+fn sum(args: &[Value]) -> Value {
+    let mut total = 0.0;
+    for value in args {
+        match value {
+            Value::Array(array) => {
+                for item in array.values() {
+                    match item {
+                        Value::Number(n) => total += n,
+                        Value::Error(e) => return Value::Error(*e),
+                        _ => {}
+                    }
+                }
+            }
+            value => match value.as_number() {
+                Ok(n) => total += n,
+                Err(e) => return Value::Error(e),
+            },
+        }
+    }
+    Value::number(total)
+}
+
+fn logical(args: &[Value], all: bool) -> Value {
+    let mut result = all;
+    for value in args {
+        match value.as_bool() {
+            Ok(b) if all => result &= b,
+            Ok(b) => result |= b,
+            Err(e) => return Value::Error(e),
+        }
+    }
+    Value::Bool(result)
+}
+
+fn text(value: &Value, f: impl FnOnce(&str) -> Value) -> Value {
+    match value.as_text() {
+        Ok(s) => f(&s),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// ISEMAIL(value), ISURL, ISIPADDRESS and ISJSON: FALSE for anything but
+/// text in the format
+fn is_format(value: &Value, format: TextFormat) -> Value {
+    Value::Bool(matches!(value, Value::Text(text) if format.matches(text)))
+}
+
+/// MD5(text), SHA1, SHA256 and CRC32: lowercase hex digest of the text
+fn digest(value: &Value, algorithm: HashAlgorithm) -> Value {
+    text(value, |s| Value::Text(algorithm.hex(s.as_bytes())))
+}
+
+fn substring(value: &Value, count: Option<&Value>, from_start: bool) -> Value {
+    let count = match count.map_or(Ok(1.0), Value::as_number) {
+        Ok(n) if n >= 0.0 => n as usize,
+        Ok(_) => return VALUE,
+        Err(e) => return Value::Error(e),
+    };
+    text(value, |s| {
+        let len = s.chars().count();
+        let taken: String = if from_start {
+            s.chars().take(count).collect()
+        } else {
+            s.chars().skip(len.saturating_sub(count)).collect()
+        };
+        Value::Text(taken)
+    })
+}
+
+/// 1-based character position of `needle` in `haystack`, `#VALUE!` if absent
+fn position(needle: &Value, haystack: &Value, ignore_case: bool) -> Value {
+    let (needle, haystack) = match (needle.as_text(), haystack.as_text()) {
+        (Ok(n), Ok(h)) if ignore_case => (n.to_lowercase(), h.to_lowercase()),
+        (Ok(n), Ok(h)) => (n, h),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    match haystack.find(&needle) {
+        Some(byte) => Value::Number((haystack[..byte].chars().count() + 1) as f64),
+        None => VALUE,
+    }
+}
+
+/// Treat a scalar argument as a 1x1 array
+fn as_array(value: &Value) -> Array {
+    match value {
+        Value::Array(array) => array.clone(),
+        scalar => Array::new(1, 1, vec![scalar.clone()]),
+    }
+}
+
+fn optional_bool(arg: Option<&Value>) -> Result<bool, ErrorValue> {
+    arg.map_or(Ok(false), Value::as_bool)
+}
+
+/// SPLIT(text, delimiter, [max_parts], [trim]): parts of text as a row,
+/// split the way text to columns does
+///
+/// A column of texts gives one row each, padded with blanks to the longest.
+/// Parts that look like numbers become numbers.
+fn split(value: &Value, delimiter: &Value, rest: &[Value]) -> Value {
+    let settings = (|| {
+        let delimiter = delimiter.as_text()?;
+        let max_parts = rest.first().map_or(Ok(0.0), Value::as_number)?;
+        if delimiter.is_empty() || max_parts < 0.0 {
+            return Err(ErrorValue::Value);
+        }
+        Ok(SplitSettings {
+            mode: SplitMode::Delimiter(delimiter),
+            max_parts: max_parts as usize,
+            trim: optional_bool(rest.get(1))?,
+        })
+    })();
+    let settings = match settings {
+        Ok(settings) => settings,
+        Err(e) => return Value::Error(e),
+    };
+    let texts = as_array(value);
+    if texts.cols() != 1 {
+        return Value::Error(ErrorValue::Value);
+    }
+
+    let rows: Vec<Vec<Value>> = texts
+        .values()
+        .iter()
+        .map(|text| match text {
+            Value::Blank => vec![],
+            Value::Error(e) => vec![Value::Error(*e)],
+            Value::Array(_) => vec![Value::Error(ErrorValue::Value)],
+            other => split_cell(&other.to_string(), &settings)
+                .iter()
+                .map(|part| Value::from_cell(part))
+                .collect(),
+        })
+        .collect();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    Value::Array(Array::from_rows(
+        rows.into_iter()
+            .map(|mut row| {
+                row.resize(width, Value::Blank);
+                row
+            })
+            .collect(),
+    ))
+}
+
+/// Identity of a value for UNIQUE: case-insensitive text, exact numbers
+fn unique_key(value: &Value) -> String {
+    match value {
+        Value::Blank => "b".to_string(),
+        Value::Number(n) => format!("n{}", n),
+        Value::Text(text) => format!("t{}", text.to_lowercase()),
+        Value::Bool(b) => format!("l{}", b),
+        Value::Error(e) => format!("e{}", e.code()),
+        Value::Array(_) => "a".to_string(),
+    }
+}
+
+/// UNIQUE(array, [by_col], [exactly_once])
+fn unique(array: &Value, rest: &[Value]) -> Value {
+    let (by_col, exactly_once) = match (optional_bool(rest.first()), optional_bool(rest.get(1))) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    let array = as_array(array);
+    let array = if by_col { array.transpose() } else { array };
+
+    let mut order: Vec<String> = Vec::new();
+    let mut seen: std::collections::HashMap<String, (usize, usize)> = Default::default();
+    for (index, row) in array.iter_rows().enumerate() {
+        let key: String = row
+            .iter()
+            .map(unique_key)
+            .collect::<Vec<_>>()
+            .join("\u{1f}");
+        match seen.get_mut(&key) {
+            Some((_, count)) => *count += 1,
+            None => {
+                seen.insert(key.clone(), (index, 1));
+                order.push(key);
+            }
+        }
+    }
+
+    let rows: Vec<Vec<Value>> = order
+        .iter()
+        .map(|key| seen[key])
+        .filter(|&(_, count)| !exactly_once || count == 1)
+        .map(|(index, _)| array.row(index).to_vec())
+        .collect();
+    if rows.is_empty() {
+        return Value::Error(ErrorValue::Calc);
+    }
+
+    let result = Array::from_rows(rows);
+    Value::Array(if by_col { result.transpose() } else { result })
+}
+
+/// SORT(array, [sort_index], [sort_order], [by_col])
+fn sort(array: &Value, rest: &[Value]) -> Value {
+    let number = |arg: Option<&Value>, default: f64| arg.map_or(Ok(default), Value::as_number);
+    let (index, order, by_col) = match (
+        number(rest.first(), 1.0),
+        number(rest.get(1), 1.0),
+        optional_bool(rest.get(2)),
+    ) {
+        (Ok(i), Ok(o), Ok(b)) => (i.trunc(), o.trunc(), b),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return Value::Error(e),
+    };
+
+    let array = as_array(array);
+    let array = if by_col { array.transpose() } else { array };
+    if index < 1.0 || index as usize > array.cols() || (order != 1.0 && order != -1.0) {
+        return VALUE;
+    }
+    let key = index as usize - 1;
+
+    // Blanks go last in either direction, as in Excel
+    let mut rows: Vec<&[Value]> = array.iter_rows().collect();
+    rows.sort_by(|a, b| match (a[key].is_blank(), b[key].is_blank()) {
+        (false, false) if order < 0.0 => a[key].sort_order(&b[key]).reverse(),
+        (false, false) => a[key].sort_order(&b[key]),
+        (a_blank, b_blank) => a_blank.cmp(&b_blank),
+    });
+
+    let result = Array::from_rows(rows.into_iter().map(<[Value]>::to_vec).collect());
+    Value::Array(if by_col { result.transpose() } else { result })
+}
+
+/// SHUFFLE(array, seed): the rows of an array in a random order, or with a
+/// number `n` instead of an array, a column holding 1 to `n` in a random order
+///
+/// The order depends only on the seed, so unlike RAND the result is stable
+/// across recalculations.
+fn shuffle(array: &Value, seed: &Value) -> Value {
+    let seed = match seed.as_number() {
+        Ok(seed) => seed.trunc() as i64 as u64,
+        Err(e) => return Value::Error(e),
+    };
+    let array = match array {
+        // At most one sheet's worth of rows
+        Value::Number(n) if !(1.0..1_048_577.0).contains(n) => {
+            return Value::Error(ErrorValue::Num)
+        }
+        Value::Number(n) => {
+            let count = n.trunc() as usize;
+            Array::new(
+                count,
+                1,
+                (1..=count).map(|i| Value::Number(i as f64)).collect(),
+            )
+        }
+        array => as_array(array),
+    };
+    let rng = SeededRandom::new(seed, (0, 0, 0));
+    let mut rows: Vec<&[Value]> = array.iter_rows().collect();
+    for i in (1..rows.len()).rev() {
+        rows.swap(i, rng.below(i + 1));
+    }
+    Value::Array(Array::from_rows(
+        rows.into_iter().map(<[Value]>::to_vec).collect(),
+    ))
+}
+
+/// FILTER(array, include, [if_empty])
+fn filter(array: &Value, include: &Value, if_empty: Option<&Value>) -> Value {
+    let array = as_array(array);
+    let include = as_array(include);
+
+    let by_row = include.cols() == 1 && include.rows() == array.rows();
+    let by_col = include.rows() == 1 && include.cols() == array.cols();
+    if !by_row && !by_col {
+        return VALUE;
+    }
+    let array = if by_row { array } else { array.transpose() };
+
+    let mut rows = Vec::new();
+    for (row, flag) in array.iter_rows().zip(include.values()) {
+        match flag.as_bool() {
+            Ok(true) => rows.push(row.to_vec()),
+            Ok(false) => {}
+            Err(e) => return Value::Error(e),
+        }
+    }
+    if rows.is_empty() {
+        return if_empty.cloned().unwrap_or(Value::Error(ErrorValue::Calc));
+    }
+
+    let result = Array::from_rows(rows);
+    Value::Array(if by_row { result } else { result.transpose() })
+}
+
+/// Index into `values` of the lookup value
+///
+/// `match_type` 0 finds the first equal value (wildcards or regex for
+/// text); 1 the last value not above it in an ascending list, -1 the last
+/// value not below it in a descending one. Only values of the lookup
+/// value's kind are compared when approximate.
+fn lookup_index(
+    lookup: &Value,
+    values: &[Value],
+    match_type: f64,
+    regex: bool,
+) -> Result<usize, ErrorValue> {
+    if match_type == 0.0 {
+        let criterion = Criterion::equal_to(lookup, regex)?;
+        return values
+            .iter()
+            .position(|value| criterion.matches(value))
+            .ok_or(ErrorValue::NA);
+    }
+    if let Value::Error(e) = lookup {
+        return Err(*e);
+    }
+    let mut found = None;
+    for (i, value) in values.iter().enumerate() {
+        if std::mem::discriminant(value) != std::mem::discriminant(lookup) {
+            continue;
+        }
+        let ordering = value.compare(lookup);
+        if (match_type > 0.0 && ordering.is_gt()) || (match_type < 0.0 && ordering.is_lt()) {
+            break;
+        }
+        found = Some(i);
+    }
+    found.ok_or(ErrorValue::NA)
+}
+
+/// MATCH(lookup_value, lookup_array, [match_type], [use_regex])
+fn match_position(lookup: &Value, array: &Value, rest: &[Value]) -> Value {
+    let (match_type, regex) = match (
+        rest.first().map_or(Ok(1.0), Value::as_number),
+        optional_bool(rest.get(1)),
+    ) {
+        (Ok(t), Ok(r)) => (t, r),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    let array = as_array(array);
+    if array.rows() > 1 && array.cols() > 1 {
+        return Value::Error(ErrorValue::NA);
+    }
+    match lookup_index(lookup, array.values(), match_type, regex) {
+        Ok(i) => Value::Number((i + 1) as f64),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// VLOOKUP(lookup_value, table, col_index, [approximate], [use_regex])
+fn vlookup(lookup: &Value, table: &Value, col: &Value, rest: &[Value]) -> Value {
+    let (col, approximate, regex) = match (
+        col.as_number(),
+        rest.first().map_or(Ok(true), Value::as_bool),
+        optional_bool(rest.get(1)),
+    ) {
+        (Ok(c), Ok(a), Ok(r)) => (c.trunc(), a, r),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return Value::Error(e),
+    };
+    let table = as_array(table);
+    if col < 1.0 {
+        return VALUE;
+    }
+    if col as usize > table.cols() {
+        return Value::Error(ErrorValue::Ref);
+    }
+    let keys: Vec<Value> = table.iter_rows().map(|row| row[0].clone()).collect();
+    match lookup_index(lookup, &keys, if approximate { 1.0 } else { 0.0 }, regex) {
+        Ok(row) => table.row(row)[col as usize - 1].clone(),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// COUNTIF(range, criteria, [use_regex])
+fn count_if(range: &Value, criteria: &Value, regex: Option<&Value>) -> Value {
+    let criterion = match optional_bool(regex).and_then(|regex| Criterion::parse(criteria, regex)) {
+        Ok(criterion) => criterion,
+        Err(e) => return Value::Error(e),
+    };
+    let array = as_array(range);
+    let count = array
+        .values()
+        .iter()
+        .filter(|v| criterion.matches(v))
+        .count();
+    Value::Number(count as f64)
+}
@@ -0,0 +1,182 @@
+//! Token classes for syntax highlighting in the formula editor
+//!
+//! Built on the lenient tokenizer, so half-typed formulas (an open string,
+//! a stray character) still highlight; the broken part comes back as
+//! `invalid`. Spans are given in bytes and in characters, since terminal
+//! cells count characters.
+
+use std::ops::Range;
+use std::os::raw::c_char;
+
+use super::lexer::{tokenize_lenient, Token, TokenKind};
+use super::parser::{CellRef, ColumnRef};
+use crate::ffi::str_arg;
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Function,
+    /// Cell, range or whole-column reference such as `B2` or `A:C`
+    Reference,
+    /// Bracketed column name
+    Column,
+    /// Sheet prefix such as `Sheet2!`
+    Sheet,
+    /// Bare name: column header or defined name
+    Name,
+    Boolean,
+    Number,
+    String,
+    Error,
+    Operator,
+    Paren,
+    Separator,
+    Invalid,
+}
+
+impl HighlightKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            HighlightKind::Function => "function",
+            HighlightKind::Reference => "reference",
+            HighlightKind::Column => "column",
+            HighlightKind::Sheet => "sheet",
+            HighlightKind::Name => "name",
+            HighlightKind::Boolean => "boolean",
+            HighlightKind::Number => "number",
+            HighlightKind::String => "string",
+            HighlightKind::Error => "error",
+            HighlightKind::Operator => "operator",
+            HighlightKind::Paren => "paren",
+            HighlightKind::Separator => "separator",
+            HighlightKind::Invalid => "invalid",
+        }
+    }
+}
+
+/// Classified token with its byte range
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub kind: HighlightKind,
+    pub span: Range<usize>,
+}
+
+pub(super) fn classify(tokens: &[Token], i: usize) -> HighlightKind {
+    let kind_at = |j: usize| tokens.get(j).map(|t| &t.kind);
+    match &tokens[i].kind {
+        TokenKind::Ident(name) => {
+            let next = kind_at(i + 1);
+            let beside_colon = next == Some(&TokenKind::Colon)
+                || (i > 0 && kind_at(i - 1) == Some(&TokenKind::Colon));
+            if next == Some(&TokenKind::LParen) {
+                HighlightKind::Function
+            } else if next == Some(&TokenKind::Colon)
+                && matches!(kind_at(i + 2), Some(TokenKind::Sheet(_)))
+            {
+                // First sheet of a 3-D span such as `Sheet1:Sheet5!`
+                HighlightKind::Sheet
+            } else if name.eq_ignore_ascii_case("TRUE") || name.eq_ignore_ascii_case("FALSE") {
+                HighlightKind::Boolean
+            } else if ["AND", "OR", "NOT"]
+                .iter()
+                .any(|word| name.eq_ignore_ascii_case(word))
+            {
+                HighlightKind::Operator
+            } else if CellRef::parse(name).is_some()
+                || (beside_colon && ColumnRef::parse(name).is_some())
+            {
+                HighlightKind::Reference
+            } else {
+                HighlightKind::Name
+            }
+        }
+        TokenKind::Number(_) => HighlightKind::Number,
+        TokenKind::Text(_) => HighlightKind::String,
+        TokenKind::Column(_) => HighlightKind::Column,
+        TokenKind::Error(_) => HighlightKind::Error,
+        TokenKind::Sheet(_) => HighlightKind::Sheet,
+        TokenKind::Invalid => HighlightKind::Invalid,
+        TokenKind::LParen | TokenKind::RParen => HighlightKind::Paren,
+        TokenKind::Comma => HighlightKind::Separator,
+        TokenKind::Colon | TokenKind::Hash => HighlightKind::Reference,
+        _ => HighlightKind::Operator,
+    }
+}
+
+/// Highlight classes for every token of `src`, in order
+pub fn highlight(src: &str) -> Vec<Highlight> {
+    let tokens = tokenize_lenient(src);
+    (0..tokens.len())
+        .map(|i| Highlight {
+            kind: classify(&tokens, i),
+            span: tokens[i].span.clone(),
+        })
+        .collect()
+}
+
+/// Tokens of a formula classified for syntax highlighting
+///
+/// # Returns
+/// StringResult with a JSON array of `{kind, text, start, end, char_start,
+/// char_end}`; `start`/`end` are byte offsets, `char_*` character offsets
+///
+/// # Safety
+/// `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_tokenize_formula(formula: *const c_char) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    let chars = |byte: usize| formula[..byte].chars().count();
+    let tokens: Vec<serde_json::Value> = highlight(formula)
+        .into_iter()
+        .map(|h| {
+            serde_json::json!({
+                "kind": h.kind.name(),
+                "text": &formula[h.span.clone()],
+                "start": h.span.start,
+                "end": h.span.end,
+                "char_start": chars(h.span.start),
+                "char_end": chars(h.span.end),
+            })
+        })
+        .collect();
+    StringResult::success(&serde_json::Value::from(tokens).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_classes() {
+        let kinds: Vec<&str> = highlight("=IF(Sheet2!A1:B2 > Rate, TRUE, \"é\" & [Net Total]) ?")
+            .into_iter()
+            .map(|h| h.kind.name())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "operator",
+                "function",
+                "paren",
+                "sheet",
+                "reference",
+                "reference",
+                "reference",
+                "operator",
+                "name",
+                "separator",
+                "boolean",
+                "separator",
+                "string",
+                "operator",
+                "column",
+                "paren",
+                "invalid",
+            ]
+        );
+        assert_eq!(highlight("=\"open")[1].span, 1..6);
+    }
+}
@@ -0,0 +1,121 @@
+//! IP address functions: IPNORMALIZE, IPVERSION and IPINCIDR
+//!
+//! Addresses are IPv4 dotted quads or IPv6 text, optionally in brackets.
+//! IPv4-mapped IPv6 addresses (`::ffff:10.0.0.1`) count as their IPv4
+//! address, so they match IPv4 networks and normalize to the dotted form.
+
+use std::net::IpAddr;
+
+use super::value::{ErrorValue, Value};
+
+fn parse_ip(text: &str) -> Option<IpAddr> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .unwrap_or(text);
+    let ip: IpAddr = text.parse().ok()?;
+    Some(ip.to_canonical())
+}
+
+fn address(value: &Value) -> Result<IpAddr, ErrorValue> {
+    parse_ip(&value.as_text()?).ok_or(ErrorValue::Value)
+}
+
+/// Whether `ip` lies in `network`, written `10.0.0.0/8`, `2001:db8::/32` or
+/// as a single address
+fn in_network(ip: IpAddr, network: &str) -> Option<bool> {
+    let (base, prefix) = match network.trim().split_once('/') {
+        Some((base, prefix)) => {
+            let prefix: u32 = prefix.trim().parse().ok()?;
+            (parse_ip(base)?, Some(prefix))
+        }
+        None => (parse_ip(network)?, None),
+    };
+    let (ip, base, bits) = match (ip, base) {
+        (IpAddr::V4(ip), IpAddr::V4(base)) => (u32::from(ip).into(), u32::from(base).into(), 32),
+        (IpAddr::V6(ip), IpAddr::V6(base)) => (u128::from(ip), u128::from(base), 128),
+        _ => return Some(false),
+    };
+    let prefix = prefix.unwrap_or(bits);
+    if prefix > bits {
+        return None;
+    }
+    let shift = bits - prefix;
+    Some(shift == bits || ip >> shift == base >> shift)
+}
+
+/// IPNORMALIZE(ip): canonical text, IPv6 compressed and lower case
+pub(super) fn normalize(value: &Value) -> Value {
+    match address(value) {
+        Ok(ip) => Value::Text(ip.to_string()),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// IPVERSION(ip): 4 or 6
+pub(super) fn version(value: &Value) -> Value {
+    match address(value) {
+        Ok(IpAddr::V4(_)) => Value::Number(4.0),
+        Ok(IpAddr::V6(_)) => Value::Number(6.0),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// IPINCIDR(ip, networks): TRUE when the address is in any of the networks,
+/// which may be a range of CIDR blocks; blank cells in the range are skipped
+pub(super) fn in_cidr(value: &Value, networks: &Value) -> Value {
+    let ip = match address(value) {
+        Ok(ip) => ip,
+        Err(e) => return Value::Error(e),
+    };
+    let networks = match networks {
+        Value::Array(array) => array.values(),
+        scalar => std::slice::from_ref(scalar),
+    };
+    let mut found = false;
+    for network in networks.iter().filter(|n| !n.is_blank()) {
+        match network.as_text().map(|n| in_network(ip, &n)) {
+            Ok(Some(inside)) => found |= inside,
+            Ok(None) => return Value::Error(ErrorValue::Value),
+            Err(e) => return Value::Error(e),
+        }
+    }
+    Value::Bool(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::value::Array;
+
+    #[test]
+    fn test_ip_functions() {
+        let text = |s: &str| Value::Text(s.into());
+
+        assert_eq!(normalize(&text(" 2001:DB8:0:0::1 ")), text("2001:db8::1"));
+        assert_eq!(normalize(&text("[::ffff:10.0.0.1]")), text("10.0.0.1"));
+        assert_eq!(normalize(&text("10.0.0")), Value::Error(ErrorValue::Value));
+        assert_eq!(version(&text("fe80::1")), Value::Number(6.0));
+
+        let inside = |ip: &str, cidr: &str| in_cidr(&text(ip), &text(cidr));
+        assert_eq!(inside("10.1.2.3", "10.0.0.0/8"), Value::Bool(true));
+        assert_eq!(inside("11.1.2.3", "10.0.0.0/8"), Value::Bool(false));
+        assert_eq!(inside("192.168.1.7", "192.168.1.7"), Value::Bool(true));
+        assert_eq!(inside("8.8.8.8", "0.0.0.0/0"), Value::Bool(true));
+        assert_eq!(inside("2001:db8::5", "2001:db8::/32"), Value::Bool(true));
+        assert_eq!(inside("10.0.0.1", "::/0"), Value::Bool(false));
+        assert_eq!(
+            inside("10.0.0.1", "10.0.0.0/33"),
+            Value::Error(ErrorValue::Value)
+        );
+
+        let private = Value::Array(Array::new(
+            3,
+            1,
+            vec![text("10.0.0.0/8"), Value::Blank, text("192.168.0.0/16")],
+        ));
+        assert_eq!(in_cidr(&text("192.168.4.4"), &private), Value::Bool(true));
+        assert_eq!(in_cidr(&text("172.16.0.1"), &private), Value::Bool(false));
+    }
+}
@@ -0,0 +1,162 @@
+//! `JSONPATH(json, path)`: values picked out of JSON text
+//!
+//! Paths are the common subset of JSONPath: `$` for the document, `.name` or
+//! `['name']` for an object member, `[0]` for an array element (negative
+//! counts from the end) and `*` or `[*]` for every member or element.
+//! Strings, numbers and booleans come back as values, `null` as blank and
+//! objects and arrays as JSON text. A path with a wildcard returns a column
+//! of every match.
+
+use super::value::{Array, ErrorValue, Value};
+use serde_json::Value as Json;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Member(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Parse `path` into steps, `None` when it is not a supported JSONPath
+fn parse_path(path: &str) -> Option<Vec<Step>> {
+    let mut rest = path.trim().strip_prefix('$')?;
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            steps.push(match &after[..end] {
+                "" => return None,
+                "*" => Step::Wildcard,
+                name => Step::Member(name.to_string()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (step, after) = match after.chars().next()? {
+                quote @ ('\'' | '"') => {
+                    let close = after[1..].find(quote)? + 1;
+                    let member = Step::Member(after[1..close].to_string());
+                    (member, after[close + 1..].strip_prefix(']')?)
+                }
+                _ => {
+                    let (inner, after) = after.split_once(']')?;
+                    let step = match inner.trim() {
+                        "*" => Step::Wildcard,
+                        index => Step::Index(index.parse().ok()?),
+                    };
+                    (step, after)
+                }
+            };
+            steps.push(step);
+            rest = after;
+        } else {
+            return None;
+        }
+    }
+    Some(steps)
+}
+
+/// Every node reached by following `steps` from `node`
+fn select<'a>(node: &'a Json, steps: &[Step], out: &mut Vec<&'a Json>) {
+    let Some((step, rest)) = steps.split_first() else {
+        out.push(node);
+        return;
+    };
+    match (step, node) {
+        (Step::Member(name), Json::Object(map)) => {
+            if let Some(child) = map.get(name) {
+                select(child, rest, out);
+            }
+        }
+        (Step::Index(index), Json::Array(items)) => {
+            let position = match *index < 0 {
+                true => items.len().checked_sub(index.unsigned_abs() as usize),
+                false => Some(*index as usize),
+            };
+            if let Some(child) = position.and_then(|i| items.get(i)) {
+                select(child, rest, out);
+            }
+        }
+        (Step::Wildcard, Json::Object(map)) => {
+            map.values().for_each(|child| select(child, rest, out))
+        }
+        (Step::Wildcard, Json::Array(items)) => {
+            items.iter().for_each(|child| select(child, rest, out))
+        }
+        _ => {}
+    }
+}
+
+fn to_value(node: &Json) -> Value {
+    match node {
+        Json::Null => Value::Blank,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => n
+            .as_f64()
+            .map_or(Value::Error(ErrorValue::Num), Value::number),
+        Json::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+/// JSONPATH(json, path): `#VALUE!` for invalid JSON or paths, `#N/A` when
+/// nothing matches
+pub(super) fn json_path(json: &Value, path: &Value) -> Value {
+    let (json, path) = match (json.as_text(), path.as_text()) {
+        (Ok(json), Ok(path)) => (json, path),
+        (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+    };
+    let Some(steps) = parse_path(&path) else {
+        return Value::Error(ErrorValue::Value);
+    };
+    let Ok(document) = serde_json::from_str::<Json>(&json) else {
+        return Value::Error(ErrorValue::Value);
+    };
+
+    let mut found = Vec::new();
+    select(&document, &steps, &mut found);
+    match (found.as_slice(), steps.contains(&Step::Wildcard)) {
+        ([], _) => Value::Error(ErrorValue::NA),
+        ([node], false) => to_value(node),
+        (nodes, _) => Value::Array(Array::new(
+            nodes.len(),
+            1,
+            nodes.iter().map(|node| to_value(node)).collect(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_path() {
+        let doc = Value::Text(
+            r#"{"order": {"id": "A-1", "paid": true, "note": null},
+                "items": [{"sku": "x", "price": 2.5}, {"sku": "y", "price": 4}],
+                "odd key": [1, [2, 3]]}"#
+                .into(),
+        );
+        let get = |path: &str| json_path(&doc, &Value::Text(path.into()));
+
+        assert_eq!(get("$.items[0].price"), Value::Number(2.5));
+        assert_eq!(get("$.order.id"), Value::Text("A-1".into()));
+        assert_eq!(get("$['order'].paid"), Value::Bool(true));
+        assert_eq!(get("$.order.note"), Value::Blank);
+        assert_eq!(get("$[\"odd key\"][-1]"), Value::Text("[2,3]".into()));
+        assert_eq!(
+            get("$.items[*].sku"),
+            Value::Array(Array::new(
+                2,
+                1,
+                vec![Value::Text("x".into()), Value::Text("y".into())]
+            ))
+        );
+        assert_eq!(get("$.items[5]"), Value::Error(ErrorValue::NA));
+        assert_eq!(get("items[0]"), Value::Error(ErrorValue::Value));
+        assert_eq!(
+            json_path(&Value::Text("{".into()), &Value::Text("$".into())),
+            Value::Error(ErrorValue::Value)
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Formula tokenizer
+
+use std::fmt;
+use std::ops::Range;
+
+use super::value::ErrorValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Text(String),
+    /// Bare identifier: function, column or defined name, cell reference, TRUE/FALSE
+    Ident(String),
+    /// Bracketed column name such as `[Order Total]`
+    Column(String),
+    /// Error literal such as `#REF!`
+    Error(ErrorValue),
+    /// Sheet prefix of a reference, `Sheet2!` or `'Q1 Sales'!` (name unquoted)
+    Sheet(String),
+    /// Text that does not lex, only produced by [`tokenize_lenient`]
+    Invalid,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Ampersand,
+    Percent,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    LParen,
+    RParen,
+    Comma,
+    Colon,
+    /// Spill-range operator directly after a cell reference, `A1#`
+    Hash,
+}
+
+/// Token with its byte range in the source text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+/// Syntax error with a stable code and the byte range it concerns
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Kebab-case identifier such as `unterminated-string`
+    pub code: &'static str,
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.message
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$')
+}
+
+/// Split formula text into tokens, skipping whitespace
+pub fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    Ok(scan(src, false)?)
+}
+
+/// Like [`tokenize`], but never fails: a stray character becomes an
+/// [`TokenKind::Invalid`] token and an unterminated string, column name or
+/// sheet name runs to the end of the text as one. For highlighting text that
+/// is still being typed.
+pub fn tokenize_lenient(src: &str) -> Vec<Token> {
+    scan(src, true).unwrap_or_default()
+}
+
+pub(super) fn scan(src: &str, lenient: bool) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+
+    // Fail, or in lenient mode record `span` as invalid and carry on
+    macro_rules! fail {
+        ($span:expr, $code:expr, $($msg:tt)*) => {{
+            if !lenient {
+                return Err(ParseError {
+                    code: $code,
+                    message: format!($($msg)*),
+                    span: $span,
+                });
+            }
+            tokens.push(Token {
+                kind: TokenKind::Invalid,
+                span: $span,
+            });
+            continue;
+        }};
+    }
+
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let kind = if c.is_ascii_digit() || (c == '.' && next_is_digit(src, start + 1)) {
+            let end = number_end(src, start);
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            let text = &src[start..end];
+            let Ok(value) = text.parse::<f64>() else {
+                fail!(
+                    start..end,
+                    "invalid-number",
+                    "Invalid number '{}' at position {}",
+                    text,
+                    start
+                )
+            };
+            tokens.push(Token {
+                kind: TokenKind::Number(value),
+                span: start..end,
+            });
+            continue;
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            let mut end = None;
+            while let Some((i, ch)) = chars.next() {
+                if ch == '"' {
+                    if chars.peek().is_some_and(|&(_, next)| next == '"') {
+                        chars.next();
+                        text.push('"');
+                    } else {
+                        end = Some(i + 1);
+                        break;
+                    }
+                } else {
+                    text.push(ch);
+                }
+            }
+            let Some(end) = end else {
+                fail!(
+                    start..src.len(),
+                    "unterminated-string",
+                    "Unterminated string literal at position {}",
+                    start
+                )
+            };
+            tokens.push(Token {
+                kind: TokenKind::Text(text),
+                span: start..end,
+            });
+            continue;
+        } else if c == '[' {
+            chars.next();
+            let mut name = String::new();
+            let mut end = None;
+            for (i, ch) in chars.by_ref() {
+                if ch == ']' {
+                    end = Some(i + 1);
+                    break;
+                }
+                name.push(ch);
+            }
+            let Some(end) = end else {
+                fail!(
+                    start..src.len(),
+                    "unterminated-column",
+                    "Unterminated column name at position {}",
+                    start
+                )
+            };
+            tokens.push(Token {
+                kind: TokenKind::Column(name.trim().to_string()),
+                span: start..end,
+            });
+            continue;
+        } else if c == '#' {
+            let error = ErrorValue::ALL.into_iter().find(|e| {
+                src.get(start..start + e.code().len())
+                    .is_some_and(|text| text.eq_ignore_ascii_case(e.code()))
+            });
+            let Some(error) = error else {
+                chars.next();
+                fail!(
+                    start..start + 1,
+                    "unexpected-character",
+                    "Unexpected character '#' at position {}",
+                    start
+                )
+            };
+            let end = start + error.code().len();
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            tokens.push(Token {
+                kind: TokenKind::Error(error),
+                span: start..end,
+            });
+            continue;
+        } else if c == '\'' {
+            chars.next();
+            let mut name = String::new();
+            let mut end = None;
+            while let Some((i, ch)) = chars.next() {
+                if ch == '\'' {
+                    if chars.peek().is_some_and(|&(_, next)| next == '\'') {
+                        chars.next();
+                        name.push('\'');
+                    } else {
+                        end = Some(i + 1);
+                        break;
+                    }
+                } else {
+                    name.push(ch);
+                }
+            }
+            let Some(end) = end else {
+                fail!(
+                    start..src.len(),
+                    "unterminated-sheet",
+                    "Unterminated sheet name at position {}",
+                    start
+                )
+            };
+            if chars.next_if(|&(_, ch)| ch == '!').is_none() {
+                fail!(
+                    start..end,
+                    "expected-sheet-separator",
+                    "Expected '!' after sheet name at position {}",
+                    end
+                )
+            }
+            tokens.push(Token {
+                kind: TokenKind::Sheet(name),
+                span: start..end + 1,
+            });
+            continue;
+        } else if is_ident_char(c) {
+            let mut end = start;
+            while let Some(&(i, ch)) = chars.peek() {
+                if !is_ident_char(ch) {
+                    break;
+                }
+                end = i + ch.len_utf8();
+                chars.next();
+            }
+            let kind = if chars.next_if(|&(_, ch)| ch == '!').is_some() {
+                end += 1;
+                TokenKind::Sheet(src[start..end - 1].to_string())
+            } else {
+                TokenKind::Ident(src[start..end].to_string())
+            };
+            tokens.push(Token {
+                kind,
+                span: start..end,
+            });
+            if let Some((at, _)) = chars.next_if(|&(_, ch)| ch == '#') {
+                tokens.push(Token {
+                    kind: TokenKind::Hash,
+                    span: at..at + 1,
+                });
+            }
+            continue;
+        } else {
+            chars.next();
+            let next = chars.peek().map(|&(_, ch)| ch);
+            match (c, next) {
+                ('<', Some('>')) => TokenKind::Ne,
+                ('<', Some('=')) => TokenKind::Le,
+                ('>', Some('=')) => TokenKind::Ge,
+                ('+', _) => TokenKind::Plus,
+                ('-', _) => TokenKind::Minus,
+                ('*', _) => TokenKind::Star,
+                ('/', _) => TokenKind::Slash,
+                ('^', _) => TokenKind::Caret,
+                ('&', _) => TokenKind::Ampersand,
+                ('%', _) => TokenKind::Percent,
+                ('=', _) => TokenKind::Eq,
+                ('<', _) => TokenKind::Lt,
+                ('>', _) => TokenKind::Gt,
+                ('(', _) => TokenKind::LParen,
+                (')', _) => TokenKind::RParen,
+                (',', _) => TokenKind::Comma,
+                (':', _) => TokenKind::Colon,
+                _ => fail!(
+                    start..start + c.len_utf8(),
+                    "unexpected-character",
+                    "Unexpected character '{}' at position {}",
+                    c,
+                    start
+                ),
+            }
+        };
+
+        let mut end = start + c.len_utf8();
+        if matches!(kind, TokenKind::Ne | TokenKind::Le | TokenKind::Ge) {
+            chars.next();
+            end += 1;
+        }
+        tokens.push(Token {
+            kind,
+            span: start..end,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn next_is_digit(src: &str, at: usize) -> bool {
+    src[at..].starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// End of a numeric literal: digits, optional fraction and exponent
+fn number_end(src: &str, start: usize) -> usize {
+    let bytes = src.as_bytes();
+    let mut i = start;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+            j += 1;
+        }
+        if j < bytes.len() && bytes[j].is_ascii_digit() {
+            while j < bytes.len() && bytes[j].is_ascii_digit() {
+                j += 1;
+            }
+            i = j;
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let kinds: Vec<TokenKind> = tokenize("Amount>=1.5e2 AND [Sale Region]<>\"E\"\"U\"")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Ident("Amount".into()),
+                TokenKind::Ge,
+                TokenKind::Number(150.0),
+                TokenKind::Ident("AND".into()),
+                TokenKind::Column("Sale Region".into()),
+                TokenKind::Ne,
+                TokenKind::Text("E\"U".into()),
+            ]
+        );
+        assert_eq!(
+            tokenize("Sheet2!A1 + 'Q1 ''24'!B2")
+                .unwrap()
+                .into_iter()
+                .map(|t| t.kind)
+                .collect::<Vec<_>>(),
+            [
+                TokenKind::Sheet("Sheet2".into()),
+                TokenKind::Ident("A1".into()),
+                TokenKind::Plus,
+                TokenKind::Sheet("Q1 '24".into()),
+                TokenKind::Ident("B2".into()),
+            ]
+        );
+        assert!(tokenize("'Sheet 2'A1").is_err());
+        assert!(tokenize("\"open").is_err());
+        assert!(tokenize("1 ? 2").is_err());
+
+        let lenient: Vec<(TokenKind, Range<usize>)> = tokenize_lenient("1 ? [Open")
+            .into_iter()
+            .map(|t| (t.kind, t.span))
+            .collect();
+        assert_eq!(
+            lenient,
+            [
+                (TokenKind::Number(1.0), 0..1),
+                (TokenKind::Invalid, 2..3),
+                (TokenKind::Invalid, 4..9),
+            ]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Formula syntax of localized spreadsheet programs
+//!
+//! European Excel versions write `=WENN(A1>1,5;"ja";"nein")`: function names
+//! and `TRUE`/`FALSE` are translated, arguments are separated by `;` and
+//! numbers use a decimal comma. Formulas are stored and evaluated in the
+//! English syntax; [`translate`] converts between any two locales. Text in
+//! strings, bracketed column names and quoted sheet names is left alone,
+//! and so are functions a locale has no name for.
+
+use std::os::raw::c_char;
+
+use super::parser::{parse, Expr};
+use crate::ffi::str_arg;
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormulaLocale {
+    /// Language code such as `de`
+    pub code: &'static str,
+    pub argument_separator: char,
+    pub decimal_separator: char,
+    /// English and local spellings of function names and `TRUE`/`FALSE`
+    pub names: &'static [(&'static str, &'static str)],
+}
+
+pub static ENGLISH: FormulaLocale = FormulaLocale {
+    code: "en",
+    argument_separator: ',',
+    decimal_separator: '.',
+    names: &[],
+};
+
+pub static LOCALES: &[FormulaLocale] = &[
+    ENGLISH,
+    FormulaLocale {
+        code: "de",
+        argument_separator: ';',
+        decimal_separator: ',',
+        names: &[
+            ("TRUE", "WAHR"),
+            ("FALSE", "FALSCH"),
+            ("AND", "UND"),
+            ("IF", "WENN"),
+            ("IFERROR", "WENNFEHLER"),
+            ("NOT", "NICHT"),
+            ("OR", "ODER"),
+            ("SUM", "SUMME"),
+            ("RAND", "ZUFALLSZAHL"),
+            ("RANDBETWEEN", "ZUFALLSBEREICH"),
+            ("ROUND", "RUNDEN"),
+            ("ROMAN", "RÖMISCH"),
+            ("ARABIC", "ARABISCH"),
+            ("CONVERT", "UMWANDELN"),
+            ("BITAND", "BITUND"),
+            ("BITOR", "BITODER"),
+            ("BITXOR", "BITXODER"),
+            ("BITLSHIFT", "BITLVERSCHIEB"),
+            ("BITRSHIFT", "BITRVERSCHIEB"),
+            ("COMPLEX", "KOMPLEXE"),
+            ("IMSUM", "IMSUMME"),
+            ("IMPRODUCT", "IMPRODUKT"),
+            ("IMREAL", "IMREALTEIL"),
+            ("IMAGINARY", "IMAGINÄRTEIL"),
+            ("FIND", "FINDEN"),
+            ("LEFT", "LINKS"),
+            ("LEN", "LÄNGE"),
+            ("LOWER", "KLEIN"),
+            ("PROPER", "GROSS2"),
+            ("RIGHT", "RECHTS"),
+            ("SEARCH", "SUCHEN"),
+            ("TRIM", "GLÄTTEN"),
+            ("UPPER", "GROSS"),
+            ("ISBLANK", "ISTLEER"),
+            ("ISERROR", "ISTFEHLER"),
+            ("ISNUMBER", "ISTZAHL"),
+            ("ISTEXT", "ISTTEXT"),
+            ("NOW", "JETZT"),
+            ("DATEVALUE", "DATWERT"),
+            ("NUMBERVALUE", "ZAHLENWERT"),
+            ("TODAY", "HEUTE"),
+            ("SORT", "SORTIEREN"),
+            ("UNIQUE", "EINDEUTIG"),
+            ("MATCH", "VERGLEICH"),
+            ("VLOOKUP", "SVERWEIS"),
+        ],
+    },
+    FormulaLocale {
+        code: "fr",
+        argument_separator: ';',
+        decimal_separator: ',',
+        names: &[
+            ("TRUE", "VRAI"),
+            ("FALSE", "FAUX"),
+            ("AND", "ET"),
+            ("IF", "SI"),
+            ("IFERROR", "SIERREUR"),
+            ("NOT", "NON"),
+            ("OR", "OU"),
+            ("SUM", "SOMME"),
+            ("RAND", "ALEA"),
+            ("RANDBETWEEN", "ALEA.ENTRE.BORNES"),
+            ("ROUND", "ARRONDI"),
+            ("ROMAN", "ROMAIN"),
+            ("ARABIC", "CHIFFRE.ARABE"),
+            ("BITAND", "BITET"),
+            ("BITOR", "BITOU"),
+            ("BITXOR", "BITOUEXCLUSIF"),
+            ("BITLSHIFT", "BITDECALG"),
+            ("BITRSHIFT", "BITDECALD"),
+            ("COMPLEX", "COMPLEXE"),
+            ("IMSUM", "COMPLEXE.SOMME"),
+            ("IMPRODUCT", "COMPLEXE.PRODUIT"),
+            ("IMREAL", "COMPLEXE.REEL"),
+            ("IMAGINARY", "COMPLEXE.IMAGINAIRE"),
+            ("IMABS", "COMPLEXE.MODULE"),
+            ("FIND", "TROUVE"),
+            ("LEFT", "GAUCHE"),
+            ("LEN", "NBCAR"),
+            ("LOWER", "MINUSCULE"),
+            ("PROPER", "NOMPROPRE"),
+            ("RIGHT", "DROITE"),
+            ("SEARCH", "CHERCHE"),
+            ("TRIM", "SUPPRESPACE"),
+            ("UPPER", "MAJUSCULE"),
+            ("ISBLANK", "ESTVIDE"),
+            ("ISERROR", "ESTERREUR"),
+            ("ISNUMBER", "ESTNUM"),
+            ("ISTEXT", "ESTTEXTE"),
+            ("NOW", "MAINTENANT"),
+            ("DATEVALUE", "DATEVAL"),
+            ("NUMBERVALUE", "VALEURNOMBRE"),
+            ("TODAY", "AUJOURDHUI"),
+            ("FILTER", "FILTRE"),
+            ("SORT", "TRIER"),
+            ("MATCH", "EQUIV"),
+            ("VLOOKUP", "RECHERCHEV"),
+        ],
+    },
+    FormulaLocale {
+        code: "es",
+        argument_separator: ';',
+        decimal_separator: ',',
+        names: &[
+            ("TRUE", "VERDADERO"),
+            ("FALSE", "FALSO"),
+            ("AND", "Y"),
+            ("IF", "SI"),
+            ("IFERROR", "SI.ERROR"),
+            ("NOT", "NO"),
+            ("OR", "O"),
+            ("SUM", "SUMA"),
+            ("RAND", "ALEATORIO"),
+            ("RANDBETWEEN", "ALEATORIO.ENTRE"),
+            ("ROUND", "REDONDEAR"),
+            ("ROMAN", "NUMERO.ROMANO"),
+            ("ARABIC", "NUMERO.ARABE"),
+            ("CONVERT", "CONVERTIR"),
+            ("BITAND", "BIT.Y"),
+            ("BITOR", "BIT.O"),
+            ("BITXOR", "BIT.XO"),
+            ("BITLSHIFT", "BIT.DESPLIZQDA"),
+            ("BITRSHIFT", "BIT.DESPLDCHA"),
+            ("COMPLEX", "COMPLEJO"),
+            ("IMSUM", "IM.SUM"),
+            ("IMPRODUCT", "IM.PRODUCT"),
+            ("IMREAL", "IM.REAL"),
+            ("IMAGINARY", "IMAGINARIO"),
+            ("IMABS", "IM.ABS"),
+            ("FIND", "ENCONTRAR"),
+            ("LEFT", "IZQUIERDA"),
+            ("LEN", "LARGO"),
+            ("LOWER", "MINUSC"),
+            ("PROPER", "NOMPROPIO"),
+            ("RIGHT", "DERECHA"),
+            ("SEARCH", "HALLAR"),
+            ("TRIM", "ESPACIOS"),
+            ("UPPER", "MAYUSC"),
+            ("ISBLANK", "ESBLANCO"),
+            ("ISERROR", "ESERROR"),
+            ("ISNUMBER", "ESNUMERO"),
+            ("ISTEXT", "ESTEXTO"),
+            ("NOW", "AHORA"),
+            ("DATEVALUE", "FECHANUMERO"),
+            ("NUMBERVALUE", "VALOR.NUMERO"),
+            ("TODAY", "HOY"),
+            ("FILTER", "FILTRAR"),
+            ("SORT", "ORDENAR"),
+            ("UNIQUE", "UNICOS"),
+            ("MATCH", "COINCIDIR"),
+            ("VLOOKUP", "BUSCARV"),
+        ],
+    },
+];
+
+/// Locale by language code, in any case
+pub fn locale(code: &str) -> Result<&'static FormulaLocale, String> {
+    LOCALES
+        .iter()
+        .find(|l| l.code.eq_ignore_ascii_case(code))
+        .ok_or_else(|| format!("Unknown formula locale '{}'", code))
+}
+
+impl FormulaLocale {
+    /// English spelling of a local word; only `TRUE`/`FALSE` outside calls
+    fn english_name(&self, word: &str, call: bool) -> Option<&'static str> {
+        let word = word.to_uppercase();
+        self.names
+            .iter()
+            .find(|&&(english, local)| local == word && call != is_boolean(english))
+            .map(|&(english, _)| english)
+    }
+
+    fn local_name(&self, english: &str) -> Option<&'static str> {
+        self.names
+            .iter()
+            .find(|&&(name, _)| name == english)
+            .map(|&(_, local)| local)
+    }
+}
+
+fn is_boolean(english: &str) -> bool {
+    english == "TRUE" || english == "FALSE"
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '_' | '$')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$')
+}
+
+/// Convert formula text written for `from` to the syntax of `to`
+pub fn translate(src: &str, from: &FormulaLocale, to: &FormulaLocale) -> String {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = String::with_capacity(src.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' | '[' => {
+                // Copy quoted text through its closing character; a doubled
+                // quote reopens the same text on the next round
+                let close = if c == '[' { ']' } else { c };
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == close)
+                    .map_or(chars.len(), |p| i + 1 + p + 1);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            _ if is_word_start(c) => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&ch| !is_word_char(ch))
+                    .map_or(chars.len(), |p| i + p);
+                let word: String = chars[i..end].iter().collect();
+                let call = chars[end..]
+                    .iter()
+                    .find(|ch| !ch.is_whitespace())
+                    .is_some_and(|&ch| ch == '(');
+                let english = match from.code {
+                    "en" => Some(word.to_uppercase()).filter(|w| call || is_boolean(w)),
+                    _ => from.english_name(&word, call).map(str::to_string),
+                };
+                match english.as_deref().and_then(|e| match to.code {
+                    "en" => Some(e),
+                    _ => to.local_name(e),
+                }) {
+                    Some(translated) if !word.eq_ignore_ascii_case(translated) => {
+                        out.push_str(translated)
+                    }
+                    _ => out.push_str(&word),
+                }
+                i = end;
+            }
+            _ if c.is_ascii_digit() => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|ch| !ch.is_ascii_digit())
+                    .map_or(chars.len(), |p| i + p);
+                out.extend(&chars[i..end]);
+                i = end;
+                let fraction = chars.get(i + 1).is_some_and(char::is_ascii_digit);
+                if chars.get(i) == Some(&from.decimal_separator) && fraction {
+                    out.push(to.decimal_separator);
+                    i += 1;
+                }
+            }
+            _ if c == from.argument_separator => {
+                out.push(to.argument_separator);
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Parse a formula written in the syntax of `locale`
+pub fn parse_localized(src: &str, locale: &FormulaLocale) -> Result<Expr, String> {
+    parse(&translate(src, locale, &ENGLISH))
+}
+
+/// Convert a formula between locale syntaxes
+///
+/// # Arguments
+/// * `formula` - Formula text
+/// * `from`, `to` - Locale codes: `en`, `de`, `fr` or `es`
+///
+/// # Returns
+/// StringResult with the translated formula
+///
+/// # Safety
+/// `formula`, `from` and `to` must be valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_translate_formula(
+    formula: *const c_char,
+    from: *const c_char,
+    to: *const c_char,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+    let (Some(from), Some(to)) = (str_arg(from), str_arg(to)) else {
+        return StringResult::error("Invalid locale encoding");
+    };
+
+    locale(from)
+        .and_then(|from| Ok(translate(formula, from, locale(to)?)))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_locales() {
+        let de = locale("DE").unwrap();
+        let german = "=WENN(länge([Name;Vorname])>1,5; \"a;b\"; falsch) + Summe1";
+        let english = translate(german, de, &ENGLISH);
+        assert_eq!(
+            english,
+            "=IF(LEN([Name;Vorname])>1.5, \"a;b\", FALSE) + Summe1"
+        );
+        assert_eq!(
+            parse_localized(german, de).unwrap(),
+            parse(&english).unwrap()
+        );
+        assert_eq!(
+            translate(&english, &ENGLISH, de),
+            "=WENN(LÄNGE([Name;Vorname])>1,5; \"a;b\"; FALSCH) + Summe1"
+        );
+
+        let fr = locale("fr").unwrap();
+        assert_eq!(
+            translate("=SI(ET(A1;B2);ALEA.ENTRE.BORNES(1;6);0,25)", fr, de),
+            "=WENN(UND(A1;B2);ZUFALLSBEREICH(1;6);0,25)"
+        );
+        // A column called like a local function is only translated when called
+        assert_eq!(
+            translate("=SI + SI(1;2;3)", fr, &ENGLISH),
+            "=SI + IF(1,2,3)"
+        );
+        assert!(locale("xx").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Formula expression language
+//!
+//! Spreadsheet-style expressions (`=IF(Amount > 100, "big", "small")`) are
+//! tokenized, parsed into an [`Expr`] tree once and then evaluated against an
+//! [`EvalContext`] that supplies column, name and cell values. The same
+//! grammar is reused for filter predicates and computed values.
+
+pub mod brackets;
+mod case;
+pub mod catalog;
+pub mod complete;
+mod complex;
+mod contact;
+mod convert;
+mod criteria;
+pub mod deps;
+pub mod diagnostics;
+pub mod eval;
+pub mod format;
+mod functions;
+pub mod highlight;
+mod ip;
+mod jsonpath;
+pub mod lexer;
+pub mod locale;
+pub mod parser;
+pub mod r1c1;
+pub mod rewrite;
+pub mod row_context;
+mod series;
+pub mod table_context;
+mod url;
+pub mod value;
+pub mod volatile;
+
+pub use eval::{evaluate, EvalContext};
+pub use parser::{parse, BinaryOp, CellRef, ColumnRef, Expr, UnaryOp};
+pub use value::{Array, ErrorValue, Value};
@@ -0,0 +1,796 @@
+//! Formula parser producing an expression tree
+//!
+//! Operator precedence follows Excel, lowest first: `OR`, `AND`, `NOT`,
+//! comparisons, `&`, `+ -`, `* /`, `^`, unary sign, postfix `%`. The infix
+//! `AND` / `OR` / `NOT` keywords are an extension for filter predicates; the
+//! function forms `AND(...)` keep working.
+
+use std::fmt;
+
+use super::lexer::{scan, ParseError, Token, TokenKind};
+use super::value::{format_number, ErrorValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Plus,
+    Percent,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+/// A single-cell reference in A1 notation (zero-based row/column)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellRef {
+    pub row: usize,
+    pub col: usize,
+    pub row_absolute: bool,
+    pub col_absolute: bool,
+}
+
+impl CellRef {
+    /// Parse `B3`, `$B$3`, `b$3`, ... (column letters up to XFD)
+    pub fn parse(text: &str) -> Option<CellRef> {
+        let (col_absolute, rest) = match text.strip_prefix('$') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let letters_end = rest.find(|c: char| !c.is_ascii_alphabetic())?;
+        let (letters, rest) = rest.split_at(letters_end);
+        let (row_absolute, digits) = match rest.strip_prefix('$') {
+            Some(digits) => (true, digits),
+            None => (false, rest),
+        };
+        if letters.is_empty() || letters.len() > 3 || digits.is_empty() {
+            return None;
+        }
+        if !digits.bytes().all(|b| b.is_ascii_digit()) || digits.starts_with('0') {
+            return None;
+        }
+
+        let col = column_from_letters(letters)?;
+        let row = digits.parse::<usize>().ok()?.checked_sub(1)?;
+        if col >= 16_384 || row >= 1_048_576 {
+            return None;
+        }
+
+        Some(CellRef {
+            row,
+            col,
+            row_absolute,
+            col_absolute,
+        })
+    }
+
+    /// Format back to A1 notation, keeping `$` markers
+    pub fn to_a1(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            if self.col_absolute { "$" } else { "" },
+            column_letters(self.col),
+            if self.row_absolute { "$" } else { "" },
+            self.row + 1
+        )
+    }
+}
+
+/// Whole-column reference endpoint, as in `A:C` or `$B:$B`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    pub col: usize,
+    pub absolute: bool,
+}
+
+impl ColumnRef {
+    /// Parse `B` or `$B`
+    pub fn parse(text: &str) -> Option<ColumnRef> {
+        let (absolute, letters) = match text.strip_prefix('$') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if letters.len() > 3 || !letters.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+        let col = column_from_letters(letters)?;
+        (col < 16_384).then_some(ColumnRef { col, absolute })
+    }
+
+    pub fn to_a1(&self) -> String {
+        format!(
+            "{}{}",
+            if self.absolute { "$" } else { "" },
+            column_letters(self.col)
+        )
+    }
+}
+
+/// Zero-based column index to spreadsheet letters (0 -> A, 26 -> AA)
+pub fn column_letters(mut col: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (col % 26) as u8);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Spreadsheet letters to zero-based column index (A -> 0, AA -> 26)
+pub fn column_from_letters(letters: &str) -> Option<usize> {
+    if letters.is_empty() {
+        return None;
+    }
+    let mut col = 0usize;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        col = col
+            .checked_mul(26)?
+            .checked_add((c.to_ascii_uppercase() as u8 - b'A') as usize + 1)?;
+    }
+    Some(col - 1)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    /// Error literal, e.g. `#REF!` left behind by a deleted reference
+    Error(ErrorValue),
+    /// Bare name: a column header or defined name, resolved by the context
+    Name(String),
+    /// Bracketed column name, `[Order Total]`
+    Column(String),
+    Cell(CellRef),
+    /// Whole spill range of the array formula anchored at a cell, `A1#`
+    Spill(CellRef),
+    Range(CellRef, CellRef),
+    /// Whole columns, `A:A` or `B:D`
+    ColumnRange(ColumnRef, ColumnRef),
+    /// Reference into another sheet, `Sheet2!A1:B3` or `'Q1 Sales'!Amount`
+    Sheet(String, Box<Expr>),
+    /// 3-D reference: the same reference on every sheet from the first to
+    /// the last, `Sheet1:Sheet5!B2`
+    Sheets(String, String, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Function call with the name upper-cased
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    /// Visit this node and every node below it, parents first
+    ///
+    /// References into other sheets are leaves: their inner reference
+    /// belongs to that sheet and is not visited.
+    pub fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Expr)) {
+        f(self);
+        match self {
+            Expr::Unary(_, operand) => operand.visit(f),
+            Expr::Binary(_, left, right) => {
+                left.visit(f);
+                right.visit(f);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.visit(f)),
+            _ => {}
+        }
+    }
+}
+
+/// Sheet name as written before `!`, quoted when it is not a plain word
+pub fn quote_sheet(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+impl BinaryOp {
+    pub(super) fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+            BinaryOp::Concat => "&",
+            BinaryOp::Eq => "=",
+            BinaryOp::Ne => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => " AND ",
+            BinaryOp::Or => " OR ",
+        }
+    }
+
+    /// Binding strength, matching the parser's levels
+    pub(super) fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => 4,
+            BinaryOp::Concat => 5,
+            BinaryOp::Add | BinaryOp::Sub => 6,
+            BinaryOp::Mul | BinaryOp::Div => 7,
+            BinaryOp::Pow => 8,
+        }
+    }
+}
+
+impl Expr {
+    pub(super) fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(op, ..) => op.precedence(),
+            Expr::Unary(UnaryOp::Not, _) => 3,
+            Expr::Unary(UnaryOp::Neg | UnaryOp::Plus, _) => 9,
+            Expr::Unary(UnaryOp::Percent, _) => 10,
+            _ => 11,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Formula text without the leading `=`, parenthesized only where needed;
+/// parsing it gives back the same tree
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => f.write_str(&format_number(*n)),
+            Expr::Text(text) => write!(f, "\"{}\"", text.replace('"', "\"\"")),
+            Expr::Bool(b) => f.write_str(if *b { "TRUE" } else { "FALSE" }),
+            Expr::Error(e) => f.write_str(e.code()),
+            Expr::Name(name) => f.write_str(name),
+            Expr::Column(name) => write!(f, "[{}]", name),
+            Expr::Cell(cell) => f.write_str(&cell.to_a1()),
+            Expr::Spill(cell) => write!(f, "{}#", cell.to_a1()),
+            Expr::Range(start, end) => write!(f, "{}:{}", start.to_a1(), end.to_a1()),
+            Expr::ColumnRange(start, end) => write!(f, "{}:{}", start.to_a1(), end.to_a1()),
+            Expr::Sheet(name, reference) => write!(f, "{}!{}", quote_sheet(name), reference),
+            Expr::Sheets(first, last, reference) => {
+                let span = format!("{}:{}", first, last);
+                if quote_sheet(first) == *first && quote_sheet(last) == *last {
+                    write!(f, "{}!{}", span, reference)
+                } else {
+                    write!(f, "{}!{}", quote_sheet(&span), reference)
+                }
+            }
+            Expr::Unary(UnaryOp::Percent, operand) => {
+                operand.fmt_operand(f, 10)?;
+                f.write_str("%")
+            }
+            Expr::Unary(op, operand) => {
+                let (prefix, min) = match op {
+                    UnaryOp::Not => ("NOT ", 3),
+                    UnaryOp::Plus => ("+", 9),
+                    _ => ("-", 9),
+                };
+                f.write_str(prefix)?;
+                operand.fmt_operand(f, min)
+            }
+            Expr::Binary(op, left, right) => {
+                // Operators are left-associative, so only the right side
+                // needs parentheses at equal precedence
+                left.fmt_operand(f, op.precedence())?;
+                f.write_str(op.symbol())?;
+                right.fmt_operand(f, op.precedence() + 1)
+            }
+            Expr::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// Parse a formula or bare expression; a leading `=` is optional
+pub fn parse(src: &str) -> Result<Expr, String> {
+    Ok(parse_detailed(src)?)
+}
+
+/// [`parse`] with the error's code and byte range in `src`
+pub fn parse_detailed(src: &str) -> Result<Expr, ParseError> {
+    let trimmed = src.trim_start();
+    let offset = src.len() - trimmed.len();
+    let body = trimmed.strip_prefix('=').unwrap_or(trimmed);
+    let offset = offset + (trimmed.len() - body.len());
+
+    let mut tokens = scan(body, false).map_err(|mut e| {
+        e.span = e.span.start + offset..e.span.end + offset;
+        e
+    })?;
+    for token in &mut tokens {
+        token.span = token.span.start + offset..token.span.end + offset;
+    }
+    if tokens.is_empty() {
+        return Err(ParseError {
+            code: "empty-formula",
+            message: "Empty formula".to_string(),
+            span: src.len()..src.len(),
+        });
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: src.len(),
+    };
+    let expr = parser.or_expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(parser.error(
+            "unexpected-token",
+            token.span.start,
+            format!("Unexpected token at position {}", token.span.start),
+        )),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<&TokenKind> {
+        self.peek().map(|t| &t.kind)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map_or(self.end, |t| t.span.start)
+    }
+
+    /// Error about the token starting at `position` (or the end of the text)
+    fn error(&self, code: &'static str, position: usize, message: String) -> ParseError {
+        let span = self
+            .tokens
+            .iter()
+            .find(|t| t.span.start == position)
+            .map_or(position..position, |t| t.span.clone());
+        ParseError {
+            code,
+            message,
+            span,
+        }
+    }
+
+    /// True when the next token is the keyword operator `word` (not a call)
+    fn at_keyword(&self, word: &str) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Ident(name)) if name.eq_ignore_ascii_case(word))
+            && !matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                Some(TokenKind::LParen)
+            )
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<(), ParseError> {
+        if self.peek_kind() == Some(&kind) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            let position = self.position();
+            let message = format!("Expected {} at position {}", what, position);
+            Err(self.error("expected-token", position, message))
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.and_expr()?;
+        while self.at_keyword("OR") {
+            self.pos += 1;
+            let right = self.and_expr()?;
+            left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.not_expr()?;
+        while self.at_keyword("AND") {
+            self.pos += 1;
+            let right = self.not_expr()?;
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, ParseError> {
+        if self.at_keyword("NOT") {
+            self.pos += 1;
+            let operand = self.not_expr()?;
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(operand)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.concat()?;
+        loop {
+            let op = match self.peek_kind() {
+                Some(TokenKind::Eq) => BinaryOp::Eq,
+                Some(TokenKind::Ne) => BinaryOp::Ne,
+                Some(TokenKind::Lt) => BinaryOp::Lt,
+                Some(TokenKind::Le) => BinaryOp::Le,
+                Some(TokenKind::Gt) => BinaryOp::Gt,
+                Some(TokenKind::Ge) => BinaryOp::Ge,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.concat()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn concat(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.additive()?;
+        while self.peek_kind() == Some(&TokenKind::Ampersand) {
+            self.pos += 1;
+            let right = self.additive()?;
+            left = Expr::Binary(BinaryOp::Concat, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek_kind() {
+                Some(TokenKind::Plus) => BinaryOp::Add,
+                Some(TokenKind::Minus) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.term()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.power()?;
+        loop {
+            let op = match self.peek_kind() {
+                Some(TokenKind::Star) => BinaryOp::Mul,
+                Some(TokenKind::Slash) => BinaryOp::Div,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.power()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while self.peek_kind() == Some(&TokenKind::Caret) {
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(BinaryOp::Pow, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let op = match self.peek_kind() {
+            Some(TokenKind::Minus) => UnaryOp::Neg,
+            Some(TokenKind::Plus) => UnaryOp::Plus,
+            _ => return self.postfix(),
+        };
+        self.pos += 1;
+        let operand = self.unary()?;
+        Ok(Expr::Unary(op, Box::new(operand)))
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        while self.peek_kind() == Some(&TokenKind::Percent) {
+            self.pos += 1;
+            expr = Expr::Unary(UnaryOp::Percent, Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let Some(token) = self.advance() else {
+            let message = "Unexpected end of formula".to_string();
+            return Err(self.error("unexpected-end", position, message));
+        };
+
+        match token.kind {
+            TokenKind::Number(value) => Ok(Expr::Number(value)),
+            TokenKind::Text(text) => Ok(Expr::Text(text)),
+            TokenKind::Error(error) => Ok(Expr::Error(error)),
+            TokenKind::Column(name) => Ok(Expr::Column(name)),
+            // Sheet names cannot contain ':', so a quoted one is a 3-D span
+            TokenKind::Sheet(sheet) => match sheet.split_once(':') {
+                Some((first, last)) => {
+                    let reference = self.sheet_reference()?;
+                    Ok(Expr::Sheets(first.into(), last.into(), reference))
+                }
+                None => Ok(Expr::Sheet(sheet, self.sheet_reference()?)),
+            },
+            TokenKind::LParen => {
+                let expr = self.or_expr()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                Ok(expr)
+            }
+            TokenKind::Ident(name) => {
+                if let Some(last) = self.sheet_span_end() {
+                    self.pos += 2;
+                    return Ok(Expr::Sheets(name, last, self.sheet_reference()?));
+                }
+                if self.peek_kind() == Some(&TokenKind::LParen) {
+                    self.pos += 1;
+                    return self.call(name);
+                }
+                if name.eq_ignore_ascii_case("TRUE") {
+                    return Ok(Expr::Bool(true));
+                }
+                if name.eq_ignore_ascii_case("FALSE") {
+                    return Ok(Expr::Bool(false));
+                }
+                if let Some(start) = CellRef::parse(&name) {
+                    return self.range_tail(start);
+                }
+                match ColumnRef::parse(&name) {
+                    Some(start) if self.peek_kind() == Some(&TokenKind::Colon) => {
+                        self.column_range_tail(start)
+                    }
+                    _ => Ok(Expr::Name(name)),
+                }
+            }
+            _ => Err(self.error(
+                "unexpected-token",
+                position,
+                format!("Unexpected token at position {}", position),
+            )),
+        }
+    }
+
+    /// Last sheet of a 3-D span when the next tokens are `:Sheet5!`
+    fn sheet_span_end(&self) -> Option<String> {
+        if self.peek_kind() != Some(&TokenKind::Colon) {
+            return None;
+        }
+        match self.tokens.get(self.pos + 1).map(|t| &t.kind) {
+            Some(TokenKind::Sheet(last)) => Some(last.clone()),
+            _ => None,
+        }
+    }
+
+    /// The reference after a sheet prefix
+    fn sheet_reference(&mut self) -> Result<Box<Expr>, ParseError> {
+        let position = self.position();
+        match self.primary()? {
+            reference @ (Expr::Cell(_)
+            | Expr::Spill(_)
+            | Expr::Range(..)
+            | Expr::ColumnRange(..)
+            | Expr::Name(_)
+            | Expr::Column(_)) => Ok(Box::new(reference)),
+            _ => Err(self.error(
+                "expected-reference",
+                position,
+                format!("Expected a reference at position {}", position),
+            )),
+        }
+    }
+
+    fn range_tail(&mut self, start: CellRef) -> Result<Expr, ParseError> {
+        match self.peek_kind() {
+            Some(TokenKind::Colon) => self.pos += 1,
+            Some(TokenKind::Hash) => {
+                self.pos += 1;
+                return Ok(Expr::Spill(start));
+            }
+            _ => return Ok(Expr::Cell(start)),
+        }
+
+        let position = self.position();
+        match self.advance().map(|t| t.kind) {
+            Some(TokenKind::Ident(name)) => match CellRef::parse(&name) {
+                Some(end) => Ok(Expr::Range(start, end)),
+                None => Err(self.invalid_range_end(position)),
+            },
+            _ => Err(self.invalid_range_end(position)),
+        }
+    }
+
+    fn column_range_tail(&mut self, start: ColumnRef) -> Result<Expr, ParseError> {
+        self.pos += 1;
+
+        let position = self.position();
+        match self.advance().map(|t| t.kind) {
+            Some(TokenKind::Ident(name)) => match ColumnRef::parse(&name) {
+                Some(end) => Ok(Expr::ColumnRange(start, end)),
+                None => Err(self.invalid_range_end(position)),
+            },
+            _ => Err(self.invalid_range_end(position)),
+        }
+    }
+
+    fn invalid_range_end(&self, position: usize) -> ParseError {
+        let message = format!("Invalid range end at position {}", position);
+        self.error("invalid-range", position, message)
+    }
+
+    fn call(&mut self, name: String) -> Result<Expr, ParseError> {
+        let mut args = Vec::new();
+        if self.peek_kind() == Some(&TokenKind::RParen) {
+            self.pos += 1;
+            return Ok(Expr::Call(name.to_uppercase(), args));
+        }
+
+        loop {
+            args.push(self.or_expr()?);
+            match self.peek_kind() {
+                Some(TokenKind::Comma) => self.pos += 1,
+                Some(TokenKind::RParen) => {
+                    self.pos += 1;
+                    return Ok(Expr::Call(name.to_uppercase(), args));
+                }
+                _ => {
+                    let position = self.position();
+                    let message = format!("Expected ',' or ')' at position {}", position);
+                    return Err(self.error("expected-token", position, message));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(value: f64) -> Box<Expr> {
+        Box::new(Expr::Number(value))
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            parse("=1+2*3^2").unwrap(),
+            Expr::Binary(
+                BinaryOp::Add,
+                num(1.0),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    num(2.0),
+                    Box::new(Expr::Binary(BinaryOp::Pow, num(3.0), num(2.0)))
+                ))
+            )
+        );
+
+        let Expr::Binary(BinaryOp::Or, left, _) = parse("a > 1 AND NOT b OR c").unwrap() else {
+            panic!("expected OR at the root");
+        };
+        assert!(matches!(*left, Expr::Binary(BinaryOp::And, _, _)));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for (formula, text) in [
+            ("=(1+2)*3", "(1+2)*3"),
+            ("=1-(2-3)", "1-(2-3)"),
+            ("=-A1^2%", "-A1^2%"),
+            ("a > 1 and not (b or c)", "a>1 AND NOT(b OR c)"),
+            (
+                "=if([Sale Region]=\"E\"\"U\", 'Q1 Sales'!B:B, 1.5e2)",
+                "IF([Sale Region]=\"E\"\"U\",'Q1 Sales'!B:B,150)",
+            ),
+            (
+                "=SUM(Jan:Mar!B2, 'Q1:Q4 2024'!A1:B2)",
+                "SUM(Jan:Mar!B2,'Q1:Q4 2024'!A1:B2)",
+            ),
+            ("=SUM(b2#) + Data!$A$1#", "SUM(B2#)+Data!$A$1#"),
+        ] {
+            let expr = parse(formula).unwrap();
+            assert_eq!(expr.to_string(), text);
+            assert_eq!(parse(text).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_references_and_calls() {
+        assert_eq!(
+            parse("SUM($A$1:b10, Total)").unwrap(),
+            Expr::Call(
+                "SUM".into(),
+                vec![
+                    Expr::Range(
+                        CellRef {
+                            row: 0,
+                            col: 0,
+                            row_absolute: true,
+                            col_absolute: true
+                        },
+                        CellRef::parse("B10").unwrap()
+                    ),
+                    Expr::Name("Total".into()),
+                ]
+            )
+        );
+        assert_eq!(column_letters(27), "AB");
+        assert_eq!(CellRef::parse("$AB$2").unwrap().to_a1(), "$AB$2");
+        assert_eq!(
+            parse("A:$C").unwrap(),
+            Expr::ColumnRange(
+                ColumnRef {
+                    col: 0,
+                    absolute: false
+                },
+                ColumnRef {
+                    col: 2,
+                    absolute: true
+                }
+            )
+        );
+        assert_eq!(
+            parse("'My Data'!B2:C3").unwrap(),
+            Expr::Sheet(
+                "My Data".into(),
+                Box::new(Expr::Range(
+                    CellRef::parse("B2").unwrap(),
+                    CellRef::parse("C3").unwrap()
+                ))
+            )
+        );
+        assert!(parse("Sheet2!SUM(A1)").is_err());
+        assert!(parse("SUM(1,").is_err());
+        assert!(parse("(1 + 2").is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! R1C1 reference notation
+//!
+//! `R2C3` is the absolute cell `$C$2`, `R[-1]C` the cell one row up in the
+//! formula's own column and `C[1]` / `C2:C4` are whole columns. Formulas are
+//! stored in A1 notation, so R1C1 is a view: [`to_r1c1`] renders a formula
+//! relative to the cell holding it and [`to_a1`] reads one back. References
+//! that would fall outside the sheet become `#REF!`.
+
+use std::os::raw::c_char;
+
+use super::lexer::{tokenize, TokenKind};
+use super::parser::{column_letters, CellRef, ColumnRef};
+use super::rewrite::is_formula;
+use super::value::ErrorValue;
+use crate::ffi::str_arg;
+use crate::StringResult;
+
+/// One coordinate of an R1C1 reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    /// Offset from the formula's own row or column, `R[-1]` (`R` alone is 0)
+    Relative(isize),
+    /// One-based index, `R3`
+    Absolute(usize),
+}
+
+impl Axis {
+    fn render(index: usize, absolute: bool, origin: usize) -> String {
+        let offset = index as isize - origin as isize;
+        match (absolute, offset) {
+            (true, _) => (index + 1).to_string(),
+            (false, 0) => String::new(),
+            (false, offset) => format!("[{}]", offset),
+        }
+    }
+
+    /// Zero-based index seen from `origin`
+    fn resolve(self, origin: usize) -> Option<(usize, bool)> {
+        match self {
+            Axis::Relative(offset) => origin.checked_add_signed(offset).map(|i| (i, false)),
+            Axis::Absolute(index) => index.checked_sub(1).map(|i| (i, true)),
+        }
+    }
+}
+
+/// A cell in R1C1 notation relative to (`row`, `col`)
+pub fn cell_to_r1c1(cell: CellRef, row: usize, col: usize) -> String {
+    format!(
+        "R{}C{}",
+        Axis::render(cell.row, cell.row_absolute, row),
+        Axis::render(cell.col, cell.col_absolute, col)
+    )
+}
+
+fn column_to_r1c1(column: ColumnRef, col: usize) -> String {
+    format!("C{}", Axis::render(column.col, column.absolute, col))
+}
+
+/// Render the A1 references of a formula stored at (`row`, `col`) in R1C1
+/// notation; text that is not a formula or does not tokenize is unchanged
+pub fn to_r1c1(formula: &str, row: usize, col: usize) -> String {
+    if !is_formula(formula) {
+        return formula.to_string();
+    }
+    let offset = formula.find('=').unwrap_or(0) + 1;
+    let Ok(tokens) = tokenize(&formula[offset..]) else {
+        return formula.to_string();
+    };
+    let ident = |i: usize| match tokens.get(i).map(|t| &t.kind) {
+        Some(TokenKind::Ident(name)) => Some(name.as_str()),
+        _ => None,
+    };
+    let kind = |i: usize| tokens.get(i).map(|t| &t.kind);
+
+    let mut out = String::with_capacity(formula.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some(name) = ident(i).filter(|_| kind(i + 1) != Some(&TokenKind::LParen)) else {
+            i += 1;
+            continue;
+        };
+        if kind(i + 1) == Some(&TokenKind::Colon)
+            && matches!(kind(i + 2), Some(TokenKind::Sheet(_)))
+        {
+            // First sheet of a 3-D span, not a reference
+            i += 3;
+            continue;
+        }
+        let columns = (kind(i + 1) == Some(&TokenKind::Colon))
+            .then(|| ident(i + 2).and_then(ColumnRef::parse))
+            .flatten()
+            .zip(ColumnRef::parse(name));
+        let (replacement, len) = match (CellRef::parse(name), columns) {
+            (Some(cell), _) => (cell_to_r1c1(cell, row, col), 1),
+            (None, Some((end, start))) => (
+                format!(
+                    "{}:{}",
+                    column_to_r1c1(start, col),
+                    column_to_r1c1(end, col)
+                ),
+                3,
+            ),
+            (None, None) => {
+                i += 1;
+                continue;
+            }
+        };
+        let start = offset + tokens[i].span.start;
+        out.push_str(&formula[copied..start]);
+        out.push_str(&replacement);
+        copied = offset + tokens[i + len - 1].span.end;
+        i += len;
+    }
+    out.push_str(&formula[copied..]);
+    out
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$')
+}
+
+/// `R`/`C` coordinate at the start of `text`: the axis and its length
+fn axis(text: &str, letter: char) -> Option<(Axis, usize)> {
+    let rest = text.strip_prefix(|c: char| c.eq_ignore_ascii_case(&letter))?;
+    if let Some(inner) = rest.strip_prefix('[') {
+        let close = inner.find(']')?;
+        let offset = inner[..close].parse().ok()?;
+        return Some((Axis::Relative(offset), close + 3));
+    }
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match digits {
+        0 => Some((Axis::Relative(0), 1)),
+        _ => Some((Axis::Absolute(rest[..digits].parse().ok()?), digits + 1)),
+    }
+}
+
+/// A1 text for the R1C1 reference (cell, or whole column or column range)
+/// at the start of `text`, with the length read
+fn reference_to_a1(text: &str, row: usize, col: usize) -> Option<(String, usize)> {
+    let ends_word =
+        |at: usize| !text[at..].starts_with(|c: char| is_word_char(c) || c == '(' || c == '[');
+    let refs = ErrorValue::Ref.code().to_string();
+
+    if let Some((rows, r)) = axis(text, 'R') {
+        let (cols, c) = axis(&text[r..], 'C')?;
+        if !ends_word(r + c) {
+            return None;
+        }
+        let cell = rows.resolve(row).zip(cols.resolve(col)).and_then(
+            |((row, row_absolute), (col, col_absolute))| {
+                let cell = CellRef {
+                    row,
+                    col,
+                    row_absolute,
+                    col_absolute,
+                };
+                CellRef::parse(&cell.to_a1()).map(|_| cell.to_a1())
+            },
+        );
+        return Some((cell.unwrap_or(refs), r + c));
+    }
+
+    let column = |text: &str| {
+        let (axis, len) = axis(text, 'C').filter(|&(axis, _)| axis != Axis::Relative(0))?;
+        let a1 = axis.resolve(col).and_then(|(index, absolute)| {
+            let column = ColumnRef::parse(&column_letters(index))?;
+            Some(ColumnRef { absolute, ..column }.to_a1())
+        });
+        Some((a1, len))
+    };
+    let (start, mut len) = column(text).filter(|&(_, len)| ends_word(len))?;
+    let mut end = start.clone();
+    if let Some((second, more)) = text[len..]
+        .strip_prefix(':')
+        .and_then(column)
+        .filter(|&(_, more)| ends_word(len + 1 + more))
+    {
+        end = second;
+        len += 1 + more;
+    }
+    let range = start
+        .zip(end)
+        .map(|(start, end)| format!("{}:{}", start, end));
+    Some((range.unwrap_or(refs), len))
+}
+
+/// Read the R1C1 references of a formula entered at (`row`, `col`) back to
+/// A1 notation; text that is not a formula is unchanged
+pub fn to_a1(formula: &str, row: usize, col: usize) -> String {
+    if !is_formula(formula) {
+        return formula.to_string();
+    }
+    let mut out = String::with_capacity(formula.len());
+    let mut rest = formula;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '"' | '\'' => rest[1..].find(c).map_or(rest.len(), |p| p + 2),
+            '[' => rest.find(']').map_or(rest.len(), |p| p + 1),
+            'R' | 'r' | 'C' | 'c' => match reference_to_a1(rest, row, col) {
+                Some((a1, len)) => {
+                    out.push_str(&a1);
+                    rest = &rest[len..];
+                    continue;
+                }
+                None => rest.len() - rest.trim_start_matches(is_word_char).len(),
+            },
+            _ if is_word_char(c) => rest.len() - rest.trim_start_matches(is_word_char).len(),
+            _ => c.len_utf8(),
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Render a formula stored at a cell in R1C1 notation
+///
+/// # Arguments
+/// * `formula` - Formula text in A1 notation
+/// * `row`, `col` - Zero-based cell holding the formula
+///
+/// # Returns
+/// StringResult with the formula in R1C1 notation
+///
+/// # Safety
+/// `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_formula_to_r1c1(
+    formula: *const c_char,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    StringResult::success(&to_r1c1(formula, row, col))
+}
+
+/// Convert a formula entered at a cell in R1C1 notation to A1 notation
+///
+/// # Arguments
+/// * `formula` - Formula text in R1C1 notation
+/// * `row`, `col` - Zero-based cell holding the formula
+///
+/// # Returns
+/// StringResult with the formula in A1 notation
+///
+/// # Safety
+/// `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_formula_to_a1(
+    formula: *const c_char,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    StringResult::success(&to_a1(formula, row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_r1c1_round_trip() {
+        let a1 = "=SUM($A$1:B2)*Sheet2!C$3 + COUNT(A:$C) + LOG10(Rate) & \"R1C1\"";
+        let r1c1 = to_r1c1(a1, 1, 1);
+        assert_eq!(
+            r1c1,
+            "=SUM(R1C1:RC)*Sheet2!R3C[1] + COUNT(C[-1]:C3) + LOG10(Rate) & \"R1C1\""
+        );
+        assert_eq!(to_a1(&r1c1, 1, 1), a1);
+
+        assert_eq!(to_a1("=r[-1]c + C[2] + [RC]", 4, 0), "=A4 + C:C + [RC]");
+        assert_eq!(to_a1("=R[-1]C + C[-1]", 0, 0), "=#REF! + #REF!");
+        assert_eq!(to_a1("R1C1", 0, 0), "R1C1");
+    }
+}
@@ -0,0 +1,264 @@
+//! Rewriting the references inside formula text
+//!
+//! Structural edits, paste and sort move cells around; formulas stored as
+//! cell text (`=B2*2`) must be rewritten so they keep pointing at the same
+//! data. Rewriting works on token spans, so everything except the rewritten
+//! references (spacing, case, literals) is preserved. References qualified
+//! with a sheet name point elsewhere and are left alone.
+
+use super::lexer::{tokenize, TokenKind};
+use super::parser::{CellRef, ColumnRef};
+use super::value::ErrorValue;
+
+/// A same-sheet reference found in a formula
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    Cell(CellRef),
+    Range(CellRef, CellRef),
+    Columns(ColumnRef, ColumnRef),
+}
+
+impl Reference {
+    pub fn to_a1(&self) -> String {
+        match self {
+            Reference::Cell(cell) => cell.to_a1(),
+            Reference::Range(start, end) => format!("{}:{}", start.to_a1(), end.to_a1()),
+            Reference::Columns(start, end) => format!("{}:{}", start.to_a1(), end.to_a1()),
+        }
+    }
+}
+
+/// True for cell text holding a formula
+pub fn is_formula(text: &str) -> bool {
+    text.trim_start().starts_with('=') && text.trim().len() > 1
+}
+
+/// Rewrite every same-sheet reference of a formula
+///
+/// `f` returns the replacement, or `None` when the target no longer exists
+/// and the reference becomes `#REF!`. Text that is not a formula, or does
+/// not tokenize, is returned unchanged.
+pub fn rewrite_references(
+    formula: &str,
+    mut f: impl FnMut(Reference) -> Option<Reference>,
+) -> String {
+    if !is_formula(formula) {
+        return formula.to_string();
+    }
+    let offset = formula.find('=').unwrap_or(0) + 1;
+    let Ok(tokens) = tokenize(&formula[offset..]) else {
+        return formula.to_string();
+    };
+
+    let ident = |i: usize| match tokens.get(i).map(|t| &t.kind) {
+        Some(TokenKind::Ident(name)) => Some(name.as_str()),
+        _ => None,
+    };
+    let kind = |i: usize| tokens.get(i).map(|t| &t.kind);
+
+    let mut out = String::with_capacity(formula.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if ident(i).is_some()
+            && kind(i + 1) == Some(&TokenKind::Colon)
+            && matches!(kind(i + 2), Some(TokenKind::Sheet(_)))
+        {
+            // First sheet of a 3-D span; the rest is skipped below
+            i += 2;
+            continue;
+        }
+        if matches!(kind(i), Some(TokenKind::Sheet(_))) {
+            // Skip the other sheet's reference, including a range end
+            i += if kind(i + 2) == Some(&TokenKind::Colon) {
+                4
+            } else {
+                2
+            };
+            continue;
+        }
+        let Some(name) = ident(i) else {
+            i += 1;
+            continue;
+        };
+        if kind(i + 1) == Some(&TokenKind::LParen) {
+            i += 1;
+            continue;
+        }
+
+        let range_end = (kind(i + 1) == Some(&TokenKind::Colon))
+            .then(|| ident(i + 2))
+            .flatten();
+        let (reference, len) = match (CellRef::parse(name), range_end) {
+            (Some(start), Some(end)) if CellRef::parse(end).is_some() => (
+                Reference::Range(start, CellRef::parse(end).unwrap_or(start)),
+                3,
+            ),
+            (Some(cell), _) => (Reference::Cell(cell), 1),
+            (None, Some(end)) => match (ColumnRef::parse(name), ColumnRef::parse(end)) {
+                (Some(start), Some(end)) => (Reference::Columns(start, end), 3),
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            },
+            (None, None) => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let span = offset + tokens[i].span.start..offset + tokens[i + len - 1].span.end;
+        let replacement = match f(reference) {
+            Some(new) if new == reference => None,
+            Some(new) => Some(new.to_a1()),
+            None => Some(ErrorValue::Ref.code().to_string()),
+        };
+        if let Some(replacement) = replacement {
+            out.push_str(&formula[copied..span.start]);
+            out.push_str(&replacement);
+            copied = span.end;
+        }
+        i += len;
+    }
+    out.push_str(&formula[copied..]);
+    out
+}
+
+/// Where a row or column index goes after `count` indices are inserted at
+/// `at` (negative `count` deletes `at..at - count`); `None` when deleted
+pub fn shift_index(index: usize, at: usize, count: isize) -> Option<usize> {
+    if index < at {
+        return Some(index);
+    }
+    if count >= 0 {
+        return Some(index + count as usize);
+    }
+    let removed = count.unsigned_abs();
+    if index < at + removed {
+        None
+    } else {
+        Some(index - removed)
+    }
+}
+
+/// Shift the ends of an inclusive span like [`shift_index`]; a span that
+/// loses only some indices shrinks, one that loses all is `None`
+pub fn shift_span(first: usize, last: usize, at: usize, count: isize) -> Option<(usize, usize)> {
+    let (first, last) = (first.min(last), first.max(last));
+    match (shift_index(first, at, count), shift_index(last, at, count)) {
+        (Some(a), Some(b)) => Some((a, b)),
+        (None, Some(b)) => Some((at, b)),
+        (Some(a), None) => Some((a, at - 1)),
+        (None, None) => None,
+    }
+}
+
+/// Reference adjusted for rows (`rows`) or columns inserted or deleted at
+/// `at`, following [`shift_index`] for the count
+pub fn shift_reference(
+    reference: Reference,
+    at: usize,
+    count: isize,
+    rows: bool,
+) -> Option<Reference> {
+    let cell = |cell: CellRef, index: usize| {
+        let mut cell = cell;
+        if rows {
+            cell.row = index;
+        } else {
+            cell.col = index;
+        }
+        cell
+    };
+    let index = |cell: &CellRef| if rows { cell.row } else { cell.col };
+
+    match reference {
+        Reference::Cell(c) => {
+            shift_index(index(&c), at, count).map(|i| Reference::Cell(cell(c, i)))
+        }
+        Reference::Range(start, end) => {
+            let (start, end) = if index(&start) <= index(&end) {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            let (first, last) = shift_span(index(&start), index(&end), at, count)?;
+            Some(Reference::Range(cell(start, first), cell(end, last)))
+        }
+        Reference::Columns(..) if rows => Some(reference),
+        Reference::Columns(start, end) => {
+            let (first, last) = shift_span(start.col, end.col, at, count)?;
+            Some(Reference::Columns(
+                ColumnRef {
+                    col: first,
+                    ..start
+                },
+                ColumnRef { col: last, ..end },
+            ))
+        }
+    }
+}
+
+/// Reference as seen from a formula copied `rows` down and `cols` right:
+/// relative parts move, `$` parts stay; `None` when it falls off the grid
+pub fn offset_reference(reference: Reference, rows: isize, cols: isize) -> Option<Reference> {
+    let shift = |index: usize, by: isize, absolute: bool| {
+        if absolute {
+            Some(index)
+        } else {
+            index.checked_add_signed(by)
+        }
+    };
+    let cell = |c: CellRef| {
+        Some(CellRef {
+            row: shift(c.row, rows, c.row_absolute)?,
+            col: shift(c.col, cols, c.col_absolute)?,
+            ..c
+        })
+    };
+    let column = |c: ColumnRef| {
+        Some(ColumnRef {
+            col: shift(c.col, cols, c.absolute)?,
+            ..c
+        })
+    };
+
+    Some(match reference {
+        Reference::Cell(c) => Reference::Cell(cell(c)?),
+        Reference::Range(start, end) => Reference::Range(cell(start)?, cell(end)?),
+        Reference::Columns(start, end) => Reference::Columns(column(start)?, column(end)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift_references() {
+        let insert = |f: &str| rewrite_references(f, |r| shift_reference(r, 2, 2, true));
+        assert_eq!(insert("=A1 + $B$3 * sum(A2:A5)"), "=A1 + $B$5 * sum(A2:A7)");
+        assert_eq!(
+            insert("=Other!A5 + LOG10(A9) + A:A"),
+            "=Other!A5 + LOG10(A11) + A:A"
+        );
+
+        let delete = |f: &str| rewrite_references(f, |r| shift_reference(r, 1, -2, true));
+        assert_eq!(delete("=A2+A4"), "=#REF!+A2");
+        assert_eq!(delete("=SUM(B1:B3, C2:C3)"), "=SUM(B1:B1, #REF!)");
+
+        let delete_col = |f: &str| rewrite_references(f, |r| shift_reference(r, 1, -1, false));
+        assert_eq!(delete_col("=B1 & C1 & A:C"), "=#REF! & B1 & A:B");
+        assert_eq!(delete_col("plain B1 text"), "plain B1 text");
+    }
+
+    #[test]
+    fn test_offset_references() {
+        let paste =
+            |f: &str, rows, cols| rewrite_references(f, |r| offset_reference(r, rows, cols));
+        assert_eq!(paste("=A1*$B$1+B$2+$C3", 2, 1), "=B3*$B$1+C$2+$C5");
+        assert_eq!(paste("=SUM(A1:A3)", -1, 0), "=SUM(#REF!)");
+        assert_eq!(paste("=SUM(B:B)", 0, 2), "=SUM(D:D)");
+    }
+}
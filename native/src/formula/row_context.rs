@@ -0,0 +1,201 @@
+//! Evaluating an expression once per table row
+//!
+//! This is the one grammar behind filter predicates, formula validation
+//! rules, computed columns and conditional formatting: `[Column Name]` or a
+//! bare header reads the current row's cell. Computed columns read as their
+//! evaluated values, stored columns as their cell text.
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+
+use super::eval::{evaluate, EvalContext};
+use super::parser::{parse, CellRef, Expr};
+use super::value::{ErrorValue, Value};
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+/// Column names used by an expression, resolved to indices once up front
+///
+/// Unknown names are reported when binding instead of silently evaluating
+/// to `#NAME?` on every row.
+#[derive(Debug, Clone, Default)]
+pub struct RowBinding {
+    columns: HashMap<String, usize>,
+}
+
+impl RowBinding {
+    pub fn new(table: &TesseraTable, expr: &Expr) -> Result<Self, String> {
+        let mut columns = HashMap::new();
+        let mut missing = None;
+
+        expr.visit(&mut |node| match node {
+            Expr::Name(name) | Expr::Column(name) => match table.column_index(name) {
+                Some(col) => {
+                    columns.insert(name.clone(), col);
+                }
+                None => {
+                    missing.get_or_insert_with(|| name.clone());
+                }
+            },
+            Expr::Cell(cell) => {
+                let name = cell.to_a1();
+                if let Some(col) = table.column_index(&name) {
+                    columns.insert(name, col);
+                }
+            }
+            _ => {}
+        });
+
+        match missing {
+            Some(name) => Err(format!("Column '{}' not found", name)),
+            None => Ok(RowBinding { columns }),
+        }
+    }
+
+    /// Indices of the bound columns
+    pub fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.columns.values().copied()
+    }
+
+    /// Context that reads bound columns from `row`
+    pub fn context<'a>(&'a self, table: &'a TesseraTable, row: usize) -> RowContext<'a> {
+        RowContext {
+            table,
+            binding: self,
+            row,
+        }
+    }
+}
+
+/// Formula parsed and bound to the columns of a table
+#[derive(Debug, Clone)]
+pub struct RowFormula {
+    expr: Expr,
+    binding: RowBinding,
+}
+
+impl RowFormula {
+    /// Fails when the formula does not parse or names an unknown column
+    pub fn new(table: &TesseraTable, formula: &str) -> Result<Self, String> {
+        let expr = parse(formula)?;
+        let binding = RowBinding::new(table, &expr)?;
+        Ok(RowFormula { expr, binding })
+    }
+
+    pub fn binding(&self) -> &RowBinding {
+        &self.binding
+    }
+
+    pub fn evaluate(&self, table: &TesseraTable, row: usize) -> Value {
+        evaluate(&self.expr, &self.binding.context(table, row))
+    }
+
+    /// True when the row evaluates to TRUE; errors and text count as false
+    pub fn matches(&self, table: &TesseraTable, row: usize) -> bool {
+        self.evaluate(table, row).as_bool().unwrap_or(false)
+    }
+}
+
+/// Evaluate `formula` for rows `[start, start + count)`
+pub fn evaluate_rows(
+    table: &TesseraTable,
+    formula: &str,
+    start: usize,
+    count: usize,
+) -> Result<Vec<Value>, String> {
+    let formula = RowFormula::new(table, formula)?;
+    let end = start.saturating_add(count).min(table.row_count());
+    Ok((start.min(end)..end)
+        .map(|row| formula.evaluate(table, row))
+        .collect())
+}
+
+/// Evaluates column names against one row of a table
+pub struct RowContext<'a> {
+    table: &'a TesseraTable,
+    binding: &'a RowBinding,
+    row: usize,
+}
+
+impl EvalContext for RowContext<'_> {
+    fn name(&self, name: &str) -> Option<Value> {
+        let col = *self.binding.columns.get(name)?;
+        Some(
+            self.table
+                .computed_value(self.row, col)
+                .unwrap_or_else(|| Value::from_cell(self.table.cell(self.row, col))),
+        )
+    }
+
+    fn cell(&self, cell: &CellRef) -> Value {
+        // A header such as "Q1" parses as a cell reference; treat it as the column
+        self.name(&cell.to_a1())
+            .unwrap_or(Value::Error(ErrorValue::Ref))
+    }
+}
+
+/// Evaluate a per-row formula such as `[Amount] > [Budget]` for a block of rows
+///
+/// Meant for conditional formatting of the visible rows: the host colors the
+/// rows (or cells) whose result is TRUE.
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `formula` - Formula whose names and `[Column]` references read the current row
+/// * `start` - First row
+/// * `count` - Number of rows (clamped to the table)
+///
+/// # Returns
+/// StringResult with a JSON array holding the result text of each row
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_evaluate_rows(
+    table: *const TesseraTable,
+    formula: *const c_char,
+    start: usize,
+    count: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    evaluate_rows(table, formula, start, count)
+        .map(|values| {
+            let texts: Vec<String> = values.iter().map(Value::to_string).collect();
+            serde_json::Value::from(texts).to_string()
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_rows() {
+        let table = TesseraTable::from_rows(
+            vec!["Amount".into(), "Budget".into(), "Q1".into()],
+            vec![
+                vec!["150".into(), "100".into(), "a".into()],
+                vec!["90".into(), "100".into(), "b".into()],
+                vec!["x".into(), "100".into()],
+            ],
+        );
+
+        let over = evaluate_rows(&table, "=[Amount] > Budget", 0, 10).unwrap();
+        assert_eq!(
+            over.iter().map(Value::to_string).collect::<Vec<_>>(),
+            ["TRUE", "FALSE", "TRUE"]
+        );
+        let labels = evaluate_rows(&table, "Q1 & \"!\"", 1, 1).unwrap();
+        assert_eq!(labels, [Value::Text("b!".into())]);
+        assert!(evaluate_rows(&table, "5", 7, 2).unwrap().is_empty());
+        assert!(evaluate_rows(&table, "[Missing] > 1", 0, 1).is_err());
+    }
+}
@@ -0,0 +1,509 @@
+//! Per-row functions over a column of values
+//!
+//! Each function takes a column (or a block, handled column by column) and
+//! returns an array of the same shape with one result per row, for
+//! inspecting time series and logs: `=DIFF(Amount)` is the change from the
+//! previous row, `=LAG(Status)` the previous row's status and
+//! `=CUMSUM(Amount)` the running total and `=EWMA(Latency, 0.2)` a
+//! smoothed copy of a noisy column.
+
+use super::value::{Array, ErrorValue, Value};
+use crate::query::aggregate::{mean, stdev};
+use crate::render::scale::percentile;
+
+/// Apply `f` to each column of `array`, top to bottom
+fn by_column(array: &Array, mut f: impl FnMut(&[Value]) -> Vec<Value>) -> Value {
+    let columns = array.transpose();
+    let results: Vec<Vec<Value>> = columns.iter_rows().map(&mut f).collect();
+    Value::Array(Array::from_rows(results).transpose())
+}
+
+/// Whole number of rows for an offset argument, at least 0
+fn rows_arg(value: Option<&Value>) -> Result<usize, ErrorValue> {
+    match value.map_or(Ok(1.0), Value::as_number)?.trunc() {
+        n if n >= 0.0 => Ok(n as usize),
+        _ => Err(ErrorValue::Num),
+    }
+}
+
+/// LAG(array, [rows], [default]) and LEAD: the value `rows` rows above
+/// (below for LEAD), or `default` (blank if omitted) past the ends
+pub(super) fn shift(
+    array: &Array,
+    rows: Option<&Value>,
+    default: Option<&Value>,
+    lead: bool,
+) -> Value {
+    let rows = match rows_arg(rows) {
+        Ok(rows) => rows,
+        Err(e) => return Value::Error(e),
+    };
+    let default = default.cloned().unwrap_or(Value::Blank);
+    by_column(array, |column| {
+        (0..column.len())
+            .map(|i| {
+                let source = match lead {
+                    true => i.checked_add(rows).filter(|&j| j < column.len()),
+                    false => i.checked_sub(rows),
+                };
+                source.map_or_else(|| default.clone(), |j| column[j].clone())
+            })
+            .collect()
+    })
+}
+
+/// DIFF(array, [rows]) and PCTCHANGE: change from the value `rows` rows
+/// above, as a difference or a fraction of the earlier value; blank for the
+/// first rows
+pub(super) fn change(array: &Array, rows: Option<&Value>, relative: bool) -> Value {
+    let rows = match rows_arg(rows) {
+        Ok(rows) => rows,
+        Err(e) => return Value::Error(e),
+    };
+    by_column(array, |column| {
+        (0..column.len())
+            .map(|i| {
+                let Some(j) = i.checked_sub(rows) else {
+                    return Value::Blank;
+                };
+                let (before, after) = match (column[j].as_number(), column[i].as_number()) {
+                    (Ok(before), Ok(after)) => (before, after),
+                    (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+                };
+                match relative {
+                    true if before == 0.0 => Value::Error(ErrorValue::Div0),
+                    true => Value::number((after - before) / before.abs()),
+                    false => Value::number(after - before),
+                }
+            })
+            .collect()
+    })
+}
+
+/// How a running value combines with the next number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Cumulative {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+impl Cumulative {
+    fn combine(self, acc: f64, n: f64) -> f64 {
+        match self {
+            Cumulative::Sum => acc + n,
+            Cumulative::Product => acc * n,
+            Cumulative::Min => acc.min(n),
+            Cumulative::Max => acc.max(n),
+        }
+    }
+}
+
+/// CUMSUM(array), CUMPROD, CUMMIN and CUMMAX: the running total, product,
+/// minimum or maximum down each column
+///
+/// Blank cells stay blank and do not interrupt the run; text and errors
+/// give `#VALUE!` or the error on their own row only.
+pub(super) fn cumulative(array: &Array, kind: Cumulative) -> Value {
+    by_column(array, |column| {
+        let mut acc: Option<f64> = None;
+        column
+            .iter()
+            .map(|value| match value {
+                Value::Blank => Value::Blank,
+                Value::Number(n) => {
+                    let next = acc.map_or(*n, |acc| kind.combine(acc, *n));
+                    acc = Some(next);
+                    Value::number(next)
+                }
+                Value::Error(e) => Value::Error(*e),
+                _ => Value::Error(ErrorValue::Value),
+            })
+            .collect()
+    })
+}
+
+/// Exponentially weighted statistic returned per row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Weighted {
+    Mean,
+    Variance,
+    StdDev,
+}
+
+/// EWMA(array, alpha), EWMVAR and EWMSTD: exponentially weighted moving
+/// mean, variance or standard deviation down each column
+///
+/// Each number moves the mean by `alpha` (0 < alpha <= 1) of its distance
+/// from it: `mean = mean + alpha * (x - mean)`, starting at the first
+/// number, and `var = (1 - alpha) * (var + alpha * (x - mean_before)^2)`,
+/// starting at 0. Blank cells stay blank, other non-numbers give an error
+/// on their own row, like [`cumulative`].
+pub(super) fn weighted(array: &Array, alpha: &Value, kind: Weighted) -> Value {
+    let alpha = match alpha.as_number() {
+        Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => alpha,
+        Ok(_) => return Value::Error(ErrorValue::Num),
+        Err(e) => return Value::Error(e),
+    };
+    by_column(array, |column| {
+        let mut state: Option<(f64, f64)> = None;
+        column
+            .iter()
+            .map(|value| match value {
+                Value::Blank => Value::Blank,
+                Value::Number(x) => {
+                    let (mean, var) = match state {
+                        None => (*x, 0.0),
+                        Some((mean, var)) => {
+                            let delta = x - mean;
+                            (
+                                mean + alpha * delta,
+                                (1.0 - alpha) * (var + alpha * delta * delta),
+                            )
+                        }
+                    };
+                    state = Some((mean, var));
+                    Value::number(match kind {
+                        Weighted::Mean => mean,
+                        Weighted::Variance => var,
+                        Weighted::StdDev => var.sqrt(),
+                    })
+                }
+                Value::Error(e) => Value::Error(*e),
+                _ => Value::Error(ErrorValue::Value),
+            })
+            .collect()
+    })
+}
+
+/// How ZSCORE-style functions center and scale a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Normalization {
+    /// Distance from the mean in sample standard deviations
+    ZScore,
+    /// Position between the minimum (0) and maximum (1)
+    MinMax,
+    /// Distance from the median in median absolute deviations, scaled by
+    /// 1.4826 to match standard deviations on normally distributed data
+    Robust,
+}
+
+/// ZSCORE(array), MINMAXSCALE and ROBUSTSCALE: each number rescaled against
+/// the numbers of its column
+///
+/// Blank cells stay blank, other non-numbers give an error on their own row
+/// and are left out of the statistics. A column without spread gives
+/// `#DIV/0!`.
+pub(super) fn normalize(array: &Array, kind: Normalization) -> Value {
+    by_column(array, |column| {
+        let mut numbers: Vec<f64> = column
+            .iter()
+            .filter_map(|value| match value {
+                Value::Number(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        numbers.sort_by(f64::total_cmp);
+        let (center, scale) = match (kind, numbers.first(), numbers.last()) {
+            (_, None, _) | (_, _, None) => (0.0, 0.0),
+            (Normalization::ZScore, ..) => (
+                mean(&numbers).unwrap_or_default(),
+                stdev(&numbers).unwrap_or_default(),
+            ),
+            (Normalization::MinMax, Some(&min), Some(&max)) => (min, max - min),
+            (Normalization::Robust, ..) => {
+                let median = percentile(&numbers, 50.0);
+                let mut deviations: Vec<f64> = numbers.iter().map(|n| (n - median).abs()).collect();
+                deviations.sort_by(f64::total_cmp);
+                (median, percentile(&deviations, 50.0) * 1.4826)
+            }
+        };
+        column
+            .iter()
+            .map(|value| match value {
+                Value::Blank => Value::Blank,
+                Value::Number(_) if scale == 0.0 => Value::Error(ErrorValue::Div0),
+                Value::Number(n) => Value::number((n - center) / scale),
+                Value::Error(e) => Value::Error(*e),
+                _ => Value::Error(ErrorValue::Value),
+            })
+            .collect()
+    })
+}
+
+/// How RANKS-style functions number the values of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Ranking {
+    /// Equal values share the best rank of their run (1, 2, 2, 4)
+    Min,
+    /// Equal values share the mean of their run's ranks (1, 2.5, 2.5, 4)
+    Average,
+    /// Equal values share the worst rank of their run (1, 3, 3, 4)
+    Max,
+    /// Equal values are ranked in row order (1, 2, 3, 4)
+    First,
+    /// Equal values share a rank and no rank is skipped (1, 2, 2, 3)
+    Dense,
+    /// Share of the other numbers that are smaller, from 0 to 1
+    Percent,
+}
+
+impl Ranking {
+    /// The `ties` argument of RANKS: 0 = min (default), 1 = average,
+    /// 2 = max, 3 = first
+    fn from_ties(ties: Option<&Value>) -> Result<Self, ErrorValue> {
+        match ties.map_or(Ok(0.0), Value::as_number)?.trunc() {
+            0.0 => Ok(Ranking::Min),
+            1.0 => Ok(Ranking::Average),
+            2.0 => Ok(Ranking::Max),
+            3.0 => Ok(Ranking::First),
+            _ => Err(ErrorValue::Num),
+        }
+    }
+}
+
+/// RANKS(array, [order], [ties]), DENSERANKS(array, [order]) and
+/// PCTRANKS(array): the rank of each number within its column
+///
+/// Like RANK, `order` 0 (the default) ranks the largest number first and
+/// anything else the smallest; PCTRANKS always counts from the smallest.
+/// Blank cells stay blank, other non-numbers give an error on their own row
+/// and are left out of the ranking.
+pub(super) fn ranks(
+    array: &Array,
+    order: Option<&Value>,
+    ties: Option<&Value>,
+    ranking: Option<Ranking>,
+) -> Value {
+    let ascending = match order.map(Value::as_number) {
+        None => ranking == Some(Ranking::Percent),
+        Some(Ok(order)) => order != 0.0,
+        Some(Err(e)) => return Value::Error(e),
+    };
+    let ranking = match ranking.map_or_else(|| Ranking::from_ties(ties), Ok) {
+        Ok(ranking) => ranking,
+        Err(e) => return Value::Error(e),
+    };
+    by_column(array, |column| {
+        let mut sorted: Vec<(f64, usize)> = column
+            .iter()
+            .enumerate()
+            .filter_map(|(row, value)| match value {
+                Value::Number(n) => Some((*n, row)),
+                _ => None,
+            })
+            .collect();
+        sorted.sort_by(|a, b| match ascending {
+            true => a.0.total_cmp(&b.0),
+            false => b.0.total_cmp(&a.0),
+        });
+
+        let mut results: Vec<Value> = column
+            .iter()
+            .map(|value| match value {
+                Value::Blank => Value::Blank,
+                Value::Error(e) => Value::Error(*e),
+                _ => Value::Error(ErrorValue::Value),
+            })
+            .collect();
+        let count = sorted.len();
+        let (mut start, mut dense) = (0, 0);
+        while start < count {
+            let end = start
+                + sorted[start..]
+                    .iter()
+                    .take_while(|(n, _)| *n == sorted[start].0)
+                    .count();
+            dense += 1;
+            for (position, &(_, row)) in sorted.iter().enumerate().take(end).skip(start) {
+                let rank = match ranking {
+                    Ranking::Min => (start + 1) as f64,
+                    Ranking::Average => (start + 1 + end) as f64 / 2.0,
+                    Ranking::Max => end as f64,
+                    Ranking::First => (position + 1) as f64,
+                    Ranking::Dense => dense as f64,
+                    Ranking::Percent if count == 1 => 1.0,
+                    Ranking::Percent => start as f64 / (count - 1) as f64,
+                };
+                results[row] = Value::number(rank);
+            }
+            start = end;
+        }
+        results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(values: &[f64]) -> Array {
+        Array::new(
+            values.len(),
+            1,
+            values.iter().map(|&n| Value::Number(n)).collect(),
+        )
+    }
+
+    fn numbers(value: Value) -> Vec<String> {
+        match value {
+            Value::Array(array) => array.values().iter().map(Value::to_string).collect(),
+            scalar => vec![scalar.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_shift_and_change() {
+        let prices = column(&[10.0, 12.0, 9.0, 0.0, 3.0]);
+        assert_eq!(
+            numbers(shift(&prices, None, None, false)),
+            ["", "10", "12", "9", "0"]
+        );
+        assert_eq!(
+            numbers(shift(
+                &prices,
+                Some(&Value::Number(2.0)),
+                Some(&Value::Number(-1.0)),
+                true
+            )),
+            ["9", "0", "3", "-1", "-1"]
+        );
+        assert_eq!(
+            numbers(change(&prices, None, false)),
+            ["", "2", "-3", "-9", "3"]
+        );
+        assert_eq!(
+            numbers(change(&prices, None, true)),
+            ["", "0.2", "-0.25", "-1", "#DIV/0!"]
+        );
+        assert_eq!(
+            numbers(shift(&prices, Some(&Value::Number(-1.0)), None, false)),
+            ["#NUM!"]
+        );
+
+        let block = Array::from_rows(vec![
+            vec![Value::Number(1.0), Value::Text("a".into())],
+            vec![Value::Number(4.0), Value::Text("b".into())],
+        ]);
+        assert_eq!(
+            numbers(shift(&block, None, None, false)),
+            ["", "", "1", "a"]
+        );
+        assert_eq!(
+            numbers(change(&block, None, false)),
+            ["", "", "3", "#VALUE!"]
+        );
+    }
+
+    #[test]
+    fn test_cumulative() {
+        let values = Array::new(
+            5,
+            1,
+            vec![
+                Value::Number(3.0),
+                Value::Blank,
+                Value::Number(-2.0),
+                Value::Text("n/a".into()),
+                Value::Number(5.0),
+            ],
+        );
+        let run = |kind| numbers(cumulative(&values, kind));
+        assert_eq!(run(Cumulative::Sum), ["3", "", "1", "#VALUE!", "6"]);
+        assert_eq!(run(Cumulative::Product), ["3", "", "-6", "#VALUE!", "-30"]);
+        assert_eq!(run(Cumulative::Min), ["3", "", "-2", "#VALUE!", "-2"]);
+        assert_eq!(run(Cumulative::Max), ["3", "", "3", "#VALUE!", "5"]);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let values = column(&[10.0, 20.0, 20.0]);
+        let half = Value::Number(0.5);
+        assert_eq!(
+            numbers(weighted(&values, &half, Weighted::Mean)),
+            ["10", "15", "17.5"]
+        );
+        assert_eq!(
+            numbers(weighted(&values, &half, Weighted::Variance)),
+            ["0", "25", "18.75"]
+        );
+        assert_eq!(
+            numbers(weighted(&values, &Value::Number(1.0), Weighted::StdDev)),
+            ["0", "0", "0"]
+        );
+        assert_eq!(
+            numbers(weighted(&values, &Value::Number(0.0), Weighted::Mean)),
+            ["#NUM!"]
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let values = column(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 10.0]);
+        let scaled = |kind| match normalize(&values, kind) {
+            Value::Array(array) => array
+                .values()
+                .iter()
+                .map(|v| (v.as_number().unwrap() * 1000.0).round() / 1000.0)
+                .collect::<Vec<f64>>(),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            scaled(Normalization::MinMax),
+            [0.0, 0.25, 0.25, 0.25, 0.375, 0.375, 0.625, 1.0]
+        );
+        assert_eq!(
+            scaled(Normalization::ZScore),
+            [-1.293, -0.466, -0.466, -0.466, -0.052, -0.052, 0.776, 2.017]
+        );
+        // Median 4.5, median absolute deviation 0.5
+        assert_eq!(scaled(Normalization::Robust)[7], 7.419);
+        assert_eq!(
+            numbers(normalize(&column(&[3.0, 3.0]), Normalization::ZScore)),
+            ["#DIV/0!", "#DIV/0!"]
+        );
+    }
+
+    #[test]
+    fn test_ranks() {
+        let scores = Array::new(
+            6,
+            1,
+            vec![
+                Value::Number(70.0),
+                Value::Number(90.0),
+                Value::Blank,
+                Value::Number(80.0),
+                Value::Number(90.0),
+                Value::Text("absent".into()),
+            ],
+        );
+        let rank = |ties: f64| numbers(ranks(&scores, None, Some(&Value::Number(ties)), None));
+        assert_eq!(rank(0.0), ["4", "1", "", "3", "1", "#VALUE!"]);
+        assert_eq!(rank(1.0), ["4", "1.5", "", "3", "1.5", "#VALUE!"]);
+        assert_eq!(rank(2.0), ["4", "2", "", "3", "2", "#VALUE!"]);
+        assert_eq!(rank(3.0), ["4", "1", "", "3", "2", "#VALUE!"]);
+        assert_eq!(
+            numbers(ranks(&scores, None, Some(&Value::Number(4.0)), None)),
+            ["#NUM!"]
+        );
+
+        let ascending = Value::Number(1.0);
+        assert_eq!(
+            numbers(ranks(&scores, Some(&ascending), None, Some(Ranking::Dense))),
+            ["1", "3", "", "2", "3", "#VALUE!"]
+        );
+        assert_eq!(
+            numbers(ranks(&scores, None, None, Some(Ranking::Percent))),
+            [
+                "0",
+                "0.6666666666666666",
+                "",
+                "0.3333333333333333",
+                "0.6666666666666666",
+                "#VALUE!"
+            ]
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Evaluating formulas against a table handle
+
+use std::os::raw::c_char;
+
+use super::eval::{evaluate, trace, EvalContext, TraceStep};
+use super::parser::{parse, CellRef};
+use super::rewrite::is_formula;
+use super::value::{Array, ErrorValue, Value};
+use super::volatile::{self, SeededRandom};
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TesseraTable};
+use crate::workbook::calc::CalcValues;
+use crate::workbook::Workbook;
+use crate::StringResult;
+
+/// Exposes a table as a grid: `A1` is the first data row of the first column
+///
+/// Column headers used as names (`Amount`, `[Sale Region]`) evaluate to the
+/// whole column as a vertical array. Computed columns read as their
+/// evaluated values. Inside a workbook, `Sheet2!A1` reads
+/// from the other sheets, other names resolve to the workbook's defined
+/// names and formula cells read as their calculated values. Empty cells
+/// covered by a spilled array read as its elements and `A1#` as the array.
+///
+/// When the workbook's volatile functions are frozen, `NOW()` reads the
+/// frozen clock and `RAND()` a generator seeded for the cell being calculated.
+pub struct TableContext<'a> {
+    table: &'a TesseraTable,
+    workbook: Option<(&'a Workbook, usize, &'a CalcValues)>,
+    frozen: Option<(f64, SeededRandom)>,
+}
+
+impl<'a> TableContext<'a> {
+    pub fn new(table: &'a TesseraTable) -> Self {
+        TableContext {
+            table,
+            workbook: None,
+            frozen: None,
+        }
+    }
+
+    /// Context for the sheet at `sheet` of a workbook (which must exist)
+    pub fn in_workbook(workbook: &'a Workbook, sheet: usize) -> Self {
+        TableContext::with_values(workbook, sheet, workbook.calculated_values())
+    }
+
+    /// Like [`TableContext::in_workbook`], reading formula results from `values`
+    pub(crate) fn with_values(
+        workbook: &'a Workbook,
+        sheet: usize,
+        values: &'a CalcValues,
+    ) -> Self {
+        TableContext {
+            table: workbook.sheet_at(sheet).expect("sheet index in range"),
+            workbook: Some((workbook, sheet, values)),
+            frozen: workbook
+                .volatile_frozen()
+                .map(|frozen| (frozen.now, SeededRandom::new(frozen.seed, (sheet, 0, 0)))),
+        }
+    }
+
+    /// Seed frozen random functions for the formula cell at `row`, `col`
+    pub(crate) fn for_cell(mut self, row: usize, col: usize) -> Self {
+        if let Some((workbook, sheet, _)) = self.workbook {
+            if let Some(frozen) = workbook.volatile_frozen() {
+                let random = SeededRandom::new(frozen.seed, (sheet, row, col));
+                self.frozen = Some((frozen.now, random));
+            }
+        }
+        self
+    }
+
+    fn value_at(&self, row: usize, col: usize) -> Value {
+        if let Some(value) = self.table.computed_value(row, col) {
+            return value;
+        }
+        let text = self.table.cell(row, col);
+        match self.workbook {
+            Some((workbook, sheet, values)) if is_formula(text) => {
+                let key = (sheet, row, col);
+                values
+                    .get(&key)
+                    .map_or(Value::Blank, |value| workbook.anchor_value(key, value))
+            }
+            Some((workbook, sheet, values)) if text.trim().is_empty() => workbook
+                .spilled_value((sheet, row, col), values)
+                .unwrap_or(Value::Blank),
+            _ => Value::from_cell(text),
+        }
+    }
+}
+
+impl EvalContext for TableContext<'_> {
+    fn name(&self, name: &str) -> Option<Value> {
+        let Some(col) = self.table.column_index(name) else {
+            // Defined names only point at references, so this cannot recurse
+            let reference = self.workbook?.0.name_reference(name)?;
+            return Some(evaluate(&parse(reference).ok()?, self));
+        };
+        Some(Value::Array(Array::new(
+            self.table.row_count(),
+            1,
+            (0..self.table.row_count())
+                .map(|row| self.value_at(row, col))
+                .collect(),
+        )))
+    }
+
+    fn cell(&self, cell: &CellRef) -> Value {
+        self.value_at(cell.row, cell.col)
+    }
+
+    fn extent(&self) -> (usize, usize) {
+        (self.table.row_count(), self.table.column_count())
+    }
+
+    fn sheet(&self, name: &str) -> Option<Box<dyn EvalContext + '_>> {
+        let (workbook, _, values) = self.workbook?;
+        let sheet = workbook.sheet_index(name)?;
+        Some(Box::new(TableContext::with_values(workbook, sheet, values)))
+    }
+
+    fn sheet_span(&self, first: &str, last: &str) -> Option<Vec<String>> {
+        let (workbook, _, _) = self.workbook?;
+        let (a, b) = (workbook.sheet_index(first)?, workbook.sheet_index(last)?);
+        let names = workbook.sheet_names();
+        Some(
+            names[a.min(b)..=a.max(b)]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        )
+    }
+
+    fn spill(&self, cell: &CellRef) -> Value {
+        let Some((workbook, sheet, values)) = self.workbook else {
+            return Value::Error(ErrorValue::Ref);
+        };
+        let key = (sheet, cell.row, cell.col);
+        match values.get(&key) {
+            Some(_) if workbook.spill_area(key).is_some_and(|area| area.blocked) => {
+                Value::Error(ErrorValue::Spill)
+            }
+            Some(value @ Value::Array(_)) => value.clone(),
+            _ => Value::Error(ErrorValue::Ref),
+        }
+    }
+
+    fn currency_rates(&self) -> Option<Value> {
+        let reference = self.workbook?.0.currency_rates()?;
+        Some(evaluate(&parse(reference).ok()?, self))
+    }
+
+    fn now(&self) -> f64 {
+        match &self.frozen {
+            Some((now, _)) => *now,
+            None => volatile::now(),
+        }
+    }
+
+    fn random(&self) -> f64 {
+        match &self.frozen {
+            Some((_, random)) => random.next(),
+            None => volatile::random(),
+        }
+    }
+}
+
+fn to_grid(value: Value) -> Vec<Vec<String>> {
+    match value {
+        Value::Array(array) => array
+            .iter_rows()
+            .map(|row| row.iter().map(Value::to_string).collect())
+            .collect(),
+        scalar => vec![vec![scalar.to_string()]],
+    }
+}
+
+/// Evaluate a formula against a table, returning its values as a grid of text
+///
+/// Scalar results come back as a 1x1 grid; array results keep their shape so
+/// the host can spill them into neighbouring cells.
+pub fn evaluate_in_table(table: &TesseraTable, formula: &str) -> Result<Vec<Vec<String>>, String> {
+    let expr = parse(formula)?;
+    Ok(to_grid(evaluate(&expr, &TableContext::new(table))))
+}
+
+/// Evaluate a formula on the sheet at `sheet`, like [`evaluate_in_table`]
+pub fn evaluate_in_workbook(
+    workbook: &Workbook,
+    sheet: usize,
+    formula: &str,
+) -> Result<Vec<Vec<String>>, String> {
+    if sheet >= workbook.sheet_count() {
+        return Err(format!("Sheet {} is out of range", sheet));
+    }
+    let expr = parse(formula)?;
+    Ok(to_grid(evaluate(
+        &expr,
+        &TableContext::in_workbook(workbook, sheet),
+    )))
+}
+
+/// Evaluation trace as JSON: `{result, steps: [{depth, expression, value}]}`
+///
+/// Scalar values are their cell text; arrays are grids like `result` of
+/// tessera_evaluate.
+pub fn trace_to_json(value: Value, steps: Vec<TraceStep>) -> String {
+    let json = |value: Value| match value {
+        Value::Array(_) => serde_json::Value::from(to_grid(value)),
+        scalar => serde_json::Value::from(scalar.to_string()),
+    };
+    let steps: Vec<_> = steps
+        .into_iter()
+        .map(|step| {
+            serde_json::json!({
+                "depth": step.depth,
+                "expression": step.expression,
+                "value": json(step.value),
+            })
+        })
+        .collect();
+    serde_json::json!({ "result": json(value), "steps": steps }).to_string()
+}
+
+/// Evaluate a formula against a table, recording every subexpression
+pub fn trace_in_table(
+    table: &TesseraTable,
+    formula: &str,
+) -> Result<(Value, Vec<TraceStep>), String> {
+    let expr = parse(formula)?;
+    Ok(trace(&expr, &TableContext::new(table)))
+}
+
+/// Like [`trace_in_table`] on the sheet at `sheet` of a workbook
+pub fn trace_in_workbook(
+    workbook: &Workbook,
+    sheet: usize,
+    formula: &str,
+) -> Result<(Value, Vec<TraceStep>), String> {
+    if sheet >= workbook.sheet_count() {
+        return Err(format!("Sheet {} is out of range", sheet));
+    }
+    let expr = parse(formula)?;
+    Ok(trace(&expr, &TableContext::in_workbook(workbook, sheet)))
+}
+
+/// Evaluate a formula such as `=SORT(UNIQUE(A:A))` against a table
+///
+/// # Returns
+/// StringResult with a JSON array of rows of cell text (`[["42"]]` for a scalar)
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_evaluate(
+    table: *const TesseraTable,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    evaluate_in_table(table, formula)
+        .map(|grid| serde_json::Value::from(grid).to_string())
+        .into()
+}
+
+/// Evaluate a formula step by step, to see where a nested formula goes wrong
+///
+/// # Returns
+/// StringResult with JSON `{result, steps: [{depth, expression, value}]}`:
+/// every evaluated subexpression in evaluation order, operands before the
+/// operation using them (depth 0 is the whole formula)
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_trace_formula(
+    table: *const TesseraTable,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    trace_in_table(table, formula)
+        .map(|(value, steps)| trace_to_json(value, steps))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::ErrorValue;
+
+    fn sales() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Region".into(), "Amount".into()],
+            [
+                ["EU", "30"],
+                ["us", "10"],
+                ["eu", "20"],
+                ["APAC", "5"],
+                ["US", "40"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    fn eval(formula: &str) -> Vec<Vec<String>> {
+        evaluate_in_table(&sales(), formula).unwrap()
+    }
+
+    #[test]
+    fn test_spill_functions() {
+        assert_eq!(eval("=UNIQUE(A:A)"), [["EU"], ["us"], ["APAC"]]);
+        assert_eq!(eval("=UNIQUE(Region, FALSE, TRUE)"), [["APAC"]]);
+        assert_eq!(
+            eval("=SORT(B1:B5, 1, -1)"),
+            [["40"], ["30"], ["20"], ["10"], ["5"]]
+        );
+        assert_eq!(
+            eval("=SORT(FILTER(A1:B5, Amount >= 20), 2)"),
+            [["eu", "20"], ["EU", "30"], ["US", "40"]]
+        );
+        assert_eq!(eval("=FILTER(A:A, B:B > 100, \"none\")"), [["none"]]);
+        assert_eq!(eval("=FILTER(A:A, B:B > 100)"), [["#CALC!"]]);
+    }
+
+    #[test]
+    fn test_lookup_functions() {
+        assert_eq!(eval("=MATCH(\"u?\", Region, 0)"), [["2"]]);
+        assert_eq!(eval("=MATCH(\"a*\", A:A, 0)"), [["4"]]);
+        assert_eq!(eval("=MATCH(\"^(us|eu)$\", A3:A5, 0, TRUE)"), [["1"]]);
+        assert_eq!(eval("=MATCH(25, SORT(Amount), 1)"), [["3"]]);
+        assert_eq!(eval("=VLOOKUP(\"ap*\", A1:B5, 2, FALSE)"), [["5"]]);
+        assert_eq!(eval("=VLOOKUP(\"x*\", A1:B5, 2, FALSE)"), [["#N/A"]]);
+        assert_eq!(eval("=COUNTIF(Region, \"eu\")"), [["2"]]);
+        assert_eq!(eval("=COUNTIF(Amount, \">=20\")"), [["3"]]);
+        assert_eq!(eval("=COUNTIF(Region, \"[ue][us]\", TRUE)"), [["4"]]);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        assert_eq!(eval("=SORT(SHUFFLE(A1:B5, 3), 2)"), eval("=SORT(A1:B5, 2)"));
+        assert_eq!(eval("=SHUFFLE(Amount, 3)"), eval("=SHUFFLE(Amount, 3)"));
+        assert_eq!(eval("=SORT(SHUFFLE(3, 9))"), [["1"], ["2"], ["3"]]);
+        assert_eq!(eval("=SHUFFLE(0, 9)"), [["#NUM!"]]);
+        assert_eq!(eval("=SHUFFLE(1048577, 9)"), [["#NUM!"]]);
+        assert_eq!(eval("=SHUFFLE(1E+300, 9)"), [["#NUM!"]]);
+    }
+
+    #[test]
+    fn test_hash_functions() {
+        assert_eq!(eval("=MD5(\"\")"), [["d41d8cd98f00b204e9800998ecf8427e"]]);
+        assert_eq!(eval("=CRC32(123456789)"), [["cbf43926"]]);
+        assert_eq!(eval("=LEN(SHA1(A1)) + LEN(SHA256(A1))"), [["104"]]);
+        assert_eq!(eval("=SHA256(1/0)"), [["#DIV/0!"]]);
+    }
+
+    #[test]
+    fn test_bitwise_functions() {
+        assert_eq!(eval("=BITAND(12, 10)"), [["8"]]);
+        assert_eq!(eval("=BITOR(12, 10) + BITXOR(12, 10)"), [["20"]]);
+        assert_eq!(eval("=BITLSHIFT(1, 40)"), [["1099511627776"]]);
+        assert_eq!(eval("=BITLSHIFT(1, 64)"), [["0"]]);
+        assert_eq!(eval("=BITRSHIFT(16, -2)"), [["64"]]);
+        assert_eq!(eval("=BITAND(1.5, 1)"), [["#NUM!"]]);
+        assert_eq!(eval("=BITOR(-1, 1)"), [["#NUM!"]]);
+        assert_eq!(eval("=BITRSHIFT(8, 0.5)"), [["#NUM!"]]);
+    }
+
+    #[test]
+    fn test_format_predicates() {
+        assert_eq!(eval("=ISEMAIL(\"ann@example.com\")"), [["TRUE"]]);
+        assert_eq!(eval("=ISEMAIL(\"ann@example\")"), [["FALSE"]]);
+        assert_eq!(eval("=ISURL(\"https://example.com/?q=1\")"), [["TRUE"]]);
+        assert_eq!(eval("=ISIPADDRESS(\"10.0.0.256\")"), [["FALSE"]]);
+        assert_eq!(eval("=ISJSON(\"{\"\"a\"\": 1}\")"), [["TRUE"]]);
+        assert_eq!(eval("=ISJSON(1)"), [["FALSE"]]);
+    }
+
+    #[test]
+    fn test_sort_mixed_column() {
+        let cells = [
+            "1", "", "b", "TRUE", "-1", "0", "", "FALSE", "A", "0", "TRUE", "",
+        ];
+        let table = TesseraTable::from_rows(
+            vec!["Mixed".into()],
+            cells.iter().map(|c| vec![c.to_string()]).collect(),
+        );
+        let sorted = |formula: &str| -> Vec<String> {
+            evaluate_in_table(&table, formula)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.concat())
+                .collect()
+        };
+        assert_eq!(
+            sorted("=SORT(A1:A12)"),
+            ["-1", "0", "0", "1", "A", "b", "FALSE", "TRUE", "TRUE", "", "", ""]
+        );
+        assert_eq!(
+            sorted("=SORT(A1:A12, 1, -1)"),
+            ["TRUE", "TRUE", "FALSE", "b", "A", "1", "0", "0", "-1", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(eval("=SPLIT(\"a,b,,3\", \",\")"), [["a", "b", "", "3"]]);
+        assert_eq!(eval("=SPLIT(\"a, b, c\", \",\", 2, TRUE)"), [["a", "b, c"]]);
+        assert_eq!(eval("=SUM(SPLIT(\"1;2;3\", \";\"))"), [["6"]]);
+        assert_eq!(
+            eval("=SPLIT(A1:A2 & \"-x\", \"-\")"),
+            [["EU", "x"], ["us", "x"]]
+        );
+        assert_eq!(eval("=SPLIT(\"abc\", \"\")"), [["#VALUE!"]]);
+    }
+
+    #[test]
+    fn test_scalar_and_broadcast() {
+        assert_eq!(eval("=B1 * 2"), [["60"]]);
+        assert_eq!(eval("=B1:B2 * 2"), [["60"], ["20"]]);
+        assert_eq!(eval("=A1:A2 & \"-\" & B1:B2"), [["EU-30"], ["us-10"]]);
+        assert!(evaluate_in_table(&sales(), "=SORT(").is_err());
+    }
+
+    #[test]
+    fn test_trace_steps() {
+        let (value, steps) =
+            trace_in_table(&sales(), "=IF(B1 > 10, LEN(A1) * (B2 - \"x\"), 0)").unwrap();
+        assert_eq!(value, Value::Error(ErrorValue::Value));
+        let steps: Vec<(usize, String, String)> = steps
+            .into_iter()
+            .map(|s| (s.depth, s.expression, s.value.to_string()))
+            .collect();
+        let step = |depth, expression: &str, value: &str| {
+            (depth, expression.to_string(), value.to_string())
+        };
+        assert_eq!(
+            steps,
+            [
+                step(2, "B1", "30"),
+                step(2, "10", "10"),
+                step(1, "B1>10", "TRUE"),
+                step(3, "A1", "EU"),
+                step(2, "LEN(A1)", "2"),
+                step(3, "B2", "10"),
+                step(3, "\"x\"", "x"),
+                step(2, "B2-\"x\"", "#VALUE!"),
+                step(1, "LEN(A1)*(B2-\"x\")", "#VALUE!"),
+                step(0, "IF(B1>10,LEN(A1)*(B2-\"x\"),0)", "#VALUE!"),
+            ]
+        );
+    }
+}
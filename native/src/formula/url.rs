@@ -0,0 +1,135 @@
+//! URL functions: URLHOST, URLPATH, URLPARAM, URLENCODE and URLDECODE
+//!
+//! URLs without a scheme (`example.com/page?id=3`) are read the same way as
+//! full ones. Query parameters are form-decoded, so `+` is a space.
+
+use super::value::{ErrorValue, Value};
+use crate::formats::{split_url, UrlParts};
+
+/// Percent-encode every byte outside the unreserved set `A-Z a-z 0-9 - _ . ~`
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes (and `+` as a space when `plus_as_space`), `None`
+/// for a malformed escape or bytes that are not UTF-8
+fn percent_decode(text: &str, plus_as_space: bool) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'%' => {
+                let hex = rest
+                    .get(..2)
+                    .filter(|h| h.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &rest[2..];
+            }
+            b'+' if plus_as_space => bytes.push(b' '),
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn with_url(value: &Value, f: impl FnOnce(UrlParts) -> Value) -> Value {
+    match value.as_text() {
+        Ok(text) => match split_url(text.trim()) {
+            Some(url) => f(url),
+            None => Value::Error(ErrorValue::Value),
+        },
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// URLHOST(url): host name in lower case
+pub(super) fn host(url: &Value) -> Value {
+    with_url(url, |url| match url.host {
+        "" => Value::Error(ErrorValue::Value),
+        host => Value::Text(host.to_lowercase()),
+    })
+}
+
+/// URLPATH(url): path from its leading `/`, empty when there is none
+pub(super) fn path(url: &Value) -> Value {
+    with_url(url, |url| Value::Text(url.path.to_string()))
+}
+
+/// URLPARAM(url, name): decoded value of the first query parameter called
+/// `name`, `#N/A` when there is none
+pub(super) fn param(url: &Value, name: &Value) -> Value {
+    let name = match name.as_text() {
+        Ok(name) => name,
+        Err(e) => return Value::Error(e),
+    };
+    with_url(url, |url| {
+        let found = url
+            .query
+            .unwrap_or_default()
+            .split('&')
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+            .find(|(key, _)| percent_decode(key, true).is_some_and(|key| key == name));
+        match found.map(|(_, value)| percent_decode(value, true)) {
+            Some(Some(value)) => Value::Text(value),
+            Some(None) => Value::Error(ErrorValue::Value),
+            None => Value::Error(ErrorValue::NA),
+        }
+    })
+}
+
+/// URLENCODE(text)
+pub(super) fn encode(value: &Value) -> Value {
+    match value.as_text() {
+        Ok(text) => Value::Text(percent_encode(&text)),
+        Err(e) => Value::Error(e),
+    }
+}
+
+/// URLDECODE(text): `#VALUE!` for malformed escapes
+pub(super) fn decode(value: &Value) -> Value {
+    match value.as_text() {
+        Ok(text) => {
+            percent_decode(&text, false).map_or(Value::Error(ErrorValue::Value), Value::Text)
+        }
+        Err(e) => Value::Error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_functions() {
+        let text = |s: &str| Value::Text(s.into());
+        let url = text("https://Shop.Example.com:8443/cart/view?id=42&q=red+shoes&x=%E2%9C%93#top");
+
+        assert_eq!(host(&url), text("shop.example.com"));
+        assert_eq!(path(&url), text("/cart/view"));
+        assert_eq!(param(&url, &text("q")), text("red shoes"));
+        assert_eq!(param(&url, &text("x")), text("✓"));
+        assert_eq!(param(&url, &text("page")), Value::Error(ErrorValue::NA));
+        assert_eq!(host(&text("http://[2001:db8::1]/")), text("2001:db8::1"));
+        assert_eq!(host(&text("example.org/a?b")), text("example.org"));
+        assert_eq!(
+            host(&text("example.com/?next=http://x")),
+            text("example.com")
+        );
+        assert_eq!(path(&text("example.org")), text(""));
+
+        assert_eq!(percent_encode("a b/ü"), "a%20b%2F%C3%BC");
+        assert_eq!(decode(&text("a%20b%2F%C3%BC+")), text("a b/ü+"));
+        assert_eq!(decode(&text("100%")), Value::Error(ErrorValue::Value));
+        assert_eq!(decode(&text("%+1")), Value::Error(ErrorValue::Value));
+    }
+}
@@ -0,0 +1,327 @@
+//! Runtime values produced by formula evaluation
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Spreadsheet error values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorValue {
+    Div0,
+    Value,
+    Name,
+    Ref,
+    NA,
+    Num,
+    Null,
+    /// Calculation produced nothing, e.g. FILTER without matches
+    Calc,
+    /// An array result cannot spill because its cells are not empty
+    Spill,
+}
+
+impl ErrorValue {
+    pub const ALL: [ErrorValue; 9] = [
+        ErrorValue::Div0,
+        ErrorValue::Value,
+        ErrorValue::Name,
+        ErrorValue::Ref,
+        ErrorValue::NA,
+        ErrorValue::Num,
+        ErrorValue::Null,
+        ErrorValue::Calc,
+        ErrorValue::Spill,
+    ];
+
+    /// Error code as displayed in a cell
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorValue::Div0 => "#DIV/0!",
+            ErrorValue::Value => "#VALUE!",
+            ErrorValue::Name => "#NAME?",
+            ErrorValue::Ref => "#REF!",
+            ErrorValue::NA => "#N/A",
+            ErrorValue::Num => "#NUM!",
+            ErrorValue::Null => "#NULL!",
+            ErrorValue::Calc => "#CALC!",
+            ErrorValue::Spill => "#SPILL!",
+        }
+    }
+
+    /// Parse an error code such as `#N/A` (case-insensitive)
+    pub fn from_code(code: &str) -> Option<ErrorValue> {
+        ErrorValue::ALL
+            .into_iter()
+            .find(|e| e.code().eq_ignore_ascii_case(code.trim()))
+    }
+}
+
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Blank,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Error(ErrorValue),
+    /// Result of a range reference or an array function, spilled by the host
+    Array(Array),
+}
+
+/// Row-major two-dimensional block of values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    rows: usize,
+    cols: usize,
+    values: Vec<Value>,
+}
+
+impl Array {
+    /// Build from row-major values; `values.len()` must be `rows * cols`
+    pub fn new(rows: usize, cols: usize, values: Vec<Value>) -> Self {
+        debug_assert_eq!(values.len(), rows * cols);
+        Array { rows, cols, values }
+    }
+
+    /// Build from a list of equally long rows
+    pub fn from_rows(rows: Vec<Vec<Value>>) -> Self {
+        let cols = rows.first().map_or(0, Vec::len);
+        let count = rows.len();
+        Array::new(count, cols, rows.into_iter().flatten().collect())
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&Value> {
+        if row < self.rows && col < self.cols {
+            self.values.get(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[Value] {
+        &self.values[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// Iterate over rows as slices
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[Value]> + '_ {
+        (0..self.rows).map(move |r| self.row(r))
+    }
+
+    pub fn transpose(&self) -> Array {
+        let mut values = Vec::with_capacity(self.values.len());
+        for c in 0..self.cols {
+            for r in 0..self.rows {
+                values.push(self.values[r * self.cols + c].clone());
+            }
+        }
+        Array::new(self.cols, self.rows, values)
+    }
+}
+
+impl Value {
+    /// Interpret raw cell text the way the grid displays it
+    ///
+    /// Numbers and `TRUE`/`FALSE` become typed values, error codes become
+    /// errors, whitespace-only cells are blank and everything else is text.
+    pub fn from_cell(text: &str) -> Value {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return Value::Blank;
+        }
+        if let Ok(number) = trimmed.parse::<f64>() {
+            if number.is_finite() {
+                return Value::Number(number);
+            }
+        }
+        if trimmed.eq_ignore_ascii_case("TRUE") {
+            return Value::Bool(true);
+        }
+        if trimmed.eq_ignore_ascii_case("FALSE") {
+            return Value::Bool(false);
+        }
+        if trimmed.starts_with('#') {
+            if let Some(error) = ErrorValue::from_code(trimmed) {
+                return Value::Error(error);
+            }
+        }
+        Value::Text(text.to_string())
+    }
+
+    /// Wrap a computed number, turning NaN and infinities into `#NUM!`
+    pub fn number(value: f64) -> Value {
+        if value.is_finite() {
+            Value::Number(value)
+        } else {
+            Value::Error(ErrorValue::Num)
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        matches!(self, Value::Blank)
+    }
+
+    /// Coerce to a number (blank is 0, booleans are 1/0, numeric text parses)
+    pub fn as_number(&self) -> Result<f64, ErrorValue> {
+        match self {
+            Value::Blank => Ok(0.0),
+            Value::Number(n) => Ok(*n),
+            Value::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+            Value::Text(text) => text.trim().parse::<f64>().map_err(|_| ErrorValue::Value),
+            Value::Error(e) => Err(*e),
+            Value::Array(_) => Err(ErrorValue::Value),
+        }
+    }
+
+    /// Coerce to text (numbers without trailing zeros, booleans upper-case)
+    pub fn as_text(&self) -> Result<String, ErrorValue> {
+        match self {
+            Value::Error(e) => Err(*e),
+            Value::Array(_) => Err(ErrorValue::Value),
+            other => Ok(other.to_string()),
+        }
+    }
+
+    /// Coerce to a boolean (numbers are non-zero, text must be TRUE/FALSE)
+    pub fn as_bool(&self) -> Result<bool, ErrorValue> {
+        match self {
+            Value::Blank => Ok(false),
+            Value::Number(n) => Ok(*n != 0.0),
+            Value::Bool(b) => Ok(*b),
+            Value::Text(text) if text.trim().eq_ignore_ascii_case("TRUE") => Ok(true),
+            Value::Text(text) if text.trim().eq_ignore_ascii_case("FALSE") => Ok(false),
+            Value::Text(_) | Value::Array(_) => Err(ErrorValue::Value),
+            Value::Error(e) => Err(*e),
+        }
+    }
+
+    /// Compare two values the way the `=`, `<` and `>` operators do
+    ///
+    /// Numbers come before text, text before booleans; text compares
+    /// case-insensitively and blank takes the zero value of the other side.
+    /// Because blank equals both 0 and FALSE this is not a total order, so
+    /// sort with [`Value::sort_order`] instead.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Number(_) => 0,
+                Value::Text(_) => 1,
+                Value::Bool(_) => 2,
+                Value::Blank | Value::Error(_) | Value::Array(_) => 3,
+            }
+        }
+
+        match (self, other) {
+            (Value::Blank, Value::Blank) => Ordering::Equal,
+            (Value::Blank, Value::Number(_)) => Value::Number(0.0).compare(other),
+            (Value::Blank, Value::Text(_)) => Value::Text(String::new()).compare(other),
+            (Value::Blank, Value::Bool(_)) => Value::Bool(false).compare(other),
+            (_, Value::Blank) => other.compare(self).reverse(),
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+
+    /// Total order for sorting: numbers, text (case-insensitive), booleans,
+    /// errors and finally blanks
+    pub fn sort_order(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Number(_) => 0,
+                Value::Text(_) => 1,
+                Value::Bool(_) => 2,
+                Value::Error(_) | Value::Array(_) => 3,
+                Value::Blank => 4,
+            }
+        }
+
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+/// Format a number the way cells show it: integers without a fraction
+pub fn format_number(value: f64) -> String {
+    if value == value.trunc() && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Blank => Ok(()),
+            Value::Number(n) => f.write_str(&format_number(*n)),
+            Value::Text(text) => f.write_str(text),
+            Value::Bool(true) => f.write_str("TRUE"),
+            Value::Bool(false) => f.write_str("FALSE"),
+            Value::Error(e) => f.write_str(e.code()),
+            // A single cell shows the top-left element of an array
+            Value::Array(array) => match array.values.first() {
+                Some(first) => first.fmt(f),
+                None => f.write_str(ErrorValue::Value.code()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cell_and_display() {
+        assert_eq!(Value::from_cell(" 42 "), Value::Number(42.0));
+        assert_eq!(Value::from_cell("true"), Value::Bool(true));
+        assert_eq!(Value::from_cell("#n/a"), Value::Error(ErrorValue::NA));
+        assert_eq!(Value::from_cell("  "), Value::Blank);
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+        assert_eq!(Value::Number(-3.0).to_string(), "-3");
+    }
+
+    #[test]
+    fn test_compare_mixed_types() {
+        let text = Value::Text("abc".into());
+        assert_eq!(Value::Number(1e9).compare(&text), Ordering::Less);
+        assert_eq!(text.compare(&Value::Bool(false)), Ordering::Less);
+        assert_eq!(text.compare(&Value::Text("ABC".into())), Ordering::Equal);
+        assert_eq!(Value::Blank.compare(&Value::Number(0.0)), Ordering::Equal);
+
+        assert_eq!(
+            Value::Blank.sort_order(&Value::Number(0.0)),
+            Ordering::Greater
+        );
+        assert_eq!(Value::Bool(true).sort_order(&Value::Blank), Ordering::Less);
+        assert_eq!(
+            Value::Number(0.0).sort_order(&Value::Bool(false)),
+            Ordering::Less
+        );
+    }
+}
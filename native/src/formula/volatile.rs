@@ -0,0 +1,117 @@
+//! Volatile functions: results that change without any input changing
+//!
+//! `NOW()`, `TODAY()`, `RAND()` and `RANDBETWEEN()` read the clock or a
+//! random generator instead of cells, so a workbook recalculates formulas
+//! calling them on every recalculation. Freezing fixes the clock and seeds
+//! the generator per formula cell, which makes results reproducible.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::Local;
+
+use super::parser::Expr;
+use crate::datetime::to_serial;
+
+/// Functions whose result can change between two evaluations
+pub const VOLATILE_FUNCTIONS: [&str; 4] = ["NOW", "TODAY", "RAND", "RANDBETWEEN"];
+
+/// Whether `expr` calls a volatile function anywhere
+pub fn is_volatile(expr: &Expr) -> bool {
+    let mut found = false;
+    expr.visit(&mut |node| {
+        if let Expr::Call(name, _) = node {
+            found |= VOLATILE_FUNCTIONS.contains(&name.as_str());
+        }
+    });
+    found
+}
+
+/// Fixed clock and random seed used instead of the real ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frozen {
+    /// Serial date-time returned by `NOW()`
+    pub now: f64,
+    pub seed: u64,
+}
+
+/// Local date and time as a serial number
+pub fn now() -> f64 {
+    to_serial(Local::now().naive_local())
+}
+
+/// SplitMix64 step: advances `state` and returns the next output
+fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Process-wide generator, seeded from the clock on first use
+pub fn random() -> f64 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let mut state = STATE.load(Ordering::Relaxed);
+    if state == 0 {
+        state = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64);
+    }
+    let value = next(&mut state);
+    // Races between threads only cost a repeated draw, never a bad value
+    STATE.store(state, Ordering::Relaxed);
+    unit(value)
+}
+
+/// Deterministic generator for one formula cell of a frozen workbook
+#[derive(Debug)]
+pub struct SeededRandom(Cell<u64>);
+
+impl SeededRandom {
+    pub fn new(seed: u64, (sheet, row, col): (usize, usize, usize)) -> Self {
+        let mut state = seed;
+        for part in [sheet, row, col] {
+            state = next(&mut state) ^ part as u64;
+        }
+        SeededRandom(Cell::new(state))
+    }
+
+    pub fn next(&self) -> f64 {
+        let mut state = self.0.get();
+        let value = next(&mut state);
+        self.0.set(state);
+        unit(value)
+    }
+
+    /// Uniform index in `0..n`; `n` must not be zero
+    pub fn below(&self, n: usize) -> usize {
+        ((self.next() * n as f64) as usize).min(n - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::parse;
+
+    #[test]
+    fn test_volatile_detection_and_seeding() {
+        assert!(is_volatile(&parse("=IF(A1, 1, RAND()*2)").unwrap()));
+        assert!(!is_volatile(&parse("=A1+[Now]").unwrap()));
+
+        let draws = |key| {
+            let rng = SeededRandom::new(7, key);
+            [rng.next(), rng.next()]
+        };
+        assert_eq!(draws((0, 1, 2)), draws((0, 1, 2)));
+        assert_ne!(draws((0, 1, 2)), draws((0, 2, 1)));
+        assert!(draws((0, 0, 0)).iter().all(|r| (0.0..1.0).contains(r)));
+        assert!((0.0..1.0).contains(&random()));
+    }
+}
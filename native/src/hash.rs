@@ -0,0 +1,336 @@
+//! Checksums and message digests of cell text
+//!
+//! Used by the `MD5`, `SHA1`, `SHA256` and `CRC32` formula functions and to
+//! hash whole rows into deduplication keys or integrity checks on exports.
+//! Text is hashed as UTF-8 and digests are returned as lowercase hex. None
+//! of this is meant for passwords or signatures.
+
+use std::os::raw::c_char;
+
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(HashAlgorithm::Md5),
+            1 => Some(HashAlgorithm::Sha1),
+            2 => Some(HashAlgorithm::Sha256),
+            3 => Some(HashAlgorithm::Crc32),
+            _ => None,
+        }
+    }
+
+    /// Lowercase hex digest of `data`
+    pub fn hex(self, data: &[u8]) -> String {
+        let digest = match self {
+            HashAlgorithm::Md5 => md5(data).to_vec(),
+            HashAlgorithm::Sha1 => sha1(data).to_vec(),
+            HashAlgorithm::Sha256 => sha256(data).to_vec(),
+            HashAlgorithm::Crc32 => crc32fast::hash(data).to_be_bytes().to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Message padded to whole 64-byte blocks, ending with its bit length
+fn padded(data: &[u8], big_endian: bool) -> Vec<u8> {
+    let bits = (data.len() as u64).wrapping_mul(8);
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend(match big_endian {
+        true => bits.to_be_bytes(),
+        false => bits.to_le_bytes(),
+    });
+    message
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for block in padded(data, false).chunks(64) {
+        let words: Vec<u32> = block
+            .chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let shift = SHIFTS[(i / 16) * 4 + i % 4];
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(shift);
+            (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (chunk, word) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xefcd_ab89,
+        0x98ba_dcfe,
+        0x1032_5476,
+        0xc3d2_e1f0,
+    ];
+    for block in padded(data, true).chunks(64) {
+        let mut words = [0u32; 80];
+        for (i, w) in block.chunks(4).enumerate() {
+            words[i] = u32::from_be_bytes([w[0], w[1], w[2], w[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a82_7999),
+                1 => (b ^ c ^ d, 0x6ed9_eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (a, b, c, d, e) = (temp, a, b.rotate_left(30), c, d);
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *word = word.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    for block in padded(data, true).chunks(64) {
+        let mut words = [0u32; 64];
+        for (i, w) in block.chunks(4).enumerate() {
+            words[i] = u32::from_be_bytes([w[0], w[1], w[2], w[3]]);
+        }
+        for i in 16..64 {
+            let s0 = words[i - 15].rotate_right(7)
+                ^ words[i - 15].rotate_right(18)
+                ^ (words[i - 15] >> 3);
+            let s1 = words[i - 2].rotate_right(17)
+                ^ words[i - 2].rotate_right(19)
+                ^ (words[i - 2] >> 10);
+            words[i] = words[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(words[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for (word, k) in words.iter().zip(SHA256_K) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (
+                g,
+                f,
+                e,
+                d.wrapping_add(temp1),
+                c,
+                b,
+                a,
+                temp1.wrapping_add(temp2),
+            );
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 32];
+    for (chunk, word) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Digest of each row's cells in `cols`, in row order
+///
+/// Cells are joined with the ASCII unit separator (0x1F) before hashing, so
+/// `["ab", "c"]` and `["a", "bc"]` hash differently.
+pub fn hash_rows(
+    table: &TesseraTable,
+    cols: &[usize],
+    algorithm: HashAlgorithm,
+) -> Result<Vec<String>, String> {
+    if let Some(col) = cols.iter().find(|&&col| col >= table.column_count()) {
+        return Err(format!("Column {} is out of range", col));
+    }
+
+    Ok((0..table.row_count())
+        .map(|row| {
+            let cells: Vec<&str> = cols.iter().map(|&col| table.cell(row, col)).collect();
+            algorithm.hex(cells.join("\u{1f}").as_bytes())
+        })
+        .collect())
+}
+
+/// Hash the cells of some columns, row by row
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `cols_ptr` - Column indices whose cells make up each row's key
+/// * `cols_count` - Number of columns
+/// * `algorithm` - 0 = MD5, 1 = SHA-1, 2 = SHA-256, 3 = CRC32
+///
+/// # Returns
+/// StringResult with a JSON array of lowercase hex digests, one per row
+///
+/// # Safety
+/// `table` must be a live table handle; `cols_ptr` must point to `cols_count` indices
+#[no_mangle]
+pub unsafe extern "C" fn tessera_hash_rows(
+    table: *const TesseraTable,
+    cols_ptr: *const usize,
+    cols_count: usize,
+    algorithm: u32,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if cols_ptr.is_null() && cols_count > 0 {
+        return StringResult::error("Null pointer provided");
+    }
+    let Some(algorithm) = HashAlgorithm::from_raw(algorithm) else {
+        return StringResult::error(&format!("Unknown hash algorithm {}", algorithm));
+    };
+
+    let cols = if cols_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(cols_ptr, cols_count)
+    };
+    hash_rows(table, cols, algorithm)
+        .map(|hashes| serde_json::json!(hashes).to_string())
+        .into()
+}
+
+/// Digest of a string
+///
+/// # Arguments
+/// * `text` - UTF-8 text to hash
+/// * `algorithm` - 0 = MD5, 1 = SHA-1, 2 = SHA-256, 3 = CRC32
+///
+/// # Returns
+/// StringResult with the lowercase hex digest
+///
+/// # Safety
+/// `text` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_hash_text(text: *const c_char, algorithm: u32) -> StringResult {
+    let Some(algorithm) = HashAlgorithm::from_raw(algorithm) else {
+        return StringResult::error(&format!("Unknown hash algorithm {}", algorithm));
+    };
+    let Some(text) = str_arg(text) else {
+        return StringResult::error("Invalid text encoding");
+    };
+    StringResult::success(&algorithm.hex(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digests() {
+        let hex = |algorithm: HashAlgorithm, text: &str| algorithm.hex(text.as_bytes());
+        assert_eq!(
+            hex(HashAlgorithm::Md5, ""),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_eq!(
+            hex(
+                HashAlgorithm::Md5,
+                "The quick brown fox jumps over the lazy dog"
+            ),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+        assert_eq!(
+            hex(HashAlgorithm::Sha1, "abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(HashAlgorithm::Sha256, "abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Two blocks once padded
+        assert_eq!(
+            hex(HashAlgorithm::Sha256, &"a".repeat(64)),
+            "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"
+        );
+        assert_eq!(hex(HashAlgorithm::Crc32, "123456789"), "cbf43926");
+
+        let table = TesseraTable::from_rows(
+            vec!["A".into(), "B".into()],
+            vec![vec!["ab".into(), "c".into()], vec!["a".into(), "bc".into()]],
+        );
+        let hashes = hash_rows(&table, &[0, 1], HashAlgorithm::Md5).unwrap();
+        assert_ne!(hashes[0], hashes[1]);
+        assert!(hash_rows(&table, &[2], HashAlgorithm::Md5).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Arrow C Data Interface interop
+//!
+//! Tables cross the boundary as Arrow record batches (a struct array plus its
+//! schema), so the C# host can hand whole columns to Arrow.NET or other Arrow
+//! consumers instead of marshaling one cell string at a time. Ownership
+//! follows the C Data Interface rules: exported structs are released by the
+//! consumer through their `release` callback, imported structs are moved in.
+
+use std::os::raw::c_char;
+use std::sync::Arc;
+
+use arrow_array::ffi::{from_ffi, to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, RecordBatchReader, StructArray};
+use arrow_schema::DataType;
+
+use super::columnar::{append_batch_rows, array_to_cells, schema_headers, to_record_batch};
+use crate::ffi::error_string;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Convert an imported Arrow array into a table
+///
+/// Struct arrays map field-per-column; any other array becomes a single column.
+fn table_from_array(array: Arc<dyn Array>, name: &str) -> Result<TesseraTable, String> {
+    if let DataType::Struct(_) = array.data_type() {
+        let struct_array = array
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .ok_or("Invalid Arrow struct array")?;
+        let batch = RecordBatch::from(struct_array.clone());
+
+        let mut rows = Vec::with_capacity(batch.num_rows());
+        append_batch_rows(&batch, &mut rows)?;
+        return Ok(TesseraTable::from_rows(
+            schema_headers(&batch.schema()),
+            rows,
+        ));
+    }
+
+    let header = if name.is_empty() { "value" } else { name };
+    let rows = array_to_cells(array.as_ref())?
+        .into_iter()
+        .map(|cell| vec![cell])
+        .collect();
+    Ok(TesseraTable::from_rows(vec![header.to_string()], rows))
+}
+
+/// Export a table as one record batch (struct array + schema)
+pub fn export_arrow(table: &TesseraTable) -> Result<(FFI_ArrowArray, FFI_ArrowSchema), String> {
+    let batch = to_record_batch(table)?;
+    let struct_array = StructArray::from(batch);
+    to_ffi(&struct_array.to_data()).map_err(|e| format!("Failed to export Arrow data: {}", e))
+}
+
+/// Export a table as a stream of record batches of at most `batch_size` rows
+pub fn export_arrow_stream(
+    table: &TesseraTable,
+    batch_size: usize,
+) -> Result<FFI_ArrowArrayStream, String> {
+    let batch = to_record_batch(table)?;
+    let schema = batch.schema();
+    let batch_size = if batch_size == 0 {
+        batch.num_rows().max(1)
+    } else {
+        batch_size
+    };
+
+    // Slices share the buffers of the single converted batch
+    let batches: Vec<RecordBatch> = (0..batch.num_rows())
+        .step_by(batch_size)
+        .map(|offset| batch.slice(offset, batch_size.min(batch.num_rows() - offset)))
+        .collect();
+
+    let reader = RecordBatchIterator::new(batches.into_iter().map(Ok), schema);
+    Ok(FFI_ArrowArrayStream::new(Box::new(reader)))
+}
+
+/// Import a table from a record batch in C Data Interface form
+///
+/// # Safety
+/// `array` and `schema` must describe a valid Arrow array
+pub unsafe fn import_arrow(
+    array: FFI_ArrowArray,
+    schema: &FFI_ArrowSchema,
+) -> Result<TesseraTable, String> {
+    let data = from_ffi(array, schema).map_err(|e| format!("Invalid Arrow data: {}", e))?;
+    table_from_array(arrow_array::make_array(data), schema.name().unwrap_or(""))
+}
+
+/// Import a table by draining an Arrow array stream
+pub fn import_arrow_stream(reader: ArrowArrayStreamReader) -> Result<TesseraTable, String> {
+    let headers = schema_headers(&reader.schema());
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to read Arrow stream: {}", e))?;
+        append_batch_rows(&batch, &mut rows)?;
+    }
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Export a table as an Arrow record batch through the C Data Interface
+///
+/// # Arguments
+/// * `table` - Table handle to export
+/// * `out_array` - Caller-allocated ArrowArray struct to fill
+/// * `out_schema` - Caller-allocated ArrowSchema struct to fill
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string).
+/// On success the caller owns both structs and must call their `release` callbacks.
+///
+/// # Safety
+/// `table` must be a live table handle; `out_array` and `out_schema` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_arrow(
+    table: *const TesseraTable,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    if out_array.is_null() || out_schema.is_null() {
+        return error_string("Null pointer provided");
+    }
+
+    match export_arrow(table) {
+        Ok((array, schema)) => {
+            std::ptr::write(out_array, array);
+            std::ptr::write(out_schema, schema);
+            std::ptr::null_mut()
+        }
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Export a table as an ArrowArrayStream of record batches
+///
+/// # Arguments
+/// * `table` - Table handle to export
+/// * `batch_size` - Maximum rows per batch, or 0 for a single batch
+/// * `out_stream` - Caller-allocated ArrowArrayStream struct to fill
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string).
+/// The stream snapshots the table, so the handle may be edited or freed afterwards.
+///
+/// # Safety
+/// `table` must be a live table handle; `out_stream` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_arrow_stream(
+    table: *const TesseraTable,
+    batch_size: usize,
+    out_stream: *mut FFI_ArrowArrayStream,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    if out_stream.is_null() {
+        return error_string("Null pointer provided");
+    }
+
+    match export_arrow_stream(table, batch_size) {
+        Ok(stream) => {
+            std::ptr::write(out_stream, stream);
+            std::ptr::null_mut()
+        }
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Import a table from an Arrow record batch (or single array)
+///
+/// Takes ownership of `array`: its contents are moved out and the caller's
+/// struct is left released. `schema` stays owned by the caller.
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free) or error message
+///
+/// # Safety
+/// `array` and `schema` must point to valid, unreleased C Data Interface structs
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_arrow(
+    array: *mut FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+) -> TableResult {
+    if array.is_null() || schema.is_null() {
+        return TableResult::error("Null pointer provided");
+    }
+
+    import_arrow(FFI_ArrowArray::from_raw(array), &*schema).into()
+}
+
+/// Import a table by draining an ArrowArrayStream
+///
+/// Takes ownership of the stream, which is left released.
+///
+/// # Safety
+/// `stream` must point to a valid, unreleased ArrowArrayStream
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_arrow_stream(
+    stream: *mut FFI_ArrowArrayStream,
+) -> TableResult {
+    if stream.is_null() {
+        return TableResult::error("Null pointer provided");
+    }
+
+    match ArrowArrayStreamReader::from_raw(stream) {
+        Ok(reader) => import_arrow_stream(reader).into(),
+        Err(e) => TableResult::error(&format!("Invalid Arrow stream: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_table() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["City".into(), "Population".into()],
+            vec![
+                vec!["Hanoi".into(), "8000000".into()],
+                vec!["Hue".into(), "".into()],
+                vec!["Da Nang".into(), "1200000".into()],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_record_batch_roundtrip() {
+        let table = sample_table();
+        let mut array = FFI_ArrowArray::empty();
+        let mut schema = FFI_ArrowSchema::empty();
+
+        let err = unsafe { tessera_export_arrow(&table, &mut array, &mut schema) };
+        assert!(err.is_null());
+
+        let result = unsafe { tessera_import_arrow(&mut array, &schema) };
+        assert!(result.error.is_null());
+        let imported = unsafe { Box::from_raw(result.table) };
+        assert_eq!(*imported, table);
+    }
+
+    #[test]
+    fn test_export_table_without_columns() {
+        let table = TesseraTable::from_rows(Vec::new(), vec![Vec::new(); 3]);
+        let mut array = FFI_ArrowArray::empty();
+        let mut schema = FFI_ArrowSchema::empty();
+
+        let err = unsafe { tessera_export_arrow(&table, &mut array, &mut schema) };
+        assert!(err.is_null());
+        let result = unsafe { tessera_import_arrow(&mut array, &schema) };
+        assert!(result.error.is_null());
+        let imported = unsafe { Box::from_raw(result.table) };
+        assert_eq!(*imported, table);
+
+        let mut stream = FFI_ArrowArrayStream::empty();
+        let err = unsafe { tessera_export_arrow_stream(&table, 2, &mut stream) };
+        assert!(err.is_null());
+    }
+
+    #[test]
+    fn test_stream_roundtrip_in_batches() {
+        let table = sample_table();
+        let mut stream = FFI_ArrowArrayStream::empty();
+
+        let err = unsafe { tessera_export_arrow_stream(&table, 2, &mut stream) };
+        assert!(err.is_null());
+
+        let result = unsafe { tessera_import_arrow_stream(&mut stream) };
+        assert!(result.error.is_null());
+        let imported = unsafe { Box::from_raw(result.table) };
+        assert_eq!(*imported, table);
+    }
+}
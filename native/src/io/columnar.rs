@@ -0,0 +1,211 @@
+//! Conversions between `TesseraTable` and Arrow record batches
+//!
+//! Columns are typed on the way out (boolean, integer, float, then text) so
+//! numeric data stays numeric in columnar files, and rendered back to the
+//! same cell text the grid shows on the way in.
+
+use std::sync::Arc;
+
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Float32Array, Float64Array, Int64Array, RecordBatch,
+    RecordBatchOptions, StringArray,
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, Field, Schema};
+
+use crate::formula::value::format_number;
+use crate::table::TesseraTable;
+
+/// `TRUE` or `FALSE` exactly as the grid writes them
+pub(crate) fn boolean_cell(cell: &str) -> Option<bool> {
+    match cell {
+        "TRUE" => Some(true),
+        "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+/// Integer whose text reads back unchanged, so `007` and `+5` stay text
+pub(crate) fn integer_cell(cell: &str) -> Option<i64> {
+    cell.parse::<i64>().ok().filter(|n| n.to_string() == cell)
+}
+
+/// Finite number whose text reads back unchanged, so `1.50`, `NaN` and
+/// ` 2` stay text
+pub(crate) fn float_cell(cell: &str) -> Option<f64> {
+    cell.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && format_number(*n) == cell)
+}
+
+/// Narrowest Arrow type that can hold every non-empty cell of a column
+/// without changing its text
+pub(crate) fn infer_column_type<'a>(cells: impl Iterator<Item = &'a str>) -> DataType {
+    let (mut boolean, mut integer, mut float, mut any) = (true, true, true, false);
+
+    for cell in cells.filter(|c| !c.is_empty()) {
+        any = true;
+        boolean &= boolean_cell(cell).is_some();
+        integer &= integer_cell(cell).is_some();
+        float &= float_cell(cell).is_some();
+        if !boolean && !float {
+            return DataType::Utf8;
+        }
+    }
+
+    match (any, boolean, integer, float) {
+        (false, ..) => DataType::Utf8,
+        (_, true, ..) => DataType::Boolean,
+        (_, _, true, _) => DataType::Int64,
+        (_, _, _, true) => DataType::Float64,
+        _ => DataType::Utf8,
+    }
+}
+
+fn column_array(table: &TesseraTable, col: usize, data_type: &DataType) -> ArrayRef {
+    let cells = table.column(col);
+    match data_type {
+        DataType::Boolean => Arc::new(cells.map(boolean_cell).collect::<BooleanArray>()),
+        DataType::Int64 => Arc::new(cells.map(integer_cell).collect::<Int64Array>()),
+        DataType::Float64 => Arc::new(cells.map(float_cell).collect::<Float64Array>()),
+        _ => Arc::new(
+            cells
+                .map(|c| (!c.is_empty()).then_some(c))
+                .collect::<StringArray>(),
+        ),
+    }
+}
+
+/// Convert a whole table into a single typed record batch
+pub(crate) fn to_record_batch(table: &TesseraTable) -> Result<RecordBatch, String> {
+    let mut fields = Vec::with_capacity(table.column_count());
+    let mut columns = Vec::with_capacity(table.column_count());
+
+    for (col, header) in table.headers().iter().enumerate() {
+        let data_type = infer_column_type(table.column(col));
+        columns.push(column_array(table, col, &data_type));
+        fields.push(Field::new(header, data_type, true));
+    }
+
+    // The row count is explicit so tables without columns keep their rows
+    let options = RecordBatchOptions::new().with_row_count(Some(table.row_count()));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options)
+        .map_err(|e| format!("Failed to build record batch: {}", e))
+}
+
+/// Column names of a record batch schema
+pub(crate) fn schema_headers(schema: &Schema) -> Vec<String> {
+    schema.fields().iter().map(|f| f.name().clone()).collect()
+}
+
+/// Render one Arrow array as cell text (nulls become empty cells)
+pub(crate) fn array_to_cells(array: &dyn Array) -> Result<Vec<String>, String> {
+    let len = array.len();
+    let text = |i: usize, value: String| {
+        if array.is_null(i) {
+            String::new()
+        } else {
+            value
+        }
+    };
+
+    // Booleans and floats use the grid's own spelling instead of Arrow's ("true", "3.0")
+    if let Some(values) = array.as_any().downcast_ref::<BooleanArray>() {
+        let cells =
+            (0..len).map(|i| text(i, if values.value(i) { "TRUE" } else { "FALSE" }.into()));
+        return Ok(cells.collect());
+    }
+    if let Some(values) = array.as_any().downcast_ref::<Float64Array>() {
+        return Ok((0..len)
+            .map(|i| text(i, values.value(i).to_string()))
+            .collect());
+    }
+    if let Some(values) = array.as_any().downcast_ref::<Float32Array>() {
+        return Ok((0..len)
+            .map(|i| text(i, values.value(i).to_string()))
+            .collect());
+    }
+
+    let options = FormatOptions::new().with_null("");
+    let formatter = ArrayFormatter::try_new(array, &options)
+        .map_err(|e| format!("Unsupported column type {}: {}", array.data_type(), e))?;
+    Ok((0..len).map(|i| formatter.value(i).to_string()).collect())
+}
+
+/// Append the rows of a record batch to `rows`
+pub(crate) fn append_batch_rows(
+    batch: &RecordBatch,
+    rows: &mut Vec<Vec<String>>,
+) -> Result<(), String> {
+    let start = rows.len();
+    rows.resize(
+        start + batch.num_rows(),
+        Vec::with_capacity(batch.num_columns()),
+    );
+
+    for column in batch.columns() {
+        for (row, cell) in rows[start..]
+            .iter_mut()
+            .zip(array_to_cells(column.as_ref())?)
+        {
+            row.push(cell);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_column_type() {
+        assert_eq!(
+            infer_column_type(["1", "", "-3"].into_iter()),
+            DataType::Int64
+        );
+        assert_eq!(
+            infer_column_type(["1", "2.5"].into_iter()),
+            DataType::Float64
+        );
+        assert_eq!(
+            infer_column_type(["TRUE", "FALSE"].into_iter()),
+            DataType::Boolean
+        );
+        assert_eq!(infer_column_type(["1", "x"].into_iter()), DataType::Utf8);
+        assert_eq!(infer_column_type(["", ""].into_iter()), DataType::Utf8);
+        for cell in ["007", "+5", "1.50", "NaN", "inf", " 2", "true"] {
+            assert_eq!(infer_column_type([cell].into_iter()), DataType::Utf8);
+        }
+    }
+
+    #[test]
+    fn test_batch_roundtrip_keeps_cell_text() {
+        let table = TesseraTable::from_rows(
+            vec!["n".into(), "f".into(), "b".into(), "s".into()],
+            vec![
+                vec!["1".into(), "3".into(), "TRUE".into(), "a".into()],
+                vec!["".into(), "1.5".into(), "".into(), "".into()],
+            ],
+        );
+        let lossy = TesseraTable::from_rows(
+            vec!["zip".into(), "price".into(), "sign".into(), "x".into()],
+            vec![
+                vec!["007".into(), "1.50".into(), "+5".into(), "NaN".into()],
+                vec!["12".into(), "2".into(), "6".into(), "inf".into()],
+                vec![" 3".into(), "3".into(), "7".into(), "true".into()],
+            ],
+        );
+
+        for table in [table, lossy] {
+            let batch = to_record_batch(&table).unwrap();
+            let mut rows = Vec::new();
+            append_batch_rows(&batch, &mut rows).unwrap();
+
+            let expected: Vec<Vec<&str>> = (0..table.row_count())
+                .map(|row| table.row(row).collect())
+                .collect();
+            assert_eq!(rows, expected);
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! CSV import, including a streaming mode for multi-gigabyte files
+//!
+//! A streaming import runs on a worker thread and appends rows to a shared
+//! table in chunks. The host polls progress, reads newly loaded rows to paint
+//! the first screen while the rest of the file is still loading, and can
+//! cancel at any time, keeping the rows loaded so far.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use csv::{ByteRecord, ReaderBuilder};
+
+use crate::ffi::str_arg;
+use crate::table::{CellText, TableResult, TesseraTable};
+
+/// Rows appended per chunk when the caller does not choose a size
+pub const DEFAULT_CHUNK_ROWS: usize = 10_000;
+
+/// Pick the delimiter from the header line, ignoring quoted sections
+///
+/// Mirrors `CsvLoader.DetectDelimiter` on the C# side, with tabs added.
+pub fn detect_delimiter(sample: &str) -> u8 {
+    let (mut commas, mut semicolons, mut tabs) = (0, 0, 0);
+    let mut in_quotes = false;
+
+    for c in sample.lines().next().unwrap_or("").chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => commas += 1,
+            ';' if !in_quotes => semicolons += 1,
+            '\t' if !in_quotes => tabs += 1,
+            _ => {}
+        }
+    }
+
+    if tabs > commas && tabs > semicolons {
+        b'\t'
+    } else if semicolons > commas {
+        b';'
+    } else {
+        b','
+    }
+}
+
+fn sniff_delimiter(path: &Path) -> Result<u8, String> {
+    let mut head = Vec::with_capacity(64 * 1024);
+    File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .take(64 * 1024)
+        .read_to_end(&mut head)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(detect_delimiter(&String::from_utf8_lossy(&head)))
+}
+
+fn record_cells(record: &ByteRecord) -> Vec<String> {
+    record
+        .iter()
+        .map(|field| String::from_utf8_lossy(field).into_owned())
+        .collect()
+}
+
+fn is_blank(record: &ByteRecord) -> bool {
+    record
+        .iter()
+        .all(|field| field.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Read a whole CSV file (delimiter 0 = detect)
+pub fn import_csv(path: &Path, delimiter: u8) -> Result<TesseraTable, String> {
+    let job = CsvImportJob::start(path, delimiter, usize::MAX)?;
+    job.finish()
+}
+
+/// Lifecycle of a streaming import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ImportState {
+    Running = 0,
+    Completed = 1,
+    Cancelled = 2,
+    Failed = 3,
+}
+
+impl ImportState {
+    fn from_raw(raw: u32) -> Self {
+        match raw {
+            0 => ImportState::Running,
+            1 => ImportState::Completed,
+            2 => ImportState::Cancelled,
+            _ => ImportState::Failed,
+        }
+    }
+}
+
+/// Progress snapshot of a streaming import
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ImportProgress {
+    pub state: u32, // ImportState
+    pub rows_loaded: usize,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+}
+
+struct JobShared {
+    table: Mutex<TesseraTable>,
+    rows_loaded: AtomicUsize,
+    bytes_read: AtomicU64,
+    total_bytes: u64,
+    state: AtomicU32,
+    cancel: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+/// Handle to a CSV file being loaded on a background thread
+pub struct CsvImportJob {
+    shared: Arc<JobShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl CsvImportJob {
+    /// Open the file and start loading it on a worker thread
+    pub fn start(path: &Path, delimiter: u8, chunk_rows: usize) -> Result<Self, String> {
+        let delimiter = if delimiter == 0 {
+            sniff_delimiter(path)?
+        } else {
+            delimiter
+        };
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let total_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let chunk_rows = if chunk_rows == 0 {
+            DEFAULT_CHUNK_ROWS
+        } else {
+            chunk_rows
+        };
+
+        let shared = Arc::new(JobShared {
+            table: Mutex::new(TesseraTable::default()),
+            rows_loaded: AtomicUsize::new(0),
+            bytes_read: AtomicU64::new(0),
+            total_bytes,
+            state: AtomicU32::new(ImportState::Running as u32),
+            cancel: AtomicBool::new(false),
+            error: Mutex::new(None),
+        });
+
+        let worker_shared = Arc::clone(&shared);
+        let worker = std::thread::Builder::new()
+            .name("tessera-csv-import".to_string())
+            .spawn(move || {
+                let state = match load_chunks(&worker_shared, file, delimiter, chunk_rows) {
+                    Ok(ImportState::Cancelled) => ImportState::Cancelled,
+                    Ok(_) => ImportState::Completed,
+                    Err(msg) => {
+                        *worker_shared.error.lock().unwrap() = Some(msg);
+                        ImportState::Failed
+                    }
+                };
+                worker_shared.state.store(state as u32, Ordering::Release);
+            })
+            .map_err(|e| format!("Failed to start import thread: {}", e))?;
+
+        Ok(CsvImportJob {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    pub fn progress(&self) -> ImportProgress {
+        ImportProgress {
+            state: self.shared.state.load(Ordering::Acquire),
+            rows_loaded: self.shared.rows_loaded.load(Ordering::Acquire),
+            bytes_read: self.shared.bytes_read.load(Ordering::Acquire),
+            total_bytes: self.shared.total_bytes,
+        }
+    }
+
+    /// Ask the worker to stop after the row it is currently reading
+    pub fn cancel(&self) {
+        self.shared.cancel.store(true, Ordering::Release);
+    }
+
+    /// Copy already-loaded rows `[start, start + count)` into a new table
+    pub fn read_rows(&self, start: usize, count: usize) -> TesseraTable {
+        let table = self.shared.table.lock().unwrap();
+        let end = start.saturating_add(count).min(table.row_count());
+        let rows = table
+            .rows()
+            .get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|cells| cells.iter().map(CellText::to_string).collect())
+            .collect();
+        TesseraTable::from_rows(table.headers().to_vec(), rows)
+    }
+
+    /// Wait for the worker and take the loaded table, which after a cancel
+    /// holds the rows loaded before it
+    pub fn finish(mut self) -> Result<TesseraTable, String> {
+        self.join();
+        match ImportState::from_raw(self.shared.state.load(Ordering::Acquire)) {
+            ImportState::Failed => Err(self
+                .shared
+                .error
+                .lock()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| "Import failed".to_string())),
+            _ => Ok(std::mem::take(&mut *self.shared.table.lock().unwrap())),
+        }
+    }
+
+    fn join(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                *self.shared.error.lock().unwrap() = Some("Import thread panicked".to_string());
+                self.shared
+                    .state
+                    .store(ImportState::Failed as u32, Ordering::Release);
+            }
+        }
+    }
+}
+
+impl Drop for CsvImportJob {
+    fn drop(&mut self) {
+        self.cancel();
+        self.join();
+    }
+}
+
+fn load_chunks(
+    shared: &JobShared,
+    file: File,
+    delimiter: u8,
+    chunk_rows: usize,
+) -> Result<ImportState, String> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(BufReader::with_capacity(1 << 20, file));
+
+    let mut record = ByteRecord::new();
+    let mut headers: Option<Vec<String>> = None;
+    let mut chunk: Vec<Vec<String>> = Vec::new();
+
+    let flush = |chunk: &mut Vec<Vec<String>>, bytes: u64| {
+        let mut table = shared.table.lock().unwrap();
+        for row in chunk.drain(..) {
+            // Records wider than the header get `ColumnN` headers, as in `from_rows`
+            let width = table.column_count();
+            if row.len() > width {
+                let names = (width..row.len())
+                    .map(|i| format!("Column{}", i + 1))
+                    .collect();
+                let _ = table.insert_columns(width, names);
+            }
+            table.push_row(row);
+        }
+        shared
+            .rows_loaded
+            .store(table.row_count(), Ordering::Release);
+        shared.bytes_read.store(bytes, Ordering::Release);
+    };
+
+    loop {
+        if shared.cancel.load(Ordering::Acquire) {
+            flush(&mut chunk, reader.position().byte());
+            return Ok(ImportState::Cancelled);
+        }
+
+        let more = reader
+            .read_byte_record(&mut record)
+            .map_err(|e| format!("Invalid CSV: {}", e))?;
+        if !more {
+            break;
+        }
+        if is_blank(&record) {
+            continue; // Same as CsvLoader: blank lines are skipped
+        }
+
+        match headers {
+            None => {
+                let mut names = record_cells(&record);
+                if let Some(first) = names.first_mut() {
+                    if let Some(name) = first.strip_prefix('\u{feff}') {
+                        *first = name.to_string();
+                    }
+                }
+                *shared.table.lock().unwrap() = TesseraTable::new(names.clone());
+                headers = Some(names);
+            }
+            Some(_) => {
+                chunk.push(record_cells(&record));
+                if chunk.len() >= chunk_rows {
+                    flush(&mut chunk, reader.position().byte());
+                }
+            }
+        }
+    }
+
+    flush(&mut chunk, shared.total_bytes);
+    Ok(ImportState::Completed)
+}
+
+/// Import a CSV file in one blocking call
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `delimiter` - Field delimiter byte, or 0 to detect (comma, semicolon or tab)
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_csv(path: *const c_char, delimiter: u8) -> TableResult {
+    let Some(path) = str_arg(path) else {
+        return TableResult::error("Invalid file path");
+    };
+
+    import_csv(Path::new(path), delimiter).into()
+}
+
+/// Start loading a CSV file in the background
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `delimiter` - Field delimiter byte, or 0 to detect
+/// * `chunk_rows` - Rows made visible per chunk, or 0 for the default (10,000)
+///
+/// # Returns
+/// Job handle (free with tessera_csv_import_free), or null if the file cannot be opened
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_start(
+    path: *const c_char,
+    delimiter: u8,
+    chunk_rows: usize,
+) -> *mut CsvImportJob {
+    let Some(path) = str_arg(path) else {
+        return std::ptr::null_mut();
+    };
+
+    match CsvImportJob::start(Path::new(path), delimiter, chunk_rows) {
+        Ok(job) => Box::into_raw(Box::new(job)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Current progress of a streaming import (rows visible so far, bytes read, state)
+///
+/// # Safety
+/// `job` must be a live job handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_progress(job: *const CsvImportJob) -> ImportProgress {
+    match job.as_ref() {
+        Some(job) => job.progress(),
+        None => ImportProgress {
+            state: ImportState::Failed as u32,
+            rows_loaded: 0,
+            bytes_read: 0,
+            total_bytes: 0,
+        },
+    }
+}
+
+/// Copy loaded rows `[start, start + count)` into a new table handle
+///
+/// Safe to call while the import is running; rows past `rows_loaded` are omitted.
+///
+/// # Safety
+/// `job` must be a live job handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_read_rows(
+    job: *const CsvImportJob,
+    start: usize,
+    count: usize,
+) -> TableResult {
+    match job.as_ref() {
+        Some(job) => TableResult::success(job.read_rows(start, count)),
+        None => TableResult::error("Null pointer provided"),
+    }
+}
+
+/// Request cancellation; the job stops at the next row
+///
+/// # Safety
+/// `job` must be a live job handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_cancel(job: *const CsvImportJob) {
+    if let Some(job) = job.as_ref() {
+        job.cancel();
+    }
+}
+
+/// Wait for the import to end and take the loaded table: the whole file,
+/// or the rows loaded before a cancel
+///
+/// Consumes the job handle; do not call tessera_csv_import_free afterwards.
+///
+/// # Safety
+/// `job` must be a live job handle that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_finish(job: *mut CsvImportJob) -> TableResult {
+    if job.is_null() {
+        return TableResult::error("Null pointer provided");
+    }
+
+    Box::from_raw(job).finish().into()
+}
+
+/// Cancel (if still running) and release a job handle
+///
+/// # Safety
+/// `job` must be null or a live job handle that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_csv_import_free(job: *mut CsvImportJob) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_csv(name: &str, contents: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("tessera_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_detect_delimiter_ignores_quotes() {
+        assert_eq!(detect_delimiter("a;b;\"c,d,e\"\n1;2;3"), b';');
+        assert_eq!(detect_delimiter("a,b,c"), b',');
+        assert_eq!(detect_delimiter("a\tb\tc"), b'\t');
+    }
+
+    #[test]
+    fn test_streaming_import_in_chunks() {
+        let mut contents = String::from("id;note\n");
+        for i in 0..250 {
+            contents.push_str(&format!("{};\"line {}\nwrapped\"\n\n", i, i));
+        }
+        let path = temp_csv("stream", &contents);
+
+        let job = CsvImportJob::start(&path, 0, 64).unwrap();
+        let table = job.finish();
+        std::fs::remove_file(&path).ok();
+
+        let table = table.unwrap();
+        assert_eq!(table.headers(), ["id", "note"]);
+        assert_eq!(table.row_count(), 250);
+        assert_eq!(table.cell(249, 1), "line 249\nwrapped");
+    }
+
+    #[test]
+    fn test_read_rows_and_cancel() {
+        let contents: String = std::iter::once("n\n".to_string())
+            .chain((0..50_000).map(|i| format!("{}\n", i)))
+            .collect();
+        let path = temp_csv("cancel", &contents);
+
+        let job = CsvImportJob::start(&path, b',', 100).unwrap();
+        while job.progress().rows_loaded < 100
+            && job.progress().state == ImportState::Running as u32
+        {
+            std::thread::yield_now();
+        }
+        let first_screen = job.read_rows(0, 3);
+        job.cancel();
+        let result = job.finish();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(first_screen.rows(), [vec!["0"], vec!["1"], vec!["2"]]);
+        // The file may have finished loading before the cancel landed
+        let table = result.unwrap();
+        assert!((100..=50_000).contains(&table.row_count()));
+        assert_eq!(table.cell(99, 0), "99");
+    }
+
+    #[test]
+    fn test_bom_and_records_wider_than_header() {
+        let path = temp_csv("wide", "\u{feff}id,name\n1,Ann\n2,Bob,x,y\n3\n");
+        let table = import_csv(&path, 0);
+        std::fs::remove_file(&path).ok();
+
+        let table = table.unwrap();
+        assert_eq!(table.headers(), ["id", "name", "Column3", "Column4"]);
+        assert_eq!(table.row(1).collect::<Vec<_>>(), ["2", "Bob", "x", "y"]);
+        assert_eq!(table.row(2).collect::<Vec<_>>(), ["3", "", "", ""]);
+    }
+}
@@ -0,0 +1,250 @@
+//! Fixed-width text import (mainframe reports, `ls -l` style listings)
+//!
+//! Column boundaries are either given explicitly as character offsets or
+//! inferred: from a header underline when the report has one, otherwise from
+//! character positions that are blank on every sampled line.
+
+use std::os::raw::c_char;
+use std::path::Path;
+
+use crate::ffi::str_arg;
+use crate::table::{TableResult, TesseraTable};
+use crate::IndexArray;
+
+/// Lines made only of rule characters (`-----  ====`) are decoration, not data
+fn is_rule_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty()
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '-' | '=' | '+' | '|' | ' ' | '\t'))
+}
+
+fn data_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !is_rule_line(line))
+        .collect()
+}
+
+/// Infer column start offsets (in characters) from the first `sample` lines
+///
+/// A position separates columns when it is whitespace (or past the end of
+/// the line) on every sampled line; each run of occupied positions is a column.
+pub fn infer_boundaries(lines: &[&str], sample: usize) -> Vec<usize> {
+    let sample = if sample == 0 {
+        lines.len()
+    } else {
+        sample.min(lines.len())
+    };
+    let mut occupied: Vec<bool> = Vec::new();
+
+    for line in &lines[..sample] {
+        for (pos, c) in line.chars().enumerate() {
+            if pos >= occupied.len() {
+                occupied.resize(pos + 1, false);
+            }
+            occupied[pos] |= !c.is_whitespace();
+        }
+    }
+
+    let mut starts = Vec::new();
+    let mut previous = false;
+    for (pos, &filled) in occupied.iter().enumerate() {
+        if filled && !previous {
+            starts.push(pos);
+        }
+        previous = filled;
+    }
+
+    // The first column always starts at 0 so leading indentation stays in it
+    if let Some(first) = starts.first_mut() {
+        *first = 0;
+    }
+    starts
+}
+
+/// Column starts taken from a header underline (`---- ------`), when the text has one
+///
+/// Underlines state the intended widths exactly, which beats guessing from
+/// blanks when values contain single spaces ("Gadget Pro").
+fn underline_boundaries(text: &str) -> Option<Vec<usize>> {
+    let rule = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .find(|line| is_rule_line(line) && line.trim().contains(' '))?;
+
+    let mut starts = Vec::new();
+    let mut previous = ' ';
+    for (pos, c) in rule.chars().enumerate() {
+        if !c.is_whitespace() && previous.is_whitespace() {
+            starts.push(pos);
+        }
+        previous = c;
+    }
+    if let Some(first) = starts.first_mut() {
+        *first = 0;
+    }
+    Some(starts)
+}
+
+/// Cut one line at the given column start offsets
+fn split_line(line: &str, starts: &[usize]) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts
+                .get(i + 1)
+                .copied()
+                .unwrap_or(chars.len())
+                .min(chars.len());
+            let start = start.min(end);
+            chars[start..end]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Parse fixed-width text into a table
+///
+/// # Arguments
+/// * `starts` - Column start offsets in characters, or None to infer them
+/// * `has_header` - Use the first data line as column names
+/// * `sample` - Lines to sample when inferring boundaries (0 = all)
+pub fn parse_fixed_width(
+    text: &str,
+    starts: Option<&[usize]>,
+    has_header: bool,
+    sample: usize,
+) -> Result<TesseraTable, String> {
+    let lines = data_lines(text);
+
+    let starts = match starts {
+        Some(explicit) => {
+            if explicit.windows(2).any(|w| w[0] >= w[1]) {
+                return Err("Column offsets must be strictly increasing".to_string());
+            }
+            explicit.to_vec()
+        }
+        None => underline_boundaries(text).unwrap_or_else(|| infer_boundaries(&lines, sample)),
+    };
+
+    let mut rows = lines.iter().map(|line| split_line(line, &starts));
+    let headers = if has_header {
+        rows.next().unwrap_or_default()
+    } else {
+        (1..=starts.len()).map(|i| format!("Column{}", i)).collect()
+    };
+
+    Ok(TesseraTable::from_rows(headers, rows.collect()))
+}
+
+/// Read a fixed-width text file into a table
+pub fn import_fixed_width(
+    path: &Path,
+    starts: Option<&[usize]>,
+    has_header: bool,
+    sample: usize,
+) -> Result<TesseraTable, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    parse_fixed_width(&text, starts, has_header, sample)
+}
+
+/// Import a fixed-width text file
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `starts_ptr` - Column start offsets in characters, or null to infer them
+/// * `starts_count` - Number of offsets
+/// * `has_header` - Treat the first data line as column names
+/// * `sample_lines` - Lines sampled when inferring boundaries (0 = all)
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string; `starts_ptr` must be null or point to `starts_count` values
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_fixed_width(
+    path: *const c_char,
+    starts_ptr: *const usize,
+    starts_count: usize,
+    has_header: bool,
+    sample_lines: usize,
+) -> TableResult {
+    let Some(path) = str_arg(path) else {
+        return TableResult::error("Invalid file path");
+    };
+
+    let starts = (!starts_ptr.is_null() && starts_count > 0)
+        .then(|| std::slice::from_raw_parts(starts_ptr, starts_count));
+    import_fixed_width(Path::new(path), starts, has_header, sample_lines).into()
+}
+
+/// Infer column start offsets for a fixed-width file without importing it
+///
+/// Lets the TUI preview the detected boundaries so the user can adjust them
+/// before calling tessera_import_fixed_width with explicit offsets.
+///
+/// # Returns
+/// IndexArray of column start offsets (free with tessera_free_index_array) or error message
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_infer_fixed_width_columns(
+    path: *const c_char,
+    sample_lines: usize,
+) -> IndexArray {
+    let Some(path) = str_arg(path) else {
+        return IndexArray::error("Invalid file path");
+    };
+
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to open file: {}", e))
+        .map(|text| {
+            underline_boundaries(&text)
+                .unwrap_or_else(|| infer_boundaries(&data_lines(&text), sample_lines))
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "\
+ID   Name          Amount
+---- ------------- ------
+   1 Widget         12.50
+  42 Gadget Pro      3.00
+1003 Thing         100.00
+";
+
+    #[test]
+    fn test_infer_boundaries_from_report() {
+        let table = parse_fixed_width(REPORT, None, true, 0).unwrap();
+
+        assert_eq!(table.headers(), ["ID", "Name", "Amount"]);
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.rows()[1], ["42", "Gadget Pro", "3.00"]);
+
+        let lines = ["ID  Name", " 1  Bolt", "12  Nut"];
+        assert_eq!(infer_boundaries(&lines, 0), [0, 4]);
+    }
+
+    #[test]
+    fn test_explicit_offsets_without_header() {
+        let table = parse_fixed_width("AB12xyz\nCD34\n", Some(&[0, 2, 4]), false, 0).unwrap();
+
+        assert_eq!(table.headers(), ["Column1", "Column2", "Column3"]);
+        assert_eq!(table.row(0).collect::<Vec<_>>(), ["AB", "12", "xyz"]);
+        assert_eq!(table.row(1).collect::<Vec<_>>(), ["CD", "34", ""]);
+        assert!(parse_fixed_width("x", Some(&[2, 2]), false, 0).is_err());
+    }
+}
@@ -0,0 +1,509 @@
+//! JSON import and export
+//!
+//! Two layouts are understood: an array of records (`[{"a": 1}, ...]`) and a
+//! columnar object (`{"a": [1, 2], "b": [3, 4]}`). Nested objects can be
+//! flattened into `parent.child` columns on import and rebuilt on export.
+
+use std::os::raw::c_char;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::ffi::{error_string, str_arg, to_c_string};
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Shape of the JSON document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// Array of objects, one per row
+    Records,
+    /// Object of equally long arrays, one per column
+    Columnar,
+}
+
+impl JsonLayout {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(JsonLayout::Records),
+            1 => Some(JsonLayout::Columnar),
+            _ => None,
+        }
+    }
+}
+
+/// How nested values are mapped onto columns when importing
+#[derive(Debug, Clone)]
+pub struct JsonImportSettings {
+    /// Expand nested objects into `parent<sep>child` columns
+    pub flatten_objects: bool,
+    /// Also expand arrays into `parent<sep>0`, `parent<sep>1`, ... columns
+    pub flatten_arrays: bool,
+    pub separator: String,
+    /// Maximum nesting depth to expand (0 = unlimited); deeper values stay JSON text
+    pub max_depth: usize,
+}
+
+impl Default for JsonImportSettings {
+    fn default() -> Self {
+        JsonImportSettings {
+            flatten_objects: true,
+            flatten_arrays: false,
+            separator: ".".to_string(),
+            max_depth: 0,
+        }
+    }
+}
+
+/// How table cells are written back out as JSON
+#[derive(Debug, Clone)]
+pub struct JsonExportSettings {
+    pub layout: JsonLayout,
+    /// Rebuild nested objects from `parent<sep>child` column names
+    pub unflatten: bool,
+    pub separator: String,
+    pub pretty: bool,
+}
+
+impl Default for JsonExportSettings {
+    fn default() -> Self {
+        JsonExportSettings {
+            layout: JsonLayout::Records,
+            unflatten: true,
+            separator: ".".to_string(),
+            pretty: true,
+        }
+    }
+}
+
+/// Parse JSON text (records or columnar layout, detected automatically) into a table
+pub fn import_json_str(text: &str, settings: &JsonImportSettings) -> Result<TesseraTable, String> {
+    let root: Value = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let records: Vec<Map<String, Value>> = match root {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(map) => map,
+                // Arrays of scalars become a single "value" column
+                other => Map::from_iter([("value".to_string(), other)]),
+            })
+            .collect(),
+        Value::Object(map) if !map.is_empty() && map.values().all(Value::is_array) => {
+            columnar_to_records(map)
+        }
+        Value::Object(map) => vec![map],
+        _ => return Err("JSON root must be an array or an object".to_string()),
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    let mut flat_rows: Vec<Map<String, Value>> = Vec::with_capacity(records.len());
+    for record in records {
+        let mut flat = Map::new();
+        for (key, value) in record {
+            flatten_into(&mut flat, key, value, settings, 1);
+        }
+        for key in flat.keys() {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+        flat_rows.push(flat);
+    }
+
+    let rows = flat_rows
+        .iter()
+        .map(|flat| {
+            headers
+                .iter()
+                .map(|key| flat.get(key).map(value_to_cell).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Read a JSON file into a table
+pub fn import_json(path: &Path, settings: &JsonImportSettings) -> Result<TesseraTable, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    import_json_str(&text, settings)
+}
+
+/// Serialize a table to JSON text
+pub fn export_json_string(table: &TesseraTable, settings: &JsonExportSettings) -> String {
+    let root = match settings.layout {
+        JsonLayout::Records => Value::Array(
+            (0..table.row_count())
+                .map(|row| {
+                    let pairs = table
+                        .headers()
+                        .iter()
+                        .zip(table.row(row))
+                        .map(|(h, c)| (h, cell_to_value(c)));
+                    Value::Object(build_object(pairs, settings))
+                })
+                .collect(),
+        ),
+        JsonLayout::Columnar => {
+            let columns = table.headers().iter().enumerate().map(|(col, header)| {
+                (
+                    header,
+                    Value::Array(table.column(col).map(cell_to_value).collect()),
+                )
+            });
+            Value::Object(build_object(columns, settings))
+        }
+    };
+
+    let serialized = if settings.pretty {
+        serde_json::to_string_pretty(&root)
+    } else {
+        serde_json::to_string(&root)
+    };
+    serialized.unwrap_or_default()
+}
+
+/// Write a table to a JSON file
+pub fn export_json(
+    table: &TesseraTable,
+    path: &Path,
+    settings: &JsonExportSettings,
+) -> Result<(), String> {
+    std::fs::write(path, export_json_string(table, settings))
+        .map_err(|e| format!("Failed to write file: {}", e))
+}
+
+fn columnar_to_records(columns: Map<String, Value>) -> Vec<Map<String, Value>> {
+    let len = columns
+        .values()
+        .filter_map(Value::as_array)
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    let mut records = vec![Map::new(); len];
+    for (key, column) in columns {
+        if let Value::Array(values) = column {
+            for (record, value) in records.iter_mut().zip(values) {
+                record.insert(key.clone(), value);
+            }
+        }
+    }
+    records
+}
+
+fn flatten_into(
+    flat: &mut Map<String, Value>,
+    key: String,
+    value: Value,
+    settings: &JsonImportSettings,
+    depth: usize,
+) {
+    let within_depth = settings.max_depth == 0 || depth <= settings.max_depth;
+    let children: Vec<(String, Value)> = match value {
+        Value::Object(map) if settings.flatten_objects && within_depth && !map.is_empty() => {
+            map.into_iter().collect()
+        }
+        Value::Array(items) if settings.flatten_arrays && within_depth && !items.is_empty() => {
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| (i.to_string(), item))
+                .collect()
+        }
+        other => {
+            flat.insert(key, other);
+            return;
+        }
+    };
+
+    for (child_key, child) in children {
+        let name = format!("{}{}{}", key, settings.separator, child_key);
+        flatten_into(flat, name, child, settings, depth + 1);
+    }
+}
+
+/// Cell text for a JSON value; containers that were not flattened stay as compact JSON
+fn value_to_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(true) => "TRUE".to_string(),
+        Value::Bool(false) => "FALSE".to_string(),
+        Value::Number(num) => num.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(_) | Value::Object(_) => value.to_string(),
+    }
+}
+
+/// JSON value for cell text, keeping strings that would not survive a numeric roundtrip
+fn cell_to_value(cell: &str) -> Value {
+    let trimmed = cell.trim();
+    if trimmed.is_empty() {
+        return Value::Null;
+    }
+    if trimmed.eq_ignore_ascii_case("true") {
+        return Value::Bool(true);
+    }
+    if trimmed.eq_ignore_ascii_case("false") {
+        return Value::Bool(false);
+    }
+
+    if trimmed.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        if let Ok(Value::Number(num)) = serde_json::from_str::<Value>(trimmed) {
+            // Only text the cell would show again: long IDs would lose digits
+            // as f64 and "1.50" its trailing zero
+            if num.as_f64().is_some_and(|n| format_number(n) == trimmed) {
+                return Value::Number(num);
+            }
+        }
+    }
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        if let Ok(value @ (Value::Object(_) | Value::Array(_))) =
+            serde_json::from_str::<Value>(trimmed)
+        {
+            return value;
+        }
+    }
+
+    Value::String(cell.to_string())
+}
+
+fn build_object<'a>(
+    pairs: impl Iterator<Item = (&'a String, Value)>,
+    settings: &JsonExportSettings,
+) -> Map<String, Value> {
+    let mut object = Map::new();
+    for (key, value) in pairs {
+        if settings.unflatten && !settings.separator.is_empty() && key.contains(&settings.separator)
+        {
+            let path: Vec<&str> = key.split(&settings.separator).collect();
+            if insert_path(&mut object, &path, value.clone()) {
+                continue;
+            }
+        }
+        // A null plain column must not clobber an object rebuilt from flattened columns
+        if value.is_null() && object.contains_key(key.as_str()) {
+            continue;
+        }
+        object.insert(key.clone(), value);
+    }
+    object
+}
+
+/// Insert `value` at a nested path, returning false when the path collides with a scalar
+fn insert_path(object: &mut Map<String, Value>, path: &[&str], value: Value) -> bool {
+    let (first, rest) = match path.split_first() {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    if rest.is_empty() {
+        if object.contains_key(*first) {
+            return false;
+        }
+        object.insert(first.to_string(), value);
+        return true;
+    }
+
+    match object
+        .entry(first.to_string())
+        .or_insert_with(|| Value::Object(Map::new()))
+    {
+        Value::Object(child) => insert_path(child, rest, value),
+        _ => false,
+    }
+}
+
+/// FFI options for `tessera_import_json`
+#[repr(C)]
+pub struct JsonImportOptions {
+    pub flatten_objects: bool,
+    pub flatten_arrays: bool,
+    pub separator: *const c_char, // null for "."
+    pub max_depth: usize,         // 0 for unlimited
+}
+
+/// FFI options for `tessera_export_json`
+#[repr(C)]
+pub struct JsonExportOptions {
+    pub layout: u32, // 0 = array of records, 1 = columnar object
+    pub unflatten: bool,
+    pub separator: *const c_char, // null for "."
+    pub pretty: bool,
+}
+
+unsafe fn import_settings(options: *const JsonImportOptions) -> JsonImportSettings {
+    match options.as_ref() {
+        Some(opts) => JsonImportSettings {
+            flatten_objects: opts.flatten_objects,
+            flatten_arrays: opts.flatten_arrays,
+            separator: str_arg(opts.separator).unwrap_or(".").to_string(),
+            max_depth: opts.max_depth,
+        },
+        None => JsonImportSettings::default(),
+    }
+}
+
+unsafe fn export_settings(options: *const JsonExportOptions) -> Result<JsonExportSettings, String> {
+    match options.as_ref() {
+        Some(opts) => Ok(JsonExportSettings {
+            layout: JsonLayout::from_raw(opts.layout).ok_or("Unknown JSON layout")?,
+            unflatten: opts.unflatten,
+            separator: str_arg(opts.separator).unwrap_or(".").to_string(),
+            pretty: opts.pretty,
+        }),
+        None => Ok(JsonExportSettings::default()),
+    }
+}
+
+/// Import a JSON file (array of records or columnar object)
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `options` - Flattening options, or null for defaults (flatten objects with ".")
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string; `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_json(
+    path: *const c_char,
+    options: *const JsonImportOptions,
+) -> TableResult {
+    let Some(path) = str_arg(path) else {
+        return TableResult::error("Invalid file path");
+    };
+
+    import_json(Path::new(path), &import_settings(options)).into()
+}
+
+/// Import JSON text already in memory (e.g. a pasted API payload)
+///
+/// # Safety
+/// `json` must be a valid C string; `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_json_text(
+    json: *const c_char,
+    options: *const JsonImportOptions,
+) -> TableResult {
+    let Some(json) = str_arg(json) else {
+        return TableResult::error("Invalid JSON encoding");
+    };
+
+    import_json_str(json, &import_settings(options)).into()
+}
+
+/// Export a table to a JSON file
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `path` must be a valid C string;
+/// `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_json(
+    table: *const TesseraTable,
+    path: *const c_char,
+    options: *const JsonExportOptions,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(path) = str_arg(path) else {
+        return error_string("Invalid file path");
+    };
+
+    match export_settings(options)
+        .and_then(|settings| export_json(table, Path::new(path), &settings))
+    {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Serialize a table to JSON text (caller must free with tessera_free_string)
+///
+/// Returns null when the handle is null or the options are invalid.
+///
+/// # Safety
+/// `table` must be a live table handle; `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_json_text(
+    table: *const TesseraTable,
+    options: *const JsonExportOptions,
+) -> *mut c_char {
+    match (table_arg(table), export_settings(options)) {
+        (Some(table), Ok(settings)) => to_c_string(&export_json_string(table, &settings)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_records_flattens_nested_objects() {
+        let json = r#"[
+            {"id": 1, "user": {"name": "Ann", "geo": {"country": "VN"}}, "tags": ["a", "b"]},
+            {"id": 2, "active": true, "user": null}
+        ]"#;
+
+        let table = import_json_str(json, &JsonImportSettings::default()).unwrap();
+        assert_eq!(
+            table.headers(),
+            [
+                "id",
+                "user.name",
+                "user.geo.country",
+                "tags",
+                "active",
+                "user"
+            ]
+        );
+        assert_eq!(table.cell(0, 2), "VN");
+        assert_eq!(table.cell(0, 3), r#"["a","b"]"#);
+        assert_eq!(table.cell(1, 4), "TRUE");
+        assert_eq!(table.cell(1, 1), "");
+    }
+
+    #[test]
+    fn test_import_columnar_with_depth_limit() {
+        let json = r#"{"a": [1, 2, 3], "b": [{"x": {"y": 1}}, null]}"#;
+        let settings = JsonImportSettings {
+            max_depth: 1,
+            ..Default::default()
+        };
+
+        let table = import_json_str(json, &settings).unwrap();
+        assert_eq!(table.headers(), ["a", "b.x", "b"]);
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.cell(0, 1), r#"{"y":1}"#);
+        assert_eq!(table.cell(2, 0), "3");
+    }
+
+    #[test]
+    fn test_export_roundtrip_rebuilds_nesting() {
+        let json = r#"[{"id":7,"zip":"007","price":"1.50","user":{"name":"Ann","tags":["x"]},"ok":false}]"#;
+        let table = import_json_str(json, &JsonImportSettings::default()).unwrap();
+
+        let settings = JsonExportSettings {
+            pretty: false,
+            ..Default::default()
+        };
+        assert_eq!(export_json_string(&table, &settings), json);
+
+        let columnar = JsonExportSettings {
+            layout: JsonLayout::Columnar,
+            unflatten: false,
+            pretty: false,
+            ..Default::default()
+        };
+        assert!(export_json_string(&table, &columnar)
+            .starts_with(r#"{"id":[7],"zip":["007"],"price":["1.50"],"user.name":["Ann"]"#));
+    }
+}
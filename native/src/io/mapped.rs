@@ -0,0 +1,352 @@
+//! Memory-mapped CSV backing for files too large to load into memory
+//!
+//! The file is mapped read-only and indexed once: only the byte offset of
+//! every `CHECKPOINT_STRIDE`-th record is kept, so the index of a 5 GB file
+//! stays in the low megabytes. Rows are parsed straight from the mapping when
+//! they are read, typically one viewport at a time.
+
+use std::fs::File;
+use std::os::raw::c_char;
+use std::path::Path;
+
+use csv::{ByteRecord, ReaderBuilder};
+use memmap2::Mmap;
+
+use super::csv::detect_delimiter;
+use crate::ffi::{error_string, str_arg, to_c_string};
+use crate::table::{TableResult, TesseraTable};
+
+/// Records between two stored offsets; a row read scans at most this many records
+const CHECKPOINT_STRIDE: usize = 64;
+
+/// Read-only, lazily parsed view over a CSV file
+pub struct MappedTable {
+    mmap: Mmap,
+    delimiter: u8,
+    headers: Vec<String>,
+    /// Byte offset of data records 0, STRIDE, 2*STRIDE, ...
+    checkpoints: Vec<usize>,
+    row_count: usize,
+}
+
+/// Offsets of every non-blank record start, honoring quoted newlines
+///
+/// Like the csv crate, a quote only opens a quoted field as the first byte
+/// of the field, so `5" screen` is plain text.
+struct RecordStarts<'a> {
+    bytes: &'a [u8],
+    delimiter: u8,
+    pos: usize,
+}
+
+impl<'a> RecordStarts<'a> {
+    fn new(bytes: &'a [u8], delimiter: u8) -> Self {
+        let pos = if bytes.starts_with(b"\xEF\xBB\xBF") {
+            3
+        } else {
+            0
+        };
+        RecordStarts {
+            bytes,
+            delimiter,
+            pos,
+        }
+    }
+}
+
+impl Iterator for RecordStarts<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.pos < self.bytes.len() {
+            let start = self.pos;
+            let (mut in_quotes, mut field_start) = (false, true);
+            let mut blank = true;
+
+            while self.pos < self.bytes.len() {
+                let b = self.bytes[self.pos];
+                self.pos += 1;
+                blank &= b.is_ascii_whitespace();
+                if in_quotes {
+                    // `""` is an escaped quote; any other quote closes the field
+                    if b == b'"' && self.bytes.get(self.pos) == Some(&b'"') {
+                        self.pos += 1;
+                    } else if b == b'"' {
+                        in_quotes = false;
+                    }
+                    continue;
+                }
+                match b {
+                    b'"' if field_start => in_quotes = true,
+                    b'\n' => break,
+                    _ => {}
+                }
+                field_start = b == self.delimiter;
+            }
+
+            if !blank {
+                return Some(start);
+            }
+        }
+        None
+    }
+}
+
+impl MappedTable {
+    /// Map a CSV file and index its records (delimiter 0 = detect)
+    pub fn open(path: &Path, delimiter: u8) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        // SAFETY: the mapping is read-only; like every mmap-backed reader we
+        // rely on the file not being truncated while the handle is alive
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| format!("Failed to map file: {}", e))?;
+
+        let delimiter = if delimiter == 0 {
+            let head = &mmap[..mmap.len().min(64 * 1024)];
+            detect_delimiter(&String::from_utf8_lossy(head))
+        } else {
+            delimiter
+        };
+
+        let mut starts = RecordStarts::new(&mmap, delimiter);
+        let header_start = starts.next();
+        let mut checkpoints = Vec::new();
+        let mut row_count = 0;
+        for (row, offset) in starts.enumerate() {
+            if row % CHECKPOINT_STRIDE == 0 {
+                checkpoints.push(offset);
+            }
+            row_count = row + 1;
+        }
+
+        let mut table = MappedTable {
+            mmap,
+            delimiter,
+            headers: Vec::new(),
+            checkpoints,
+            row_count,
+        };
+        if let Some(offset) = header_start {
+            table.headers = table.parse_from(offset, 1).pop().unwrap_or_default();
+        }
+        Ok(table)
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Parse up to `count` non-blank records starting at byte `offset`
+    fn parse_from(&self, offset: usize, count: usize) -> Vec<Vec<String>> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(&self.mmap[offset..]);
+
+        let mut record = ByteRecord::new();
+        let mut rows = Vec::with_capacity(count.min(4096));
+        while rows.len() < count {
+            match reader.read_byte_record(&mut record) {
+                Ok(true) => {}
+                _ => break,
+            }
+            if record.iter().all(|f| f.iter().all(u8::is_ascii_whitespace)) {
+                continue;
+            }
+            rows.push(
+                record
+                    .iter()
+                    .map(|field| String::from_utf8_lossy(field).into_owned())
+                    .collect(),
+            );
+        }
+        rows
+    }
+
+    /// Materialize data rows `[start, start + count)`
+    pub fn read_rows(&self, start: usize, count: usize) -> Vec<Vec<String>> {
+        if start >= self.row_count || count == 0 {
+            return Vec::new();
+        }
+
+        let count = count.min(self.row_count - start);
+        let checkpoint = start / CHECKPOINT_STRIDE;
+        let skip = start - checkpoint * CHECKPOINT_STRIDE;
+
+        let mut rows = self.parse_from(self.checkpoints[checkpoint], skip + count);
+        rows.drain(..skip.min(rows.len()));
+        for row in &mut rows {
+            row.resize(self.headers.len().max(row.len()), String::new());
+        }
+        rows
+    }
+
+    /// Iterate over every data row in order, one checkpoint block at a time
+    pub fn iter_rows(&self) -> impl Iterator<Item = Vec<String>> + '_ {
+        (0..self.checkpoints.len())
+            .flat_map(move |block| self.read_rows(block * CHECKPOINT_STRIDE, CHECKPOINT_STRIDE))
+    }
+
+    /// Copy a row range into a regular in-memory table
+    pub fn to_table(&self, start: usize, count: usize) -> TesseraTable {
+        TesseraTable::from_rows(self.headers.clone(), self.read_rows(start, count))
+    }
+}
+
+/// FFI-safe result for functions that produce a mapped table handle
+#[repr(C)]
+pub struct MappedTableResult {
+    pub table: *mut MappedTable,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+/// Open a CSV file as a memory-mapped table
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `delimiter` - Field delimiter byte, or 0 to detect
+///
+/// # Returns
+/// MappedTableResult with a handle (free with tessera_mapped_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_open(
+    path: *const c_char,
+    delimiter: u8,
+) -> MappedTableResult {
+    let result = match str_arg(path) {
+        Some(path) => MappedTable::open(Path::new(path), delimiter),
+        None => Err("Invalid file path".to_string()),
+    };
+
+    match result {
+        Ok(table) => MappedTableResult {
+            table: Box::into_raw(Box::new(table)),
+            error: std::ptr::null_mut(),
+        },
+        Err(msg) => MappedTableResult {
+            table: std::ptr::null_mut(),
+            error: error_string(&msg),
+        },
+    }
+}
+
+/// Release a mapped table handle (unmaps the file)
+///
+/// # Safety
+/// `table` must be null or a mapped handle that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_free(table: *mut MappedTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+/// Number of data rows (header excluded)
+///
+/// # Safety
+/// `table` must be null or a live mapped handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_row_count(table: *const MappedTable) -> usize {
+    table.as_ref().map_or(0, MappedTable::row_count)
+}
+
+/// Number of columns
+///
+/// # Safety
+/// `table` must be null or a live mapped handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_column_count(table: *const MappedTable) -> usize {
+    table.as_ref().map_or(0, MappedTable::column_count)
+}
+
+/// Column name at `col` (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live mapped handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_header(
+    table: *const MappedTable,
+    col: usize,
+) -> *mut c_char {
+    match table.as_ref().and_then(|t| t.headers().get(col)) {
+        Some(header) => to_c_string(header),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Materialize rows `[start, start + count)` as a regular table handle
+///
+/// Intended for the visible viewport; the rest of the file stays on disk.
+///
+/// # Safety
+/// `table` must be a live mapped handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_read_rows(
+    table: *const MappedTable,
+    start: usize,
+    count: usize,
+) -> TableResult {
+    match table.as_ref() {
+        Some(table) => TableResult::success(table.to_table(start, count)),
+        None => TableResult::error("Null pointer provided"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_access_across_checkpoints() {
+        let mut contents = String::from("id,text\n\n");
+        for i in 0..200 {
+            contents.push_str(&format!("{},\"row {}\nsecond line\"\n", i, i));
+        }
+        let path = std::env::temp_dir().join(format!("tessera_mapped_{}.csv", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let table = MappedTable::open(&path, 0).unwrap();
+        assert_eq!(table.headers(), ["id", "text"]);
+        assert_eq!(table.row_count(), 200);
+
+        let rows = table.read_rows(63, 3);
+        assert_eq!(rows[0], ["63", "row 63\nsecond line"]);
+        assert_eq!(rows[2][0], "65");
+        assert!(table.read_rows(200, 5).is_empty());
+        assert_eq!(table.read_rows(198, 5).len(), 2);
+        assert_eq!(table.iter_rows().count(), 200);
+
+        drop(table);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_bom_and_quotes_inside_unquoted_fields() {
+        let contents =
+            "\u{feff}item,note\n5\" screen,a \"\"b\"\"\n\"x\"\"y\",\"two\nlines\"\nlast,\n";
+        let path =
+            std::env::temp_dir().join(format!("tessera_mapped_quotes_{}.csv", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let table = MappedTable::open(&path, b',').unwrap();
+        assert_eq!(table.headers(), ["item", "note"]);
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.read_rows(0, 1)[0], ["5\" screen", "a \"\"b\"\""]);
+        assert_eq!(table.read_rows(1, 1)[0], ["x\"y", "two\nlines"]);
+        assert_eq!(table.read_rows(2, 1)[0], ["last", ""]);
+
+        drop(table);
+        std::fs::remove_file(&path).ok();
+    }
+}
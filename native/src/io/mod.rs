@@ -0,0 +1,24 @@
+//! File importers and exporters that produce or consume `TesseraTable` handles
+
+pub mod arrow;
+mod columnar;
+pub mod csv;
+pub mod fixed_width;
+pub mod json;
+pub mod mapped;
+pub mod ods;
+pub mod parquet;
+pub mod sqlite;
+pub mod xlsx;
+
+use crate::table::TesseraTable;
+
+/// Build a table from a grid whose first row holds the column names
+pub(crate) fn table_from_grid(mut grid: Vec<Vec<String>>) -> TesseraTable {
+    if grid.is_empty() {
+        return TesseraTable::default();
+    }
+
+    let headers = grid.remove(0);
+    TesseraTable::from_rows(headers, grid)
+}
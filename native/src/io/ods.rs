@@ -0,0 +1,418 @@
+//! OpenDocument Spreadsheet (.ods) import and export
+//!
+//! Only cell values are exchanged: the first row of the sheet becomes the
+//! header row, numeric cells are read from their typed `office:value` so
+//! locale-formatted display text never leaks into the data.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::table_from_grid;
+use crate::ffi::{error_string, str_arg};
+use crate::formula::value::format_number;
+use crate::table::{table_arg, CellText, TableResult, TesseraTable};
+
+const MIME_TYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+/// Read one sheet of an .ods file (the first sheet when `sheet_name` is None)
+pub fn import_ods(path: &Path, sheet_name: Option<&str>) -> Result<TesseraTable, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut archive =
+        ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Invalid ODS archive: {}", e))?;
+
+    let mut content = String::new();
+    archive
+        .by_name("content.xml")
+        .map_err(|_| "ODS archive has no content.xml".to_string())?
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read content.xml: {}", e))?;
+
+    let grid = read_sheet_grid(&content, sheet_name)?;
+    Ok(table_from_grid(grid))
+}
+
+/// Write a table as a single-sheet .ods file
+pub fn export_ods(table: &TesseraTable, path: &Path, sheet_name: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    // The mimetype entry must come first and be stored uncompressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let write_error = |e: &dyn std::fmt::Display| format!("Failed to write ODS file: {}", e);
+
+    zip.start_file("mimetype", stored)
+        .map_err(|e| write_error(&e))?;
+    zip.write_all(MIME_TYPE.as_bytes())
+        .map_err(|e| write_error(&e))?;
+    zip.start_file("META-INF/manifest.xml", deflated)
+        .map_err(|e| write_error(&e))?;
+    zip.write_all(MANIFEST.as_bytes())
+        .map_err(|e| write_error(&e))?;
+    zip.start_file("content.xml", deflated)
+        .map_err(|e| write_error(&e))?;
+    zip.write_all(content_xml(table, sheet_name).as_bytes())
+        .map_err(|e| write_error(&e))?;
+    zip.finish().map_err(|e| write_error(&e))?;
+
+    Ok(())
+}
+
+/// Accumulates rows while collapsing ODS repeat attributes
+///
+/// Spreadsheet apps pad sheets with huge repeated blocks of empty rows and
+/// cells, so trailing empties are only materialized once real content follows.
+#[derive(Default)]
+struct GridBuilder {
+    grid: Vec<Vec<String>>,
+    pending_empty_rows: usize,
+    row: Vec<String>,
+    pending_empty_cells: usize,
+}
+
+impl GridBuilder {
+    fn push_cell(&mut self, value: String, repeat: usize) {
+        if value.is_empty() {
+            self.pending_empty_cells += repeat;
+            return;
+        }
+
+        let pending = std::mem::take(&mut self.pending_empty_cells);
+        self.row.extend(std::iter::repeat_n(String::new(), pending));
+        self.row.extend(std::iter::repeat_n(value, repeat));
+    }
+
+    fn finish_row(&mut self, repeat: usize) {
+        self.pending_empty_cells = 0;
+        let row = std::mem::take(&mut self.row);
+        if row.is_empty() {
+            self.pending_empty_rows += repeat;
+            return;
+        }
+
+        let pending = std::mem::take(&mut self.pending_empty_rows);
+        self.grid.extend(std::iter::repeat_n(Vec::new(), pending));
+        self.grid.extend(std::iter::repeat_n(row, repeat));
+    }
+}
+
+fn attr_value(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn repeat_attr(element: &BytesStart, name: &[u8]) -> usize {
+    attr_value(element, name)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Typed value of a cell, when the cell carries one
+fn typed_value(element: &BytesStart) -> Option<String> {
+    let value_type = attr_value(element, b"office:value-type")?;
+    match value_type.as_str() {
+        "float" | "percentage" | "currency" => attr_value(element, b"office:value"),
+        "date" => attr_value(element, b"office:date-value"),
+        "time" => attr_value(element, b"office:time-value"),
+        "boolean" => attr_value(element, b"office:boolean-value").map(|v| v.to_uppercase()),
+        _ => None,
+    }
+}
+
+fn read_sheet_grid(content: &str, sheet_name: Option<&str>) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut builder = GridBuilder::default();
+
+    let mut found = false;
+    let mut in_sheet = false;
+    let mut row_repeat = 1;
+    // (typed value, collected text, repeat count) for the cell being read
+    let mut cell: Option<(Option<String>, String, usize)> = None;
+    let mut paragraphs = 0;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid content.xml: {}", e))?;
+
+        if !in_sheet {
+            let is_start = matches!(event, Event::Start(_));
+            match event {
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"table:table" => {
+                    let name = attr_value(&e, b"table:name").unwrap_or_default();
+                    if sheet_name.is_none_or(|wanted| wanted == name) {
+                        found = true;
+                        // A self-closing <table:table/> is an empty sheet
+                        if !is_start {
+                            break;
+                        }
+                        in_sheet = true;
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(e) => match e.name().as_ref() {
+                b"table:table-row" => row_repeat = repeat_attr(&e, b"table:number-rows-repeated"),
+                b"table:table-cell" | b"table:covered-table-cell" => {
+                    let repeat = repeat_attr(&e, b"table:number-columns-repeated");
+                    cell = Some((typed_value(&e), String::new(), repeat));
+                    paragraphs = 0;
+                }
+                b"text:p" => {
+                    if let Some((_, text, _)) = cell.as_mut() {
+                        if paragraphs > 0 {
+                            text.push('\n');
+                        }
+                        paragraphs += 1;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"table:table-row" => {
+                    builder.finish_row(repeat_attr(&e, b"table:number-rows-repeated"))
+                }
+                b"table:table-cell" | b"table:covered-table-cell" => {
+                    let repeat = repeat_attr(&e, b"table:number-columns-repeated");
+                    builder.push_cell(typed_value(&e).unwrap_or_default(), repeat);
+                }
+                b"text:s" => {
+                    if let Some((_, text, _)) = cell.as_mut() {
+                        let spaces = repeat_attr(&e, b"text:c");
+                        text.extend(std::iter::repeat_n(' ', spaces));
+                    }
+                }
+                b"text:tab" => {
+                    if let Some((_, text, _)) = cell.as_mut() {
+                        text.push('\t');
+                    }
+                }
+                b"text:line-break" => {
+                    if let Some((_, text, _)) = cell.as_mut() {
+                        text.push('\n');
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(e) => {
+                if let Some((_, text, _)) = cell.as_mut() {
+                    let unescaped = e
+                        .unescape()
+                        .map_err(|e| format!("Invalid content.xml: {}", e))?;
+                    text.push_str(&unescaped);
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"table:table" => break,
+                b"table:table-row" => builder.finish_row(row_repeat),
+                b"table:table-cell" | b"table:covered-table-cell" => {
+                    if let Some((typed, text, repeat)) = cell.take() {
+                        builder.push_cell(typed.unwrap_or(text), repeat);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !found {
+        return Err(match sheet_name {
+            Some(name) => format!("Sheet '{}' not found", name),
+            None => "ODS file contains no sheets".to_string(),
+        });
+    }
+
+    Ok(builder.grid)
+}
+
+fn write_cell(xml: &mut String, value: &str) {
+    if value.is_empty() {
+        xml.push_str("<table:table-cell/>");
+        return;
+    }
+
+    // Only numbers that read back the same are floats, so "007" and "1.50"
+    // keep their digits
+    let text = escape(value);
+    match value.parse::<f64>() {
+        Ok(num) if num.is_finite() && format_number(num) == value => xml.push_str(&format!(
+            r#"<table:table-cell office:value-type="float" office:value="{}"><text:p>{}</text:p></table:table-cell>"#,
+            num, text
+        )),
+        _ => xml.push_str(&format!(
+            r#"<table:table-cell office:value-type="string"><text:p>{}</text:p></table:table-cell>"#,
+            text
+        )),
+    }
+}
+
+fn content_xml(table: &TesseraTable, sheet_name: &str) -> String {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0""#,
+        r#" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0""#,
+        r#" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" office:version="1.2">"#,
+        r#"<office:body><office:spreadsheet>"#
+    ));
+
+    xml.push_str(&format!(
+        r#"<table:table table:name="{}">"#,
+        escape(sheet_name)
+    ));
+    xml.push_str(&format!(
+        r#"<table:table-column table:number-columns-repeated="{}"/>"#,
+        table.column_count().max(1)
+    ));
+
+    let headers = table.headers().iter().map(String::as_str).collect();
+    let rows = table
+        .rows()
+        .iter()
+        .map(|cells| cells.iter().map(CellText::as_str).collect::<Vec<_>>());
+    for cells in std::iter::once(headers).chain(rows) {
+        xml.push_str("<table:table-row>");
+        for value in cells {
+            write_cell(&mut xml, value);
+        }
+        xml.push_str("</table:table-row>");
+    }
+
+    xml.push_str("</table:table></office:spreadsheet></office:body></office:document-content>");
+    xml
+}
+
+/// Import a sheet from an .ods file
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `sheet_name` - Sheet to read, or null for the first sheet
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string; `sheet_name` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_ods(
+    path: *const c_char,
+    sheet_name: *const c_char,
+) -> TableResult {
+    let Some(path) = str_arg(path) else {
+        return TableResult::error("Invalid file path");
+    };
+
+    import_ods(Path::new(path), str_arg(sheet_name)).into()
+}
+
+/// Export a table to an .ods file
+///
+/// # Arguments
+/// * `table` - Table handle to write
+/// * `path` - C string with the destination path
+/// * `sheet_name` - Sheet name, or null for "Sheet1"
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; string arguments must be null or valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_ods(
+    table: *const TesseraTable,
+    path: *const c_char,
+    sheet_name: *const c_char,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(path) = str_arg(path) else {
+        return error_string("Invalid file path");
+    };
+
+    match export_ods(
+        table,
+        Path::new(path),
+        str_arg(sheet_name).unwrap_or("Sheet1"),
+    ) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_collapses_repeats() {
+        let xml = r#"<office:document-content><office:body><office:spreadsheet>
+            <table:table table:name="Data">
+              <table:table-row>
+                <table:table-cell office:value-type="string"><text:p>Name</text:p></table:table-cell>
+                <table:table-cell office:value-type="string"><text:p>Price</text:p></table:table-cell>
+                <table:table-cell table:number-columns-repeated="16382"/>
+              </table:table-row>
+              <table:table-row>
+                <table:table-cell office:value-type="string"><text:p>a<text:s text:c="2"/>b</text:p></table:table-cell>
+                <table:table-cell office:value-type="float" office:value="1.5"><text:p>1,50</text:p></table:table-cell>
+              </table:table-row>
+              <table:table-row table:number-rows-repeated="1048574"><table:table-cell/></table:table-row>
+            </table:table>
+          </office:spreadsheet></office:body></office:document-content>"#;
+
+        let table = table_from_grid(read_sheet_grid(xml, None).unwrap());
+        assert_eq!(table.headers(), ["Name", "Price"]);
+        assert_eq!(table.row_count(), 1);
+        assert_eq!(table.cell(0, 0), "a  b");
+        assert_eq!(table.cell(0, 1), "1.5");
+    }
+
+    #[test]
+    fn test_missing_sheet() {
+        let xml =
+            r#"<office:document-content><table:table table:name="A"/></office:document-content>"#;
+        assert!(read_sheet_grid(xml, Some("B")).is_err());
+        assert!(read_sheet_grid(xml, Some("A")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut table = TesseraTable::new(vec!["Item".into(), "Qty".into()]);
+        table.push_row(vec!["Bolt <M4>".into(), "12".into()]);
+        table.push_row(vec!["".into(), "0.25".into()]);
+        table.push_row(vec!["007".into(), "1.50".into()]);
+
+        let path = std::env::temp_dir().join(format!("tessera_ods_{}.ods", std::process::id()));
+        export_ods(&table, &path, "Parts").unwrap();
+        let loaded = import_ods(&path, Some("Parts"));
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.unwrap(), table);
+    }
+}
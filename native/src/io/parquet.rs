@@ -0,0 +1,138 @@
+//! Apache Parquet import and export
+//!
+//! Files are read through the Arrow reader one record batch at a time and
+//! written with per-column types inferred from the cell text.
+
+use std::fs::File;
+use std::os::raw::c_char;
+use std::path::Path;
+
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use super::columnar::{append_batch_rows, schema_headers, to_record_batch};
+use crate::ffi::{error_string, str_arg};
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Read a Parquet file into a table
+///
+/// `max_rows` limits how many rows are loaded (0 = all) so very large lake
+/// files can be previewed without materializing everything.
+pub fn import_parquet(path: &Path, max_rows: usize) -> Result<TesseraTable, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| format!("Invalid Parquet file: {}", e))?;
+
+    let headers = schema_headers(builder.schema());
+    let builder = if max_rows > 0 {
+        builder.with_limit(max_rows)
+    } else {
+        builder
+    };
+    let reader = builder
+        .build()
+        .map_err(|e| format!("Invalid Parquet file: {}", e))?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to read Parquet data: {}", e))?;
+        append_batch_rows(&batch, &mut rows)?;
+    }
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Write a table to a Snappy-compressed Parquet file
+pub fn export_parquet(table: &TesseraTable, path: &Path) -> Result<(), String> {
+    let batch = to_record_batch(table)?;
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let write_error =
+        |e: parquet::errors::ParquetError| format!("Failed to write Parquet file: {}", e);
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), Some(props)).map_err(write_error)?;
+    writer.write(&batch).map_err(write_error)?;
+    writer.close().map_err(write_error)?;
+
+    Ok(())
+}
+
+/// Import a Parquet file
+///
+/// # Arguments
+/// * `path` - C string with the file path
+/// * `max_rows` - Maximum number of rows to load, or 0 for all rows
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_import_parquet(
+    path: *const c_char,
+    max_rows: usize,
+) -> TableResult {
+    let Some(path) = str_arg(path) else {
+        return TableResult::error("Invalid file path");
+    };
+
+    import_parquet(Path::new(path), max_rows).into()
+}
+
+/// Export a table to a Parquet file
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_export_parquet(
+    table: *const TesseraTable,
+    path: *const c_char,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(path) = str_arg(path) else {
+        return error_string("Invalid file path");
+    };
+
+    match export_parquet(table, Path::new(path)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let table = TesseraTable::from_rows(
+            vec!["Region".into(), "Amount".into(), "Units".into()],
+            vec![
+                vec!["EU".into(), "10.5".into(), "3".into()],
+                vec!["US".into(), "".into(), "7".into()],
+                vec!["".into(), "2".into(), "".into()],
+            ],
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("tessera_parquet_{}.parquet", std::process::id()));
+        export_parquet(&table, &path).unwrap();
+        let loaded = import_parquet(&path, 0);
+        let preview = import_parquet(&path, 2);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.unwrap(), table);
+        assert_eq!(preview.unwrap().row_count(), 2);
+    }
+}
@@ -0,0 +1,378 @@
+//! SQLite import, export and ad-hoc queries
+//!
+//! A database file can be browsed (list tables), loaded table-by-table or
+//! through an arbitrary SELECT, and a table handle can be written back as a
+//! SQLite table with column affinities inferred from the cell text. The same
+//! machinery runs SQL over a table handle by copying it into an in-memory
+//! database first.
+
+use std::os::raw::c_char;
+use std::path::Path;
+
+use arrow_schema::DataType;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+
+use super::columnar::{float_cell, infer_column_type, integer_cell};
+use crate::ffi::{error_string, str_arg};
+use crate::table::{table_arg, TableResult, TesseraTable};
+use crate::StringResult;
+
+fn sql_error(e: rusqlite::Error) -> String {
+    format!("SQLite error: {}", e)
+}
+
+fn open_read_only(path: &Path) -> Result<Connection, String> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sql_error)
+}
+
+/// Quote an identifier for use in generated SQL
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn value_to_cell(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        ValueRef::Blob(bytes) => bytes.iter().map(|b| format!("{:02X}", b)).collect(),
+    }
+}
+
+/// Names of the user tables in a database file
+pub fn list_tables(path: &Path) -> Result<Vec<String>, String> {
+    let conn = open_read_only(path)?;
+    let mut stmt = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+        .map_err(sql_error)?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(sql_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(sql_error)?;
+    Ok(names)
+}
+
+/// Run a query and collect its result set into a table
+pub fn query(path: &Path, sql: &str) -> Result<TesseraTable, String> {
+    collect_query(&open_read_only(path)?, sql)
+}
+
+fn collect_query(conn: &Connection, sql: &str) -> Result<TesseraTable, String> {
+    let mut stmt = conn.prepare(sql).map_err(sql_error)?;
+    if !stmt.readonly() {
+        return Err("Only read-only queries are supported".to_string());
+    }
+    let headers: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let width = headers.len();
+
+    let mut table = TesseraTable::new(headers);
+    let mut rows = stmt.query([]).map_err(sql_error)?;
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let cells = (0..width)
+            .map(|i| row.get_ref(i).map(value_to_cell))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+        table.push_row(cells);
+    }
+
+    Ok(table)
+}
+
+/// Load every row of one table
+pub fn load_table(path: &Path, table_name: &str) -> Result<TesseraTable, String> {
+    query(path, &format!("SELECT * FROM {}", quote_ident(table_name)))
+}
+
+/// Write a table into a database file (created if missing)
+///
+/// With `replace` set an existing table of the same name is dropped first;
+/// otherwise writing into an existing table is an error.
+pub fn write_table(
+    table: &TesseraTable,
+    path: &Path,
+    table_name: &str,
+    replace: bool,
+) -> Result<(), String> {
+    let mut conn = Connection::open(path).map_err(sql_error)?;
+    insert_table(&mut conn, table, table_name, replace)
+}
+
+/// Run SQL over a table handle, which is visible to the query as `t`
+///
+/// The table is copied into a private in-memory database with the same
+/// column affinities as [`write_table`], so aggregates and comparisons on
+/// numeric columns behave numerically. Only read-only statements run.
+pub fn query_table(table: &TesseraTable, sql: &str) -> Result<TesseraTable, String> {
+    let mut conn = Connection::open_in_memory().map_err(sql_error)?;
+    insert_table(&mut conn, table, "t", false)?;
+    collect_query(&conn, sql)
+}
+
+fn insert_table(
+    conn: &mut Connection,
+    table: &TesseraTable,
+    table_name: &str,
+    replace: bool,
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(sql_error)?;
+    let name = quote_ident(table_name);
+
+    if replace {
+        tx.execute(&format!("DROP TABLE IF EXISTS {}", name), [])
+            .map_err(sql_error)?;
+    }
+
+    let types: Vec<DataType> = (0..table.column_count())
+        .map(|col| infer_column_type(table.column(col)))
+        .collect();
+    let columns: Vec<String> = table
+        .headers()
+        .iter()
+        .zip(&types)
+        .map(|(header, data_type)| {
+            // Booleans stay TEXT so they load back as TRUE/FALSE, not 1/0
+            let affinity = match data_type {
+                DataType::Int64 => "INTEGER",
+                DataType::Float64 => "REAL",
+                _ => "TEXT",
+            };
+            format!("{} {}", quote_ident(header), affinity)
+        })
+        .collect();
+    tx.execute(
+        &format!("CREATE TABLE {} ({})", name, columns.join(", ")),
+        [],
+    )
+    .map_err(sql_error)?;
+
+    if table.column_count() > 0 {
+        let placeholders = vec!["?"; table.column_count()].join(", ");
+        let mut insert = tx
+            .prepare(&format!("INSERT INTO {} VALUES ({})", name, placeholders))
+            .map_err(sql_error)?;
+
+        for row in 0..table.row_count() {
+            let values = table.row(row).zip(&types).map(|(cell, data_type)| {
+                if cell.is_empty() {
+                    return Value::Null;
+                }
+                match data_type {
+                    DataType::Int64 => integer_cell(cell).map_or(Value::Null, Value::Integer),
+                    DataType::Float64 => float_cell(cell).map_or(Value::Null, Value::Real),
+                    _ => Value::Text(cell.to_string()),
+                }
+            });
+            insert
+                .execute(params_from_iter(values))
+                .map_err(sql_error)?;
+        }
+    }
+
+    tx.commit().map_err(sql_error)
+}
+
+/// List the tables of a SQLite database
+///
+/// # Returns
+/// StringResult with a JSON array of table names or error message
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sqlite_list_tables(path: *const c_char) -> StringResult {
+    let Some(path) = str_arg(path) else {
+        return StringResult::error("Invalid file path");
+    };
+
+    list_tables(Path::new(path))
+        .map(|names| serde_json::Value::from(names).to_string())
+        .into()
+}
+
+/// Load a SQLite table into a new table handle
+///
+/// # Safety
+/// `path` and `table_name` must be valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sqlite_load_table(
+    path: *const c_char,
+    table_name: *const c_char,
+) -> TableResult {
+    let (Some(path), Some(table_name)) = (str_arg(path), str_arg(table_name)) else {
+        return TableResult::error("Null pointer provided");
+    };
+
+    load_table(Path::new(path), table_name).into()
+}
+
+/// Run a read-only SQL query and load the result set into a new table handle
+///
+/// # Safety
+/// `path` and `sql` must be valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sqlite_query(
+    path: *const c_char,
+    sql: *const c_char,
+) -> TableResult {
+    let (Some(path), Some(sql)) = (str_arg(path), str_arg(sql)) else {
+        return TableResult::error("Null pointer provided");
+    };
+
+    query(Path::new(path), sql).into()
+}
+
+/// Run SQL over a table handle and return the result set as a new table
+///
+/// The table is named `t` in the query, e.g.
+/// `SELECT region, SUM(amount) FROM t GROUP BY region`.
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle; `sql` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_query(
+    table: *const TesseraTable,
+    sql: *const c_char,
+) -> TableResult {
+    let (Some(table), Some(sql)) = (table_arg(table), str_arg(sql)) else {
+        return TableResult::error("Null pointer provided");
+    };
+
+    query_table(table, sql).into()
+}
+
+/// Write a table handle into a SQLite database as `table_name`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `path` and `table_name` must be valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sqlite_write_table(
+    table: *const TesseraTable,
+    path: *const c_char,
+    table_name: *const c_char,
+    replace: bool,
+) -> *mut c_char {
+    let Some(table) = table_arg(table) else {
+        return error_string("Null pointer provided");
+    };
+    let (Some(path), Some(table_name)) = (str_arg(path), str_arg(table_name)) else {
+        return error_string("Null pointer provided");
+    };
+
+    match write_table(table, Path::new(path), table_name, replace) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_list_load_and_query() {
+        let table = TesseraTable::from_rows(
+            vec!["Region".into(), "Amount".into(), "Note \"q\"".into()],
+            vec![
+                vec!["EU".into(), "150".into(), "".into()],
+                vec!["US".into(), "90".into(), "late".into()],
+                vec!["EU".into(), "30".into(), "".into()],
+            ],
+        );
+
+        let path = std::env::temp_dir().join(format!("tessera_sqlite_{}.db", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        write_table(&table, &path, "sales", false).unwrap();
+        assert!(write_table(&table, &path, "sales", false).is_err());
+        write_table(&table, &path, "sales", true).unwrap();
+
+        let names = list_tables(&path).unwrap();
+        let loaded = load_table(&path, "sales").unwrap();
+        let grouped = query(
+            &path,
+            "SELECT Region, SUM(Amount) AS Total FROM sales GROUP BY Region ORDER BY Region",
+        );
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(names, ["sales"]);
+        assert_eq!(loaded, table);
+        let grouped = grouped.unwrap();
+        assert_eq!(grouped.headers(), ["Region", "Total"]);
+        assert_eq!(grouped.rows(), [vec!["EU", "180"], vec!["US", "90"]]);
+    }
+
+    #[test]
+    fn test_write_and_load_keeps_cell_text() {
+        let table = TesseraTable::from_rows(
+            vec![
+                "zip".into(),
+                "price".into(),
+                "ok".into(),
+                "n".into(),
+                "x".into(),
+            ],
+            vec![
+                vec![
+                    "007".into(),
+                    "1.50".into(),
+                    "TRUE".into(),
+                    "4".into(),
+                    "NaN".into(),
+                ],
+                vec![
+                    "12".into(),
+                    "2".into(),
+                    "FALSE".into(),
+                    "".into(),
+                    " 1".into(),
+                ],
+                vec![
+                    "+5".into(),
+                    "2.5".into(),
+                    "".into(),
+                    "-6".into(),
+                    "true".into(),
+                ],
+            ],
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("tessera_sqlite_text_{}.db", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        write_table(&table, &path, "t", false).unwrap();
+        let loaded = load_table(&path, "t");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.unwrap(), table);
+    }
+
+    #[test]
+    fn test_query_table_handle() {
+        let table = TesseraTable::from_rows(
+            vec!["region".into(), "amount".into()],
+            vec![
+                vec!["EU".into(), "150".into()],
+                vec!["US".into(), "90".into()],
+                vec!["EU".into(), "30".into()],
+            ],
+        );
+
+        let result = query_table(
+            &table,
+            "SELECT region, SUM(amount) FROM t WHERE amount > 50 GROUP BY region ORDER BY 2 DESC",
+        )
+        .unwrap();
+        assert_eq!(result.headers(), ["region", "SUM(amount)"]);
+        assert_eq!(result.rows(), [vec!["EU", "150"], vec!["US", "90"]]);
+        assert!(query_table(&table, "DELETE FROM t").is_err());
+        assert!(query_table(&table, "SELECT nope FROM t").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Excel workbook (.xlsx) import
+//!
+//! Only cell values are read: the first row of the sheet becomes the header
+//! row, shared and inline strings are resolved, and numbers come from the
+//! stored value rather than any display format. Legacy binary `.xls` files
+//! are not supported.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use super::table_from_grid;
+use crate::formula::value::format_number;
+use crate::table::TesseraTable;
+
+type Archive = ZipArchive<BufReader<File>>;
+
+/// Read one sheet of an .xlsx file (the first sheet when `sheet_name` is None)
+pub fn import_xlsx(path: &Path, sheet_name: Option<&str>) -> Result<TesseraTable, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Invalid XLSX archive: {}", e))?;
+
+    let workbook =
+        read_part(&mut archive, "xl/workbook.xml")?.ok_or("XLSX archive has no xl/workbook.xml")?;
+    let relationship = sheet_relationship(&workbook, sheet_name)?;
+    let rels = read_part(&mut archive, "xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let target = sheet_target(&rels, &relationship)
+        .ok_or_else(|| format!("Sheet relationship {} not found", relationship))?;
+    let strings = match read_part(&mut archive, "xl/sharedStrings.xml")? {
+        Some(xml) => shared_strings(&xml)?,
+        None => Vec::new(),
+    };
+    let sheet = read_part(&mut archive, &target)?
+        .ok_or_else(|| format!("XLSX archive has no {}", target))?;
+
+    Ok(table_from_grid(read_sheet_grid(&sheet, &strings)?))
+}
+
+/// Text of an archive member, `None` when there is no such member
+fn read_part(archive: &mut Archive, name: &str) -> Result<Option<String>, String> {
+    let Ok(mut part) = archive.by_name(name) else {
+        return Ok(None);
+    };
+    let mut text = String::new();
+    part.read_to_string(&mut text)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(Some(text))
+}
+
+/// Attribute by local name, so `r:id` and a bare `id` both match `id`
+fn attr_value(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Relationship id of the wanted sheet in xl/workbook.xml
+fn sheet_relationship(workbook: &str, sheet_name: Option<&str>) -> Result<String, String> {
+    let mut reader = Reader::from_str(workbook);
+    loop {
+        match reader
+            .read_event()
+            .map_err(|e| format!("Invalid workbook.xml: {}", e))?
+        {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheet" => {
+                let name = attr_value(&e, b"name").unwrap_or_default();
+                if sheet_name.is_none_or(|wanted| wanted == name) {
+                    return attr_value(&e, b"id").ok_or_else(|| "Sheet has no r:id".to_string());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Err(match sheet_name {
+        Some(name) => format!("Sheet '{}' not found", name),
+        None => "XLSX file contains no sheets".to_string(),
+    })
+}
+
+/// Archive path of the worksheet a relationship points to
+fn sheet_target(rels: &str, relationship: &str) -> Option<String> {
+    let mut reader = Reader::from_str(rels);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"Relationship"
+                    && attr_value(&e, b"Id").as_deref() == Some(relationship) =>
+            {
+                let target = attr_value(&e, b"Target")?;
+                // Targets are relative to xl/ unless they start at the root
+                return Some(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Text of every `<si>` in xl/sharedStrings.xml, without phonetic runs
+fn shared_strings(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let (mut current, mut in_text, mut in_phonetic) = (None::<String>, false, false);
+    loop {
+        match reader
+            .read_event()
+            .map_err(|e| format!("Invalid sharedStrings.xml: {}", e))?
+        {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"si" => current = Some(String::new()),
+                b"t" => in_text = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Text(e) if in_text && !in_phonetic => {
+                if let Some(text) = current.as_mut() {
+                    let unescaped = e
+                        .unescape()
+                        .map_err(|e| format!("Invalid sharedStrings.xml: {}", e))?;
+                    text.push_str(&unescaped);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"si" => strings.extend(current.take()),
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+/// Zero-based row and column of a reference such as `B3`
+fn cell_position(reference: &str) -> Option<(usize, usize)> {
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = reference.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let col = letters
+        .bytes()
+        .fold(0usize, |col, b| col * 26 + (b - b'A' + 1) as usize);
+    let row: usize = row.parse().ok()?;
+    (row > 0).then(|| (row - 1, col - 1))
+}
+
+/// Cell text for the stored value `raw` of a cell of type `cell_type`
+fn cell_text(cell_type: &str, raw: String, strings: &[String]) -> String {
+    match cell_type {
+        "s" => raw
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| strings.get(i).cloned())
+            .unwrap_or_default(),
+        "b" => match raw.trim() {
+            "1" => "TRUE".to_string(),
+            _ => "FALSE".to_string(),
+        },
+        "n" | "" => raw.trim().parse::<f64>().map_or(raw, format_number),
+        _ => raw,
+    }
+}
+
+fn read_sheet_grid(sheet: &str, strings: &[String]) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = Reader::from_str(sheet);
+    let mut grid: Vec<Vec<String>> = Vec::new();
+    let mut place = |row: usize, col: usize, text: String| {
+        if text.is_empty() {
+            return;
+        }
+        if grid.len() <= row {
+            grid.resize(row + 1, Vec::new());
+        }
+        let cells = &mut grid[row];
+        if cells.len() <= col {
+            cells.resize(col + 1, String::new());
+        }
+        cells[col] = text;
+    };
+
+    let (mut row, mut col) = (0, 0);
+    // (type, collected value) for the cell being read
+    let mut cell: Option<(String, String)> = None;
+    let mut in_value = false;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid worksheet: {}", e))?;
+        match event {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"row" => {
+                // Rows without a number follow the previous one
+                row = attr_value(&e, b"r")
+                    .and_then(|r| r.parse::<usize>().ok())
+                    .map_or(row, |r| r.saturating_sub(1));
+                col = 0;
+            }
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"c" => {
+                    if let Some((r, c)) = attr_value(&e, b"r").and_then(|r| cell_position(&r)) {
+                        (row, col) = (r, c);
+                    }
+                    cell = Some((attr_value(&e, b"t").unwrap_or_default(), String::new()));
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"c" => {
+                if let Some((r, c)) = attr_value(&e, b"r").and_then(|r| cell_position(&r)) {
+                    (row, col) = (r, c);
+                }
+                col += 1;
+            }
+            Event::Text(e) if in_value => {
+                if let Some((_, value)) = cell.as_mut() {
+                    let unescaped = e
+                        .unescape()
+                        .map_err(|e| format!("Invalid worksheet: {}", e))?;
+                    value.push_str(&unescaped);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    if let Some((cell_type, raw)) = cell.take() {
+                        place(row, col, cell_text(&cell_type, raw, strings));
+                    }
+                    col += 1;
+                }
+                b"row" => row += 1,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn write_xlsx(path: &Path) {
+        let parts = [
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="urn:r"><sheets><sheet name="Notes" sheetId="1" r:id="rId2"/><sheet name="Stock" sheetId="2" r:id="rId1"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Item</t></si><si><t>Qty</t></si><si><r><t>Bolt </t></r><r><t>&lt;M4&gt;</t></r><rPh><t>x</t></rPh></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="C1" t="inlineStr"><is><t>Ok</t></is></c></row><row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2"><v>12.50</v></c><c r="C2" t="b"><v>1</v></c></row><row r="4"><c r="B4"><v>3</v></c><c r="C4" t="e"><v>#N/A</v></c></row></sheetData></worksheet>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData><row><c t="str"><v>Note</v></c></row></sheetData></worksheet>"#,
+            ),
+        ];
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, xml) in parts {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_import_named_and_first_sheet() {
+        let path = std::env::temp_dir().join(format!("tessera_xlsx_{}.xlsx", std::process::id()));
+        write_xlsx(&path);
+        let stock = import_xlsx(&path, Some("Stock"));
+        let first = import_xlsx(&path, None);
+        let missing = import_xlsx(&path, Some("Nope"));
+        std::fs::remove_file(&path).ok();
+
+        let stock = stock.unwrap();
+        assert_eq!(stock.headers(), ["Item", "Qty", "Ok"]);
+        assert_eq!(
+            stock.row(0).collect::<Vec<_>>(),
+            ["Bolt <M4>", "12.5", "TRUE"]
+        );
+        assert_eq!(stock.row(1).collect::<Vec<_>>(), ["", "", ""]);
+        assert_eq!(stock.row(2).collect::<Vec<_>>(), ["", "3", "#N/A"]);
+        assert_eq!(first.unwrap().headers(), ["Note"]);
+        assert_eq!(missing.unwrap_err(), "Sheet 'Nope' not found");
+    }
+}
@@ -1,6 +1,19 @@
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_double};
 
+pub mod datetime;
+mod ffi;
+pub mod formats;
+pub mod formula;
+pub mod hash;
+pub mod io;
+pub mod query;
+pub mod render;
+pub mod table;
+pub mod transform;
+pub mod validation;
+pub mod workbook;
+
 /// FFI-safe string buffer for returning results
 #[repr(C)]
 pub struct FormulaResult {
@@ -17,19 +30,111 @@ impl FormulaResult {
     }
 
     fn error(msg: &str) -> Self {
-        let c_str = CString::new(msg).unwrap();
         FormulaResult {
             value: 0.0,
-            error: c_str.into_raw(),
+            error: ffi::error_string(msg),
         }
     }
 }
 
+/// FFI-safe result for functions that return text (JSON documents, rendered output)
+#[repr(C)]
+pub struct StringResult {
+    pub value: *mut c_char, // null if error, free with tessera_free_string
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl StringResult {
+    pub(crate) fn success(value: &str) -> Self {
+        StringResult {
+            value: ffi::to_c_string(value),
+            error: std::ptr::null_mut(),
+        }
+    }
+
+    pub(crate) fn error(msg: &str) -> Self {
+        StringResult {
+            value: std::ptr::null_mut(),
+            error: ffi::error_string(msg),
+        }
+    }
+}
+
+impl From<Result<String, String>> for StringResult {
+    fn from(result: Result<String, String>) -> Self {
+        match result {
+            Ok(value) => StringResult::success(&value),
+            Err(msg) => StringResult::error(&msg),
+        }
+    }
+}
+
+/// FFI-safe array of indices (row numbers, column offsets, permutations)
+#[repr(C)]
+pub struct IndexArray {
+    pub data: *mut usize, // null if empty or error, free with tessera_free_index_array
+    pub len: usize,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl IndexArray {
+    pub(crate) fn success(indices: Vec<usize>) -> Self {
+        if indices.is_empty() {
+            return IndexArray {
+                data: std::ptr::null_mut(),
+                len: 0,
+                error: std::ptr::null_mut(),
+            };
+        }
+
+        let boxed = indices.into_boxed_slice();
+        let len = boxed.len();
+        IndexArray {
+            data: Box::into_raw(boxed) as *mut usize,
+            len,
+            error: std::ptr::null_mut(),
+        }
+    }
+
+    pub(crate) fn error(msg: &str) -> Self {
+        IndexArray {
+            data: std::ptr::null_mut(),
+            len: 0,
+            error: ffi::error_string(msg),
+        }
+    }
+}
+
+impl From<Result<Vec<usize>, String>> for IndexArray {
+    fn from(result: Result<Vec<usize>, String>) -> Self {
+        match result {
+            Ok(indices) => IndexArray::success(indices),
+            Err(msg) => IndexArray::error(&msg),
+        }
+    }
+}
+
+/// Free the data of an IndexArray (the error string is freed with tessera_free_string)
+///
+/// # Safety
+/// `data` and `len` must come from the same IndexArray returned by this library
+#[no_mangle]
+pub unsafe extern "C" fn tessera_free_index_array(data: *mut usize, len: usize) {
+    if !data.is_null() {
+        let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(data, len));
+    }
+}
+
 /// Free the error string returned by formula functions
 /// Call this from C# after reading the error message
+///
+/// Common error messages are static and freeing them is a no-op.
+///
+/// # Safety
+/// `ptr` must be null or a string previously returned by this library
 #[no_mangle]
-pub extern "C" fn tessera_free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
+pub unsafe extern "C" fn tessera_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() && !ffi::is_static_error(ptr) {
         unsafe {
             let _ = CString::from_raw(ptr);
         }
@@ -49,7 +154,7 @@ pub extern "C" fn tessera_free_string(ptr: *mut c_char) {
 /// # Safety
 /// Caller must ensure values_ptr points to valid array of count C strings
 #[no_mangle]
-pub extern "C" fn tessera_sum(
+pub unsafe extern "C" fn tessera_sum(
     column_name: *const c_char,
     values_ptr: *const *const c_char,
     count: usize,
@@ -72,12 +177,12 @@ pub extern "C" fn tessera_sum(
 
     unsafe {
         let values = std::slice::from_raw_parts(values_ptr, count);
-        for i in 0..count {
-            if values[i].is_null() {
+        for &value in values {
+            if value.is_null() {
                 continue; // Skip null values
             }
 
-            let value_str = match CStr::from_ptr(values[i]).to_str() {
+            let value_str = match CStr::from_ptr(value).to_str() {
                 Ok(s) => s.trim(),
                 Err(_) => continue, // Skip invalid encoding
             };
@@ -108,8 +213,11 @@ pub extern "C" fn tessera_sum(
 }
 
 /// Calculate AVG (average) for a column
+///
+/// # Safety
+/// Caller must ensure values_ptr points to valid array of count C strings
 #[no_mangle]
-pub extern "C" fn tessera_avg(
+pub unsafe extern "C" fn tessera_avg(
     column_name: *const c_char,
     values_ptr: *const *const c_char,
     count: usize,
@@ -130,12 +238,12 @@ pub extern "C" fn tessera_avg(
 
     unsafe {
         let values = std::slice::from_raw_parts(values_ptr, count);
-        for i in 0..count {
-            if values[i].is_null() {
+        for &value in values {
+            if value.is_null() {
                 continue;
             }
 
-            let value_str = match CStr::from_ptr(values[i]).to_str() {
+            let value_str = match CStr::from_ptr(value).to_str() {
                 Ok(s) => s.trim(),
                 Err(_) => continue,
             };
@@ -162,8 +270,11 @@ pub extern "C" fn tessera_avg(
 }
 
 /// Calculate MIN for a column
+///
+/// # Safety
+/// Caller must ensure values_ptr points to valid array of count C strings
 #[no_mangle]
-pub extern "C" fn tessera_min(
+pub unsafe extern "C" fn tessera_min(
     column_name: *const c_char,
     values_ptr: *const *const c_char,
     count: usize,
@@ -183,12 +294,12 @@ pub extern "C" fn tessera_min(
 
     unsafe {
         let values = std::slice::from_raw_parts(values_ptr, count);
-        for i in 0..count {
-            if values[i].is_null() {
+        for &value in values {
+            if value.is_null() {
                 continue;
             }
 
-            let value_str = match CStr::from_ptr(values[i]).to_str() {
+            let value_str = match CStr::from_ptr(value).to_str() {
                 Ok(s) => s.trim(),
                 Err(_) => continue,
             };
@@ -216,8 +327,11 @@ pub extern "C" fn tessera_min(
 }
 
 /// Calculate MAX for a column
+///
+/// # Safety
+/// Caller must ensure values_ptr points to valid array of count C strings
 #[no_mangle]
-pub extern "C" fn tessera_max(
+pub unsafe extern "C" fn tessera_max(
     column_name: *const c_char,
     values_ptr: *const *const c_char,
     count: usize,
@@ -237,12 +351,12 @@ pub extern "C" fn tessera_max(
 
     unsafe {
         let values = std::slice::from_raw_parts(values_ptr, count);
-        for i in 0..count {
-            if values[i].is_null() {
+        for &value in values {
+            if value.is_null() {
                 continue;
             }
 
-            let value_str = match CStr::from_ptr(values[i]).to_str() {
+            let value_str = match CStr::from_ptr(value).to_str() {
                 Ok(s) => s.trim(),
                 Err(_) => continue,
             };
@@ -270,8 +384,11 @@ pub extern "C" fn tessera_max(
 }
 
 /// Calculate COUNT for a column (counts non-null, non-empty values)
+///
+/// # Safety
+/// Caller must ensure values_ptr points to valid array of count C strings
 #[no_mangle]
-pub extern "C" fn tessera_count(
+pub unsafe extern "C" fn tessera_count(
     column_name: *const c_char,
     values_ptr: *const *const c_char,
     count: usize,
@@ -291,12 +408,12 @@ pub extern "C" fn tessera_count(
 
     unsafe {
         let values = std::slice::from_raw_parts(values_ptr, count);
-        for i in 0..count {
-            if values[i].is_null() {
+        for &value in values {
+            if value.is_null() {
                 continue;
             }
 
-            let value_str = match CStr::from_ptr(values[i]).to_str() {
+            let value_str = match CStr::from_ptr(value).to_str() {
                 Ok(s) => s.trim(),
                 Err(_) => continue,
             };
@@ -317,24 +434,22 @@ pub extern "C" fn tessera_count(
 /// 
 /// # Returns
 /// C string with parsed result or error (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `formula` must be null or a valid NUL-terminated C string
 #[no_mangle]
-pub extern "C" fn tessera_parse_formula(formula: *const c_char) -> *mut c_char {
+pub unsafe extern "C" fn tessera_parse_formula(formula: *const c_char) -> *mut c_char {
     if formula.is_null() {
-        let err = CString::new("Null formula string").unwrap();
-        return err.into_raw();
+        return ffi::error_string("Null formula string");
     }
 
     let formula_str = match unsafe { CStr::from_ptr(formula).to_str() } {
         Ok(s) => s.trim(),
-        Err(_) => {
-            let err = CString::new("Invalid formula encoding").unwrap();
-            return err.into_raw();
-        }
+        Err(_) => return ffi::error_string("Invalid formula encoding"),
     };
 
     if !formula_str.starts_with('=') {
-        let err = CString::new("Formula must start with '='").unwrap();
-        return err.into_raw();
+        return ffi::error_string("Formula must start with '='");
     }
 
     // Simple parser for "=SUM(ColumnName)" format
@@ -345,8 +460,7 @@ pub extern "C" fn tessera_parse_formula(formula: *const c_char) -> *mut c_char {
         let args_start = func_end + 1;
         
         if !formula_body.ends_with(')') {
-            let err = CString::new("Formula missing closing parenthesis").unwrap();
-            return err.into_raw();
+            return ffi::error_string("Formula missing closing parenthesis");
         }
 
         let args = &formula_body[args_start..formula_body.len() - 1].trim();
@@ -355,16 +469,9 @@ pub extern "C" fn tessera_parse_formula(formula: *const c_char) -> *mut c_char {
         // Format: "FUNCTION:ColumnName"
         let result = format!("{}:{}", func_name, args);
         
-        match CString::new(result) {
-            Ok(c_str) => c_str.into_raw(),
-            Err(_) => {
-                let err = CString::new("Failed to create result string").unwrap();
-                err.into_raw()
-            }
-        }
+        ffi::to_c_string(&result)
     } else {
-        let err = CString::new("Invalid formula syntax: expected function(arg)").unwrap();
-        err.into_raw()
+        ffi::error_string("Invalid formula syntax: expected function(arg)")
     }
 }
 
@@ -376,23 +483,23 @@ mod tests {
     #[test]
     fn test_parse_formula() {
         let formula = CString::new("=SUM(ColumnA)").unwrap();
-        let result_ptr = tessera_parse_formula(formula.as_ptr());
+        let result_ptr = unsafe { tessera_parse_formula(formula.as_ptr()) };
         let result = unsafe { CStr::from_ptr(result_ptr).to_str().unwrap() };
         assert_eq!(result, "SUM:ColumnA");
-        tessera_free_string(result_ptr);
+        unsafe { tessera_free_string(result_ptr) };
     }
 
     #[test]
     fn test_sum_basic() {
         let col_name = CString::new("Test").unwrap();
-        let values = vec![
+        let values = [
             CString::new("10").unwrap(),
             CString::new("20").unwrap(),
             CString::new("30").unwrap(),
         ];
         let ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
         
-        let result = tessera_sum(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len());
+        let result = unsafe { tessera_sum(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len()) };
         assert_eq!(result.value, 60.0);
         assert!(result.error.is_null());
     }
@@ -400,14 +507,14 @@ mod tests {
     #[test]
     fn test_avg_basic() {
         let col_name = CString::new("Test").unwrap();
-        let values = vec![
+        let values = [
             CString::new("10").unwrap(),
             CString::new("20").unwrap(),
             CString::new("30").unwrap(),
         ];
         let ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
         
-        let result = tessera_avg(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len());
+        let result = unsafe { tessera_avg(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len()) };
         assert_eq!(result.value, 20.0);
         assert!(result.error.is_null());
     }
@@ -415,14 +522,14 @@ mod tests {
     #[test]
     fn test_min_basic() {
         let col_name = CString::new("Test").unwrap();
-        let values = vec![
+        let values = [
             CString::new("10").unwrap(),
             CString::new("20").unwrap(),
             CString::new("5").unwrap(),
         ];
         let ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
         
-        let result = tessera_min(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len());
+        let result = unsafe { tessera_min(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len()) };
         assert_eq!(result.value, 5.0);
         assert!(result.error.is_null());
     }
@@ -430,14 +537,14 @@ mod tests {
     #[test]
     fn test_max_basic() {
         let col_name = CString::new("Test").unwrap();
-        let values = vec![
+        let values = [
             CString::new("10").unwrap(),
             CString::new("20").unwrap(),
             CString::new("5").unwrap(),
         ];
         let ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
         
-        let result = tessera_max(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len());
+        let result = unsafe { tessera_max(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len()) };
         assert_eq!(result.value, 20.0);
         assert!(result.error.is_null());
     }
@@ -445,7 +552,7 @@ mod tests {
     #[test]
     fn test_count_basic() {
         let col_name = CString::new("Test").unwrap();
-        let values = vec![
+        let values = [
             CString::new("10").unwrap(),
             CString::new("").unwrap(),
             CString::new("30").unwrap(),
@@ -453,9 +560,24 @@ mod tests {
         ];
         let ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
         
-        let result = tessera_count(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len());
+        let result = unsafe { tessera_count(col_name.as_ptr(), ptrs.as_ptr(), ptrs.len()) };
         assert_eq!(result.value, 3.0); // Counts non-empty values
         assert!(result.error.is_null());
     }
+
+    #[test]
+    fn test_static_errors() {
+        let first = unsafe { tessera_sum(std::ptr::null(), std::ptr::null(), 0) };
+        let second = unsafe { tessera_sum(std::ptr::null(), std::ptr::null(), 0) };
+        assert_eq!(first.error, second.error);
+        let msg = unsafe { CStr::from_ptr(first.error).to_str().unwrap() };
+        assert_eq!(msg, "Null pointer provided");
+        unsafe { tessera_free_string(first.error) };
+
+        let owned = FormulaResult::error("bad\0text");
+        let msg = unsafe { CStr::from_ptr(owned.error).to_str().unwrap() };
+        assert_eq!(msg, "badtext");
+        unsafe { tessera_free_string(owned.error) };
+    }
 }
 
@@ -0,0 +1,161 @@
+//! Locale-aware text collation for sorting
+//!
+//! Wraps ICU collators so non-English data orders the way users expect
+//! (e.g. Swedish "ö" after "z", Vietnamese "đ" after "d").
+
+use std::cmp::Ordering;
+use std::os::raw::c_char;
+
+use icu_collator::{CaseLevel, Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::Locale;
+
+use crate::ffi::str_arg;
+
+/// Collation options for text comparison
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollationSettings {
+    /// BCP 47 locale tag such as "sv", "de" or "vi"; empty for the root order
+    pub locale: String,
+    /// Treat "a" and "A" as different
+    pub case_sensitive: bool,
+    /// Treat "e" and "é" as different
+    pub accent_sensitive: bool,
+}
+
+impl Default for CollationSettings {
+    fn default() -> Self {
+        CollationSettings {
+            locale: String::new(),
+            case_sensitive: false,
+            accent_sensitive: true,
+        }
+    }
+}
+
+/// FFI form of [`CollationSettings`]
+#[repr(C)]
+pub struct CollationOptions {
+    pub locale: *const c_char, // null or empty for the root order
+    pub case_sensitive: bool,
+    pub accent_sensitive: bool,
+}
+
+impl CollationOptions {
+    /// Read options from a nullable pointer, falling back to defaults
+    ///
+    /// # Safety
+    /// `options` must be null or point to a valid `CollationOptions`
+    pub(crate) unsafe fn settings(
+        options: *const CollationOptions,
+    ) -> Result<CollationSettings, String> {
+        let Some(options) = options.as_ref() else {
+            return Ok(CollationSettings::default());
+        };
+        let locale = if options.locale.is_null() {
+            ""
+        } else {
+            str_arg(options.locale).ok_or("Invalid locale encoding")?
+        };
+
+        Ok(CollationSettings {
+            locale: locale.to_string(),
+            case_sensitive: options.case_sensitive,
+            accent_sensitive: options.accent_sensitive,
+        })
+    }
+}
+
+/// Text comparer built from [`CollationSettings`]
+pub struct TextCollator {
+    plain: Collator,
+    numeric: Collator,
+}
+
+impl TextCollator {
+    pub fn new(settings: &CollationSettings) -> Result<Self, String> {
+        let locale: Locale = if settings.locale.trim().is_empty() {
+            Locale::UND
+        } else {
+            settings
+                .locale
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid locale: {}", settings.locale))?
+        };
+
+        let mut options = CollatorOptions::new();
+        options.strength = Some(match (settings.accent_sensitive, settings.case_sensitive) {
+            (false, _) => Strength::Primary,
+            (true, false) => Strength::Secondary,
+            (true, true) => Strength::Tertiary,
+        });
+        if settings.case_sensitive && !settings.accent_sensitive {
+            options.case_level = Some(CaseLevel::On);
+        }
+
+        let data_locale = (&locale).into();
+        let plain = Collator::try_new(&data_locale, options)
+            .map_err(|e| format!("Failed to load collation for {}: {}", settings.locale, e))?;
+        options.numeric = Some(Numeric::On);
+        let numeric = Collator::try_new(&data_locale, options)
+            .map_err(|e| format!("Failed to load collation for {}: {}", settings.locale, e))?;
+
+        Ok(TextCollator { plain, numeric })
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.plain.compare(a, b)
+    }
+
+    /// Compare with digit runs ordered by value ("file2" before "file10")
+    pub fn compare_natural(&self, a: &str, b: &str) -> Ordering {
+        self.numeric.compare(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collator(locale: &str, case_sensitive: bool, accent_sensitive: bool) -> TextCollator {
+        TextCollator::new(&CollationSettings {
+            locale: locale.into(),
+            case_sensitive,
+            accent_sensitive,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_locale_tailoring() {
+        assert_eq!(
+            collator("sv", false, true).compare("öl", "zebra"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            collator("en", false, true).compare("öl", "zebra"),
+            Ordering::Less
+        );
+        assert!(TextCollator::new(&CollationSettings {
+            locale: "not a locale!".into(),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_case_and_accent_sensitivity() {
+        let loose = collator("", false, false);
+        assert_eq!(loose.compare("Resume", "résumé"), Ordering::Equal);
+
+        let accents = collator("", false, true);
+        assert_eq!(accents.compare("resume", "RESUME"), Ordering::Equal);
+        assert_ne!(accents.compare("resume", "résumé"), Ordering::Equal);
+
+        let case_only = collator("", true, false);
+        assert_eq!(case_only.compare("resume", "résumé"), Ordering::Equal);
+        assert_ne!(case_only.compare("resume", "Resume"), Ordering::Equal);
+
+        assert_eq!(loose.compare_natural("file2", "File10"), Ordering::Less);
+    }
+}
@@ -0,0 +1,319 @@
+//! Row and cell differences between two tables, for the side-by-side diff view
+//!
+//! Columns are matched by header (case-insensitive). Rows are matched on key
+//! columns when given, otherwise by position; with duplicate keys the n-th
+//! occurrence on the left pairs with the n-th on the right. Cells compare by
+//! exact text.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::ffi::{str_arg, str_array_arg};
+use crate::io::csv::import_csv;
+use crate::io::ods::import_ods;
+use crate::io::xlsx::import_xlsx;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange {
+    pub column: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowDiff {
+    Added {
+        right_row: usize,
+    },
+    Removed {
+        left_row: usize,
+    },
+    Changed {
+        left_row: usize,
+        right_row: usize,
+        cells: Vec<CellChange>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDiff {
+    pub added_columns: Vec<String>,
+    pub removed_columns: Vec<String>,
+    /// Left rows in order (removed or changed), then rows only on the right
+    pub rows: Vec<RowDiff>,
+    pub unchanged: usize,
+}
+
+fn key_columns(table: &TesseraTable, keys: &[String]) -> Result<Vec<usize>, String> {
+    keys.iter()
+        .map(|key| {
+            table
+                .column_index(key)
+                .ok_or_else(|| format!("Column '{}' not found", key))
+        })
+        .collect()
+}
+
+/// Pair up rows: `pairs[left_row]` is the matching right row, if any
+fn match_rows(
+    left: &TesseraTable,
+    right: &TesseraTable,
+    keys: &[String],
+) -> Result<Vec<Option<usize>>, String> {
+    if keys.is_empty() {
+        return Ok((0..left.row_count())
+            .map(|row| (row < right.row_count()).then_some(row))
+            .collect());
+    }
+
+    let (left_keys, right_keys) = (key_columns(left, keys)?, key_columns(right, keys)?);
+    let mut index: HashMap<Vec<&str>, VecDeque<usize>> = HashMap::new();
+    for row in 0..right.row_count() {
+        let key = right_keys.iter().map(|&c| right.cell(row, c)).collect();
+        index.entry(key).or_default().push_back(row);
+    }
+    Ok((0..left.row_count())
+        .map(|row| {
+            let key: Vec<&str> = left_keys.iter().map(|&c| left.cell(row, c)).collect();
+            index.get_mut(&key).and_then(VecDeque::pop_front)
+        })
+        .collect())
+}
+
+/// Compare `left` (old) against `right` (new)
+pub fn compare_tables(
+    left: &TesseraTable,
+    right: &TesseraTable,
+    keys: &[String],
+) -> Result<TableDiff, String> {
+    let mut diff = TableDiff::default();
+
+    // Shared columns as (left, right, name), in left order
+    let mut shared = Vec::new();
+    for (col, name) in left.headers().iter().enumerate() {
+        match right.column_index(name) {
+            Some(other) => shared.push((col, other, name)),
+            None => diff.removed_columns.push(name.clone()),
+        }
+    }
+    diff.added_columns = right
+        .headers()
+        .iter()
+        .filter(|name| left.column_index(name).is_none())
+        .cloned()
+        .collect();
+
+    let pairs = match_rows(left, right, keys)?;
+    let mut matched = vec![false; right.row_count()];
+    for (left_row, pair) in pairs.into_iter().enumerate() {
+        let Some(right_row) = pair else {
+            diff.rows.push(RowDiff::Removed { left_row });
+            continue;
+        };
+        matched[right_row] = true;
+
+        let cells: Vec<CellChange> = shared
+            .iter()
+            .filter(|(l, r, _)| left.cell(left_row, *l) != right.cell(right_row, *r))
+            .map(|(l, r, name)| CellChange {
+                column: name.to_string(),
+                old: left.cell(left_row, *l).to_string(),
+                new: right.cell(right_row, *r).to_string(),
+            })
+            .collect();
+        if cells.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.rows.push(RowDiff::Changed {
+                left_row,
+                right_row,
+                cells,
+            });
+        }
+    }
+    diff.rows.extend(
+        (0..right.row_count())
+            .filter(|&row| !matched[row])
+            .map(|right_row| RowDiff::Added { right_row }),
+    );
+    Ok(diff)
+}
+
+/// Load a file for comparison based on its extension
+fn load_for_compare(path: &Path) -> Result<TesseraTable, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "ods" => import_ods(path, None),
+        "xlsx" => import_xlsx(path, None),
+        "xls" => Err("Legacy .xls workbooks are not supported; save as .xlsx".to_string()),
+        _ => import_csv(path, 0),
+    }
+}
+
+/// Compare two files (CSV/TSV, ODS or XLSX; the first sheet of a workbook)
+pub fn compare_files(left: &Path, right: &Path, keys: &[String]) -> Result<TableDiff, String> {
+    compare_tables(&load_for_compare(left)?, &load_for_compare(right)?, keys)
+}
+
+impl TableDiff {
+    fn to_json(&self) -> serde_json::Value {
+        let rows: Vec<_> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                RowDiff::Added { right_row } => {
+                    json!({ "status": "added", "right_row": right_row })
+                }
+                RowDiff::Removed { left_row } => {
+                    json!({ "status": "removed", "left_row": left_row })
+                }
+                RowDiff::Changed {
+                    left_row,
+                    right_row,
+                    cells,
+                } => json!({
+                    "status": "changed",
+                    "left_row": left_row,
+                    "right_row": right_row,
+                    "cells": cells
+                        .iter()
+                        .map(|c| json!({ "column": c.column, "old": c.old, "new": c.new }))
+                        .collect::<Vec<_>>(),
+                }),
+            })
+            .collect();
+        json!({
+            "added_columns": self.added_columns,
+            "removed_columns": self.removed_columns,
+            "rows": rows,
+            "unchanged": self.unchanged,
+        })
+    }
+}
+
+/// Compare two tables row by row
+///
+/// # Arguments
+/// * `left`, `right` - Old and new table handles
+/// * `keys_ptr` - Key column names (matched by header on both sides), or null
+///   with `key_count` 0 to match rows by position
+///
+/// # Returns
+/// StringResult with JSON `{added_columns, removed_columns, rows, unchanged}`;
+/// each row is `{status: "added"|"removed"|"changed", left_row, right_row, cells}`
+///
+/// # Safety
+/// `left` and `right` must be live table handles; `keys_ptr` must point to `key_count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_compare_tables(
+    left: *const TesseraTable,
+    right: *const TesseraTable,
+    keys_ptr: *const *const c_char,
+    key_count: usize,
+) -> StringResult {
+    let (Some(left), Some(right)) = (table_arg(left), table_arg(right)) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(keys) = str_array_arg(keys_ptr, key_count) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    compare_tables(left, right, &keys)
+        .map(|diff| diff.to_json().to_string())
+        .into()
+}
+
+/// Compare two CSV/TSV, ODS or XLSX files, like tessera_compare_tables
+///
+/// # Safety
+/// `left_path` and `right_path` must be valid C strings; `keys_ptr` must point to `key_count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_compare_files(
+    left_path: *const c_char,
+    right_path: *const c_char,
+    keys_ptr: *const *const c_char,
+    key_count: usize,
+) -> StringResult {
+    let (Some(left_path), Some(right_path)) = (str_arg(left_path), str_arg(right_path)) else {
+        return StringResult::error("Invalid file path");
+    };
+    let Some(keys) = str_array_arg(keys_ptr, key_count) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    compare_files(Path::new(left_path), Path::new(right_path), &keys)
+        .map(|diff| diff.to_json().to_string())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> TesseraTable {
+        TesseraTable::from_rows(
+            headers.iter().map(|h| h.to_string()).collect(),
+            rows.iter()
+                .map(|r| r.iter().map(|c| c.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_compare_by_key() {
+        let left = table(
+            &["Id", "Name", "Note"],
+            &[&["1", "Ann", "a"], &["2", "Bob", "b"], &["3", "Cy", "c"]],
+        );
+        let right = table(
+            &["id", "Name", "City"],
+            &[
+                &["3", "Cy", "Hue"],
+                &["1", "Anna", "Hanoi"],
+                &["4", "Dee", "Hue"],
+            ],
+        );
+
+        let diff = compare_tables(&left, &right, &["Id".into()]).unwrap();
+        assert_eq!(diff.removed_columns, ["Note"]);
+        assert_eq!(diff.added_columns, ["City"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.rows,
+            [
+                RowDiff::Changed {
+                    left_row: 0,
+                    right_row: 1,
+                    cells: vec![CellChange {
+                        column: "Name".into(),
+                        old: "Ann".into(),
+                        new: "Anna".into(),
+                    }],
+                },
+                RowDiff::Removed { left_row: 1 },
+                RowDiff::Added { right_row: 2 },
+            ]
+        );
+        assert!(compare_tables(&left, &right, &["Missing".into()]).is_err());
+    }
+
+    #[test]
+    fn test_compare_by_position() {
+        let left = table(&["A"], &[&["1"], &["2"]]);
+        let right = table(&["A"], &[&["1"], &["5"], &["6"]]);
+
+        let diff = compare_tables(&left, &right, &[]).unwrap();
+        assert_eq!(diff.unchanged, 1);
+        assert!(matches!(diff.rows[0], RowDiff::Changed { left_row: 1, .. }));
+        assert_eq!(diff.rows[1], RowDiff::Added { right_row: 2 });
+    }
+}
@@ -0,0 +1,140 @@
+//! Pairwise correlation of the numeric columns of a table, for a heatmap
+
+use rayon::prelude::*;
+
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Columns whose non-empty cells are all numbers, with at least one number
+pub fn numeric_columns(table: &TesseraTable) -> Vec<usize> {
+    (0..table.column_count())
+        .filter(|&col| {
+            let numbers = table.numbers(col);
+            numbers.iter().any(Option::is_some)
+                && numbers
+                    .iter()
+                    .zip(table.column(col))
+                    .all(|(number, cell)| number.is_some() || cell.trim().is_empty())
+        })
+        .collect()
+}
+
+/// Pearson correlation over the rows where both columns hold a number;
+/// `None` with fewer than two such rows or when either side is constant
+pub fn pearson(a: &[Option<f64>], b: &[Option<f64>]) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = a
+        .iter()
+        .zip(b)
+        .filter_map(|(x, y)| Some(((*x)?, (*y)?)))
+        .collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut xy, mut xx, mut yy) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        xy += dx * dy;
+        xx += dx * dx;
+        yy += dy * dy;
+    }
+    (xx > 0.0 && yy > 0.0).then(|| (xy / (xx * yy).sqrt()).clamp(-1.0, 1.0))
+}
+
+/// Correlation matrix of every numeric column
+///
+/// The first column names the row's column and each further column holds
+/// its correlation with the others; the diagonal is 1 and undefined pairs
+/// are empty. Pairs are computed in parallel.
+pub fn correlation_matrix(table: &TesseraTable) -> TesseraTable {
+    let columns = numeric_columns(table);
+    let numbers: Vec<_> = columns.iter().map(|&col| table.numbers(col)).collect();
+    let pairs: Vec<(usize, usize)> = (0..columns.len())
+        .flat_map(|i| (i + 1..columns.len()).map(move |j| (i, j)))
+        .collect();
+    let values: Vec<Option<f64>> = pairs
+        .par_iter()
+        .map(|&(i, j)| pearson(&numbers[i], &numbers[j]))
+        .collect();
+
+    let mut matrix = vec![vec![String::new(); columns.len()]; columns.len()];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = pearson(&numbers[i], &numbers[i])
+            .map(format_number)
+            .unwrap_or_default();
+    }
+    for (&(i, j), value) in pairs.iter().zip(values) {
+        let text = value.map(format_number).unwrap_or_default();
+        matrix[i][j] = text.clone();
+        matrix[j][i] = text;
+    }
+
+    let names: Vec<String> = columns
+        .iter()
+        .map(|&col| table.headers()[col].clone())
+        .collect();
+    let headers = std::iter::once("Column".to_string())
+        .chain(names.iter().cloned())
+        .collect();
+    let rows = names
+        .into_iter()
+        .zip(matrix)
+        .map(|(name, row)| std::iter::once(name).chain(row).collect())
+        .collect();
+    TesseraTable::from_rows(headers, rows)
+}
+
+/// Correlation matrix of all numeric columns as a new table
+///
+/// # Arguments
+/// * `table` - Table handle
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free): one
+/// row per numeric column, the column name first and then its Pearson
+/// correlation with every numeric column; empty where undefined
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_correlation_matrix(table: *const TesseraTable) -> TableResult {
+    match table_arg(table) {
+        Some(table) => TableResult::success(correlation_matrix(table)),
+        None => TableResult::error("Null pointer provided"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlation_matrix() {
+        let table = TesseraTable::from_rows(
+            vec!["Hours".into(), "Name".into(), "Score".into(), "Flat".into()],
+            [
+                ["1", "a", "10", "5"],
+                ["2", "b", "20", "5"],
+                ["3", "c", "", "5"],
+                ["4", "d", "40", ""],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        );
+        assert_eq!(numeric_columns(&table), [0, 2, 3]);
+
+        let matrix = correlation_matrix(&table);
+        assert_eq!(matrix.headers(), ["Column", "Hours", "Score", "Flat"]);
+        let row = |r| (0..4).map(|c| matrix.cell(r, c)).collect::<Vec<_>>();
+        assert_eq!(row(0), ["Hours", "1", "1", ""]);
+        assert_eq!(row(1), ["Score", "1", "1", ""]);
+        assert_eq!(row(2), ["Flat", "", "", ""]);
+
+        let a = [Some(1.0), Some(2.0), Some(3.0)];
+        let b = [Some(3.0), Some(2.0), Some(1.0)];
+        assert_eq!(pearson(&a, &b), Some(-1.0));
+    }
+}
@@ -0,0 +1,189 @@
+//! Two-variable cross-tabulation (contingency tables)
+
+use std::collections::HashMap;
+
+use super::sort::natural_cmp;
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Label used for empty category values
+const BLANK_LABEL: &str = "(blank)";
+const TOTAL_LABEL: &str = "Total";
+
+/// What each crosstab cell shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrosstabValues {
+    Count,
+    PercentOfTotal,
+    PercentOfRow,
+    PercentOfColumn,
+}
+
+impl CrosstabValues {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(CrosstabValues::Count),
+            1 => Some(CrosstabValues::PercentOfTotal),
+            2 => Some(CrosstabValues::PercentOfRow),
+            3 => Some(CrosstabValues::PercentOfColumn),
+            _ => None,
+        }
+    }
+}
+
+/// Cross-tabulate `row_col` against `col_col`
+///
+/// Categories are listed in natural order along each axis, with a trailing
+/// `Total` row and column. Percentages are rounded to two decimals.
+pub fn crosstab(
+    table: &TesseraTable,
+    row_col: usize,
+    col_col: usize,
+    values: CrosstabValues,
+) -> Result<TesseraTable, String> {
+    if row_col >= table.column_count() || col_col >= table.column_count() {
+        return Err("Crosstab column is out of range".to_string());
+    }
+
+    let label = |cell: &str| {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            BLANK_LABEL.to_string()
+        } else {
+            cell.to_string()
+        }
+    };
+
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    let mut row_totals: HashMap<String, usize> = HashMap::new();
+    let mut col_totals: HashMap<String, usize> = HashMap::new();
+    for row in 0..table.row_count() {
+        let r = label(table.cell(row, row_col));
+        let c = label(table.cell(row, col_col));
+        *row_totals.entry(r.clone()).or_default() += 1;
+        *col_totals.entry(c.clone()).or_default() += 1;
+        *counts.entry((r, c)).or_default() += 1;
+    }
+    let total = table.row_count();
+
+    let mut row_labels: Vec<&String> = row_totals.keys().collect();
+    let mut col_labels: Vec<&String> = col_totals.keys().collect();
+    row_labels.sort_by(|a, b| natural_cmp(a, b));
+    col_labels.sort_by(|a, b| natural_cmp(a, b));
+
+    let format = |count: usize, row_total: usize, col_total: usize| {
+        let denominator = match values {
+            CrosstabValues::Count => return count.to_string(),
+            CrosstabValues::PercentOfTotal => total,
+            CrosstabValues::PercentOfRow => row_total,
+            CrosstabValues::PercentOfColumn => col_total,
+        };
+        if denominator == 0 {
+            return String::new();
+        }
+        let percent = count as f64 * 100.0 / denominator as f64;
+        format_number((percent * 100.0).round() / 100.0)
+    };
+
+    let mut headers = vec![table.headers()[row_col].clone()];
+    headers.extend(col_labels.iter().map(|c| c.to_string()));
+    headers.push(TOTAL_LABEL.to_string());
+
+    let mut rows = Vec::with_capacity(row_labels.len() + 1);
+    for r in &row_labels {
+        let row_total = row_totals[*r];
+        let mut cells = vec![r.to_string()];
+        for c in &col_labels {
+            let count = counts
+                .get(&(r.to_string(), c.to_string()))
+                .copied()
+                .unwrap_or(0);
+            cells.push(format(count, row_total, col_totals[*c]));
+        }
+        cells.push(format(row_total, row_total, total));
+        rows.push(cells);
+    }
+
+    let mut totals = vec![TOTAL_LABEL.to_string()];
+    for c in &col_labels {
+        totals.push(format(col_totals[*c], total, col_totals[*c]));
+    }
+    totals.push(format(total, total, total));
+    rows.push(totals);
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Build a crosstab of two categorical columns
+///
+/// # Arguments
+/// * `table` - Source table handle
+/// * `row_col` - Column whose values become the rows
+/// * `col_col` - Column whose values become the columns
+/// * `values` - 0 = counts, 1 = % of total, 2 = % of row, 3 = % of column
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_crosstab(
+    table: *const TesseraTable,
+    row_col: usize,
+    col_col: usize,
+    values: u32,
+) -> TableResult {
+    let Some(table) = table_arg(table) else {
+        return TableResult::error("Null pointer provided");
+    };
+    let Some(values) = CrosstabValues::from_raw(values) else {
+        return TableResult::error("Unknown crosstab value mode");
+    };
+
+    crosstab(table, row_col, col_col, values).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn survey() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Gender".into(), "Answer".into()],
+            [
+                ["F", "Yes"],
+                ["M", "No"],
+                ["F", "Yes"],
+                ["M", "Yes"],
+                ["F", ""],
+                ["F", "No"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_crosstab_counts() {
+        let result = crosstab(&survey(), 0, 1, CrosstabValues::Count).unwrap();
+
+        assert_eq!(
+            result.headers(),
+            ["Gender", "(blank)", "No", "Yes", "Total"]
+        );
+        assert_eq!(result.rows()[0], ["F", "1", "1", "2", "4"]);
+        assert_eq!(result.rows()[1], ["M", "0", "1", "1", "2"]);
+        assert_eq!(result.rows()[2], ["Total", "1", "2", "3", "6"]);
+    }
+
+    #[test]
+    fn test_crosstab_percentages() {
+        let by_row = crosstab(&survey(), 0, 1, CrosstabValues::PercentOfRow).unwrap();
+        assert_eq!(by_row.rows()[0], ["F", "25", "25", "50", "100"]);
+
+        let by_total = crosstab(&survey(), 0, 1, CrosstabValues::PercentOfTotal).unwrap();
+        assert_eq!(by_total.rows()[1], ["M", "0", "16.67", "16.67", "33.33"]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Duplicate detection and removal
+
+use std::collections::HashMap;
+
+use crate::ffi::slice_arg;
+use crate::table::{table_arg, CellText, TableResult, TesseraTable};
+use crate::StringResult;
+
+/// How cells are compared when looking for duplicates
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeOptions {
+    pub ignore_case: bool,
+    /// Ignore leading and trailing whitespace
+    pub trim: bool,
+}
+
+fn row_key(
+    table: &TesseraTable,
+    row: usize,
+    columns: &[usize],
+    options: &DedupeOptions,
+) -> Vec<String> {
+    columns
+        .iter()
+        .map(|&col| {
+            let cell = table.cell(row, col);
+            let cell = if options.trim { cell.trim() } else { cell };
+            if options.ignore_case {
+                cell.to_lowercase()
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Group row indices by key, in order of first occurrence
+fn key_groups(
+    table: &TesseraTable,
+    columns: &[usize],
+    options: &DedupeOptions,
+) -> Result<Vec<Vec<usize>>, String> {
+    if let Some(&col) = columns.iter().find(|&&c| c >= table.column_count()) {
+        return Err(format!("Column {} is out of range", col));
+    }
+    let all: Vec<usize>;
+    let columns = if columns.is_empty() {
+        all = (0..table.column_count()).collect();
+        &all
+    } else {
+        columns
+    };
+
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for row in 0..table.row_count() {
+        let key = row_key(table, row, columns, options);
+        match index.get(&key) {
+            Some(&slot) => groups[slot].push(row),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(vec![row]);
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Groups of rows that share the same values in `columns` (all columns when empty)
+///
+/// Only groups with at least two rows are returned, ordered by their first row.
+pub fn find_duplicates(
+    table: &TesseraTable,
+    columns: &[usize],
+    options: &DedupeOptions,
+) -> Result<Vec<Vec<usize>>, String> {
+    let mut groups = key_groups(table, columns, options)?;
+    groups.retain(|group| group.len() > 1);
+    Ok(groups)
+}
+
+/// Copy of the table keeping only the first row of each duplicate group
+pub fn deduplicate(
+    table: &TesseraTable,
+    columns: &[usize],
+    options: &DedupeOptions,
+) -> Result<TesseraTable, String> {
+    // Groups are in first-occurrence order, so their first rows are ascending
+    let mut result = TesseraTable::new(table.headers().to_vec());
+    for row in key_groups(table, columns, options)?
+        .into_iter()
+        .map(|g| g[0])
+    {
+        result.push_row(table.rows()[row].iter().map(CellText::to_string).collect());
+    }
+    Ok(result)
+}
+
+/// Find duplicate rows
+///
+/// # Arguments
+/// * `table` - Table handle to inspect
+/// * `columns_ptr` - Columns to compare (`column_count` 0 compares all columns)
+/// * `column_count` - Number of columns
+/// * `options` - Comparison options (null for exact comparison)
+///
+/// # Returns
+/// StringResult with a JSON array of duplicate groups, e.g. `[[0,3],[1,4,5]]`
+///
+/// # Safety
+/// `table` must be a live table handle; `columns_ptr` must point to `column_count`
+/// indices; `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_find_duplicates(
+    table: *const TesseraTable,
+    columns_ptr: *const usize,
+    column_count: usize,
+    options: *const DedupeOptions,
+) -> StringResult {
+    let (Some(table), Some(columns)) = (table_arg(table), slice_arg(columns_ptr, column_count))
+    else {
+        return StringResult::error("Null pointer provided");
+    };
+    let options = options.as_ref().copied().unwrap_or_default();
+
+    find_duplicates(table, columns, &options)
+        .map(|groups| serde_json::Value::from(groups).to_string())
+        .into()
+}
+
+/// Copy a table without duplicate rows, keeping the first of each group
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle; `columns_ptr` must point to `column_count`
+/// indices; `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_deduplicate(
+    table: *const TesseraTable,
+    columns_ptr: *const usize,
+    column_count: usize,
+    options: *const DedupeOptions,
+) -> TableResult {
+    let (Some(table), Some(columns)) = (table_arg(table), slice_arg(columns_ptr, column_count))
+    else {
+        return TableResult::error("Null pointer provided");
+    };
+    let options = options.as_ref().copied().unwrap_or_default();
+
+    deduplicate(table, columns, &options).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contacts() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Email".into(), "Name".into()],
+            [
+                ["ann@x.io", "Ann"],
+                ["bob@x.io", "Bob"],
+                ["ANN@x.io ", "Ann B"],
+                ["bob@x.io", "Bob"],
+                ["cid@x.io", "Cid"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let table = contacts();
+
+        assert_eq!(
+            find_duplicates(&table, &[], &DedupeOptions::default()).unwrap(),
+            [vec![1, 3]]
+        );
+
+        let loose = DedupeOptions {
+            ignore_case: true,
+            trim: true,
+        };
+        assert_eq!(
+            find_duplicates(&table, &[0], &loose).unwrap(),
+            [vec![0, 2], vec![1, 3]]
+        );
+        assert!(find_duplicates(&table, &[7], &loose).is_err());
+    }
+
+    #[test]
+    fn test_deduplicate_keeps_first() {
+        let loose = DedupeOptions {
+            ignore_case: true,
+            trim: true,
+        };
+        let result = deduplicate(&contacts(), &[0], &loose).unwrap();
+
+        assert_eq!(result.row_count(), 3);
+        assert_eq!(result.column(1).collect::<Vec<_>>(), ["Ann", "Bob", "Cid"]);
+    }
+}
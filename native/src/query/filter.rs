@@ -0,0 +1,95 @@
+//! Row filtering with formula-expression predicates
+
+use std::os::raw::c_char;
+
+use crate::ffi::str_arg;
+use crate::formula::row_context::RowFormula;
+use crate::table::{table_arg, TesseraTable};
+use crate::IndexArray;
+
+/// Indices of the rows for which `predicate` is true
+///
+/// Columns are referenced by bare name (`Amount > 100`) or in brackets
+/// (`[Sale Region] = "EU"`). Rows whose predicate evaluates to an error or
+/// to non-boolean text are excluded.
+pub fn filter_rows(table: &TesseraTable, predicate: &str) -> Result<Vec<usize>, String> {
+    let predicate = RowFormula::new(table, predicate)?;
+    Ok((0..table.row_count())
+        .filter(|&row| predicate.matches(table, row))
+        .collect())
+}
+
+/// Filter rows with an expression such as `Amount > 100 AND Region = "EU"`
+///
+/// # Returns
+/// IndexArray of matching row indices in table order (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `predicate` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_filter(
+    table: *const TesseraTable,
+    predicate: *const c_char,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    let Some(predicate) = str_arg(predicate) else {
+        return IndexArray::error("Invalid filter expression");
+    };
+
+    filter_rows(table, predicate).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sales() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Amount".into(), "Region".into(), "Q1".into()],
+            vec![
+                vec!["150".into(), "EU".into(), "x".into()],
+                vec!["90".into(), "EU".into(), "".into()],
+                vec!["300".into(), "us".into(), "x".into()],
+                vec!["n/a".into(), "eu".into(), "x".into()],
+                vec!["101".into(), "eu".into(), "".into()],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let table = sales();
+
+        // Text compares greater than any number, as in spreadsheet comparisons
+        assert_eq!(
+            filter_rows(&table, "Amount > 100 AND Region = \"EU\"").unwrap(),
+            [0, 3, 4]
+        );
+        assert_eq!(
+            filter_rows(
+                &table,
+                "ISNUMBER(Amount) AND Amount > 100 AND Region = \"EU\""
+            )
+            .unwrap(),
+            [0, 4]
+        );
+        assert_eq!(
+            filter_rows(&table, "=[region] <> \"eu\" OR Q1 = \"\"").unwrap(),
+            [1, 2, 4]
+        );
+        assert_eq!(filter_rows(&table, "Amount * 2 > 250").unwrap(), [0, 2]);
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let table = sales();
+
+        assert_eq!(
+            filter_rows(&table, "Price > 1").unwrap_err(),
+            "Column 'Price' not found"
+        );
+        assert!(filter_rows(&table, "Amount >").is_err());
+    }
+}
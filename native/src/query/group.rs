@@ -0,0 +1,519 @@
+//! Group-by aggregation
+
+use std::collections::HashMap;
+
+use chrono::Datelike;
+
+use crate::datetime::parse_datetime;
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Aggregate functions, with the same semantics as the column aggregates
+/// (`tessera_sum`, `tessera_count`, ...): numeric cells only, except COUNT
+/// which counts non-empty cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(AggregateFunction::Sum),
+            1 => Some(AggregateFunction::Avg),
+            2 => Some(AggregateFunction::Count),
+            3 => Some(AggregateFunction::Min),
+            4 => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        }
+    }
+}
+
+/// One output column of a group-by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aggregate {
+    pub column: usize,
+    pub function: AggregateFunction,
+}
+
+/// FFI form of [`Aggregate`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AggregateSpec {
+    pub column: usize,
+    pub function: u32, // 0 = sum, 1 = avg, 2 = count, 3 = min, 4 = max
+}
+
+impl TryFrom<AggregateSpec> for Aggregate {
+    type Error = String;
+
+    fn try_from(spec: AggregateSpec) -> Result<Self, String> {
+        Ok(Aggregate {
+            column: spec.column,
+            function: AggregateFunction::from_raw(spec.function)
+                .ok_or("Unknown aggregate function")?,
+        })
+    }
+}
+
+/// Running state for one aggregate over a stream of cells
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Accumulator {
+    sum: f64,
+    numeric: usize,
+    non_empty: usize,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Accumulator {
+    pub(crate) fn add(&mut self, cell: &str) {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            return;
+        }
+        self.non_empty += 1;
+
+        if let Ok(value) = cell.parse::<f64>() {
+            self.sum += value;
+            self.numeric += 1;
+            self.min = Some(self.min.map_or(value, |m| m.min(value)));
+            self.max = Some(self.max.map_or(value, |m| m.max(value)));
+        }
+    }
+
+    /// Result of `function`, or `None` when there were no numeric values
+    pub(crate) fn value(&self, function: AggregateFunction) -> Option<f64> {
+        match function {
+            AggregateFunction::Count => Some(self.non_empty as f64),
+            AggregateFunction::Sum => Some(self.sum),
+            AggregateFunction::Avg if self.numeric > 0 => Some(self.sum / self.numeric as f64),
+            AggregateFunction::Avg => None,
+            AggregateFunction::Min => self.min,
+            AggregateFunction::Max => self.max,
+        }
+    }
+
+    /// Result formatted as cell text (empty when undefined)
+    pub(crate) fn finish(&self, function: AggregateFunction) -> String {
+        self.value(function).map(format_number).unwrap_or_default()
+    }
+}
+
+/// Group rows by the values of `group_cols` and aggregate each group
+///
+/// Groups appear in order of first occurrence and keys compare by exact
+/// cell text. The result has the group columns followed by one column per
+/// aggregate, named like `SUM(Amount)`. With no group columns the result is
+/// a single row of grand totals.
+pub fn group_by(
+    table: &TesseraTable,
+    group_cols: &[usize],
+    aggregates: &[Aggregate],
+) -> Result<TesseraTable, String> {
+    let columns = group_cols
+        .iter()
+        .copied()
+        .chain(aggregates.iter().map(|a| a.column));
+    for col in columns {
+        if col >= table.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+    }
+
+    let mut index: HashMap<Vec<&str>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<&str>, Vec<Accumulator>)> = Vec::new();
+    if group_cols.is_empty() {
+        groups.push((Vec::new(), vec![Accumulator::default(); aggregates.len()]));
+    }
+
+    for row in 0..table.row_count() {
+        let key: Vec<&str> = group_cols.iter().map(|&c| table.cell(row, c)).collect();
+        let slot = match index.get(&key) {
+            Some(&slot) => slot,
+            None if group_cols.is_empty() => 0,
+            None => {
+                groups.push((key.clone(), vec![Accumulator::default(); aggregates.len()]));
+                index.insert(key, groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+
+        for (acc, aggregate) in groups[slot].1.iter_mut().zip(aggregates) {
+            acc.add(table.cell(row, aggregate.column));
+        }
+    }
+
+    let headers = group_cols
+        .iter()
+        .map(|&c| table.headers()[c].clone())
+        .chain(
+            aggregates
+                .iter()
+                .map(|a| format!("{}({})", a.function.name(), table.headers()[a.column])),
+        )
+        .collect();
+    let rows = groups
+        .into_iter()
+        .map(|(key, accs)| {
+            key.into_iter()
+                .map(str::to_string)
+                .chain(
+                    accs.iter()
+                        .zip(aggregates)
+                        .map(|(acc, a)| acc.finish(a.function)),
+                )
+                .collect()
+        })
+        .collect();
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Calendar period a date column is grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateBucket {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl DateBucket {
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(DateBucket::Day),
+            1 => Some(DateBucket::Week),
+            2 => Some(DateBucket::Month),
+            3 => Some(DateBucket::Quarter),
+            4 => Some(DateBucket::Year),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DateBucket::Day => "DAY",
+            DateBucket::Week => "WEEK",
+            DateBucket::Month => "MONTH",
+            DateBucket::Quarter => "QUARTER",
+            DateBucket::Year => "YEAR",
+        }
+    }
+
+    /// Label of the period holding a date cell: `2024-03-05`, `2024-W10`
+    /// (ISO week), `2024-03`, `2024-Q1` or `2024`; `None` when the cell is
+    /// not a date. Labels sort in date order.
+    pub fn label(self, cell: &str) -> Option<String> {
+        let date = parse_datetime(cell)?.date();
+        Some(match self {
+            DateBucket::Day => date.format("%Y-%m-%d").to_string(),
+            DateBucket::Week => {
+                let week = date.iso_week();
+                format!("{:04}-W{:02}", week.year(), week.week())
+            }
+            DateBucket::Month => date.format("%Y-%m").to_string(),
+            DateBucket::Quarter => format!("{:04}-Q{}", date.year(), date.month0() / 3 + 1),
+            DateBucket::Year => format!("{:04}", date.year()),
+        })
+    }
+}
+
+/// Group rows by the period of a date column, then by `group_cols`, and
+/// aggregate each group
+///
+/// The first result column holds the period labels and is named like
+/// `MONTH(Date)`; groups come in date order, with rows whose date does not
+/// parse in a last group with an empty label. Otherwise like [`group_by`].
+pub fn group_by_date(
+    table: &TesseraTable,
+    date_col: usize,
+    bucket: DateBucket,
+    group_cols: &[usize],
+    aggregates: &[Aggregate],
+) -> Result<TesseraTable, String> {
+    let columns = [date_col]
+        .into_iter()
+        .chain(group_cols.iter().copied())
+        .chain(aggregates.iter().map(|a| a.column));
+    for col in columns {
+        if col >= table.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+    }
+
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<String>, Vec<Accumulator>)> = Vec::new();
+    let mut labels: HashMap<&str, String> = HashMap::new();
+    for row in 0..table.row_count() {
+        let date = table.cell(row, date_col);
+        let label = labels
+            .entry(date)
+            .or_insert_with(|| bucket.label(date).unwrap_or_default());
+        let key: Vec<String> = std::iter::once(label.clone())
+            .chain(group_cols.iter().map(|&c| table.cell(row, c).to_string()))
+            .collect();
+        let slot = match index.get(&key) {
+            Some(&slot) => slot,
+            None => {
+                groups.push((key.clone(), vec![Accumulator::default(); aggregates.len()]));
+                index.insert(key, groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+
+        for (acc, aggregate) in groups[slot].1.iter_mut().zip(aggregates) {
+            acc.add(table.cell(row, aggregate.column));
+        }
+    }
+    // Stable, so groups within a period keep their order of first occurrence
+    groups.sort_by(|(a, _), (b, _)| (a[0].is_empty(), &a[0]).cmp(&(b[0].is_empty(), &b[0])));
+
+    let headers = std::iter::once(format!("{}({})", bucket.name(), table.headers()[date_col]))
+        .chain(group_cols.iter().map(|&c| table.headers()[c].clone()))
+        .chain(
+            aggregates
+                .iter()
+                .map(|a| format!("{}({})", a.function.name(), table.headers()[a.column])),
+        )
+        .collect();
+    let rows = groups
+        .into_iter()
+        .map(|(key, accs)| {
+            key.into_iter()
+                .chain(
+                    accs.iter()
+                        .zip(aggregates)
+                        .map(|(acc, a)| acc.finish(a.function)),
+                )
+                .collect()
+        })
+        .collect();
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Group rows and aggregate each group into a new table
+///
+/// # Arguments
+/// * `table` - Source table handle
+/// * `group_cols_ptr` - Column indices to group by
+/// * `group_count` - Number of group columns (0 for grand totals)
+/// * `aggregates_ptr` - Aggregates to compute per group
+/// * `aggregate_count` - Number of aggregates
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle; the pointers must reference arrays of the given lengths
+#[no_mangle]
+pub unsafe extern "C" fn tessera_group_by(
+    table: *const TesseraTable,
+    group_cols_ptr: *const usize,
+    group_count: usize,
+    aggregates_ptr: *const AggregateSpec,
+    aggregate_count: usize,
+) -> TableResult {
+    let Some(table) = table_arg(table) else {
+        return TableResult::error("Null pointer provided");
+    };
+    if (group_cols_ptr.is_null() && group_count > 0)
+        || (aggregates_ptr.is_null() && aggregate_count > 0)
+    {
+        return TableResult::error("Null pointer provided");
+    }
+
+    let group_cols = if group_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(group_cols_ptr, group_count)
+    };
+    let specs = if aggregate_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(aggregates_ptr, aggregate_count)
+    };
+
+    specs
+        .iter()
+        .map(|&spec| Aggregate::try_from(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|aggregates| group_by(table, group_cols, &aggregates))
+        .into()
+}
+
+/// Group rows by day, week, month, quarter or year of a date column and
+/// aggregate each group into a new table
+///
+/// # Arguments
+/// * `table` - Source table handle
+/// * `date_col` - Column holding the dates
+/// * `bucket` - 0 = day, 1 = ISO week, 2 = month, 3 = quarter, 4 = year
+/// * `group_cols_ptr` - Further column indices to group by
+/// * `group_count` - Number of further group columns (may be 0)
+/// * `aggregates_ptr` - Aggregates to compute per group
+/// * `aggregate_count` - Number of aggregates
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free); the
+/// first column holds period labels such as `2024-03` or `2024-Q1`
+///
+/// # Safety
+/// `table` must be a live table handle; the pointers must reference arrays of the given lengths
+#[no_mangle]
+pub unsafe extern "C" fn tessera_group_by_date(
+    table: *const TesseraTable,
+    date_col: usize,
+    bucket: u32,
+    group_cols_ptr: *const usize,
+    group_count: usize,
+    aggregates_ptr: *const AggregateSpec,
+    aggregate_count: usize,
+) -> TableResult {
+    let Some(table) = table_arg(table) else {
+        return TableResult::error("Null pointer provided");
+    };
+    if (group_cols_ptr.is_null() && group_count > 0)
+        || (aggregates_ptr.is_null() && aggregate_count > 0)
+    {
+        return TableResult::error("Null pointer provided");
+    }
+    let Some(bucket) = DateBucket::from_raw(bucket) else {
+        return TableResult::error(&format!("Unknown date bucket {}", bucket));
+    };
+
+    let group_cols = if group_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(group_cols_ptr, group_count)
+    };
+    let specs = if aggregate_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(aggregates_ptr, aggregate_count)
+    };
+
+    specs
+        .iter()
+        .map(|&spec| Aggregate::try_from(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|aggregates| group_by_date(table, date_col, bucket, group_cols, &aggregates))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sales() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Region".into(), "Rep".into(), "Amount".into()],
+            [
+                ["EU", "Ann", "100"],
+                ["US", "Bob", "50"],
+                ["EU", "Cid", "25.5"],
+                ["EU", "Ann", "n/a"],
+                ["US", "Bob", ""],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    fn agg(column: usize, function: AggregateFunction) -> Aggregate {
+        Aggregate { column, function }
+    }
+
+    #[test]
+    fn test_group_by() {
+        let result = group_by(
+            &sales(),
+            &[0],
+            &[
+                agg(2, AggregateFunction::Sum),
+                agg(2, AggregateFunction::Avg),
+                agg(2, AggregateFunction::Count),
+                agg(2, AggregateFunction::Max),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.headers(),
+            [
+                "Region",
+                "SUM(Amount)",
+                "AVG(Amount)",
+                "COUNT(Amount)",
+                "MAX(Amount)"
+            ]
+        );
+        assert_eq!(result.rows()[0], ["EU", "125.5", "62.75", "3", "100"]);
+        assert_eq!(result.rows()[1], ["US", "50", "50", "1", "50"]);
+    }
+
+    #[test]
+    fn test_group_by_multiple_keys_and_totals() {
+        let table = sales();
+        let by_rep = group_by(&table, &[0, 1], &[agg(2, AggregateFunction::Min)]).unwrap();
+        assert_eq!(by_rep.row_count(), 3);
+        assert_eq!(by_rep.rows()[2], ["EU", "Cid", "25.5"]);
+
+        let totals = group_by(&table, &[], &[agg(2, AggregateFunction::Sum)]).unwrap();
+        assert_eq!(totals.rows(), [vec!["175.5".to_string()]]);
+        assert!(group_by(&table, &[9], &[]).is_err());
+    }
+
+    #[test]
+    fn test_group_by_date() {
+        let table = TesseraTable::from_rows(
+            vec!["Date".into(), "Region".into(), "Amount".into()],
+            [
+                ["2024-03-05", "EU", "10"],
+                ["2024-01-31", "US", "5"],
+                ["03/20/2024", "EU", "7"],
+                ["not a date", "EU", "1"],
+                ["2024-02-29 18:00", "EU", "2"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        );
+        let sum = [agg(2, AggregateFunction::Sum)];
+
+        let months = group_by_date(&table, 0, DateBucket::Month, &[], &sum).unwrap();
+        assert_eq!(months.headers(), ["MONTH(Date)", "SUM(Amount)"]);
+        assert_eq!(
+            months.rows(),
+            [
+                ["2024-01", "5"],
+                ["2024-02", "2"],
+                ["2024-03", "17"],
+                ["", "1"]
+            ]
+        );
+
+        let quarters = group_by_date(&table, 0, DateBucket::Quarter, &[1], &sum).unwrap();
+        assert_eq!(quarters.rows()[0], ["2024-Q1", "EU", "19"]);
+        assert_eq!(quarters.rows()[1], ["2024-Q1", "US", "5"]);
+        assert_eq!(DateBucket::Week.label("2024-12-30").unwrap(), "2025-W01");
+        assert!(group_by_date(&table, 7, DateBucket::Year, &[], &sum).is_err());
+    }
+}
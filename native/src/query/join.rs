@@ -0,0 +1,247 @@
+//! Hash joins between two tables
+
+use std::collections::HashMap;
+
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+impl JoinKind {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(JoinKind::Inner),
+            1 => Some(JoinKind::Left),
+            2 => Some(JoinKind::Right),
+            3 => Some(JoinKind::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Pair of columns that must be equal for two rows to join
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinColumns {
+    pub left: usize,
+    pub right: usize,
+}
+
+/// Join two tables on one or more key columns
+///
+/// The result has every left column followed by the right columns that are
+/// not join keys; right headers that clash with a left header get a
+/// `_right` suffix. Keys compare by exact cell text. Row order follows the
+/// left table, with unmatched right rows (right/full joins) appended in
+/// their original order; their key columns are filled from the right table.
+pub fn join(
+    left: &TesseraTable,
+    right: &TesseraTable,
+    on: &[JoinColumns],
+    kind: JoinKind,
+) -> Result<TesseraTable, String> {
+    if on.is_empty() {
+        return Err("At least one join column is required".to_string());
+    }
+    for pair in on {
+        if pair.left >= left.column_count() || pair.right >= right.column_count() {
+            return Err(format!(
+                "Join columns ({}, {}) are out of range",
+                pair.left, pair.right
+            ));
+        }
+    }
+
+    let right_cols: Vec<usize> = (0..right.column_count())
+        .filter(|&c| !on.iter().any(|pair| pair.right == c))
+        .collect();
+
+    let mut headers = left.headers().to_vec();
+    for &c in &right_cols {
+        let name = &right.headers()[c];
+        if left.column_index(name).is_some() {
+            headers.push(format!("{}_right", name));
+        } else {
+            headers.push(name.clone());
+        }
+    }
+
+    // Build on the right table, probe with the left so output follows left
+    // order; a single key column indexed on the right is probed directly
+    let indexed = match on {
+        [pair] if right.is_indexed(pair.right) => Some(pair.right),
+        _ => None,
+    };
+    let mut index: HashMap<Vec<&str>, Vec<usize>> = HashMap::new();
+    for row in (0..right.row_count()).filter(|_| indexed.is_none()) {
+        let key = on.iter().map(|pair| right.cell(row, pair.right)).collect();
+        index.entry(key).or_default().push(row);
+    }
+
+    let mut rows = Vec::new();
+    let mut right_matched = vec![false; right.row_count()];
+    let right_part = |row: Option<usize>| {
+        right_cols.iter().map(move |&c| match row {
+            Some(r) => right.cell(r, c).to_string(),
+            None => String::new(),
+        })
+    };
+
+    for row in 0..left.row_count() {
+        let key: Vec<&str> = on.iter().map(|pair| left.cell(row, pair.left)).collect();
+        let matches = match indexed {
+            Some(col) => right
+                .indexed_rows(col, key[0])
+                .filter(|rows| !rows.is_empty()),
+            None => index.get(&key).map(Vec::as_slice),
+        };
+        match matches {
+            Some(matches) => {
+                for &r in matches {
+                    right_matched[r] = true;
+                    let mut cells: Vec<String> = left.row(row).map(str::to_string).collect();
+                    cells.extend(right_part(Some(r)));
+                    rows.push(cells);
+                }
+            }
+            None if matches!(kind, JoinKind::Left | JoinKind::Full) => {
+                let mut cells: Vec<String> = left.row(row).map(str::to_string).collect();
+                cells.extend(right_part(None));
+                rows.push(cells);
+            }
+            None => {}
+        }
+    }
+
+    if matches!(kind, JoinKind::Right | JoinKind::Full) {
+        for (r, _) in right_matched.iter().enumerate().filter(|(_, &m)| !m) {
+            let mut cells = vec![String::new(); left.column_count()];
+            for pair in on {
+                cells[pair.left] = right.cell(r, pair.right).to_string();
+            }
+            cells.extend(right_part(Some(r)));
+            rows.push(cells);
+        }
+    }
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Join two tables into a new table
+///
+/// # Arguments
+/// * `left` / `right` - Table handles to join
+/// * `on_ptr` - Key column pairs (left index, right index)
+/// * `on_count` - Number of key column pairs
+/// * `kind` - 0 = inner, 1 = left, 2 = right, 3 = full outer
+///
+/// # Returns
+/// TableResult with a new table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `left` and `right` must be live table handles; `on_ptr` must point to `on_count` pairs
+#[no_mangle]
+pub unsafe extern "C" fn tessera_join(
+    left: *const TesseraTable,
+    right: *const TesseraTable,
+    on_ptr: *const JoinColumns,
+    on_count: usize,
+    kind: u32,
+) -> TableResult {
+    let (Some(left), Some(right)) = (table_arg(left), table_arg(right)) else {
+        return TableResult::error("Null pointer provided");
+    };
+    if on_ptr.is_null() {
+        return TableResult::error("Null pointer provided");
+    }
+    let Some(kind) = JoinKind::from_raw(kind) else {
+        return TableResult::error("Unknown join kind");
+    };
+
+    let on = std::slice::from_raw_parts(on_ptr, on_count);
+    join(left, right, on, kind).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> TesseraTable {
+        TesseraTable::from_rows(
+            headers.iter().map(|h| h.to_string()).collect(),
+            rows.iter()
+                .map(|r| r.iter().map(|c| c.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    fn orders_and_customers() -> (TesseraTable, TesseraTable) {
+        let orders = table(
+            &["Order", "CustomerId", "Total"],
+            &[
+                &["o1", "1", "10"],
+                &["o2", "2", "20"],
+                &["o3", "9", "30"],
+                &["o4", "1", "40"],
+            ],
+        );
+        let customers = table(
+            &["Id", "Name", "Total"],
+            &[&["1", "Ann", "50"], &["2", "Bob", "20"], &["3", "Cid", "0"]],
+        );
+        (orders, customers)
+    }
+
+    const ON: [JoinColumns; 1] = [JoinColumns { left: 1, right: 0 }];
+
+    #[test]
+    fn test_inner_and_left_join() {
+        let (orders, customers) = orders_and_customers();
+
+        let inner = join(&orders, &customers, &ON, JoinKind::Inner).unwrap();
+        assert_eq!(
+            inner.headers(),
+            ["Order", "CustomerId", "Total", "Name", "Total_right"]
+        );
+        assert_eq!(inner.row_count(), 3);
+        assert_eq!(inner.rows()[2], ["o4", "1", "40", "Ann", "50"]);
+
+        let mut indexed = customers.clone();
+        indexed.create_index(0, false).unwrap();
+        assert_eq!(
+            join(&orders, &indexed, &ON, JoinKind::Inner).unwrap(),
+            inner
+        );
+
+        let left = join(&orders, &customers, &ON, JoinKind::Left).unwrap();
+        assert_eq!(left.row(2).collect::<Vec<_>>(), ["o3", "9", "30", "", ""]);
+    }
+
+    #[test]
+    fn test_right_and_full_join() {
+        let (orders, customers) = orders_and_customers();
+
+        let right = join(&orders, &customers, &ON, JoinKind::Right).unwrap();
+        assert_eq!(right.row_count(), 4);
+        assert_eq!(right.row(3).collect::<Vec<_>>(), ["", "3", "", "Cid", "0"]);
+
+        let full = join(&orders, &customers, &ON, JoinKind::Full).unwrap();
+        assert_eq!(full.row_count(), 5);
+        assert!(join(&orders, &customers, &[], JoinKind::Full).is_err());
+    }
+
+    #[test]
+    fn test_join_keeps_trailing_empty_left_cells() {
+        let left = table(&["id", "k", "note"], &[&["1", "a", ""]]);
+        let right = table(&["k", "v"], &[&["a", "X"]]);
+        let on = [JoinColumns { left: 1, right: 0 }];
+
+        let joined = join(&left, &right, &on, JoinKind::Inner).unwrap();
+        assert_eq!(joined.row(0).collect::<Vec<_>>(), ["1", "a", "", "X"]);
+    }
+}
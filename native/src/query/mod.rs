@@ -0,0 +1,23 @@
+//! Query operations over table handles (sorting, filtering, searching, ...)
+//!
+//! Operations never reorder or copy the table themselves unless they say so:
+//! most return row indices that the host applies to its own view.
+
+pub mod aggregate;
+pub mod collation;
+pub mod compare;
+pub mod correlation;
+pub mod crosstab;
+pub mod dedupe;
+pub mod filter;
+pub mod group;
+pub mod join;
+pub mod outliers;
+pub mod profile;
+pub mod replace;
+pub mod sample;
+pub mod search;
+pub mod sort;
+pub mod stream;
+pub mod top;
+pub mod window;
@@ -0,0 +1,138 @@
+//! Outlier rows of a numeric column, for highlighting in the grid
+
+use super::aggregate::{mean, stdev};
+use crate::render::scale::percentile;
+use crate::table::{table_arg, TesseraTable};
+use crate::IndexArray;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlierMethod {
+    /// Beyond `k` interquartile ranges below the first or above the third
+    /// quartile (Tukey's fences)
+    Iqr,
+    /// More than `k` sample standard deviations from the mean
+    ZScore,
+}
+
+impl OutlierMethod {
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(OutlierMethod::Iqr),
+            1 => Some(OutlierMethod::ZScore),
+            _ => None,
+        }
+    }
+
+    /// Usual threshold: 1.5 IQRs or 3 standard deviations
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            OutlierMethod::Iqr => 1.5,
+            OutlierMethod::ZScore => 3.0,
+        }
+    }
+}
+
+/// Indices of the rows whose number in `col` is an outlier, in row order
+///
+/// Non-numeric and empty cells are skipped and never flagged. A column
+/// without spread has no outliers.
+pub fn find_outliers(
+    table: &TesseraTable,
+    col: usize,
+    method: OutlierMethod,
+    threshold: f64,
+) -> Result<Vec<usize>, String> {
+    if col >= table.column_count() {
+        return Err(format!("Column {} is out of range", col));
+    }
+    if !(threshold > 0.0 && threshold.is_finite()) {
+        return Err("Outlier threshold must be a positive number".to_string());
+    }
+
+    let numbers = table.numbers(col);
+    let mut values: Vec<f64> = numbers.iter().flatten().copied().collect();
+    let (low, high) = match method {
+        OutlierMethod::Iqr => {
+            if values.is_empty() {
+                return Ok(Vec::new());
+            }
+            values.sort_by(f64::total_cmp);
+            let (q1, q3) = (percentile(&values, 25.0), percentile(&values, 75.0));
+            let spread = (q3 - q1) * threshold;
+            (q1 - spread, q3 + spread)
+        }
+        OutlierMethod::ZScore => {
+            let (Some(mean), Some(sd)) = (mean(&values), stdev(&values)) else {
+                return Ok(Vec::new());
+            };
+            (mean - sd * threshold, mean + sd * threshold)
+        }
+    };
+
+    Ok(numbers
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| value.is_some_and(|v| v < low || v > high))
+        .map(|(row, _)| row)
+        .collect())
+}
+
+/// Flag the outlier rows of a numeric column
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `column` - Numeric column to check
+/// * `method` - 0 = IQR fences, 1 = z-score
+/// * `threshold` - IQR multiplier or number of standard deviations; 0 uses
+///   the usual 1.5 IQRs or 3 standard deviations
+///
+/// # Returns
+/// IndexArray of outlier row indices in row order (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_find_outliers(
+    table: *const TesseraTable,
+    column: usize,
+    method: u32,
+    threshold: f64,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    let Some(method) = OutlierMethod::from_raw(method) else {
+        return IndexArray::error(&format!("Unknown outlier method {}", method));
+    };
+
+    let threshold = if threshold == 0.0 {
+        method.default_threshold()
+    } else {
+        threshold
+    };
+    find_outliers(table, column, method, threshold).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_outliers() {
+        let latencies = [
+            "12", "14", "13", "n/a", "15", "11", "240", "", "13", "14", "12", "-80",
+        ];
+        let table = TesseraTable::from_rows(
+            vec!["Latency".into()],
+            latencies.iter().map(|a| vec![a.to_string()]).collect(),
+        );
+
+        let iqr = OutlierMethod::Iqr;
+        assert_eq!(find_outliers(&table, 0, iqr, 1.5).unwrap(), [6, 11]);
+        let z = OutlierMethod::ZScore;
+        assert_eq!(find_outliers(&table, 0, z, 2.0).unwrap(), [6]);
+        assert!(find_outliers(&table, 0, z, 3.0).unwrap().is_empty());
+        assert!(find_outliers(&table, 0, iqr, -1.0).is_err());
+        assert!(find_outliers(&table, 1, iqr, 1.5).is_err());
+    }
+}
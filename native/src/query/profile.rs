@@ -0,0 +1,318 @@
+//! Column type inference and profiling
+
+use std::collections::HashSet;
+
+use super::aggregate;
+use crate::datetime::{format_datetime, parse_datetime};
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TableResult, TesseraTable};
+use crate::StringResult;
+
+/// Number of sample values reported per column
+const SAMPLE_SIZE: usize = 5;
+
+/// Column type, inferred with the same precedence as the C# `SchemaAgent`:
+/// bool, then int, float, date, and text as the fallback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferredType {
+    Int,
+    Float,
+    Date,
+    Bool,
+    Text,
+}
+
+impl InferredType {
+    pub fn name(self) -> &'static str {
+        match self {
+            InferredType::Int => "int",
+            InferredType::Float => "float",
+            InferredType::Date => "date",
+            InferredType::Bool => "bool",
+            InferredType::Text => "text",
+        }
+    }
+}
+
+/// Parse a number the way the schema inference does, allowing thousands separators
+pub(crate) fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let value = match text.parse::<f64>() {
+        Ok(value) => value,
+        Err(_) if text.contains(',') => text.replace(',', "").parse().ok()?,
+        Err(_) => return None,
+    };
+    value.is_finite().then_some(value)
+}
+
+fn is_bool(text: &str) -> bool {
+    text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false")
+}
+
+/// Infer the type of a column from its non-empty, trimmed values
+pub fn infer_type(values: &[&str]) -> InferredType {
+    if values.is_empty() {
+        InferredType::Text
+    } else if values.iter().all(|v| is_bool(v)) {
+        InferredType::Bool
+    } else if values.iter().all(|v| v.parse::<i64>().is_ok()) {
+        InferredType::Int
+    } else if values.iter().all(|v| parse_number(v).is_some()) {
+        InferredType::Float
+    } else if values.iter().all(|v| parse_datetime(v).is_some()) {
+        InferredType::Date
+    } else {
+        InferredType::Text
+    }
+}
+
+/// Summary of one column for the column popover and `describe`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub inferred_type: InferredType,
+    /// Number of rows
+    pub count: usize,
+    /// Empty or whitespace-only cells
+    pub null_count: usize,
+    /// Distinct non-empty values (compared after trimming)
+    pub distinct_count: usize,
+    /// Smallest value for numeric and date columns
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Mean of numeric columns
+    pub mean: Option<f64>,
+    /// Sample standard deviation of numeric columns (needs two values)
+    pub std_dev: Option<f64>,
+    /// First few non-empty values
+    pub samples: Vec<String>,
+}
+
+impl ColumnProfile {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "type": self.inferred_type.name(),
+            "count": self.count,
+            "nullCount": self.null_count,
+            "distinctCount": self.distinct_count,
+            "min": self.min,
+            "max": self.max,
+            "mean": self.mean,
+            "stdDev": self.std_dev,
+            "samples": self.samples,
+        })
+    }
+}
+
+/// Profile one column of a table
+pub fn profile_column(table: &TesseraTable, col: usize) -> Result<ColumnProfile, String> {
+    let Some(name) = table.headers().get(col) else {
+        return Err(format!("Column {} is out of range", col));
+    };
+
+    let values: Vec<&str> = table
+        .column(col)
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+    let inferred_type = infer_type(&values);
+    let distinct: HashSet<&str> = values.iter().copied().collect();
+
+    let (min, max, mean, std_dev) = match inferred_type {
+        InferredType::Int | InferredType::Float => {
+            let numbers: Vec<f64> = values.iter().filter_map(|v| parse_number(v)).collect();
+            (
+                aggregate::min(&numbers).map(format_number),
+                aggregate::max(&numbers).map(format_number),
+                aggregate::mean(&numbers),
+                aggregate::stdev(&numbers),
+            )
+        }
+        InferredType::Date => {
+            let dates: Vec<_> = values.iter().filter_map(|v| parse_datetime(v)).collect();
+            (
+                dates.iter().min().copied().map(format_datetime),
+                dates.iter().max().copied().map(format_datetime),
+                None,
+                None,
+            )
+        }
+        InferredType::Bool | InferredType::Text => (None, None, None, None),
+    };
+
+    Ok(ColumnProfile {
+        name: name.clone(),
+        inferred_type,
+        count: table.row_count(),
+        null_count: table.row_count() - values.len(),
+        distinct_count: distinct.len(),
+        min,
+        max,
+        mean,
+        std_dev,
+        samples: values
+            .iter()
+            .take(SAMPLE_SIZE)
+            .map(|v| v.to_string())
+            .collect(),
+    })
+}
+
+/// Summarize every column as a table, one row per column
+///
+/// Columns: Column, Type, Count, Missing, Missing %, Distinct, Min, Max,
+/// Mean, Std Dev. Statistics that do not apply to a column are left empty.
+pub fn describe(table: &TesseraTable) -> TesseraTable {
+    let headers = [
+        "Column",
+        "Type",
+        "Count",
+        "Missing",
+        "Missing %",
+        "Distinct",
+        "Min",
+        "Max",
+        "Mean",
+        "Std Dev",
+    ];
+    let round = |value: f64, digits: i32| {
+        let factor = 10f64.powi(digits);
+        format_number((value * factor).round() / factor)
+    };
+
+    let mut result = TesseraTable::new(headers.iter().map(|h| h.to_string()).collect());
+    for col in 0..table.column_count() {
+        let Ok(profile) = profile_column(table, col) else {
+            continue;
+        };
+        let missing_percent = if profile.count == 0 {
+            0.0
+        } else {
+            profile.null_count as f64 * 100.0 / profile.count as f64
+        };
+
+        result.push_row(vec![
+            profile.name,
+            profile.inferred_type.name().to_string(),
+            profile.count.to_string(),
+            profile.null_count.to_string(),
+            round(missing_percent, 2),
+            profile.distinct_count.to_string(),
+            profile.min.unwrap_or_default(),
+            profile.max.unwrap_or_default(),
+            profile.mean.map(|m| round(m, 6)).unwrap_or_default(),
+            profile.std_dev.map(|s| round(s, 6)).unwrap_or_default(),
+        ]);
+    }
+    result
+}
+
+/// Profile a column: inferred type, null and distinct counts, min/max, mean, samples
+///
+/// # Returns
+/// StringResult with a JSON object (`type` is one of int, float, date, bool, text)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_profile_column(
+    table: *const TesseraTable,
+    col: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    profile_column(table, col)
+        .map(|profile| profile.to_json().to_string())
+        .into()
+}
+
+/// Summarize every column of a table (like pandas `describe()`)
+///
+/// # Returns
+/// TableResult with one row per column (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_describe(table: *const TesseraTable) -> TableResult {
+    match table_arg(table) {
+        Some(table) => TableResult::success(describe(table)),
+        None => TableResult::error("Null pointer provided"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec![
+                "Id".into(),
+                "Price".into(),
+                "Joined".into(),
+                "Active".into(),
+            ],
+            [
+                ["1", "1,200.50", "2024-01-05", "true"],
+                ["2", "3", "", "FALSE"],
+                ["3", " ", "02/01/2023", "True"],
+                ["3", "7.5", "2024-12-31", ""],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_profile_numeric_columns() {
+        let table = table();
+
+        let id = profile_column(&table, 0).unwrap();
+        assert_eq!(id.inferred_type, InferredType::Int);
+        assert_eq!(id.distinct_count, 3);
+        assert_eq!(id.mean, Some(2.25));
+
+        let price = profile_column(&table, 1).unwrap();
+        assert_eq!(price.inferred_type, InferredType::Float);
+        assert_eq!(price.null_count, 1);
+        assert_eq!(price.min.as_deref(), Some("3"));
+        assert_eq!(price.max.as_deref(), Some("1200.5"));
+    }
+
+    #[test]
+    fn test_profile_dates_and_bools() {
+        let table = table();
+
+        let joined = profile_column(&table, 2).unwrap();
+        assert_eq!(joined.inferred_type, InferredType::Date);
+        assert_eq!(joined.min.as_deref(), Some("2023-02-01"));
+        assert_eq!(joined.samples, ["2024-01-05", "02/01/2023", "2024-12-31"]);
+
+        let active = profile_column(&table, 3).unwrap();
+        assert_eq!(active.inferred_type, InferredType::Bool);
+        assert_eq!(active.to_json()["type"], "bool");
+        assert!(profile_column(&table, 4).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let summary = describe(&table());
+
+        assert_eq!(summary.row_count(), 4);
+        assert_eq!(summary.headers()[4], "Missing %");
+        assert_eq!(
+            summary.rows()[0],
+            ["Id", "int", "4", "0", "0", "3", "1", "3", "2.25", "0.957427"]
+        );
+        assert_eq!(
+            summary.rows()[3][..6],
+            ["Active", "bool", "4", "1", "25", "3"]
+        );
+        assert_eq!(summary.cell(3, 8), "");
+    }
+}
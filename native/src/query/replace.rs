@@ -0,0 +1,217 @@
+//! Find-and-replace over a table, a column or a selection
+
+use std::os::raw::c_char;
+
+use regex::NoExpand;
+
+use super::search::{build_matcher, SearchMode, SearchOptions, SearchSettings};
+use crate::ffi::{error_string, str_arg};
+use crate::table::{table_arg_mut, CellPosition, CellRange, TesseraTable};
+
+/// Outcome of a replace operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceSummary {
+    /// Total number of matches replaced
+    pub replacements: usize,
+    /// Cells whose text changed, in row-major order
+    pub cells: Vec<CellPosition>,
+}
+
+/// Replace every match of `pattern` inside `range` (the whole table when `None`)
+///
+/// In regex mode the replacement may reference capture groups as `$1` or
+/// `${name}` (`${1}x` when a letter follows, `$$` for a literal dollar); in
+/// substring mode it is inserted verbatim.
+pub fn replace(
+    table: &mut TesseraTable,
+    pattern: &str,
+    replacement: &str,
+    settings: &SearchSettings,
+    range: Option<CellRange>,
+) -> Result<ReplaceSummary, String> {
+    let matcher = build_matcher(pattern, settings)?;
+    let (rows, cols) = range.unwrap_or_else(|| CellRange::all(table)).clamp(table);
+    let mut summary = ReplaceSummary::default();
+
+    for row in rows {
+        for col in cols.clone() {
+            let text = table.cell(row, col);
+            let count = matcher.find_iter(text).count();
+            if count == 0 {
+                continue;
+            }
+
+            let replaced = match settings.mode {
+                SearchMode::Substring => matcher.replace_all(text, NoExpand(replacement)),
+                SearchMode::Regex => matcher.replace_all(text, replacement),
+            };
+            if replaced != text {
+                let replaced = replaced.into_owned();
+                table.set_cell(row, col, replaced)?;
+                summary.replacements += count;
+                summary.cells.push(CellPosition { row, col });
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// FFI result of a replace operation
+#[repr(C)]
+pub struct ReplaceResult {
+    pub replacements: usize,
+    pub cells: *mut CellPosition, // null if none changed or error, free with tessera_free_cell_positions
+    pub len: usize,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl From<Result<ReplaceSummary, String>> for ReplaceResult {
+    fn from(result: Result<ReplaceSummary, String>) -> Self {
+        match result {
+            Ok(summary) if summary.cells.is_empty() => ReplaceResult {
+                replacements: summary.replacements,
+                cells: std::ptr::null_mut(),
+                len: 0,
+                error: std::ptr::null_mut(),
+            },
+            Ok(summary) => {
+                let boxed = summary.cells.into_boxed_slice();
+                let len = boxed.len();
+                ReplaceResult {
+                    replacements: summary.replacements,
+                    cells: Box::into_raw(boxed) as *mut CellPosition,
+                    len,
+                    error: std::ptr::null_mut(),
+                }
+            }
+            Err(msg) => ReplaceResult {
+                replacements: 0,
+                cells: std::ptr::null_mut(),
+                len: 0,
+                error: error_string(&msg),
+            },
+        }
+    }
+}
+
+/// Find and replace text in a table
+///
+/// # Arguments
+/// * `table` - Table handle to modify
+/// * `pattern` - Text or regular expression to find
+/// * `replacement` - Replacement text (`$1` / `${name}` capture references in regex mode)
+/// * `options` - Search options (null for case-insensitive substring matching)
+/// * `range` - Cells to touch; null for the whole table (a one-column range for a column)
+///
+/// # Returns
+/// ReplaceResult with the match count and changed cells (free cells with tessera_free_cell_positions)
+///
+/// # Safety
+/// `table` must be a live table handle; `pattern` and `replacement` must be valid
+/// C strings; `options` and `range` must be null or point to valid structs
+#[no_mangle]
+pub unsafe extern "C" fn tessera_replace(
+    table: *mut TesseraTable,
+    pattern: *const c_char,
+    replacement: *const c_char,
+    options: *const SearchOptions,
+    range: *const CellRange,
+) -> ReplaceResult {
+    let Some(table) = table_arg_mut(table) else {
+        return Err("Null pointer provided".to_string()).into();
+    };
+    let (Some(pattern), Some(replacement)) = (str_arg(pattern), str_arg(replacement)) else {
+        return Err("Invalid pattern or replacement encoding".to_string()).into();
+    };
+
+    SearchOptions::settings(options)
+        .and_then(|settings| {
+            replace(
+                table,
+                pattern,
+                replacement,
+                &settings,
+                range.as_ref().copied(),
+            )
+        })
+        .into()
+}
+
+/// Free a cell position array (the error string is freed with tessera_free_string)
+///
+/// # Safety
+/// `cells` and `len` must come from the same result returned by this library
+#[no_mangle]
+pub unsafe extern "C" fn tessera_free_cell_positions(cells: *mut CellPosition, len: usize) {
+    if !cells.is_null() {
+        let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(cells, len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Name".into(), "Phone".into()],
+            vec![
+                vec!["Doe, John".into(), "555-1234".into()],
+                vec!["Roe, Jane".into(), "555-9876 / 555-0000".into()],
+                vec!["Smith".into(), "n/a".into()],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_regex_replace_with_captures() {
+        let mut table = people();
+        let settings = SearchSettings {
+            mode: SearchMode::Regex,
+            ..Default::default()
+        };
+
+        let summary = replace(&mut table, r"(\w+), (\w+)", "$2 $1", &settings, None).unwrap();
+        assert_eq!(summary.replacements, 2);
+        assert_eq!(table.cell(0, 0), "John Doe");
+        assert_eq!(table.cell(1, 0), "Jane Roe");
+        assert_eq!(table.cell(2, 0), "Smith");
+
+        let phone = CellRange::column(&table, 1);
+        let summary = replace(
+            &mut table,
+            r"(\d{3})-(\d{4})",
+            "(${1}) $2",
+            &settings,
+            Some(phone),
+        )
+        .unwrap();
+        assert_eq!(summary.replacements, 3);
+        assert_eq!(table.cell(1, 1), "(555) 9876 / (555) 0000");
+    }
+
+    #[test]
+    fn test_literal_replace_in_selection() {
+        let mut table = people();
+        let range = CellRange {
+            row: 1,
+            col: 0,
+            rows: 10,
+            cols: 10,
+        };
+
+        let summary = replace(
+            &mut table,
+            "555",
+            "$1",
+            &SearchSettings::default(),
+            Some(range),
+        )
+        .unwrap();
+        assert_eq!(summary.replacements, 2);
+        assert_eq!(summary.cells, [CellPosition { row: 1, col: 1 }]);
+        assert_eq!(table.cell(0, 1), "555-1234");
+        assert_eq!(table.cell(1, 1), "$1-9876 / $1-0000");
+    }
+}
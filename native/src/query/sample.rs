@@ -0,0 +1,193 @@
+//! Row samples of a large table, for exploring it interactively
+//!
+//! Every sample is drawn from a seed, so the same seed over the same table
+//! gives the same rows. Samples come back in row order.
+
+use std::collections::HashMap;
+
+use crate::formula::volatile::SeededRandom;
+use crate::table::{table_arg, TesseraTable};
+use crate::IndexArray;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMethod {
+    /// Rows drawn uniformly without replacement
+    Random,
+    /// Every k-th row from a random start, k being rows / n
+    Systematic,
+    /// A random sample of each distinct value of the column, sized in
+    /// proportion to how many rows hold it
+    Stratified(usize),
+}
+
+impl SampleMethod {
+    pub(crate) fn from_raw(raw: u32, column: usize) -> Option<Self> {
+        match raw {
+            0 => Some(SampleMethod::Random),
+            1 => Some(SampleMethod::Systematic),
+            2 => Some(SampleMethod::Stratified(column)),
+            _ => None,
+        }
+    }
+}
+
+/// `n` of `rows` drawn without replacement (partial Fisher-Yates shuffle)
+fn draw(mut rows: Vec<usize>, n: usize, rng: &SeededRandom) -> Vec<usize> {
+    let n = n.min(rows.len());
+    for i in 0..n {
+        let j = i + rng.below(rows.len() - i);
+        rows.swap(i, j);
+    }
+    rows.truncate(n);
+    rows
+}
+
+/// Split `n` across groups of the given sizes in proportion, handing the
+/// rounding remainder to the largest fractions (earlier groups on ties)
+fn allocate(sizes: &[usize], n: usize) -> Vec<usize> {
+    let total: usize = sizes.iter().sum();
+    let exact: Vec<f64> = sizes
+        .iter()
+        .map(|&size| size as f64 * n as f64 / total as f64)
+        .collect();
+    let mut quotas: Vec<usize> = exact.iter().map(|e| *e as usize).collect();
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    let fraction = |group: usize| exact[group] - exact[group].floor();
+    order.sort_by(|&a, &b| fraction(b).total_cmp(&fraction(a)));
+    let remainder = n.saturating_sub(quotas.iter().sum());
+    for &group in order.iter().take(remainder) {
+        quotas[group] += 1;
+    }
+    quotas
+}
+
+/// Indices of a sample of `n` rows, in row order
+///
+/// Asking for at least as many rows as the table has returns every row.
+pub fn sample_rows(
+    table: &TesseraTable,
+    n: usize,
+    method: SampleMethod,
+    seed: u64,
+) -> Result<Vec<usize>, String> {
+    if let SampleMethod::Stratified(col) = method {
+        if col >= table.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+    }
+    let count = table.row_count();
+    if n >= count {
+        return Ok((0..count).collect());
+    }
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+
+    let rng = SeededRandom::new(seed, (0, 0, 0));
+    let mut rows = match method {
+        SampleMethod::Random => draw((0..count).collect(), n, &rng),
+        SampleMethod::Systematic => {
+            let step = count as f64 / n as f64;
+            let start = rng.next() * step;
+            (0..n)
+                .map(|i| ((start + i as f64 * step) as usize).min(count - 1))
+                .collect()
+        }
+        SampleMethod::Stratified(col) => {
+            let mut index = HashMap::new();
+            let mut strata: Vec<Vec<usize>> = Vec::new();
+            for (row, cell) in table.column(col).enumerate() {
+                let stratum = *index.entry(cell).or_insert_with(|| {
+                    strata.push(Vec::new());
+                    strata.len() - 1
+                });
+                strata[stratum].push(row);
+            }
+            let sizes: Vec<usize> = strata.iter().map(Vec::len).collect();
+            strata
+                .into_iter()
+                .zip(allocate(&sizes, n))
+                .flat_map(|(rows, quota)| draw(rows, quota, &rng))
+                .collect()
+        }
+    };
+    rows.sort_unstable();
+    Ok(rows)
+}
+
+/// Sample rows of a table
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `n` - Number of rows wanted
+/// * `method` - 0 = random, 1 = systematic, 2 = stratified by `column`
+/// * `column` - Stratum column for method 2 (ignored otherwise)
+/// * `seed` - Random seed; the same seed gives the same sample
+///
+/// # Returns
+/// IndexArray of sampled row indices in row order (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sample_rows(
+    table: *const TesseraTable,
+    n: usize,
+    method: u32,
+    column: usize,
+    seed: u64,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    let Some(method) = SampleMethod::from_raw(method, column) else {
+        return IndexArray::error(&format!("Unknown sample method {}", method));
+    };
+    sample_rows(table, n, method, seed).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_rows() {
+        let table = TesseraTable::from_rows(
+            vec!["Id".into(), "Region".into()],
+            (0..100)
+                .map(|i| {
+                    let region = if i < 80 { "North" } else { "South" };
+                    vec![i.to_string(), region.to_string()]
+                })
+                .collect(),
+        );
+
+        let random = sample_rows(&table, 10, SampleMethod::Random, 42).unwrap();
+        assert_eq!(random.len(), 10);
+        assert!(random.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            random,
+            sample_rows(&table, 10, SampleMethod::Random, 42).unwrap()
+        );
+        assert_ne!(
+            random,
+            sample_rows(&table, 10, SampleMethod::Random, 7).unwrap()
+        );
+
+        let systematic = sample_rows(&table, 4, SampleMethod::Systematic, 1).unwrap();
+        assert!(systematic.windows(2).all(|w| w[1] - w[0] == 25));
+
+        let stratified = sample_rows(&table, 10, SampleMethod::Stratified(1), 3).unwrap();
+        let south = stratified.iter().filter(|&&row| row >= 80).count();
+        assert_eq!((stratified.len(), south), (10, 2));
+        assert_eq!(allocate(&[6, 3, 1], 3), [2, 1, 0]);
+
+        assert_eq!(
+            sample_rows(&table, 500, SampleMethod::Random, 0)
+                .unwrap()
+                .len(),
+            100
+        );
+        assert!(sample_rows(&table, 5, SampleMethod::Stratified(2), 0).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! Bulk text search over every cell of a table
+
+use std::os::raw::c_char;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::ffi::{error_string, str_arg};
+use crate::table::{table_arg, TesseraTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Plain text, no special characters
+    Substring,
+    /// Regular expression (Rust `regex` syntax)
+    Regex,
+}
+
+impl SearchMode {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(SearchMode::Substring),
+            1 => Some(SearchMode::Regex),
+            _ => None,
+        }
+    }
+}
+
+/// How a search pattern is matched against cell text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchSettings {
+    pub mode: SearchMode,
+    pub match_case: bool,
+    /// Only match cells whose entire text matches the pattern
+    pub whole_cell: bool,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        SearchSettings {
+            mode: SearchMode::Substring,
+            match_case: false,
+            whole_cell: false,
+        }
+    }
+}
+
+/// FFI form of [`SearchSettings`]
+#[repr(C)]
+pub struct SearchOptions {
+    pub mode: u32, // 0 = substring, 1 = regex
+    pub match_case: bool,
+    pub whole_cell: bool,
+}
+
+impl SearchOptions {
+    /// Read options from a nullable pointer, falling back to defaults
+    ///
+    /// # Safety
+    /// `options` must be null or point to a valid `SearchOptions`
+    pub(crate) unsafe fn settings(options: *const SearchOptions) -> Result<SearchSettings, String> {
+        match options.as_ref() {
+            None => Ok(SearchSettings::default()),
+            Some(options) => Ok(SearchSettings {
+                mode: SearchMode::from_raw(options.mode).ok_or("Unknown search mode")?,
+                match_case: options.match_case,
+                whole_cell: options.whole_cell,
+            }),
+        }
+    }
+}
+
+/// Compile a pattern into the regex used for both search and replace
+pub(crate) fn build_matcher(pattern: &str, settings: &SearchSettings) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+
+    let body = match settings.mode {
+        SearchMode::Substring => regex::escape(pattern),
+        SearchMode::Regex => pattern.to_string(),
+    };
+    let body = if settings.whole_cell {
+        format!("^(?:{})$", body)
+    } else {
+        body
+    };
+
+    RegexBuilder::new(&body)
+        .case_insensitive(!settings.match_case)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Convert a byte offset in `text` to UTF-16 code units, the unit .NET strings index by
+pub(crate) fn utf16_offset(text: &str, byte: usize) -> usize {
+    if text.is_ascii() {
+        byte
+    } else {
+        text[..byte].encode_utf16().count()
+    }
+}
+
+/// One match: cell address plus offset and length within the cell text
+///
+/// `start` and `len` are in UTF-16 code units so the host can highlight
+/// the match without re-scanning the string.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchHit {
+    pub row: usize,
+    pub col: usize,
+    pub start: usize,
+    pub len: usize,
+}
+
+/// Find every match in the table, row by row, left to right
+///
+/// Empty matches (e.g. from `a*`) are skipped.
+pub fn search(
+    table: &TesseraTable,
+    pattern: &str,
+    settings: &SearchSettings,
+) -> Result<Vec<SearchHit>, String> {
+    let matcher = build_matcher(pattern, settings)?;
+    let mut hits = Vec::new();
+
+    for (row, cells) in table.rows().iter().enumerate() {
+        for (col, text) in cells.iter().enumerate() {
+            for found in matcher.find_iter(text).filter(|m| !m.is_empty()) {
+                let start = utf16_offset(text, found.start());
+                hits.push(SearchHit {
+                    row,
+                    col,
+                    start,
+                    len: utf16_offset(text, found.end()) - start,
+                });
+            }
+        }
+    }
+
+    Ok(hits)
+}
+
+/// FFI-safe array of search hits
+#[repr(C)]
+pub struct SearchResult {
+    pub hits: *mut SearchHit, // null if empty or error, free with tessera_free_search_hits
+    pub len: usize,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl From<Result<Vec<SearchHit>, String>> for SearchResult {
+    fn from(result: Result<Vec<SearchHit>, String>) -> Self {
+        match result {
+            Ok(hits) if hits.is_empty() => SearchResult {
+                hits: std::ptr::null_mut(),
+                len: 0,
+                error: std::ptr::null_mut(),
+            },
+            Ok(hits) => {
+                let boxed = hits.into_boxed_slice();
+                let len = boxed.len();
+                SearchResult {
+                    hits: Box::into_raw(boxed) as *mut SearchHit,
+                    len,
+                    error: std::ptr::null_mut(),
+                }
+            }
+            Err(msg) => SearchResult {
+                hits: std::ptr::null_mut(),
+                len: 0,
+                error: error_string(&msg),
+            },
+        }
+    }
+}
+
+/// Search every cell of a table in one pass
+///
+/// # Arguments
+/// * `table` - Table handle to search
+/// * `pattern` - Text or regular expression to find
+/// * `options` - Search options (null for case-insensitive substring search)
+///
+/// # Returns
+/// SearchResult with all hits in row-major order (free with tessera_free_search_hits)
+///
+/// # Safety
+/// `table` must be a live table handle; `pattern` must be a valid C string;
+/// `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_search(
+    table: *const TesseraTable,
+    pattern: *const c_char,
+    options: *const SearchOptions,
+) -> SearchResult {
+    let Some(table) = table_arg(table) else {
+        return Err("Null pointer provided".to_string()).into();
+    };
+    let Some(pattern) = str_arg(pattern) else {
+        return Err("Invalid search pattern encoding".to_string()).into();
+    };
+
+    SearchOptions::settings(options)
+        .and_then(|settings| search(table, pattern, &settings))
+        .into()
+}
+
+/// Free the hits of a SearchResult (the error string is freed with tessera_free_string)
+///
+/// # Safety
+/// `hits` and `len` must come from the same SearchResult returned by this library
+#[no_mangle]
+pub unsafe extern "C" fn tessera_free_search_hits(hits: *mut SearchHit, len: usize) {
+    if !hits.is_null() {
+        let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(hits, len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Name".into(), "Note".into()],
+            vec![
+                vec!["Apple".into(), "apple pie, APPLE".into()],
+                vec!["Pineapple".into(), "Crème brûlée apple".into()],
+            ],
+        )
+    }
+
+    fn hit(row: usize, col: usize, start: usize, len: usize) -> SearchHit {
+        SearchHit {
+            row,
+            col,
+            start,
+            len,
+        }
+    }
+
+    #[test]
+    fn test_substring_search() {
+        let hits = search(&table(), "apple", &SearchSettings::default()).unwrap();
+        assert_eq!(
+            hits,
+            [
+                hit(0, 0, 0, 5),
+                hit(0, 1, 0, 5),
+                hit(0, 1, 11, 5),
+                hit(1, 0, 4, 5),
+                hit(1, 1, 13, 5),
+            ]
+        );
+
+        let exact = SearchSettings {
+            match_case: true,
+            whole_cell: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            search(&table(), "Apple", &exact).unwrap(),
+            [hit(0, 0, 0, 5)]
+        );
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let settings = SearchSettings {
+            mode: SearchMode::Regex,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            search(&table(), r"br\w+", &settings).unwrap(),
+            [hit(1, 1, 6, 6)]
+        );
+        assert!(search(&table(), "(", &settings).is_err());
+        assert!(search(&table(), "", &settings).is_err());
+    }
+}
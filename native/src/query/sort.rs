@@ -0,0 +1,477 @@
+//! Multi-key row sorting
+//!
+//! Sorting returns a row permutation rather than reordering the table so the
+//! host can keep its own row model and undo the sort trivially. When the
+//! table itself should change, `tessera_sort_in_place` applies the order and
+//! rewrites formula references so they follow their rows.
+
+use std::cmp::Ordering;
+use std::os::raw::c_char;
+
+use super::collation::{CollationOptions, TextCollator};
+use crate::ffi::error_string;
+use crate::table::{table_arg, table_arg_mut, TesseraTable};
+use crate::IndexArray;
+
+/// How cell text is compared for one sort key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// Plain ordinal string comparison
+    Lexicographic,
+    /// Numbers by value; non-numeric text after all numbers
+    Numeric,
+    /// Digit runs compared by value ("file2" before "file10"), like NaturalStringComparer
+    Natural,
+}
+
+impl SortMode {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(SortMode::Lexicographic),
+            1 => Some(SortMode::Numeric),
+            2 => Some(SortMode::Natural),
+            _ => None,
+        }
+    }
+}
+
+/// One key of a multi-key sort
+#[derive(Debug, Clone, Copy)]
+pub struct SortSpec {
+    pub column: usize,
+    pub descending: bool,
+    pub mode: SortMode,
+}
+
+/// FFI form of a sort key
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    pub column: usize,
+    pub descending: bool,
+    pub mode: u32, // 0 = lexicographic, 1 = numeric, 2 = natural
+}
+
+impl TryFrom<SortKey> for SortSpec {
+    type Error = String;
+
+    fn try_from(key: SortKey) -> Result<Self, String> {
+        Ok(SortSpec {
+            column: key.column,
+            descending: key.descending,
+            mode: SortMode::from_raw(key.mode).ok_or("Unknown sort mode")?,
+        })
+    }
+}
+
+/// Compare two strings the way `NaturalStringComparer` does on the C# side
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a);
+    let mut b_chunks = chunks(b);
+
+    loop {
+        match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+                let result = match digits(x) && digits(y) {
+                    true => cmp_digits(x, y),
+                    false => cmp_ignore_case(x, y),
+                };
+                if result != Ordering::Equal {
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+/// Compare digit runs by value without parsing them, so runs of any length
+/// work; equal values with fewer leading zeros come first
+fn cmp_digits(a: &str, b: &str) -> Ordering {
+    let (a_value, b_value) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a_value
+        .len()
+        .cmp(&b_value.len())
+        .then_with(|| a_value.cmp(b_value))
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Split into alternating digit / non-digit runs
+fn chunks(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let digits = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+/// Per-key sort data, parsed once up front instead of on every comparison
+enum KeyColumn<'a> {
+    Text(Vec<&'a str>),
+    Numeric(Vec<Result<f64, &'a str>>),
+}
+
+impl KeyColumn<'_> {
+    fn is_blank(&self, row: usize) -> bool {
+        match self {
+            KeyColumn::Text(cells) => cells[row].trim().is_empty(),
+            KeyColumn::Numeric(cells) => matches!(cells[row], Err(text) if text.trim().is_empty()),
+        }
+    }
+
+    fn compare(
+        &self,
+        mode: SortMode,
+        collator: Option<&TextCollator>,
+        a: usize,
+        b: usize,
+    ) -> Ordering {
+        let text_cmp = |x: &str, y: &str| match collator {
+            Some(collator) => collator.compare(x, y),
+            None => x.cmp(y),
+        };
+
+        match self {
+            KeyColumn::Text(cells) if mode == SortMode::Natural => match collator {
+                Some(collator) => collator.compare_natural(cells[a], cells[b]),
+                None => natural_cmp(cells[a], cells[b]),
+            },
+            KeyColumn::Text(cells) => text_cmp(cells[a], cells[b]),
+            KeyColumn::Numeric(cells) => match (&cells[a], &cells[b]) {
+                (Ok(x), Ok(y)) => x.total_cmp(y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(x), Err(y)) => text_cmp(x, y),
+            },
+        }
+    }
+}
+
+/// Compute the stable row order for a multi-key sort
+///
+/// Blank cells always sort last, whatever the direction, matching spreadsheet
+/// behavior; rows that compare equal on every key keep their original order.
+pub fn sort_permutation(table: &TesseraTable, keys: &[SortSpec]) -> Result<Vec<usize>, String> {
+    sort_rows(table, keys, None)
+}
+
+/// Like [`sort_permutation`], comparing text with a locale-aware collator
+pub fn sort_permutation_collated(
+    table: &TesseraTable,
+    keys: &[SortSpec],
+    collator: &TextCollator,
+) -> Result<Vec<usize>, String> {
+    sort_rows(table, keys, Some(collator))
+}
+
+fn sort_rows(
+    table: &TesseraTable,
+    keys: &[SortSpec],
+    collator: Option<&TextCollator>,
+) -> Result<Vec<usize>, String> {
+    let rows = RowOrder::new(table, keys, collator)?;
+    let mut order: Vec<usize> = (0..table.row_count()).collect();
+    order.sort_by(|&a, &b| rows.compare(a, b));
+    Ok(order)
+}
+
+/// Row comparison for a multi-key sort, with the key columns parsed once
+pub(crate) struct RowOrder<'a> {
+    keys: &'a [SortSpec],
+    columns: Vec<KeyColumn<'a>>,
+    collator: Option<&'a TextCollator>,
+}
+
+impl<'a> RowOrder<'a> {
+    pub(crate) fn new(
+        table: &'a TesseraTable,
+        keys: &'a [SortSpec],
+        collator: Option<&'a TextCollator>,
+    ) -> Result<Self, String> {
+        if let Some(key) = keys.iter().find(|k| k.column >= table.column_count()) {
+            return Err(format!("Sort column {} is out of range", key.column));
+        }
+
+        let columns = keys
+            .iter()
+            .map(|key| match key.mode {
+                SortMode::Numeric => KeyColumn::Numeric(
+                    table
+                        .column(key.column)
+                        .map(|cell| cell.trim().parse::<f64>().map_err(|_| cell))
+                        .collect(),
+                ),
+                _ => KeyColumn::Text(table.column(key.column).collect()),
+            })
+            .collect();
+        Ok(RowOrder {
+            keys,
+            columns,
+            collator,
+        })
+    }
+
+    /// Order of rows `a` and `b`; `Equal` when they tie on every key
+    pub(crate) fn compare(&self, a: usize, b: usize) -> Ordering {
+        for (key, column) in self.keys.iter().zip(&self.columns) {
+            let result = match (column.is_blank(a), column.is_blank(b)) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {
+                    let ordering = column.compare(key.mode, self.collator, a, b);
+                    if key.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                }
+            };
+            if result != Ordering::Equal {
+                return result;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// Sort rows by one or more keys
+///
+/// # Arguments
+/// * `table` - Table handle to sort
+/// * `keys_ptr` - Array of sort keys, most significant first
+/// * `keys_count` - Number of keys
+///
+/// # Returns
+/// IndexArray where entry `i` is the original row shown at position `i`
+/// (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `keys_ptr` must point to `keys_count` keys
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sort(
+    table: *const TesseraTable,
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+
+    sort_specs(keys_ptr, keys_count)
+        .and_then(|specs| sort_permutation(table, &specs))
+        .into()
+}
+
+/// Sort rows by one or more keys using locale-aware text collation
+///
+/// # Arguments
+/// * `table` - Table handle to sort
+/// * `keys_ptr` - Array of sort keys, most significant first
+/// * `keys_count` - Number of keys
+/// * `options` - Collation options (null for root order, case-insensitive)
+///
+/// # Returns
+/// IndexArray where entry `i` is the original row shown at position `i`
+/// (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `keys_ptr` must point to `keys_count` keys;
+/// `options` must be null or point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sort_collated(
+    table: *const TesseraTable,
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+    options: *const CollationOptions,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+
+    let collator = match CollationOptions::settings(options).and_then(|s| TextCollator::new(&s)) {
+        Ok(collator) => collator,
+        Err(msg) => return IndexArray::error(&msg),
+    };
+    sort_specs(keys_ptr, keys_count)
+        .and_then(|specs| sort_permutation_collated(table, &specs, &collator))
+        .into()
+}
+
+/// Sort the rows of the table itself, relocating formulas
+///
+/// Single-cell references inside formulas are rewritten to follow the rows
+/// they point at (see `TesseraTable::reorder_rows`).
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `keys_ptr` must point to `keys_count` keys
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sort_in_place(
+    table: *mut TesseraTable,
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+) -> *mut c_char {
+    let Some(table) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    let result = sort_specs(keys_ptr, keys_count)
+        .and_then(|specs| sort_permutation(table, &specs))
+        .and_then(|order| table.reorder_rows(&order));
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// # Safety
+/// `keys_ptr` must be null or point to `keys_count` keys
+pub(crate) unsafe fn sort_specs(
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+) -> Result<Vec<SortSpec>, String> {
+    if keys_count == 0 {
+        return Ok(Vec::new());
+    }
+    if keys_ptr.is_null() {
+        return Err("Null pointer provided".to_string());
+    }
+
+    std::slice::from_raw_parts(keys_ptr, keys_count)
+        .iter()
+        .map(|&key| SortSpec::try_from(key))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[[&str; 2]]) -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Name".into(), "Amount".into()],
+            rows.iter()
+                .map(|r| r.iter().map(|c| c.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("File10", "file10"), Ordering::Equal);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("1", "01"), Ordering::Less);
+
+        let long = "123456789012345678901234";
+        assert_eq!(natural_cmp(long, "99"), Ordering::Greater);
+        assert_eq!(natural_cmp(long, &format!("0{}", long)), Ordering::Less);
+        let mut ids = vec![
+            format!("id{}", long),
+            "id01".to_string(),
+            "id9".to_string(),
+            format!("id0{}", long),
+            "ID1".to_string(),
+        ];
+        ids.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            ids,
+            [
+                "ID1",
+                "id01",
+                "id9",
+                &format!("id{}", long),
+                &format!("id0{}", long)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numeric_descending_keeps_blanks_last() {
+        let t = table(&[
+            ["a", "10"],
+            ["b", ""],
+            ["c", "9"],
+            ["d", "n/a"],
+            ["e", "100"],
+        ]);
+        let keys = [SortSpec {
+            column: 1,
+            descending: true,
+            mode: SortMode::Numeric,
+        }];
+
+        assert_eq!(sort_permutation(&t, &keys).unwrap(), [3, 4, 0, 2, 1]);
+    }
+
+    #[test]
+    fn test_multi_key_is_stable() {
+        let t = table(&[["x10", "1"], ["x2", "2"], ["x10", "0"], ["x2", "2"]]);
+        let keys = [
+            SortSpec {
+                column: 0,
+                descending: false,
+                mode: SortMode::Natural,
+            },
+            SortSpec {
+                column: 1,
+                descending: true,
+                mode: SortMode::Lexicographic,
+            },
+        ];
+
+        assert_eq!(sort_permutation(&t, &keys).unwrap(), [1, 3, 0, 2]);
+        assert!(sort_permutation(
+            &t,
+            &[SortSpec {
+                column: 5,
+                ..keys[0]
+            }]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reorder_relocates_formulas() {
+        let mut t = TesseraTable::from_rows(
+            vec!["Qty".into(), "Price".into(), "Total".into()],
+            vec![
+                vec!["3".into(), "5".into(), "=A1*B1".into()],
+                vec!["1".into(), "7".into(), "=A2*B2 + C1".into()],
+                vec!["2".into(), "4".into(), "=SUM(A1:A3)".into()],
+            ],
+        );
+        let keys = [SortSpec {
+            column: 0,
+            descending: false,
+            mode: SortMode::Numeric,
+        }];
+
+        let order = sort_permutation(&t, &keys).unwrap();
+        t.reorder_rows(&order).unwrap();
+
+        assert_eq!(
+            t.column(2).collect::<Vec<_>>(),
+            ["=A1*B1 + C3", "=SUM(A1:A3)", "=A3*B3"]
+        );
+        assert!(t.reorder_rows(&[0, 0, 1]).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Aggregates and group-bys over memory-mapped tables in one streaming pass
+//!
+//! Rows are parsed a checkpoint block at a time and dropped right after they
+//! are folded in, so memory stays bounded by the number of groups rather than
+//! the size of the file. Sums are added left to right and can differ from the
+//! vectorized in-memory kernels in the last bits.
+
+use std::collections::HashMap;
+
+use super::aggregate::ColumnAggregates;
+use super::group::{Accumulator, Aggregate, AggregateSpec};
+use crate::io::mapped::MappedTable;
+use crate::table::{TableResult, TesseraTable};
+use crate::StringResult;
+
+/// Running count/sum/min/max plus Welford's mean and squared deviations
+#[derive(Debug, Clone, Copy, Default)]
+struct RunningStats {
+    count: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn finish(self) -> ColumnAggregates {
+        ColumnAggregates {
+            count: self.count,
+            sum: self.sum,
+            min: self.min,
+            max: self.max,
+            mean: (self.count > 0).then_some(self.mean),
+            stdev: (self.count > 1).then(|| (self.m2 / (self.count - 1) as f64).sqrt()),
+        }
+    }
+}
+
+fn check_columns(
+    table: &MappedTable,
+    columns: impl IntoIterator<Item = usize>,
+) -> Result<(), String> {
+    for col in columns {
+        if col >= table.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+    }
+    Ok(())
+}
+
+/// Numeric aggregates of a mapped column, same rules as
+/// [`column_aggregates`](super::aggregate::column_aggregates)
+pub fn mapped_aggregates(table: &MappedTable, col: usize) -> Result<ColumnAggregates, String> {
+    check_columns(table, [col])?;
+
+    let mut stats = RunningStats::default();
+    for row in table.iter_rows() {
+        if let Some(value) = row.get(col).and_then(|cell| cell.trim().parse().ok()) {
+            stats.add(value);
+        }
+    }
+    Ok(stats.finish())
+}
+
+/// [`group_by`](super::group::group_by) over a mapped table
+///
+/// Only the group keys and their accumulators are kept in memory.
+pub fn mapped_group_by(
+    table: &MappedTable,
+    group_cols: &[usize],
+    aggregates: &[Aggregate],
+) -> Result<TesseraTable, String> {
+    check_columns(
+        table,
+        group_cols
+            .iter()
+            .copied()
+            .chain(aggregates.iter().map(|a| a.column)),
+    )?;
+
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<String>, Vec<Accumulator>)> = Vec::new();
+    if group_cols.is_empty() {
+        groups.push((Vec::new(), vec![Accumulator::default(); aggregates.len()]));
+    }
+
+    for row in table.iter_rows() {
+        let cell = |col: usize| row.get(col).map_or("", String::as_str);
+        let slot = if group_cols.is_empty() {
+            0
+        } else {
+            let key: Vec<String> = group_cols.iter().map(|&c| cell(c).to_string()).collect();
+            match index.get(&key) {
+                Some(&slot) => slot,
+                None => {
+                    groups.push((key.clone(), vec![Accumulator::default(); aggregates.len()]));
+                    index.insert(key, groups.len() - 1);
+                    groups.len() - 1
+                }
+            }
+        };
+
+        for (acc, aggregate) in groups[slot].1.iter_mut().zip(aggregates) {
+            acc.add(cell(aggregate.column));
+        }
+    }
+
+    let headers = table.headers();
+    let output_headers = group_cols
+        .iter()
+        .map(|&c| headers[c].clone())
+        .chain(
+            aggregates
+                .iter()
+                .map(|a| format!("{}({})", a.function.name(), headers[a.column])),
+        )
+        .collect();
+    let rows = groups
+        .into_iter()
+        .map(|(key, accs)| {
+            key.into_iter()
+                .chain(
+                    accs.iter()
+                        .zip(aggregates)
+                        .map(|(acc, a)| acc.finish(a.function)),
+                )
+                .collect()
+        })
+        .collect();
+
+    Ok(TesseraTable::from_rows(output_headers, rows))
+}
+
+/// Numeric aggregates of a mapped column in one pass over the file
+///
+/// # Returns
+/// StringResult with JSON `{count, sum, min, max, mean, stdev}`, like
+/// tessera_column_aggregates
+///
+/// # Safety
+/// `table` must be null or a live mapped handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_aggregates(
+    table: *const MappedTable,
+    col: usize,
+) -> StringResult {
+    let Some(table) = table.as_ref() else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    mapped_aggregates(table, col)
+        .map(|stats| {
+            serde_json::json!({
+                "count": stats.count,
+                "sum": stats.sum,
+                "min": stats.min,
+                "max": stats.max,
+                "mean": stats.mean,
+                "stdev": stats.stdev,
+            })
+            .to_string()
+        })
+        .into()
+}
+
+/// Group a mapped table and aggregate each group in one pass over the file
+///
+/// # Arguments
+/// * `table` - Mapped table handle
+/// * `group_cols_ptr` - Column indices to group by
+/// * `group_count` - Number of group columns (0 for grand totals)
+/// * `aggregates_ptr` - Aggregates to compute per group
+/// * `aggregate_count` - Number of aggregates
+///
+/// # Returns
+/// TableResult with a new in-memory table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be null or a live mapped handle; the pointers must reference
+/// arrays of the given lengths
+#[no_mangle]
+pub unsafe extern "C" fn tessera_mapped_group_by(
+    table: *const MappedTable,
+    group_cols_ptr: *const usize,
+    group_count: usize,
+    aggregates_ptr: *const AggregateSpec,
+    aggregate_count: usize,
+) -> TableResult {
+    let Some(table) = table.as_ref() else {
+        return TableResult::error("Null pointer provided");
+    };
+    if (group_cols_ptr.is_null() && group_count > 0)
+        || (aggregates_ptr.is_null() && aggregate_count > 0)
+    {
+        return TableResult::error("Null pointer provided");
+    }
+
+    let group_cols = if group_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(group_cols_ptr, group_count)
+    };
+    let specs = if aggregate_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(aggregates_ptr, aggregate_count)
+    };
+
+    specs
+        .iter()
+        .map(|&spec| Aggregate::try_from(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|aggregates| mapped_group_by(table, group_cols, &aggregates))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::aggregate::column_aggregates;
+    use crate::query::group::{group_by, AggregateFunction};
+
+    #[test]
+    fn test_streaming_matches_in_memory() {
+        let mut contents = String::from("region,amount\n");
+        for i in 0..500 {
+            let amount = if i % 7 == 0 {
+                "n/a".to_string()
+            } else {
+                (i % 13).to_string()
+            };
+            contents.push_str(&format!("r{},{}\n", i % 3, amount));
+        }
+        let path = std::env::temp_dir().join(format!("tessera_stream_{}.csv", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let mapped = MappedTable::open(&path, 0).unwrap();
+        let loaded = mapped.to_table(0, mapped.row_count());
+
+        let streamed = mapped_aggregates(&mapped, 1).unwrap();
+        let expected = column_aggregates(&loaded, 1).unwrap();
+        assert_eq!(streamed.count, expected.count);
+        assert_eq!((streamed.min, streamed.max), (expected.min, expected.max));
+        assert!((streamed.sum - expected.sum).abs() < 1e-9);
+        assert!((streamed.stdev.unwrap() - expected.stdev.unwrap()).abs() < 1e-9);
+        assert!(mapped_aggregates(&mapped, 2).is_err());
+
+        let aggregates = [
+            Aggregate {
+                column: 1,
+                function: AggregateFunction::Sum,
+            },
+            Aggregate {
+                column: 1,
+                function: AggregateFunction::Count,
+            },
+        ];
+        assert_eq!(
+            mapped_group_by(&mapped, &[0], &aggregates).unwrap(),
+            group_by(&loaded, &[0], &aggregates).unwrap()
+        );
+
+        drop(mapped);
+        std::fs::remove_file(&path).ok();
+    }
+}
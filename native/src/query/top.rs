@@ -0,0 +1,268 @@
+//! Top-N / bottom-N rows by a numeric column without sorting the table
+//!
+//! A heap bounded to `n` entries keeps the best rows seen so far, so picking
+//! the top 50 of 10M rows is one pass with O(n) memory instead of a full sort.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::table::{table_arg, TesseraTable};
+use crate::IndexArray;
+
+/// Row in the running selection; greater means "should rank higher"
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    /// The value for largest-first, its negation for smallest-first
+    rank: f64,
+    row: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Ties go to the earlier row, like a stable sort
+        self.rank
+            .total_cmp(&other.rank)
+            .then(other.row.cmp(&self.row))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+/// The best `n` candidates pushed so far
+struct Selection {
+    /// Min-heap on rank: the root is the weakest row kept so far
+    heap: BinaryHeap<Reverse<Candidate>>,
+    n: usize,
+}
+
+impl Selection {
+    fn new(n: usize, capacity: usize) -> Self {
+        Selection {
+            heap: BinaryHeap::with_capacity(n.min(capacity) + 1),
+            n,
+        }
+    }
+
+    fn push(&mut self, candidate: Candidate) {
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(candidate));
+        } else if self
+            .heap
+            .peek()
+            .is_some_and(|Reverse(worst)| candidate > *worst)
+        {
+            self.heap.pop();
+            self.heap.push(Reverse(candidate));
+        }
+    }
+
+    /// Rows kept, best first
+    fn into_rows(self) -> impl Iterator<Item = usize> {
+        // Ascending order of Reverse is best-first
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(candidate)| candidate.row)
+    }
+}
+
+/// Numeric cells of `col` as candidates, skipping everything else
+fn candidates(
+    table: &TesseraTable,
+    col: usize,
+    largest: bool,
+) -> impl Iterator<Item = Candidate> + '_ {
+    let numbers = table.numbers(col);
+    (0..numbers.len()).filter_map(move |row| {
+        let value = numbers[row]?;
+        Some(Candidate {
+            rank: if largest { value } else { -value },
+            row,
+        })
+    })
+}
+
+/// Indices of the `n` rows with the largest (or smallest) numbers in `col`,
+/// best first
+///
+/// Non-numeric and empty cells are skipped; equal values keep row order.
+pub fn top_n(
+    table: &TesseraTable,
+    col: usize,
+    n: usize,
+    largest: bool,
+) -> Result<Vec<usize>, String> {
+    if col >= table.column_count() {
+        return Err(format!("Column {} is out of range", col));
+    }
+
+    let mut selection = Selection::new(n, table.row_count());
+    for candidate in candidates(table, col, largest) {
+        selection.push(candidate);
+    }
+    Ok(selection.into_rows().collect())
+}
+
+/// Indices of the `n` rows with the largest (or smallest) numbers in `col`
+/// within each group of rows sharing the values of `group_cols`
+///
+/// Groups come in order of first appearance, each best first; a group
+/// without numbers contributes nothing. Like [`top_n`], each group only
+/// keeps `n` rows in memory.
+pub fn top_n_per_group(
+    table: &TesseraTable,
+    group_cols: &[usize],
+    col: usize,
+    n: usize,
+    largest: bool,
+) -> Result<Vec<usize>, String> {
+    if let Some(col) = group_cols
+        .iter()
+        .chain([&col])
+        .find(|&&col| col >= table.column_count())
+    {
+        return Err(format!("Column {} is out of range", col));
+    }
+
+    let mut index: HashMap<Vec<&str>, usize> = HashMap::new();
+    let mut groups: Vec<Selection> = Vec::new();
+    for candidate in candidates(table, col, largest) {
+        let key: Vec<&str> = group_cols
+            .iter()
+            .map(|&c| table.cell(candidate.row, c))
+            .collect();
+        let group = *index.entry(key).or_insert_with(|| {
+            groups.push(Selection::new(n, 0));
+            groups.len() - 1
+        });
+        groups[group].push(candidate);
+    }
+    Ok(groups.into_iter().flat_map(Selection::into_rows).collect())
+}
+
+/// Rows with the `n` largest or smallest values of a column
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `column` - Numeric column to rank by
+/// * `n` - Maximum number of rows returned
+/// * `largest` - True for top-N, false for bottom-N
+///
+/// # Returns
+/// IndexArray of row indices, best first (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_top_n(
+    table: *const TesseraTable,
+    column: usize,
+    n: usize,
+    largest: bool,
+) -> IndexArray {
+    match table_arg(table) {
+        Some(table) => top_n(table, column, n, largest).into(),
+        None => IndexArray::error("Null pointer provided"),
+    }
+}
+
+/// Rows with the `n` largest or smallest values of a column within each group
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `group_cols_ptr` - Column indices to group by
+/// * `group_count` - Number of group columns
+/// * `column` - Numeric column to rank by
+/// * `n` - Maximum number of rows returned per group
+/// * `largest` - True for top-N, false for bottom-N
+///
+/// # Returns
+/// IndexArray of row indices, group by group in order of first appearance
+/// and best first within a group (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `group_cols_ptr` must point to
+/// `group_count` indices
+#[no_mangle]
+pub unsafe extern "C" fn tessera_top_n_per_group(
+    table: *const TesseraTable,
+    group_cols_ptr: *const usize,
+    group_count: usize,
+    column: usize,
+    n: usize,
+    largest: bool,
+) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    if group_cols_ptr.is_null() && group_count > 0 {
+        return IndexArray::error("Null pointer provided");
+    }
+
+    let group_cols = if group_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(group_cols_ptr, group_count)
+    };
+    top_n_per_group(table, group_cols, column, n, largest).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_and_bottom_n() {
+        let amounts = ["5", "x", "9", "1", "9", "", "3"];
+        let table = TesseraTable::from_rows(
+            vec!["Amount".into()],
+            amounts.iter().map(|a| vec![a.to_string()]).collect(),
+        );
+
+        assert_eq!(top_n(&table, 0, 3, true).unwrap(), [2, 4, 0]);
+        assert_eq!(top_n(&table, 0, 2, false).unwrap(), [3, 6]);
+        assert_eq!(top_n(&table, 0, 10, true).unwrap().len(), 5);
+        assert!(top_n(&table, 0, 0, true).unwrap().is_empty());
+        assert!(top_n(&table, 1, 3, true).is_err());
+    }
+
+    #[test]
+    fn test_top_n_per_group() {
+        let table = TesseraTable::from_rows(
+            vec!["Region".into(), "Product".into(), "Revenue".into()],
+            [
+                ["EU", "Desk", "120"],
+                ["US", "Desk", "300"],
+                ["EU", "Lamp", "80"],
+                ["EU", "Chair", "150"],
+                ["US", "Lamp", "n/a"],
+                ["US", "Chair", "90"],
+                ["EU", "Shelf", "150"],
+                ["APAC", "Desk", ""],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        );
+
+        assert_eq!(
+            top_n_per_group(&table, &[0], 2, 2, true).unwrap(),
+            [3, 6, 1, 5]
+        );
+        assert_eq!(top_n_per_group(&table, &[0], 2, 1, false).unwrap(), [2, 5]);
+        assert_eq!(top_n_per_group(&table, &[], 2, 1, true).unwrap(), [1]);
+        assert!(top_n_per_group(&table, &[3], 2, 1, true).is_err());
+    }
+}
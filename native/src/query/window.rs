@@ -0,0 +1,248 @@
+//! Window functions: per-row values computed within groups of rows
+//!
+//! Like SQL's `OVER (PARTITION BY ... ORDER BY ...)`, rows are split into
+//! partitions by the values of the partition columns, each partition is
+//! walked in the order of the sort keys, and every row gets one value. The
+//! result is aligned with the table's own row order, ready to show as a
+//! computed column.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::sort::{sort_specs, RowOrder, SortKey, SortSpec};
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// Position in the partition, from 1
+    RowNumber,
+    /// Position of the first row with the same sort key (1, 2, 2, 4)
+    Rank,
+    /// Like `Rank` without gaps (1, 2, 2, 3)
+    DenseRank,
+    /// Total of the value column's numbers so far
+    RunningSum,
+    /// Mean of the value column's numbers over the current row and the rows
+    /// before it, this many rows in all
+    MovingAverage(usize),
+}
+
+impl WindowFunction {
+    pub(crate) fn from_raw(raw: u32, size: usize) -> Option<Self> {
+        match raw {
+            0 => Some(WindowFunction::RowNumber),
+            1 => Some(WindowFunction::Rank),
+            2 => Some(WindowFunction::DenseRank),
+            3 => Some(WindowFunction::RunningSum),
+            4 => Some(WindowFunction::MovingAverage(size)),
+            _ => None,
+        }
+    }
+
+    fn uses_values(self) -> bool {
+        matches!(
+            self,
+            WindowFunction::RunningSum | WindowFunction::MovingAverage(_)
+        )
+    }
+}
+
+/// Running state of one partition
+#[derive(Debug, Default)]
+struct Partition {
+    rows: usize,
+    rank: usize,
+    dense: usize,
+    last_row: Option<usize>,
+    sum: f64,
+    recent: VecDeque<Option<f64>>,
+}
+
+/// Value of `function` for every row, in row order
+///
+/// Without sort keys a partition is walked in row order, and every row of it
+/// ties for rank 1. Empty results (a moving average over no numbers) are
+/// empty strings.
+pub fn window(
+    table: &TesseraTable,
+    partition_cols: &[usize],
+    order: &[SortSpec],
+    value_col: usize,
+    function: WindowFunction,
+) -> Result<Vec<String>, String> {
+    if let Some(col) = partition_cols
+        .iter()
+        .chain(function.uses_values().then_some(&value_col))
+        .find(|&&col| col >= table.column_count())
+    {
+        return Err(format!("Column {} is out of range", col));
+    }
+    if function == WindowFunction::MovingAverage(0) {
+        return Err("Moving average window must hold at least one row".to_string());
+    }
+
+    let numbers = function.uses_values().then(|| table.numbers(value_col));
+    let mut partitions: HashMap<Vec<&str>, Partition> = HashMap::new();
+    let mut results = vec![String::new(); table.row_count()];
+    // Ties are found with the sort's own comparison, so rows it keeps
+    // together (`eu` and `EU` in natural order) share a rank
+    let row_order = RowOrder::new(table, order, None)?;
+    let mut rows: Vec<usize> = (0..table.row_count()).collect();
+    rows.sort_by(|&a, &b| row_order.compare(a, b));
+    for row in rows {
+        let key: Vec<&str> = partition_cols.iter().map(|&c| table.cell(row, c)).collect();
+        let partition = partitions.entry(key).or_default();
+        partition.rows += 1;
+        let tie = partition
+            .last_row
+            .is_some_and(|last| row_order.compare(last, row).is_eq());
+        if !tie {
+            partition.rank = partition.rows;
+            partition.dense += 1;
+        }
+        partition.last_row = Some(row);
+
+        let number = numbers.as_ref().and_then(|numbers| numbers[row]);
+        results[row] = match function {
+            WindowFunction::RowNumber => partition.rows.to_string(),
+            WindowFunction::Rank => partition.rank.to_string(),
+            WindowFunction::DenseRank => partition.dense.to_string(),
+            WindowFunction::RunningSum => {
+                partition.sum += number.unwrap_or(0.0);
+                format_number(partition.sum)
+            }
+            WindowFunction::MovingAverage(size) => {
+                partition.recent.push_back(number);
+                if partition.recent.len() > size {
+                    partition.recent.pop_front();
+                }
+                let window: Vec<f64> = partition.recent.iter().flatten().copied().collect();
+                match window.is_empty() {
+                    true => String::new(),
+                    false => format_number(window.iter().sum::<f64>() / window.len() as f64),
+                }
+            }
+        };
+    }
+    Ok(results)
+}
+
+/// Compute a window function over partitions of the rows
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `partition_ptr` - Column indices to partition by (none for one partition)
+/// * `partition_count` - Number of partition columns
+/// * `keys_ptr` - Sort keys ordering each partition, most significant first
+/// * `keys_count` - Number of sort keys (0 for row order)
+/// * `value_col` - Column summed or averaged (ignored by the ranking functions)
+/// * `function` - 0 = row number, 1 = rank, 2 = dense rank, 3 = running sum,
+///   4 = moving average
+/// * `size` - Rows in the moving average window, counting the current row
+///
+/// # Returns
+/// StringResult with a JSON array holding one value per row, in row order
+///
+/// # Safety
+/// `table` must be a live table handle; `partition_ptr` must point to
+/// `partition_count` indices and `keys_ptr` to `keys_count` keys
+#[no_mangle]
+pub unsafe extern "C" fn tessera_window(
+    table: *const TesseraTable,
+    partition_ptr: *const usize,
+    partition_count: usize,
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+    value_col: usize,
+    function: u32,
+    size: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if partition_ptr.is_null() && partition_count > 0 {
+        return StringResult::error("Null pointer provided");
+    }
+    let Some(function) = WindowFunction::from_raw(function, size) else {
+        return StringResult::error(&format!("Unknown window function {}", function));
+    };
+
+    let partition_cols = if partition_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(partition_ptr, partition_count)
+    };
+    sort_specs(keys_ptr, keys_count)
+        .and_then(|order| window(table, partition_cols, &order, value_col, function))
+        .map(|values| serde_json::json!(values).to_string())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::sort::SortMode;
+
+    #[test]
+    fn test_window_functions() {
+        let table = TesseraTable::from_rows(
+            vec!["Region".into(), "Sales".into()],
+            [
+                ["EU", "30"],
+                ["US", "10"],
+                ["EU", "50"],
+                ["US", ""],
+                ["EU", "30"],
+                ["US", "40"],
+                ["EU", "20"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        );
+        let by_sales = [SortSpec {
+            column: 1,
+            descending: true,
+            mode: SortMode::Numeric,
+        }];
+        let run = |order: &[SortSpec], function| window(&table, &[0], order, 1, function).unwrap();
+
+        assert_eq!(
+            run(&[], WindowFunction::RowNumber),
+            ["1", "1", "2", "2", "3", "3", "4"]
+        );
+        assert_eq!(
+            run(&by_sales, WindowFunction::Rank),
+            ["2", "2", "1", "3", "2", "1", "4"]
+        );
+        assert_eq!(
+            run(&by_sales, WindowFunction::DenseRank),
+            ["2", "2", "1", "3", "2", "1", "3"]
+        );
+        assert_eq!(
+            run(&[], WindowFunction::RunningSum),
+            ["30", "10", "80", "10", "110", "50", "130"]
+        );
+        assert_eq!(
+            run(&[], WindowFunction::MovingAverage(2)),
+            ["30", "10", "40", "10", "40", "40", "25"]
+        );
+
+        let regions = TesseraTable::from_rows(
+            vec!["Region".into()],
+            vec![vec!["fr".into()], vec!["EU".into()], vec!["eu".into()]],
+        );
+        let by_region = [SortSpec {
+            column: 0,
+            descending: false,
+            mode: SortMode::Natural,
+        }];
+        let rank = |function| window(&regions, &[], &by_region, 0, function).unwrap();
+        assert_eq!(rank(WindowFunction::Rank), ["3", "1", "1"]);
+        assert_eq!(rank(WindowFunction::DenseRank), ["2", "1", "1"]);
+
+        assert!(window(&table, &[2], &[], 1, WindowFunction::RowNumber).is_err());
+        assert!(window(&table, &[0], &[], 1, WindowFunction::MovingAverage(0)).is_err());
+    }
+}
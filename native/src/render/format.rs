@@ -0,0 +1,1077 @@
+//! Spreadsheet number format codes (`#,##0.00;[Red](#,##0.00)`, `yyyy-mm-dd`)
+//!
+//! A code has up to four `;`-separated sections: positive, negative, zero and
+//! text. Sections may carry a `[Color]` and a `[>=100]` condition. Supported
+//! tokens are the digit placeholders `0 # ?`, `.`, thousands and scaling
+//! commas, `%`, `E+00`, `@`, `General`, quoted or escaped literals, `_x`
+//! padding and the date/time codes (`yyyy mmm d hh:mm:ss.0 AM/PM [h]`).
+//! Fill repeats (`*x`) are ignored since cells are padded by the grid.
+
+use std::os::raw::c_char;
+
+use chrono::{Datelike, Timelike};
+
+use crate::datetime::{from_serial, parse_datetime, to_serial};
+use crate::ffi::{error_string, str_arg, str_array_arg, to_c_string};
+use crate::formula::value::format_number;
+use crate::query::profile::parse_number;
+use crate::table::{table_arg, table_arg_mut, CellRange, TesseraTable};
+use crate::StringResult;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DatePart {
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    /// Fraction of a second with this many digits, at most three (`ss.00`)
+    Fraction(usize),
+    ElapsedHours(usize),
+    ElapsedMinutes(usize),
+    ElapsedSeconds(usize),
+    /// `AM/PM` (true) or `A/P` (false), keeping the code's case
+    AmPm(bool, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    /// Digit placeholder: `0` pads with zeros, `?` with spaces, `#` not at all
+    Digit(char),
+    Point,
+    Comma,
+    Percent,
+    Exponent {
+        plus: bool,
+        digits: usize,
+    },
+    Text,
+    General,
+    Date(DatePart),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Condition {
+    op: &'static str,
+    value: f64,
+}
+
+impl Condition {
+    fn matches(&self, value: f64) -> bool {
+        match self.op {
+            "<" => value < self.value,
+            "<=" => value <= self.value,
+            ">" => value > self.value,
+            ">=" => value >= self.value,
+            "<>" => value != self.value,
+            _ => value == self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Section {
+    parts: Vec<Part>,
+    color: Option<String>,
+    condition: Option<Condition>,
+    thousands: bool,
+    /// Number of scaling commas (each divides by 1000)
+    scale: i32,
+}
+
+impl Section {
+    fn is_date(&self) -> bool {
+        self.parts.iter().any(|p| matches!(p, Part::Date(_)))
+    }
+
+    fn has_text(&self) -> bool {
+        self.parts.contains(&Part::Text)
+    }
+
+    fn shows_number(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| matches!(p, Part::Digit(_) | Part::General | Part::Date(_)))
+    }
+}
+
+/// Formatted display text plus the section color (lowercase, e.g. `red`)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Formatted {
+    pub text: String,
+    pub color: Option<String>,
+}
+
+impl Formatted {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "text": self.text, "color": self.color })
+    }
+}
+
+/// A parsed format code
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    code: String,
+    sections: Vec<Section>,
+    text: Option<Section>,
+}
+
+fn parse_bracket(content: &str, section: &mut Section) -> Result<(), String> {
+    let lower = content.to_ascii_lowercase();
+    let elapsed = |c: char| !lower.is_empty() && lower.chars().all(|x| x == c);
+
+    if elapsed('h') {
+        section
+            .parts
+            .push(Part::Date(DatePart::ElapsedHours(lower.len())));
+    } else if elapsed('m') {
+        section
+            .parts
+            .push(Part::Date(DatePart::ElapsedMinutes(lower.len())));
+    } else if elapsed('s') {
+        section
+            .parts
+            .push(Part::Date(DatePart::ElapsedSeconds(lower.len())));
+    } else if let Some(currency) = content.strip_prefix('$') {
+        // Locale tag such as [$€-407]: keep the symbol only
+        let symbol = currency.split('-').next().unwrap_or_default();
+        section.parts.push(Part::Literal(symbol.to_string()));
+    } else if let Some(op) = ["<=", ">=", "<>", "<", ">", "="]
+        .into_iter()
+        .find(|op| content.starts_with(op))
+    {
+        let value = content[op.len()..]
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid format condition [{}]", content))?;
+        section.condition = Some(Condition { op, value });
+    } else if content.chars().all(|c| c.is_ascii_alphanumeric()) && !content.is_empty() {
+        section.color = Some(lower);
+    } else {
+        return Err(format!("Invalid format code [{}]", content));
+    }
+    Ok(())
+}
+
+/// Resolve commas into thousands separators, scaling or literals
+fn resolve_commas(section: &mut Section) {
+    let int_end = section
+        .parts
+        .iter()
+        .position(|p| matches!(p, Part::Point | Part::Exponent { .. }))
+        .unwrap_or(section.parts.len());
+    let mut parts = Vec::with_capacity(section.parts.len());
+
+    for (i, part) in section.parts.iter().enumerate() {
+        if *part != Part::Comma {
+            parts.push(part.clone());
+            continue;
+        }
+        let digit_before = section.parts[..i]
+            .iter()
+            .any(|p| matches!(p, Part::Digit(_)));
+        let digit_after = i < int_end
+            && section.parts[i + 1..int_end]
+                .iter()
+                .any(|p| matches!(p, Part::Digit(_)));
+        let scales = matches!(parts.last(), Some(Part::Digit(_)))
+            || (section.scale > 0 && section.parts[i - 1] == Part::Comma);
+
+        if digit_before && digit_after {
+            section.thousands = true;
+        } else if scales {
+            section.scale += 1;
+        } else {
+            parts.push(Part::Literal(",".to_string()));
+        }
+    }
+    section.parts = parts;
+}
+
+/// Turn `m`/`mm` into minutes next to hours or seconds, and `.0` after
+/// seconds into fractions
+fn resolve_dates(section: &mut Section) {
+    if !section.is_date() {
+        return;
+    }
+
+    let dates: Vec<(usize, DatePart)> = section
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| match p {
+            Part::Date(d) => Some((i, *d)),
+            _ => None,
+        })
+        .collect();
+    for (k, &(i, part)) in dates.iter().enumerate() {
+        let DatePart::Month(n) = part else { continue };
+        let after_hour = k > 0
+            && matches!(
+                dates[k - 1].1,
+                DatePart::Hour(_) | DatePart::ElapsedHours(_)
+            );
+        let before_second = dates
+            .get(k + 1)
+            .is_some_and(|(_, d)| matches!(d, DatePart::Second(_) | DatePart::ElapsedSeconds(_)));
+        if n <= 2 && (after_hour || before_second) {
+            section.parts[i] = Part::Date(DatePart::Minute(n));
+        }
+    }
+
+    let mut parts: Vec<Part> = Vec::with_capacity(section.parts.len());
+    for part in std::mem::take(&mut section.parts) {
+        match (parts.last_mut(), &part) {
+            (Some(Part::Date(DatePart::Fraction(n))), Part::Digit('0')) => *n += 1,
+            (Some(Part::Date(DatePart::Second(_) | DatePart::ElapsedSeconds(_))), Part::Point) => {
+                parts.push(Part::Date(DatePart::Fraction(0)))
+            }
+            _ => parts.push(part),
+        }
+    }
+    section.parts = parts;
+}
+
+fn parse_sections(code: &str) -> Result<Vec<Section>, String> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut sections = Vec::new();
+    let mut section = Section::default();
+    let mut i = 0;
+
+    let literal = |section: &mut Section, text: &str| match section.parts.last_mut() {
+        Some(Part::Literal(prev)) => prev.push_str(text),
+        _ => section.parts.push(Part::Literal(text.to_string())),
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().take(7).collect();
+        let lower = rest.to_ascii_lowercase();
+        i += 1;
+
+        match c {
+            ';' => sections.push(std::mem::take(&mut section)),
+            '"' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&x| x == '"')
+                    .ok_or("Unterminated quote in number format")?;
+                let text: String = chars[i..i + end].iter().collect();
+                literal(&mut section, &text);
+                i += end + 1;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&x| x == ']')
+                    .ok_or("Unterminated bracket in number format")?;
+                let content: String = chars[i..i + end].iter().collect();
+                parse_bracket(&content, &mut section)?;
+                i += end + 1;
+            }
+            '\\' | '_' | '*' => {
+                let next = chars.get(i).copied().unwrap_or_default();
+                match c {
+                    '\\' => literal(&mut section, &next.to_string()),
+                    '_' => literal(&mut section, " "),
+                    _ => {}
+                }
+                i += 1;
+            }
+            '0' | '#' | '?' => section.parts.push(Part::Digit(c)),
+            '.' => section.parts.push(Part::Point),
+            ',' => section.parts.push(Part::Comma),
+            '%' => section.parts.push(Part::Percent),
+            '@' => section.parts.push(Part::Text),
+            'E' | 'e' if matches!(chars.get(i), Some('+' | '-')) => {
+                let plus = chars[i] == '+';
+                i += 1;
+                let digits = chars[i..]
+                    .iter()
+                    .take_while(|&&x| matches!(x, '0' | '#' | '?'))
+                    .count();
+                i += digits;
+                section.parts.push(Part::Exponent {
+                    plus,
+                    digits: digits.max(1),
+                });
+            }
+            _ if lower.starts_with("general") => {
+                section.parts.push(Part::General);
+                i += 6;
+            }
+            _ if lower.starts_with("am/pm") => {
+                section
+                    .parts
+                    .push(Part::Date(DatePart::AmPm(true, c == 'A')));
+                i += 4;
+            }
+            _ if lower.starts_with("a/p") => {
+                section
+                    .parts
+                    .push(Part::Date(DatePart::AmPm(false, c == 'A')));
+                i += 2;
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => {
+                let run = 1 + chars[i..]
+                    .iter()
+                    .take_while(|x| x.eq_ignore_ascii_case(&c))
+                    .count();
+                i += run - 1;
+                section.parts.push(Part::Date(match c.to_ascii_lowercase() {
+                    'y' => DatePart::Year(if run <= 2 { 2 } else { 4 }),
+                    'm' => DatePart::Month(run.min(5)),
+                    'd' => DatePart::Day(run.min(4)),
+                    'h' => DatePart::Hour(run.min(2)),
+                    _ => DatePart::Second(run.min(2)),
+                }));
+            }
+            _ => literal(&mut section, &c.to_string()),
+        }
+    }
+    sections.push(section);
+
+    if sections.len() > 4 {
+        return Err("Number format has more than four sections".to_string());
+    }
+    for section in &mut sections {
+        resolve_dates(section);
+        if !section.is_date() {
+            resolve_commas(section);
+        }
+        let too_precise = section
+            .parts
+            .iter()
+            .any(|p| matches!(p, Part::Date(DatePart::Fraction(n)) if *n > 3));
+        if too_precise {
+            return Err("Fractions of a second have at most three digits".to_string());
+        }
+    }
+    Ok(sections)
+}
+
+/// Render the digits of `value` (already non-negative) into a number section
+fn render_number(section: &Section, value: f64) -> String {
+    let mut value = value
+        * 100f64.powi(
+            section
+                .parts
+                .iter()
+                .filter(|p| **p == Part::Percent)
+                .count() as i32,
+        )
+        / 1000f64.powi(section.scale);
+
+    let point = section
+        .parts
+        .iter()
+        .position(|p| matches!(p, Part::Point | Part::Exponent { .. }))
+        .unwrap_or(section.parts.len());
+    let is_digit = |p: &&Part| matches!(p, Part::Digit(_));
+    let int_slots = section.parts[..point].iter().filter(is_digit).count();
+    let frac_end = section.parts[point..]
+        .iter()
+        .position(|p| matches!(p, Part::Exponent { .. }))
+        .map_or(section.parts.len(), |i| point + i);
+    let frac_slots = section.parts[point..frac_end]
+        .iter()
+        .filter(is_digit)
+        .count();
+
+    let mut exponent = 0;
+    if section
+        .parts
+        .iter()
+        .any(|p| matches!(p, Part::Exponent { .. }))
+        && value != 0.0
+    {
+        exponent = value.log10().floor() as i32;
+        if int_slots > 1 {
+            // Engineering style: exponent is a multiple of the integer width
+            exponent -= exponent.rem_euclid(int_slots as i32);
+        }
+        value /= 10f64.powi(exponent);
+        if format!("{:.*}", frac_slots, value).starts_with("10") && int_slots <= 1 {
+            value /= 10.0;
+            exponent += 1;
+        }
+    }
+
+    // Round half away from zero like spreadsheets (format! rounds half to even)
+    let factor = 10f64.powi(frac_slots as i32);
+    let rounded = format!("{:.*}", frac_slots, (value * factor).round() / factor);
+    let (int_text, frac_text) = rounded.split_once('.').unwrap_or((&rounded, ""));
+    let digits: Vec<char> = if int_text == "0" {
+        Vec::new()
+    } else {
+        int_text.chars().collect()
+    };
+    let frac: Vec<char> = frac_text.chars().collect();
+    let frac_used = frac.iter().rposition(|&d| d != '0').map_or(0, |i| i + 1);
+
+    let mut out = String::new();
+    let mut int_slot = 0;
+    let mut frac_slot = 0;
+    let mut in_fraction = false;
+    let group = |out: &mut String, position: usize, ch: char| {
+        out.push(ch);
+        if section.thousands && position > 0 && position.is_multiple_of(3) && ch.is_ascii_digit() {
+            out.push(',');
+        }
+    };
+
+    for part in &section.parts {
+        match part {
+            Part::Digit(placeholder) if !in_fraction => {
+                let position = int_slots - 1 - int_slot;
+                let top = if int_slot == 0 {
+                    digits.len().max(position + 1)
+                } else {
+                    position + 1
+                };
+                for q in (position..top).rev() {
+                    let ch = if q < digits.len() {
+                        Some(digits[digits.len() - 1 - q])
+                    } else if q == position {
+                        match placeholder {
+                            '0' => Some('0'),
+                            '?' => Some(' '),
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    };
+                    if let Some(ch) = ch {
+                        group(&mut out, q, ch);
+                    }
+                }
+                int_slot += 1;
+            }
+            Part::Digit(placeholder) => {
+                if frac_slot < frac_used {
+                    out.push(frac[frac_slot]);
+                } else {
+                    match placeholder {
+                        '0' => out.push(frac.get(frac_slot).copied().unwrap_or('0')),
+                        '?' => out.push(' '),
+                        _ => {}
+                    }
+                }
+                frac_slot += 1;
+            }
+            Part::Point => {
+                if int_slots == 0 {
+                    for (q, &ch) in digits.iter().rev().enumerate().rev() {
+                        group(&mut out, q, ch);
+                    }
+                }
+                out.push('.');
+                in_fraction = true;
+            }
+            Part::Exponent { plus, digits } => {
+                out.push('E');
+                if exponent < 0 {
+                    out.push('-');
+                } else if *plus {
+                    out.push('+');
+                }
+                out.push_str(&format!("{:0width$}", exponent.abs(), width = *digits));
+                in_fraction = true;
+            }
+            Part::Percent => out.push('%'),
+            Part::Literal(text) => out.push_str(text),
+            Part::General | Part::Text => out.push_str(&format_number(value)),
+            Part::Comma | Part::Date(_) => {}
+        }
+    }
+    out
+}
+
+/// Render a spreadsheet serial number through a date/time section
+fn render_date(section: &Section, serial: f64) -> String {
+    let fraction_digits = section
+        .parts
+        .iter()
+        .find_map(|p| match p {
+            Part::Date(DatePart::Fraction(n)) => Some(*n as i32),
+            _ => None,
+        })
+        .unwrap_or(0);
+    // Round to the displayed precision so 10:59:59.6 shows as 11:00:00
+    let unit = 86_400.0 * 10f64.powi(fraction_digits);
+    let serial = (serial * unit).round() / unit;
+    let Some(dt) = from_serial(serial) else {
+        return "#####".to_string();
+    };
+    let twelve_hour = section
+        .parts
+        .iter()
+        .any(|p| matches!(p, Part::Date(DatePart::AmPm(..))));
+
+    let mut out = String::new();
+    for part in &section.parts {
+        let date = match part {
+            Part::Date(date) => date,
+            Part::Literal(text) => {
+                out.push_str(text);
+                continue;
+            }
+            Part::Point => {
+                out.push('.');
+                continue;
+            }
+            Part::Comma => {
+                out.push(',');
+                continue;
+            }
+            _ => continue,
+        };
+        let text = match *date {
+            DatePart::Year(2) => format!("{:02}", dt.year().rem_euclid(100)),
+            DatePart::Year(_) => format!("{:04}", dt.year()),
+            DatePart::Month(1) => dt.month().to_string(),
+            DatePart::Month(2) => format!("{:02}", dt.month()),
+            DatePart::Month(n) => {
+                let name = MONTHS[dt.month0() as usize];
+                match n {
+                    3 => name[..3].to_string(),
+                    4 => name.to_string(),
+                    _ => name[..1].to_string(),
+                }
+            }
+            DatePart::Day(1) => dt.day().to_string(),
+            DatePart::Day(2) => format!("{:02}", dt.day()),
+            DatePart::Day(n) => {
+                let name = WEEKDAYS[dt.weekday().num_days_from_monday() as usize];
+                if n == 3 {
+                    name[..3].to_string()
+                } else {
+                    name.to_string()
+                }
+            }
+            DatePart::Hour(n) => {
+                let hour = if twelve_hour {
+                    dt.hour12().1
+                } else {
+                    dt.hour()
+                };
+                format!("{:0width$}", hour, width = n)
+            }
+            DatePart::Minute(n) => format!("{:0width$}", dt.minute(), width = n),
+            DatePart::Second(n) => format!("{:0width$}", dt.second(), width = n),
+            DatePart::Fraction(n) => {
+                let millis = dt.nanosecond() / 1_000_000;
+                format!(".{:03}", millis)[..n + 1].to_string()
+            }
+            DatePart::ElapsedHours(n) => {
+                format!("{:0width$}", (serial * 24.0).floor() as i64, width = n)
+            }
+            DatePart::ElapsedMinutes(n) => {
+                format!("{:0width$}", (serial * 1440.0).floor() as i64, width = n)
+            }
+            DatePart::ElapsedSeconds(n) => {
+                format!("{:0width$}", (serial * 86_400.0).floor() as i64, width = n)
+            }
+            DatePart::AmPm(full, upper) => {
+                let pm = dt.hour() >= 12;
+                let text = match (full, pm) {
+                    (true, false) => "AM",
+                    (true, true) => "PM",
+                    (false, false) => "A",
+                    (false, true) => "P",
+                };
+                if upper {
+                    text.to_string()
+                } else {
+                    text.to_lowercase()
+                }
+            }
+        };
+        out.push_str(&text);
+    }
+    out
+}
+
+impl NumberFormat {
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let mut sections = parse_sections(code)?;
+        let text = if sections.len() == 4
+            || (sections.len() > 1 && sections[sections.len() - 1].has_text())
+        {
+            sections.pop()
+        } else if sections[0].has_text() && !sections[0].shows_number() {
+            Some(sections[0].clone())
+        } else {
+            None
+        };
+        Ok(NumberFormat {
+            code: code.to_string(),
+            sections,
+            text,
+        })
+    }
+
+    /// The code this format was parsed from
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// `chrono` pattern that reads text written in this date format back,
+    /// e.g. `%d/%m/%Y` for `dd/mm/yyyy`; `None` for number formats and for
+    /// codes that cannot be read back (elapsed time, `mmmmm`, `A/P`)
+    pub fn date_pattern(&self) -> Option<String> {
+        let section = &self.sections[0];
+        if !section.is_date() {
+            return None;
+        }
+        let twelve_hour = section
+            .parts
+            .iter()
+            .any(|p| matches!(p, Part::Date(DatePart::AmPm(..))));
+        let mut pattern = String::new();
+        for part in &section.parts {
+            let code = match part {
+                Part::Literal(text) => {
+                    pattern.push_str(&text.replace('%', "%%"));
+                    continue;
+                }
+                Part::Point => ".",
+                Part::Comma => ",",
+                Part::Date(DatePart::Year(2)) => "%y",
+                Part::Date(DatePart::Year(_)) => "%Y",
+                Part::Date(DatePart::Month(1 | 2)) => "%m",
+                Part::Date(DatePart::Month(3)) => "%b",
+                Part::Date(DatePart::Month(4)) => "%B",
+                Part::Date(DatePart::Day(1 | 2)) => "%d",
+                Part::Date(DatePart::Day(3)) => "%a",
+                Part::Date(DatePart::Day(4)) => "%A",
+                Part::Date(DatePart::Hour(_)) if twelve_hour => "%I",
+                Part::Date(DatePart::Hour(_)) => "%H",
+                Part::Date(DatePart::Minute(_)) => "%M",
+                Part::Date(DatePart::Second(_)) => "%S",
+                Part::Date(DatePart::Fraction(_)) => "%.f",
+                Part::Date(DatePart::AmPm(true, _)) => "%p",
+                _ => return None,
+            };
+            pattern.push_str(code);
+        }
+        Some(pattern)
+    }
+
+    /// Pick the section for `value` and whether it still needs a minus sign
+    fn section_for(&self, value: f64) -> (&Section, bool) {
+        let sections = &self.sections;
+        if sections.iter().any(|s| s.condition.is_some()) {
+            let section = sections
+                .iter()
+                .find(|s| s.condition.is_some_and(|c| c.matches(value)))
+                .or_else(|| sections.iter().find(|s| s.condition.is_none()))
+                .unwrap_or(&sections[0]);
+            return (section, value < 0.0);
+        }
+
+        match sections.len() {
+            1 => (&sections[0], value < 0.0),
+            2 if value < 0.0 => (&sections[1], false),
+            2 => (&sections[0], false),
+            _ if value < 0.0 => (&sections[1], false),
+            _ if value == 0.0 => (&sections[2], false),
+            _ => (&sections[0], false),
+        }
+    }
+
+    /// Format a numeric value (or a date/time serial)
+    pub fn format_value(&self, value: f64) -> Formatted {
+        let (section, sign) = self.section_for(value);
+        let text = if section.is_date() {
+            if sign {
+                "#####".to_string()
+            } else {
+                render_date(section, value)
+            }
+        } else {
+            let body = render_number(section, value.abs());
+            if sign
+                && section.shows_number()
+                && body.chars().any(|c| c.is_ascii_digit() && c != '0')
+            {
+                format!("-{}", body)
+            } else {
+                body
+            }
+        };
+        Formatted {
+            text,
+            color: section.color.clone(),
+        }
+    }
+
+    /// Format cell text: numbers (and dates, for date formats) are formatted,
+    /// other text goes through the text section or is shown unchanged
+    pub fn format_cell(&self, cell: &str) -> Formatted {
+        if cell.trim().is_empty() {
+            return Formatted::default();
+        }
+        if let Some(value) = parse_number(cell) {
+            return self.format_value(value);
+        }
+        if self.sections[0].is_date() {
+            if let Some(dt) = parse_datetime(cell) {
+                return self.format_value(to_serial(dt));
+            }
+        }
+
+        match &self.text {
+            Some(section) => {
+                let text = section
+                    .parts
+                    .iter()
+                    .map(|p| match p {
+                        Part::Text => cell,
+                        Part::Literal(text) => text,
+                        _ => "",
+                    })
+                    .collect();
+                Formatted {
+                    text,
+                    color: section.color.clone(),
+                }
+            }
+            None => Formatted {
+                text: cell.to_string(),
+                color: None,
+            },
+        }
+    }
+}
+
+/// Display text of a cell after applying its stored number format
+pub fn display_cell(table: &TesseraTable, row: usize, col: usize) -> Formatted {
+    let cell = table.cell(row, col);
+    match table.number_format(row, col) {
+        Some(format) => format.format_cell(cell),
+        None => Formatted {
+            text: cell.to_string(),
+            color: None,
+        },
+    }
+}
+
+/// Formatted rows of a block of cells (clamped to the table)
+pub fn render_range(table: &TesseraTable, range: CellRange) -> Vec<Vec<Formatted>> {
+    let (rows, cols) = range.clamp(table);
+    rows.map(|row| {
+        cols.clone()
+            .map(|col| display_cell(table, row, col))
+            .collect()
+    })
+    .collect()
+}
+
+/// FFI-safe result for [`tessera_format_number`]
+#[repr(C)]
+pub struct FormattedResult {
+    pub value: *mut c_char, // free with tessera_free_string
+    pub color: *mut c_char, // section color such as "red", null if none
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl From<Result<Formatted, String>> for FormattedResult {
+    fn from(result: Result<Formatted, String>) -> Self {
+        match result {
+            Ok(formatted) => FormattedResult {
+                value: to_c_string(&formatted.text),
+                color: formatted
+                    .color
+                    .as_deref()
+                    .map_or(std::ptr::null_mut(), to_c_string),
+                error: std::ptr::null_mut(),
+            },
+            Err(msg) => FormattedResult {
+                value: std::ptr::null_mut(),
+                color: std::ptr::null_mut(),
+                error: error_string(&msg),
+            },
+        }
+    }
+}
+
+fn formatted_json(values: impl Iterator<Item = Formatted>) -> String {
+    serde_json::Value::from(values.map(|f| f.to_json()).collect::<Vec<_>>()).to_string()
+}
+
+/// Format one number with a format code
+///
+/// # Arguments
+/// * `value` - Number or date/time serial
+/// * `code` - Format code, e.g. `#,##0.00;[Red](#,##0.00)`
+///
+/// # Safety
+/// `code` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_format_number(value: f64, code: *const c_char) -> FormattedResult {
+    let Some(code) = str_arg(code) else {
+        return FormattedResult::from(Err("Invalid format code encoding".to_string()));
+    };
+
+    NumberFormat::parse(code)
+        .map(|format| format.format_value(value))
+        .into()
+}
+
+/// Format an array of cell strings with one format code
+///
+/// # Returns
+/// StringResult with a JSON array of `{text, color}` objects
+///
+/// # Safety
+/// `values_ptr` must point to `count` valid C strings; `code` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_format_values(
+    values_ptr: *const *const c_char,
+    count: usize,
+    code: *const c_char,
+) -> StringResult {
+    let (Some(values), Some(code)) = (str_array_arg(values_ptr, count), str_arg(code)) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    NumberFormat::parse(code)
+        .map(|format| formatted_json(values.iter().map(|v| format.format_cell(v))))
+        .into()
+}
+
+/// Format every cell of a column with one format code
+///
+/// # Returns
+/// StringResult with a JSON array of `{text, color}` objects, one per row
+///
+/// # Safety
+/// `table` must be a live table handle; `code` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_format_column(
+    table: *const TesseraTable,
+    col: usize,
+    code: *const c_char,
+) -> StringResult {
+    let (Some(table), Some(code)) = (table_arg(table), str_arg(code)) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if col >= table.column_count() {
+        return StringResult::error(&format!("Column {} is out of range", col));
+    }
+
+    NumberFormat::parse(code)
+        .map(|format| formatted_json(table.column(col).map(|v| format.format_cell(v))))
+        .into()
+}
+
+/// Parse an optional format code (null or empty clears the format)
+///
+/// # Safety
+/// `code` must be null or a valid C string
+unsafe fn format_arg(code: *const c_char) -> Result<Option<NumberFormat>, String> {
+    if code.is_null() {
+        return Ok(None);
+    }
+    match str_arg(code) {
+        Some("") => Ok(None),
+        Some(code) => NumberFormat::parse(code).map(Some),
+        None => Err("Invalid format code encoding".to_string()),
+    }
+}
+
+/// Store a number format for a column (null or empty `code` clears it)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `code` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_column_format(
+    table: *mut TesseraTable,
+    col: usize,
+    code: *const c_char,
+) -> *mut c_char {
+    let Some(table) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match format_arg(code).and_then(|format| table.set_column_format(col, format)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Store a number format for one cell, overriding the column format
+/// (null or empty `code` clears it)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `code` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_cell_format(
+    table: *mut TesseraTable,
+    row: usize,
+    col: usize,
+    code: *const c_char,
+) -> *mut c_char {
+    let Some(table) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match format_arg(code).and_then(|format| table.set_cell_format(row, col, format)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Display strings for a viewport, with stored number formats applied
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `range` - Visible block of cells (null for the whole table)
+///
+/// # Returns
+/// StringResult with a JSON array of rows, each an array of `{text, color}`
+///
+/// # Safety
+/// `table` must be a live table handle; `range` must be null or valid
+#[no_mangle]
+pub unsafe extern "C" fn tessera_render_range(
+    table: *const TesseraTable,
+    range: *const CellRange,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let range = range
+        .as_ref()
+        .copied()
+        .unwrap_or_else(|| CellRange::all(table));
+
+    let rows: Vec<serde_json::Value> = render_range(table, range)
+        .into_iter()
+        .map(|row| serde_json::Value::from(row.iter().map(Formatted::to_json).collect::<Vec<_>>()))
+        .collect();
+    StringResult::success(&serde_json::Value::from(rows).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(code: &str, value: f64) -> String {
+        NumberFormat::parse(code).unwrap().format_value(value).text
+    }
+
+    #[test]
+    fn test_number_codes() {
+        assert_eq!(fmt("#,##0.00", 1234567.891), "1,234,567.89");
+        assert_eq!(fmt("#,##0.00", -5.0), "-5.00");
+        assert_eq!(fmt("0000", 42.0), "0042");
+        assert_eq!(fmt("#.##", 0.5), ".5");
+        assert_eq!(fmt("0.0%", 0.256), "25.6%");
+        assert_eq!(fmt("#,##0,\"K\"", 15300.0), "15K");
+        assert_eq!(fmt("0.00E+00", 12345.0), "1.23E+04");
+        assert_eq!(fmt("0.0E+0", 0.00042), "4.2E-4");
+        assert_eq!(fmt("\"$\"#,##0_);(\"$\"#,##0)", -1500.0), "($1,500)");
+        assert_eq!(fmt("000-0000", 5551234.0), "555-1234");
+        assert_eq!(fmt("0;-0;\"zero\"", 0.0), "zero");
+        assert_eq!(fmt("[>=100]\"big\";\"small\"", 150.0), "big");
+        assert_eq!(fmt("General", 3.5), "3.5");
+
+        let negative = NumberFormat::parse("#,##0.00;[Red](#,##0.00)")
+            .unwrap()
+            .format_value(-1234.5);
+        assert_eq!(negative.text, "(1,234.50)");
+        assert_eq!(negative.color.as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn test_date_codes_and_text() {
+        // 2024-03-05 14:07:09
+        let serial = 45356.0 + (14.0 * 3600.0 + 7.0 * 60.0 + 9.0) / 86_400.0;
+        assert_eq!(fmt("yyyy-mm-dd hh:mm:ss", serial), "2024-03-05 14:07:09");
+        assert_eq!(fmt("ddd, mmm d yy", serial), "Tue, Mar 5 24");
+        assert_eq!(fmt("h:mm AM/PM", serial), "2:07 PM");
+        assert_eq!(fmt("[h]:mm", 1.5), "36:00");
+        assert_eq!(
+            fmt("hh:mm:ss.000", serial + 0.25 / 86_400.0),
+            "14:07:09.250"
+        );
+        assert!(NumberFormat::parse("hh:mm:ss.0000").is_err());
+        assert_eq!(fmt("mmmm", serial), "March");
+
+        let format = NumberFormat::parse("0.0;-0.0;0;\"<\"@\">\"").unwrap();
+        assert_eq!(format.format_cell("n/a").text, "<n/a>");
+        assert_eq!(format.format_cell("1,200").text, "1200.0");
+        assert_eq!(format.format_cell("").text, "");
+
+        let date = NumberFormat::parse("dd/mm/yyyy").unwrap();
+        assert_eq!(date.format_cell("2024-03-05").text, "05/03/2024");
+        assert!(NumberFormat::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn test_render_range_with_stored_formats() {
+        let mut table = TesseraTable::from_rows(
+            vec!["Item".into(), "Price".into()],
+            vec![
+                vec!["Tea".into(), "1234.5".into()],
+                vec!["Cake".into(), "-3".into()],
+                vec!["Jam".into(), "".into()],
+            ],
+        );
+        let money = NumberFormat::parse("#,##0.00;[Red]-#,##0.00").unwrap();
+        table.set_column_format(1, Some(money)).unwrap();
+        table
+            .set_cell_format(0, 1, Some(NumberFormat::parse("0").unwrap()))
+            .unwrap();
+        assert!(table.set_column_format(5, None).is_err());
+
+        let range = CellRange {
+            row: 0,
+            col: 1,
+            rows: 10,
+            cols: 1,
+        };
+        let rendered = render_range(&table, range);
+        let texts: Vec<&str> = rendered.iter().map(|r| r[0].text.as_str()).collect();
+        assert_eq!(texts, ["1235", "-3.00", ""]);
+        assert_eq!(rendered[1][0].color.as_deref(), Some("red"));
+
+        table.set_cell_format(0, 1, None).unwrap();
+        assert_eq!(display_cell(&table, 0, 1).text, "1,234.50");
+    }
+}
@@ -0,0 +1,186 @@
+//! Histograms of numeric columns, as bin counts or ready-to-print text
+
+use crate::formula::value::format_number;
+use crate::query::profile::parse_number;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+/// Left blocks for the fractional end of a bar, one to seven eighths wide
+const PARTIAL: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Half-open bin `[lower, upper)`; the last bin also includes its upper edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Bin the numeric values of a column (non-numeric cells are ignored)
+///
+/// A `bins` of 0 picks a count with Sturges' rule. A column with a single
+/// distinct value yields one zero-width bin; no numbers yield no bins.
+pub fn histogram<'a>(values: impl IntoIterator<Item = &'a str>, bins: usize) -> Vec<Bin> {
+    let numbers: Vec<f64> = values.into_iter().filter_map(parse_number).collect();
+    let Some(lo) = numbers.iter().copied().reduce(f64::min) else {
+        return Vec::new();
+    };
+    let hi = numbers.iter().copied().fold(lo, f64::max);
+    if hi == lo {
+        return vec![Bin {
+            lower: lo,
+            upper: hi,
+            count: numbers.len(),
+        }];
+    }
+
+    let bins = if bins == 0 {
+        (numbers.len() as f64).log2().ceil() as usize + 1
+    } else {
+        bins
+    };
+    let step = (hi - lo) / bins as f64;
+    let mut result: Vec<Bin> = (0..bins)
+        .map(|i| Bin {
+            lower: lo + step * i as f64,
+            upper: if i + 1 == bins {
+                hi
+            } else {
+                lo + step * (i + 1) as f64
+            },
+            count: 0,
+        })
+        .collect();
+
+    for value in numbers {
+        let index = (((value - lo) / step) as usize).min(bins - 1);
+        result[index].count += 1;
+    }
+    result
+}
+
+/// Render bins as labelled horizontal bars, the longest `width` cells wide
+///
+/// ```text
+/// 0 – 5  │████▌ 9
+/// 5 – 10 │██ 4
+/// ```
+pub fn histogram_text(bins: &[Bin], width: usize) -> String {
+    let labels: Vec<String> = bins
+        .iter()
+        .map(|bin| {
+            format!(
+                "{} – {}",
+                format_number(bin.lower),
+                format_number(bin.upper)
+            )
+        })
+        .collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let peak = bins.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+
+    bins.iter()
+        .zip(&labels)
+        .map(|(bin, label)| {
+            let eighths = (bin.count * width * 8 + peak / 2) / peak;
+            let mut bar = "█".repeat(eighths / 8);
+            if let Some(&partial) = (eighths % 8).checked_sub(1).and_then(|i| PARTIAL.get(i)) {
+                bar.push(partial);
+            }
+            let pad = label_width - label.chars().count();
+            format!("{}{} │{} {}", label, " ".repeat(pad), bar, bin.count)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Bin a numeric column
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `col` - Column to bin
+/// * `bins` - Number of bins (0 to choose automatically)
+///
+/// # Returns
+/// StringResult with a JSON array of `{lower, upper, count}` bins
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_histogram(
+    table: *const TesseraTable,
+    col: usize,
+    bins: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if col >= table.column_count() {
+        return StringResult::error(&format!("Column {} is out of range", col));
+    }
+
+    let json: Vec<serde_json::Value> = histogram(table.column(col), bins)
+        .iter()
+        .map(|bin| {
+            serde_json::json!({
+                "lower": bin.lower,
+                "upper": bin.upper,
+                "count": bin.count,
+            })
+        })
+        .collect();
+    StringResult::success(&serde_json::Value::from(json).to_string())
+}
+
+/// Render a text histogram of a numeric column (lines separated by `\n`)
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `col` - Column to bin
+/// * `bins` - Number of bins (0 to choose automatically)
+/// * `width` - Length in cells of the longest bar
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_histogram_text(
+    table: *const TesseraTable,
+    col: usize,
+    bins: usize,
+    width: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if col >= table.column_count() {
+        return StringResult::error(&format!("Column {} is out of range", col));
+    }
+
+    StringResult::success(&histogram_text(&histogram(table.column(col), bins), width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_bins() {
+        let bins = histogram(["0", "1", "4", "5", "9", "10", "", "n/a"], 2);
+        let counts: Vec<(f64, f64, usize)> =
+            bins.iter().map(|b| (b.lower, b.upper, b.count)).collect();
+        assert_eq!(counts, [(0.0, 5.0, 3), (5.0, 10.0, 3)]);
+
+        assert_eq!(
+            histogram(["1", "2", "3", "4", "5", "6", "7", "8"], 0).len(),
+            4
+        );
+        assert_eq!(histogram(["3", "3"], 0)[0].count, 2);
+        assert!(histogram(["x"], 3).is_empty());
+    }
+
+    #[test]
+    fn test_histogram_text() {
+        let bins = histogram(["0", "1", "2", "3", "10"], 2);
+        assert_eq!(histogram_text(&bins, 4), "0 – 5  │████ 4\n5 – 10 │█ 1");
+    }
+}
@@ -0,0 +1,8 @@
+//! Helpers that prepare cell data for display in the TUI grid (heatmaps,
+//! inline charts, text measurement)
+
+pub mod format;
+pub mod histogram;
+pub mod scale;
+pub mod spark;
+pub mod text;
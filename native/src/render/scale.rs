@@ -0,0 +1,281 @@
+//! Color-scale and data-bar intensities for numeric columns
+//!
+//! Each numeric cell is mapped to an intensity in 0..=1 between the min and
+//! max anchors (with an optional mid anchor pinned to 0.5 for three-color
+//! scales) and to one of `buckets` discrete color steps.
+
+use std::os::raw::c_char;
+
+use crate::ffi::error_string;
+use crate::query::profile::parse_number;
+use crate::table::{table_arg, TesseraTable};
+
+/// Where a scale anchor sits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// Lowest value for the min anchor, highest for max, median for mid
+    Auto,
+    /// Fixed value
+    Number(f64),
+    /// Percentage (0-100) of the way from the column minimum to its maximum
+    Percent(f64),
+    /// Percentile (0-100) of the column values
+    Percentile(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleSettings {
+    pub min: Anchor,
+    pub mid: Option<Anchor>,
+    pub max: Anchor,
+    /// Number of discrete color steps (at least 1)
+    pub buckets: usize,
+}
+
+impl Default for ScaleSettings {
+    fn default() -> Self {
+        ScaleSettings {
+            min: Anchor::Auto,
+            mid: None,
+            max: Anchor::Auto,
+            buckets: 5,
+        }
+    }
+}
+
+/// FFI form of [`Anchor`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ScaleAnchor {
+    pub kind: u32, // 0 = auto, 1 = number, 2 = percent, 3 = percentile
+    pub value: f64,
+}
+
+impl ScaleAnchor {
+    fn anchor(&self) -> Result<Anchor, String> {
+        match self.kind {
+            0 => Ok(Anchor::Auto),
+            1 => Ok(Anchor::Number(self.value)),
+            2 => Ok(Anchor::Percent(self.value)),
+            3 => Ok(Anchor::Percentile(self.value)),
+            _ => Err("Unknown scale anchor".to_string()),
+        }
+    }
+}
+
+/// FFI form of [`ScaleSettings`]
+#[repr(C)]
+pub struct ScaleOptions {
+    pub min: ScaleAnchor,
+    pub mid: ScaleAnchor,
+    pub has_mid: bool,
+    pub max: ScaleAnchor,
+    pub buckets: usize,
+}
+
+impl ScaleOptions {
+    fn settings(&self) -> Result<ScaleSettings, String> {
+        Ok(ScaleSettings {
+            min: self.min.anchor()?,
+            mid: if self.has_mid {
+                Some(self.mid.anchor()?)
+            } else {
+                None
+            },
+            max: self.max.anchor()?,
+            buckets: self.buckets,
+        })
+    }
+}
+
+/// Scale position of one cell; non-numeric cells get NaN and bucket -1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleCell {
+    pub intensity: f64,
+    pub bucket: i32,
+}
+
+impl ScaleCell {
+    const EMPTY: ScaleCell = ScaleCell {
+        intensity: f64::NAN,
+        bucket: -1,
+    };
+}
+
+/// Linear-interpolated percentile (0-100) of sorted values
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn resolve(anchor: Anchor, sorted: &[f64], auto: f64) -> f64 {
+    let (lo, hi) = (sorted[0], sorted[sorted.len() - 1]);
+    match anchor {
+        Anchor::Auto => auto,
+        Anchor::Number(n) => n,
+        Anchor::Percent(p) => lo + (hi - lo) * p / 100.0,
+        Anchor::Percentile(p) => percentile(sorted, p),
+    }
+}
+
+/// Position of `value` between `from` and `to`, clamped to 0..=1
+fn fraction(value: f64, from: f64, to: f64) -> f64 {
+    if to <= from {
+        return if value < from { 0.0 } else { 1.0 };
+    }
+    ((value - from) / (to - from)).clamp(0.0, 1.0)
+}
+
+/// Map each value to its scale intensity and color bucket
+pub fn color_scale<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+    settings: &ScaleSettings,
+) -> Vec<ScaleCell> {
+    let numbers: Vec<Option<f64>> = values.into_iter().map(parse_number).collect();
+    let mut sorted: Vec<f64> = numbers.iter().flatten().copied().collect();
+    if sorted.is_empty() {
+        return vec![ScaleCell::EMPTY; numbers.len()];
+    }
+    sorted.sort_by(f64::total_cmp);
+
+    let min = resolve(settings.min, &sorted, sorted[0]);
+    let max = resolve(settings.max, &sorted, sorted[sorted.len() - 1]);
+    let mid = settings
+        .mid
+        .map(|anchor| resolve(anchor, &sorted, percentile(&sorted, 50.0)));
+    let buckets = settings.buckets.max(1);
+
+    numbers
+        .iter()
+        .map(|number| {
+            let Some(value) = *number else {
+                return ScaleCell::EMPTY;
+            };
+            let intensity = match mid {
+                Some(mid) if value < mid => fraction(value, min, mid) / 2.0,
+                Some(mid) => 0.5 + fraction(value, mid, max) / 2.0,
+                None => fraction(value, min, max),
+            };
+            let bucket = ((intensity * buckets as f64) as usize).min(buckets - 1);
+            ScaleCell {
+                intensity,
+                bucket: bucket as i32,
+            }
+        })
+        .collect()
+}
+
+/// FFI-safe result for [`tessera_color_scale`]
+#[repr(C)]
+pub struct ScaleResult {
+    pub cells: *mut ScaleCell, // one per row, free with tessera_free_scale_cells
+    pub len: usize,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl From<Result<Vec<ScaleCell>, String>> for ScaleResult {
+    fn from(result: Result<Vec<ScaleCell>, String>) -> Self {
+        match result {
+            Ok(cells) if cells.is_empty() => ScaleResult {
+                cells: std::ptr::null_mut(),
+                len: 0,
+                error: std::ptr::null_mut(),
+            },
+            Ok(cells) => {
+                let boxed = cells.into_boxed_slice();
+                let len = boxed.len();
+                ScaleResult {
+                    cells: Box::into_raw(boxed) as *mut ScaleCell,
+                    len,
+                    error: std::ptr::null_mut(),
+                }
+            }
+            Err(msg) => ScaleResult {
+                cells: std::ptr::null_mut(),
+                len: 0,
+                error: error_string(&msg),
+            },
+        }
+    }
+}
+
+/// Compute heatmap / data-bar positions for every row of a column
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `col` - Numeric column to scale
+/// * `options` - Anchors and bucket count (null for an auto min/max scale with 5 buckets)
+///
+/// # Returns
+/// ScaleResult with one cell per row (free with tessera_free_scale_cells)
+///
+/// # Safety
+/// `table` must be a live table handle; `options` must be null or valid
+#[no_mangle]
+pub unsafe extern "C" fn tessera_color_scale(
+    table: *const TesseraTable,
+    col: usize,
+    options: *const ScaleOptions,
+) -> ScaleResult {
+    let Some(table) = table_arg(table) else {
+        return ScaleResult::from(Err("Null pointer provided".to_string()));
+    };
+    if col >= table.column_count() {
+        return ScaleResult::from(Err(format!("Column {} is out of range", col)));
+    }
+
+    let settings = match options.as_ref() {
+        Some(options) => options.settings(),
+        None => Ok(ScaleSettings::default()),
+    };
+    settings
+        .map(|settings| color_scale(table.column(col), &settings))
+        .into()
+}
+
+/// Free the cells returned by tessera_color_scale
+///
+/// # Safety
+/// `cells` and `len` must come from the same ScaleResult, freed once
+#[no_mangle]
+pub unsafe extern "C" fn tessera_free_scale_cells(cells: *mut ScaleCell, len: usize) {
+    if !cells.is_null() {
+        let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(cells, len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_color_scale() {
+        let cells = color_scale(["0", "25", "", "100", "x"], &ScaleSettings::default());
+
+        let intensities: Vec<f64> = cells.iter().map(|c| c.intensity).collect();
+        assert_eq!(intensities[..2], [0.0, 0.25]);
+        assert!(intensities[2].is_nan());
+        assert_eq!(intensities[3], 1.0);
+        let buckets: Vec<i32> = cells.iter().map(|c| c.bucket).collect();
+        assert_eq!(buckets, [0, 1, -1, 4, -1]);
+    }
+
+    #[test]
+    fn test_anchored_three_color_scale() {
+        let settings = ScaleSettings {
+            min: Anchor::Number(0.0),
+            mid: Some(Anchor::Number(10.0)),
+            max: Anchor::Percentile(100.0),
+            buckets: 4,
+        };
+        let cells = color_scale(["-5", "5", "10", "20", "30"], &settings);
+
+        let intensities: Vec<f64> = cells.iter().map(|c| c.intensity).collect();
+        assert_eq!(intensities, [0.0, 0.25, 0.5, 0.75, 1.0]);
+        let buckets: Vec<i32> = cells.iter().map(|c| c.bucket).collect();
+        assert_eq!(buckets, [0, 1, 2, 3, 3]);
+    }
+}
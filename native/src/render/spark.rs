@@ -0,0 +1,156 @@
+//! Inline sparklines and small bar charts built from Unicode block characters
+
+use std::os::raw::c_char;
+
+use crate::ffi::str_array_arg;
+use crate::query::profile::parse_number;
+use crate::StringResult;
+
+/// Lower eighth blocks, from one eighth to a full cell
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Average values down to `width` points (0 keeps one point per value)
+///
+/// Non-numeric cells are skipped; a point with no numbers in it is `None`.
+fn resample(values: &[Option<f64>], width: usize) -> Vec<Option<f64>> {
+    if width == 0 || width >= values.len() {
+        return values.to_vec();
+    }
+
+    (0..width)
+        .map(|i| {
+            let chunk = &values[i * values.len() / width..(i + 1) * values.len() / width];
+            let numbers: Vec<f64> = chunk.iter().flatten().copied().collect();
+            (!numbers.is_empty()).then(|| numbers.iter().sum::<f64>() / numbers.len() as f64)
+        })
+        .collect()
+}
+
+fn bounds(points: &[Option<f64>]) -> Option<(f64, f64)> {
+    points.iter().flatten().fold(None, |acc, &v| match acc {
+        None => Some((v, v)),
+        Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+    })
+}
+
+/// One-line trend: lowest point `▁`, highest `█`, gaps as spaces
+pub fn sparkline<'a>(values: impl IntoIterator<Item = &'a str>, width: usize) -> String {
+    let numbers: Vec<Option<f64>> = values.into_iter().map(parse_number).collect();
+    let points = resample(&numbers, width);
+    let Some((lo, hi)) = bounds(&points) else {
+        return " ".repeat(points.len());
+    };
+
+    points
+        .iter()
+        .map(|point| match point {
+            None => ' ',
+            Some(_) if hi == lo => BLOCKS[3],
+            Some(v) => BLOCKS[((v - lo) / (hi - lo) * 7.0).round() as usize],
+        })
+        .collect()
+}
+
+/// Column chart `height` lines tall, bars rising from zero (or the minimum
+/// when values go negative); lines are joined with `\n`, top line first
+pub fn bar_chart<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+    width: usize,
+    height: usize,
+) -> String {
+    let numbers: Vec<Option<f64>> = values.into_iter().map(parse_number).collect();
+    let points = resample(&numbers, width);
+    let height = height.max(1);
+    let (lo, hi) = bounds(&points).unwrap_or((0.0, 0.0));
+    let (base, top) = (lo.min(0.0), hi.max(0.0));
+
+    // Bar heights in eighths of a line
+    let levels: Vec<usize> = points
+        .iter()
+        .map(|point| match point {
+            Some(v) if top > base => {
+                ((v - base) / (top - base) * (height * 8) as f64).round() as usize
+            }
+            _ => 0,
+        })
+        .collect();
+
+    (0..height)
+        .map(|line| {
+            let floor = (height - 1 - line) * 8;
+            levels
+                .iter()
+                .map(|&level| match level.saturating_sub(floor).min(8) {
+                    0 => ' ',
+                    eighths => BLOCKS[eighths - 1],
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render a one-line sparkline
+///
+/// # Arguments
+/// * `values_ptr` - Array of C strings (non-numeric entries render as gaps)
+/// * `count` - Number of values
+/// * `width` - Number of characters, averaging values when there are more (0 for one per value)
+///
+/// # Safety
+/// `values_ptr` must point to `count` valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sparkline(
+    values_ptr: *const *const c_char,
+    count: usize,
+    width: usize,
+) -> StringResult {
+    let Some(values) = str_array_arg(values_ptr, count) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    StringResult::success(&sparkline(values.iter().map(String::as_str), width))
+}
+
+/// Render a multi-line column chart (lines separated by `\n`)
+///
+/// # Arguments
+/// * `values_ptr` - Array of C strings (non-numeric entries render as gaps)
+/// * `count` - Number of values
+/// * `width` - Number of columns, averaging values when there are more (0 for one per value)
+/// * `height` - Number of text lines
+///
+/// # Safety
+/// `values_ptr` must point to `count` valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_sparkline_bars(
+    values_ptr: *const *const c_char,
+    count: usize,
+    width: usize,
+    height: usize,
+) -> StringResult {
+    let Some(values) = str_array_arg(values_ptr, count) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    StringResult::success(&bar_chart(values.iter().map(String::as_str), width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(["1", "8", "", "4", "x", "1"], 0), "▁█ ▄ ▁");
+        assert_eq!(sparkline(["1", "3", "5", "7"], 2), "▁█");
+        assert_eq!(sparkline(["2", "2"], 0), "▄▄");
+        assert_eq!(sparkline(["a"], 0), " ");
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        assert_eq!(bar_chart(["0", "1", "2", "4"], 0, 2), "   █\n ▄██");
+        assert_eq!(bar_chart(["3", "", "6"], 0, 1), "▄ █");
+    }
+}
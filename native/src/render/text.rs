@@ -0,0 +1,322 @@
+//! Terminal display width of cell text
+//!
+//! Widths are measured per grapheme cluster, so combining marks, emoji ZWJ
+//! sequences and flags count as one glyph; East Asian wide characters and
+//! emoji take two columns.
+//!
+//! Wrapping breaks lines at Unicode line-break opportunities (UAX #14),
+//! falling back to grapheme boundaries for words wider than the column.
+//! Truncation never splits a cluster, so a cut next to a wide glyph may leave
+//! the result one column short of the requested width.
+
+use std::os::raw::c_char;
+
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ffi::slice_arg;
+use crate::ffi::{error_string, str_arg, str_array_arg, to_c_string};
+use crate::table::{table_arg, TesseraTable};
+use crate::{IndexArray, StringResult};
+
+/// Columns taken by one grapheme cluster (control characters take none)
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme.chars().next().is_some_and(char::is_control) {
+        return 0;
+    }
+    // A cluster renders as a single glyph, at most two cells wide
+    grapheme.width().min(2)
+}
+
+/// Columns needed to display `text`; multi-line text takes its widest line
+pub fn display_width(text: &str) -> usize {
+    text.lines()
+        .map(|line| line.graphemes(true).map(grapheme_width).sum())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Cut `text` to at most `max_width` columns, ending with `ellipsis` when
+/// anything was removed
+///
+/// Text is treated as one line (line breaks take no width). When even the
+/// ellipsis does not fit, the text is cut without it. Returns the new text
+/// and its display width.
+pub fn truncate(text: &str, max_width: usize, ellipsis: &str) -> (String, usize) {
+    let width: usize = text.graphemes(true).map(grapheme_width).sum();
+    if width <= max_width {
+        return (text.to_string(), width);
+    }
+
+    let ellipsis_width: usize = ellipsis.graphemes(true).map(grapheme_width).sum();
+    let (ellipsis, ellipsis_width) = if ellipsis_width <= max_width {
+        (ellipsis, ellipsis_width)
+    } else {
+        ("", 0)
+    };
+    let budget = max_width - ellipsis_width;
+
+    let mut result = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let w = grapheme_width(grapheme);
+        if used + w > budget {
+            break;
+        }
+        result.push_str(grapheme);
+        used += w;
+    }
+    result.push_str(ellipsis);
+    let used = used
+        + if ellipsis.is_empty() {
+            0
+        } else {
+            ellipsis_width
+        };
+    (result, used)
+}
+
+fn str_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Break `text` into lines of at most `width` columns
+///
+/// Explicit line breaks are kept, whitespace at a wrap point is dropped and
+/// a word wider than the column is split between grapheme clusters. Empty
+/// text wraps to a single empty line.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut start = 0;
+
+    for (end, opportunity) in linebreaks(text) {
+        let segment = &text[start..end];
+        start = end;
+        let word = segment.trim_end_matches(['\r', '\n', '\u{85}', '\u{2028}', '\u{2029}']);
+
+        if !line.is_empty() && str_width(line.trim_end()) + str_width(word.trim_end()) > width {
+            lines.push(line.trim_end().to_string());
+            line.clear();
+        }
+        for grapheme in word.graphemes(true) {
+            if str_width(&line) + grapheme_width(grapheme) > width
+                && !line.is_empty()
+                && !grapheme.trim().is_empty()
+            {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(grapheme);
+        }
+
+        if opportunity == BreakOpportunity::Mandatory && end < text.len() {
+            lines.push(line.trim_end().to_string());
+            line.clear();
+        }
+    }
+
+    lines.push(line.trim_end().to_string());
+    lines
+}
+
+/// FFI-safe result for [`tessera_truncate`]
+#[repr(C)]
+pub struct TruncateResult {
+    pub value: *mut c_char, // free with tessera_free_string
+    pub width: usize,       // display width of `value`
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+/// Truncate text to a number of terminal columns
+///
+/// # Arguments
+/// * `text` - Cell text
+/// * `max_width` - Available columns
+/// * `ellipsis` - Marker appended after a cut (null for `…`)
+///
+/// # Safety
+/// `text` must be a valid C string; `ellipsis` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_truncate(
+    text: *const c_char,
+    max_width: usize,
+    ellipsis: *const c_char,
+) -> TruncateResult {
+    let Some(text) = str_arg(text) else {
+        return TruncateResult {
+            value: std::ptr::null_mut(),
+            width: 0,
+            error: error_string("Invalid text encoding"),
+        };
+    };
+    let ellipsis = if ellipsis.is_null() {
+        "…"
+    } else {
+        str_arg(ellipsis).unwrap_or("…")
+    };
+
+    let (value, width) = truncate(text, max_width, ellipsis);
+    TruncateResult {
+        value: to_c_string(&value),
+        width,
+        error: std::ptr::null_mut(),
+    }
+}
+
+/// Word-wrap text to a column width
+///
+/// # Returns
+/// StringResult with a JSON array of lines
+///
+/// # Safety
+/// `text` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_wrap_text(text: *const c_char, width: usize) -> StringResult {
+    let Some(text) = str_arg(text) else {
+        return StringResult::error("Invalid text encoding");
+    };
+
+    StringResult::success(&serde_json::Value::from(wrap(text, width)).to_string())
+}
+
+/// Number of wrapped lines each row needs for the given column widths
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `widths_ptr` - Display width of each column
+/// * `count` - Number of widths (must match the column count)
+///
+/// # Returns
+/// IndexArray with one line count per row (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `widths_ptr` must point to `count` values
+#[no_mangle]
+pub unsafe extern "C" fn tessera_row_heights(
+    table: *const TesseraTable,
+    widths_ptr: *const usize,
+    count: usize,
+) -> IndexArray {
+    let (Some(table), Some(widths)) = (table_arg(table), slice_arg(widths_ptr, count)) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    if widths.len() != table.column_count() {
+        return IndexArray::error("Column width count does not match the table");
+    }
+
+    IndexArray::success(
+        (0..table.row_count())
+            .map(|row| {
+                table
+                    .row(row)
+                    .zip(widths)
+                    .map(|(cell, &width)| wrap(cell, width).len())
+                    .max()
+                    .unwrap_or(1)
+            })
+            .collect(),
+    )
+}
+
+/// Display width of each string
+///
+/// # Returns
+/// IndexArray with one width per value (free with tessera_free_index_array)
+///
+/// # Safety
+/// `values_ptr` must point to `count` valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_display_widths(
+    values_ptr: *const *const c_char,
+    count: usize,
+) -> IndexArray {
+    let Some(values) = str_array_arg(values_ptr, count) else {
+        return IndexArray::error("Null pointer provided");
+    };
+
+    IndexArray::success(values.iter().map(|v| display_width(v)).collect())
+}
+
+/// Widest display width of each column, header included, for auto-fit
+///
+/// # Returns
+/// IndexArray with one width per column (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_column_display_widths(table: *const TesseraTable) -> IndexArray {
+    let Some(table) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+
+    IndexArray::success(
+        table
+            .headers()
+            .iter()
+            .enumerate()
+            .map(|(col, header)| {
+                table
+                    .column(col)
+                    .map(display_width)
+                    .fold(display_width(header), usize::max)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("👍🏽"), 2);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+        assert_eq!(display_width("🇻🇳 ok"), 5);
+        assert_eq!(display_width("a\tb"), 2);
+        assert_eq!(display_width("short\nlonger line"), 11);
+        assert_eq!(display_width(""), 0);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 5, "…"), ("hello".to_string(), 5));
+        assert_eq!(truncate("hello world", 6, "…"), ("hello…".to_string(), 6));
+        assert_eq!(truncate("日本語テキスト", 6, "…"), ("日本…".to_string(), 5));
+        assert_eq!(truncate("👨‍👩‍👧👨‍👩‍👧", 3, "…"), ("👨‍👩‍👧…".to_string(), 3));
+        assert_eq!(truncate("cafe\u{301}s", 4, "..."), ("c...".to_string(), 4));
+        assert_eq!(truncate("abc", 1, "..."), ("a".to_string(), 1));
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("line one\nsecond", 20), ["line one", "second"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("日本語のテキスト", 6), ["日本語", "のテキ", "スト"]);
+        assert_eq!(wrap("self-service desk", 6), ["self-", "servic", "e desk"]);
+        assert_eq!(wrap("", 5), [""]);
+    }
+
+    #[test]
+    fn test_column_display_widths() {
+        let table = TesseraTable::from_rows(
+            vec!["Name".into(), "Id".into()],
+            vec![
+                vec!["東京都".into(), "7".into()],
+                vec!["Ann".into(), "".into()],
+            ],
+        );
+
+        let result = unsafe { tessera_column_display_widths(&table) };
+        let widths = unsafe { std::slice::from_raw_parts(result.data, result.len) }.to_vec();
+        unsafe { crate::tessera_free_index_array(result.data, result.len) };
+        assert_eq!(widths, [6, 2]);
+    }
+}
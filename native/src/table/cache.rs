@@ -0,0 +1,166 @@
+//! Per-column numeric caches behind the status-bar aggregates
+//!
+//! A column is parsed the first time it is aggregated. After that, single
+//! cell edits and appended rows update both the parsed values and the running
+//! count/sum/min/max, so reading them is O(1). Removing the current minimum
+//! or maximum marks the extremes stale and the next read rescans the column.
+//! Structural edits drop the whole cache.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Numeric parse of a cell as the column aggregates see it
+pub(super) fn parse_cell(cell: &str) -> Option<f64> {
+    cell.trim().parse().ok()
+}
+
+/// Running aggregates of the numeric cells of a column
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ColumnStats {
+    /// Numeric cells
+    pub count: usize,
+    /// Kept incrementally, so it can differ from a fresh sum in the last bits
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ColumnStats {
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedColumn {
+    numbers: Arc<Vec<Option<f64>>>,
+    stats: ColumnStats,
+    /// Set when the min or max was removed and has to be found again
+    stale_extremes: bool,
+}
+
+impl CachedColumn {
+    fn new(numbers: Vec<Option<f64>>) -> Self {
+        let mut column = CachedColumn {
+            numbers: Arc::new(numbers),
+            stats: ColumnStats::default(),
+            stale_extremes: false,
+        };
+        for value in column.numbers.iter().flatten() {
+            column.stats.count += 1;
+            column.stats.sum += value;
+        }
+        column.rescan_extremes();
+        column
+    }
+
+    fn rescan_extremes(&mut self) {
+        let values = self.numbers.iter().flatten().copied();
+        self.stats.min = values.clone().reduce(f64::min);
+        self.stats.max = values.reduce(f64::max);
+        self.stale_extremes = false;
+    }
+
+    fn add(&mut self, value: Option<f64>) {
+        let Some(value) = value else { return };
+        self.stats.count += 1;
+        self.stats.sum += value;
+        if !self.stale_extremes {
+            self.stats.min = Some(self.stats.min.map_or(value, |min| min.min(value)));
+            self.stats.max = Some(self.stats.max.map_or(value, |max| max.max(value)));
+        }
+    }
+
+    fn remove(&mut self, value: Option<f64>) {
+        let Some(value) = value else { return };
+        self.stats.count -= 1;
+        self.stats.sum -= value;
+        if self.stats.count == 0 {
+            // Drop accumulated rounding error along with the last value
+            self.stats.sum = 0.0;
+        }
+        if self.stats.min == Some(value) || self.stats.max == Some(value) {
+            self.stale_extremes = true;
+        }
+    }
+
+    fn set(&mut self, row: usize, value: Option<f64>) {
+        let old = std::mem::replace(&mut Arc::make_mut(&mut self.numbers)[row], value);
+        self.remove(old);
+        self.add(value);
+    }
+
+    fn push(&mut self, value: Option<f64>) {
+        Arc::make_mut(&mut self.numbers).push(value);
+        self.add(value);
+    }
+
+    fn stats(&mut self) -> ColumnStats {
+        if self.stale_extremes {
+            self.rescan_extremes();
+        }
+        self.stats
+    }
+}
+
+/// Cached columns by index; never part of table equality
+#[derive(Debug, Default)]
+pub(super) struct NumberCache(Mutex<HashMap<usize, CachedColumn>>);
+
+impl NumberCache {
+    pub(super) fn clear(&mut self) {
+        self.0.get_mut().unwrap().clear();
+    }
+
+    /// Track a single-cell edit in a cached column
+    pub(super) fn set(&mut self, row: usize, col: usize, text: &str) {
+        if let Some(column) = self.0.get_mut().unwrap().get_mut(&col) {
+            column.set(row, parse_cell(text));
+        }
+    }
+
+    /// Track an appended row
+    pub(super) fn push_row(&mut self, cells: &[String]) {
+        for (&col, column) in self.0.get_mut().unwrap() {
+            column.push(cells.get(col).and_then(|cell| parse_cell(cell)));
+        }
+    }
+
+    /// Parsed values of a column, calling `parse` on first use
+    pub(super) fn numbers(
+        &self,
+        col: usize,
+        parse: impl FnOnce() -> Vec<Option<f64>>,
+    ) -> Arc<Vec<Option<f64>>> {
+        let mut columns = self.0.lock().unwrap();
+        let column = columns
+            .entry(col)
+            .or_insert_with(|| CachedColumn::new(parse()));
+        column.numbers.clone()
+    }
+
+    /// Running aggregates of a column, calling `parse` on first use
+    pub(super) fn stats(
+        &self,
+        col: usize,
+        parse: impl FnOnce() -> Vec<Option<f64>>,
+    ) -> ColumnStats {
+        let mut columns = self.0.lock().unwrap();
+        columns
+            .entry(col)
+            .or_insert_with(|| CachedColumn::new(parse()))
+            .stats()
+    }
+}
+
+impl Clone for NumberCache {
+    fn clone(&self) -> Self {
+        NumberCache(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl PartialEq for NumberCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
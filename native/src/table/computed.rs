@@ -0,0 +1,328 @@
+//! Computed columns: a formula evaluated per row instead of stored cells
+//!
+//! A computed column keeps one formula such as `=[Price]*[Qty]` on the table
+//! and ignores the text stored in its cells. Its values are evaluated for the
+//! whole column the first time they are read; after that a single-cell edit
+//! re-evaluates just that row in the computed columns reading the edited
+//! column, directly or through other computed columns. Appended rows and
+//! structural edits drop the cached values. A formula that reads its own
+//! column, directly or through others, evaluates to `#CALC!`.
+
+use std::collections::{HashMap, HashSet};
+use std::os::raw::c_char;
+use std::sync::{Arc, Mutex};
+
+use super::{table_arg, table_arg_mut, TesseraTable};
+use crate::ffi::{error_string, str_arg};
+use crate::formula::row_context::RowFormula;
+use crate::formula::{ErrorValue, Value};
+use crate::StringResult;
+
+/// Formula attached to a column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedColumn {
+    pub column: usize,
+    pub formula: String,
+}
+
+/// Evaluated computed columns by index; never part of table equality
+#[derive(Debug, Default)]
+pub(super) struct ComputedCache(Mutex<HashMap<usize, Arc<Vec<Value>>>>);
+
+impl ComputedCache {
+    pub(super) fn clear(&mut self) {
+        self.0.get_mut().unwrap().clear();
+    }
+
+    fn get(&self, col: usize) -> Option<Arc<Vec<Value>>> {
+        self.0.lock().unwrap().get(&col).cloned()
+    }
+
+    fn insert(&self, col: usize, values: Arc<Vec<Value>>) {
+        self.0.lock().unwrap().insert(col, values);
+    }
+
+    fn remove(&mut self, col: usize) {
+        self.0.get_mut().unwrap().remove(&col);
+    }
+
+    fn is_cached(&mut self, col: usize) -> bool {
+        self.0.get_mut().unwrap().contains_key(&col)
+    }
+
+    fn set_row(&mut self, col: usize, row: usize, value: Value) {
+        if let Some(values) = self.0.get_mut().unwrap().get_mut(&col) {
+            if let Some(slot) = Arc::make_mut(values).get_mut(row) {
+                *slot = value;
+            }
+        }
+    }
+}
+
+impl Clone for ComputedCache {
+    fn clone(&self) -> Self {
+        ComputedCache(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl PartialEq for ComputedCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl TesseraTable {
+    /// Formulas of the computed columns, in definition order
+    pub fn computed_columns(&self) -> &[ComputedColumn] {
+        &self.computed
+    }
+
+    pub fn is_computed(&self, col: usize) -> bool {
+        self.computed.iter().any(|c| c.column == col)
+    }
+
+    /// Make `col` a computed column, replacing any formula it had
+    ///
+    /// Fails when the formula does not parse or names a column that does
+    /// not exist.
+    pub fn set_computed_column(&mut self, col: usize, formula: &str) -> Result<(), String> {
+        if col >= self.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+        RowFormula::new(self, formula)?;
+
+        let formula = formula.trim().to_string();
+        match self.computed.iter_mut().find(|c| c.column == col) {
+            Some(computed) => computed.formula = formula,
+            None => self.computed.push(ComputedColumn {
+                column: col,
+                formula,
+            }),
+        }
+        self.computed_values.clear();
+        Ok(())
+    }
+
+    /// Turn a computed column back into a stored one; false if it was not computed
+    pub fn clear_computed_column(&mut self, col: usize) -> bool {
+        let before = self.computed.len();
+        self.computed.retain(|c| c.column != col);
+        self.computed_values.clear();
+        self.computed.len() != before
+    }
+
+    /// Values of a computed column, evaluated on first use
+    pub fn computed_values(&self, col: usize) -> Option<Arc<Vec<Value>>> {
+        if let Some(values) = self.computed_values.get(col) {
+            return Some(values);
+        }
+        let computed = self.computed.iter().find(|c| c.column == col)?;
+
+        let rows = self.row_count();
+        let values = match self.compile(computed) {
+            None => vec![Value::Error(ErrorValue::Name); rows],
+            Some(_) if self.reads_itself(col) => vec![Value::Error(ErrorValue::Calc); rows],
+            Some(formula) => (0..rows).map(|row| formula.evaluate(self, row)).collect(),
+        };
+        let values = Arc::new(values);
+        self.computed_values.insert(col, values.clone());
+        Some(values)
+    }
+
+    /// Value of one cell of a computed column; `None` for stored columns
+    pub fn computed_value(&self, row: usize, col: usize) -> Option<Value> {
+        let values = self.computed_values(col)?;
+        Some(values.get(row).cloned().unwrap_or(Value::Blank))
+    }
+
+    /// `None` when the formula no longer binds, e.g. after a header rename
+    fn compile(&self, computed: &ComputedColumn) -> Option<RowFormula> {
+        RowFormula::new(self, &computed.formula).ok()
+    }
+
+    /// Columns read by the formula of `col` (none for stored columns)
+    pub(crate) fn computed_inputs(&self, col: usize) -> Vec<usize> {
+        self.computed
+            .iter()
+            .find(|c| c.column == col)
+            .and_then(|computed| self.compile(computed))
+            .map(|formula| formula.binding().columns().collect())
+            .unwrap_or_default()
+    }
+
+    fn reads_itself(&self, col: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = self.computed_inputs(col);
+        while let Some(input) = stack.pop() {
+            if input == col {
+                return true;
+            }
+            if seen.insert(input) {
+                stack.extend(self.computed_inputs(input));
+            }
+        }
+        false
+    }
+
+    /// Re-evaluate `row` in the cached computed columns downstream of `col`
+    pub(super) fn refresh_computed(&mut self, row: usize, col: usize) {
+        if self.computed.is_empty() {
+            return;
+        }
+
+        // Downstream columns, each listed after the computed columns it reads
+        let inputs: HashMap<usize, Vec<usize>> = self
+            .computed
+            .iter()
+            .map(|c| (c.column, self.computed_inputs(c.column)))
+            .collect();
+        let mut affected: HashSet<usize> = HashSet::new();
+        let mut stack = vec![col];
+        while let Some(changed) = stack.pop() {
+            for (&computed, reads) in &inputs {
+                if reads.contains(&changed) && affected.insert(computed) {
+                    stack.push(computed);
+                }
+            }
+        }
+        let mut order = Vec::new();
+        let mut pending: Vec<usize> = affected.iter().copied().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|c| {
+                let ready = inputs[c]
+                    .iter()
+                    .all(|input| !affected.contains(input) || order.contains(input));
+                if ready {
+                    order.push(*c);
+                }
+                !ready
+            });
+            if pending.len() == before {
+                // A cycle: these evaluate to #CALC! when read again
+                for c in pending.drain(..) {
+                    self.computed_values.remove(c);
+                }
+            }
+        }
+
+        for computed in order {
+            if !self.computed_values.is_cached(computed) {
+                continue;
+            }
+            let definition = self.computed.iter().find(|c| c.column == computed);
+            let value = match definition.and_then(|c| self.compile(c)) {
+                Some(formula) => formula.evaluate(self, row),
+                None => Value::Error(ErrorValue::Name),
+            };
+            self.computed_values.set_row(computed, row, value);
+        }
+    }
+}
+
+/// Compute a column from a per-row formula such as `=[Price]*[Qty]`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_computed_column(
+    table: *mut TesseraTable,
+    column: usize,
+    formula: *const c_char,
+) -> *mut c_char {
+    let Some(table) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return error_string("Invalid formula encoding");
+    };
+
+    match table.set_computed_column(column, formula) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Go back to the stored cells of a computed column
+///
+/// # Returns
+/// True if the column was computed
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_clear_computed_column(
+    table: *mut TesseraTable,
+    column: usize,
+) -> bool {
+    table_arg_mut(table).is_some_and(|table| table.clear_computed_column(column))
+}
+
+/// Values of rows `[start, start + count)` of a computed column
+///
+/// # Returns
+/// StringResult with a JSON array of cell text, or an error when the column
+/// is not computed
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_computed_values(
+    table: *const TesseraTable,
+    column: usize,
+    start: usize,
+    count: usize,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(values) = table.computed_values(column) else {
+        return StringResult::error(&format!("Column {} is not computed", column));
+    };
+
+    let texts: Vec<String> = values
+        .iter()
+        .skip(start)
+        .take(count)
+        .map(Value::to_string)
+        .collect();
+    StringResult::success(&serde_json::Value::from(texts).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_computed_column_follows_edits() {
+        let mut table = TesseraTable::from_rows(
+            vec!["Price".into(), "Qty".into(), "Total".into(), "Big".into()],
+            vec![
+                vec!["2".into(), "3".into()],
+                vec!["5".into(), "4".into(), "stored".into()],
+            ],
+        );
+        table.set_computed_column(2, "=[Price]*[Qty]").unwrap();
+        table.set_computed_column(3, "=Total > 10").unwrap();
+        assert!(table.set_computed_column(2, "=[Nope]*2").is_err());
+
+        let text = |t: &TesseraTable, row, col| t.computed_value(row, col).unwrap().to_string();
+        assert_eq!(text(&table, 1, 2), "20");
+        assert_eq!(text(&table, 0, 3), "FALSE");
+        assert_eq!(table.computed_value(0, 0), None);
+
+        table.set_cell(0, 1, "30".into()).unwrap();
+        assert_eq!(text(&table, 0, 2), "60");
+        assert_eq!(text(&table, 0, 3), "TRUE");
+
+        table.insert_columns(0, vec!["Id".into()]).unwrap();
+        assert_eq!(text(&table, 1, 3), "20");
+        table.set_computed_column(1, "=[Total]").unwrap();
+        assert_eq!(text(&table, 0, 3), "#CALC!");
+        assert!(table.clear_computed_column(3));
+        assert_eq!(table.cell(1, 3), "stored");
+    }
+}
@@ -0,0 +1,101 @@
+//! Column indexes for repeated lookups
+//!
+//! An index maps each distinct cell text of a column to the rows holding it,
+//! and can also keep the numeric cells sorted by value for range queries.
+//! Indexes are created explicitly, follow single-cell edits and appended rows
+//! incrementally, and are rebuilt after structural edits.
+
+use std::collections::HashMap;
+
+use super::cache::parse_cell;
+use super::CellText;
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct ColumnIndex {
+    /// Rows by exact cell text, in ascending row order
+    rows: HashMap<CellText, Vec<usize>>,
+    /// `(value, row)` for every numeric cell, sorted
+    sorted: Option<Vec<(f64, usize)>>,
+}
+
+fn by_value(a: &(f64, usize), b: &(f64, usize)) -> std::cmp::Ordering {
+    a.0.total_cmp(&b.0).then(a.1.cmp(&b.1))
+}
+
+impl ColumnIndex {
+    pub(super) fn build<'a>(cells: impl Iterator<Item = &'a CellText>, sorted: bool) -> Self {
+        let mut index = ColumnIndex {
+            rows: HashMap::new(),
+            sorted: sorted.then(Vec::new),
+        };
+        for (row, cell) in cells.enumerate() {
+            index.rows.entry(cell.clone()).or_default().push(row);
+            if let (Some(sorted), Some(value)) = (&mut index.sorted, parse_cell(cell)) {
+                sorted.push((value, row));
+            }
+        }
+        if let Some(sorted) = &mut index.sorted {
+            sorted.sort_by(by_value);
+        }
+        index
+    }
+
+    pub(super) fn is_sorted(&self) -> bool {
+        self.sorted.is_some()
+    }
+
+    pub(super) fn lookup(&self, text: &str) -> &[usize] {
+        self.rows.get(text).map_or(&[], Vec::as_slice)
+    }
+
+    /// Rows whose numeric value is within `min..=max`, in value order
+    pub(super) fn range(&self, min: f64, max: f64) -> Option<Vec<usize>> {
+        let sorted = self.sorted.as_ref()?;
+        let start = sorted.partition_point(|(value, _)| *value < min);
+        let end = sorted.partition_point(|(value, _)| *value <= max);
+        Some(
+            sorted[start..end.max(start)]
+                .iter()
+                .map(|&(_, row)| row)
+                .collect(),
+        )
+    }
+
+    pub(super) fn remove(&mut self, row: usize, text: &str) {
+        if let Some(rows) = self.rows.get_mut(text) {
+            if let Ok(at) = rows.binary_search(&row) {
+                rows.remove(at);
+            }
+            if rows.is_empty() {
+                self.rows.remove(text);
+            }
+        }
+        if let (Some(sorted), Some(value)) = (&mut self.sorted, parse_cell(text)) {
+            if let Ok(at) = sorted.binary_search_by(|entry| by_value(entry, &(value, row))) {
+                sorted.remove(at);
+            }
+        }
+    }
+
+    pub(super) fn insert(&mut self, row: usize, text: &CellText) {
+        let rows = self.rows.entry(text.clone()).or_default();
+        if let Err(at) = rows.binary_search(&row) {
+            rows.insert(at, row);
+        }
+        if let (Some(sorted), Some(value)) = (&mut self.sorted, parse_cell(text)) {
+            if let Err(at) = sorted.binary_search_by(|entry| by_value(entry, &(value, row))) {
+                sorted.insert(at, (value, row));
+            }
+        }
+    }
+}
+
+/// Indexes by column; never part of table equality
+#[derive(Debug, Clone, Default)]
+pub(super) struct Indexes(pub(super) HashMap<usize, ColumnIndex>);
+
+impl PartialEq for Indexes {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
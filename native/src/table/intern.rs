@@ -0,0 +1,149 @@
+//! Shared cell text
+//!
+//! Categorical columns (country, status, unit, ...) repeat a handful of
+//! values across millions of rows. Each table keeps a pool of the distinct
+//! texts it holds and every cell points into it, so a repeated value is one
+//! reference-counted allocation and equal cells compare by pointer first.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Immutable, cheaply clonable cell text
+#[derive(Clone, Default, PartialOrd, Ord)]
+pub struct CellText(Arc<str>);
+
+impl CellText {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for CellText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for CellText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for CellText {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for CellText {
+    fn from(text: String) -> Self {
+        CellText(text.into())
+    }
+}
+
+impl From<&str> for CellText {
+    fn from(text: &str) -> Self {
+        CellText(text.into())
+    }
+}
+
+impl PartialEq for CellText {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for CellText {}
+
+// Must hash like `str` for pool lookups through `Borrow<str>`
+impl Hash for CellText {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl PartialEq<str> for CellText {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for CellText {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for CellText {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<CellText> for String {
+    fn eq(&self, other: &CellText) -> bool {
+        **self == *other.0
+    }
+}
+
+impl PartialEq<CellText> for &str {
+    fn eq(&self, other: &CellText) -> bool {
+        **self == *other.0
+    }
+}
+
+impl fmt::Debug for CellText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for CellText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Pool of the distinct texts in one table
+///
+/// Entries are dropped when the last cell using them is overwritten, or by
+/// [`Interner::prune`] after bulk deletes. Never part of table equality.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Interner(HashSet<CellText>);
+
+impl Interner {
+    pub(crate) fn intern(&mut self, text: String) -> CellText {
+        if let Some(shared) = self.0.get(text.as_str()) {
+            return shared.clone();
+        }
+        let text = CellText::from(text);
+        self.0.insert(text.clone());
+        text
+    }
+
+    /// Give back a cell's text, dropping the pool entry if nothing else uses it
+    pub(crate) fn release(&mut self, text: CellText) {
+        // One reference is `text` itself and one is the pool's
+        if Arc::strong_count(&text.0) == 2 {
+            self.0.remove(text.as_str());
+        }
+    }
+
+    /// Drop every entry no cell uses anymore
+    pub(crate) fn prune(&mut self) {
+        self.0.retain(|text| Arc::strong_count(&text.0) > 1);
+    }
+}
+
+impl PartialEq for Interner {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
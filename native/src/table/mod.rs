@@ -0,0 +1,1146 @@
+//! In-memory table handle shared between the importers, exporters and the C# host
+
+mod cache;
+mod computed;
+mod index;
+pub mod intern;
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::sync::Arc;
+
+use crate::ffi::{error_string, slice_arg, str_arg, str_array_arg, to_c_string};
+use crate::formula::rewrite::{
+    is_formula, rewrite_references, shift_index, shift_reference, Reference,
+};
+use crate::render::format::NumberFormat;
+use crate::validation::ValidationRule;
+use crate::IndexArray;
+pub use cache::ColumnStats;
+use cache::{parse_cell, NumberCache};
+use computed::ComputedCache;
+pub use computed::ComputedColumn;
+use index::{ColumnIndex, Indexes};
+pub use intern::CellText;
+use intern::Interner;
+
+/// Drop the empty cells at the end of a stored row
+fn trim_row(cells: &mut Vec<CellText>, strings: &mut Interner) {
+    while cells.last().is_some_and(|cell| cell.is_empty()) {
+        strings.release(cells.pop().unwrap_or_default());
+    }
+}
+
+/// Rectangular table of string cells with a header row
+///
+/// Cells are kept as the raw text the user sees, the same way the C#
+/// `TableModel` stores them; numeric interpretation happens per operation,
+/// except for the parsed numbers cached for column aggregates. Equal texts
+/// share one allocation through the table's string pool.
+///
+/// Storage is sparse along rows: trailing empty cells are not stored, so a
+/// blank row costs no heap allocation and inserting 100,000 rows above a
+/// formula only allocates the row list. Readers go through [`Self::cell`],
+/// [`Self::row`] or [`Self::column`], which fill the gaps with empty text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TesseraTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<CellText>>,
+    rules: Vec<ValidationRule>,
+    computed: Vec<ComputedColumn>,
+    column_formats: HashMap<usize, NumberFormat>,
+    cell_formats: HashMap<(usize, usize), NumberFormat>,
+    numbers: NumberCache,
+    computed_values: ComputedCache,
+    strings: Interner,
+    indexes: Indexes,
+}
+
+impl TesseraTable {
+    /// Create an empty table with the given column names
+    pub fn new(headers: Vec<String>) -> Self {
+        TesseraTable {
+            headers,
+            rows: Vec::new(),
+            rules: Vec::new(),
+            computed: Vec::new(),
+            column_formats: HashMap::new(),
+            cell_formats: HashMap::new(),
+            numbers: NumberCache::default(),
+            computed_values: ComputedCache::default(),
+            strings: Interner::default(),
+            indexes: Indexes::default(),
+        }
+    }
+
+    /// Create a table from headers and rows, padding short rows with empty cells
+    ///
+    /// Rows wider than the header row get generated `ColumnN` headers so no
+    /// data is silently dropped.
+    pub fn from_rows(mut headers: Vec<String>, rows: Vec<Vec<String>>) -> Self {
+        let width = rows
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+            .max(headers.len());
+
+        while headers.len() < width {
+            headers.push(format!("Column{}", headers.len() + 1));
+        }
+        let mut strings = Interner::default();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let mut cells = row.into_iter().map(|cell| strings.intern(cell)).collect();
+                trim_row(&mut cells, &mut strings);
+                cells
+            })
+            .collect();
+
+        TesseraTable {
+            headers,
+            rows,
+            rules: Vec::new(),
+            computed: Vec::new(),
+            column_formats: HashMap::new(),
+            cell_formats: HashMap::new(),
+            numbers: NumberCache::default(),
+            computed_values: ComputedCache::default(),
+            strings,
+            indexes: Indexes::default(),
+        }
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Stored rows; a row can be shorter than the column count when it ends
+    /// in empty cells
+    pub fn rows(&self) -> &[Vec<CellText>] {
+        &self.rows
+    }
+
+    /// Every cell of one row, padded with empty text to the column count
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &str> + '_ {
+        (0..self.column_count()).map(move |col| self.cell(row, col))
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Find a column by name (case-insensitive, like the C# FormulaAgent)
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+    }
+
+    /// Cell text, or an empty string when out of range
+    pub fn cell(&self, row: usize, col: usize) -> &str {
+        self.rows
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .map(CellText::as_str)
+            .unwrap_or("")
+    }
+
+    /// Overwrite a cell, failing when the address is outside the table
+    pub fn set_cell(&mut self, row: usize, col: usize, value: String) -> Result<(), String> {
+        let width = self.headers.len();
+        let Some(cells) = self.rows.get_mut(row).filter(|_| col < width) else {
+            return Err(format!("Cell ({}, {}) is out of range", row, col));
+        };
+        self.numbers.set(row, col, &value);
+        let value = self.strings.intern(value);
+        if let Some(index) = self.indexes.0.get_mut(&col) {
+            index.remove(row, cells.get(col).map_or("", CellText::as_str));
+            index.insert(row, &value);
+        }
+
+        if col >= cells.len() {
+            if value.is_empty() {
+                return Ok(());
+            }
+            cells.resize(col + 1, self.strings.intern(String::new()));
+        }
+        let old = std::mem::replace(&mut cells[col], value);
+        self.strings.release(old);
+        trim_row(cells, &mut self.strings);
+        self.refresh_computed(row, col);
+        Ok(())
+    }
+
+    /// True when only cell text can differ between the two tables
+    pub(crate) fn same_layout(&self, other: &TesseraTable) -> bool {
+        self.headers == other.headers
+            && self.rows.len() == other.rows.len()
+            && self.rules == other.rules
+            && self.computed == other.computed
+            && self.column_formats == other.column_formats
+            && self.cell_formats == other.cell_formats
+    }
+
+    /// Append a row, padding or truncating it to the column count
+    pub fn push_row(&mut self, mut cells: Vec<String>) {
+        cells.truncate(self.headers.len());
+        self.numbers.push_row(&cells);
+        self.computed_values.clear();
+        let mut cells = cells
+            .into_iter()
+            .map(|cell| self.strings.intern(cell))
+            .collect();
+        trim_row(&mut cells, &mut self.strings);
+        let row = self.rows.len();
+        for (&col, index) in &mut self.indexes.0 {
+            index.insert(row, cells.get(col).unwrap_or(&CellText::default()));
+        }
+        self.rows.push(cells);
+    }
+
+    /// Validation rules attached to this table
+    pub fn validation_rules(&self) -> &[ValidationRule] {
+        &self.rules
+    }
+
+    /// Attach a validation rule, failing when its column does not exist
+    pub fn add_validation_rule(&mut self, rule: ValidationRule) -> Result<(), String> {
+        if rule.column >= self.column_count() {
+            return Err(format!("Column {} is out of range", rule.column));
+        }
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Remove the rules of one column, or of every column when `None`
+    pub fn clear_validation_rules(&mut self, column: Option<usize>) {
+        self.rules
+            .retain(|rule| column.is_some_and(|col| rule.column != col));
+    }
+
+    /// Set or clear (`None`) the number format of a whole column
+    pub fn set_column_format(
+        &mut self,
+        col: usize,
+        format: Option<NumberFormat>,
+    ) -> Result<(), String> {
+        if col >= self.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+        match format {
+            Some(format) => self.column_formats.insert(col, format),
+            None => self.column_formats.remove(&col),
+        };
+        Ok(())
+    }
+
+    /// Set or clear (`None`) a number format for one cell, overriding its column
+    pub fn set_cell_format(
+        &mut self,
+        row: usize,
+        col: usize,
+        format: Option<NumberFormat>,
+    ) -> Result<(), String> {
+        if row >= self.row_count() || col >= self.column_count() {
+            return Err(format!("Cell ({}, {}) is out of range", row, col));
+        }
+        match format {
+            Some(format) => self.cell_formats.insert((row, col), format),
+            None => self.cell_formats.remove(&(row, col)),
+        };
+        Ok(())
+    }
+
+    /// Column formats by column index
+    pub fn column_formats(&self) -> &HashMap<usize, NumberFormat> {
+        &self.column_formats
+    }
+
+    /// Per-cell format overrides by (row, col)
+    pub fn cell_formats(&self) -> &HashMap<(usize, usize), NumberFormat> {
+        &self.cell_formats
+    }
+
+    /// Number format applying to a cell: its own, else its column's
+    pub fn number_format(&self, row: usize, col: usize) -> Option<&NumberFormat> {
+        self.cell_formats
+            .get(&(row, col))
+            .or_else(|| self.column_formats.get(&col))
+    }
+
+    /// Apply `f` to every reference of every formula cell
+    pub(crate) fn rewrite_formulas(&mut self, mut f: impl FnMut(Reference) -> Option<Reference>) {
+        for cell in self.rows.iter_mut().flatten() {
+            if is_formula(cell) {
+                let text = self.strings.intern(rewrite_references(cell, &mut f));
+                self.strings.release(std::mem::replace(cell, text));
+            }
+        }
+        let indexed = std::mem::take(&mut self.indexes.0);
+        self.reindex(indexed, Some);
+    }
+
+    /// Move per-cell and per-column metadata after a structural edit
+    fn shift_metadata(&mut self, at: usize, count: isize, rows: bool) {
+        self.cell_formats = std::mem::take(&mut self.cell_formats)
+            .into_iter()
+            .filter_map(|((row, col), format)| {
+                let key = if rows {
+                    (shift_index(row, at, count)?, col)
+                } else {
+                    (row, shift_index(col, at, count)?)
+                };
+                Some((key, format))
+            })
+            .collect();
+        if rows {
+            return;
+        }
+        self.column_formats = std::mem::take(&mut self.column_formats)
+            .into_iter()
+            .filter_map(|(col, format)| Some((shift_index(col, at, count)?, format)))
+            .collect();
+        self.rules
+            .retain_mut(|rule| match shift_index(rule.column, at, count) {
+                Some(col) => {
+                    rule.column = col;
+                    true
+                }
+                None => false,
+            });
+        self.computed
+            .retain_mut(|computed| match shift_index(computed.column, at, count) {
+                Some(col) => {
+                    computed.column = col;
+                    true
+                }
+                None => false,
+            });
+    }
+
+    /// Insert `count` empty rows before row `at` (`at == row_count` appends)
+    ///
+    /// Formula references at or below `at` move down; ranges spanning the
+    /// insertion point grow.
+    pub fn insert_rows(&mut self, at: usize, count: usize) -> Result<(), String> {
+        if at > self.row_count() {
+            return Err(format!("Row {} is out of range", at));
+        }
+        let indexed = std::mem::take(&mut self.indexes.0);
+        self.rows.splice(at..at, (0..count).map(|_| Vec::new()));
+        self.numbers.clear();
+        self.computed_values.clear();
+        self.rewrite_formulas(|r| shift_reference(r, at, count as isize, true));
+        self.shift_metadata(at, count as isize, true);
+        self.reindex(indexed, Some);
+        Ok(())
+    }
+
+    /// Delete rows `at..at + count`; references to them become `#REF!`
+    pub fn delete_rows(&mut self, at: usize, count: usize) -> Result<(), String> {
+        if at.saturating_add(count) > self.row_count() {
+            return Err(format!(
+                "Rows {}..{} are out of range",
+                at,
+                at.saturating_add(count)
+            ));
+        }
+        let indexed = std::mem::take(&mut self.indexes.0);
+        self.rows.drain(at..at + count);
+        self.strings.prune();
+        self.numbers.clear();
+        self.computed_values.clear();
+        self.rewrite_formulas(|r| shift_reference(r, at, -(count as isize), true));
+        self.shift_metadata(at, -(count as isize), true);
+        self.reindex(indexed, Some);
+        Ok(())
+    }
+
+    /// Insert columns named `names` before column `at`
+    pub fn insert_columns(&mut self, at: usize, names: Vec<String>) -> Result<(), String> {
+        if at > self.column_count() {
+            return Err(format!("Column {} is out of range", at));
+        }
+        let count = names.len();
+        let indexed = std::mem::take(&mut self.indexes.0);
+        self.headers.splice(at..at, names);
+        let empty = self.strings.intern(String::new());
+        for row in self.rows.iter_mut().filter(|row| row.len() > at) {
+            row.splice(at..at, (0..count).map(|_| empty.clone()));
+        }
+        self.numbers.clear();
+        self.computed_values.clear();
+        self.rewrite_formulas(|r| shift_reference(r, at, count as isize, false));
+        self.shift_metadata(at, count as isize, false);
+        self.reindex(indexed, |col| shift_index(col, at, count as isize));
+        Ok(())
+    }
+
+    /// Delete columns `at..at + count` along with their formats and rules
+    pub fn delete_columns(&mut self, at: usize, count: usize) -> Result<(), String> {
+        if at.saturating_add(count) > self.column_count() {
+            return Err(format!(
+                "Columns {}..{} are out of range",
+                at,
+                at.saturating_add(count)
+            ));
+        }
+        let indexed = std::mem::take(&mut self.indexes.0);
+        self.headers.drain(at..at + count);
+        for row in &mut self.rows {
+            row.drain(at.min(row.len())..(at + count).min(row.len()));
+            trim_row(row, &mut self.strings);
+        }
+        self.strings.prune();
+        self.numbers.clear();
+        self.computed_values.clear();
+        self.rewrite_formulas(|r| shift_reference(r, at, -(count as isize), false));
+        self.shift_metadata(at, -(count as isize), false);
+        self.reindex(indexed, |col| shift_index(col, at, -(count as isize)));
+        Ok(())
+    }
+
+    /// Reorder rows so that row `order[i]` ends up at position `i`
+    ///
+    /// `order` must be a permutation of all row indices, such as the result
+    /// of a sort. Single-cell formula references follow the rows they point
+    /// at, so a formula like `=A2*B2` keeps reading its own row; ranges are
+    /// left as they are since they cannot follow scattered rows.
+    pub fn reorder_rows(&mut self, order: &[usize]) -> Result<(), String> {
+        let count = self.row_count();
+        let mut new_index = vec![usize::MAX; count];
+        for (position, &row) in order.iter().enumerate() {
+            if row >= count || new_index[row] != usize::MAX {
+                return Err("Row order is not a permutation of the table rows".to_string());
+            }
+            new_index[row] = position;
+        }
+        if order.len() != count {
+            return Err("Row order is not a permutation of the table rows".to_string());
+        }
+
+        let indexed = std::mem::take(&mut self.indexes.0);
+        let mut old_rows: Vec<Option<Vec<CellText>>> = std::mem::take(&mut self.rows)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.rows = order
+            .iter()
+            .map(|&row| old_rows[row].take().unwrap_or_default())
+            .collect();
+        self.numbers.clear();
+        self.computed_values.clear();
+        self.rewrite_formulas(|reference| match reference {
+            Reference::Cell(mut cell) if cell.row < count => {
+                cell.row = new_index[cell.row];
+                Some(Reference::Cell(cell))
+            }
+            _ => Some(reference),
+        });
+        self.cell_formats = std::mem::take(&mut self.cell_formats)
+            .into_iter()
+            .map(|((row, col), format)| ((new_index[row], col), format))
+            .collect();
+        self.reindex(indexed, Some);
+        Ok(())
+    }
+
+    /// Index a column for [`Self::lookup`]; `sorted` also keeps its numbers
+    /// ordered for [`Self::lookup_range`]
+    pub fn create_index(&mut self, col: usize, sorted: bool) -> Result<(), String> {
+        if col >= self.column_count() {
+            return Err(format!("Column {} is out of range", col));
+        }
+        let index = self.build_index(col, sorted);
+        self.indexes.0.insert(col, index);
+        Ok(())
+    }
+
+    /// Remove a column's index, returning whether there was one
+    pub fn drop_index(&mut self, col: usize) -> bool {
+        self.indexes.0.remove(&col).is_some()
+    }
+
+    fn build_index(&self, col: usize, sorted: bool) -> ColumnIndex {
+        let empty = CellText::default();
+        let cells = self
+            .rows
+            .iter()
+            .map(|cells| cells.get(col).unwrap_or(&empty));
+        ColumnIndex::build(cells, sorted)
+    }
+
+    /// Rebuild indexes taken out before a structural edit, following their
+    /// columns to where `column` says they moved
+    fn reindex(
+        &mut self,
+        indexed: HashMap<usize, ColumnIndex>,
+        column: impl Fn(usize) -> Option<usize>,
+    ) {
+        for (col, index) in indexed {
+            if let Some(col) = column(col) {
+                let rebuilt = self.build_index(col, index.is_sorted());
+                self.indexes.0.insert(col, rebuilt);
+            }
+        }
+    }
+
+    pub fn is_indexed(&self, col: usize) -> bool {
+        self.indexes.0.contains_key(&col)
+    }
+
+    /// Rows of an indexed column holding exactly `text`, without scanning
+    pub(crate) fn indexed_rows(&self, col: usize, text: &str) -> Option<&[usize]> {
+        Some(self.indexes.0.get(&col)?.lookup(text))
+    }
+
+    /// Rows whose cell in `col` is exactly `text`, in row order
+    ///
+    /// O(1) when the column is indexed, otherwise a scan.
+    pub fn lookup(&self, col: usize, text: &str) -> Vec<usize> {
+        match self.indexed_rows(col, text) {
+            Some(rows) => rows.to_vec(),
+            None => (0..self.row_count())
+                .filter(|&row| self.cell(row, col) == text)
+                .collect(),
+        }
+    }
+
+    /// Rows whose number in `col` lies in `min..=max`, ordered by value
+    ///
+    /// O(log n) plus the result size with a sorted index, otherwise a scan
+    /// and sort.
+    pub fn lookup_range(&self, col: usize, min: f64, max: f64) -> Vec<usize> {
+        if let Some(rows) = self.indexes.0.get(&col).and_then(|i| i.range(min, max)) {
+            return rows;
+        }
+        let mut found: Vec<(f64, usize)> = self
+            .column(col)
+            .enumerate()
+            .filter_map(|(row, cell)| Some((parse_cell(cell)?, row)))
+            .filter(|(value, _)| (min..=max).contains(value))
+            .collect();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        found.into_iter().map(|(_, row)| row).collect()
+    }
+
+    /// Numeric value of every cell in a column (`None` for text and empty
+    /// cells), parsed once and cached until the column changes shape
+    pub fn numbers(&self, col: usize) -> Arc<Vec<Option<f64>>> {
+        self.numbers
+            .numbers(col, || self.column(col).map(parse_cell).collect())
+    }
+
+    /// Count, sum, min and max of the numeric cells in a column, kept up to
+    /// date across cell edits so repeated calls are O(1)
+    pub fn column_stats(&self, col: usize) -> ColumnStats {
+        self.numbers
+            .stats(col, || self.column(col).map(parse_cell).collect())
+    }
+
+    /// Iterate over the cells of one column
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &str> + '_ {
+        self.rows
+            .iter()
+            .map(move |cells| cells.get(col).map(CellText::as_str).unwrap_or(""))
+    }
+}
+
+/// Cell address passed across the FFI boundary
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellPosition {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Rectangular block of cells (`rows` x `cols` starting at `row`, `col`)
+///
+/// Ranges reaching past the table are clamped to it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRange {
+    pub row: usize,
+    pub col: usize,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl CellRange {
+    /// Range covering every cell of a table
+    pub fn all(table: &TesseraTable) -> Self {
+        CellRange {
+            row: 0,
+            col: 0,
+            rows: table.row_count(),
+            cols: table.column_count(),
+        }
+    }
+
+    /// Range covering every row of one column
+    pub fn column(table: &TesseraTable, col: usize) -> Self {
+        CellRange {
+            row: 0,
+            col,
+            rows: table.row_count(),
+            cols: 1,
+        }
+    }
+
+    /// Row and column bounds clamped to the table
+    pub fn clamp(&self, table: &TesseraTable) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let rows = self.row.min(table.row_count())
+            ..self.row.saturating_add(self.rows).min(table.row_count());
+        let cols = self.col.min(table.column_count())
+            ..self.col.saturating_add(self.cols).min(table.column_count());
+        (rows, cols)
+    }
+}
+
+/// FFI-safe result for functions that produce a new table handle
+#[repr(C)]
+pub struct TableResult {
+    pub table: *mut TesseraTable,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl TableResult {
+    pub(crate) fn success(table: TesseraTable) -> Self {
+        TableResult {
+            table: Box::into_raw(Box::new(table)),
+            error: std::ptr::null_mut(),
+        }
+    }
+
+    pub(crate) fn error(msg: &str) -> Self {
+        TableResult {
+            table: std::ptr::null_mut(),
+            error: error_string(msg),
+        }
+    }
+}
+
+impl From<Result<TesseraTable, String>> for TableResult {
+    fn from(result: Result<TesseraTable, String>) -> Self {
+        match result {
+            Ok(table) => TableResult::success(table),
+            Err(msg) => TableResult::error(&msg),
+        }
+    }
+}
+
+/// Borrow a table handle passed in from the host
+///
+/// # Safety
+/// `table` must be null or a live handle returned by this library
+pub(crate) unsafe fn table_arg<'a>(table: *const TesseraTable) -> Option<&'a TesseraTable> {
+    table.as_ref()
+}
+
+/// Mutably borrow a table handle passed in from the host
+///
+/// # Safety
+/// `table` must be null or a live handle returned by this library, not aliased
+pub(crate) unsafe fn table_arg_mut<'a>(table: *mut TesseraTable) -> Option<&'a mut TesseraTable> {
+    table.as_mut()
+}
+
+/// Create an empty table with the given column names
+///
+/// # Safety
+/// `headers_ptr` must point to `count` valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_new(
+    headers_ptr: *const *const c_char,
+    count: usize,
+) -> *mut TesseraTable {
+    match str_array_arg(headers_ptr, count) {
+        Some(headers) => Box::into_raw(Box::new(TesseraTable::new(headers))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Release a table handle
+///
+/// # Safety
+/// `table` must be null or a handle returned by this library that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_free(table: *mut TesseraTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+/// Number of data rows (header excluded)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_row_count(table: *const TesseraTable) -> usize {
+    table_arg(table).map_or(0, TesseraTable::row_count)
+}
+
+/// Number of columns
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_column_count(table: *const TesseraTable) -> usize {
+    table_arg(table).map_or(0, TesseraTable::column_count)
+}
+
+/// Column name at `col` (caller must free with tessera_free_string)
+///
+/// Returns null when the handle is null or the column is out of range.
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_header(
+    table: *const TesseraTable,
+    col: usize,
+) -> *mut c_char {
+    match table_arg(table).and_then(|t| t.headers().get(col)) {
+        Some(header) => to_c_string(header),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Cell text at (`row`, `col`) (caller must free with tessera_free_string)
+///
+/// Out-of-range cells read as empty strings; a null handle returns null.
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_get_cell(
+    table: *const TesseraTable,
+    row: usize,
+    col: usize,
+) -> *mut c_char {
+    match table_arg(table) {
+        Some(t) => to_c_string(t.cell(row, col)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Overwrite the cell at (`row`, `col`)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle; `value` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_set_cell(
+    table: *mut TesseraTable,
+    row: usize,
+    col: usize,
+    value: *const c_char,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(value) = str_arg(value) else {
+        return error_string("Invalid cell value encoding");
+    };
+
+    match t.set_cell(row, col, value.to_string()) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Append a row of `count` values (padded or truncated to the column count)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle; `values_ptr` must point to `count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_push_row(
+    table: *mut TesseraTable,
+    values_ptr: *const *const c_char,
+    count: usize,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(values) = str_array_arg(values_ptr, count) else {
+        return error_string("Null pointer provided");
+    };
+
+    t.push_row(values);
+    std::ptr::null_mut()
+}
+
+/// Insert `count` empty rows before row `at`, adjusting formula references
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_insert_rows(
+    table: *mut TesseraTable,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.insert_rows(at, count) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Delete rows `at..at + count`; formula references to them become `#REF!`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_delete_rows(
+    table: *mut TesseraTable,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.delete_rows(at, count) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Insert `count` columns named by `names_ptr` before column `at`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle; `names_ptr` must point to `count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_insert_columns(
+    table: *mut TesseraTable,
+    at: usize,
+    names_ptr: *const *const c_char,
+    count: usize,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(names) = str_array_arg(names_ptr, count) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.insert_columns(at, names) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Delete columns `at..at + count`; formula references to them become `#REF!`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_delete_columns(
+    table: *mut TesseraTable,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.delete_columns(at, count) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Reorder rows in place, e.g. with a permutation from tessera_sort
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `order_ptr` - Entry `i` is the current row that moves to position `i`
+/// * `count` - Number of entries (must equal the row count)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle; `order_ptr` must point to `count` indices
+#[no_mangle]
+pub unsafe extern "C" fn tessera_table_reorder_rows(
+    table: *mut TesseraTable,
+    order_ptr: *const usize,
+    count: usize,
+) -> *mut c_char {
+    let (Some(t), Some(order)) = (table_arg_mut(table), slice_arg(order_ptr, count)) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.reorder_rows(order) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Index a column so lookups and joins on it stop scanning
+///
+/// The index follows cell edits and appended rows and is rebuilt after
+/// structural edits; creating it again replaces it.
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `column` - Column to index
+/// * `sorted` - Also keep the column's numbers ordered for tessera_lookup_range
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_create_index(
+    table: *mut TesseraTable,
+    column: usize,
+    sorted: bool,
+) -> *mut c_char {
+    let Some(t) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+
+    match t.create_index(column, sorted) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Remove a column's index; returns false when it had none
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_drop_index(table: *mut TesseraTable, column: usize) -> bool {
+    table_arg_mut(table).is_some_and(|t| t.drop_index(column))
+}
+
+/// Rows whose cell in `column` equals `value` exactly
+///
+/// # Returns
+/// IndexArray of row indices in row order (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle; `value` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_lookup(
+    table: *const TesseraTable,
+    column: usize,
+    value: *const c_char,
+) -> IndexArray {
+    let Some(t) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    let Some(value) = str_arg(value) else {
+        return IndexArray::error("Invalid cell value encoding");
+    };
+    if column >= t.column_count() {
+        return IndexArray::error(&format!("Column {} is out of range", column));
+    }
+
+    IndexArray::success(t.lookup(column, value))
+}
+
+/// Rows whose number in `column` lies in `min..=max`, ordered by value
+///
+/// # Returns
+/// IndexArray of row indices (free with tessera_free_index_array)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_lookup_range(
+    table: *const TesseraTable,
+    column: usize,
+    min: f64,
+    max: f64,
+) -> IndexArray {
+    let Some(t) = table_arg(table) else {
+        return IndexArray::error("Null pointer provided");
+    };
+    if column >= t.column_count() {
+        return IndexArray::error(&format!("Column {} is out of range", column));
+    }
+
+    IndexArray::success(t.lookup_range(column, min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rows_pads_ragged_rows() {
+        let table = TesseraTable::from_rows(
+            vec!["Name".into()],
+            vec![vec!["a".into(), "1".into()], vec!["b".into()]],
+        );
+
+        assert_eq!(table.headers(), ["Name", "Column2"]);
+        assert_eq!(table.cell(1, 1), "");
+        assert_eq!(table.cell(0, 1), "1");
+    }
+
+    #[test]
+    fn test_set_cell_out_of_range() {
+        let mut table = TesseraTable::new(vec!["A".into()]);
+        table.push_row(vec!["1".into(), "ignored".into()]);
+
+        assert!(table.set_cell(0, 0, "2".into()).is_ok());
+        assert!(table.set_cell(0, 1, "x".into()).is_err());
+        assert_eq!(table.rows()[0], ["2"]);
+    }
+
+    #[test]
+    fn test_repeated_values_share_text() {
+        let mut table = TesseraTable::from_rows(
+            vec!["Country".into()],
+            vec![vec!["VN".into()], vec!["US".into()], vec!["VN".into()]],
+        );
+        table.push_row(vec!["US".into()]);
+        let at = |table: &TesseraTable, row| table.cell(row, 0).as_ptr();
+        assert_eq!(at(&table, 0), at(&table, 2));
+        assert_eq!(at(&table, 1), at(&table, 3));
+
+        table.set_cell(2, 0, "US".into()).unwrap();
+        assert_eq!(at(&table, 1), at(&table, 2));
+        assert_eq!(table.rows()[2], ["US"]);
+    }
+
+    #[test]
+    fn test_blank_cells_are_not_stored() {
+        let mut table = TesseraTable::new(vec!["A".into(), "B".into()]);
+        table.push_row(vec!["x".into(), "".into()]);
+        table.insert_rows(1, 100_000).unwrap();
+        table.set_cell(100_000, 1, "=A1".into()).unwrap();
+
+        assert!(table.rows()[1..100_000].iter().all(Vec::is_empty));
+        assert_eq!(table.rows()[0].len(), 1);
+        assert_eq!(table.row(100_000).collect::<Vec<_>>(), ["", "=A1"]);
+
+        table.set_cell(100_000, 1, "".into()).unwrap();
+        assert!(table.rows()[100_000].is_empty());
+    }
+
+    #[test]
+    fn test_column_stats_follow_edits() {
+        let mut table = TesseraTable::from_rows(
+            vec!["A".into()],
+            vec![vec!["4".into()], vec!["x".into()], vec!["1".into()]],
+        );
+        let stats = table.column_stats(0);
+        assert_eq!((stats.count, stats.sum), (2, 5.0));
+        assert_eq!((stats.min, stats.max), (Some(1.0), Some(4.0)));
+
+        // Replacing the minimum forces a rescan of the extremes
+        table.set_cell(2, 0, "7".into()).unwrap();
+        table.set_cell(1, 0, "2".into()).unwrap();
+        table.push_row(vec!["-3".into()]);
+        let stats = table.column_stats(0);
+        assert_eq!((stats.count, stats.sum), (4, 10.0));
+        assert_eq!((stats.min, stats.max), (Some(-3.0), Some(7.0)));
+        assert_eq!(stats.mean(), Some(2.5));
+
+        table.set_cell(2, 0, "".into()).unwrap();
+        assert_eq!(table.column_stats(0).max, Some(4.0));
+    }
+
+    #[test]
+    fn test_index_follows_edits() {
+        let mut table = TesseraTable::from_rows(
+            vec!["Id".into(), "Amount".into()],
+            vec![
+                vec!["a".into(), "5".into()],
+                vec!["b".into(), "1".into()],
+                vec!["a".into(), "3".into()],
+            ],
+        );
+        table.create_index(0, false).unwrap();
+        table.create_index(1, true).unwrap();
+        assert_eq!(table.indexed_rows(0, "a"), Some(&[0, 2][..]));
+        assert_eq!(table.lookup_range(1, 2.0, 5.0), [2, 0]);
+
+        table.set_cell(1, 0, "a".into()).unwrap();
+        table.set_cell(0, 1, "0".into()).unwrap();
+        table.push_row(vec!["c".into(), "4".into()]);
+        assert_eq!(table.lookup(0, "a"), [0, 1, 2]);
+        assert_eq!(table.lookup_range(1, 0.0, 10.0), [0, 1, 2, 3]);
+
+        table.insert_columns(0, vec!["New".into()]).unwrap();
+        table.delete_rows(0, 1).unwrap();
+        assert_eq!(table.indexed_rows(1, "a"), Some(&[0, 1][..]));
+        assert_eq!(table.indexed_rows(0, "a"), None);
+        assert_eq!(table.lookup_range(2, 3.0, 4.0), [1, 2]);
+    }
+
+    #[test]
+    fn test_number_cache_follows_edits() {
+        let mut table =
+            TesseraTable::from_rows(vec!["A".into()], vec![vec!["1".into()], vec!["x".into()]]);
+        assert_eq!(*table.numbers(0), [Some(1.0), None]);
+
+        let held = table.numbers(0);
+        table.set_cell(1, 0, " 2.5 ".into()).unwrap();
+        table.push_row(vec!["3".into()]);
+        assert_eq!(*table.numbers(0), [Some(1.0), Some(2.5), Some(3.0)]);
+        assert_eq!(*held, [Some(1.0), None]);
+
+        table.delete_rows(0, 1).unwrap();
+        assert_eq!(*table.numbers(0), [Some(2.5), Some(3.0)]);
+    }
+
+    #[test]
+    fn test_structural_edits_adjust_formulas() {
+        let mut table = TesseraTable::from_rows(
+            vec!["A".into(), "B".into(), "C".into()],
+            vec![
+                vec!["1".into(), "2".into(), "=A1+B1".into()],
+                vec!["3".into(), "4".into(), "=SUM(A1:A2) * $B$2".into()],
+            ],
+        );
+
+        table.insert_rows(1, 1).unwrap();
+        assert_eq!(table.cell(0, 2), "=A1+B1");
+        assert_eq!(table.cell(2, 2), "=SUM(A1:A3) * $B$3");
+
+        table.delete_columns(0, 1).unwrap();
+        assert_eq!(table.headers(), ["B", "C"]);
+        assert_eq!(table.cell(0, 1), "=#REF!+A1");
+        assert_eq!(table.cell(2, 1), "=SUM(#REF!) * $A$3");
+
+        table.insert_columns(0, vec!["New".into()]).unwrap();
+        table.delete_rows(0, 2).unwrap();
+        assert_eq!(table.rows(), [vec!["", "4", "=SUM(#REF!) * $B$1"]]);
+        assert!(table.delete_rows(0, 2).is_err());
+    }
+}
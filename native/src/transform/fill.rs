@@ -0,0 +1,326 @@
+//! Autofill series detection, mirroring spreadsheet drag-fill
+//!
+//! The seed cells decide the series: numbers continue their linear trend,
+//! dates step by days or whole months, text ending in a number counts up,
+//! weekday and month names cycle, and anything else repeats the seed.
+
+use std::os::raw::c_char;
+
+use chrono::{Datelike, Months, NaiveDateTime, Timelike};
+
+use crate::datetime::{from_serial, parse_with_format, to_serial};
+use crate::ffi::str_array_arg;
+use crate::formula::value::format_number;
+use crate::StringResult;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Generate the next `count` values following `seed`
+pub fn fill_series(seed: &[String], count: usize) -> Vec<String> {
+    let seed: Vec<&str> = seed.iter().map(|s| s.trim()).collect();
+    if seed.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    fill_numbers(&seed, count)
+        .or_else(|| fill_dates(&seed, count))
+        .or_else(|| fill_named(&seed, count, &WEEKDAYS))
+        .or_else(|| fill_named(&seed, count, &MONTHS))
+        .or_else(|| fill_numbered_text(&seed, count))
+        .unwrap_or_else(|| {
+            (0..count)
+                .map(|i| seed[i % seed.len()].to_string())
+                .collect()
+        })
+}
+
+/// Least-squares line through (0, y0), (1, y1), ... as (intercept, slope)
+fn linear_trend(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (mut num, mut den) = (0.0, 0.0);
+    for (i, y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        num += dx * (y - mean_y);
+        den += dx * dx;
+    }
+    let slope = if den == 0.0 { 0.0 } else { num / den };
+    (mean_y - slope * mean_x, slope)
+}
+
+fn decimals(text: &str) -> usize {
+    text.split_once('.').map_or(0, |(_, frac)| {
+        frac.chars().take_while(char::is_ascii_digit).count()
+    })
+}
+
+/// A single number repeats; two or more continue their linear trend
+fn fill_numbers(seed: &[&str], count: usize) -> Option<Vec<String>> {
+    let numbers: Vec<f64> = seed
+        .iter()
+        .map(|s| s.parse::<f64>().ok().filter(|n| n.is_finite()))
+        .collect::<Option<_>>()?;
+    if numbers.len() == 1 {
+        return Some(vec![seed[0].to_string(); count]);
+    }
+
+    let (intercept, slope) = linear_trend(&numbers);
+    // Round to the seed precision so 0.1 + 0.2 style noise never shows up
+    let factor = 10f64.powi(seed.iter().map(|s| decimals(s)).max().unwrap_or(0).min(15) as i32);
+    Some(
+        (0..count)
+            .map(|i| {
+                let value = intercept + slope * (numbers.len() + i) as f64;
+                format_number((value * factor).round() / factor)
+            })
+            .collect(),
+    )
+}
+
+/// Dates step by whole months when every seed falls on the same day of the
+/// month (or every seed is a month end), otherwise by their (linear) day spacing; a single date steps by a day
+fn fill_dates(seed: &[&str], count: usize) -> Option<Vec<String>> {
+    let parsed: Vec<(NaiveDateTime, &str)> = seed
+        .iter()
+        .map(|s| parse_with_format(s))
+        .collect::<Option<_>>()?;
+    let format = parsed[parsed.len() - 1].1;
+    let dates: Vec<NaiveDateTime> = parsed.iter().map(|(d, _)| *d).collect();
+    let last = dates[dates.len() - 1];
+    let write = |dt: NaiveDateTime| dt.format(format).to_string();
+
+    if dates.len() == 1 {
+        return Some(
+            (1..=count as i64)
+                .filter_map(|i| last.checked_add_signed(chrono::Duration::days(i)))
+                .map(write)
+                .collect(),
+        );
+    }
+
+    let month_index = |d: &NaiveDateTime| d.year() as i64 * 12 + d.month0() as i64;
+    let steps: Vec<i64> = dates
+        .windows(2)
+        .map(|w| month_index(&w[1]) - month_index(&w[0]))
+        .collect();
+    let same_time = dates
+        .iter()
+        .all(|d| d.num_seconds_from_midnight() == dates[0].num_seconds_from_midnight());
+    let same_day = dates.iter().all(|d| d.day() == dates[0].day());
+    let month_end = dates.iter().all(is_month_end);
+    if same_time && (same_day || month_end) && steps[0] != 0 && steps.iter().all(|&s| s == steps[0])
+    {
+        let step = steps[0];
+        return Some(
+            (1..=count as i64)
+                .filter_map(|i| {
+                    let next = add_months(last, step * i)?;
+                    if month_end {
+                        last_of_month(next)
+                    } else {
+                        Some(next)
+                    }
+                })
+                .map(write)
+                .collect(),
+        );
+    }
+
+    let serials: Vec<f64> = dates.iter().map(|d| to_serial(*d)).collect();
+    let (intercept, slope) = linear_trend(&serials);
+    Some(
+        (0..count)
+            .filter_map(|i| from_serial(intercept + slope * (serials.len() + i) as f64))
+            .map(write)
+            .collect(),
+    )
+}
+
+fn add_months(dt: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    if months >= 0 {
+        dt.checked_add_months(Months::new(u32::try_from(months).ok()?))
+    } else {
+        dt.checked_sub_months(Months::new(u32::try_from(months.unsigned_abs()).ok()?))
+    }
+}
+
+fn is_month_end(dt: &NaiveDateTime) -> bool {
+    dt.date().succ_opt().is_some_and(|next| next.day() == 1)
+}
+
+fn last_of_month(dt: NaiveDateTime) -> Option<NaiveDateTime> {
+    let first = dt.with_day(1)?;
+    let next_month = add_months(first, 1)?;
+    next_month.checked_sub_signed(chrono::Duration::days(1))
+}
+
+/// Match the capitalization of `sample` (UPPER, lower or Title)
+fn match_case(name: &str, sample: &str) -> String {
+    if sample.chars().all(|c| !c.is_lowercase()) {
+        name.to_uppercase()
+    } else if sample.chars().all(|c| !c.is_uppercase()) {
+        name.to_lowercase()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Cycle through weekday or month names, full or three-letter
+fn fill_named(seed: &[&str], count: usize, names: &[&str]) -> Option<Vec<String>> {
+    let lookup = |text: &str| -> Option<(usize, bool)> {
+        names.iter().enumerate().find_map(|(i, name)| {
+            if name.eq_ignore_ascii_case(text) {
+                Some((i, false))
+            } else if text.len() == 3 && name[..3].eq_ignore_ascii_case(text) {
+                Some((i, true))
+            } else {
+                None
+            }
+        })
+    };
+    let found: Vec<(usize, bool)> = seed.iter().map(|s| lookup(s)).collect::<Option<_>>()?;
+
+    let len = names.len() as i64;
+    let step = if found.len() > 1 {
+        (found[found.len() - 1].0 as i64 - found[found.len() - 2].0 as i64).rem_euclid(len)
+    } else {
+        1
+    };
+    let (last, short) = found[found.len() - 1];
+    let sample = seed[seed.len() - 1];
+
+    Some(
+        (1..=count as i64)
+            .map(|i| {
+                let name = names[(last as i64 + step * i).rem_euclid(len) as usize];
+                match_case(if short { &name[..3] } else { name }, sample)
+            })
+            .collect(),
+    )
+}
+
+/// Split `Item 007b` into ("Item ", "007", "b"): the last run of digits
+fn split_number(text: &str) -> Option<(&str, &str, &str)> {
+    let end = text.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = text[..end]
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .len();
+    Some((&text[..start], &text[start..end], &text[end..]))
+}
+
+/// Text with a trailing number counts up by one, or by the seed step
+fn fill_numbered_text(seed: &[&str], count: usize) -> Option<Vec<String>> {
+    let parts: Vec<(&str, &str, &str)> = seed
+        .iter()
+        .map(|s| split_number(s))
+        .collect::<Option<_>>()?;
+    let (prefix, digits, suffix) = parts[parts.len() - 1];
+    if parts.iter().any(|(p, _, s)| *p != prefix || *s != suffix) {
+        return None;
+    }
+
+    let numbers: Vec<i64> = parts
+        .iter()
+        .map(|(_, d, _)| d.parse().ok())
+        .collect::<Option<_>>()?;
+    let step = match numbers.len() {
+        1 => 1,
+        n => numbers[n - 1] - numbers[n - 2],
+    };
+    let width = if digits.starts_with('0') {
+        digits.len()
+    } else {
+        0
+    };
+    let last = numbers[numbers.len() - 1];
+
+    Some(
+        (1..=count as i64)
+            .map(|i| {
+                let value = last.saturating_add(step.saturating_mul(i)).unsigned_abs();
+                format!("{}{:0width$}{}", prefix, value, suffix, width = width)
+            })
+            .collect(),
+    )
+}
+
+/// Extend a seed selection the way drag-fill does
+///
+/// # Arguments
+/// * `values_ptr` - Seed cell values, in fill direction order
+/// * `seed_count` - Number of seed values
+/// * `fill_count` - Number of values to generate
+///
+/// # Returns
+/// StringResult with a JSON array of the generated values
+///
+/// # Safety
+/// `values_ptr` must point to `seed_count` valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_autofill(
+    values_ptr: *const *const c_char,
+    seed_count: usize,
+    fill_count: usize,
+) -> StringResult {
+    let Some(seed) = str_array_arg(values_ptr, seed_count) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    StringResult::success(&serde_json::Value::from(fill_series(&seed, fill_count)).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(seed: &[&str], count: usize) -> Vec<String> {
+        let seed: Vec<String> = seed.iter().map(|s| s.to_string()).collect();
+        fill_series(&seed, count)
+    }
+
+    #[test]
+    fn test_numeric_and_text_series() {
+        assert_eq!(fill(&["1", "3"], 3), ["5", "7", "9"]);
+        assert_eq!(fill(&["0.1", "0.2"], 2), ["0.3", "0.4"]);
+        assert_eq!(fill(&["7"], 2), ["7", "7"]);
+        assert_eq!(fill(&["Item 9"], 2), ["Item 10", "Item 11"]);
+        assert_eq!(fill(&["file008", "file010"], 2), ["file012", "file014"]);
+        assert_eq!(fill(&["Café9"], 2), ["Café10", "Café11"]);
+        assert_eq!(fill(&["🙂10", "🙂12"], 1), ["🙂14"]);
+        assert_eq!(fill(&["a", "b"], 3), ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_date_and_name_series() {
+        assert_eq!(fill(&["2024-01-30"], 2), ["2024-01-31", "2024-02-01"]);
+        assert_eq!(fill(&["01/31/2024", "02/29/2024"], 1), ["03/31/2024"]);
+        assert_eq!(fill(&["2024-01-01", "2024-01-08"], 1), ["2024-01-15"]);
+        assert_eq!(fill(&["2024-01-15", "2024-04-15"], 1), ["2024-07-15"]);
+        assert_eq!(fill(&["Mon", "Wed"], 3), ["Fri", "Sun", "Tue"]);
+        assert_eq!(fill(&["NOVEMBER"], 2), ["DECEMBER", "JANUARY"]);
+    }
+}
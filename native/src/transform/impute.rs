@@ -0,0 +1,187 @@
+//! Filling the gaps (empty cells) of a column
+//!
+//! The table is not changed: the filled column comes back as a copy along
+//! with the rows that were filled, so the host can preview it, write it back
+//! or mark the imputed cells.
+
+use std::os::raw::c_char;
+
+use crate::ffi::str_arg;
+use crate::formula::value::format_number;
+use crate::table::{table_arg, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GapFill {
+    /// Straight line between the numbers on either side of a gap; gaps
+    /// before the first or after the last number stay empty
+    Linear,
+    /// Last value above the gap
+    Forward,
+    /// First value below the gap
+    Backward,
+    /// The same text in every gap
+    Constant(String),
+}
+
+impl GapFill {
+    pub(crate) fn from_raw(raw: u32, constant: &str) -> Option<Self> {
+        match raw {
+            0 => Some(GapFill::Linear),
+            1 => Some(GapFill::Forward),
+            2 => Some(GapFill::Backward),
+            3 => Some(GapFill::Constant(constant.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilledColumn {
+    /// Every cell of the column, gaps filled where possible
+    pub values: Vec<String>,
+    /// Rows that were empty and got a value, in row order
+    pub imputed: Vec<usize>,
+}
+
+/// Copy of column `col` with its empty cells filled by `method`
+pub fn fill_gaps(
+    table: &TesseraTable,
+    col: usize,
+    method: &GapFill,
+) -> Result<FilledColumn, String> {
+    if col >= table.column_count() {
+        return Err(format!("Column {} is out of range", col));
+    }
+
+    let mut values: Vec<String> = table.column(col).map(str::to_string).collect();
+    let gaps: Vec<usize> = (0..values.len())
+        .filter(|&row| values[row].trim().is_empty())
+        .collect();
+    let filled: Vec<(usize, String)> = match method {
+        GapFill::Constant(text) => gaps.iter().map(|&row| (row, text.clone())).collect(),
+        GapFill::Forward | GapFill::Backward => {
+            let rows: Box<dyn Iterator<Item = usize>> = match method {
+                GapFill::Forward => Box::new(0..values.len()),
+                _ => Box::new((0..values.len()).rev()),
+            };
+            let mut last: Option<&str> = None;
+            let mut filled = Vec::new();
+            for row in rows {
+                match last {
+                    _ if !values[row].trim().is_empty() => last = Some(&values[row]),
+                    Some(value) => filled.push((row, value.to_string())),
+                    None => {}
+                }
+            }
+            filled
+        }
+        GapFill::Linear => {
+            let numbers = table.numbers(col);
+            let mut filled = Vec::new();
+            let mut previous: Option<(usize, f64)> = None;
+            for (row, number) in numbers.iter().enumerate() {
+                let Some(number) = *number else { continue };
+                if let Some((start, from)) = previous {
+                    let span = (row - start) as f64;
+                    for gap in (start + 1..row).filter(|&r| values[r].trim().is_empty()) {
+                        let value = from + (number - from) * (gap - start) as f64 / span;
+                        filled.push((gap, format_number(value)));
+                    }
+                }
+                previous = Some((row, number));
+            }
+            filled
+        }
+    };
+
+    let mut imputed = Vec::with_capacity(filled.len());
+    for (row, value) in filled {
+        values[row] = value;
+        imputed.push(row);
+    }
+    imputed.sort_unstable();
+    Ok(FilledColumn { values, imputed })
+}
+
+/// Fill the empty cells of a column, returning a filled copy
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `col` - Column to fill
+/// * `method` - 0 = linear interpolation, 1 = forward fill, 2 = back fill,
+///   3 = constant
+/// * `constant` - Fill text for method 3 (may be null otherwise)
+///
+/// # Returns
+/// StringResult with JSON `{values: [..], imputed: [..]}`: every cell of the
+/// column after filling, and the rows that were filled
+///
+/// # Safety
+/// `table` must be a live table handle; `constant` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_fill_gaps(
+    table: *const TesseraTable,
+    col: usize,
+    method: u32,
+    constant: *const c_char,
+) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let constant = match constant.is_null() {
+        true if method == 3 => return StringResult::error("Null pointer provided"),
+        true => "",
+        false => match str_arg(constant) {
+            Some(constant) => constant,
+            None => return StringResult::error("Invalid text encoding"),
+        },
+    };
+    let Some(method) = GapFill::from_raw(method, constant) else {
+        return StringResult::error(&format!("Unknown fill method {}", method));
+    };
+
+    fill_gaps(table, col, &method)
+        .map(|filled| {
+            serde_json::json!({
+                "values": filled.values,
+                "imputed": filled.imputed,
+            })
+            .to_string()
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_gaps() {
+        let readings = ["", "10", " ", "", "16", "n/a", "", "22", ""];
+        let table = TesseraTable::from_rows(
+            vec!["Reading".into()],
+            readings.iter().map(|r| vec![r.to_string()]).collect(),
+        );
+
+        let linear = fill_gaps(&table, 0, &GapFill::Linear).unwrap();
+        assert_eq!(
+            linear.values,
+            ["", "10", "12", "14", "16", "n/a", "20", "22", ""]
+        );
+        assert_eq!(linear.imputed, [2, 3, 6]);
+
+        let forward = fill_gaps(&table, 0, &GapFill::Forward).unwrap();
+        assert_eq!(forward.values[..4], ["", "10", "10", "10"]);
+        assert_eq!(forward.values[6..], ["n/a", "22", "22"]);
+        assert_eq!(forward.imputed, [2, 3, 6, 8]);
+
+        let backward = fill_gaps(&table, 0, &GapFill::Backward).unwrap();
+        assert_eq!(backward.values[..4], ["10", "10", "16", "16"]);
+        assert_eq!(backward.imputed, [0, 2, 3, 6]);
+
+        let constant = fill_gaps(&table, 0, &GapFill::Constant("0".into())).unwrap();
+        assert_eq!(constant.imputed, [0, 2, 3, 6, 8]);
+        assert!(fill_gaps(&table, 1, &GapFill::Linear).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! Operations that generate or restructure cell data (autofill, text to
+//! columns, reshaping, copy and paste, filling gaps)
+
+pub mod fill;
+pub mod impute;
+pub mod paste;
+pub mod reshape;
+pub mod split;
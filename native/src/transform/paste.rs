@@ -0,0 +1,284 @@
+//! Copy and move (cut/paste) of cell blocks within a table
+//!
+//! Copied formulas are adjusted by the paste offset: relative references
+//! move with the cell and `$` references stay put, as in a spreadsheet.
+//! Moving keeps the moved formulas as they are and instead repoints every
+//! formula that referenced the moved block.
+
+use std::os::raw::c_char;
+
+use crate::ffi::error_string;
+use crate::formula::rewrite::{is_formula, offset_reference, rewrite_references, Reference};
+use crate::formula::CellRef;
+use crate::table::{table_arg_mut, CellPosition, CellRange, TesseraTable};
+
+/// Cells of a source block and the signed offset from it to the paste target
+struct Block {
+    cells: Vec<Vec<String>>,
+    origin: CellPosition,
+    rows: isize,
+    cols: isize,
+}
+
+/// Read a block (clamped to the table) and its offset to `dest`
+fn take_block(
+    table: &TesseraTable,
+    source: CellRange,
+    dest: CellPosition,
+) -> Result<Block, String> {
+    let (rows, cols) = source.clamp(table);
+    if rows.is_empty() || cols.is_empty() {
+        return Err("Source range is empty".to_string());
+    }
+    if dest.row >= table.row_count() || dest.col >= table.column_count() {
+        return Err(format!("Cell ({}, {}) is out of range", dest.row, dest.col));
+    }
+
+    let cells = rows
+        .clone()
+        .map(|row| {
+            cols.clone()
+                .map(|col| table.cell(row, col).to_string())
+                .collect()
+        })
+        .collect();
+    let origin = CellPosition {
+        row: rows.start,
+        col: cols.start,
+    };
+    Ok(Block {
+        cells,
+        origin,
+        rows: dest.row as isize - origin.row as isize,
+        cols: dest.col as isize - origin.col as isize,
+    })
+}
+
+/// Write a block at `dest`, dropping cells that fall outside the table
+fn put_block(table: &mut TesseraTable, block: Vec<Vec<String>>, dest: CellPosition) {
+    for (r, cells) in block.into_iter().enumerate() {
+        for (c, value) in cells.into_iter().enumerate() {
+            // Out-of-range cells are clipped, not an error
+            let _ = table.set_cell(dest.row + r, dest.col + c, value);
+        }
+    }
+}
+
+/// Copy `source` so its top-left cell lands on `dest`
+pub fn copy_range(
+    table: &mut TesseraTable,
+    source: CellRange,
+    dest: CellPosition,
+) -> Result<(), String> {
+    let mut block = take_block(table, source, dest)?;
+    for cell in block.cells.iter_mut().flatten() {
+        if is_formula(cell) {
+            *cell = rewrite_references(cell, |r| offset_reference(r, block.rows, block.cols));
+        }
+    }
+    put_block(table, block.cells, dest);
+    Ok(())
+}
+
+/// Move `source` so its top-left cell lands on `dest`, clearing the source
+///
+/// References anywhere in the table to cells of the block (a single cell
+/// or a range lying entirely inside it) follow the block.
+pub fn move_range(
+    table: &mut TesseraTable,
+    source: CellRange,
+    dest: CellPosition,
+) -> Result<(), String> {
+    let Block {
+        cells,
+        origin,
+        rows,
+        cols,
+    } = take_block(table, source, dest)?;
+    let height = cells.len();
+    let width = cells[0].len();
+    for row in origin.row..origin.row + height {
+        for col in origin.col..origin.col + width {
+            table.set_cell(row, col, String::new())?;
+        }
+    }
+    put_block(table, cells, dest);
+
+    let inside = |cell: &CellRef| {
+        (origin.row..origin.row + height).contains(&cell.row)
+            && (origin.col..origin.col + width).contains(&cell.col)
+    };
+    table.rewrite_formulas(|reference| match reference {
+        Reference::Cell(cell) if inside(&cell) => offset_all(reference, rows, cols),
+        Reference::Range(start, end) if inside(&start) && inside(&end) => {
+            offset_all(reference, rows, cols)
+        }
+        _ => Some(reference),
+    });
+    Ok(())
+}
+
+/// Offset a reference including its `$` parts (the target itself moved)
+fn offset_all(reference: Reference, rows: isize, cols: isize) -> Option<Reference> {
+    let relative = |cell: CellRef| CellRef {
+        row_absolute: false,
+        col_absolute: false,
+        ..cell
+    };
+    let restore = |moved: CellRef, original: CellRef| CellRef {
+        row_absolute: original.row_absolute,
+        col_absolute: original.col_absolute,
+        ..moved
+    };
+
+    match reference {
+        Reference::Cell(cell) => {
+            match offset_reference(Reference::Cell(relative(cell)), rows, cols)? {
+                Reference::Cell(moved) => Some(Reference::Cell(restore(moved, cell))),
+                _ => None,
+            }
+        }
+        Reference::Range(start, end) => {
+            match offset_reference(Reference::Range(relative(start), relative(end)), rows, cols)? {
+                Reference::Range(a, b) => {
+                    Some(Reference::Range(restore(a, start), restore(b, end)))
+                }
+                _ => None,
+            }
+        }
+        Reference::Columns(..) => Some(reference),
+    }
+}
+
+/// Copy a block of cells, adjusting relative references in copied formulas
+///
+/// # Arguments
+/// * `table` - Table handle
+/// * `source` - Block to copy
+/// * `dest_row`, `dest_col` - Where the top-left cell of the block lands
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `source` must be a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn tessera_copy_range(
+    table: *mut TesseraTable,
+    source: *const CellRange,
+    dest_row: usize,
+    dest_col: usize,
+) -> *mut c_char {
+    let (Some(table), Some(source)) = (table_arg_mut(table), source.as_ref()) else {
+        return error_string("Null pointer provided");
+    };
+    let dest = CellPosition {
+        row: dest_row,
+        col: dest_col,
+    };
+
+    match copy_range(table, *source, dest) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Cut and paste a block of cells, repointing formulas that referenced it
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `source` must be a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn tessera_move_range(
+    table: *mut TesseraTable,
+    source: *const CellRange,
+    dest_row: usize,
+    dest_col: usize,
+) -> *mut c_char {
+    let (Some(table), Some(source)) = (table_arg_mut(table), source.as_ref()) else {
+        return error_string("Null pointer provided");
+    };
+    let dest = CellPosition {
+        row: dest_row,
+        col: dest_col,
+    };
+
+    match move_range(table, *source, dest) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["A".into(), "B".into(), "C".into()],
+            vec![
+                vec!["1".into(), "=A1*2".into(), "=$A$1+A1".into()],
+                vec!["2".into(), "".into(), "=SUM(A1:A2)".into()],
+                vec!["3".into(), "".into(), "".into()],
+            ],
+        )
+    }
+
+    fn range(row: usize, col: usize, rows: usize, cols: usize) -> CellRange {
+        CellRange {
+            row,
+            col,
+            rows,
+            cols,
+        }
+    }
+
+    #[test]
+    fn test_copy_adjusts_relative_references() {
+        let mut table = grid();
+        copy_range(
+            &mut table,
+            range(0, 1, 1, 2),
+            CellPosition { row: 1, col: 1 },
+        )
+        .unwrap();
+        assert_eq!(table.rows()[1], ["2", "=A2*2", "=$A$1+A2"]);
+
+        // Pasting past the table edge clips; pasting above row 1 breaks refs
+        copy_range(
+            &mut table,
+            range(1, 2, 1, 1),
+            CellPosition { row: 2, col: 2 },
+        )
+        .unwrap();
+        assert_eq!(table.cell(2, 2), "=$A$1+A3");
+        copy_range(
+            &mut table,
+            range(0, 1, 1, 1),
+            CellPosition { row: 0, col: 0 },
+        )
+        .unwrap();
+        assert_eq!(table.cell(0, 0), "=#REF!*2");
+    }
+
+    #[test]
+    fn test_move_repoints_references() {
+        let mut table = grid();
+        move_range(
+            &mut table,
+            range(0, 0, 2, 1),
+            CellPosition { row: 1, col: 1 },
+        )
+        .unwrap();
+
+        assert_eq!(table.column(0).collect::<Vec<_>>(), ["", "", "3"]);
+        assert_eq!(table.cell(1, 1), "1");
+        assert_eq!(table.cell(2, 1), "2");
+        // Formulas outside the block stay put but follow the moved cells
+        assert_eq!(table.cell(0, 1), "=B2*2");
+        assert_eq!(table.cell(0, 2), "=$B$2+B2");
+        assert_eq!(table.cell(1, 2), "=SUM(B2:B3)");
+    }
+}
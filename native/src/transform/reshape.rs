@@ -0,0 +1,247 @@
+//! Transpose, melt (wide to long) and pivot-wider reshaping
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+
+use crate::ffi::{slice_arg, str_arg};
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Swap rows and columns, treating the header row as the first row
+///
+/// The first column (header included) becomes the new header row.
+pub fn transpose(table: &TesseraTable) -> TesseraTable {
+    let grid_rows = table.row_count() + 1;
+    let grid_cell = |row: usize, col: usize| {
+        if row == 0 {
+            table.headers()[col].clone()
+        } else {
+            table.cell(row - 1, col).to_string()
+        }
+    };
+
+    let mut transposed = (0..table.column_count()).map(|col| {
+        (0..grid_rows)
+            .map(|row| grid_cell(row, col))
+            .collect::<Vec<_>>()
+    });
+    let headers = transposed.next().unwrap_or_default();
+    TesseraTable::from_rows(headers, transposed.collect())
+}
+
+fn check_columns(table: &TesseraTable, cols: &[usize]) -> Result<(), String> {
+    match cols.iter().find(|&&c| c >= table.column_count()) {
+        Some(col) => Err(format!("Column {} is out of range", col)),
+        None => Ok(()),
+    }
+}
+
+/// Unpivot value columns into `variable` / `value` pairs (wide to long)
+///
+/// Each source row produces one row per value column, keeping the id
+/// columns. An empty `value_cols` melts every column that is not an id.
+pub fn melt(
+    table: &TesseraTable,
+    id_cols: &[usize],
+    value_cols: &[usize],
+    var_name: &str,
+    value_name: &str,
+) -> Result<TesseraTable, String> {
+    check_columns(table, id_cols)?;
+    check_columns(table, value_cols)?;
+    let value_cols: Vec<usize> = if value_cols.is_empty() {
+        (0..table.column_count())
+            .filter(|c| !id_cols.contains(c))
+            .collect()
+    } else {
+        value_cols.to_vec()
+    };
+
+    let mut headers: Vec<String> = id_cols
+        .iter()
+        .map(|&c| table.headers()[c].clone())
+        .collect();
+    headers.push(var_name.to_string());
+    headers.push(value_name.to_string());
+
+    let mut result = TesseraTable::new(headers);
+    for row in 0..table.row_count() {
+        for &col in &value_cols {
+            let mut cells: Vec<String> = id_cols
+                .iter()
+                .map(|&c| table.cell(row, c).to_string())
+                .collect();
+            cells.push(table.headers()[col].clone());
+            cells.push(table.cell(row, col).to_string());
+            result.push_row(cells);
+        }
+    }
+    Ok(result)
+}
+
+/// Spread `names_col` values into columns filled from `values_col` (long to wide)
+///
+/// Rows are grouped by the index columns in order of first occurrence, and
+/// new columns appear in the order their names are first seen. Two rows with
+/// the same index and name are an error; aggregate them with a group-by first.
+pub fn pivot_wider(
+    table: &TesseraTable,
+    index_cols: &[usize],
+    names_col: usize,
+    values_col: usize,
+) -> Result<TesseraTable, String> {
+    check_columns(table, index_cols)?;
+    check_columns(table, &[names_col, values_col])?;
+
+    let mut names: Vec<&str> = Vec::new();
+    let mut name_slots: HashMap<&str, usize> = HashMap::new();
+    let mut keys: Vec<Vec<&str>> = Vec::new();
+    let mut key_slots: HashMap<Vec<&str>, usize> = HashMap::new();
+    let mut cells: HashMap<(usize, usize), &str> = HashMap::new();
+
+    for row in 0..table.row_count() {
+        let key: Vec<&str> = index_cols.iter().map(|&c| table.cell(row, c)).collect();
+        let key_slot = *key_slots.entry(key.clone()).or_insert_with(|| {
+            keys.push(key);
+            keys.len() - 1
+        });
+        let name = table.cell(row, names_col);
+        let name_slot = *name_slots.entry(name).or_insert_with(|| {
+            names.push(name);
+            names.len() - 1
+        });
+
+        if cells
+            .insert((key_slot, name_slot), table.cell(row, values_col))
+            .is_some()
+        {
+            return Err(format!(
+                "Duplicate value for '{}' in row {}; aggregate before pivoting",
+                name,
+                row + 1
+            ));
+        }
+    }
+
+    let mut headers: Vec<String> = index_cols
+        .iter()
+        .map(|&c| table.headers()[c].clone())
+        .collect();
+    headers.extend(names.iter().map(|n| n.to_string()));
+
+    let rows = keys
+        .iter()
+        .enumerate()
+        .map(|(k, key)| {
+            key.iter()
+                .map(|c| c.to_string())
+                .chain((0..names.len()).map(|n| cells.get(&(k, n)).unwrap_or(&"").to_string()))
+                .collect()
+        })
+        .collect();
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Transpose a table into a new table
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_transpose(table: *const TesseraTable) -> TableResult {
+    match table_arg(table) {
+        Some(table) => TableResult::success(transpose(table)),
+        None => TableResult::error("Null pointer provided"),
+    }
+}
+
+/// Melt value columns into variable/value rows (wide to long)
+///
+/// # Arguments
+/// * `id_cols_ptr` / `id_count` - Columns kept on every output row
+/// * `value_cols_ptr` / `value_count` - Columns to unpivot (0 for all non-id columns)
+/// * `var_name` / `value_name` - Names of the two new columns (null for "variable" / "value")
+///
+/// # Safety
+/// `table` must be a live table handle; the arrays must hold the given counts;
+/// names must be null or valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_melt(
+    table: *const TesseraTable,
+    id_cols_ptr: *const usize,
+    id_count: usize,
+    value_cols_ptr: *const usize,
+    value_count: usize,
+    var_name: *const c_char,
+    value_name: *const c_char,
+) -> TableResult {
+    let (Some(table), Some(id_cols), Some(value_cols)) = (
+        table_arg(table),
+        slice_arg(id_cols_ptr, id_count),
+        slice_arg(value_cols_ptr, value_count),
+    ) else {
+        return TableResult::error("Null pointer provided");
+    };
+    let var_name = str_arg(var_name).unwrap_or("variable");
+    let value_name = str_arg(value_name).unwrap_or("value");
+
+    melt(table, id_cols, value_cols, var_name, value_name).into()
+}
+
+/// Spread a names column into new columns filled from a values column (long to wide)
+///
+/// # Safety
+/// `table` must be a live table handle; `index_cols_ptr` must hold `index_count` indices
+#[no_mangle]
+pub unsafe extern "C" fn tessera_pivot_wider(
+    table: *const TesseraTable,
+    index_cols_ptr: *const usize,
+    index_count: usize,
+    names_col: usize,
+    values_col: usize,
+) -> TableResult {
+    let (Some(table), Some(index_cols)) =
+        (table_arg(table), slice_arg(index_cols_ptr, index_count))
+    else {
+        return TableResult::error("Null pointer provided");
+    };
+
+    pivot_wider(table, index_cols, names_col, values_col).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wide() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["City".into(), "2023".into(), "2024".into()],
+            vec![
+                vec!["Hanoi".into(), "10".into(), "12".into()],
+                vec!["Hue".into(), "4".into(), "".into()],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_transpose() {
+        let result = transpose(&wide());
+
+        assert_eq!(result.headers(), ["City", "Hanoi", "Hue"]);
+        assert_eq!(result.row(0).collect::<Vec<_>>(), ["2023", "10", "4"]);
+        assert_eq!(result.row(1).collect::<Vec<_>>(), ["2024", "12", ""]);
+        assert_eq!(transpose(&result), wide());
+    }
+
+    #[test]
+    fn test_melt_and_pivot_round_trip() {
+        let long = melt(&wide(), &[0], &[], "Year", "Sales").unwrap();
+        assert_eq!(long.headers(), ["City", "Year", "Sales"]);
+        assert_eq!(long.row_count(), 4);
+        assert_eq!(long.rows()[1], ["Hanoi", "2024", "12"]);
+
+        assert_eq!(pivot_wider(&long, &[0], 1, 2).unwrap(), wide());
+
+        let mut duplicated = long.clone();
+        duplicated.push_row(vec!["Hue".into(), "2023".into(), "5".into()]);
+        assert!(pivot_wider(&duplicated, &[0], 1, 2).is_err());
+    }
+}
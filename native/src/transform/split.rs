@@ -0,0 +1,215 @@
+//! Text to columns: split one column into several
+
+use std::os::raw::c_char;
+
+use regex::Regex;
+
+use crate::ffi::str_arg;
+use crate::table::{table_arg, TableResult, TesseraTable};
+
+/// Where to cut each cell
+#[derive(Debug, Clone)]
+pub enum SplitMode {
+    /// Split on every occurrence of a literal delimiter
+    Delimiter(String),
+    /// Split on every match of a regular expression
+    Regex(Regex),
+    /// Cut at character positions (e.g. `[3, 5]` gives `..3`, `3..5`, `5..`)
+    Positions(Vec<usize>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitSettings {
+    pub mode: SplitMode,
+    /// Maximum number of parts (0 for no limit); the last part keeps the rest
+    pub max_parts: usize,
+    /// Trim whitespace around each part
+    pub trim: bool,
+}
+
+/// FFI form of [`SplitSettings`]
+#[repr(C)]
+pub struct SplitOptions {
+    pub mode: u32,               // 0 = delimiter, 1 = regex, 2 = positions
+    pub pattern: *const c_char,  // delimiter or regex (modes 0 and 1)
+    pub positions: *const usize, // cut positions (mode 2)
+    pub position_count: usize,
+    pub max_parts: usize,
+    pub trim: bool,
+}
+
+impl SplitOptions {
+    /// # Safety
+    /// `pattern` / `positions` must be valid for the selected mode
+    unsafe fn settings(&self) -> Result<SplitSettings, String> {
+        let mode = match self.mode {
+            0 | 1 => {
+                let pattern = str_arg(self.pattern).ok_or("Invalid split pattern")?;
+                if pattern.is_empty() {
+                    return Err("Split pattern is empty".to_string());
+                }
+                if self.mode == 0 {
+                    SplitMode::Delimiter(pattern.to_string())
+                } else {
+                    SplitMode::Regex(
+                        Regex::new(pattern).map_err(|e| format!("Invalid split pattern: {}", e))?,
+                    )
+                }
+            }
+            2 if self.position_count == 0 => SplitMode::Positions(Vec::new()),
+            2 if self.positions.is_null() => return Err("Null pointer provided".to_string()),
+            2 => SplitMode::Positions(
+                std::slice::from_raw_parts(self.positions, self.position_count).to_vec(),
+            ),
+            _ => return Err("Unknown split mode".to_string()),
+        };
+
+        Ok(SplitSettings {
+            mode,
+            max_parts: self.max_parts,
+            trim: self.trim,
+        })
+    }
+}
+
+/// Parts of one cell; shared with the `SPLIT` formula function
+pub(crate) fn split_cell(text: &str, settings: &SplitSettings) -> Vec<String> {
+    let limit = if settings.max_parts == 0 {
+        usize::MAX
+    } else {
+        settings.max_parts
+    };
+
+    let parts: Vec<&str> = match &settings.mode {
+        SplitMode::Delimiter(delimiter) => text.splitn(limit, delimiter.as_str()).collect(),
+        SplitMode::Regex(regex) => regex.splitn(text, limit).collect(),
+        SplitMode::Positions(positions) => {
+            let mut cuts: Vec<usize> = positions
+                .iter()
+                .filter_map(|&p| text.char_indices().nth(p).map(|(i, _)| i))
+                .collect();
+            cuts.sort_unstable();
+            cuts.dedup();
+            cuts.truncate(limit.saturating_sub(1));
+
+            let mut parts = Vec::with_capacity(cuts.len() + 1);
+            let mut start = 0;
+            for cut in cuts {
+                parts.push(&text[start..cut]);
+                start = cut;
+            }
+            parts.push(&text[start..]);
+            parts
+        }
+    };
+
+    parts
+        .into_iter()
+        .map(|part| if settings.trim { part.trim() } else { part }.to_string())
+        .collect()
+}
+
+/// Split column `col` into new columns named `<header>_1`, `<header>_2`, ...
+///
+/// The result has one row per source row and as many columns as the
+/// longest split; shorter rows are padded with empty cells. The source table
+/// is not modified so the host can insert the columns where it wants.
+pub fn split_column(
+    table: &TesseraTable,
+    col: usize,
+    settings: &SplitSettings,
+) -> Result<TesseraTable, String> {
+    let Some(header) = table.headers().get(col) else {
+        return Err(format!("Column {} is out of range", col));
+    };
+
+    let rows: Vec<Vec<String>> = table
+        .column(col)
+        .map(|cell| {
+            if cell.is_empty() {
+                Vec::new()
+            } else {
+                split_cell(cell, settings)
+            }
+        })
+        .collect();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let headers = (1..=width).map(|i| format!("{}_{}", header, i)).collect();
+
+    Ok(TesseraTable::from_rows(headers, rows))
+}
+
+/// Split one column into several by delimiter, regex or character positions
+///
+/// # Returns
+/// TableResult with the new columns as a table handle (free with tessera_table_free)
+///
+/// # Safety
+/// `table` must be a live table handle; `options` must point to valid options
+#[no_mangle]
+pub unsafe extern "C" fn tessera_split_column(
+    table: *const TesseraTable,
+    col: usize,
+    options: *const SplitOptions,
+) -> TableResult {
+    let (Some(table), Some(options)) = (table_arg(table), options.as_ref()) else {
+        return TableResult::error("Null pointer provided");
+    };
+
+    options
+        .settings()
+        .and_then(|settings| split_column(table, col, &settings))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec!["Name".into()],
+            vec![
+                vec!["Doe, John, Jr".into()],
+                vec!["Roe,Jane".into()],
+                vec!["".into()],
+            ],
+        )
+    }
+
+    fn settings(mode: SplitMode, max_parts: usize) -> SplitSettings {
+        SplitSettings {
+            mode,
+            max_parts,
+            trim: true,
+        }
+    }
+
+    #[test]
+    fn test_split_by_delimiter_and_regex() {
+        let result =
+            split_column(&table(), 0, &settings(SplitMode::Delimiter(",".into()), 0)).unwrap();
+        assert_eq!(result.headers(), ["Name_1", "Name_2", "Name_3"]);
+        assert_eq!(result.rows()[0], ["Doe", "John", "Jr"]);
+        assert_eq!(result.row(1).collect::<Vec<_>>(), ["Roe", "Jane", ""]);
+        assert_eq!(result.row(2).collect::<Vec<_>>(), ["", "", ""]);
+
+        let regex = SplitMode::Regex(Regex::new(r",\s*").unwrap());
+        let result = split_column(&table(), 0, &settings(regex, 2)).unwrap();
+        assert_eq!(result.rows()[0], ["Doe", "John, Jr"]);
+    }
+
+    #[test]
+    fn test_split_by_positions() {
+        let table = TesseraTable::from_rows(
+            vec!["Code".into()],
+            vec![vec!["VN2024ábc".into()], vec!["US".into()]],
+        );
+        let result =
+            split_column(&table, 0, &settings(SplitMode::Positions(vec![6, 2]), 0)).unwrap();
+
+        assert_eq!(result.rows()[0], ["VN", "2024", "ábc"]);
+        assert_eq!(result.row(1).collect::<Vec<_>>(), ["US", "", ""]);
+        assert!(split_column(&table, 3, &settings(SplitMode::Positions(vec![]), 0)).is_err());
+    }
+}
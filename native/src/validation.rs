@@ -0,0 +1,484 @@
+//! Per-column data validation rules stored on a table handle
+//!
+//! Rules are kept as plain definitions and compiled (regexes, formulas) when
+//! validating, so tables stay cheap to clone and compare.
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+
+use regex::Regex;
+
+use crate::ffi::{error_string, str_arg, str_array_arg};
+use crate::formats::TextFormat;
+use crate::formula::parse;
+use crate::formula::row_context::RowFormula;
+use crate::table::{table_arg, table_arg_mut, TesseraTable};
+use crate::StringResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleKind {
+    /// Numeric value within optional inclusive bounds
+    Range { min: Option<f64>, max: Option<f64> },
+    /// Value must be one of a fixed list
+    List {
+        values: Vec<String>,
+        ignore_case: bool,
+    },
+    /// Value must match a regular expression (anywhere unless anchored)
+    Pattern(String),
+    /// Value must be an email address, URL, IP address or JSON document
+    Format(TextFormat),
+    /// No other row may hold the same value
+    Unique,
+    /// Formula evaluated per row must be TRUE, e.g. `Amount <= Budget`
+    Formula(String),
+}
+
+impl RuleKind {
+    fn name(&self) -> &'static str {
+        match self {
+            RuleKind::Range { .. } => "range",
+            RuleKind::List { .. } => "list",
+            RuleKind::Pattern(_) => "pattern",
+            RuleKind::Format(_) => "format",
+            RuleKind::Unique => "unique",
+            RuleKind::Formula(_) => "formula",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationRule {
+    pub column: usize,
+    pub kind: RuleKind,
+}
+
+/// One failed check; blank cells are never reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub row: usize,
+    pub col: usize,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl Violation {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "row": self.row,
+            "col": self.col,
+            "rule": self.rule,
+            "message": self.message,
+        })
+    }
+}
+
+/// A rule with its regex or formula compiled
+enum Compiled<'a> {
+    Range(Option<f64>, Option<f64>),
+    List(HashMap<String, ()>, bool, &'a [String]),
+    Pattern(Regex, &'a str),
+    Format(TextFormat),
+    Unique,
+    Formula(RowFormula),
+}
+
+fn compile<'a>(table: &TesseraTable, kind: &'a RuleKind) -> Result<Compiled<'a>, String> {
+    Ok(match kind {
+        RuleKind::Range { min, max } => Compiled::Range(*min, *max),
+        RuleKind::List {
+            values,
+            ignore_case,
+        } => {
+            let allowed = values
+                .iter()
+                .map(|v| {
+                    let v = v.trim();
+                    (
+                        if *ignore_case {
+                            v.to_lowercase()
+                        } else {
+                            v.to_string()
+                        },
+                        (),
+                    )
+                })
+                .collect();
+            Compiled::List(allowed, *ignore_case, values)
+        }
+        RuleKind::Pattern(pattern) => Compiled::Pattern(
+            Regex::new(pattern).map_err(|e| format!("Invalid validation pattern: {}", e))?,
+            pattern,
+        ),
+        RuleKind::Format(format) => Compiled::Format(*format),
+        RuleKind::Unique => Compiled::Unique,
+        RuleKind::Formula(formula) => Compiled::Formula(RowFormula::new(table, formula)?),
+    })
+}
+
+fn format_bound(value: f64) -> String {
+    crate::formula::value::format_number(value)
+}
+
+/// Check every rule attached to the table, returning violations in row order
+pub fn validate(table: &TesseraTable) -> Result<Vec<Violation>, String> {
+    let mut violations = Vec::new();
+
+    for rule in table.validation_rules() {
+        let compiled = compile(table, &rule.kind)?;
+        let name = rule.kind.name();
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+
+        for row in 0..table.row_count() {
+            let cell = table.cell(row, rule.column);
+            let value = cell.trim();
+            if value.is_empty() {
+                continue;
+            }
+
+            let message = match &compiled {
+                Compiled::Range(min, max) => match value.parse::<f64>() {
+                    Err(_) => Some("Value must be a number".to_string()),
+                    Ok(n) if min.is_some_and(|m| n < m) || max.is_some_and(|m| n > m) => {
+                        Some(match (min, max) {
+                            (Some(lo), Some(hi)) => format!(
+                                "Value must be between {} and {}",
+                                format_bound(*lo),
+                                format_bound(*hi)
+                            ),
+                            (Some(lo), None) => {
+                                format!("Value must be at least {}", format_bound(*lo))
+                            }
+                            (_, Some(hi)) => format!("Value must be at most {}", format_bound(*hi)),
+                            (None, None) => unreachable!(),
+                        })
+                    }
+                    Ok(_) => None,
+                },
+                Compiled::List(allowed, ignore_case, values) => {
+                    let key = if *ignore_case {
+                        value.to_lowercase()
+                    } else {
+                        value.to_string()
+                    };
+                    (!allowed.contains_key(&key))
+                        .then(|| format!("Value must be one of: {}", values.join(", ")))
+                }
+                Compiled::Pattern(regex, pattern) => (!regex.is_match(value))
+                    .then(|| format!("Value does not match pattern {}", pattern)),
+                Compiled::Format(format) => (!format.matches(value))
+                    .then(|| format!("Value must be {}", format.description())),
+                Compiled::Unique => match seen.get(value) {
+                    Some(first) => Some(format!("Duplicate of row {}", first + 1)),
+                    None => {
+                        seen.insert(value, row);
+                        None
+                    }
+                },
+                Compiled::Formula(formula) => {
+                    let result = formula.evaluate(table, row);
+                    (!result.as_bool().unwrap_or(false))
+                        .then(|| format!("Custom rule failed ({})", result))
+                }
+            };
+
+            if let Some(message) = message {
+                violations.push(Violation {
+                    row,
+                    col: rule.column,
+                    rule: name,
+                    message,
+                });
+            }
+        }
+    }
+
+    violations.sort_by_key(|v| (v.row, v.col));
+    Ok(violations)
+}
+
+/// # Safety
+/// `table` must be null or a live table handle
+unsafe fn add_rule(table: *mut TesseraTable, column: usize, kind: RuleKind) -> *mut c_char {
+    let Some(table) = table_arg_mut(table) else {
+        return error_string("Null pointer provided");
+    };
+    match table.add_validation_rule(ValidationRule { column, kind }) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Require numbers within `min`..=`max` (pass NaN for an open bound)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_range(
+    table: *mut TesseraTable,
+    column: usize,
+    min: f64,
+    max: f64,
+) -> *mut c_char {
+    let bound = |b: f64| (!b.is_nan()).then_some(b);
+    add_rule(
+        table,
+        column,
+        RuleKind::Range {
+            min: bound(min),
+            max: bound(max),
+        },
+    )
+}
+
+/// Require values from a fixed list
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `values_ptr` must point to `count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_list(
+    table: *mut TesseraTable,
+    column: usize,
+    values_ptr: *const *const c_char,
+    count: usize,
+    ignore_case: bool,
+) -> *mut c_char {
+    let Some(values) = str_array_arg(values_ptr, count) else {
+        return error_string("Null pointer provided");
+    };
+    add_rule(
+        table,
+        column,
+        RuleKind::List {
+            values,
+            ignore_case,
+        },
+    )
+}
+
+/// Require values matching a regular expression
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `pattern` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_pattern(
+    table: *mut TesseraTable,
+    column: usize,
+    pattern: *const c_char,
+) -> *mut c_char {
+    let Some(pattern) = str_arg(pattern) else {
+        return error_string("Invalid pattern encoding");
+    };
+    if let Err(e) = Regex::new(pattern) {
+        return error_string(&format!("Invalid validation pattern: {}", e));
+    }
+    add_rule(table, column, RuleKind::Pattern(pattern.to_string()))
+}
+
+/// Require values in a common text format
+///
+/// # Arguments
+/// * `format` - 0 = email address, 1 = URL, 2 = IP address, 3 = JSON object or array
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_format(
+    table: *mut TesseraTable,
+    column: usize,
+    format: u32,
+) -> *mut c_char {
+    let Some(kind) = TextFormat::from_raw(format) else {
+        return error_string(&format!("Unknown text format {}", format));
+    };
+    add_rule(table, column, RuleKind::Format(kind))
+}
+
+/// Require unique values in a column
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_unique(
+    table: *mut TesseraTable,
+    column: usize,
+) -> *mut c_char {
+    add_rule(table, column, RuleKind::Unique)
+}
+
+/// Require a per-row formula such as `Amount <= Budget` to be TRUE
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `table` must be a live table handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_add_formula(
+    table: *mut TesseraTable,
+    column: usize,
+    formula: *const c_char,
+) -> *mut c_char {
+    let Some(formula) = str_arg(formula) else {
+        return error_string("Invalid formula encoding");
+    };
+    if let Err(msg) = parse(formula) {
+        return error_string(&msg);
+    }
+    add_rule(table, column, RuleKind::Formula(formula.to_string()))
+}
+
+/// Remove the rules of one column (pass `usize::MAX` to clear every rule)
+///
+/// # Safety
+/// `table` must be null or a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validation_clear(table: *mut TesseraTable, column: usize) {
+    if let Some(table) = table_arg_mut(table) {
+        table.clear_validation_rules((column != usize::MAX).then_some(column));
+    }
+}
+
+/// Check all rules on the table
+///
+/// # Returns
+/// StringResult with a JSON array of `{row, col, rule, message}` violations
+///
+/// # Safety
+/// `table` must be a live table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_validate(table: *const TesseraTable) -> StringResult {
+    let Some(table) = table_arg(table) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    validate(table)
+        .map(|violations| {
+            serde_json::Value::from(
+                violations
+                    .iter()
+                    .map(Violation::to_json)
+                    .collect::<Vec<_>>(),
+            )
+            .to_string()
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orders() -> TesseraTable {
+        TesseraTable::from_rows(
+            vec![
+                "Id".into(),
+                "Status".into(),
+                "Amount".into(),
+                "Budget".into(),
+            ],
+            [
+                ["A-1", "open", "50", "100"],
+                ["A-2", "Closed", "150", "100"],
+                ["B3", "lost", "-5", ""],
+                ["A-1", "", "abc", "10"],
+            ]
+            .iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect(),
+        )
+    }
+
+    fn rule(column: usize, kind: RuleKind) -> ValidationRule {
+        ValidationRule { column, kind }
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let mut table = orders();
+        table
+            .add_validation_rule(rule(0, RuleKind::Pattern(r"^[A-Z]-\d+$".into())))
+            .unwrap();
+        table
+            .add_validation_rule(rule(0, RuleKind::Unique))
+            .unwrap();
+        table
+            .add_validation_rule(rule(
+                1,
+                RuleKind::List {
+                    values: vec!["Open".into(), "Closed".into()],
+                    ignore_case: true,
+                },
+            ))
+            .unwrap();
+        table
+            .add_validation_rule(rule(
+                2,
+                RuleKind::Range {
+                    min: Some(0.0),
+                    max: None,
+                },
+            ))
+            .unwrap();
+
+        let found: Vec<(usize, usize, &str)> = validate(&table)
+            .unwrap()
+            .iter()
+            .map(|v| (v.row, v.col, v.rule))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, 0, "pattern"),
+                (2, 1, "list"),
+                (2, 2, "range"),
+                (3, 0, "unique"),
+                (3, 2, "range"),
+            ]
+        );
+        assert!(table
+            .add_validation_rule(rule(9, RuleKind::Unique))
+            .is_err());
+
+        table.clear_validation_rules(None);
+        table
+            .add_validation_rule(rule(1, RuleKind::Format(TextFormat::Email)))
+            .unwrap();
+        table.set_cell(0, 1, "ann@example.com".into()).unwrap();
+        let violations = validate(&table).unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].message, "Value must be an email address");
+    }
+
+    #[test]
+    fn test_formula_rule_and_clear() {
+        let mut table = orders();
+        table
+            .add_validation_rule(rule(2, RuleKind::Formula("Amount <= Budget".into())))
+            .unwrap();
+
+        let violations = validate(&table).unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[1].row, 3);
+        assert_eq!(violations[0].row, 1);
+        assert_eq!(violations[0].message, "Custom rule failed (FALSE)");
+
+        table.clear_validation_rules(Some(2));
+        assert!(validate(&table).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,1279 @@
+//! Recalculation of formula cells
+//!
+//! Results of formula cells are cached on the workbook, keyed by
+//! (sheet, row, col). Edits made through the workbook mark the formulas that
+//! read the edited cells, directly or through other formulas, as dirty. In
+//! automatic mode dirty formulas are recalculated right after each step (a
+//! batch recalculates once, on commit); in manual mode they wait for
+//! `tessera_calculate_now`. "Automatic except tables" defers only formulas
+//! whose last result was an array, such as `=SORT(A:A)`.
+//!
+//! The dependency graph between formulas is kept across edits and rebuilt
+//! only when formula text, the sheet layout or the placed spills change, so
+//! editing plain values stays cheap on large workbooks, in manual mode too.
+//!
+//! Formulas are evaluated in dependency order, one level at a time; the
+//! formulas of a level never read each other, so large levels are spread
+//! over worker threads (`tessera_set_calc_threads`). Formulas on a reference
+//! cycle evaluate to `#CALC!` unless iterative calculation is on
+//! (`tessera_set_iterative_calc`): then they are evaluated in turn, each pass
+//! reading the previous pass's results, until no result moves by more than
+//! the allowed change or the iteration limit is reached. Edits made directly
+//! on a borrowed sheet handle are not seen: call `tessera_calculate_now`
+//! afterwards.
+//!
+//! Formulas calling volatile functions (`NOW()`, `RAND()`, ...) are marked
+//! dirty, along with everything downstream, on every recalculation. Freezing
+//! volatility (`tessera_set_volatile_frozen`) pins the clock and seeds the
+//! random functions per cell instead, so results are reproducible in tests.
+//!
+//! Array results spill into the empty cells below and to the right of their
+//! formula (see [`super::spill`]). Placing them changes what those cells
+//! read as, so formulas reading them are recalculated after each placement.
+//!
+//! Profiling is opt-in (`tessera_set_calc_profiling`): while it is on, every
+//! evaluation of a formula cell is timed and counted, so the host can list
+//! the cells that cost the most.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::os::raw::c_char;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use super::history::Change;
+use super::spill::SpillArea;
+use super::trace::cell_ref;
+use super::{workbook_arg, workbook_arg_mut, Workbook};
+use crate::ffi::error_string;
+use crate::formula::deps::{precedents, Precedent};
+use crate::formula::rewrite::is_formula;
+use crate::formula::table_context::TableContext;
+use crate::formula::volatile::{is_volatile, Frozen};
+use crate::formula::{evaluate, parse, ErrorValue, Expr, Value};
+use crate::StringResult;
+
+/// Position of a cell in a workbook: (sheet, row, col)
+pub type CellKey = (usize, usize, usize);
+
+/// Calculated results of formula cells
+pub type CalcValues = HashMap<CellKey, Value>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CalcMode {
+    #[default]
+    Automatic,
+    /// Automatic, except formulas producing arrays
+    AutomaticExceptTables,
+    Manual,
+}
+
+impl CalcMode {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(CalcMode::Automatic),
+            1 => Some(CalcMode::AutomaticExceptTables),
+            2 => Some(CalcMode::Manual),
+            _ => None,
+        }
+    }
+}
+
+/// Levels smaller than this are evaluated on the calling thread
+const PARALLEL_MIN: usize = 64;
+
+/// Recalculations after placing spills before giving up on them settling
+const SPILL_PASSES: usize = 8;
+
+/// Thread pool for recalculation; `None` uses rayon's global pool
+#[derive(Clone, Default)]
+struct CalcPool(Option<Arc<ThreadPool>>);
+
+impl CalcPool {
+    fn new(threads: usize) -> Result<Self, String> {
+        if threads == 0 {
+            return Ok(CalcPool(None));
+        }
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map(|pool| CalcPool(Some(Arc::new(pool))))
+            .map_err(|e| format!("Failed to start calculation threads: {}", e))
+    }
+
+    fn run<R: Send>(&self, job: impl FnOnce() -> R + Send) -> R {
+        match &self.0 {
+            Some(pool) => pool.install(job),
+            None => job(),
+        }
+    }
+}
+
+impl fmt::Debug for CalcPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let threads = self.0.as_ref().map_or(0, |pool| pool.current_num_threads());
+        write!(f, "CalcPool({})", threads)
+    }
+}
+
+impl PartialEq for CalcPool {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+/// Dependency graph kept between edits; dropped when formula text, the
+/// sheet layout or the placed spills change
+#[derive(Clone, Default)]
+struct GraphCache(Option<Arc<Graph>>);
+
+impl fmt::Debug for GraphCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(graph) => write!(f, "GraphCache({} formulas)", graph.formulas.len()),
+            None => f.write_str("GraphCache(None)"),
+        }
+    }
+}
+
+/// The cache is derived from the workbook, so it never makes two differ
+impl PartialEq for GraphCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Evaluation cost of one formula cell while profiling
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CellProfile {
+    pub evaluations: usize,
+    pub total: Duration,
+}
+
+/// Limits for evaluating reference cycles iteratively
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iteration {
+    /// Passes over the cycle before giving up (at least 1)
+    pub max_iterations: usize,
+    /// Largest change of any numeric result at which a pass counts as converged
+    pub max_change: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CalcState {
+    mode: CalcMode,
+    values: CalcValues,
+    dirty: BTreeSet<CellKey>,
+    pool: CalcPool,
+    /// `Some` while profiling is on
+    profile: Option<HashMap<CellKey, CellProfile>>,
+    /// `Some` while volatile functions are frozen
+    frozen: Option<Frozen>,
+    /// `Some` when reference cycles are iterated instead of failing
+    iteration: Option<Iteration>,
+    /// Blocks of array results larger than one cell, by anchor
+    pub(super) spills: HashMap<CellKey, SpillArea>,
+    graph: GraphCache,
+}
+
+/// Block of cells a formula reads, resolved to a sheet index
+#[derive(Debug, Clone, PartialEq)]
+pub struct Area {
+    pub sheet: usize,
+    pub rows: RangeInclusive<usize>,
+    pub cols: RangeInclusive<usize>,
+}
+
+impl Area {
+    pub(crate) fn contains(&self, (sheet, row, col): CellKey) -> bool {
+        self.sheet == sheet && self.rows.contains(&row) && self.cols.contains(&col)
+    }
+}
+
+pub(crate) struct Formula {
+    pub key: CellKey,
+    /// `None` when the formula text does not parse
+    pub expr: Option<Expr>,
+    pub reads: Vec<Area>,
+    /// Calls a volatile function such as `NOW()`
+    pub volatile: bool,
+}
+
+/// Formula cells of a workbook with the edges between them
+pub(crate) struct Graph {
+    pub formulas: Vec<Formula>,
+    /// Index in `formulas` of each formula cell
+    pub positions: HashMap<CellKey, usize>,
+    /// `dependents[i]`: formulas reading formula `i`
+    pub dependents: Vec<Vec<usize>>,
+    /// Formulas not on a cycle, each after everything it reads
+    pub order: Vec<usize>,
+    /// Formulas on (or downstream of) a reference cycle
+    pub cyclic: Vec<usize>,
+}
+
+/// Whether `expr` calls `CURRENCY()`, which reads the workbook's rates table
+fn reads_currency_rates(expr: &Expr) -> bool {
+    let mut found = false;
+    expr.visit(&mut |node| {
+        found |= matches!(node, Expr::Call(name, _) if name == "CURRENCY");
+    });
+    found
+}
+
+fn resolve(workbook: &Workbook, sheet: usize, precedent: Precedent, out: &mut Vec<Area>) {
+    let target = |name: Option<String>| match name {
+        Some(name) => workbook.sheet_index(&name),
+        None => Some(sheet),
+    };
+    match precedent {
+        Precedent::Area {
+            sheet: name,
+            rows,
+            cols,
+        } => {
+            if let Some(sheet) = target(name) {
+                out.push(Area { sheet, rows, cols });
+            }
+        }
+        Precedent::Name {
+            sheet: name,
+            name: reference,
+        } => {
+            let Some(sheet) = target(name) else {
+                return;
+            };
+            let table = workbook.sheet_at(sheet).expect("resolved sheet exists");
+            if let Some(col) = table.column_index(&reference) {
+                out.push(Area {
+                    sheet,
+                    rows: 0..=usize::MAX,
+                    cols: col..=col,
+                });
+            } else if let Some(expr) = workbook
+                .name_reference(&reference)
+                .and_then(|text| parse(text).ok())
+            {
+                // Defined names hold plain references, so this recursion ends here
+                for inner in precedents(&expr) {
+                    resolve(workbook, sheet, inner, out);
+                }
+            }
+        }
+        Precedent::Sheets {
+            first,
+            last,
+            reference,
+        } => {
+            let (Some(a), Some(b)) = (workbook.sheet_index(&first), workbook.sheet_index(&last))
+            else {
+                return;
+            };
+            for sheet in a.min(b)..=a.max(b) {
+                resolve(workbook, sheet, (*reference).clone(), out);
+            }
+        }
+    }
+}
+
+/// Add the columns that computed columns in `reads` are evaluated from, so
+/// formulas reading a computed column follow edits to its inputs
+fn expand_computed(workbook: &Workbook, reads: &mut Vec<Area>) {
+    let mut seen = HashSet::new();
+    let mut i = 0;
+    while i < reads.len() {
+        let area = reads[i].clone();
+        let table = &workbook.sheets[area.sheet].table;
+        for computed in table.computed_columns() {
+            if area.cols.contains(&computed.column) && seen.insert((area.sheet, computed.column)) {
+                for col in table.computed_inputs(computed.column) {
+                    reads.push(Area {
+                        sheet: area.sheet,
+                        rows: area.rows.clone(),
+                        cols: col..=col,
+                    });
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+impl Graph {
+    pub(crate) fn build(workbook: &Workbook) -> Graph {
+        let mut formulas = Vec::new();
+        for (s, sheet) in workbook.sheets.iter().enumerate() {
+            for (row, cells) in sheet.table.rows().iter().enumerate() {
+                for (col, text) in cells.iter().enumerate() {
+                    if is_formula(text) {
+                        let expr = parse(text).ok();
+                        let mut reads = Vec::new();
+                        for precedent in expr.iter().flat_map(precedents) {
+                            resolve(workbook, s, precedent, &mut reads);
+                        }
+                        if expr.as_ref().is_some_and(reads_currency_rates) {
+                            let rates = workbook.currency_rates().and_then(|r| parse(r).ok());
+                            for precedent in rates.iter().flat_map(precedents) {
+                                resolve(workbook, s, precedent, &mut reads);
+                            }
+                        }
+                        expand_computed(workbook, &mut reads);
+                        formulas.push(Formula {
+                            key: (s, row, col),
+                            volatile: expr.as_ref().is_some_and(is_volatile),
+                            expr,
+                            reads,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Formula rows per (sheet, col)
+        let mut index: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        for (i, formula) in formulas.iter().enumerate() {
+            let (sheet, row, col) = formula.key;
+            index.entry((sheet, col)).or_default().push((row, i));
+        }
+        // Spilled cells read as their anchor formula
+        let positions: HashMap<CellKey, usize> = formulas
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.key, i))
+            .collect();
+        for (&anchor, area) in workbook.calc.spills.iter().filter(|(_, a)| !a.blocked) {
+            let Some(&i) = positions.get(&anchor) else {
+                continue;
+            };
+            for (sheet, row, col) in area.cells(anchor) {
+                index.entry((sheet, col)).or_default().push((row, i));
+            }
+        }
+        for rows in index.values_mut() {
+            rows.sort_unstable();
+        }
+
+        let mut dependents = vec![Vec::new(); formulas.len()];
+        let mut pending = vec![0usize; formulas.len()];
+        for (i, formula) in formulas.iter().enumerate() {
+            for area in &formula.reads {
+                let width = workbook.sheets[area.sheet].table.column_count();
+                let last_col = (*area.cols.end()).min(width.saturating_sub(1));
+                for col in *area.cols.start()..=last_col {
+                    let Some(rows) = index.get(&(area.sheet, col)) else {
+                        continue;
+                    };
+                    let start = rows.partition_point(|&(row, _)| row < *area.rows.start());
+                    for &(_, j) in rows[start..]
+                        .iter()
+                        .take_while(|&&(row, _)| row <= *area.rows.end())
+                    {
+                        dependents[j].push(i);
+                        pending[i] += 1;
+                    }
+                }
+            }
+        }
+
+        let mut queue: VecDeque<usize> = (0..formulas.len()).filter(|&i| pending[i] == 0).collect();
+        let mut order = Vec::with_capacity(formulas.len());
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for &j in &dependents[i] {
+                pending[j] -= 1;
+                if pending[j] == 0 {
+                    queue.push_back(j);
+                }
+            }
+        }
+        let cyclic = (0..formulas.len()).filter(|&i| pending[i] > 0).collect();
+
+        Graph {
+            formulas,
+            positions,
+            dependents,
+            order,
+            cyclic,
+        }
+    }
+
+    /// Formulas of `order` grouped so that each only reads earlier groups
+    pub(crate) fn levels(&self) -> Vec<Vec<usize>> {
+        let mut depth = vec![0usize; self.formulas.len()];
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for &i in &self.order {
+            let level = depth[i];
+            for &j in &self.dependents[i] {
+                depth[j] = depth[j].max(level + 1);
+            }
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(i);
+        }
+        levels
+    }
+
+    /// Formulas affected by changes to `changed`: those reading a changed
+    /// cell or being one, plus everything downstream of them
+    fn affected(&self, changed: &HashSet<CellKey>) -> Vec<usize> {
+        let mut seen = vec![false; self.formulas.len()];
+        let mut stack: Vec<usize> = self
+            .formulas
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| {
+                changed.contains(&f.key)
+                    || f.reads
+                        .iter()
+                        .any(|area| changed.iter().any(|&key| area.contains(key)))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let mut affected = Vec::new();
+        while let Some(i) = stack.pop() {
+            if !std::mem::replace(&mut seen[i], true) {
+                affected.push(i);
+                stack.extend(&self.dependents[i]);
+            }
+        }
+        affected
+    }
+}
+
+impl Workbook {
+    pub fn calc_mode(&self) -> CalcMode {
+        self.calc.mode
+    }
+
+    /// Switch mode; leaving manual mode recalculates what is dirty
+    pub fn set_calc_mode(&mut self, mode: CalcMode) {
+        self.calc.mode = mode;
+        self.recalculate_if_automatic();
+    }
+
+    pub(crate) fn calculated_values(&self) -> &CalcValues {
+        &self.calc.values
+    }
+
+    /// Formula cells waiting for recalculation, in sheet, row, column order
+    pub fn dirty_cells(&self) -> Vec<CellKey> {
+        self.calc.dirty.iter().copied().collect()
+    }
+
+    /// Text shown for a cell: the calculated result of a formula, the element
+    /// spilled into an empty cell, else the cell text
+    pub fn display_value(&self, sheet: usize, row: usize, col: usize) -> Option<String> {
+        let table = self.sheet_at(sheet)?;
+        if let Some(value) = table.computed_value(row, col) {
+            return Some(value.to_string());
+        }
+        let text = table.cell(row, col);
+        let key = (sheet, row, col);
+        if !is_formula(text) {
+            let spilled = text
+                .trim()
+                .is_empty()
+                .then(|| self.spilled_value(key, &self.calc.values))
+                .flatten();
+            return Some(spilled.map_or_else(|| text.to_string(), |value| value.to_string()));
+        }
+        Some(
+            self.calc
+                .values
+                .get(&key)
+                .map(|value| self.anchor_value(key, value).to_string())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Mark formulas affected by a step dirty and recalculate if automatic
+    pub(super) fn after_changes(&mut self, changes: &[Change]) {
+        if changes.iter().any(|c| matches!(c, Change::Table { .. })) {
+            return self.invalidate_all();
+        }
+        let mut changed: HashSet<CellKey> = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::Cell {
+                    sheet, row, col, ..
+                } => Some((*sheet, *row, *col)),
+                Change::Table { .. } => None,
+            })
+            .collect();
+
+        // Typing into a spill moves it: recalculate what reads its anchor
+        let anchors: Vec<CellKey> = self
+            .calc
+            .spills
+            .iter()
+            .filter(|(&anchor, area)| area.cells(anchor).any(|cell| changed.contains(&cell)))
+            .map(|(&anchor, _)| anchor)
+            .collect();
+        changed.extend(anchors);
+
+        let formula_edited = changes.iter().any(|change| {
+            matches!(change, Change::Cell { old, new, .. } if is_formula(old) || is_formula(new))
+        });
+        if formula_edited {
+            self.calc.graph = GraphCache::default();
+        }
+        let graph = self.graph();
+        for key in &changed {
+            if !graph.positions.contains_key(key) {
+                self.calc.values.remove(key);
+            }
+        }
+        for i in graph.affected(&changed) {
+            self.calc.dirty.insert(graph.formulas[i].key);
+        }
+        self.recalculate_if_automatic_with(&graph);
+    }
+
+    /// Forget every result (after a layout or sheet change) and mark all formulas dirty
+    pub(super) fn invalidate_all(&mut self) {
+        self.calc.values.clear();
+        self.calc.spills.clear();
+        self.calc.graph = GraphCache::default();
+        let graph = self.graph();
+        self.calc.dirty = graph.formulas.iter().map(|f| f.key).collect();
+        self.recalculate_if_automatic_with(&graph);
+    }
+
+    fn recalculate_if_automatic(&mut self) {
+        if self.calc.mode != CalcMode::Manual && !self.calc.dirty.is_empty() {
+            let graph = self.graph();
+            self.recalculate_if_automatic_with(&graph);
+        }
+    }
+
+    fn recalculate_if_automatic_with(&mut self, graph: &Graph) {
+        match self.calc.mode {
+            CalcMode::Automatic => self.calculate(graph, true),
+            CalcMode::AutomaticExceptTables => self.calculate(graph, false),
+            CalcMode::Manual => {}
+        }
+    }
+
+    /// Dependency graph of every formula, built again only after
+    /// `after_changes` or `invalidate_all` dropped it
+    fn graph(&mut self) -> Arc<Graph> {
+        if let Some(graph) = &self.calc.graph.0 {
+            return Arc::clone(graph);
+        }
+        let graph = Arc::new(Graph::build(self));
+        self.calc.graph = GraphCache(Some(Arc::clone(&graph)));
+        graph
+    }
+
+    /// Recalculate every dirty formula, whatever the mode
+    ///
+    /// The graph is rebuilt first, picking up edits made on borrowed sheet
+    /// handles.
+    pub fn calculate_now(&mut self) {
+        self.calc.graph = GraphCache::default();
+        let graph = self.graph();
+        self.calculate(&graph, true);
+    }
+
+    /// Recalculate dirty formulas, then place array results and recalculate
+    /// what reads the cells they moved into or out of, until spills settle
+    fn calculate(&mut self, graph: &Graph, include_tables: bool) {
+        if self.calc.frozen.is_none() {
+            self.mark_volatile_dirty(graph);
+        }
+        self.calculate_pass(graph, include_tables);
+        for _ in 0..SPILL_PASSES {
+            let moved = self.place_spills();
+            if moved.is_empty() {
+                break;
+            }
+            // Spilled cells read as their anchor, so the edges have moved
+            self.calc.graph = GraphCache::default();
+            let graph = self.graph();
+            for i in graph.affected(&moved) {
+                self.calc.dirty.insert(graph.formulas[i].key);
+            }
+            self.calculate_pass(&graph, include_tables);
+        }
+    }
+
+    /// Evaluate dirty formulas level by level and report changed results
+    ///
+    /// Formulas within a level do not read each other, so large levels are
+    /// evaluated in parallel.
+    fn calculate_pass(&mut self, graph: &Graph, include_tables: bool) {
+        let mut values = std::mem::take(&mut self.calc.values);
+        let mut done = Vec::new();
+        let mut changes = Vec::new();
+        let pool = self.calc.pool.clone();
+
+        let mut levels = graph.levels();
+        levels.push(graph.cyclic.clone());
+        let cyclic_level = levels.len() - 1;
+        for (level, members) in levels.iter().enumerate() {
+            let todo: Vec<usize> = members
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    let key = graph.formulas[i].key;
+                    self.calc.dirty.contains(&key)
+                        && (include_tables || !matches!(values.get(&key), Some(Value::Array(_))))
+                })
+                .collect();
+
+            let this = &*self;
+            let snapshot = &values;
+            let profiling = self.calc.profile.is_some();
+            let eval = |i: usize| {
+                let formula = &graph.formulas[i];
+                let started = profiling.then(Instant::now);
+                let value = match &formula.expr {
+                    _ if level == cyclic_level => Value::Error(ErrorValue::Calc),
+                    Some(expr) => {
+                        let (sheet, row, col) = formula.key;
+                        let ctx =
+                            TableContext::with_values(this, sheet, snapshot).for_cell(row, col);
+                        evaluate(expr, &ctx)
+                    }
+                    None => Value::Error(ErrorValue::Value),
+                };
+                (formula.key, value, started.map(|t| t.elapsed()))
+            };
+            let results: Vec<(CellKey, Value, Option<Duration>)> =
+                if level == cyclic_level && self.calc.iteration.is_some() {
+                    self.iterate(graph, &todo, snapshot)
+                } else if todo.len() >= PARALLEL_MIN {
+                    pool.run(|| todo.par_iter().map(|&i| eval(i)).collect())
+                } else {
+                    todo.iter().map(|&i| eval(i)).collect()
+                };
+
+            for (key, value, elapsed) in results {
+                if let (Some(profile), Some(elapsed)) = (&mut self.calc.profile, elapsed) {
+                    let cell = profile.entry(key).or_default();
+                    cell.evaluations += 1;
+                    cell.total += elapsed;
+                }
+                let old = values.get(&key).map(Value::to_string).unwrap_or_default();
+                let new = value.to_string();
+                if old != new {
+                    changes.push(Change::Cell {
+                        sheet: key.0,
+                        row: key.1,
+                        col: key.2,
+                        old,
+                        new,
+                    });
+                }
+                values.insert(key, value);
+                done.push(key);
+            }
+        }
+
+        self.calc.values = values;
+        for key in done {
+            self.calc.dirty.remove(&key);
+        }
+        if !changes.is_empty() {
+            self.notify(&changes, true);
+        }
+    }
+
+    /// Evaluate the formulas of reference cycles in passes until they settle
+    ///
+    /// Each formula reads the latest results, including those of earlier
+    /// formulas in the same pass; the final results are returned with the
+    /// total evaluation time of each formula when profiling.
+    fn iterate(
+        &self,
+        graph: &Graph,
+        todo: &[usize],
+        values: &CalcValues,
+    ) -> Vec<(CellKey, Value, Option<Duration>)> {
+        let Some(iteration) = self.calc.iteration else {
+            return Vec::new();
+        };
+        let profiling = self.calc.profile.is_some();
+        // Start from the previous numeric results; anything else (such as
+        // the `#CALC!` of a cycle that was not iterated) starts blank
+        let mut working = values.clone();
+        for &i in todo {
+            let key = graph.formulas[i].key;
+            if !matches!(working.get(&key), Some(Value::Number(_))) {
+                working.remove(&key);
+            }
+        }
+        let mut elapsed = vec![Duration::ZERO; todo.len()];
+
+        for _ in 0..iteration.max_iterations.max(1) {
+            let mut change = 0.0f64;
+            for (slot, &i) in todo.iter().enumerate() {
+                let formula = &graph.formulas[i];
+                let started = profiling.then(Instant::now);
+                let value = match &formula.expr {
+                    Some(expr) => {
+                        let (sheet, row, col) = formula.key;
+                        let ctx =
+                            TableContext::with_values(self, sheet, &working).for_cell(row, col);
+                        evaluate(expr, &ctx)
+                    }
+                    None => Value::Error(ErrorValue::Value),
+                };
+                if let Some(started) = started {
+                    elapsed[slot] += started.elapsed();
+                }
+
+                let moved = match (working.get(&formula.key), &value) {
+                    (Some(Value::Number(old)), Value::Number(new)) => (new - old).abs(),
+                    (None | Some(Value::Blank), Value::Number(new)) => new.abs(),
+                    (Some(old), new) if old.to_string() == new.to_string() => 0.0,
+                    _ => f64::INFINITY,
+                };
+                change = change.max(moved);
+                working.insert(formula.key, value);
+            }
+            if change <= iteration.max_change {
+                break;
+            }
+        }
+
+        todo.iter()
+            .zip(elapsed)
+            .map(|(&i, elapsed)| {
+                let key = graph.formulas[i].key;
+                let value = working.remove(&key).unwrap_or(Value::Blank);
+                (key, value, profiling.then_some(elapsed))
+            })
+            .collect()
+    }
+
+    pub fn iteration(&self) -> Option<Iteration> {
+        self.calc.iteration
+    }
+
+    /// Iterate reference cycles within `iteration`, or make them `#CALC!` again with `None`
+    pub fn set_iteration(&mut self, iteration: Option<Iteration>) -> Result<(), String> {
+        if let Some(iteration) = iteration {
+            if iteration.max_iterations == 0 {
+                return Err("Iteration limit must be at least 1".to_string());
+            }
+            if !(iteration.max_change >= 0.0 && iteration.max_change.is_finite()) {
+                return Err("Maximum change must be a non-negative number".to_string());
+            }
+        }
+        self.calc.iteration = iteration;
+        let graph = self.graph();
+        for &i in &graph.cyclic {
+            self.calc.dirty.insert(graph.formulas[i].key);
+        }
+        self.recalculate_if_automatic_with(&graph);
+        Ok(())
+    }
+
+    /// Mark volatile formulas and everything reading them dirty
+    fn mark_volatile_dirty(&mut self, graph: &Graph) {
+        let mut seen = vec![false; graph.formulas.len()];
+        let mut stack: Vec<usize> = (0..graph.formulas.len())
+            .filter(|&i| graph.formulas[i].volatile)
+            .collect();
+        while let Some(i) = stack.pop() {
+            if !std::mem::replace(&mut seen[i], true) {
+                self.calc.dirty.insert(graph.formulas[i].key);
+                stack.extend(&graph.dependents[i]);
+            }
+        }
+    }
+
+    pub fn volatile_frozen(&self) -> Option<Frozen> {
+        self.calc.frozen
+    }
+
+    /// Freeze volatile functions at `frozen`, or let them follow the real
+    /// clock again with `None`; volatile formulas recalculate either way
+    pub fn set_volatile_frozen(&mut self, frozen: Option<Frozen>) {
+        self.calc.frozen = frozen;
+        let graph = self.graph();
+        self.mark_volatile_dirty(&graph);
+        self.recalculate_if_automatic_with(&graph);
+    }
+
+    /// Turn profiling on (starting from empty counters) or off
+    pub fn set_calc_profiling(&mut self, enabled: bool) {
+        self.calc.profile = enabled.then(HashMap::new);
+    }
+
+    /// Up to `limit` profiled cells, by total evaluation time, slowest first
+    pub fn slowest_cells(&self, limit: usize) -> Vec<(CellKey, CellProfile)> {
+        let mut cells: Vec<(CellKey, CellProfile)> = self
+            .calc
+            .profile
+            .iter()
+            .flatten()
+            .map(|(&key, &profile)| (key, profile))
+            .collect();
+        cells.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(&b.0)));
+        cells.truncate(limit);
+        cells
+    }
+
+    /// Worker threads used for recalculation; 0 means one per core
+    pub fn set_calc_threads(&mut self, threads: usize) -> Result<(), String> {
+        self.calc.pool = CalcPool::new(threads)?;
+        Ok(())
+    }
+}
+
+/// Set the calculation mode
+///
+/// # Arguments
+/// * `mode` - 0 = automatic, 1 = automatic except array formulas, 2 = manual
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_calc_mode(workbook: *mut Workbook, mode: u32) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(mode) = CalcMode::from_raw(mode) else {
+        return error_string(&format!("Unknown calculation mode {}", mode));
+    };
+
+    workbook.set_calc_mode(mode);
+    std::ptr::null_mut()
+}
+
+/// Current calculation mode (see tessera_set_calc_mode)
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_calc_mode(workbook: *const Workbook) -> u32 {
+    workbook_arg(workbook).map_or(0, |w| w.calc_mode() as u32)
+}
+
+/// Recalculate every dirty formula now
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_calculate_now(workbook: *mut Workbook) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    workbook.calculate_now();
+    std::ptr::null_mut()
+}
+
+/// Set how many worker threads recalculation may use
+///
+/// # Arguments
+/// * `threads` - Thread count; 0 = one per core, 1 = no parallelism
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_calc_threads(
+    workbook: *mut Workbook,
+    threads: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.set_calc_threads(threads) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Start (resetting the counters) or stop recording formula evaluation times
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_calc_profiling(workbook: *mut Workbook, enabled: bool) {
+    if let Some(workbook) = workbook_arg_mut(workbook) {
+        workbook.set_calc_profiling(enabled);
+    }
+}
+
+/// Turn iterative calculation of reference cycles on or off
+///
+/// # Arguments
+/// * `enabled` - True to iterate cycles, false to make them `#CALC!`
+/// * `max_iterations` - Passes over a cycle before stopping (at least 1)
+/// * `max_change` - Largest change of a result at which iteration stops early
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_iterative_calc(
+    workbook: *mut Workbook,
+    enabled: bool,
+    max_iterations: usize,
+    max_change: f64,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    let iteration = enabled.then_some(Iteration {
+        max_iterations,
+        max_change,
+    });
+    match workbook.set_iteration(iteration) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Freeze or unfreeze volatile functions
+///
+/// # Arguments
+/// * `frozen` - True to freeze, false to follow the real clock again
+/// * `now` - Serial date-time returned by `NOW()` while frozen
+/// * `seed` - Seed of `RAND()` and `RANDBETWEEN()` while frozen
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_volatile_frozen(
+    workbook: *mut Workbook,
+    frozen: bool,
+    now: f64,
+    seed: u64,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    if frozen && !now.is_finite() {
+        return error_string("Frozen time must be a finite serial number");
+    }
+
+    workbook.set_volatile_frozen(frozen.then_some(Frozen { now, seed }));
+    std::ptr::null_mut()
+}
+
+/// Formula cells that took the most evaluation time while profiling
+///
+/// # Arguments
+/// * `limit` - Maximum number of cells returned
+///
+/// # Returns
+/// StringResult with a JSON array of `{cell: [sheet, row, col], reference,
+/// evaluations, total_ms, mean_ms}`, slowest first (empty when profiling is off)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_calc_profile(
+    workbook: *const Workbook,
+    limit: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    let cells: Vec<_> = workbook
+        .slowest_cells(limit)
+        .into_iter()
+        .map(|(key, profile)| {
+            let total_ms = profile.total.as_secs_f64() * 1000.0;
+            serde_json::json!({
+                "cell": [key.0, key.1, key.2],
+                "reference": cell_ref(workbook, key),
+                "evaluations": profile.evaluations,
+                "total_ms": total_ms,
+                "mean_ms": total_ms / profile.evaluations.max(1) as f64,
+            })
+        })
+        .collect();
+    StringResult::success(&serde_json::Value::from(cells).to_string())
+}
+
+/// Formula cells waiting for recalculation
+///
+/// # Returns
+/// StringResult with a JSON array of `[sheet, row, col]` triples
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_dirty_cells(workbook: *const Workbook) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    let cells: Vec<[usize; 3]> = workbook
+        .dirty_cells()
+        .into_iter()
+        .map(|(sheet, row, col)| [sheet, row, col])
+        .collect();
+    StringResult::success(&serde_json::Value::from(cells).to_string())
+}
+
+/// Text to display for a cell: the calculated result of a formula cell
+/// (possibly stale in manual mode), otherwise the cell text
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_value(
+    workbook: *const Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    match workbook.display_value(sheet, row, col) {
+        Some(text) => StringResult::success(&text),
+        None => StringResult::error(&format!("Sheet {} is out of range", sheet)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    fn workbook() -> Workbook {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["Qty".into(), "Total".into()],
+            vec![
+                vec!["2".into(), "=A1*10".into()],
+                vec!["3".into(), "=B1+A2".into()],
+            ],
+        );
+        workbook.add_sheet("Sheet1", table).unwrap();
+        workbook
+            .add_sheet(
+                "Summary",
+                TesseraTable::from_rows(vec!["X".into()], vec![vec!["=Sheet1!B2*2".into()]]),
+            )
+            .unwrap();
+        workbook
+    }
+
+    #[test]
+    fn test_automatic_recalculation() {
+        let mut workbook = workbook();
+        let value = |w: &Workbook, sheet, row, col| w.display_value(sheet, row, col).unwrap();
+        assert_eq!(value(&workbook, 0, 1, 1), "23");
+        assert_eq!(value(&workbook, 1, 0, 0), "46");
+
+        workbook.set_cell(0, 0, 0, "5".into()).unwrap();
+        assert_eq!(value(&workbook, 1, 0, 0), "106");
+        workbook.set_cell(0, 0, 1, "=B2".into()).unwrap();
+        assert_eq!(value(&workbook, 0, 0, 1), "#CALC!");
+        workbook.undo().unwrap();
+        assert_eq!(value(&workbook, 0, 1, 1), "53");
+        assert!(workbook.dirty_cells().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let rows = (0..500)
+            .map(|i| {
+                vec![
+                    i.to_string(),
+                    format!("=A{}*2", i + 1),
+                    format!("=B{}+A{}", i + 1, (i + 1) % 500 + 1),
+                ]
+            })
+            .collect();
+        let table = TesseraTable::from_rows(vec!["A".into(), "B".into(), "C".into()], rows);
+
+        let mut results = Vec::new();
+        for threads in [1, 4] {
+            let mut workbook = Workbook::new();
+            workbook.set_calc_threads(threads).unwrap();
+            workbook.add_sheet("Data", table.clone()).unwrap();
+            assert_eq!(workbook.display_value(0, 499, 2).unwrap(), "998");
+            results.push(workbook.calculated_values().clone());
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn test_formula_reading_computed_column() {
+        let mut workbook = workbook();
+        workbook
+            .sheet_at_mut(0)
+            .unwrap()
+            .insert_columns(2, vec!["Double".into()])
+            .unwrap();
+        workbook
+            .sheet_at_mut(0)
+            .unwrap()
+            .set_computed_column(2, "=[Qty]*2")
+            .unwrap();
+        workbook
+            .add_sheet(
+                "Check",
+                TesseraTable::from_rows(vec!["X".into()], vec![vec!["=Sheet1!C2+1".into()]]),
+            )
+            .unwrap();
+        assert_eq!(workbook.display_value(0, 1, 2).unwrap(), "6");
+        assert_eq!(workbook.display_value(2, 0, 0).unwrap(), "7");
+
+        workbook.set_cell(0, 1, 0, "10".into()).unwrap();
+        assert_eq!(workbook.display_value(2, 0, 0).unwrap(), "21");
+    }
+
+    #[test]
+    fn test_profiling_counts_evaluations() {
+        let mut workbook = workbook();
+        assert!(workbook.slowest_cells(10).is_empty());
+
+        workbook.set_calc_profiling(true);
+        workbook.set_cell(0, 0, 0, "5".into()).unwrap();
+        workbook.set_cell(0, 1, 0, "6".into()).unwrap();
+        let cells = workbook.slowest_cells(10);
+        let count = |key| {
+            cells
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, p)| p.evaluations)
+        };
+        assert_eq!(cells.len(), 3);
+        assert_eq!(count((0, 0, 1)), Some(1));
+        assert_eq!(count((1, 0, 0)), Some(2));
+        assert_eq!(workbook.slowest_cells(1).len(), 1);
+
+        workbook.set_calc_profiling(false);
+        assert!(workbook.slowest_cells(10).is_empty());
+    }
+
+    #[test]
+    fn test_volatile_recalculate_unless_frozen() {
+        let mut workbook = workbook();
+        workbook.set_calc_mode(CalcMode::Manual);
+        workbook.set_cell(0, 0, 0, "=RAND()".into()).unwrap();
+        workbook.set_cell(0, 1, 0, "=TODAY()".into()).unwrap();
+        workbook.calculate_now();
+        assert!(workbook.dirty_cells().is_empty());
+
+        let results = |w: &mut Workbook| {
+            w.calculate_now();
+            [(0, 0, 1), (0, 1, 1), (1, 0, 0)].map(|(s, r, c)| w.display_value(s, r, c).unwrap())
+        };
+        let first = results(&mut workbook);
+        assert_ne!(first[0], results(&mut workbook)[0]);
+
+        workbook.set_volatile_frozen(Some(Frozen {
+            now: 45_000.75,
+            seed: 42,
+        }));
+        let frozen = results(&mut workbook);
+        assert_eq!(frozen, results(&mut workbook));
+        let random: f64 = workbook.display_value(0, 0, 0).unwrap().parse().unwrap();
+        assert_eq!(frozen[0], (random * 10.0).to_string());
+        assert_eq!(workbook.display_value(0, 1, 0).unwrap(), "45000");
+
+        workbook.set_volatile_frozen(None);
+        workbook.set_volatile_frozen(Some(Frozen {
+            now: 45_000.75,
+            seed: 42,
+        }));
+        assert_eq!(results(&mut workbook), frozen);
+    }
+
+    #[test]
+    fn test_iterative_circular_reference() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["Principal".into(), "Balance".into(), "Interest".into()],
+            vec![vec!["1000".into(), "=A1+C1".into(), "=B1*0.05".into()]],
+        );
+        workbook.add_sheet("Loan", table).unwrap();
+        assert_eq!(workbook.display_value(0, 0, 1).unwrap(), "#CALC!");
+
+        let iteration = Iteration {
+            max_iterations: 100,
+            max_change: 0.0001,
+        };
+        workbook.set_iteration(Some(iteration)).unwrap();
+        let balance: f64 = workbook.display_value(0, 0, 1).unwrap().parse().unwrap();
+        assert!((balance - 1000.0 / 0.95).abs() < 0.001);
+
+        workbook.set_cell(0, 0, 0, "2000".into()).unwrap();
+        let balance: f64 = workbook.display_value(0, 0, 1).unwrap().parse().unwrap();
+        assert!((balance - 2000.0 / 0.95).abs() < 0.001);
+
+        let capped = Iteration {
+            max_iterations: 1,
+            ..iteration
+        };
+        workbook.set_iteration(Some(capped)).unwrap();
+        assert!(workbook
+            .set_iteration(Some(Iteration {
+                max_iterations: 0,
+                ..iteration
+            }))
+            .is_err());
+        workbook.set_iteration(None).unwrap();
+        assert_eq!(workbook.display_value(0, 0, 2).unwrap(), "#CALC!");
+    }
+
+    #[test]
+    fn test_graph_kept_until_formulas_change() {
+        let mut workbook = workbook();
+        workbook.set_calc_mode(CalcMode::Manual);
+        let graph = workbook.graph();
+
+        workbook.set_cell(0, 0, 0, "7".into()).unwrap();
+        assert!(Arc::ptr_eq(&graph, &workbook.graph()));
+
+        workbook.set_cell(0, 0, 0, "=6+1".into()).unwrap();
+        assert!(!Arc::ptr_eq(&graph, &workbook.graph()));
+        workbook.set_cell(1, 0, 0, "=Sheet1!A1".into()).unwrap();
+        workbook.set_cell(0, 0, 0, "9".into()).unwrap();
+        assert!(workbook.dirty_cells().contains(&(1, 0, 0)));
+
+        workbook.calculate_now();
+        assert_eq!(workbook.display_value(1, 0, 0).unwrap(), "9");
+    }
+
+    #[test]
+    fn test_manual_mode_defers() {
+        let mut workbook = workbook();
+        workbook.set_calc_mode(CalcMode::Manual);
+
+        workbook.set_cell(0, 1, 0, "4".into()).unwrap();
+        assert_eq!(workbook.dirty_cells(), [(0, 1, 1), (1, 0, 0)]);
+        assert_eq!(workbook.display_value(1, 0, 0).unwrap(), "46");
+
+        workbook.calculate_now();
+        assert!(workbook.dirty_cells().is_empty());
+        assert_eq!(workbook.display_value(1, 0, 0).unwrap(), "48");
+    }
+}
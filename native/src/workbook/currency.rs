@@ -0,0 +1,113 @@
+//! Exchange rates for `CURRENCY()`
+//!
+//! The host registers where the rates live: a range such as `Rates!A:C` or a
+//! defined name. Its rows hold a currency code, the rate (units of that
+//! currency per unit of a common base currency, which should be listed with
+//! rate 1) and optionally the date the rate applies from. Rows whose rate is
+//! not a positive number, such as a header row, are ignored. Formulas
+//! calling `CURRENCY` recalculate when the rates change.
+
+use std::os::raw::c_char;
+
+use super::{check_name_reference, workbook_arg_mut, Workbook};
+use crate::ffi::{error_string, str_arg};
+
+impl Workbook {
+    /// Reference of the rates table, `None` until the host registers one
+    pub fn currency_rates(&self) -> Option<&str> {
+        self.currency_rates.as_deref()
+    }
+
+    /// Register the rates table (a range reference or defined name), or
+    /// unregister it with `None`
+    pub fn set_currency_rates(&mut self, reference: Option<&str>) -> Result<(), String> {
+        let reference = reference.map(|r| r.trim().trim_start_matches('=').trim());
+        if let Some(reference) = reference {
+            if self.name_reference(reference).is_none() {
+                check_name_reference(reference)?;
+            }
+        }
+        self.currency_rates = reference.map(str::to_string);
+        self.invalidate_all();
+        Ok(())
+    }
+}
+
+/// Register the exchange rates read by `CURRENCY()`
+///
+/// # Arguments
+/// * `reference` - Range such as `Rates!A:C` or a defined name; rows hold a
+///   currency code, its rate against a common base and optionally the date
+///   the rate applies from. Null or empty unregisters the rates.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `reference` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_set_currency_rates(
+    workbook: *mut Workbook,
+    reference: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    let reference = str_arg(reference).filter(|r| !r.trim().is_empty());
+    match workbook.set_currency_rates(reference) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    fn sheet(headers: &[&str], rows: &[&[&str]]) -> TesseraTable {
+        TesseraTable::from_rows(
+            headers.iter().map(|h| h.to_string()).collect(),
+            rows.iter()
+                .map(|r| r.iter().map(|c| c.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_currency_rates() {
+        let mut workbook = Workbook::new();
+        let rates = sheet(
+            &["Code", "Rate", "From"],
+            &[
+                &["USD", "1", ""],
+                &["EUR", "0.8", "45292"],
+                &["EUR", "0.9", "45658"],
+                &["JPY", "150", "45292"],
+            ],
+        );
+        let prices = sheet(
+            &["Price", "EUR", "On date"],
+            &[&[
+                "100",
+                "=CURRENCY(A1, \"usd\", \"EUR\")",
+                "=CURRENCY(A1, \"USD\", \"EUR\", 45300)",
+            ]],
+        );
+        workbook.add_sheet("Rates", rates).unwrap();
+        workbook.add_sheet("Prices", prices).unwrap();
+
+        let shown = |workbook: &Workbook, col| workbook.display_value(1, 0, col).unwrap();
+        assert_eq!(shown(&workbook, 1), "#N/A");
+
+        workbook.set_currency_rates(Some("Rates!A:C")).unwrap();
+        assert_eq!(workbook.currency_rates(), Some("Rates!A:C"));
+        assert_eq!(shown(&workbook, 1), "90");
+        assert_eq!(shown(&workbook, 2), "80");
+
+        workbook.set_cell(0, 2, 1, "0.5".into()).unwrap();
+        assert_eq!(shown(&workbook, 1), "50");
+        assert!(workbook.set_currency_rates(Some("SUM(A1)")).is_err());
+    }
+}
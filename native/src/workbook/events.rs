@@ -0,0 +1,195 @@
+//! Change notifications for the host
+//!
+//! The host registers one callback per workbook. It receives every batch of
+//! cell changes made through the workbook (a single edit, a committed batch,
+//! an undo or a redo) so the TUI can repaint only the affected regions. A
+//! change that alters the layout of a sheet (inserted rows, a sort, an
+//! import) is reported as one whole-sheet entry instead of per cell.
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+use super::history::Change;
+use super::{workbook_arg_mut, Workbook};
+use crate::ffi::error_string;
+
+/// Row and column of an entry that covers the whole sheet
+pub const WHOLE_SHEET: usize = usize::MAX;
+
+/// One changed cell, as passed to the host callback
+///
+/// `old_value`/`new_value` are only valid for the duration of the callback;
+/// both are null for whole-sheet entries (`row` and `col` set to `usize::MAX`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CellChange {
+    pub sheet: usize,
+    pub row: usize,
+    pub col: usize,
+    pub old_value: *const c_char,
+    pub new_value: *const c_char,
+}
+
+pub type ChangeCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, changes: *const CellChange, count: usize);
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Listener {
+    callback: ChangeCallback,
+    user_data: *mut c_void,
+}
+
+// SAFETY: the callback and user data are only handed back to the host on the
+// thread making the edit; parallel recalculation never touches them
+unsafe impl Send for Listener {}
+unsafe impl Sync for Listener {}
+
+impl PartialEq for Listener {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::fn_addr_eq(self.callback, other.callback) && self.user_data == other.user_data
+    }
+}
+
+fn c_text(value: &str) -> CString {
+    CString::new(value)
+        .or_else(|_| CString::new(value.replace('\0', "")))
+        .unwrap_or_default()
+}
+
+impl Workbook {
+    /// Report changes to the registered callback, if any
+    ///
+    /// `forward` is false when the changes are being reverted (undo), which
+    /// swaps the old and new values.
+    pub(super) fn notify(&self, changes: &[Change], forward: bool) {
+        let Some(listener) = self.listener else {
+            return;
+        };
+
+        // The strings must outlive the callback, so collect them first
+        let mut texts = Vec::new();
+        let mut entries = Vec::with_capacity(changes.len());
+        for change in changes {
+            match change {
+                Change::Cell {
+                    sheet,
+                    row,
+                    col,
+                    old,
+                    new,
+                } => {
+                    let (old, new) = if forward { (old, new) } else { (new, old) };
+                    let (old, new) = (c_text(old), c_text(new));
+                    entries.push(CellChange {
+                        sheet: *sheet,
+                        row: *row,
+                        col: *col,
+                        old_value: old.as_ptr(),
+                        new_value: new.as_ptr(),
+                    });
+                    texts.push((old, new));
+                }
+                Change::Table { sheet, .. } => entries.push(CellChange {
+                    sheet: *sheet,
+                    row: WHOLE_SHEET,
+                    col: WHOLE_SHEET,
+                    old_value: std::ptr::null(),
+                    new_value: std::ptr::null(),
+                }),
+            }
+        }
+
+        // SAFETY: the host promised the callback is safe to call with its user data
+        unsafe { (listener.callback)(listener.user_data, entries.as_ptr(), entries.len()) };
+        drop(texts);
+    }
+}
+
+/// Register the change callback of a workbook, replacing any previous one
+///
+/// Pass a null `callback` to unregister. The callback runs synchronously on the
+/// thread making the edit and must not call back into the same workbook.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `user_data` is passed back untouched
+/// and must stay valid for as long as the callback is registered
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_set_change_callback(
+    workbook: *mut Workbook,
+    callback: Option<ChangeCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    workbook.listener = callback.map(|callback| Listener {
+        callback,
+        user_data,
+    });
+    std::ptr::null_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+    use crate::table::TesseraTable;
+
+    type Seen = Vec<(usize, usize, String, String)>;
+
+    unsafe extern "C" fn collect(user_data: *mut c_void, changes: *const CellChange, count: usize) {
+        let seen = &mut *(user_data as *mut Vec<Seen>);
+        let text = |ptr: *const c_char| {
+            if ptr.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(ptr).to_string_lossy().into_owned()
+            }
+        };
+        seen.push(
+            std::slice::from_raw_parts(changes, count)
+                .iter()
+                .map(|c| (c.row, c.col, text(c.old_value), text(c.new_value)))
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn test_change_batches() {
+        let mut seen: Vec<Seen> = Vec::new();
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(vec!["A".into()], vec![vec!["1".into()]]);
+        workbook.add_sheet("Sheet1", table).unwrap();
+        let ptr = &mut workbook as *mut Workbook;
+        let seen_ptr = &mut seen as *mut Vec<Seen> as *mut c_void;
+        unsafe {
+            assert!(tessera_workbook_set_change_callback(ptr, Some(collect), seen_ptr).is_null())
+        };
+
+        workbook.set_cell(0, 0, 0, "2".into()).unwrap();
+        workbook.begin_undo_group("Paste");
+        workbook.set_cell(0, 0, 0, "3".into()).unwrap();
+        workbook
+            .edit_sheet(0, "Insert rows", |t| t.insert_rows(0, 1))
+            .unwrap();
+        assert_eq!(seen.len(), 1);
+        workbook.end_undo_group().unwrap();
+        workbook.undo().unwrap();
+
+        let cell = |old: &str, new: &str| (0, 0, old.to_string(), new.to_string());
+        let sheet = (WHOLE_SHEET, WHOLE_SHEET, String::new(), String::new());
+        assert_eq!(
+            seen,
+            [
+                vec![cell("1", "2")],
+                vec![cell("2", "3"), sheet.clone()],
+                vec![cell("3", "2"), sheet],
+            ]
+        );
+    }
+}
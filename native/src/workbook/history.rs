@@ -0,0 +1,771 @@
+//! Undo/redo command log for workbook edits
+//!
+//! Edits made through the workbook entry points in this module (cell edits,
+//! structural changes, in-place sorts, pastes and imports) are recorded as
+//! steps that `tessera_undo` and `tessera_redo` replay. Edits that only touch
+//! cell text are stored as per-cell diffs; anything that changes the layout
+//! of a sheet keeps a before/after copy of it. Everything recorded between
+//! `tessera_begin_undo_group` and `tessera_end_undo_group` undoes as one step;
+//! the batch variants (`tessera_begin_batch`, `tessera_commit`,
+//! `tessera_rollback`) can also throw a half-done operation away.
+//!
+//! Edits made directly on a borrowed sheet handle bypass the log. Removing or
+//! moving a sheet clears it, since recorded sheet indexes would go stale.
+
+use std::os::raw::c_char;
+
+use super::{workbook_arg, workbook_arg_mut, Workbook};
+use crate::ffi::{error_string, str_arg, str_array_arg};
+use crate::query::sort::{sort_permutation, sort_specs, SortKey};
+use crate::table::{CellPosition, CellRange, TesseraTable};
+use crate::transform::paste::{copy_range, move_range};
+use crate::StringResult;
+
+/// Oldest steps are dropped beyond this many
+const MAX_STEPS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Change {
+    Cell {
+        sheet: usize,
+        row: usize,
+        col: usize,
+        old: String,
+        new: String,
+    },
+    Table {
+        sheet: usize,
+        before: Box<TesseraTable>,
+        after: Box<TesseraTable>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    label: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    group: Option<Step>,
+    // Change count at each open begin, innermost last
+    marks: Vec<usize>,
+}
+
+impl History {
+    fn push(&mut self, step: Step) {
+        self.undo.push(step);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = History::default();
+    }
+}
+
+/// Cell-level diff when the layout is unchanged, otherwise a full snapshot
+fn diff(sheet: usize, before: TesseraTable, after: &TesseraTable) -> Vec<Change> {
+    if !before.same_layout(after) {
+        return vec![Change::Table {
+            sheet,
+            before: Box::new(before),
+            after: Box::new(after.clone()),
+        }];
+    }
+
+    let mut changes = Vec::new();
+    for row in 0..before.row_count() {
+        for (col, (old, new)) in before.row(row).zip(after.row(row)).enumerate() {
+            if old != new {
+                changes.push(Change::Cell {
+                    sheet,
+                    row,
+                    col,
+                    old: old.to_string(),
+                    new: new.to_string(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+impl Workbook {
+    fn record(&mut self, label: &str, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        match &mut self.history.group {
+            Some(group) => group.changes.extend(changes),
+            None => {
+                self.notify(&changes, true);
+                self.after_changes(&changes);
+                self.history.push(Step {
+                    label: label.to_string(),
+                    changes,
+                });
+            }
+        }
+    }
+
+    /// Once the outermost group is closed, record what is left of it
+    fn finish_group(&mut self) {
+        if !self.history.marks.is_empty() {
+            return;
+        }
+        if let Some(step) = self.history.group.take() {
+            if !step.changes.is_empty() {
+                self.notify(&step.changes, true);
+                self.after_changes(&step.changes);
+                self.history.push(step);
+            }
+        }
+    }
+
+    /// Overwrite one cell as an undoable step
+    pub fn set_cell(
+        &mut self,
+        sheet: usize,
+        row: usize,
+        col: usize,
+        value: String,
+    ) -> Result<(), String> {
+        self.check_index(sheet)?;
+        let table = &mut self.sheets[sheet].table;
+        let old = table.cell(row, col).to_string();
+        table.set_cell(row, col, value.clone())?;
+
+        if old != value {
+            let change = Change::Cell {
+                sheet,
+                row,
+                col,
+                old,
+                new: value,
+            };
+            self.record("Edit cell", vec![change]);
+        }
+        Ok(())
+    }
+
+    /// Run an edit on one sheet as an undoable step
+    ///
+    /// The sheet is restored untouched when `edit` fails.
+    pub fn edit_sheet<T>(
+        &mut self,
+        sheet: usize,
+        label: &str,
+        edit: impl FnOnce(&mut TesseraTable) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.check_index(sheet)?;
+        let table = &mut *self.sheets[sheet].table;
+        let before = table.clone();
+
+        match edit(table) {
+            Ok(value) => {
+                let changes = diff(sheet, before, table);
+                self.record(label, changes);
+                Ok(value)
+            }
+            Err(msg) => {
+                *table = before;
+                Err(msg)
+            }
+        }
+    }
+
+    /// Start collecting edits into a single undo step; groups may nest
+    pub fn begin_undo_group(&mut self, label: &str) {
+        let group = self.history.group.get_or_insert_with(|| Step {
+            label: label.to_string(),
+            changes: Vec::new(),
+        });
+        self.history.marks.push(group.changes.len());
+    }
+
+    /// Close the innermost group; the outermost one becomes a single step
+    pub fn end_undo_group(&mut self) -> Result<(), String> {
+        if self.history.marks.pop().is_none() {
+            return Err("No undo group is open".to_string());
+        }
+        self.finish_group();
+        Ok(())
+    }
+
+    /// Discard every edit since the innermost open begin and close that group
+    pub fn rollback_undo_group(&mut self) -> Result<(), String> {
+        let Some(mark) = self.history.marks.pop() else {
+            return Err("No undo group is open".to_string());
+        };
+        let mut reverted = match &mut self.history.group {
+            Some(group) => group.changes.split_off(mark),
+            None => Vec::new(),
+        };
+        while let Some(change) = reverted.pop() {
+            self.replay(&change, false);
+        }
+        self.finish_group();
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.marks.is_empty() && !self.history.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.marks.is_empty() && !self.history.redo.is_empty()
+    }
+
+    /// Labels of the undo and redo stacks, most recent first
+    pub fn undo_labels(&self) -> (Vec<&str>, Vec<&str>) {
+        fn labels(steps: &[Step]) -> Vec<&str> {
+            steps.iter().rev().map(|step| step.label.as_str()).collect()
+        }
+        (labels(&self.history.undo), labels(&self.history.redo))
+    }
+
+    pub fn undo(&mut self) -> Result<(), String> {
+        if !self.history.marks.is_empty() {
+            return Err("Cannot undo while an undo group is open".to_string());
+        }
+        let step = self.history.undo.pop().ok_or("Nothing to undo")?;
+        for change in step.changes.iter().rev() {
+            self.replay(change, false);
+        }
+        self.notify(&step.changes, false);
+        self.after_changes(&step.changes);
+        self.history.redo.push(step);
+        Ok(())
+    }
+
+    pub fn redo(&mut self) -> Result<(), String> {
+        if !self.history.marks.is_empty() {
+            return Err("Cannot redo while an undo group is open".to_string());
+        }
+        let step = self.history.redo.pop().ok_or("Nothing to redo")?;
+        for change in &step.changes {
+            self.replay(change, true);
+        }
+        self.notify(&step.changes, true);
+        self.after_changes(&step.changes);
+        self.history.undo.push(step);
+        Ok(())
+    }
+
+    fn replay(&mut self, change: &Change, forward: bool) {
+        match change {
+            Change::Cell {
+                sheet,
+                row,
+                col,
+                old,
+                new,
+            } => {
+                let value = if forward { new } else { old };
+                if let Some(table) = self.sheet_at_mut(*sheet) {
+                    let _ = table.set_cell(*row, *col, value.clone());
+                }
+            }
+            Change::Table {
+                sheet,
+                before,
+                after,
+            } => {
+                let state = if forward { after } else { before };
+                if let Some(table) = self.sheet_at_mut(*sheet) {
+                    // Assign in place so borrowed sheet handles stay valid
+                    *table = TesseraTable::clone(state);
+                }
+            }
+        }
+    }
+}
+
+/// Overwrite a cell as an undoable step
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `value` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_set_cell(
+    workbook: *mut Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+    value: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(value) = str_arg(value) else {
+        return error_string("Invalid cell value encoding");
+    };
+
+    match workbook.set_cell(sheet, row, col, value.to_string()) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Insert empty rows as an undoable step (see tessera_table_insert_rows)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_insert_rows(
+    workbook: *mut Workbook,
+    sheet: usize,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.edit_sheet(sheet, "Insert rows", |t| t.insert_rows(at, count)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Delete rows as an undoable step (see tessera_table_delete_rows)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_delete_rows(
+    workbook: *mut Workbook,
+    sheet: usize,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.edit_sheet(sheet, "Delete rows", |t| t.delete_rows(at, count)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Insert named columns as an undoable step (see tessera_table_insert_columns)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `names_ptr` must point to `count` C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_insert_columns(
+    workbook: *mut Workbook,
+    sheet: usize,
+    at: usize,
+    names_ptr: *const *const c_char,
+    count: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(names) = str_array_arg(names_ptr, count) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.edit_sheet(sheet, "Insert columns", |t| t.insert_columns(at, names)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Delete columns as an undoable step (see tessera_table_delete_columns)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_delete_columns(
+    workbook: *mut Workbook,
+    sheet: usize,
+    at: usize,
+    count: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.edit_sheet(sheet, "Delete columns", |t| t.delete_columns(at, count)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Sort the rows of a sheet in place as an undoable step (see tessera_sort_in_place)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `keys_ptr` must point to `keys_count` keys
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_sort(
+    workbook: *mut Workbook,
+    sheet: usize,
+    keys_ptr: *const SortKey,
+    keys_count: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let specs = match sort_specs(keys_ptr, keys_count) {
+        Ok(specs) => specs,
+        Err(msg) => return error_string(&msg),
+    };
+
+    let result = workbook.edit_sheet(sheet, "Sort", |t| {
+        sort_permutation(t, &specs).and_then(|order| t.reorder_rows(&order))
+    });
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Copy and paste a block of cells as one undoable step (see tessera_copy_range)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `source` must be a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_copy_range(
+    workbook: *mut Workbook,
+    sheet: usize,
+    source: *const CellRange,
+    dest_row: usize,
+    dest_col: usize,
+) -> *mut c_char {
+    let (Some(workbook), Some(&source)) = (workbook_arg_mut(workbook), source.as_ref()) else {
+        return error_string("Null pointer provided");
+    };
+    let dest = CellPosition {
+        row: dest_row,
+        col: dest_col,
+    };
+
+    match workbook.edit_sheet(sheet, "Paste", |t| copy_range(t, source, dest)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Cut and paste a block of cells as one undoable step (see tessera_move_range)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `source` must be a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_move_range(
+    workbook: *mut Workbook,
+    sheet: usize,
+    source: *const CellRange,
+    dest_row: usize,
+    dest_col: usize,
+) -> *mut c_char {
+    let (Some(workbook), Some(&source)) = (workbook_arg_mut(workbook), source.as_ref()) else {
+        return error_string("Null pointer provided");
+    };
+    let dest = CellPosition {
+        row: dest_row,
+        col: dest_col,
+    };
+
+    match workbook.edit_sheet(sheet, "Move", |t| move_range(t, source, dest)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Replace the contents of a sheet with an imported table as an undoable step
+///
+/// On success the workbook takes ownership of `table` (its contents move into
+/// the sheet and the handle is released); on error ownership stays with the caller.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `table` must be an owned table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_import(
+    workbook: *mut Workbook,
+    sheet: usize,
+    table: *mut TesseraTable,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    if table.is_null() {
+        return error_string("Null pointer provided");
+    }
+    if sheet >= workbook.sheet_count() {
+        return error_string(&format!("Sheet {} is out of range", sheet));
+    }
+
+    let imported = *Box::from_raw(table);
+    match workbook.edit_sheet(sheet, "Import", |t| {
+        *t = imported;
+        Ok(())
+    }) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Start grouping edits into one undo step (e.g. a multi-cell paste from the host)
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle; `label` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_begin_undo_group(workbook: *mut Workbook, label: *const c_char) {
+    if let Some(workbook) = workbook_arg_mut(workbook) {
+        workbook.begin_undo_group(str_arg(label).unwrap_or("Edit"));
+    }
+}
+
+/// Close the group opened by tessera_begin_undo_group
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_end_undo_group(workbook: *mut Workbook) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.end_undo_group() {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Start a batch: later edits apply as one undo step, or not at all
+///
+/// A batch is an undo group that can also be rolled back; batches nest.
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle; `label` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_begin_batch(workbook: *mut Workbook, label: *const c_char) {
+    tessera_begin_undo_group(workbook, label);
+}
+
+/// Keep the edits of the innermost batch
+///
+/// Closing the outermost batch records its edits as a single undo step.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_commit(workbook: *mut Workbook) -> *mut c_char {
+    tessera_end_undo_group(workbook)
+}
+
+/// Discard the edits of the innermost batch, restoring the cells it changed
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_rollback(workbook: *mut Workbook) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.rollback_undo_group() {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Revert the most recent step
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_undo(workbook: *mut Workbook) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.undo() {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Reapply the most recently undone step
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_redo(workbook: *mut Workbook) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.redo() {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_can_undo(workbook: *const Workbook) -> bool {
+    workbook_arg(workbook).is_some_and(Workbook::can_undo)
+}
+
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_can_redo(workbook: *const Workbook) -> bool {
+    workbook_arg(workbook).is_some_and(Workbook::can_redo)
+}
+
+/// Step labels for Edit menu entries such as "Undo Paste"
+///
+/// # Returns
+/// StringResult with JSON `{"undo": [...], "redo": [...]}`, most recent first
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_undo_labels(workbook: *const Workbook) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    let (undo, redo) = workbook.undo_labels();
+    StringResult::success(&serde_json::json!({ "undo": undo, "redo": redo }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workbook() -> Workbook {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["Name".into(), "Amount".into()],
+            vec![vec!["b".into(), "2".into()], vec!["a".into(), "1".into()]],
+        );
+        workbook.add_sheet("Sheet1", table).unwrap();
+        workbook
+    }
+
+    #[test]
+    fn test_undo_redo_steps() {
+        let mut workbook = workbook();
+        let original = workbook.clone();
+
+        workbook.set_cell(0, 0, 1, "5".into()).unwrap();
+        workbook
+            .edit_sheet(0, "Insert rows", |t| t.insert_rows(0, 2))
+            .unwrap();
+        assert_eq!(workbook.sheet_at(0).unwrap().row_count(), 4);
+        assert!(workbook
+            .edit_sheet(0, "Delete rows", |t| t.delete_rows(9, 1))
+            .is_err());
+        assert_eq!(workbook.undo_labels().0, ["Insert rows", "Edit cell"]);
+
+        workbook.undo().unwrap();
+        workbook.undo().unwrap();
+        assert_eq!(workbook.sheet_at(0), original.sheet_at(0));
+        assert!(workbook.undo().is_err());
+
+        workbook.redo().unwrap();
+        assert_eq!(workbook.sheet_at(0).unwrap().cell(0, 1), "5");
+        workbook.set_cell(0, 1, 1, "7".into()).unwrap();
+        assert!(!workbook.can_redo());
+    }
+
+    #[test]
+    fn test_group_undoes_as_one_step() {
+        let mut workbook = workbook();
+
+        workbook.begin_undo_group("Paste");
+        for row in 0..2 {
+            workbook.set_cell(0, row, 0, "x".into()).unwrap();
+        }
+        workbook.begin_undo_group("Nested");
+        workbook.set_cell(0, 0, 1, "9".into()).unwrap();
+        workbook.end_undo_group().unwrap();
+        assert!(!workbook.can_undo());
+        workbook.end_undo_group().unwrap();
+        assert!(workbook.end_undo_group().is_err());
+
+        assert_eq!(workbook.undo_labels().0, ["Paste"]);
+        workbook.undo().unwrap();
+        let sheet = workbook.sheet_at(0).unwrap();
+        assert_eq!(
+            (sheet.cell(0, 0), sheet.cell(1, 0), sheet.cell(0, 1)),
+            ("b", "a", "2")
+        );
+    }
+
+    #[test]
+    fn test_rollback_batch() {
+        let mut workbook = workbook();
+        let original = workbook.clone();
+
+        workbook.begin_undo_group("Fill");
+        workbook.set_cell(0, 0, 0, "x".into()).unwrap();
+        workbook.begin_undo_group("Inner");
+        workbook
+            .edit_sheet(0, "Insert rows", |t| t.insert_rows(1, 1))
+            .unwrap();
+        workbook.set_cell(0, 0, 1, "y".into()).unwrap();
+        workbook.rollback_undo_group().unwrap();
+        assert_eq!(workbook.sheet_at(0).unwrap().row_count(), 2);
+        assert_eq!(workbook.sheet_at(0).unwrap().cell(0, 0), "x");
+
+        workbook.rollback_undo_group().unwrap();
+        assert_eq!(workbook.sheet_at(0), original.sheet_at(0));
+        assert!(!workbook.can_undo());
+        assert!(workbook.rollback_undo_group().is_err());
+    }
+}
@@ -0,0 +1,773 @@
+//! Workbook handle: an ordered set of named sheets
+//!
+//! Each sheet is a [`TesseraTable`] owned by the workbook. The host borrows
+//! sheet handles with `tessera_workbook_sheet` and can use every table API on
+//! them; a borrowed handle stays valid until its sheet is removed or the
+//! workbook is freed. Formulas evaluated in a workbook can reference other
+//! sheets as `Sheet2!A1` or `'Q1 Sales'!Amount`. Edits made through the
+//! workbook itself can be undone (see [`history`]) and are reported to the
+//! host through a change callback (see [`events`]). Workbooks are saved in
+//! their own versioned file format (see [`save`]).
+
+pub mod calc;
+pub mod currency;
+pub mod events;
+pub mod history;
+pub mod notation;
+pub mod save;
+pub mod simulation;
+pub mod snapshot;
+pub mod solver;
+pub mod spill;
+pub mod trace;
+
+use std::os::raw::c_char;
+
+use crate::ffi::{error_string, str_arg};
+use crate::formula::complete::{candidates_to_json, complete, Scope};
+use crate::formula::diagnostics::{diagnose, diagnostics_to_json};
+use crate::formula::locale::{locale, translate, FormulaLocale};
+use crate::formula::rewrite::is_formula;
+use crate::formula::table_context::{evaluate_in_workbook, trace_in_workbook, trace_to_json};
+use crate::formula::{parse, Expr};
+use crate::table::TesseraTable;
+use crate::StringResult;
+use calc::CalcState;
+use events::Listener;
+use history::History;
+use notation::ReferenceStyle;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Sheet {
+    name: String,
+    // Boxed so handles lent to the host survive reordering
+    table: Box<TesseraTable>,
+}
+
+/// Workbook-level name for a reference, e.g. `Sales` = `'Q1 Sales'!B2:B40`
+#[derive(Debug, Clone, PartialEq)]
+struct DefinedName {
+    name: String,
+    reference: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workbook {
+    sheets: Vec<Sheet>,
+    names: Vec<DefinedName>,
+    history: History,
+    listener: Option<Listener>,
+    calc: CalcState,
+    reference_style: ReferenceStyle,
+    /// Reference of the exchange rates read by `CURRENCY()`
+    currency_rates: Option<String>,
+}
+
+/// Sheet names follow the spreadsheet rules: 1-31 characters, none of `[]:*?/\`
+fn check_sheet_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Sheet name is empty".to_string());
+    }
+    if name.chars().count() > 31 {
+        return Err(format!(
+            "Sheet name '{}' is longer than 31 characters",
+            name
+        ));
+    }
+    if name.contains(['[', ']', ':', '*', '?', '/', '\\']) {
+        return Err(format!(
+            "Sheet name '{}' contains an invalid character",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// A defined name must parse as a bare name, so `A1`, `TRUE` or `x y` are rejected
+fn check_defined_name(name: &str) -> Result<(), String> {
+    match parse(name) {
+        Ok(Expr::Name(parsed)) if parsed == name => Ok(()),
+        _ => Err(format!("'{}' is not a valid name", name)),
+    }
+}
+
+/// Names may only point at cells, ranges or whole columns, possibly on
+/// another sheet, which also rules out names referring to each other
+fn check_name_reference(reference: &str) -> Result<(), String> {
+    let plain = |expr: &Expr| {
+        matches!(
+            expr,
+            Expr::Cell(_) | Expr::Range(..) | Expr::ColumnRange(..)
+        )
+    };
+    match parse(reference)? {
+        Expr::Sheet(_, inner) if plain(&inner) => Ok(()),
+        expr if plain(&expr) => Ok(()),
+        _ => Err(format!("'{}' is not a cell or range reference", reference)),
+    }
+}
+
+impl Workbook {
+    pub fn new() -> Self {
+        Workbook::default()
+    }
+
+    pub fn sheet_count(&self) -> usize {
+        self.sheets.len()
+    }
+
+    pub fn sheet_names(&self) -> Vec<&str> {
+        self.sheets.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    /// Find a sheet by name (case-insensitive)
+    pub fn sheet_index(&self, name: &str) -> Option<usize> {
+        self.sheets
+            .iter()
+            .position(|s| s.name.to_lowercase() == name.to_lowercase())
+    }
+
+    pub fn sheet(&self, name: &str) -> Option<&TesseraTable> {
+        self.sheet_index(name).map(|i| &*self.sheets[i].table)
+    }
+
+    pub fn sheet_at(&self, index: usize) -> Option<&TesseraTable> {
+        self.sheets.get(index).map(|s| &*s.table)
+    }
+
+    pub fn sheet_at_mut(&mut self, index: usize) -> Option<&mut TesseraTable> {
+        self.sheets.get_mut(index).map(|s| &mut *s.table)
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), String> {
+        if index < self.sheets.len() {
+            Ok(())
+        } else {
+            Err(format!("Sheet {} is out of range", index))
+        }
+    }
+
+    fn check_unique(&self, name: &str, except: Option<usize>) -> Result<(), String> {
+        match self.sheet_index(name) {
+            Some(i) if Some(i) != except => Err(format!("Sheet '{}' already exists", name)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Append a sheet, returning its index
+    pub fn add_sheet(&mut self, name: &str, table: TesseraTable) -> Result<usize, String> {
+        check_sheet_name(name)?;
+        self.check_unique(name, None)?;
+        self.sheets.push(Sheet {
+            name: name.to_string(),
+            table: Box::new(table),
+        });
+        self.invalidate_all();
+        Ok(self.sheets.len() - 1)
+    }
+
+    pub fn rename_sheet(&mut self, index: usize, name: &str) -> Result<(), String> {
+        self.check_index(index)?;
+        check_sheet_name(name)?;
+        self.check_unique(name, Some(index))?;
+        self.sheets[index].name = name.to_string();
+        self.invalidate_all();
+        Ok(())
+    }
+
+    /// Define or redefine a name (case-insensitive)
+    pub fn define_name(&mut self, name: &str, reference: &str) -> Result<(), String> {
+        check_defined_name(name)?;
+        check_name_reference(reference)?;
+        let reference = reference.trim().trim_start_matches('=').trim().to_string();
+        match self
+            .names
+            .iter_mut()
+            .find(|n| n.name.eq_ignore_ascii_case(name))
+        {
+            Some(defined) => defined.reference = reference,
+            None => self.names.push(DefinedName {
+                name: name.to_string(),
+                reference,
+            }),
+        }
+        self.invalidate_all();
+        Ok(())
+    }
+
+    pub fn remove_name(&mut self, name: &str) -> Result<(), String> {
+        let before = self.names.len();
+        self.names.retain(|n| !n.name.eq_ignore_ascii_case(name));
+        if self.names.len() == before {
+            return Err(format!("Name '{}' not found", name));
+        }
+        self.invalidate_all();
+        Ok(())
+    }
+
+    /// Defined names and their references, in definition order
+    pub fn defined_names(&self) -> Vec<(&str, &str)> {
+        self.names
+            .iter()
+            .map(|n| (n.name.as_str(), n.reference.as_str()))
+            .collect()
+    }
+
+    /// Reference text of a defined name (case-insensitive)
+    pub fn name_reference(&self, name: &str) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|n| n.name.eq_ignore_ascii_case(name))
+            .map(|n| n.reference.as_str())
+    }
+
+    /// Remove a sheet and hand back its table (clears the undo log)
+    pub fn remove_sheet(&mut self, index: usize) -> Result<TesseraTable, String> {
+        self.check_index(index)?;
+        self.history.clear();
+        let sheet = self.sheets.remove(index);
+        self.invalidate_all();
+        Ok(*sheet.table)
+    }
+
+    /// Move the sheet at `from` so that it ends up at index `to` (clears the undo log)
+    pub fn move_sheet(&mut self, from: usize, to: usize) -> Result<(), String> {
+        self.check_index(from)?;
+        self.check_index(to)?;
+        self.history.clear();
+        let sheet = self.sheets.remove(from);
+        self.sheets.insert(to, sheet);
+        self.invalidate_all();
+        Ok(())
+    }
+
+    /// Rewrite every formula cell from one locale's syntax to another's as a
+    /// single undo step, returning the number of cells changed
+    pub fn translate_formulas(
+        &mut self,
+        from: &FormulaLocale,
+        to: &FormulaLocale,
+    ) -> Result<usize, String> {
+        let mut changes = Vec::new();
+        for (s, sheet) in self.sheets.iter().enumerate() {
+            for (row, cells) in sheet.table.rows().iter().enumerate() {
+                for (col, text) in cells.iter().enumerate() {
+                    if is_formula(text) {
+                        let translated = translate(text, from, to);
+                        if translated != *text {
+                            changes.push((s, row, col, translated));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.begin_undo_group("Translate formulas");
+        for (sheet, row, col, text) in changes.iter().cloned() {
+            if let Err(msg) = self.set_cell(sheet, row, col, text) {
+                self.rollback_undo_group()?;
+                return Err(msg);
+            }
+        }
+        self.end_undo_group()?;
+        Ok(changes.len())
+    }
+}
+
+/// Borrow a workbook handle passed in from the host
+///
+/// # Safety
+/// `workbook` must be null or a live handle returned by this library
+pub(crate) unsafe fn workbook_arg<'a>(workbook: *const Workbook) -> Option<&'a Workbook> {
+    workbook.as_ref()
+}
+
+/// Mutably borrow a workbook handle passed in from the host
+///
+/// # Safety
+/// `workbook` must be null or a live handle returned by this library, not aliased
+pub(crate) unsafe fn workbook_arg_mut<'a>(workbook: *mut Workbook) -> Option<&'a mut Workbook> {
+    workbook.as_mut()
+}
+
+/// FFI-safe result for functions that produce a new workbook handle
+#[repr(C)]
+pub struct WorkbookResult {
+    pub workbook: *mut Workbook,
+    pub error: *mut c_char, // null if success, C string if error
+}
+
+impl WorkbookResult {
+    pub(crate) fn success(workbook: Workbook) -> Self {
+        WorkbookResult {
+            workbook: Box::into_raw(Box::new(workbook)),
+            error: std::ptr::null_mut(),
+        }
+    }
+
+    pub(crate) fn error(msg: &str) -> Self {
+        WorkbookResult {
+            workbook: std::ptr::null_mut(),
+            error: error_string(msg),
+        }
+    }
+}
+
+impl From<Result<Workbook, String>> for WorkbookResult {
+    fn from(result: Result<Workbook, String>) -> Self {
+        match result {
+            Ok(workbook) => WorkbookResult::success(workbook),
+            Err(msg) => WorkbookResult::error(&msg),
+        }
+    }
+}
+
+/// Create an empty workbook
+#[no_mangle]
+pub extern "C" fn tessera_workbook_new() -> *mut Workbook {
+    Box::into_raw(Box::new(Workbook::new()))
+}
+
+/// Release a workbook and all of its sheets
+///
+/// # Safety
+/// `workbook` must be null or a handle returned by this library that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_free(workbook: *mut Workbook) {
+    if !workbook.is_null() {
+        drop(Box::from_raw(workbook));
+    }
+}
+
+/// Number of sheets
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_sheet_count(workbook: *const Workbook) -> usize {
+    workbook_arg(workbook).map_or(0, Workbook::sheet_count)
+}
+
+/// Sheet names in order
+///
+/// # Returns
+/// StringResult with a JSON array of names
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_sheet_names(workbook: *const Workbook) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    StringResult::success(&serde_json::Value::from(workbook.sheet_names()).to_string())
+}
+
+/// Append a sheet
+///
+/// On success the workbook takes ownership of `table`: do not free it, borrow
+/// it back with tessera_workbook_sheet. A null `table` adds an empty sheet.
+/// On error ownership stays with the caller.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `name` must be a valid C string;
+/// `table` must be null or an owned table handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_add_sheet(
+    workbook: *mut Workbook,
+    name: *const c_char,
+    table: *mut TesseraTable,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(name) = str_arg(name) else {
+        return error_string("Invalid sheet name encoding");
+    };
+    if let Err(msg) = check_sheet_name(name).and_then(|()| workbook.check_unique(name, None)) {
+        return error_string(&msg);
+    }
+
+    let table = if table.is_null() {
+        TesseraTable::default()
+    } else {
+        *Box::from_raw(table)
+    };
+    match workbook.add_sheet(name, table) {
+        Ok(_) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Borrow the table of a sheet (null when out of range)
+///
+/// The handle is owned by the workbook: do not free it. It stays valid until
+/// the sheet is removed or the workbook is freed.
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_sheet(
+    workbook: *mut Workbook,
+    index: usize,
+) -> *mut TesseraTable {
+    match workbook_arg_mut(workbook).and_then(|w| w.sheet_at_mut(index)) {
+        Some(table) => table,
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Rename a sheet
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `name` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_rename_sheet(
+    workbook: *mut Workbook,
+    index: usize,
+    name: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(name) = str_arg(name) else {
+        return error_string("Invalid sheet name encoding");
+    };
+
+    match workbook.rename_sheet(index, name) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Delete a sheet; handles borrowed for it become invalid
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_remove_sheet(
+    workbook: *mut Workbook,
+    index: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.remove_sheet(index) {
+        Ok(_) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Move a sheet to a new position; borrowed handles stay valid
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_move_sheet(
+    workbook: *mut Workbook,
+    from: usize,
+    to: usize,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+
+    match workbook.move_sheet(from, to) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Define a workbook-level name, or remove it when `reference` is null or empty
+///
+/// # Arguments
+/// * `name` - Name usable in formulas, e.g. `Sales`
+/// * `reference` - Cell, range or column reference, e.g. `'Q1 Sales'!B2:B40`
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `name` must be a valid C string;
+/// `reference` must be null or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_define_name(
+    workbook: *mut Workbook,
+    name: *const c_char,
+    reference: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(name) = str_arg(name) else {
+        return error_string("Invalid name encoding");
+    };
+
+    let result = match str_arg(reference).filter(|r| !r.trim().is_empty()) {
+        Some(reference) => workbook.define_name(name, reference),
+        None => workbook.remove_name(name),
+    };
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Defined names of the workbook
+///
+/// # Returns
+/// StringResult with a JSON object mapping each name to its reference
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_names(workbook: *const Workbook) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    let names: serde_json::Map<String, serde_json::Value> = workbook
+        .defined_names()
+        .into_iter()
+        .map(|(name, reference)| (name.to_string(), reference.into()))
+        .collect();
+    StringResult::success(&serde_json::Value::Object(names).to_string())
+}
+
+/// Evaluate a formula on one sheet, with cross-sheet references resolved
+///
+/// # Returns
+/// StringResult with a JSON array of rows of cell text (`[["42"]]` for a scalar)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_evaluate(
+    workbook: *const Workbook,
+    sheet: usize,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    evaluate_in_workbook(workbook, sheet, formula)
+        .map(|grid| serde_json::Value::from(grid).to_string())
+        .into()
+}
+
+/// Evaluate a formula on one sheet step by step (see tessera_trace_formula)
+///
+/// # Returns
+/// StringResult with JSON `{result, steps: [{depth, expression, value}]}`
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_trace_formula(
+    workbook: *const Workbook,
+    sheet: usize,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+
+    trace_in_workbook(workbook, sheet, formula)
+        .map(|(value, steps)| trace_to_json(value, steps))
+        .into()
+}
+
+/// Completion candidates for a formula typed on one sheet (see tessera_complete_formula)
+///
+/// # Returns
+/// StringResult with a JSON array of `{kind, label, insert, start, end, detail}`
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_complete_formula(
+    workbook: *const Workbook,
+    sheet: usize,
+    formula: *const c_char,
+    cursor: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+    if sheet >= workbook.sheet_count() {
+        return StringResult::error(&format!("Sheet {} is out of range", sheet));
+    }
+
+    complete(formula, cursor, &Scope::for_workbook(workbook, sheet))
+        .map(|candidates| candidates_to_json(&candidates))
+        .into()
+}
+
+/// Problems in a formula written on one sheet (see tessera_diagnose_formula)
+///
+/// Names are checked against the sheet's columns and the workbook's defined
+/// names, sheet prefixes against the workbook's sheets.
+///
+/// # Returns
+/// StringResult with a JSON array of `{severity, code, message, start, end,
+/// char_start, char_end, suggestion}`
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `formula` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_diagnose_formula(
+    workbook: *const Workbook,
+    sheet: usize,
+    formula: *const c_char,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(formula) = str_arg(formula) else {
+        return StringResult::error("Invalid formula encoding");
+    };
+    if sheet >= workbook.sheet_count() {
+        return StringResult::error(&format!("Sheet {} is out of range", sheet));
+    }
+
+    let scope = Scope::for_workbook(workbook, sheet);
+    let diagnostics = diagnose(formula, Some(&scope));
+    StringResult::success(&diagnostics_to_json(formula, &diagnostics))
+}
+
+/// Convert every formula in a workbook between locale syntaxes, e.g. after
+/// importing a sheet saved by a German spreadsheet program (one undo step)
+///
+/// # Arguments
+/// * `from`, `to` - Locale codes: `en`, `de`, `fr` or `es`
+///
+/// # Returns
+/// StringResult with the number of cells changed
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `from` and `to` must be valid C strings
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_translate_formulas(
+    workbook: *mut Workbook,
+    from: *const c_char,
+    to: *const c_char,
+) -> StringResult {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let (Some(from), Some(to)) = (str_arg(from), str_arg(to)) else {
+        return StringResult::error("Invalid locale encoding");
+    };
+
+    let (from, to) = match (locale(from), locale(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(msg), _) | (_, Err(msg)) => return StringResult::error(&msg),
+    };
+    workbook
+        .translate_formulas(from, to)
+        .map(|count| count.to_string())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(header: &str, values: &[&str]) -> TesseraTable {
+        TesseraTable::from_rows(
+            vec![header.to_string()],
+            values.iter().map(|v| vec![v.to_string()]).collect(),
+        )
+    }
+
+    #[test]
+    fn test_sheet_management() {
+        let mut workbook = Workbook::new();
+        workbook
+            .add_sheet("Sheet1", TesseraTable::default())
+            .unwrap();
+        workbook.add_sheet("Data", table("A", &["1"])).unwrap();
+        workbook
+            .add_sheet("Notes", TesseraTable::default())
+            .unwrap();
+
+        assert!(workbook.add_sheet("data", TesseraTable::default()).is_err());
+        assert!(workbook.add_sheet("a/b", TesseraTable::default()).is_err());
+        assert!(workbook.rename_sheet(0, "Notes").is_err());
+
+        workbook.rename_sheet(0, "Summary").unwrap();
+        workbook.move_sheet(2, 0).unwrap();
+        assert_eq!(workbook.sheet_names(), ["Notes", "Summary", "Data"]);
+
+        let removed = workbook.remove_sheet(2).unwrap();
+        assert_eq!(removed.cell(0, 0), "1");
+        assert_eq!(workbook.sheet_names(), ["Notes", "Summary"]);
+        assert!(workbook.move_sheet(0, 5).is_err());
+    }
+
+    #[test]
+    fn test_cross_sheet_references() {
+        let mut workbook = Workbook::new();
+        workbook
+            .add_sheet("Summary", table("Total", &["5"]))
+            .unwrap();
+        workbook
+            .add_sheet("Q1 Sales", table("Amount", &["10", "20", "30"]))
+            .unwrap();
+
+        let eval = |formula: &str| evaluate_in_workbook(&workbook, 0, formula).unwrap();
+        assert_eq!(eval("='Q1 Sales'!A2 + A1"), [["25"]]);
+        assert_eq!(
+            eval("=SORT('q1 sales'!Amount, 1, -1)"),
+            [["30"], ["20"], ["10"]]
+        );
+        assert_eq!(eval("=Missing!A1"), [["#REF!"]]);
+
+        workbook.define_name("Sales", "='Q1 Sales'!A1:A2").unwrap();
+        assert!(workbook.define_name("B2", "A1").is_err());
+        assert!(workbook.define_name("Loop", "Sales").is_err());
+        let eval = |formula: &str| evaluate_in_workbook(&workbook, 0, formula).unwrap();
+        assert_eq!(eval("=sales + Total"), [["15"], ["25"]]);
+        assert!(evaluate_in_workbook(&workbook, 3, "=1").is_err());
+
+        workbook
+            .add_sheet("Q2", table("Amount", &["1", "2"]))
+            .unwrap();
+        workbook.add_sheet("Q3", table("Amount", &["100"])).unwrap();
+        let eval = |formula: &str| evaluate_in_workbook(&workbook, 0, formula).unwrap();
+        assert_eq!(eval("=SUM(Q3:'Q1 Sales'!A1)"), [["111"]]);
+        assert_eq!(eval("=SUM('Q1 Sales:Q2'!A1:A3)"), [["63"]]);
+        assert_eq!(eval("=SUM(Q2:Nowhere!A1)"), [["#REF!"]]);
+
+        workbook
+            .set_cell(0, 0, 0, "=SUM('Q1 Sales:Q3'!A1)".into())
+            .unwrap();
+        assert_eq!(workbook.display_value(0, 0, 0).unwrap(), "111");
+        workbook.set_cell(2, 0, 0, "5".into()).unwrap();
+        assert_eq!(workbook.display_value(0, 0, 0).unwrap(), "115");
+    }
+}
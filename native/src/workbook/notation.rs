@@ -0,0 +1,175 @@
+//! Reference style shown to the user: A1 or R1C1
+//!
+//! Cells always store formulas in A1 notation. In R1C1 style the formula
+//! text handed to the host is rendered relative to its cell, and formulas
+//! typed by the user are read back before they are stored.
+
+use std::os::raw::c_char;
+
+use super::{workbook_arg, workbook_arg_mut, Workbook};
+use crate::ffi::{error_string, str_arg};
+use crate::formula::r1c1::{to_a1, to_r1c1};
+use crate::StringResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferenceStyle {
+    #[default]
+    A1,
+    R1C1,
+}
+
+impl ReferenceStyle {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(ReferenceStyle::A1),
+            1 => Some(ReferenceStyle::R1C1),
+            _ => None,
+        }
+    }
+}
+
+impl Workbook {
+    pub fn reference_style(&self) -> ReferenceStyle {
+        self.reference_style
+    }
+
+    pub fn set_reference_style(&mut self, style: ReferenceStyle) {
+        self.reference_style = style;
+    }
+
+    /// Cell text as the user sees it in the formula bar
+    pub fn formula_text(&self, sheet: usize, row: usize, col: usize) -> Result<String, String> {
+        self.check_cell(sheet, row, col)?;
+        let text = self.sheets[sheet].table.cell(row, col);
+        Ok(match self.reference_style {
+            ReferenceStyle::A1 => text.to_string(),
+            ReferenceStyle::R1C1 => to_r1c1(text, row, col),
+        })
+    }
+
+    /// Store text typed in the formula bar, reading R1C1 references back to A1
+    pub fn enter_formula(
+        &mut self,
+        sheet: usize,
+        row: usize,
+        col: usize,
+        text: &str,
+    ) -> Result<(), String> {
+        let text = match self.reference_style {
+            ReferenceStyle::A1 => text.to_string(),
+            ReferenceStyle::R1C1 => to_a1(text, row, col),
+        };
+        self.set_cell(sheet, row, col, text)
+    }
+}
+
+/// Set the reference style used for formula text exchanged with the host
+///
+/// # Arguments
+/// * `style` - 0 = A1, 1 = R1C1
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_set_reference_style(
+    workbook: *mut Workbook,
+    style: u32,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(style) = ReferenceStyle::from_raw(style) else {
+        return error_string(&format!("Unknown reference style {}", style));
+    };
+
+    workbook.set_reference_style(style);
+    std::ptr::null_mut()
+}
+
+/// Current reference style (see tessera_set_reference_style)
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_reference_style(workbook: *const Workbook) -> u32 {
+    workbook_arg(workbook).map_or(0, |w| w.reference_style() as u32)
+}
+
+/// Text of a cell in the workbook's reference style, for the formula bar
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_formula_text(
+    workbook: *const Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    workbook.formula_text(sheet, row, col).into()
+}
+
+/// Store formula bar text in a cell, converting from the workbook's
+/// reference style (one undo step)
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `text` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_workbook_enter_formula(
+    workbook: *mut Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+    text: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(text) = str_arg(text) else {
+        return error_string("Invalid text encoding");
+    };
+
+    match workbook.enter_formula(sheet, row, col, text) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    #[test]
+    fn test_r1c1_style_round_trips_through_cells() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["A".into(), "B".into()],
+            vec![
+                vec!["1".into(), "=A1*2".into()],
+                vec!["2".into(), "".into()],
+            ],
+        );
+        workbook.add_sheet("Data", table).unwrap();
+
+        workbook.set_reference_style(ReferenceStyle::R1C1);
+        assert_eq!(workbook.formula_text(0, 0, 1).unwrap(), "=RC[-1]*2");
+        workbook.enter_formula(0, 1, 1, "=RC[-1]*2").unwrap();
+        assert_eq!(workbook.sheet_at(0).unwrap().cell(1, 1), "=A2*2");
+        assert_eq!(workbook.display_value(0, 1, 1).unwrap(), "4");
+
+        workbook.set_reference_style(ReferenceStyle::A1);
+        assert_eq!(workbook.formula_text(0, 1, 1).unwrap(), "=A2*2");
+        assert!(workbook.formula_text(0, 2, 0).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Native workbook file format (`.tsra`)
+//!
+//! A zip archive holding two deflated JSON documents:
+//! - `manifest.json`: `{"format": "tessera-workbook", "version": N, "min_reader_version": M}`
+//! - `workbook.json`: sheets (headers, cell text including formulas, number
+//!   formats, validation rules, computed columns) and defined names
+//!
+//! Versioning is forward compatible: additions bump `version` only and are
+//! ignored by older readers, which skip unknown fields. `min_reader_version`
+//! is raised only when older readers would misread the file, and a reader
+//! refuses files whose `min_reader_version` is above [`FORMAT_VERSION`].
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, Write};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use serde_json::{json, Map, Value as Json};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{workbook_arg, Workbook, WorkbookResult};
+use crate::ffi::{error_string, str_arg};
+use crate::formats::TextFormat;
+use crate::render::format::NumberFormat;
+use crate::table::{CellText, TesseraTable};
+use crate::validation::{RuleKind, ValidationRule};
+
+const FORMAT_NAME: &str = "tessera-workbook";
+
+/// Version written by this build, and the newest one it can read
+pub const FORMAT_VERSION: u64 = 2;
+
+fn rule_to_json(rule: &ValidationRule) -> Json {
+    let mut value = match &rule.kind {
+        RuleKind::Range { min, max } => json!({ "kind": "range", "min": min, "max": max }),
+        RuleKind::List {
+            values,
+            ignore_case,
+        } => json!({ "kind": "list", "values": values, "ignore_case": ignore_case }),
+        RuleKind::Pattern(pattern) => json!({ "kind": "pattern", "pattern": pattern }),
+        RuleKind::Format(format) => json!({ "kind": "format", "format": format.name() }),
+        RuleKind::Unique => json!({ "kind": "unique" }),
+        RuleKind::Formula(formula) => json!({ "kind": "formula", "formula": formula }),
+    };
+    value["column"] = rule.column.into();
+    value
+}
+
+fn rule_from_json(value: &Json) -> Result<ValidationRule, String> {
+    let text = |key: &str| {
+        value[key]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("Validation rule is missing '{}'", key))
+    };
+    let kind = match value["kind"].as_str().unwrap_or_default() {
+        "range" => RuleKind::Range {
+            min: value["min"].as_f64(),
+            max: value["max"].as_f64(),
+        },
+        "list" => RuleKind::List {
+            values: strings(&value["values"]),
+            ignore_case: value["ignore_case"].as_bool().unwrap_or(false),
+        },
+        "pattern" => RuleKind::Pattern(text("pattern")?),
+        "format" => RuleKind::Format(
+            TextFormat::from_name(&text("format")?)
+                .ok_or_else(|| format!("Unknown text format '{}'", value["format"]))?,
+        ),
+        "unique" => RuleKind::Unique,
+        "formula" => RuleKind::Formula(text("formula")?),
+        other => return Err(format!("Unknown validation rule '{}'", other)),
+    };
+    Ok(ValidationRule {
+        column: index(&value["column"])?,
+        kind,
+    })
+}
+
+fn strings(value: &Json) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().unwrap_or_default().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn index(value: &Json) -> Result<usize, String> {
+    value
+        .as_u64()
+        .map(|n| n as usize)
+        .ok_or_else(|| "Expected a row or column index".to_string())
+}
+
+fn sheet_to_json(name: &str, table: &TesseraTable) -> Json {
+    // Sorted so saving the same workbook twice gives the same bytes
+    let mut column_formats: Vec<_> = table.column_formats().iter().collect();
+    column_formats.sort_by_key(|(col, _)| **col);
+    let mut cell_formats: Vec<_> = table.cell_formats().iter().collect();
+    cell_formats.sort_by_key(|(cell, _)| **cell);
+
+    json!({
+        "name": name,
+        "headers": table.headers(),
+        "rows": table
+            .rows()
+            .iter()
+            .map(|cells| cells.iter().map(CellText::as_str).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        "column_formats": column_formats
+            .into_iter()
+            .map(|(col, format)| json!({ "col": col, "format": format.code() }))
+            .collect::<Vec<_>>(),
+        "cell_formats": cell_formats
+            .into_iter()
+            .map(|((row, col), format)| json!({ "row": row, "col": col, "format": format.code() }))
+            .collect::<Vec<_>>(),
+        "validation": table.validation_rules().iter().map(rule_to_json).collect::<Vec<_>>(),
+        "computed": table
+            .computed_columns()
+            .iter()
+            .map(|computed| json!({ "col": computed.column, "formula": computed.formula }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn sheet_from_json(value: &Json) -> Result<(String, TesseraTable), String> {
+    let name = value["name"]
+        .as_str()
+        .ok_or("Sheet is missing its name")?
+        .to_string();
+    let rows = value["rows"]
+        .as_array()
+        .map(|rows| rows.iter().map(strings).collect())
+        .unwrap_or_default();
+    let mut table = TesseraTable::from_rows(strings(&value["headers"]), rows);
+
+    let items = |key: &str| value[key].as_array().cloned().unwrap_or_default();
+    let format = |item: &Json| NumberFormat::parse(item["format"].as_str().unwrap_or_default());
+    for item in items("column_formats") {
+        table.set_column_format(index(&item["col"])?, Some(format(&item)?))?;
+    }
+    for item in items("cell_formats") {
+        let (row, col) = (index(&item["row"])?, index(&item["col"])?);
+        table.set_cell_format(row, col, Some(format(&item)?))?;
+    }
+    for item in items("validation") {
+        table.add_validation_rule(rule_from_json(&item)?)?;
+    }
+    for item in items("computed") {
+        let formula = item["formula"].as_str().unwrap_or_default();
+        table.set_computed_column(index(&item["col"])?, formula)?;
+    }
+    Ok((name, table))
+}
+
+fn workbook_to_json(workbook: &Workbook) -> Json {
+    let names: Map<String, Json> = workbook
+        .defined_names()
+        .into_iter()
+        .map(|(name, reference)| (name.to_string(), reference.into()))
+        .collect();
+    json!({
+        "sheets": workbook
+            .sheets
+            .iter()
+            .map(|sheet| sheet_to_json(&sheet.name, &sheet.table))
+            .collect::<Vec<_>>(),
+        "names": names,
+        "currency_rates": workbook.currency_rates(),
+    })
+}
+
+fn workbook_from_json(value: &Json) -> Result<Workbook, String> {
+    let mut workbook = Workbook::new();
+    for sheet in value["sheets"].as_array().into_iter().flatten() {
+        let (name, table) = sheet_from_json(sheet)?;
+        workbook.add_sheet(&name, table)?;
+    }
+    for (name, reference) in value["names"].as_object().into_iter().flatten() {
+        workbook.define_name(name, reference.as_str().unwrap_or_default())?;
+    }
+    if let Some(rates) = value["currency_rates"].as_str() {
+        workbook.set_currency_rates(Some(rates))?;
+    }
+    Ok(workbook)
+}
+
+/// Write a workbook in the native format
+pub fn save_to<W: Write + Seek>(workbook: &Workbook, writer: W) -> Result<(), String> {
+    let mut zip = ZipWriter::new(writer);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let write_error = |e: &dyn std::fmt::Display| format!("Failed to write workbook: {}", e);
+
+    let manifest = json!({
+        "format": FORMAT_NAME,
+        "version": FORMAT_VERSION,
+        "min_reader_version": 1,
+    });
+    for (entry, document) in [
+        ("manifest.json", manifest),
+        ("workbook.json", workbook_to_json(workbook)),
+    ] {
+        zip.start_file(entry, deflated)
+            .map_err(|e| write_error(&e))?;
+        zip.write_all(document.to_string().as_bytes())
+            .map_err(|e| write_error(&e))?;
+    }
+    zip.finish().map_err(|e| write_error(&e))?;
+    Ok(())
+}
+
+/// Read a workbook written by [`save_to`] (by this or an older/compatible build)
+pub fn load_from<R: Read + Seek>(reader: R) -> Result<Workbook, String> {
+    let mut archive =
+        ZipArchive::new(reader).map_err(|e| format!("Invalid workbook file: {}", e))?;
+    let mut read_json = |entry: &str| -> Result<Json, String> {
+        let mut content = String::new();
+        archive
+            .by_name(entry)
+            .map_err(|_| format!("Workbook file has no {}", entry))?
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read {}: {}", entry, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", entry, e))
+    };
+
+    let manifest = read_json("manifest.json")?;
+    if manifest["format"] != FORMAT_NAME {
+        return Err("Not a Tessera workbook file".to_string());
+    }
+    let required = manifest["min_reader_version"].as_u64().unwrap_or(1);
+    if required > FORMAT_VERSION {
+        return Err(format!(
+            "Workbook needs format version {} but this build reads up to {}",
+            required, FORMAT_VERSION
+        ));
+    }
+
+    workbook_from_json(&read_json("workbook.json")?)
+}
+
+pub fn save(workbook: &Workbook, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    save_to(workbook, file)
+}
+
+pub fn load(path: &Path) -> Result<Workbook, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    load_from(BufReader::new(file))
+}
+
+/// Save a workbook in the native format
+///
+/// Cell values and formulas, number formats, validation rules, computed
+/// columns and defined names are kept; the undo log and change callback are not.
+///
+/// # Returns
+/// Null on success, otherwise an error message (caller must free with tessera_free_string)
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_save(
+    workbook: *const Workbook,
+    path: *const c_char,
+) -> *mut c_char {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return error_string("Null pointer provided");
+    };
+    let Some(path) = str_arg(path) else {
+        return error_string("Invalid file path");
+    };
+
+    match save(workbook, Path::new(path)) {
+        Ok(()) => std::ptr::null_mut(),
+        Err(msg) => error_string(&msg),
+    }
+}
+
+/// Load a workbook saved with tessera_save
+///
+/// # Returns
+/// WorkbookResult with a new workbook handle (free with tessera_workbook_free) or error message
+///
+/// # Safety
+/// `path` must be a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn tessera_load(path: *const c_char) -> WorkbookResult {
+    let Some(path) = str_arg(path) else {
+        return WorkbookResult::error("Invalid file path");
+    };
+
+    load(Path::new(path)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut table = TesseraTable::from_rows(
+            vec!["Item".into(), "Price".into()],
+            vec![
+                vec!["Pen".into(), "1.5".into()],
+                vec!["Total".into(), "=B1*2".into()],
+            ],
+        );
+        table
+            .set_column_format(1, Some(NumberFormat::parse("#,##0.00").unwrap()))
+            .unwrap();
+        table
+            .set_cell_format(1, 1, Some(NumberFormat::parse("[Red]0").unwrap()))
+            .unwrap();
+        table
+            .add_validation_rule(ValidationRule {
+                column: 1,
+                kind: RuleKind::Range {
+                    min: Some(0.0),
+                    max: None,
+                },
+            })
+            .unwrap();
+
+        let mut workbook = Workbook::new();
+        workbook.add_sheet("Prices", table).unwrap();
+        workbook
+            .add_sheet("Empty", TesseraTable::default())
+            .unwrap();
+        workbook.define_name("Price", "Prices!B1:B2").unwrap();
+
+        let mut bytes = Cursor::new(Vec::new());
+        save_to(&workbook, &mut bytes).unwrap();
+        let loaded = load_from(Cursor::new(bytes.into_inner())).unwrap();
+        assert_eq!(loaded.sheet_names(), ["Prices", "Empty"]);
+        assert_eq!(loaded.sheet_at(0), workbook.sheet_at(0));
+        assert_eq!(loaded.defined_names(), [("Price", "Prices!B1:B2")]);
+    }
+
+    #[test]
+    fn test_rejects_newer_format() {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut bytes);
+        zip.start_file("manifest.json", SimpleFileOptions::default())
+            .unwrap();
+        let manifest = json!({ "format": FORMAT_NAME, "version": 7, "min_reader_version": 5 });
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let err = load_from(Cursor::new(bytes.into_inner())).unwrap_err();
+        assert!(err.contains("format version 5"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Monte Carlo simulation of a formula cell
+//!
+//! Each trial freezes the volatile functions with its own seed on a scratch
+//! copy of the workbook, so `RAND()` and `RANDBETWEEN()` draw fresh inputs,
+//! recalculates and records the target cell. Trial seeds derive from one
+//! base seed, which makes a whole run reproducible. Trials where the target
+//! is not a number are counted as errors and left out of the statistics.
+
+use super::calc::{CalcMode, CellKey};
+use super::{workbook_arg, Workbook};
+use crate::formula::volatile::{self, Frozen};
+use crate::render::scale::percentile;
+use crate::StringResult;
+
+/// Percentiles reported by tessera_simulate
+const BANDS: [f64; 7] = [5.0, 10.0, 25.0, 50.0, 75.0, 90.0, 95.0];
+
+/// Outcomes of a simulation run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    /// Numeric outcomes, sorted ascending
+    pub outcomes: Vec<f64>,
+    /// Trials whose target was not a number
+    pub errors: usize,
+}
+
+impl Simulation {
+    pub fn mean(&self) -> Option<f64> {
+        (!self.outcomes.is_empty())
+            .then(|| self.outcomes.iter().sum::<f64>() / self.outcomes.len() as f64)
+    }
+
+    /// Sample standard deviation
+    pub fn stdev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let n = self.outcomes.len();
+        (n > 1).then(|| {
+            let squares: f64 = self.outcomes.iter().map(|x| (x - mean).powi(2)).sum();
+            (squares / (n - 1) as f64).sqrt()
+        })
+    }
+
+    /// Linear-interpolated percentile (0-100) of the outcomes
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        (!self.outcomes.is_empty()).then(|| percentile(&self.outcomes, p))
+    }
+}
+
+impl Workbook {
+    /// Recalculate `target` over `trials` random draws; the workbook is left as is
+    pub fn simulate(
+        &self,
+        target: CellKey,
+        trials: usize,
+        seed: u64,
+    ) -> Result<Simulation, String> {
+        let (sheet, row, col) = target;
+        self.check_cell(sheet, row, col)?;
+        if trials == 0 {
+            return Err("Trial count must be at least 1".to_string());
+        }
+
+        let mut scratch = self.clone();
+        scratch.listener = None;
+        scratch.set_calc_mode(CalcMode::Automatic);
+        let now = self.volatile_frozen().map_or_else(volatile::now, |f| f.now);
+
+        let mut outcomes = Vec::with_capacity(trials);
+        let mut errors = 0;
+        for trial in 0..trials {
+            scratch.set_volatile_frozen(Some(Frozen {
+                now,
+                seed: seed.wrapping_add(trial as u64),
+            }));
+            match scratch.number_at(target) {
+                Ok(value) => outcomes.push(value),
+                Err(_) => errors += 1,
+            }
+        }
+        outcomes.sort_by(f64::total_cmp);
+        Ok(Simulation { outcomes, errors })
+    }
+}
+
+/// Run a Monte Carlo simulation of a formula cell driven by random functions
+///
+/// # Arguments
+/// * `sheet`, `row`, `col` - Target cell
+/// * `trials` - Number of recalculations
+/// * `seed` - Base seed; the same seed gives the same outcomes
+///
+/// # Returns
+/// StringResult with JSON `{trials, errors, mean, stdev, min, max,
+/// percentiles: {"5": …, "10": …, "25": …, "50": …, "75": …, "90": …, "95": …}}`;
+/// statistics are null when no trial produced a number
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_simulate(
+    workbook: *const Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+    trials: usize,
+    seed: u64,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    workbook
+        .simulate((sheet, row, col), trials, seed)
+        .map(|simulation| {
+            let percentiles: serde_json::Map<String, serde_json::Value> = BANDS
+                .iter()
+                .map(|&p| (p.to_string(), simulation.percentile(p).into()))
+                .collect();
+            serde_json::json!({
+                "trials": trials,
+                "errors": simulation.errors,
+                "mean": simulation.mean(),
+                "stdev": simulation.stdev(),
+                "min": simulation.outcomes.first(),
+                "max": simulation.outcomes.last(),
+                "percentiles": percentiles,
+            })
+            .to_string()
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    #[test]
+    fn test_simulation_is_reproducible() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["Draw".into(), "Scaled".into(), "Die".into()],
+            vec![vec![
+                "=RAND()".into(),
+                "=A1*100".into(),
+                "=IF(RANDBETWEEN(1, 6) = 6, #N/A, 1)".into(),
+            ]],
+        );
+        workbook.add_sheet("Model", table).unwrap();
+
+        let run = workbook.simulate((0, 0, 1), 2000, 7).unwrap();
+        assert_eq!(run.outcomes.len(), 2000);
+        assert!((run.mean().unwrap() - 50.0).abs() < 3.0);
+        assert!((run.percentile(50.0).unwrap() - 50.0).abs() < 5.0);
+        assert!(run.outcomes[0] >= 0.0 && run.outcomes[1999] < 100.0);
+        assert_eq!(run, workbook.simulate((0, 0, 1), 2000, 7).unwrap());
+        assert_eq!(workbook.volatile_frozen(), None);
+
+        let die = workbook.simulate((0, 0, 2), 600, 1).unwrap();
+        assert!(die.errors > 50 && die.errors < 150);
+        assert_eq!(die.outcomes.len() + die.errors, 600);
+        assert!(workbook.simulate((0, 3, 0), 10, 1).is_err());
+    }
+}
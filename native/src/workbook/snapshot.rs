@@ -0,0 +1,207 @@
+//! Frozen copies of a workbook and cell-level comparison between them
+//!
+//! The host takes a snapshot when a file is opened and diffs it against a
+//! fresh one to build a "changes since open" view. Sheets are matched by name
+//! (case-insensitive) and cells by position; blank cells count as absent, so
+//! clearing a cell shows up as a removal.
+
+use serde_json::json;
+
+use super::{workbook_arg, Workbook};
+use crate::table::TesseraTable;
+use crate::StringResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    sheets: Vec<(String, TesseraTable)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellDiff {
+    pub sheet: String,
+    pub row: usize,
+    pub col: usize,
+    /// Empty for added cells
+    pub old: String,
+    /// Empty for removed cells
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    pub added_sheets: Vec<String>,
+    pub removed_sheets: Vec<String>,
+    pub added: Vec<CellDiff>,
+    pub removed: Vec<CellDiff>,
+    pub changed: Vec<CellDiff>,
+}
+
+impl Workbook {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            sheets: self
+                .sheets
+                .iter()
+                .map(|sheet| (sheet.name.clone(), (*sheet.table).clone()))
+                .collect(),
+        }
+    }
+}
+
+impl Snapshot {
+    fn sheet(&self, name: &str) -> Option<&TesseraTable> {
+        self.sheets
+            .iter()
+            .find(|(n, _)| n.to_lowercase() == name.to_lowercase())
+            .map(|(_, table)| table)
+    }
+}
+
+fn diff_sheet(name: &str, old: &TesseraTable, new: &TesseraTable, out: &mut SnapshotDiff) {
+    let rows = old.row_count().max(new.row_count());
+    let cols = old.column_count().max(new.column_count());
+    for row in 0..rows {
+        for col in 0..cols {
+            let (before, after) = (old.cell(row, col), new.cell(row, col));
+            if before == after {
+                continue;
+            }
+            let cell = CellDiff {
+                sheet: name.to_string(),
+                row,
+                col,
+                old: before.to_string(),
+                new: after.to_string(),
+            };
+            match (before.is_empty(), after.is_empty()) {
+                (true, _) => out.added.push(cell),
+                (_, true) => out.removed.push(cell),
+                _ => out.changed.push(cell),
+            }
+        }
+    }
+}
+
+/// Cells added, removed and changed going from `old` to `new`, in sheet,
+/// row, column order
+pub fn diff(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+    let mut out = SnapshotDiff::default();
+    let empty = TesseraTable::default();
+
+    for (name, table) in &new.sheets {
+        let before = old.sheet(name).unwrap_or_else(|| {
+            out.added_sheets.push(name.clone());
+            &empty
+        });
+        diff_sheet(name, before, table, &mut out);
+    }
+    for (name, table) in &old.sheets {
+        if new.sheet(name).is_none() {
+            out.removed_sheets.push(name.clone());
+            diff_sheet(name, table, &empty, &mut out);
+        }
+    }
+    out
+}
+
+impl SnapshotDiff {
+    fn to_json(&self) -> serde_json::Value {
+        let cells = |cells: &[CellDiff]| -> Vec<serde_json::Value> {
+            cells
+                .iter()
+                .map(|c| {
+                    json!({ "sheet": c.sheet, "row": c.row, "col": c.col, "old": c.old, "new": c.new })
+                })
+                .collect()
+        };
+        json!({
+            "added_sheets": self.added_sheets,
+            "removed_sheets": self.removed_sheets,
+            "added": cells(&self.added),
+            "removed": cells(&self.removed),
+            "changed": cells(&self.changed),
+        })
+    }
+}
+
+/// Take a frozen copy of every sheet (free with tessera_snapshot_free)
+///
+/// # Safety
+/// `workbook` must be null or a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_snapshot(workbook: *const Workbook) -> *mut Snapshot {
+    match workbook_arg(workbook) {
+        Some(workbook) => Box::into_raw(Box::new(workbook.snapshot())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `snapshot` must be null or a handle returned by tessera_snapshot that is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tessera_snapshot_free(snapshot: *mut Snapshot) {
+    if !snapshot.is_null() {
+        drop(Box::from_raw(snapshot));
+    }
+}
+
+/// Compare two snapshots
+///
+/// # Returns
+/// StringResult with JSON `{added_sheets, removed_sheets, added, removed, changed}`;
+/// each cell entry is `{sheet, row, col, old, new}`
+///
+/// # Safety
+/// `snap_a` and `snap_b` must be live snapshot handles
+#[no_mangle]
+pub unsafe extern "C" fn tessera_diff(
+    snap_a: *const Snapshot,
+    snap_b: *const Snapshot,
+) -> StringResult {
+    let (Some(a), Some(b)) = (snap_a.as_ref(), snap_b.as_ref()) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    StringResult::success(&diff(a, b).to_json().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["A".into(), "B".into()],
+            vec![vec!["1".into(), "x".into()], vec!["2".into(), "".into()]],
+        );
+        workbook.add_sheet("Data", table).unwrap();
+        workbook.add_sheet("Old", TesseraTable::default()).unwrap();
+        let before = workbook.snapshot();
+
+        workbook.set_cell(0, 0, 0, "10".into()).unwrap();
+        workbook.set_cell(0, 0, 1, "".into()).unwrap();
+        workbook.set_cell(0, 1, 1, "y".into()).unwrap();
+        workbook.remove_sheet(1).unwrap();
+        let new_sheet = TesseraTable::from_rows(vec!["C".into()], vec![vec!["z".into()]]);
+        workbook.add_sheet("New", new_sheet).unwrap();
+
+        let diff = diff(&before, &workbook.snapshot());
+        let at = |cells: &[CellDiff]| -> Vec<(String, usize, usize)> {
+            cells
+                .iter()
+                .map(|c| (c.sheet.clone(), c.row, c.col))
+                .collect()
+        };
+        assert_eq!(diff.added_sheets, ["New"]);
+        assert_eq!(diff.removed_sheets, ["Old"]);
+        assert_eq!(
+            at(&diff.added),
+            [("Data".into(), 1, 1), ("New".into(), 0, 0)]
+        );
+        assert_eq!(at(&diff.removed), [("Data".into(), 0, 1)]);
+        assert_eq!(diff.changed[0].old, "1");
+        assert_eq!(diff.changed[0].new, "10");
+    }
+}
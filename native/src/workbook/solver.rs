@@ -0,0 +1,547 @@
+//! Linear-programming solver over workbook cells
+//!
+//! A problem names an objective cell to minimize or maximize, the variable
+//! cells the solver may change and constraints on other cells (`<=`, `>=`
+//! or `=` a bound). Variables are non-negative. The model is read off the
+//! sheet: every formula is recalculated with the variables at zero, at each
+//! unit vector and at one extra point on a scratch copy of the workbook, and
+//! the problem is rejected when those results are not linear in the
+//! variables. The linear program is then solved with a dense two-phase
+//! simplex (Bland's rule, so it cannot cycle), which suits the small models
+//! people build by hand.
+
+use super::calc::{CalcMode, CellKey};
+use super::trace::cell_ref;
+use super::{workbook_arg_mut, Workbook};
+use crate::formula::rewrite::is_formula;
+use crate::formula::value::format_number;
+use crate::formula::Value;
+use crate::StringResult;
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    LessEqual,
+    GreaterEqual,
+    Equal,
+}
+
+impl Relation {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(Relation::LessEqual),
+            1 => Some(Relation::GreaterEqual),
+            2 => Some(Relation::Equal),
+            _ => None,
+        }
+    }
+}
+
+/// `cell` compared with `bound`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraint {
+    pub cell: CellKey,
+    pub relation: Relation,
+    pub bound: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub objective: CellKey,
+    pub maximize: bool,
+    pub variables: Vec<CellKey>,
+    pub constraints: Vec<Constraint>,
+}
+
+/// Optimal values of the variables, in problem order, and of the objective
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub values: Vec<f64>,
+    pub objective: f64,
+}
+
+/// FFI form of a cell position
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SolverCell {
+    pub sheet: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// FFI form of [`Constraint`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SolverConstraint {
+    pub cell: SolverCell,
+    pub relation: u32, // 0 = <=, 1 = >=, 2 = =
+    pub bound: f64,
+}
+
+impl From<SolverCell> for CellKey {
+    fn from(cell: SolverCell) -> Self {
+        (cell.sheet, cell.row, cell.col)
+    }
+}
+
+impl TryFrom<SolverConstraint> for Constraint {
+    type Error = String;
+
+    fn try_from(spec: SolverConstraint) -> Result<Self, String> {
+        Ok(Constraint {
+            cell: spec.cell.into(),
+            relation: Relation::from_raw(spec.relation).ok_or("Unknown constraint relation")?,
+            bound: spec.bound,
+        })
+    }
+}
+
+/// Dense simplex tableau: `rows[i]` holds the coefficients and, last, the
+/// right-hand side of the constraint whose basic variable is `basis[i]`
+struct Tableau {
+    rows: Vec<Vec<f64>>,
+    basis: Vec<usize>,
+    /// Reduced costs of the objective being maximized
+    costs: Vec<f64>,
+    columns: usize,
+}
+
+impl Tableau {
+    fn rhs(&self, row: usize) -> f64 {
+        self.rows[row][self.columns]
+    }
+
+    fn pivot(&mut self, row: usize, col: usize) {
+        let factor = self.rows[row][col];
+        self.rows[row].iter_mut().for_each(|x| *x /= factor);
+        let pivot_row = self.rows[row].clone();
+        for (i, other) in self.rows.iter_mut().enumerate() {
+            let scale = other[col];
+            if i != row && scale != 0.0 {
+                other
+                    .iter_mut()
+                    .zip(&pivot_row)
+                    .for_each(|(x, p)| *x -= scale * p);
+            }
+        }
+        let scale = self.costs[col];
+        self.costs
+            .iter_mut()
+            .zip(&pivot_row)
+            .for_each(|(x, p)| *x -= scale * p);
+        self.basis[row] = col;
+    }
+
+    /// Reduced costs for maximizing `objective`, given per column
+    fn set_objective(&mut self, objective: &[f64]) {
+        self.costs = objective.to_vec();
+        self.costs.push(0.0);
+        for (row, &basic) in self.rows.iter().zip(&self.basis) {
+            let scale = objective[basic];
+            if scale != 0.0 {
+                self.costs
+                    .iter_mut()
+                    .zip(row)
+                    .for_each(|(x, r)| *x -= scale * r);
+            }
+        }
+    }
+
+    /// Pivot until no column below `limit` improves the objective
+    fn optimize(&mut self, limit: usize) -> Result<(), String> {
+        loop {
+            let Some(enter) = (0..limit).find(|&j| self.costs[j] > EPSILON) else {
+                return Ok(());
+            };
+            let mut leave: Option<(usize, f64)> = None;
+            for i in 0..self.rows.len() {
+                let coefficient = self.rows[i][enter];
+                if coefficient <= EPSILON {
+                    continue;
+                }
+                let ratio = self.rhs(i) / coefficient;
+                let better = match leave {
+                    None => true,
+                    Some((best, best_ratio)) => {
+                        ratio < best_ratio - EPSILON
+                            || (ratio <= best_ratio + EPSILON && self.basis[i] < self.basis[best])
+                    }
+                };
+                if better {
+                    leave = Some((i, ratio));
+                }
+            }
+            let (row, _) = leave.ok_or("Problem is unbounded")?;
+            self.pivot(row, enter);
+        }
+    }
+}
+
+/// Maximize `objective`·x subject to `rows` and x >= 0
+fn simplex(objective: &[f64], rows: &[(Vec<f64>, Relation, f64)]) -> Result<Vec<f64>, String> {
+    let n = objective.len();
+    // Flip rows so every right-hand side is non-negative
+    let rows: Vec<(Vec<f64>, Relation, f64)> = rows
+        .iter()
+        .map(|(a, relation, b)| {
+            if *b >= 0.0 {
+                (a.clone(), *relation, *b)
+            } else {
+                let flipped = match relation {
+                    Relation::LessEqual => Relation::GreaterEqual,
+                    Relation::GreaterEqual => Relation::LessEqual,
+                    Relation::Equal => Relation::Equal,
+                };
+                (a.iter().map(|x| -x).collect(), flipped, -b)
+            }
+        })
+        .collect();
+
+    // Columns: variables, one slack or surplus per inequality, then artificials
+    let slacks = rows
+        .iter()
+        .filter(|(_, r, _)| *r != Relation::Equal)
+        .count();
+    let artificials = rows
+        .iter()
+        .filter(|(_, r, _)| *r != Relation::LessEqual)
+        .count();
+    let first_artificial = n + slacks;
+    let columns = first_artificial + artificials;
+
+    let mut tableau = Tableau {
+        rows: Vec::with_capacity(rows.len()),
+        basis: Vec::with_capacity(rows.len()),
+        costs: Vec::new(),
+        columns,
+    };
+    let (mut slack, mut artificial) = (n, first_artificial);
+    for (a, relation, b) in &rows {
+        let mut row = vec![0.0; columns + 1];
+        row[..n].copy_from_slice(a);
+        row[columns] = *b;
+        match relation {
+            Relation::LessEqual => {
+                row[slack] = 1.0;
+                tableau.basis.push(slack);
+                slack += 1;
+            }
+            Relation::GreaterEqual => {
+                row[slack] = -1.0;
+                slack += 1;
+                row[artificial] = 1.0;
+                tableau.basis.push(artificial);
+                artificial += 1;
+            }
+            Relation::Equal => {
+                row[artificial] = 1.0;
+                tableau.basis.push(artificial);
+                artificial += 1;
+            }
+        }
+        tableau.rows.push(row);
+    }
+
+    if artificials > 0 {
+        // Phase 1: drive the artificials to zero
+        let mut phase1 = vec![0.0; columns];
+        phase1[first_artificial..]
+            .iter_mut()
+            .for_each(|x| *x = -1.0);
+        tableau.set_objective(&phase1);
+        tableau.optimize(columns)?;
+        let infeasibility: f64 = (0..tableau.rows.len())
+            .filter(|&i| tableau.basis[i] >= first_artificial)
+            .map(|i| tableau.rhs(i))
+            .sum();
+        if infeasibility > 1e-7 {
+            return Err("Problem is infeasible".to_string());
+        }
+        // Artificials left in the basis sit at zero; swap them out where possible
+        for i in 0..tableau.rows.len() {
+            if tableau.basis[i] >= first_artificial {
+                if let Some(j) = (0..first_artificial).find(|&j| tableau.rows[i][j].abs() > EPSILON)
+                {
+                    tableau.pivot(i, j);
+                }
+            }
+        }
+    }
+
+    let mut phase2 = vec![0.0; columns];
+    phase2[..n].copy_from_slice(objective);
+    tableau.set_objective(&phase2);
+    tableau.optimize(first_artificial)?;
+
+    let mut x = vec![0.0; n];
+    for (i, &basic) in tableau.basis.iter().enumerate() {
+        if basic < n {
+            x[basic] = tableau.rhs(i);
+        }
+    }
+    Ok(x)
+}
+
+impl Workbook {
+    /// Number shown by a cell: blanks count as zero, anything else that is
+    /// not a number is an error
+    pub(super) fn number_at(&self, key: CellKey) -> Result<f64, String> {
+        let (sheet, row, col) = key;
+        let table = self
+            .sheet_at(sheet)
+            .ok_or(format!("Sheet {} is out of range", sheet))?;
+        let value = match table.computed_value(row, col) {
+            Some(value) => value,
+            None if is_formula(table.cell(row, col)) => self
+                .calculated_values()
+                .get(&key)
+                .cloned()
+                .unwrap_or(Value::Blank),
+            None => Value::from_cell(table.cell(row, col)),
+        };
+        match value {
+            Value::Number(n) => Ok(n),
+            Value::Blank => Ok(0.0),
+            _ => Err(format!(
+                "{} does not evaluate to a number",
+                cell_ref(self, key)
+            )),
+        }
+    }
+
+    /// Find the variable values optimizing `problem`; the workbook is left as is
+    pub fn solve(&self, problem: &Problem) -> Result<Solution, String> {
+        if problem.variables.is_empty() {
+            return Err("No variable cells given".to_string());
+        }
+        let outputs: Vec<CellKey> = std::iter::once(problem.objective)
+            .chain(problem.constraints.iter().map(|c| c.cell))
+            .collect();
+        for &key in problem.variables.iter().chain(&outputs) {
+            self.check_cell(key.0, key.1, key.2)?;
+        }
+        for &key in &problem.variables {
+            let (sheet, row, col) = key;
+            if is_formula(self.sheets[sheet].table.cell(row, col)) {
+                return Err(format!(
+                    "Variable cell {} holds a formula",
+                    cell_ref(self, key)
+                ));
+            }
+        }
+
+        let mut scratch = self.clone();
+        scratch.listener = None;
+        scratch.set_calc_mode(CalcMode::Automatic);
+        let mut sample = |point: &[f64]| -> Result<Vec<f64>, String> {
+            for (&(sheet, row, col), &x) in problem.variables.iter().zip(point) {
+                scratch.set_cell(sheet, row, col, format_number(x))?;
+            }
+            outputs.iter().map(|&key| scratch.number_at(key)).collect()
+        };
+
+        // Output k at point x is base[k] + sum of gradient[k][j] * x[j]
+        let n = problem.variables.len();
+        let base = sample(&vec![0.0; n])?;
+        let mut gradient = vec![vec![0.0; n]; outputs.len()];
+        for j in 0..n {
+            let mut point = vec![0.0; n];
+            point[j] = 1.0;
+            for (k, value) in sample(&point)?.into_iter().enumerate() {
+                gradient[k][j] = value - base[k];
+            }
+        }
+        let check: Vec<f64> = (0..n).map(|j| (j + 2) as f64).collect();
+        for (k, value) in sample(&check)?.into_iter().enumerate() {
+            let predicted = base[k]
+                + gradient[k]
+                    .iter()
+                    .zip(&check)
+                    .map(|(g, x)| g * x)
+                    .sum::<f64>();
+            if (value - predicted).abs() > 1e-6 * (1.0 + predicted.abs()) {
+                return Err(format!(
+                    "{} is not linear in the variable cells",
+                    cell_ref(self, outputs[k])
+                ));
+            }
+        }
+
+        let sign = if problem.maximize { 1.0 } else { -1.0 };
+        let objective: Vec<f64> = gradient[0].iter().map(|g| sign * g).collect();
+        let rows: Vec<(Vec<f64>, Relation, f64)> = problem
+            .constraints
+            .iter()
+            .enumerate()
+            .map(|(k, c)| (gradient[k + 1].clone(), c.relation, c.bound - base[k + 1]))
+            .collect();
+        let values = simplex(&objective, &rows)?;
+        let objective = base[0]
+            + gradient[0]
+                .iter()
+                .zip(&values)
+                .map(|(g, x)| g * x)
+                .sum::<f64>();
+        Ok(Solution { values, objective })
+    }
+
+    /// Write a solution into the variable cells as one undoable step
+    pub fn apply_solution(&mut self, problem: &Problem, solution: &Solution) -> Result<(), String> {
+        self.begin_undo_group("Solve");
+        for (&(sheet, row, col), &x) in problem.variables.iter().zip(&solution.values) {
+            if let Err(msg) = self.set_cell(sheet, row, col, format_number(x)) {
+                self.rollback_undo_group()?;
+                return Err(msg);
+            }
+        }
+        self.end_undo_group()
+    }
+}
+
+/// Optimize an objective cell by changing variable cells, subject to constraints
+///
+/// # Arguments
+/// * `objective` - Cell to optimize
+/// * `maximize` - True to maximize, false to minimize
+/// * `variables_ptr` - Cells the solver may change (kept non-negative)
+/// * `variable_count` - Number of variable cells
+/// * `constraints_ptr` - Constraints on other cells
+/// * `constraint_count` - Number of constraints
+/// * `apply` - True to write the solution into the variable cells (one undo step)
+///
+/// # Returns
+/// StringResult with JSON `{objective, values}`, or an error when the model
+/// is not linear, infeasible or unbounded
+///
+/// # Safety
+/// `workbook` must be a live workbook handle; the pointers must reference
+/// arrays of the given lengths
+#[no_mangle]
+pub unsafe extern "C" fn tessera_solve(
+    workbook: *mut Workbook,
+    objective: SolverCell,
+    maximize: bool,
+    variables_ptr: *const SolverCell,
+    variable_count: usize,
+    constraints_ptr: *const SolverConstraint,
+    constraint_count: usize,
+    apply: bool,
+) -> StringResult {
+    let Some(workbook) = workbook_arg_mut(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if (variables_ptr.is_null() && variable_count > 0)
+        || (constraints_ptr.is_null() && constraint_count > 0)
+    {
+        return StringResult::error("Null pointer provided");
+    }
+
+    let variables = if variable_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(variables_ptr, variable_count)
+    };
+    let constraints = if constraint_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(constraints_ptr, constraint_count)
+    };
+
+    let problem = match constraints
+        .iter()
+        .map(|&spec| Constraint::try_from(spec))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(constraints) => Problem {
+            objective: objective.into(),
+            maximize,
+            variables: variables.iter().map(|&cell| cell.into()).collect(),
+            constraints,
+        },
+        Err(msg) => return StringResult::error(&msg),
+    };
+
+    workbook
+        .solve(&problem)
+        .and_then(|solution| {
+            if apply {
+                workbook.apply_solution(&problem, &solution)?;
+            }
+            Ok(serde_json::json!({
+                "objective": solution.objective,
+                "values": solution.values,
+            })
+            .to_string())
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    #[test]
+    fn test_solve_product_mix() {
+        // Maximize 3x + 5y with x <= 4, 2y <= 12, 3x + 2y <= 18
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["X".into(), "Y".into(), "Profit".into(), "Plant".into()],
+            vec![
+                vec![
+                    "".into(),
+                    "".into(),
+                    "=3*A1+5*B1".into(),
+                    "=3*A1+2*B1".into(),
+                ],
+                vec!["=A1".into(), "=2*B1".into(), "=A1*B1".into()],
+            ],
+        );
+        workbook.add_sheet("Model", table).unwrap();
+
+        let constraint = |row, col, relation, bound| Constraint {
+            cell: (0, row, col),
+            relation,
+            bound,
+        };
+        let mut problem = Problem {
+            objective: (0, 0, 2),
+            maximize: true,
+            variables: vec![(0, 0, 0), (0, 0, 1)],
+            constraints: vec![
+                constraint(1, 0, Relation::LessEqual, 4.0),
+                constraint(1, 1, Relation::LessEqual, 12.0),
+                constraint(0, 3, Relation::LessEqual, 18.0),
+            ],
+        };
+        let solution = workbook.solve(&problem).unwrap();
+        assert!((solution.objective - 36.0).abs() < 1e-9);
+        assert!((solution.values[0] - 2.0).abs() < 1e-9);
+        assert!((solution.values[1] - 6.0).abs() < 1e-9);
+
+        workbook.apply_solution(&problem, &solution).unwrap();
+        assert_eq!(workbook.display_value(0, 0, 2).unwrap(), "36");
+        workbook.undo().unwrap();
+        assert_eq!(workbook.sheet_at(0).unwrap().cell(0, 0), "");
+
+        // Minimizing with a lower bound needs phase 1
+        problem.maximize = false;
+        problem
+            .constraints
+            .push(constraint(0, 3, Relation::GreaterEqual, 6.0));
+        let solution = workbook.solve(&problem).unwrap();
+        assert!((solution.objective - 6.0).abs() < 1e-9);
+
+        problem
+            .constraints
+            .push(constraint(1, 0, Relation::GreaterEqual, 5.0));
+        assert_eq!(
+            workbook.solve(&problem).unwrap_err(),
+            "Problem is infeasible"
+        );
+
+        problem.constraints = vec![constraint(1, 2, Relation::LessEqual, 1.0)];
+        assert!(workbook.solve(&problem).unwrap_err().contains("not linear"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Spilling array results into neighbouring cells
+//!
+//! A formula whose result is an array spills it down and to the right of its
+//! own cell. The spilled cells stay empty in the table but read as the array
+//! values, in formulas and on screen. When any of them holds text, falls
+//! outside the sheet or is already taken by an earlier spill (in sheet, row,
+//! column order), the formula shows `#SPILL!` instead until the way is clear.
+//! `A1#` refers to the whole spilled array.
+
+use std::collections::{HashMap, HashSet};
+
+use super::calc::{CalcValues, CellKey};
+use super::history::Change;
+use super::{workbook_arg, Workbook};
+use crate::formula::{ErrorValue, Value};
+use crate::StringResult;
+
+/// Block an array formula spills into, anchored at the formula cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpillArea {
+    pub rows: usize,
+    pub cols: usize,
+    /// The block is not free, so the formula shows `#SPILL!`
+    pub blocked: bool,
+}
+
+impl SpillArea {
+    /// Cells the block covers besides the anchor
+    pub(super) fn cells(self, (sheet, row, col): CellKey) -> impl Iterator<Item = CellKey> {
+        (row..row + self.rows)
+            .flat_map(move |r| (col..col + self.cols).map(move |c| (sheet, r, c)))
+            .filter(move |&key| key != (sheet, row, col))
+    }
+}
+
+impl Workbook {
+    /// Spill block of the formula at `anchor`, `None` unless it returned an
+    /// array of more than one value
+    pub fn spill_area(&self, anchor: CellKey) -> Option<SpillArea> {
+        self.calc.spills.get(&anchor).copied()
+    }
+
+    /// Anchors and blocks of every spilling formula on a sheet, in row order
+    pub fn spill_areas(&self, sheet: usize) -> Vec<(CellKey, SpillArea)> {
+        let mut areas: Vec<(CellKey, SpillArea)> = self
+            .calc
+            .spills
+            .iter()
+            .filter(|(key, _)| key.0 == sheet)
+            .map(|(&key, &area)| (key, area))
+            .collect();
+        areas.sort_by_key(|&(key, _)| key);
+        areas
+    }
+
+    /// Anchor of the placed spill covering `key`, other than `key` itself
+    fn spill_anchor(&self, (sheet, row, col): CellKey) -> Option<CellKey> {
+        self.calc.spills.iter().find_map(|(&anchor, area)| {
+            let (s, r, c) = anchor;
+            let inside = s == sheet
+                && (r..r + area.rows).contains(&row)
+                && (c..c + area.cols).contains(&col)
+                && anchor != (sheet, row, col);
+            (inside && !area.blocked).then_some(anchor)
+        })
+    }
+
+    /// Element of a spilled array shown in the empty cell `key`, read from
+    /// the anchor's result in `values`
+    pub(crate) fn spilled_value(&self, key: CellKey, values: &CalcValues) -> Option<Value> {
+        let anchor = self.spill_anchor(key)?;
+        match values.get(&anchor)? {
+            Value::Array(array) => array.get(key.1 - anchor.1, key.2 - anchor.2).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Result of the formula at `key` as its cell shows it: `#SPILL!` when
+    /// its spill is blocked, else the top-left element of an array
+    pub(crate) fn anchor_value(&self, key: CellKey, value: &Value) -> Value {
+        match value {
+            _ if self.spill_area(key).is_some_and(|area| area.blocked) => {
+                Value::Error(ErrorValue::Spill)
+            }
+            Value::Array(array) => array.get(0, 0).cloned().unwrap_or(Value::Blank),
+            value => value.clone(),
+        }
+    }
+
+    /// Lay out the spills of the current results, in sheet, row, column order
+    ///
+    /// Returns the cells whose spill state changed: cells entering or leaving
+    /// a spill and anchors whose block changed. Changes to the text they show
+    /// are reported to the change listener.
+    pub(super) fn place_spills(&mut self) -> HashSet<CellKey> {
+        let mut anchors: Vec<(CellKey, usize, usize)> = self
+            .calculated_values()
+            .iter()
+            .filter_map(|(&key, value)| match value {
+                Value::Array(array) if array.rows() * array.cols() > 1 => {
+                    Some((key, array.rows(), array.cols()))
+                }
+                _ => None,
+            })
+            .collect();
+        anchors.sort_by_key(|&(key, ..)| key);
+
+        let mut taken: HashSet<CellKey> = anchors.iter().map(|&(key, ..)| key).collect();
+        let mut spills = HashMap::new();
+        for (key, rows, cols) in anchors {
+            let table = &self.sheets[key.0].table;
+            let fits = key.1 + rows <= table.row_count() && key.2 + cols <= table.column_count();
+            let mut area = SpillArea {
+                rows,
+                cols,
+                blocked: !fits,
+            };
+            area.blocked = area.blocked
+                || area.cells(key).any(|cell @ (_, row, col)| {
+                    taken.contains(&cell)
+                        || !table.cell(row, col).trim().is_empty()
+                        || table.computed_value(row, col).is_some()
+                });
+            if !area.blocked {
+                taken.extend(area.cells(key));
+            }
+            spills.insert(key, area);
+        }
+
+        let covered = |spills: &HashMap<CellKey, SpillArea>| -> HashMap<CellKey, CellKey> {
+            spills
+                .iter()
+                .filter(|(_, area)| !area.blocked)
+                .flat_map(|(&anchor, &area)| area.cells(anchor).map(move |cell| (cell, anchor)))
+                .collect()
+        };
+        let (before, after) = (covered(&self.calc.spills), covered(&spills));
+        let moved: HashSet<CellKey> = before
+            .iter()
+            .filter(|&(cell, anchor)| after.get(cell) != Some(anchor))
+            .chain(
+                after
+                    .iter()
+                    .filter(|&(cell, anchor)| before.get(cell) != Some(anchor)),
+            )
+            .map(|(&cell, _)| cell)
+            .chain(
+                self.calc
+                    .spills
+                    .keys()
+                    .chain(spills.keys())
+                    .filter(|&key| self.calc.spills.get(key) != spills.get(key))
+                    .copied(),
+            )
+            .collect();
+
+        let shown = |workbook: &Workbook, &(sheet, row, col): &CellKey| {
+            workbook.display_value(sheet, row, col).unwrap_or_default()
+        };
+        let old: Vec<String> = moved.iter().map(|key| shown(self, key)).collect();
+        self.calc.spills = spills;
+        let changes: Vec<Change> = moved
+            .iter()
+            .zip(old)
+            .filter_map(|(key, old)| {
+                let new = shown(self, key);
+                (old != new).then_some(Change::Cell {
+                    sheet: key.0,
+                    row: key.1,
+                    col: key.2,
+                    old,
+                    new,
+                })
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.notify(&changes, true);
+        }
+        moved
+    }
+}
+
+/// Spill blocks of the array formulas on a sheet
+///
+/// # Returns
+/// StringResult with a JSON array of `{row, col, rows, cols, blocked}`, one
+/// per formula whose array result has more than one value; `row`/`col` is
+/// the formula cell and `blocked` formulas show `#SPILL!`
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_spill_ranges(
+    workbook: *const Workbook,
+    sheet: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    if sheet >= workbook.sheet_count() {
+        return StringResult::error(&format!("Sheet {} is out of range", sheet));
+    }
+
+    let areas: Vec<serde_json::Value> = workbook
+        .spill_areas(sheet)
+        .into_iter()
+        .map(|((_, row, col), area)| {
+            serde_json::json!({
+                "row": row,
+                "col": col,
+                "rows": area.rows,
+                "cols": area.cols,
+                "blocked": area.blocked,
+            })
+        })
+        .collect();
+    StringResult::success(&serde_json::Value::from(areas).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    #[test]
+    fn test_spill_and_block() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["N".into(), "Sorted".into(), "Total".into()],
+            vec![
+                vec!["3".into(), "=SORT(A1:A3)".into(), "=SUM(B1#)".into()],
+                vec!["1".into(), "".into(), "=B2 * 10".into()],
+                vec!["2".into(), "".into(), "".into()],
+            ],
+        );
+        workbook.add_sheet("Data", table).unwrap();
+
+        let shown = |workbook: &Workbook, row, col| workbook.display_value(0, row, col).unwrap();
+        assert_eq!(
+            workbook.spill_areas(0),
+            [(
+                (0, 0, 1),
+                SpillArea {
+                    rows: 3,
+                    cols: 1,
+                    blocked: false
+                }
+            )]
+        );
+        assert_eq!(
+            (shown(&workbook, 0, 1), shown(&workbook, 2, 1)),
+            ("1".into(), "3".into())
+        );
+        assert_eq!(shown(&workbook, 0, 2), "6");
+        assert_eq!(shown(&workbook, 1, 2), "20");
+
+        workbook.set_cell(0, 2, 1, "x".into()).unwrap();
+        assert!(workbook.spill_area((0, 0, 1)).unwrap().blocked);
+        assert_eq!(shown(&workbook, 0, 1), "#SPILL!");
+        assert_eq!(shown(&workbook, 0, 2), "#SPILL!");
+        assert_eq!(shown(&workbook, 1, 2), "0");
+
+        workbook.set_cell(0, 2, 1, "".into()).unwrap();
+        assert_eq!(shown(&workbook, 1, 2), "20");
+        assert_eq!(shown(&workbook, 0, 2), "6");
+    }
+}
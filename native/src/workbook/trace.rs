@@ -0,0 +1,463 @@
+//! Tracing the precedents and dependents of a cell
+//!
+//! Backs audit arrows and "why did this value change?" navigation. Both
+//! traces walk the calculation graph breadth first and tag every step with
+//! its depth: depth 1 is what the cell reads directly (or the formulas
+//! reading it directly), depth 2 what those read, and so on. Each formula is
+//! expanded once, so reference cycles end the walk.
+//!
+//! The whole graph can also be exported, as JSON or Graphviz DOT, to
+//! visualize and debug complex sheets.
+
+use std::collections::VecDeque;
+
+use super::calc::{Area, CellKey, Graph};
+use super::{workbook_arg, Workbook};
+use crate::formula::parser::{column_letters, quote_sheet};
+use crate::StringResult;
+
+/// A block of cells read by the formula at `from`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecedentStep {
+    pub depth: usize,
+    pub from: CellKey,
+    pub area: Area,
+}
+
+/// A formula at `cell` reading `via`, the previous cell of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DependentStep {
+    pub depth: usize,
+    pub cell: CellKey,
+    pub via: CellKey,
+}
+
+/// Sheet-qualified A1 text for an area, like `Sheet1!B2`, `'Q1 Sales'!A1:C4`
+/// or `Data!B:B` for whole columns
+pub(crate) fn area_ref(workbook: &Workbook, area: &Area) -> String {
+    let sheet = quote_sheet(&workbook.sheets[area.sheet].name);
+    let (first_col, last_col) = (*area.cols.start(), *area.cols.end());
+    let (first_row, last_row) = (*area.rows.start(), *area.rows.end());
+
+    let block = if first_row == 0 && last_row == usize::MAX {
+        format!("{}:{}", column_letters(first_col), column_letters(last_col))
+    } else if first_row == last_row && first_col == last_col {
+        format!("{}{}", column_letters(first_col), first_row + 1)
+    } else {
+        format!(
+            "{}{}:{}{}",
+            column_letters(first_col),
+            first_row + 1,
+            column_letters(last_col),
+            last_row + 1
+        )
+    };
+    format!("{}!{}", sheet, block)
+}
+
+/// `Sheet1!B2` for a single cell
+pub(crate) fn cell_ref(workbook: &Workbook, (sheet, row, col): CellKey) -> String {
+    area_ref(
+        workbook,
+        &Area {
+            sheet,
+            rows: row..=row,
+            cols: col..=col,
+        },
+    )
+}
+
+impl Workbook {
+    pub(super) fn check_cell(&self, sheet: usize, row: usize, col: usize) -> Result<(), String> {
+        self.check_index(sheet)?;
+        let table = &self.sheets[sheet].table;
+        if row >= table.row_count() || col >= table.column_count() {
+            return Err(format!("Cell ({}, {}) is out of range", row, col));
+        }
+        Ok(())
+    }
+
+    /// Everything the cell reads, directly and through other formulas
+    ///
+    /// Empty for cells that are not formulas.
+    pub fn trace_precedents(
+        &self,
+        sheet: usize,
+        row: usize,
+        col: usize,
+    ) -> Result<Vec<PrecedentStep>, String> {
+        self.check_cell(sheet, row, col)?;
+        let graph = Graph::build(self);
+        // reads[i]: formulas inside the areas formula `i` reads
+        let mut reads = vec![Vec::new(); graph.formulas.len()];
+        for (j, dependents) in graph.dependents.iter().enumerate() {
+            for &i in dependents {
+                reads[i].push(j);
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut seen = vec![false; graph.formulas.len()];
+        let mut queue: VecDeque<(usize, usize)> = graph
+            .positions
+            .get(&(sheet, row, col))
+            .map(|&i| (i, 1))
+            .into_iter()
+            .collect();
+        while let Some((i, depth)) = queue.pop_front() {
+            if std::mem::replace(&mut seen[i], true) {
+                continue;
+            }
+            let formula = &graph.formulas[i];
+            for area in &formula.reads {
+                steps.push(PrecedentStep {
+                    depth,
+                    from: formula.key,
+                    area: area.clone(),
+                });
+            }
+            queue.extend(reads[i].iter().map(|&j| (j, depth + 1)));
+        }
+        Ok(steps)
+    }
+
+    /// Every formula reading the cell, directly and through other formulas
+    pub fn trace_dependents(
+        &self,
+        sheet: usize,
+        row: usize,
+        col: usize,
+    ) -> Result<Vec<DependentStep>, String> {
+        self.check_cell(sheet, row, col)?;
+        let key = (sheet, row, col);
+        let graph = Graph::build(self);
+
+        let mut queue: VecDeque<(usize, usize, CellKey)> = graph
+            .formulas
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.reads.iter().any(|area| area.contains(key)))
+            .map(|(i, _)| (i, 1, key))
+            .collect();
+        let mut steps = Vec::new();
+        let mut seen = vec![false; graph.formulas.len()];
+        while let Some((i, depth, via)) = queue.pop_front() {
+            if std::mem::replace(&mut seen[i], true) {
+                continue;
+            }
+            let cell = graph.formulas[i].key;
+            steps.push(DependentStep { depth, cell, via });
+            queue.extend(graph.dependents[i].iter().map(|&j| (j, depth + 1, cell)));
+        }
+        Ok(steps)
+    }
+}
+
+/// Export format of [`Workbook::dependency_graph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+}
+
+impl GraphFormat {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(GraphFormat::Json),
+            1 => Some(GraphFormat::Dot),
+            _ => None,
+        }
+    }
+}
+
+fn dot_quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+impl Workbook {
+    /// Every formula cell with the references it reads and the formulas it feeds
+    ///
+    /// JSON is `{nodes: [{id, cell: [sheet, row, col], formula, cyclic}],
+    /// edges: [{from, to, kind}]}` where `kind` is `reads` for an edge from a
+    /// referenced area to the formula reading it and `feeds` for an edge from
+    /// a formula to a formula reading its cell. DOT draws formulas as
+    /// ellipses (red on a cycle) and referenced areas as boxes.
+    pub fn dependency_graph(&self, format: GraphFormat) -> String {
+        let graph = Graph::build(self);
+        let ids: Vec<String> = graph
+            .formulas
+            .iter()
+            .map(|f| cell_ref(self, f.key))
+            .collect();
+        let mut cyclic = vec![false; graph.formulas.len()];
+        for &i in &graph.cyclic {
+            cyclic[i] = true;
+        }
+
+        let mut edges: Vec<(String, &str, &str)> = Vec::new();
+        for (i, formula) in graph.formulas.iter().enumerate() {
+            for area in &formula.reads {
+                edges.push((area_ref(self, area), &ids[i], "reads"));
+            }
+        }
+        for (j, dependents) in graph.dependents.iter().enumerate() {
+            for &i in dependents {
+                edges.push((ids[j].clone(), &ids[i], "feeds"));
+            }
+        }
+
+        match format {
+            GraphFormat::Json => {
+                let nodes: Vec<_> = graph
+                    .formulas
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let (s, r, c) = f.key;
+                        serde_json::json!({
+                            "id": ids[i],
+                            "cell": [s, r, c],
+                            "formula": self.sheets[s].table.cell(r, c),
+                            "cyclic": cyclic[i],
+                        })
+                    })
+                    .collect();
+                let edges: Vec<_> = edges
+                    .iter()
+                    .map(|(from, to, kind)| serde_json::json!({ "from": from, "to": to, "kind": kind }))
+                    .collect();
+                serde_json::json!({ "nodes": nodes, "edges": edges }).to_string()
+            }
+            GraphFormat::Dot => {
+                let mut dot = String::from("digraph dependencies {\n    rankdir=LR;\n");
+                for (i, f) in graph.formulas.iter().enumerate() {
+                    let (s, r, c) = f.key;
+                    let label = format!("{}\n{}", ids[i], self.sheets[s].table.cell(r, c));
+                    let color = if cyclic[i] { ", color=red" } else { "" };
+                    dot.push_str(&format!(
+                        "    {} [label={}{}];\n",
+                        dot_quote(&ids[i]),
+                        dot_quote(&label),
+                        color
+                    ));
+                }
+                let mut areas: Vec<&String> = edges
+                    .iter()
+                    .filter(|(_, _, kind)| *kind == "reads")
+                    .map(|(from, _, _)| from)
+                    .filter(|from| !ids.contains(from))
+                    .collect();
+                areas.sort();
+                areas.dedup();
+                for area in areas {
+                    dot.push_str(&format!("    {} [shape=box];\n", dot_quote(area)));
+                }
+                for (from, to, kind) in &edges {
+                    let style = if *kind == "feeds" {
+                        " [style=dashed]"
+                    } else {
+                        ""
+                    };
+                    dot.push_str(&format!(
+                        "    {} -> {}{};\n",
+                        dot_quote(from),
+                        dot_quote(to),
+                        style
+                    ));
+                }
+                dot.push_str("}\n");
+                dot
+            }
+        }
+    }
+}
+
+/// Precedents of a cell for audit arrows
+///
+/// # Returns
+/// StringResult with a JSON array of `{depth, from: [sheet, row, col],
+/// reference, sheet, first: [row, col], last: [row, col]}`, breadth first.
+/// `last` is clamped to the used area of the sheet; `reference` is the
+/// area as A1 text such as `Sheet1!A1:A3`.
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_trace_precedents(
+    workbook: *const Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    workbook
+        .trace_precedents(sheet, row, col)
+        .map(|steps| {
+            let steps: Vec<_> = steps
+                .iter()
+                .map(|step| {
+                    let table = &workbook.sheets[step.area.sheet].table;
+                    let last_row = (*step.area.rows.end())
+                        .min(table.row_count().saturating_sub(1))
+                        .max(*step.area.rows.start());
+                    let last_col = (*step.area.cols.end())
+                        .min(table.column_count().saturating_sub(1))
+                        .max(*step.area.cols.start());
+                    let (s, r, c) = step.from;
+                    serde_json::json!({
+                        "depth": step.depth,
+                        "from": [s, r, c],
+                        "reference": area_ref(workbook, &step.area),
+                        "sheet": step.area.sheet,
+                        "first": [step.area.rows.start(), step.area.cols.start()],
+                        "last": [last_row, last_col],
+                    })
+                })
+                .collect();
+            serde_json::Value::from(steps).to_string()
+        })
+        .into()
+}
+
+/// Formulas depending on a cell, for "why did this value change?" navigation
+///
+/// # Returns
+/// StringResult with a JSON array of `{depth, cell: [sheet, row, col],
+/// reference, via: [sheet, row, col]}`, breadth first; `via` is the cell the
+/// formula reads on the way from the traced cell
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_trace_dependents(
+    workbook: *const Workbook,
+    sheet: usize,
+    row: usize,
+    col: usize,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+
+    workbook
+        .trace_dependents(sheet, row, col)
+        .map(|steps| {
+            let steps: Vec<_> = steps
+                .iter()
+                .map(|step| {
+                    let (s, r, c) = step.cell;
+                    let (vs, vr, vc) = step.via;
+                    serde_json::json!({
+                        "depth": step.depth,
+                        "cell": [s, r, c],
+                        "reference": cell_ref(workbook, step.cell),
+                        "via": [vs, vr, vc],
+                    })
+                })
+                .collect();
+            serde_json::Value::from(steps).to_string()
+        })
+        .into()
+}
+
+/// Export the formula dependency graph of the whole workbook
+///
+/// # Arguments
+/// * `format` - 0 = JSON, 1 = Graphviz DOT
+///
+/// # Returns
+/// StringResult with the graph text (see [`Workbook::dependency_graph`])
+///
+/// # Safety
+/// `workbook` must be a live workbook handle
+#[no_mangle]
+pub unsafe extern "C" fn tessera_dependency_graph(
+    workbook: *const Workbook,
+    format: u32,
+) -> StringResult {
+    let Some(workbook) = workbook_arg(workbook) else {
+        return StringResult::error("Null pointer provided");
+    };
+    let Some(format) = GraphFormat::from_raw(format) else {
+        return StringResult::error(&format!("Unknown graph format {}", format));
+    };
+
+    StringResult::success(&workbook.dependency_graph(format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::TesseraTable;
+
+    #[test]
+    fn test_trace_chains() {
+        let mut workbook = Workbook::new();
+        let table = TesseraTable::from_rows(
+            vec!["Qty".into(), "Total".into()],
+            vec![
+                vec!["2".into(), "=A1*10".into()],
+                vec!["3".into(), "=B1+A2".into()],
+            ],
+        );
+        workbook.add_sheet("Sheet1", table).unwrap();
+        workbook
+            .add_sheet(
+                "Q1 Sales",
+                TesseraTable::from_rows(vec!["X".into()], vec![vec!["=Sheet1!B2*2".into()]]),
+            )
+            .unwrap();
+
+        let precedents = workbook.trace_precedents(1, 0, 0).unwrap();
+        let refs: Vec<(usize, String)> = precedents
+            .iter()
+            .map(|s| (s.depth, area_ref(&workbook, &s.area)))
+            .collect();
+        assert_eq!(
+            refs,
+            [
+                (1, "Sheet1!B2".to_string()),
+                (2, "Sheet1!B1".to_string()),
+                (2, "Sheet1!A2".to_string()),
+                (3, "Sheet1!A1".to_string()),
+            ]
+        );
+        assert!(workbook.trace_precedents(0, 0, 0).unwrap().is_empty());
+
+        let dependents = workbook.trace_dependents(0, 0, 0).unwrap();
+        let cells: Vec<(usize, String)> = dependents
+            .iter()
+            .map(|s| (s.depth, cell_ref(&workbook, s.cell)))
+            .collect();
+        assert_eq!(
+            cells,
+            [
+                (1, "Sheet1!B1".to_string()),
+                (2, "Sheet1!B2".to_string()),
+                (3, "'Q1 Sales'!A1".to_string()),
+            ]
+        );
+        assert_eq!(dependents[2].via, (0, 1, 1));
+        assert!(workbook.trace_dependents(0, 5, 0).is_err());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&workbook.dependency_graph(GraphFormat::Json)).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(json["nodes"][2]["formula"], "=Sheet1!B2*2");
+        assert!(json["edges"].as_array().unwrap().contains(
+            &serde_json::json!({ "from": "Sheet1!B2", "to": "'Q1 Sales'!A1", "kind": "feeds" })
+        ));
+
+        let dot = workbook.dependency_graph(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph dependencies {"));
+        assert!(dot.contains("    \"Sheet1!A1\" [shape=box];\n"));
+        assert!(dot.contains("    \"Sheet1!B1\" -> \"Sheet1!B2\" [style=dashed];\n"));
+    }
+}